@@ -16,7 +16,7 @@ fn main() {
             window.remove_node(&mut c);
         } else {
             c = window.add_cube(1.0, 1.0, 1.0);
-            c.set_color(1.0, 0.0, 0.0);
+            c.set_color((1.0, 0.0, 0.0));
         }
 
         added = !added;
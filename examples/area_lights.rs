@@ -0,0 +1,53 @@
+extern crate kiss3d;
+extern crate nalgebra as na;
+
+use kiss3d::light::{Light, LightSource};
+use kiss3d::window::Window;
+use na::{Point3, Translation3, UnitQuaternion, Vector3};
+
+fn main() {
+    let mut window = Window::new("Kiss3d: area lights");
+
+    let mut ground = window.add_cube(10.0, 0.1, 10.0);
+    ground.set_color((0.6, 0.6, 0.6));
+    ground.append_translation(&Translation3::new(0.0, -0.05, 0.0));
+
+    let mut product = window.add_sphere(0.5);
+    product.set_color((0.9, 0.2, 0.2));
+    product.append_translation(&Translation3::new(0.0, 0.5, 0.0));
+
+    // Two softboxes on each side of the product, and a long strip above it.
+    window.add_light(
+        LightSource::rect(
+            Point3::new(-2.0, 1.5, 1.0),
+            Vector3::new(1.0, -0.3, -0.5),
+            1.5,
+            2.0,
+        )
+        .with_intensity(3.0)
+        .with_attenuation(1.0, 0.0, 0.05),
+    );
+    window.add_light(
+        LightSource::rect(
+            Point3::new(2.0, 1.5, 1.0),
+            Vector3::new(-1.0, -0.3, -0.5),
+            1.5,
+            2.0,
+        )
+        .with_color((1.0, 0.9, 0.8))
+        .with_intensity(2.0)
+        .with_attenuation(1.0, 0.0, 0.05),
+    );
+    window.add_light(
+        LightSource::rect(Point3::new(0.0, 3.0, 0.0), -Vector3::y(), 4.0, 0.3).with_intensity(1.5),
+    );
+
+    window.set_light(Light::Absolute(Point3::new(0.0, 10.0, 10.0)));
+    window.set_light_debug(true);
+
+    let rot = UnitQuaternion::from_axis_angle(&Vector3::y_axis(), 0.01);
+
+    while window.render() {
+        product.prepend_to_local_rotation(&rot);
+    }
+}
@@ -0,0 +1,48 @@
+extern crate kiss3d;
+extern crate nalgebra as na;
+
+use kiss3d::event::{Action, Key, WindowEvent};
+use kiss3d::light::Light;
+use kiss3d::scene::ContactShadows;
+use kiss3d::window::Window;
+use na::{Point3, Translation3};
+
+fn main() {
+    let mut window = Window::new("Kiss3d: contact shadows");
+
+    let mut ground = window.add_cube(100.0, 0.1, 100.0);
+    ground.set_color((0.9, 0.9, 0.9));
+    ground.append_translation(&Translation3::new(0.0, -0.05, 0.0));
+    ground.set_shadow_caster(false);
+
+    // Small objects resting on a large ground, whose shadow map texels are too coarse to
+    // capture where they touch it.
+    for i in 0..10 {
+        let x = i as f32 * 0.3 - 1.5;
+        let mut c = if i % 2 == 0 {
+            window.add_cube(0.1, 0.1, 0.1)
+        } else {
+            window.add_sphere(0.05)
+        };
+        c.set_color((0.8, 0.3, 0.2));
+        c.append_translation(&Translation3::new(x, 0.05, 0.0));
+    }
+
+    window.set_light(Light::Absolute(Point3::new(20.0, 15.0, 10.0)));
+    window.set_shadow_mapping(true);
+    window.set_contact_shadows(Some(ContactShadows::new().with_length(0.1)));
+
+    println!("Press C to toggle the contact shadows.");
+
+    while window.render() {
+        for event in window.events().iter() {
+            if let WindowEvent::Key(Key::C, Action::Press, _) = event.value {
+                let contact_shadows = match window.contact_shadows() {
+                    Some(_) => None,
+                    None => Some(ContactShadows::new().with_length(0.1)),
+                };
+                window.set_contact_shadows(contact_shadows);
+            }
+        }
+    }
+}
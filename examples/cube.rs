@@ -10,7 +10,7 @@ fn main() {
     let mut window = Window::new("Kiss3d: cube");
     let mut c = window.add_cube(1.0, 1.0, 1.0);
 
-    c.set_color(1.0, 0.0, 0.0);
+    c.set_color((1.0, 0.0, 0.0));
 
     window.set_light(Light::StickToCamera);
 
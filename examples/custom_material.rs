@@ -41,7 +41,8 @@ pub struct NormalMaterial {
 
 impl NormalMaterial {
     pub fn new() -> NormalMaterial {
-        let mut shader = Effect::new_from_str(NORMAL_VERTEX_SRC, NORMAL_FRAGMENT_SRC);
+        let mut shader = Effect::new_from_str(NORMAL_VERTEX_SRC, NORMAL_FRAGMENT_SRC)
+            .unwrap_or_else(|e| panic!("{}", e));
 
         shader.use_program();
 
@@ -23,7 +23,7 @@ fn main() {
     )));
     let mut c = window.add_mesh(mesh, Vector3::new(1.0, 1.0, 1.0));
 
-    c.set_color(1.0, 0.0, 0.0);
+    c.set_color((1.0, 0.0, 0.0));
     c.enable_backface_culling(false);
 
     window.set_light(Light::StickToCamera);
@@ -32,8 +32,8 @@ fn main() {
         .add_geom_with_name("custom_mesh", Vector3::new(1.0, 1.0, 1.0))
         .unwrap();
 
-    c1.set_color(1.0, 0.0, 0.0);
-    c2.set_color(0.0, 1.0, 0.0);
+    c1.set_color((1.0, 0.0, 0.0));
+    c2.set_color((0.0, 1.0, 0.0));
 
     c1.enable_backface_culling(false);
     c2.enable_backface_culling(false);
@@ -0,0 +1,56 @@
+extern crate kiss3d;
+extern crate nalgebra as na;
+
+use kiss3d::light::Light;
+use kiss3d::post_processing::FullScreenQuadEffect;
+use kiss3d::window::Window;
+use na::{Translation3, Vector3};
+
+// A vignette with chromatic aberration towards the borders of the screen, pulsing with the time.
+const VIGNETTE: &str = "#version 100
+#ifdef GL_FRAGMENT_PRECISION_HIGH
+   precision highp float;
+#else
+   precision mediump float;
+#endif
+
+    uniform sampler2D fbo_texture;
+    uniform vec2      resolution;
+    uniform float     time;
+    uniform float     strength;
+    uniform vec3      tint;
+    varying vec2      f_texcoord;
+
+    void main(void) {
+      vec2 dir = f_texcoord - vec2(0.5);
+      vec2 shift = dir * (2.0 + sin(time * 2.0)) / resolution * 8.0;
+      float r = texture2D(fbo_texture, f_texcoord + shift).r;
+      float g = texture2D(fbo_texture, f_texcoord).g;
+      float b = texture2D(fbo_texture, f_texcoord - shift).b;
+      float vignette = 1.0 - strength * dot(dir, dir);
+
+      gl_FragColor = vec4(vec3(r, g, b) * tint * vignette, 1.0);
+    }";
+
+fn main() {
+    let mut window = Window::new("Kiss3d: custom post-processing");
+
+    let mut c = window.add_cube(1.0, 1.0, 1.0);
+    let mut s = window.add_sphere(0.5);
+    c.append_translation(&Translation3::new(1.0, 0.0, 0.0));
+    s.append_translation(&Translation3::new(-1.0, 0.0, 0.0));
+    c.set_color((0.2, 0.6, 1.0));
+    s.set_color((1.0, 0.6, 0.2));
+
+    let mut vignette =
+        FullScreenQuadEffect::from_fragment_shader(VIGNETTE).unwrap_or_else(|e| panic!("{}", e));
+    vignette.set_uniform("strength", 2.0f32);
+
+    window.set_background_color((1.0, 1.0, 1.0));
+    window.set_light(Light::StickToCamera);
+
+    while window.render_with_effect(&mut vignette) {
+        let warmth = (vignette.time() * 0.5).sin() * 0.2;
+        vignette.set_uniform("tint", Vector3::new(1.0 + warmth, 1.0, 1.0 - warmth));
+    }
+}
@@ -59,7 +59,7 @@ fn main() {
     let mtl_path = Path::new("none");
     let teapot = obj::parse_file(obj_path, mtl_path, "none").unwrap();
 
-    let mut m = window.add_obj(obj_path, mtl_path, scale);
+    let mut m = window.add_obj(obj_path, mtl_path, scale).unwrap();
     m.set_surface_rendering_activation(false);
     // m.set_lines_width(1.0);
     let data = m.data();
@@ -96,7 +96,7 @@ fn main() {
                     let b = random();
 
                     let mut m = window.add_trimesh(comp, scale);
-                    m.set_color(r, g, b);
+                    m.set_color((r, g, b));
                     m.append_translation(&Translation3::new(-0.1, 0.1, 0.0));
                     // m.set_surface_rendering_activation(false);
                     // m.enable_backface_culling(false);
@@ -123,7 +123,7 @@ fn main() {
                     );
                     let mesh = Rc::new(RefCell::new(mesh));
                     let mut m = window.add_mesh(mesh, scale);
-                    m.set_color(r, g, b);
+                    m.set_color((r, g, b));
                     m.append_translation(&Translation3::new(0.1, 0.1, 0.0));
                     // m.set_surface_rendering_activation(false);
                     // m.enable_backface_culling(false);
@@ -0,0 +1,31 @@
+extern crate kiss3d;
+
+#[cfg(feature = "gltf")]
+use kiss3d::light::Light;
+#[cfg(feature = "gltf")]
+use kiss3d::window::Window;
+#[cfg(feature = "gltf")]
+use std::path::Path;
+
+#[cfg(not(feature = "gltf"))]
+fn main() {
+    panic!("The 'gltf' feature must be enabled for this example to work.")
+}
+
+// Try it with, for example:
+// cargo run --example gltf --features gltf -- path/to/model.glb
+#[cfg(feature = "gltf")]
+fn main() {
+    let path = std::env::args()
+        .nth(1)
+        .expect("Usage: gltf <path to a .gltf or .glb file>");
+    let mut window = Window::new("Kiss3d: glTF");
+
+    if let Err(e) = window.add_gltf(Path::new(&path)) {
+        panic!("{}", e);
+    }
+
+    window.set_light(Light::StickToCamera);
+
+    while window.render() {}
+}
@@ -20,8 +20,8 @@ fn main() {
     g2.add_cube(1.0, 5.0, 1.0);
     g2.add_cube(1.0, 1.0, 5.0);
 
-    g1.set_color(1.0, 0.0, 0.0);
-    g2.set_color(0.0, 1.0, 0.0);
+    g1.set_color((1.0, 0.0, 0.0));
+    g2.set_color((0.0, 1.0, 0.0));
 
     window.set_light(Light::StickToCamera);
 
@@ -0,0 +1,45 @@
+extern crate kiss3d;
+extern crate nalgebra as na;
+
+use kiss3d::light::Light;
+use kiss3d::renderer::Instance;
+use kiss3d::resource::MeshManager;
+use kiss3d::window::Window;
+use na::{Isometry3, Vector3};
+
+const SIDE: usize = 100;
+
+// A wave of cubes, colored by their height.
+fn instances(time: f32) -> Vec<Instance> {
+    let mut instances = Vec::with_capacity(SIDE * SIDE);
+
+    for i in 0..SIDE {
+        for j in 0..SIDE {
+            let x = i as f32 - SIDE as f32 / 2.0;
+            let z = j as f32 - SIDE as f32 / 2.0;
+            let height = ((x * 0.2 + time).sin() + (z * 0.3 + time * 0.7).cos()) * 0.25 + 0.5;
+
+            instances.push(
+                Instance::new(Isometry3::translation(x, height / 2.0, z))
+                    .with_scale(Vector3::new(0.8, height, 0.8))
+                    .with_color((height, 0.3, 1.0 - height)),
+            );
+        }
+    }
+
+    instances
+}
+
+fn main() {
+    let mut window = Window::new("Kiss3d: instancing");
+    let cube = MeshManager::get_global_manager(|mm| mm.get("cube")).unwrap();
+    let mut cubes = window.add_instanced_mesh(cube, &instances(0.0));
+    let mut time = 0.0;
+
+    window.set_light(Light::StickToCamera);
+
+    while window.render() {
+        time += 0.02;
+        cubes.set_instances(&instances(time));
+    }
+}
@@ -0,0 +1,28 @@
+extern crate kiss3d;
+extern crate nalgebra as na;
+
+use kiss3d::light::LightSource;
+use kiss3d::scene::LightmapOptions;
+use kiss3d::window::Window;
+use na::{Point3, Translation3};
+
+fn main() {
+    let mut window = Window::new("Kiss3d: lightmap");
+
+    let mut ground = window.add_cube(6.0, 0.1, 6.0);
+    let mut c = window.add_cube(1.0, 1.0, 1.0);
+    let mut s = window.add_sphere(0.5);
+
+    ground.append_translation(&Translation3::new(0.0, -0.55, 0.0));
+    c.set_color((1.0, 0.5, 0.2));
+    s.set_color((0.2, 0.5, 1.0));
+    s.append_translation(&Translation3::new(1.2, 0.0, 0.5));
+
+    // The lighting is computed once, so the scene must not move afterward.
+    let light = LightSource::point(Point3::new(2.0, 4.0, 3.0)).with_intensity(2.0);
+    let options = LightmapOptions::direct_lighting(vec![light]).with_resolution(256);
+    let num_baked = window.scene_mut().bake_lightmaps(&options);
+    println!("Baked {} lightmaps.", num_baked);
+
+    while window.render() {}
+}
@@ -0,0 +1,47 @@
+extern crate kiss3d;
+extern crate nalgebra as na;
+
+use kiss3d::light::{LightKind, LightSource};
+use kiss3d::window::Window;
+use na::{Point3, Translation3, UnitQuaternion, Vector3};
+
+fn main() {
+    let mut window = Window::new("Kiss3d: lights");
+
+    let mut ground = window.add_cube(10.0, 0.1, 10.0);
+    let mut c = window.add_cube(1.0, 1.0, 1.0);
+    let mut s = window.add_sphere(0.5);
+
+    ground.append_translation(&Translation3::new(0.0, -1.0, 0.0));
+    s.append_translation(&Translation3::new(1.5, 0.0, 0.0));
+
+    let red = window.add_light(
+        LightSource::point(Point3::new(-2.0, 1.0, 2.0))
+            .with_color((1.0, 0.2, 0.2))
+            .with_attenuation(1.0, 0.0, 0.1),
+    );
+    window.add_light(
+        LightSource::spot(
+            Point3::new(0.0, 4.0, 0.0),
+            -Vector3::y(),
+            30.0f32.to_radians(),
+        )
+        .with_color((0.2, 0.4, 1.0))
+        .with_intensity(2.0),
+    );
+    window.add_light(LightSource::directional(Vector3::new(1.0, -1.0, 0.0)).with_intensity(0.3));
+    window.set_light_debug(true);
+
+    let rot = UnitQuaternion::from_axis_angle(&Vector3::y_axis(), 0.014);
+
+    while window.render() {
+        c.prepend_to_local_rotation(&rot);
+
+        // The red light orbits around the cube.
+        if let Some(light) = window.light_mut(red) {
+            if let LightKind::Point(ref mut pos) = light.kind {
+                *pos = rot * *pos;
+            }
+        }
+    }
+}
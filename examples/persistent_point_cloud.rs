@@ -86,7 +86,7 @@ struct PointCloudRenderer {
 impl PointCloudRenderer {
     /// Creates a new points renderer.
     fn new(point_size: f32) -> PointCloudRenderer {
-        let mut shader = Effect::new_from_str(VERTEX_SHADER_SRC, FRAGMENT_SHADER_SRC);
+        let mut shader = Effect::new_from_str(VERTEX_SHADER_SRC, FRAGMENT_SHADER_SRC).unwrap();
 
         shader.use_program();
 
@@ -0,0 +1,42 @@
+extern crate kiss3d;
+extern crate nalgebra as na;
+
+use kiss3d::light::Light;
+use kiss3d::window::Window;
+use na::{Point3, Translation3};
+
+fn main() {
+    let mut window = Window::new("Kiss3d: picking");
+
+    let mut nodes = vec![
+        window.add_cube(1.0, 1.0, 1.0),
+        window.add_sphere(0.5),
+        window.add_cone(0.5, 1.0),
+    ];
+
+    for (i, node) in nodes.iter_mut().enumerate() {
+        node.append_translation(&Translation3::new(i as f32 * 1.5 - 1.5, 0.0, 0.0));
+    }
+
+    window.set_light(Light::StickToCamera);
+    window.set_point_size(8.0);
+
+    while window.render() {
+        for node in nodes.iter_mut() {
+            node.set_color((0.8, 0.8, 0.8));
+        }
+
+        // Highlight the object under the mouse, and the hit point on its surface.
+        let hit = window.cursor_pos().and_then(|(x, y)| window.pick(x, y));
+
+        if let Some(mut hit) = hit {
+            hit.node.set_color((1.0, 0.3, 0.0));
+            window.draw_point(&hit.point, &Point3::new(0.0, 1.0, 0.0));
+            window.draw_line(
+                &hit.point,
+                &(hit.point + hit.normal * 0.3),
+                &Point3::new(0.0, 1.0, 0.0),
+            );
+        }
+    }
+}
@@ -4,7 +4,7 @@ extern crate rand;
 
 use kiss3d::light::Light;
 use kiss3d::post_processing::SobelEdgeHighlight;
-use kiss3d::post_processing::{Grayscales, Waves};
+use kiss3d::post_processing::{Grayscales, PostProcessingChain, Waves};
 use kiss3d::window::Window;
 use na::Translation3;
 use rand::random;
@@ -24,17 +24,20 @@ fn main() {
     y.append_translation(&Translation3::new(-4.0, 0.0, 0.0));
     a.append_translation(&Translation3::new(0.0, 0.0, 0.0));
 
-    c.set_color(random(), random(), random());
-    b.set_color(random(), random(), random());
-    p.set_color(random(), random(), random());
-    y.set_color(random(), random(), random());
-    a.set_color(random(), random(), random());
+    c.set_color((random(), random(), random()));
+    b.set_color((random(), random(), random()));
+    p.set_color((random(), random(), random()));
+    y.set_color((random(), random(), random()));
+    a.set_color((random(), random(), random()));
 
     let mut sobel = SobelEdgeHighlight::new(4.0);
     let mut waves = Waves::new();
     let mut grays = Grayscales::new();
+    let mut chain = PostProcessingChain::new();
+    chain.push(SobelEdgeHighlight::new(4.0));
+    chain.push(Grayscales::new());
 
-    window.set_background_color(1.0, 1.0, 1.0);
+    window.set_background_color((1.0, 1.0, 1.0));
     window.set_light(Light::StickToCamera);
     window.set_framerate_limit(Some(60));
 
@@ -44,7 +47,7 @@ fn main() {
     while !window.should_close() {
         if time % 200 == 0 {
             time = 0;
-            counter = (counter + 1) % 4;
+            counter = (counter + 1) % 5;
         }
 
         time = time + 1;
@@ -54,6 +57,7 @@ fn main() {
             1 => window.render_with_effect(&mut grays),
             2 => window.render_with_effect(&mut waves),
             3 => window.render_with_effect(&mut sobel),
+            4 => window.render_with_effect(&mut chain),
             _ => unreachable!(),
         };
     }
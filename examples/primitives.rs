@@ -16,11 +16,11 @@ fn main() {
     let mut y = window.add_cylinder(0.5, 1.0);
     let mut a = window.add_capsule(0.5, 1.0);
 
-    c.set_color(random(), random(), random());
-    s.set_color(random(), random(), random());
-    p.set_color(random(), random(), random());
-    y.set_color(random(), random(), random());
-    a.set_color(random(), random(), random());
+    c.set_color((random(), random(), random()));
+    s.set_color((random(), random(), random()));
+    p.set_color((random(), random(), random()));
+    y.set_color((random(), random(), random()));
+    a.set_color((random(), random(), random()));
 
     c.append_translation(&Translation3::new(2.0, 0.0, 0.0));
     s.append_translation(&Translation3::new(4.0, 0.0, 0.0));
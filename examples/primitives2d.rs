@@ -10,8 +10,8 @@ fn main() {
     let mut circ = window.add_circle(50.0);
     circ.append_translation(&Translation2::new(200.0, 0.0));
 
-    rect.set_color(0.0, 1.0, 0.0);
-    circ.set_color(0.0, 0.0, 1.0);
+    rect.set_color((0.0, 1.0, 0.0));
+    circ.set_color((0.0, 0.0, 1.0));
 
     let rot_rect = UnitComplex::new(0.014);
     let rot_circ = UnitComplex::new(-0.014);
@@ -29,11 +29,11 @@ fn main() {
         cy.append_translation(&Translation3::new(offset, -2.0, 0.0));
         ca.append_translation(&Translation3::new(offset, 0.0, 0.0));
 
-        cu.set_color(random(), random(), random());
-        sp.set_color(random(), random(), random());
-        co.set_color(random(), random(), random());
-        cy.set_color(random(), random(), random());
-        ca.set_color(random(), random(), random());
+        cu.set_color((random(), random(), random()));
+        sp.set_color((random(), random(), random()));
+        co.set_color((random(), random(), random()));
+        cy.set_color((random(), random(), random()));
+        ca.set_color((random(), random(), random()));
     }
 
     window.set_light(Light::StickToCamera);
@@ -21,7 +21,8 @@ fn main() {
     let cube = ncollide3d::procedural::cuboid(&Vector3::new(0.7f32, 0.2, 0.4));
     let mut c = window.add_trimesh(cube, Vector3::from_element(1.0));
     c.append_translation(&Translation3::new(1.0, 0.0, 0.0));
-    c.set_texture_from_file(Path::new("./examples/media/kitten.png"), "kitten");
+    c.set_texture_from_file(Path::new("./examples/media/kitten.png"), "kitten")
+        .unwrap();
 
     /*
      * A sphere.
@@ -36,7 +37,7 @@ fn main() {
     let capsule = ncollide3d::procedural::capsule(&0.4f32, &0.4f32, 20, 20);
     let mut c = window.add_trimesh(capsule, Vector3::from_element(1.0));
     c.append_translation(&Translation3::new(-1.0, 0.0, 0.0));
-    c.set_color(0.0, 0.0, 1.0);
+    c.set_color((0.0, 0.0, 1.0));
 
     // /*
     //  * Triangulation.
@@ -62,7 +63,7 @@ fn main() {
     // let mut t = window.add_trimesh(to_triangulate, Vector3::from_element(1.0));
     // t.set_surface_rendering_activation(false);
     // t.set_lines_width(2.0);
-    // t.set_color(0.0, 1.0, 0.0);
+    // t.set_color((0.0, 1.0, 0.0));
 
     /*
      * A (non-rational) bicubic Bézier surface.
@@ -93,7 +94,7 @@ fn main() {
     // XXX: replace by an `add_mesh`.
     let mut control_polyhedra_gfx = window.add_quad_with_vertices(&control_points, 4, 4);
     control_polyhedra_gfx.append_translation(&Translation3::new(-1.5, -1.5, 0.0));
-    control_polyhedra_gfx.set_color(0.0, 0.0, 1.0);
+    control_polyhedra_gfx.set_color((0.0, 0.0, 1.0));
     control_polyhedra_gfx.set_surface_rendering_activation(false);
     control_polyhedra_gfx.set_lines_width(2.0);
 
@@ -102,7 +103,7 @@ fn main() {
         Vector3::from_element(1.0),
     );
     control_points_gfx.append_translation(&Translation3::new(-1.5, -1.5, 0.0));
-    control_points_gfx.set_color(1.0, 0.0, 0.0);
+    control_points_gfx.set_color((1.0, 0.0, 0.0));
     control_points_gfx.set_surface_rendering_activation(false);
     control_points_gfx.set_points_size(10.0);
 
@@ -132,7 +133,7 @@ fn main() {
     let mesh = pattern.stroke(&mut path);
     let mut m = window.add_trimesh(mesh, Vector3::new(0.5f32, 0.5, 0.5));
     m.append_translation(&Translation3::new(4.0, -1.0, 0.0));
-    m.set_color(1.0, 1.0, 0.0);
+    m.set_color((1.0, 1.0, 0.0));
 
     /*
      * Convex hull of 100,000 random 3d points.
@@ -149,11 +150,11 @@ fn main() {
         Vector3::from_element(1.0),
     );
     mhull.append_translation(&Translation3::new(0.0, 2.0, -1.0));
-    mhull.set_color(0.0, 1.0, 0.0);
+    mhull.set_color((0.0, 1.0, 0.0));
     mhull.set_lines_width(2.0);
     mhull.set_surface_rendering_activation(false);
     mhull.set_points_size(10.0);
-    mpts.set_color(0.0, 0.0, 1.0);
+    mpts.set_color((0.0, 0.0, 1.0));
     mpts.append_translation(&Translation3::new(0.0, 2.0, -1.0));
     mpts.set_points_size(2.0);
     mpts.set_surface_rendering_activation(false);
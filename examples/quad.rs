@@ -11,7 +11,7 @@ fn main() {
 
     let mut c = window.add_quad(5.0, 4.0, 100, 100);
 
-    c.set_color(random(), random(), random());
+    c.set_color((random(), random(), random()));
 
     let mut time = 0.016f32;
 
@@ -9,7 +9,7 @@ fn main() {
     let mut window = Window::new("Kiss3d: rectangle");
     let mut c = window.add_rectangle(100.0, 150.0);
 
-    c.set_color(1.0, 0.0, 0.0);
+    c.set_color((1.0, 0.0, 0.0));
 
     window.set_light(Light::StickToCamera);
 
@@ -0,0 +1,28 @@
+extern crate kiss3d;
+
+#[cfg(feature = "remote")]
+use kiss3d::light::Light;
+#[cfg(feature = "remote")]
+use kiss3d::remote::RemoteViewer;
+#[cfg(feature = "remote")]
+use kiss3d::window::Window;
+
+#[cfg(not(feature = "remote"))]
+fn main() {
+    panic!("The 'remote' feature must be enabled for this example to work.")
+}
+
+// Try it with, for example:
+// echo '{"cmd": "add_cube", "id": "box", "size": [1.0, 1.0, 1.0]}' | nc 127.0.0.1 4455
+#[cfg(feature = "remote")]
+fn main() {
+    let mut window = Window::new("Kiss3d: remote viewer");
+    let mut viewer = RemoteViewer::bind("127.0.0.1:4455").unwrap();
+
+    println!("Listening at {}", viewer.local_addr());
+    window.set_light(Light::StickToCamera);
+
+    while window.render() {
+        let _ = viewer.update(&mut window);
+    }
+}
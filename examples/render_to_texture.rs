@@ -0,0 +1,52 @@
+extern crate kiss3d;
+extern crate nalgebra as na;
+
+use kiss3d::camera::ArcBall;
+use kiss3d::light::Light;
+use kiss3d::resource::FramebufferManager;
+use kiss3d::window::Window;
+use na::{Point3, Translation3};
+
+fn main() {
+    let mut window = Window::new("Kiss3d: render to texture");
+
+    for i in 0..5 {
+        let angle = i as f32 * std::f32::consts::TAU / 5.0;
+        let mut c = window.add_cube(1.0, 1.0, 1.0);
+        c.set_color(((i as f32 / 4.0), 0.5, 1.0 - i as f32 / 4.0));
+        c.append_translation(&Translation3::new(
+            angle.cos() * 2.5,
+            0.5,
+            angle.sin() * 2.5,
+        ));
+    }
+
+    let mut ground = window.add_quad(12.0, 12.0, 1, 1);
+    ground.set_color((0.6, 0.6, 0.6));
+    ground.append_rotation(&na::UnitQuaternion::from_axis_angle(
+        &na::Vector3::x_axis(),
+        -std::f32::consts::FRAC_PI_2,
+    ));
+
+    // A screen above the scene, out of sight of the security camera, showing what it sees.
+    let target = FramebufferManager::new_render_target(512, 512, false);
+    let mut screen = window.add_quad(4.0, 4.0, 1, 1);
+    screen.set_texture(target.texture().unwrap());
+    screen.append_translation(&Translation3::new(0.0, 8.0, 0.0));
+    // The render target stores the bottom row of the image first.
+    screen.set_local_scale(1.0, -1.0, 1.0);
+
+    let mut camera = ArcBall::new(Point3::new(0.0, 6.0, 15.0), Point3::new(0.0, 4.0, 0.0));
+    let mut security_camera = ArcBall::new(Point3::new(0.0, 1.0, 6.0), Point3::new(0.0, 0.5, 0.0));
+
+    window.set_light(Light::StickToCamera);
+
+    loop {
+        security_camera.set_yaw(security_camera.yaw() + 0.01);
+        window.render_to_texture(&mut security_camera, &target);
+
+        if !window.render_with_camera(&mut camera) {
+            break;
+        }
+    }
+}
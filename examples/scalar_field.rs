@@ -0,0 +1,28 @@
+extern crate kiss3d;
+extern crate nalgebra as na;
+
+use kiss3d::colormap::{self, Colormap};
+use kiss3d::light::Light;
+use kiss3d::overlay::ColormapLegend;
+use kiss3d::window::Window;
+
+fn main() {
+    env_logger::init();
+    let mut window = Window::new("Kiss3d: scalar field");
+    let mut s = window.add_sphere(0.5);
+
+    // Color the sphere according to the height of each vertex.
+    let mut heights = Vec::new();
+    s.read_vertices(&mut |coords| heights.extend(coords.iter().map(|p| p.y)));
+
+    let range = colormap::value_range(&heights);
+    s.set_scalar_field(&heights, &Colormap::Viridis, Some(range));
+
+    let mut legend = ColormapLegend::new(Colormap::Viridis, range);
+    legend.title = "Height".to_string();
+    window.set_colormap_legend(Some(legend));
+
+    window.set_light(Light::StickToCamera);
+
+    while window.render() {}
+}
@@ -4,7 +4,7 @@ extern crate nalgebra as na;
 use std::path::Path;
 
 use kiss3d::light::Light;
-use kiss3d::window::Window;
+use kiss3d::window::{SnapFormat, Window};
 use na::{UnitQuaternion, Vector3};
 
 // Based on cube example.
@@ -12,7 +12,7 @@ fn main() {
     let mut window = Window::new("Kiss3d: screenshot");
     let mut c = window.add_cube(0.2, 0.2, 0.2);
 
-    c.set_color(1.0, 0.0, 0.0);
+    c.set_color((1.0, 0.0, 0.0));
     c.prepend_to_local_rotation(&UnitQuaternion::from_axis_angle(&Vector3::y_axis(), 0.785));
     c.prepend_to_local_rotation(&UnitQuaternion::from_axis_angle(
         &Vector3::x_axis(),
@@ -26,6 +26,15 @@ fn main() {
         let img_path = Path::new("screenshot.png");
         img.save(img_path).unwrap();
         println!("Screeshot saved to `screenshot.png`");
+
+        // 16-bit and float snapshots, and a dump of the depth buffer.
+        window
+            .save_snap("screenshot16.png", SnapFormat::Rgb16)
+            .unwrap();
+        window
+            .save_snap("screenshot.exr", SnapFormat::Rgb32F)
+            .unwrap();
+        window.save_snap("depth.exr", SnapFormat::Depth32F).unwrap();
         break;
     }
 }
@@ -0,0 +1,61 @@
+extern crate kiss3d;
+extern crate nalgebra as na;
+
+use kiss3d::event::{Action, Key, WindowEvent};
+use kiss3d::light::Light;
+use kiss3d::scene::{CascadeSplit, ShadowCascades};
+use kiss3d::window::Window;
+use na::{Point3, Translation3};
+
+fn main() {
+    let mut window = Window::new("Kiss3d: shadow cascades");
+
+    let mut ground = window.add_cube(400.0, 0.1, 400.0);
+    ground.set_color((0.8, 0.8, 0.8));
+    ground.append_translation(&Translation3::new(0.0, -0.05, 0.0));
+    ground.set_shadow_caster(false);
+
+    // A forest of pillars, from the camera to the horizon.
+    for i in -20..20 {
+        for j in -20..20 {
+            let height = 1.0 + ((i * 7 + j * 13) as f32).sin().abs() * 3.0;
+            let mut c = window.add_cube(0.5, height, 0.5);
+            c.set_color((0.3, 0.6, 0.3));
+            c.append_translation(&Translation3::new(
+                i as f32 * 8.0,
+                height / 2.0,
+                j as f32 * 8.0,
+            ));
+        }
+    }
+
+    window.set_light(Light::Absolute(Point3::new(2000.0, 3000.0, 1000.0)));
+    window.set_shadow_mapping(true);
+    window.set_shadow_cascades(Some(
+        ShadowCascades::new(4)
+            .with_split(CascadeSplit::Practical(0.75))
+            .with_max_distance(200.0),
+    ));
+    window.set_shadow_cascade_debug(true);
+
+    println!("Press D to toggle the colors of the cascades, C to toggle the cascades.");
+
+    while window.render() {
+        for event in window.events().iter() {
+            match event.value {
+                WindowEvent::Key(Key::D, Action::Press, _) => {
+                    let debug = window.shadow_cascade_debug_enabled();
+                    window.set_shadow_cascade_debug(!debug);
+                }
+                WindowEvent::Key(Key::C, Action::Press, _) => {
+                    let cascades = match window.shadow_cascades() {
+                        Some(_) => None,
+                        None => Some(ShadowCascades::new(4).with_max_distance(200.0)),
+                    };
+                    window.set_shadow_cascades(cascades);
+                }
+                _ => {}
+            }
+        }
+    }
+}
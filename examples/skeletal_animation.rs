@@ -0,0 +1,96 @@
+extern crate kiss3d;
+extern crate nalgebra as na;
+
+use kiss3d::light::Light;
+use kiss3d::resource::{JointTransform, Mesh, Skeleton};
+use kiss3d::scene::{AnimationClip, AnimationPlayer};
+use kiss3d::window::Window;
+use na::{Isometry3, Point3, UnitQuaternion, Vector3};
+use std::cell::RefCell;
+use std::f32;
+use std::rc::Rc;
+
+const NJOINTS: usize = 4;
+const RINGS_PER_JOINT: usize = 8;
+const SIDES: usize = 16;
+
+// A tube along the `y` axis, one unit long per joint, each ring of vertices following the
+// joints around it.
+fn tentacle() -> Mesh {
+    let nrings = NJOINTS * RINGS_PER_JOINT + 1;
+    let mut coords = Vec::new();
+    let mut joints = Vec::new();
+    let mut weights = Vec::new();
+    let mut faces = Vec::new();
+
+    for i in 0..nrings {
+        let y = i as f32 / RINGS_PER_JOINT as f32;
+        let radius = 0.3 * (1.0 - y / (NJOINTS as f32 + 0.5));
+        // Blends between the joint starting below the ring and the next one.
+        let joint = (y.floor() as usize).min(NJOINTS - 1);
+        let next = (joint + 1).min(NJOINTS - 1);
+        let t = (y - joint as f32).clamp(0.0, 1.0);
+
+        for j in 0..SIDES {
+            let angle = j as f32 / SIDES as f32 * f32::consts::PI * 2.0;
+            coords.push(Point3::new(radius * angle.cos(), y, radius * angle.sin()));
+            joints.push([joint as u16, next as u16, 0, 0]);
+            weights.push([1.0 - t, t, 0.0, 0.0]);
+        }
+    }
+
+    for i in 0..nrings - 1 {
+        for j in 0..SIDES {
+            let a = (i * SIDES + j) as u16;
+            let b = (i * SIDES + (j + 1) % SIDES) as u16;
+            let c = a + SIDES as u16;
+            let d = b + SIDES as u16;
+            faces.push(Point3::new(a, c, b));
+            faces.push(Point3::new(b, c, d));
+        }
+    }
+
+    let mut mesh = Mesh::new(coords, faces, None, None, false);
+    mesh.set_skin(joints, weights);
+    mesh
+}
+
+fn main() {
+    let mut window = Window::new("Kiss3d: skeletal animation");
+    window.set_light(Light::StickToCamera);
+
+    // A chain of joints, one unit apart.
+    let mut skeleton = Skeleton::new();
+    let mut parent = None;
+
+    for i in 0..NJOINTS {
+        let offset = if i == 0 { 0.0 } else { 1.0 };
+        let rest = JointTransform::from_isometry(&Isometry3::translation(0.0, offset, 0.0));
+        parent = Some(skeleton.add_joint(&format!("joint{}", i), parent, rest));
+    }
+
+    // Each joint sways a bit later than its parent.
+    let mut sway = AnimationClip::new();
+
+    for i in 0..NJOINTS {
+        let rest = *skeleton.rest_pose(i);
+
+        for k in 0..=8 {
+            let time = k as f32 * 0.25;
+            let phase = (time - i as f32 * 0.2) * f32::consts::PI;
+            let mut pose = rest;
+            pose.rotation = UnitQuaternion::from_axis_angle(&Vector3::z_axis(), phase.sin() * 0.4);
+            sway.add_keyframe(i, time, pose);
+        }
+    }
+
+    let mut tentacle = window.add_mesh(Rc::new(RefCell::new(tentacle())), Vector3::repeat(1.0));
+    tentacle.set_color((0.8, 0.4, 0.6));
+
+    let mut player = AnimationPlayer::new(Rc::new(RefCell::new(skeleton)));
+    player.set_looping(true);
+    player.play(Rc::new(sway));
+    tentacle.add_animation_player(player);
+
+    while window.render() {}
+}
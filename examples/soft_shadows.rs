@@ -0,0 +1,44 @@
+extern crate kiss3d;
+extern crate nalgebra as na;
+
+use kiss3d::event::{Action, Key, WindowEvent};
+use kiss3d::light::Light;
+use kiss3d::scene::ShadowFilter;
+use kiss3d::window::Window;
+use na::{Point3, Translation3};
+
+fn main() {
+    let mut window = Window::new("Kiss3d: soft shadows");
+
+    let mut ground = window.add_cube(10.0, 0.1, 10.0);
+    ground.set_color((0.9, 0.9, 0.9));
+    ground.append_translation(&Translation3::new(0.0, -0.05, 0.0));
+    ground.set_shadow_caster(false);
+
+    // The shadow of the pole is sharp at its foot and blurry at its top.
+    let mut pole = window.add_cylinder(0.05, 3.0);
+    pole.set_color((0.8, 0.2, 0.2));
+    pole.append_translation(&Translation3::new(-1.0, 1.5, 0.0));
+
+    let mut sphere = window.add_sphere(0.5);
+    sphere.set_color((0.2, 0.4, 0.8));
+    sphere.append_translation(&Translation3::new(1.0, 1.2, 0.0));
+
+    window.set_light(Light::Absolute(Point3::new(1.0, 8.0, 4.0)));
+    window.set_shadow_mapping(true);
+    window.set_shadow_filter(ShadowFilter::Pcss { light_size: 1.0 });
+
+    println!("Press S to toggle the soft shadows.");
+
+    while window.render() {
+        for event in window.events().iter() {
+            if let WindowEvent::Key(Key::S, Action::Press, _) = event.value {
+                let filter = match window.shadow_filter() {
+                    ShadowFilter::Pcf => ShadowFilter::Pcss { light_size: 1.0 },
+                    ShadowFilter::Pcss { .. } => ShadowFilter::Pcf,
+                };
+                window.set_shadow_filter(filter);
+            }
+        }
+    }
+}
@@ -17,7 +17,7 @@ fn main() {
     let at = Point3::new(0.0f32, 0.0, 0.0);
     let mut camera = FirstPersonStereo::new(eye, at, 0.3f32);
 
-    c.set_color(1.0, 0.0, 0.0);
+    c.set_color((1.0, 0.0, 0.0));
 
     window.set_light(Light::StickToCamera);
 
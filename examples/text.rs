@@ -1,13 +1,19 @@
 extern crate kiss3d;
 extern crate nalgebra as na;
 
-use kiss3d::text::Font;
+use kiss3d::text::{Font, FontFamily, TextSpan};
 use kiss3d::window::Window;
 use na::{Point2, Point3};
 
 fn main() {
     let mut window = Window::new("Kiss3d: text");
     let font = Font::default();
+    let family = FontFamily::new(font.clone());
+    let spans = TextSpan::parse_markup(
+        "Styled <color=#ff4040>red</color>, <u>underlined</u>,\nand <color=#40ff40>green <u>text</u></color>.",
+        &family,
+        &Point3::new(1.0, 1.0, 1.0),
+    );
 
     while window.render() {
         window.draw_text(
@@ -27,5 +33,7 @@ fn main() {
             &font,
             &Point3::new(1.0, 1.0, 0.0),
         );
+
+        window.draw_text_spans(&spans, &Point2::new(0.0, 300.0), 60.0);
     }
 }
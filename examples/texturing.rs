@@ -10,12 +10,13 @@ fn main() {
     let mut window = Window::new("Kiss3d: texturing");
 
     let mut c = window.add_cube(1.0, 1.0, 1.0);
-    c.set_color(1.0, 0.0, 0.0);
-    c.set_texture_from_file(Path::new("./examples/media/kitten.png"), "kitten");
+    c.set_color((1.0, 0.0, 0.0));
+    c.set_texture_from_file(Path::new("./examples/media/kitten.png"), "kitten")
+        .unwrap();
 
     let mut r = window.add_rectangle(100.0, 100.0);
     r.append_translation(&Translation2::new(-100.0, -100.0));
-    r.set_color(0.0, 0.0, 1.0);
+    r.set_color((0.0, 0.0, 1.0));
     r.set_texture_from_memory(include_bytes!("./media/kitten.png"), "kitten_mem");
 
     window.set_light(Light::StickToCamera);
@@ -15,12 +15,12 @@ fn main() {
     // without mipmaps.
     TextureManager::get_global_manager(|tm| tm.set_generate_mipmaps(false));
     let mut q1 = window.add_sphere(1.0);
-    q1.set_texture_from_file(tex_path, "no-mipmaps");
+    q1.set_texture_from_file(tex_path, "no-mipmaps").unwrap();
     q1.set_local_translation(Translation3::new(0.3, 0.0, 0.0));
 
     TextureManager::get_global_manager(|tm| tm.set_generate_mipmaps(true));
     let mut q2 = window.add_sphere(1.0);
-    q2.set_texture_from_file(tex_path, "with-mipmaps");
+    q2.set_texture_from_file(tex_path, "with-mipmaps").unwrap();
     q2.set_local_translation(Translation3::new(-0.3, 0.0, 0.0));
 
     window.set_light(Light::StickToCamera);
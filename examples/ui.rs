@@ -24,16 +24,18 @@ fn main() {
 #[cfg(feature = "conrod")]
 fn main() {
     let mut window = Window::new("Kiss3d: UI");
-    window.set_background_color(1.0, 1.0, 1.0);
+    window.set_background_color((1.0, 1.0, 1.0));
     let mut c = window.add_cube(0.1, 0.1, 0.1);
-    c.set_color(1.0, 0.0, 0.0);
+    c.set_color((1.0, 0.0, 0.0));
 
     window.set_light(Light::StickToCamera);
 
     // Generate the widget identifiers.
     let ids = Ids::new(window.conrod_ui_mut().widget_id_generator());
     window.conrod_ui_mut().theme = theme();
-    window.add_texture(&Path::new("./examples/media/kitten.png"), "cat");
+    window
+        .add_texture(&Path::new("./examples/media/kitten.png"), "cat")
+        .unwrap();
     let cat_texture = window.conrod_texture_id("cat").unwrap();
 
     let mut app = DemoApp::new(cat_texture);
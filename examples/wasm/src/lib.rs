@@ -35,10 +35,10 @@ impl State for AppState {
 #[wasm_bindgen(start)]
 pub fn main() -> Result<(), JsValue> {
     let mut window = Window::new("Kiss3d: wasm example");
-    window.set_background_color(1.0, 1.0, 1.0);
+    window.set_background_color((1.0, 1.0, 1.0));
     let mut c = window.add_cube(0.1, 0.1, 0.1);
 
-    c.set_color(1.0, 0.0, 0.0);
+    c.set_color((1.0, 0.0, 0.0));
 
     window.set_light(Light::StickToCamera);
 
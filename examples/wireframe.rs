@@ -9,7 +9,7 @@ fn main() {
     let mut window = Window::new("Kiss3d: wireframe");
     let mut c = window.add_cube(1.0, 1.0, 1.0);
 
-    c.set_color(1.0, 0.0, 0.0);
+    c.set_color((1.0, 0.0, 0.0));
     c.set_points_size(10.0);
     c.set_lines_width(1.0);
     c.set_surface_rendering_activation(false);
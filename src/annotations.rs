@@ -0,0 +1,359 @@
+//! Retained callouts: text cards connected to points of the scene by leader lines.
+//!
+//! Callouts are added to the `Annotations` of a window and drawn on top of everything at every
+//! frame until removed. Their cards are laid out around their anchors so that they do not
+//! overlap each other:
+//!
+//! ```no_run
+//! # use kiss3d::window::Window;
+//! # use kiss3d::nalgebra::Point3;
+//! # use kiss3d::annotations::Callout;
+//! let mut window = Window::new("Callouts");
+//! let _ = window.add_cube(1.0, 1.0, 1.0);
+//! let annotations = window.annotations_mut();
+//! let _ = annotations.add(Callout::new(Point3::new(0.5, 0.5, 0.5), "Corner"));
+//! let _ = annotations.add(Callout::new(Point3::new(0.0, 0.5, 0.0), "Top face").with_layer("faces"));
+//!
+//! while window.render() {}
+//! ```
+//!
+//! Each callout belongs to a layer, identified by its name, that can be hidden as a whole.
+
+use std::collections::HashSet;
+
+use na::{Point2, Point3, Vector2};
+
+use crate::camera::Camera;
+use crate::overlay::OverlayContext;
+
+/// The appearance of a callout.
+#[derive(Clone, Debug, PartialEq)]
+pub struct CalloutStyle {
+    /// The color of the text.
+    pub text_color: Point3<f32>,
+    /// The color of the leader line, of the anchor marker, and of the card border.
+    pub line_color: Point3<f32>,
+    /// The color filling the card, or `None` for a transparent card.
+    pub background: Option<Point3<f32>>,
+    /// The size of the text font, in logical pixels.
+    pub text_size: f32,
+    /// The space between the text and the border of the card, in logical pixels.
+    pub padding: f32,
+    /// The preferred length of the leader line, in logical pixels.
+    pub leader_length: f32,
+    /// The half-size of the square marking the anchor, in logical pixels.
+    pub marker_radius: f32,
+}
+
+impl Default for CalloutStyle {
+    fn default() -> Self {
+        CalloutStyle {
+            text_color: Point3::new(1.0, 1.0, 1.0),
+            line_color: Point3::new(0.8, 0.8, 0.8),
+            background: Some(Point3::new(0.15, 0.15, 0.15)),
+            text_size: 16.0,
+            padding: 4.0,
+            leader_length: 40.0,
+            marker_radius: 3.0,
+        }
+    }
+}
+
+/// A text card attached to a point of the scene.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Callout {
+    /// The world-space point the leader line points at.
+    pub anchor: Point3<f32>,
+    /// The text of the card.
+    pub text: String,
+    /// The name of the layer of this callout.
+    pub layer: String,
+}
+
+impl Callout {
+    /// Creates a callout of the default layer, whose name is empty.
+    pub fn new(anchor: Point3<f32>, text: &str) -> Callout {
+        Callout {
+            anchor,
+            text: text.to_string(),
+            layer: String::new(),
+        }
+    }
+
+    /// Moves this callout to the given layer.
+    pub fn with_layer(mut self, layer: &str) -> Callout {
+        self.layer = layer.to_string();
+        self
+    }
+}
+
+/// The identifier of a callout added to `Annotations`.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct CalloutId(u64);
+
+/// The set of callouts drawn by a window.
+pub struct Annotations {
+    /// The style given to the callouts added with `add`.
+    pub default_style: CalloutStyle,
+    items: Vec<(CalloutId, Callout, CalloutStyle)>,
+    hidden_layers: HashSet<String>,
+    next_id: u64,
+}
+
+impl Default for Annotations {
+    fn default() -> Self {
+        Annotations::new()
+    }
+}
+
+impl Annotations {
+    /// Creates an empty set of callouts.
+    pub fn new() -> Annotations {
+        Annotations {
+            default_style: CalloutStyle::default(),
+            items: Vec::new(),
+            hidden_layers: HashSet::new(),
+            next_id: 0,
+        }
+    }
+
+    /// Adds a callout drawn with the default style.
+    pub fn add(&mut self, callout: Callout) -> CalloutId {
+        let style = self.default_style.clone();
+        self.add_with_style(callout, style)
+    }
+
+    /// Adds a callout drawn with a specific style.
+    pub fn add_with_style(&mut self, callout: Callout, style: CalloutStyle) -> CalloutId {
+        let id = CalloutId(self.next_id);
+        self.next_id += 1;
+        self.items.push((id, callout, style));
+        id
+    }
+
+    /// Removes a callout. Returns it if it existed.
+    pub fn remove(&mut self, id: CalloutId) -> Option<Callout> {
+        let i = self.items.iter().position(|item| item.0 == id)?;
+        Some(self.items.remove(i).1)
+    }
+
+    /// Removes all the callouts.
+    pub fn clear(&mut self) {
+        self.items.clear()
+    }
+
+    /// The number of callouts.
+    pub fn len(&self) -> usize {
+        self.items.len()
+    }
+
+    /// Whether there is no callout.
+    pub fn is_empty(&self) -> bool {
+        self.items.is_empty()
+    }
+
+    /// The callout with the given identifier.
+    pub fn get(&self, id: CalloutId) -> Option<&Callout> {
+        self.items
+            .iter()
+            .find(|item| item.0 == id)
+            .map(|item| &item.1)
+    }
+
+    /// A mutable reference to the callout with the given identifier, e.g., to move its anchor.
+    pub fn get_mut(&mut self, id: CalloutId) -> Option<&mut Callout> {
+        self.items
+            .iter_mut()
+            .find(|item| item.0 == id)
+            .map(|item| &mut item.1)
+    }
+
+    /// A mutable reference to the style of the callout with the given identifier.
+    pub fn style_mut(&mut self, id: CalloutId) -> Option<&mut CalloutStyle> {
+        self.items
+            .iter_mut()
+            .find(|item| item.0 == id)
+            .map(|item| &mut item.2)
+    }
+
+    /// Iterates through the identifiers and values of all the callouts.
+    pub fn iter(&self) -> impl Iterator<Item = (CalloutId, &Callout)> {
+        self.items.iter().map(|item| (item.0, &item.1))
+    }
+
+    /// Shows or hides all the callouts of a layer.
+    pub fn set_layer_visible(&mut self, layer: &str, visible: bool) {
+        if visible {
+            let _ = self.hidden_layers.remove(layer);
+        } else {
+            let _ = self.hidden_layers.insert(layer.to_string());
+        }
+    }
+
+    /// Whether the callouts of a layer are shown.
+    pub fn is_layer_visible(&self, layer: &str) -> bool {
+        !self.hidden_layers.contains(layer)
+    }
+
+    pub(crate) fn draw(&self, camera: &dyn Camera, ctxt: &mut OverlayContext) {
+        let size = ctxt.size();
+        let scale = ctxt.scale();
+        let transform = camera.transformation();
+        let mut placed: Vec<Rect> = Vec::new();
+
+        for (_, callout, style) in &self.items {
+            if !self.is_layer_visible(&callout.layer) {
+                continue;
+            }
+
+            let h = transform * callout.anchor.to_homogeneous();
+
+            // Skip the anchors behind the camera or outside of the window.
+            if h.w <= 1.0e-6 || h.x.abs() > h.w || h.y.abs() > h.w {
+                continue;
+            }
+
+            let anchor = Point2::new(
+                (h.x / h.w + 1.0) / 2.0 * size.x,
+                (1.0 - h.y / h.w) / 2.0 * size.y,
+            );
+            let padding = style.padding * scale;
+            let card =
+                ctxt.text_size(&callout.text, style.text_size) + Vector2::repeat(padding * 2.0);
+            let rect = layout(&anchor, &card, style.leader_length * scale, &size, &placed);
+
+            let marker = style.marker_radius * scale;
+            let corners = [
+                anchor + Vector2::new(-marker, -marker),
+                anchor + Vector2::new(marker, -marker),
+                anchor + Vector2::new(marker, marker),
+                anchor + Vector2::new(-marker, marker),
+            ];
+
+            for i in 0..4 {
+                ctxt.draw_line(&corners[i], &corners[(i + 1) % 4], &style.line_color);
+            }
+
+            ctxt.draw_line(&anchor, &rect.closest_point(&anchor), &style.line_color);
+
+            if let Some(background) = style.background {
+                // The card is filled with one horizontal line per physical pixel.
+                let nlines = (rect.max.y - rect.min.y).ceil() as usize;
+
+                for i in 0..=nlines {
+                    let y = rect.min.y + i as f32;
+                    ctxt.draw_line(
+                        &Point2::new(rect.min.x, y),
+                        &Point2::new(rect.max.x, y),
+                        &background,
+                    );
+                }
+            }
+
+            let corners = [
+                rect.min,
+                Point2::new(rect.max.x, rect.min.y),
+                rect.max,
+                Point2::new(rect.min.x, rect.max.y),
+            ];
+
+            for i in 0..4 {
+                ctxt.draw_line(&corners[i], &corners[(i + 1) % 4], &style.line_color);
+            }
+
+            let text_pos = rect.min + Vector2::repeat(padding);
+            ctxt.draw_text(&callout.text, &text_pos, style.text_size, &style.text_color);
+            placed.push(rect);
+        }
+    }
+}
+
+// An axis-aligned rectangle, in window coordinates.
+#[derive(Copy, Clone, Debug)]
+struct Rect {
+    min: Point2<f32>,
+    max: Point2<f32>,
+}
+
+impl Rect {
+    fn overlap(&self, other: &Rect) -> f32 {
+        let w = self.max.x.min(other.max.x) - self.min.x.max(other.min.x);
+        let h = self.max.y.min(other.max.y) - self.min.y.max(other.min.y);
+        w.max(0.0) * h.max(0.0)
+    }
+
+    fn closest_point(&self, pt: &Point2<f32>) -> Point2<f32> {
+        pt.sup(&self.min).inf(&self.max)
+    }
+}
+
+// The preferred directions of the cards around their anchor, in window coordinates.
+const DIRECTIONS: [(f32, f32); 8] = [
+    (1.0, -1.0),
+    (-1.0, -1.0),
+    (1.0, 1.0),
+    (-1.0, 1.0),
+    (0.0, -1.0),
+    (0.0, 1.0),
+    (1.0, 0.0),
+    (-1.0, 0.0),
+];
+
+// Places a card around its anchor, at the end of a leader line, where it overlaps the least the
+// cards already placed and the borders of the window.
+fn layout(
+    anchor: &Point2<f32>,
+    card: &Vector2<f32>,
+    leader: f32,
+    window: &Vector2<f32>,
+    placed: &[Rect],
+) -> Rect {
+    let screen = Rect {
+        min: Point2::origin(),
+        max: Point2::from(*window),
+    };
+    let mut best = (
+        f32::MAX,
+        Rect {
+            min: *anchor,
+            max: anchor + card,
+        },
+    );
+
+    for k in 1..=3 {
+        for &(dx, dy) in &DIRECTIONS {
+            let dir = Vector2::new(dx, dy).normalize();
+            let end = anchor + dir * (leader * k as f32);
+            // The card is attached to the leader line by its side or corner facing the anchor.
+            let attach = |d: f32, extent: f32| {
+                if d > 0.0 {
+                    0.0
+                } else if d < 0.0 {
+                    -extent
+                } else {
+                    -extent / 2.0
+                }
+            };
+            let offset = Vector2::new(attach(dx, card.x), attach(dy, card.y));
+            let min = end + offset;
+            let rect = Rect {
+                min,
+                max: min + card,
+            };
+            let area = card.x * card.y;
+            let cost = placed.iter().map(|r| r.overlap(&rect)).sum::<f32>()
+                + (area - screen.overlap(&rect));
+
+            // Less than a pixel of overlap is not worth a longer leader line.
+            if cost < 1.0 {
+                return rect;
+            }
+
+            if cost < best.0 {
+                best = (cost, rect);
+            }
+        }
+    }
+
+    best.1
+}
@@ -0,0 +1,451 @@
+//! Sound playback positioned relative to the camera, and spectrum analysis for audio-reactive
+//! visuals.
+//!
+//! ```no_run
+//! # use kiss3d::window::Window;
+//! # use kiss3d::camera::ArcBall;
+//! # use kiss3d::audio::{AudioPlayer, Sound};
+//! # use kiss3d::nalgebra::Point3;
+//! let mut window = Window::new("Audio");
+//! let mut camera = ArcBall::new(Point3::new(0.0, 0.0, -5.0), Point3::origin());
+//! let mut cube = window.add_cube(1.0, 1.0, 1.0);
+//!
+//! let mut player = AudioPlayer::new().unwrap();
+//! let music = Sound::load("music.ogg").unwrap();
+//! let _ = player.play_on_node(&music, &cube, true);
+//!
+//! while window.render_with_camera(&mut camera) {
+//!     player.update(&camera);
+//!     // Scale the cube with the bass.
+//!     let bass = player.spectrum()[..8].iter().sum::<f32>();
+//!     cube.set_local_scale(1.0 + bass, 1.0 + bass, 1.0 + bass);
+//! }
+//! ```
+
+use na::Point3;
+use rodio::source::{Buffered, SamplesConverter};
+use rodio::{Decoder, OutputStream, OutputStreamHandle, Source, SpatialSink};
+use std::collections::{HashMap, VecDeque};
+use std::f32::consts::PI;
+use std::fmt;
+use std::io::{self, Cursor};
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use crate::camera::Camera;
+use crate::loader::asset_source;
+use crate::scene::SceneNode;
+
+// The number of samples analyzed by the FFT.
+const FFT_SIZE: usize = 1024;
+// The distance between the ears of the listener.
+const EAR_DISTANCE: f32 = 0.2;
+
+/// An error raised by the audio module.
+#[derive(Debug)]
+pub enum AudioError {
+    /// The sound file could not be read.
+    Io(io::Error),
+    /// The sound could not be decoded.
+    Decoder(rodio::decoder::DecoderError),
+    /// No audio output device is available.
+    Stream(rodio::StreamError),
+    /// The sound could not be played on the output device.
+    Play(rodio::PlayError),
+}
+
+impl fmt::Display for AudioError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            AudioError::Io(e) => write!(f, "unable to read the sound: {}", e),
+            AudioError::Decoder(e) => write!(f, "unable to decode the sound: {}", e),
+            AudioError::Stream(e) => write!(f, "unable to open the audio output: {}", e),
+            AudioError::Play(e) => write!(f, "unable to play the sound: {}", e),
+        }
+    }
+}
+
+impl std::error::Error for AudioError {}
+
+/// A decoded sound, which can be played any number of times.
+///
+/// WAV and Ogg Vorbis files are supported.
+#[derive(Clone)]
+pub struct Sound {
+    source: Buffered<SamplesConverter<Decoder<Cursor<Vec<u8>>>, f32>>,
+}
+
+impl Sound {
+    /// Loads a sound from a file.
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Sound, AudioError> {
+        let bytes = asset_source::read(path.as_ref()).map_err(AudioError::Io)?;
+        Sound::from_bytes(bytes)
+    }
+
+    /// Loads a sound from the content of a file.
+    pub fn from_bytes(bytes: Vec<u8>) -> Result<Sound, AudioError> {
+        let decoder = Decoder::new(Cursor::new(bytes)).map_err(AudioError::Decoder)?;
+
+        Ok(Sound {
+            source: decoder.convert_samples().buffered(),
+        })
+    }
+
+    /// The duration of the sound, if known.
+    pub fn duration(&self) -> Option<Duration> {
+        self.source.total_duration()
+    }
+}
+
+/// A sound being played by an `AudioPlayer`.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub struct SoundHandle(u64);
+
+enum Emitter {
+    // Played at the position of the listener.
+    Listener,
+    Point(Point3<f32>),
+    Node(SceneNode),
+}
+
+struct Playing {
+    sink: SpatialSink,
+    emitter: Emitter,
+}
+
+// The latest samples played, mixed to mono.
+struct Tap {
+    samples: VecDeque<f32>,
+    sample_rate: u32,
+}
+
+/// Plays sounds positioned in the scene, heard from the camera.
+///
+/// Call `update` once per frame with the camera used for rendering, to move the listener and the
+/// sounds attached to scene nodes, and to compute the spectrum of the sounds played during the
+/// frame.
+pub struct AudioPlayer {
+    // Must be kept alive for the sounds to be played.
+    _stream: OutputStream,
+    handle: OutputStreamHandle,
+    playing: HashMap<u64, Playing>,
+    next_id: u64,
+    taps: Vec<Arc<Mutex<Tap>>>,
+    left_ear: Point3<f32>,
+    right_ear: Point3<f32>,
+    spectrum: Vec<f32>,
+    sample_rate: u32,
+}
+
+impl AudioPlayer {
+    /// Opens the default audio output device.
+    pub fn new() -> Result<AudioPlayer, AudioError> {
+        let (stream, handle) = OutputStream::try_default().map_err(AudioError::Stream)?;
+
+        Ok(AudioPlayer {
+            _stream: stream,
+            handle,
+            playing: HashMap::new(),
+            next_id: 0,
+            taps: Vec::new(),
+            left_ear: Point3::new(-EAR_DISTANCE / 2.0, 0.0, 0.0),
+            right_ear: Point3::new(EAR_DISTANCE / 2.0, 0.0, 0.0),
+            spectrum: vec![0.0; FFT_SIZE / 2],
+            sample_rate: 44100,
+        })
+    }
+
+    /// Plays a sound at the position of the listener, i.e., without spatialization.
+    pub fn play(&mut self, sound: &Sound, repeat: bool) -> Result<SoundHandle, AudioError> {
+        self.start(sound, Emitter::Listener, repeat)
+    }
+
+    /// Plays a sound emitted at a fixed position of the scene.
+    pub fn play_at(
+        &mut self,
+        sound: &Sound,
+        position: Point3<f32>,
+        repeat: bool,
+    ) -> Result<SoundHandle, AudioError> {
+        self.start(sound, Emitter::Point(position), repeat)
+    }
+
+    /// Plays a sound emitted by a scene node, following its world position.
+    pub fn play_on_node(
+        &mut self,
+        sound: &Sound,
+        node: &SceneNode,
+        repeat: bool,
+    ) -> Result<SoundHandle, AudioError> {
+        self.start(sound, Emitter::Node(node.clone()), repeat)
+    }
+
+    fn start(
+        &mut self,
+        sound: &Sound,
+        emitter: Emitter,
+        repeat: bool,
+    ) -> Result<SoundHandle, AudioError> {
+        let sink = SpatialSink::try_new(
+            &self.handle,
+            self.emitter_position(&emitter),
+            self.left_ear.coords.into(),
+            self.right_ear.coords.into(),
+        )
+        .map_err(AudioError::Play)?;
+
+        let tap = Arc::new(Mutex::new(Tap {
+            samples: VecDeque::with_capacity(FFT_SIZE),
+            sample_rate: sound.source.sample_rate(),
+        }));
+        self.taps.push(tap.clone());
+
+        let source = Tapped::new(sound.source.clone(), tap);
+
+        if repeat {
+            sink.append(source.repeat_infinite());
+        } else {
+            sink.append(source);
+        }
+
+        let id = self.next_id;
+        self.next_id += 1;
+        let _ = self.playing.insert(id, Playing { sink, emitter });
+
+        Ok(SoundHandle(id))
+    }
+
+    /// Stops a sound.
+    pub fn stop(&mut self, sound: SoundHandle) {
+        if let Some(playing) = self.playing.remove(&sound.0) {
+            playing.sink.stop();
+        }
+    }
+
+    /// Stops all the sounds.
+    pub fn stop_all(&mut self) {
+        for (_, playing) in self.playing.drain() {
+            playing.sink.stop();
+        }
+    }
+
+    /// Whether a sound is still playing.
+    pub fn is_playing(&self, sound: SoundHandle) -> bool {
+        self.playing.contains_key(&sound.0)
+    }
+
+    /// Sets the volume of a sound, `1.0` being its original volume.
+    pub fn set_volume(&self, sound: SoundHandle, volume: f32) {
+        if let Some(playing) = self.playing.get(&sound.0) {
+            playing.sink.set_volume(volume);
+        }
+    }
+
+    /// Pauses or resumes a sound.
+    pub fn set_paused(&self, sound: SoundHandle, paused: bool) {
+        if let Some(playing) = self.playing.get(&sound.0) {
+            if paused {
+                playing.sink.pause()
+            } else {
+                playing.sink.play()
+            }
+        }
+    }
+
+    /// Moves a sound played at a fixed position.
+    pub fn set_position(&mut self, sound: SoundHandle, position: Point3<f32>) {
+        if let Some(playing) = self.playing.get_mut(&sound.0) {
+            playing.emitter = Emitter::Point(position);
+            playing.sink.set_emitter_position(position.coords.into());
+        }
+    }
+
+    /// Moves the listener to the camera, updates the positions of the sounds attached to scene
+    /// nodes, and computes the spectrum of the sounds played since the last call.
+    ///
+    /// Call this once per frame.
+    pub fn update(&mut self, camera: &dyn Camera) {
+        let pose = camera.view_transform().inverse();
+        self.left_ear = pose * Point3::new(-EAR_DISTANCE / 2.0, 0.0, 0.0);
+        self.right_ear = pose * Point3::new(EAR_DISTANCE / 2.0, 0.0, 0.0);
+
+        self.playing.retain(|_, playing| !playing.sink.empty());
+
+        for playing in self.playing.values() {
+            playing
+                .sink
+                .set_emitter_position(self.emitter_position(&playing.emitter));
+            playing
+                .sink
+                .set_left_ear_position(self.left_ear.coords.into());
+            playing
+                .sink
+                .set_right_ear_position(self.right_ear.coords.into());
+        }
+
+        self.update_spectrum();
+    }
+
+    fn emitter_position(&self, emitter: &Emitter) -> [f32; 3] {
+        let position = match emitter {
+            Emitter::Listener => na::center(&self.left_ear, &self.right_ear),
+            Emitter::Point(position) => *position,
+            Emitter::Node(node) => Point3::from(node.world_transformation().translation.vector),
+        };
+
+        position.coords.into()
+    }
+
+    /// The magnitude of the frequencies of the latest 1024 samples played, as computed by the
+    /// last call to `update`.
+    ///
+    /// The bin `i` covers the frequencies around `i * sample_rate / 1024` Hz, up to half the
+    /// sample rate. See `bin_frequency`.
+    pub fn spectrum(&self) -> &[f32] {
+        &self.spectrum
+    }
+
+    /// The frequency, in Hz, of the `i`-th bin of the spectrum.
+    pub fn bin_frequency(&self, i: usize) -> f32 {
+        i as f32 * self.sample_rate as f32 / FFT_SIZE as f32
+    }
+
+    fn update_spectrum(&mut self) {
+        // Forget the sounds that finished playing.
+        self.taps.retain(|tap| Arc::strong_count(tap) > 1);
+
+        let mut re = vec![0.0; FFT_SIZE];
+        let mut im = vec![0.0; FFT_SIZE];
+
+        for (i, tap) in self.taps.iter().enumerate() {
+            let tap = tap.lock().unwrap();
+
+            if i == 0 {
+                self.sample_rate = tap.sample_rate;
+            }
+
+            // Mix the latest samples of all the sounds.
+            let offset = FFT_SIZE - tap.samples.len();
+
+            for (j, sample) in tap.samples.iter().enumerate() {
+                re[offset + j] += *sample;
+            }
+        }
+
+        // Hann window.
+        for (i, e) in re.iter_mut().enumerate() {
+            *e *= 0.5 - 0.5 * (2.0 * PI * i as f32 / (FFT_SIZE - 1) as f32).cos();
+        }
+
+        fft(&mut re, &mut im);
+
+        for (i, magnitude) in self.spectrum.iter_mut().enumerate() {
+            *magnitude = (re[i] * re[i] + im[i] * im[i]).sqrt() * 2.0 / FFT_SIZE as f32;
+        }
+    }
+}
+
+impl Drop for AudioPlayer {
+    fn drop(&mut self) {
+        self.stop_all()
+    }
+}
+
+// A source recording the samples it produces, mixed to mono.
+struct Tapped<S> {
+    source: S,
+    tap: Arc<Mutex<Tap>>,
+    // The samples of the current frame, i.e., one sample per channel.
+    frame: Vec<f32>,
+}
+
+impl<S: Source<Item = f32>> Tapped<S> {
+    fn new(source: S, tap: Arc<Mutex<Tap>>) -> Tapped<S> {
+        Tapped {
+            source,
+            tap,
+            frame: Vec::new(),
+        }
+    }
+}
+
+impl<S: Source<Item = f32>> Iterator for Tapped<S> {
+    type Item = f32;
+
+    fn next(&mut self) -> Option<f32> {
+        let sample = self.source.next()?;
+        self.frame.push(sample);
+
+        let channels = self.source.channels().max(1) as usize;
+
+        if self.frame.len() >= channels {
+            let mono = self.frame.iter().sum::<f32>() / self.frame.len() as f32;
+            self.frame.clear();
+
+            let mut tap = self.tap.lock().unwrap();
+
+            if tap.samples.len() == FFT_SIZE {
+                let _ = tap.samples.pop_front();
+            }
+
+            tap.samples.push_back(mono);
+        }
+
+        Some(sample)
+    }
+}
+
+impl<S: Source<Item = f32>> Source for Tapped<S> {
+    fn current_frame_len(&self) -> Option<usize> {
+        self.source.current_frame_len()
+    }
+
+    fn channels(&self) -> u16 {
+        self.source.channels()
+    }
+
+    fn sample_rate(&self) -> u32 {
+        self.source.sample_rate()
+    }
+
+    fn total_duration(&self) -> Option<Duration> {
+        self.source.total_duration()
+    }
+}
+
+// In-place iterative radix-2 FFT. The length of the inputs must be a power of two.
+fn fft(re: &mut [f32], im: &mut [f32]) {
+    let n = re.len();
+    let bits = n.trailing_zeros();
+
+    for i in 0..n {
+        let j = i.reverse_bits() >> (usize::BITS - bits);
+
+        if i < j {
+            re.swap(i, j);
+            im.swap(i, j);
+        }
+    }
+
+    let mut len = 2;
+
+    while len <= n {
+        let angle = -2.0 * PI / len as f32;
+
+        for start in (0..n).step_by(len) {
+            for k in 0..len / 2 {
+                let (sin, cos) = (angle * k as f32).sin_cos();
+                let (a, b) = (start + k, start + k + len / 2);
+                let tre = re[b] * cos - im[b] * sin;
+                let tim = re[b] * sin + im[b] * cos;
+                re[b] = re[a] - tre;
+                im[b] = im[a] - tim;
+                re[a] += tre;
+                im[a] += tim;
+            }
+        }
+
+        len *= 2;
+    }
+}
@@ -0,0 +1,303 @@
+use crate::camera::Camera;
+use crate::context::Context;
+use crate::light::Light;
+use crate::resource::Material;
+use crate::resource::{
+    AllocationType, BufferType, Effect, GPUVec, Mesh, ShaderAttribute, ShaderUniform,
+};
+use crate::scene::{DebugVisualization, ObjectData};
+use na::{Isometry3, Matrix3, Matrix4, Point2, Point3, Vector3};
+
+#[path = "../error.rs"]
+mod error;
+
+// The values of the `mode` uniform of the shaders.
+const MODE_NORMALS: i32 = 0;
+const MODE_UV_CHECKER: i32 = 1;
+const MODE_TANGENTS: i32 = 2;
+const MODE_GLYPHS: i32 = 3;
+
+/// A material that draws the normals, texture coordinates, or tangents of an object, as selected
+/// by its `DebugVisualization`.
+pub struct DebugMaterial {
+    shader: Effect,
+    position: ShaderAttribute<Point3<f32>>,
+    vector: ShaderAttribute<Vector3<f32>>,
+    tex_coord: ShaderAttribute<Point2<f32>>,
+    mode: ShaderUniform<i32>,
+    proj: ShaderUniform<Matrix4<f32>>,
+    view: ShaderUniform<Matrix4<f32>>,
+    transform: ShaderUniform<Matrix4<f32>>,
+    ntransform: ShaderUniform<Matrix3<f32>>,
+    scale: ShaderUniform<Matrix3<f32>>,
+    // Recomputed each frame for the objects drawn with their tangents.
+    tangents: GPUVec<Vector3<f32>>,
+    // The world-space end points of the normal glyphs, with the normal each one is drawn for.
+    glyphs: GPUVec<Point3<f32>>,
+    glyph_normals: GPUVec<Vector3<f32>>,
+}
+
+impl DebugMaterial {
+    /// Creates a new DebugMaterial.
+    pub fn new() -> DebugMaterial {
+        let mut shader = Effect::new_from_str(DEBUG_VERTEX_SRC, DEBUG_FRAGMENT_SRC).unwrap();
+
+        shader.use_program();
+
+        DebugMaterial {
+            position: shader.get_attrib("position").unwrap(),
+            vector: shader.get_attrib("vector").unwrap(),
+            tex_coord: shader.get_attrib("tex_coord").unwrap(),
+            mode: shader.get_uniform("mode").unwrap(),
+            transform: shader.get_uniform("transform").unwrap(),
+            ntransform: shader.get_uniform("ntransform").unwrap(),
+            scale: shader.get_uniform("scale").unwrap(),
+            view: shader.get_uniform("view").unwrap(),
+            proj: shader.get_uniform("proj").unwrap(),
+            shader,
+            tangents: GPUVec::new(Vec::new(), BufferType::Array, AllocationType::StreamDraw),
+            glyphs: GPUVec::new(Vec::new(), BufferType::Array, AllocationType::StreamDraw),
+            glyph_normals: GPUVec::new(Vec::new(), BufferType::Array, AllocationType::StreamDraw),
+        }
+    }
+
+    // Computes the tangents of the mesh. Returns `false` if its data is not available on RAM.
+    fn update_tangents(&mut self, mesh: &Mesh) -> bool {
+        let coords = mesh.coords().read().unwrap();
+        let normals = mesh.normals().read().unwrap();
+        let uvs = mesh.uvs().read().unwrap();
+        let faces = mesh.faces().read().unwrap();
+
+        match (coords.data(), normals.data(), uvs.data(), faces.data()) {
+            (Some(c), Some(n), Some(u), Some(f)) => {
+                *self.tangents.data_mut() = Some(Mesh::compute_tangents_array(c, n, u, f));
+                true
+            }
+            _ => false,
+        }
+    }
+
+    // Computes the world-space glyphs of the normals of the mesh. Returns `false` if its data is
+    // not available on RAM.
+    fn update_glyphs(
+        &mut self,
+        mesh: &Mesh,
+        transform: &Isometry3<f32>,
+        scale: &Vector3<f32>,
+        length: f32,
+    ) -> bool {
+        let coords = mesh.coords().read().unwrap();
+        let normals = mesh.normals().read().unwrap();
+
+        let (coords, normals) = match (coords.data(), normals.data()) {
+            (Some(c), Some(n)) => (c, n),
+            _ => return false,
+        };
+
+        let mut glyphs = Vec::with_capacity(coords.len() * 2);
+        let mut glyph_normals = Vec::with_capacity(coords.len() * 2);
+
+        for (pt, normal) in coords.iter().zip(normals.iter()) {
+            // The normals are transformed by the inverse transpose of the scaling.
+            let start = transform * Point3::from(pt.coords.component_mul(scale));
+            let normal = (transform.rotation * normal.component_div(scale))
+                .try_normalize(1.0e-12)
+                .unwrap_or_else(Vector3::zeros);
+
+            glyphs.push(start);
+            glyphs.push(start + normal * length);
+            glyph_normals.push(normal);
+            glyph_normals.push(normal);
+        }
+
+        *self.glyphs.data_mut() = Some(glyphs);
+        *self.glyph_normals.data_mut() = Some(glyph_normals);
+        true
+    }
+
+    fn render_glyphs(
+        &mut self,
+        transform: &Isometry3<f32>,
+        scale: &Vector3<f32>,
+        length: f32,
+        mesh: &mut Mesh,
+    ) {
+        if !self.update_glyphs(mesh, transform, scale, length) {
+            return;
+        }
+
+        let ctxt = Context::get();
+
+        // The glyphs are already in world-space.
+        self.transform.upload(&Matrix4::identity());
+        self.ntransform.upload(&Matrix3::identity());
+        self.scale.upload(&Matrix3::identity());
+        self.mode.upload(&MODE_GLYPHS);
+
+        self.position.bind(&mut self.glyphs);
+        self.vector.bind(&mut self.glyph_normals);
+
+        verify!(ctxt.draw_arrays(Context::LINES, 0, self.glyphs.len() as i32));
+
+        self.glyphs.unbind();
+        self.glyph_normals.unbind();
+    }
+}
+
+impl Default for DebugMaterial {
+    fn default() -> DebugMaterial {
+        DebugMaterial::new()
+    }
+}
+
+impl Material for DebugMaterial {
+    fn render(
+        &mut self,
+        pass: usize,
+        transform: &Isometry3<f32>,
+        scale: &Vector3<f32>,
+        camera: &mut dyn Camera,
+        _: &Light,
+        data: &ObjectData,
+        mesh: &mut Mesh,
+    ) {
+        let mode = match data.debug_visualization() {
+            DebugVisualization::None => return,
+            DebugVisualization::Normals => MODE_NORMALS,
+            DebugVisualization::UvChecker => MODE_UV_CHECKER,
+            DebugVisualization::Tangents => MODE_TANGENTS,
+            DebugVisualization::NormalGlyphs(_) => MODE_GLYPHS,
+        };
+
+        if mode != MODE_GLYPHS && !data.surface_rendering_active() {
+            return;
+        }
+
+        if mode == MODE_TANGENTS && !self.update_tangents(mesh) {
+            return;
+        }
+
+        let ctxt = Context::get();
+
+        self.shader.use_program();
+        self.position.enable();
+        self.vector.enable();
+
+        /*
+         *
+         * Setup camera.
+         *
+         */
+        camera.upload(pass, &mut self.proj, &mut self.view);
+
+        if let DebugVisualization::NormalGlyphs(length) = data.debug_visualization() {
+            self.render_glyphs(transform, scale, length, mesh);
+            self.position.disable();
+            self.vector.disable();
+            return;
+        }
+
+        // enable/disable culling.
+        if data.backface_culling_enabled() {
+            verify!(ctxt.enable(Context::CULL_FACE));
+        } else {
+            verify!(ctxt.disable(Context::CULL_FACE));
+        }
+
+        /*
+         *
+         * Setup object-related stuffs.
+         *
+         */
+        let formated_transform = transform.to_homogeneous();
+        let formated_ntransform = transform.rotation.to_rotation_matrix().into_inner();
+        let formated_scale = Matrix3::from_diagonal(&Vector3::new(scale.x, scale.y, scale.z));
+
+        self.transform.upload(&formated_transform);
+        self.ntransform.upload(&formated_ntransform);
+        self.scale.upload(&formated_scale);
+        self.mode.upload(&mode);
+
+        mesh.bind_coords(&mut self.position);
+
+        if mode == MODE_TANGENTS {
+            self.vector.bind(&mut self.tangents);
+        } else {
+            mesh.bind_normals(&mut self.vector);
+        }
+
+        if mode == MODE_UV_CHECKER {
+            self.tex_coord.enable();
+            mesh.bind_uvs(&mut self.tex_coord);
+        }
+
+        mesh.bind_faces();
+
+        let _ = verify!(ctxt.polygon_mode(Context::FRONT_AND_BACK, Context::FILL));
+        verify!(ctxt.draw_elements(
+            Context::TRIANGLES,
+            mesh.num_pts() as i32,
+            Context::UNSIGNED_SHORT,
+            0
+        ));
+
+        mesh.unbind();
+        self.tangents.unbind();
+
+        self.position.disable();
+        self.vector.disable();
+        self.tex_coord.disable();
+    }
+}
+
+/// A vertex shader for coloring an object depending on its normals, texture coordinates, or
+/// tangents.
+pub static DEBUG_VERTEX_SRC: &str = A_VERY_LONG_STRING;
+
+/// A fragment shader for coloring an object depending on its normals, texture coordinates, or
+/// tangents.
+pub static DEBUG_FRAGMENT_SRC: &str = ANOTHER_VERY_LONG_STRING;
+
+const A_VERY_LONG_STRING: &str = "#version 100
+attribute vec3 position;
+attribute vec3 vector;
+attribute vec2 tex_coord;
+uniform mat4 proj;
+uniform mat4 view;
+uniform mat4 transform;
+uniform mat3 ntransform;
+uniform mat3 scale;
+varying vec3 ws_vector;
+varying vec2 uv;
+
+void main() {
+    ws_vector   = ntransform * vector;
+    uv          = tex_coord;
+    gl_Position = proj * view * transform * vec4(scale * position, 1.0);
+}
+";
+
+const ANOTHER_VERY_LONG_STRING: &str = "#version 100
+#ifdef GL_FRAGMENT_PRECISION_HIGH
+   precision highp float;
+#else
+   precision mediump float;
+#endif
+
+uniform int mode;
+varying vec3 ws_vector;
+varying vec2 uv;
+
+void main() {
+    if (mode == 1) {
+        // Eight squares per unit of texture coordinates.
+        vec2 cell = floor(uv * 8.0);
+        float checker = mod(cell.x + cell.y, 2.0);
+        vec3 tint = vec3(fract(uv), 1.0);
+        gl_FragColor = vec4(mix(0.35, 0.9, checker) * tint, 1.0);
+    } else {
+        vec3 v = length(ws_vector) > 0.0 ? normalize(ws_vector) : ws_vector;
+        gl_FragColor = vec4((v + 1.0) / 2.0, 1.0);
+    }
+}
+";
@@ -1,35 +1,321 @@
 #version 100
+#if defined(FLAT_SHADING) && defined(GL_ES) && __VERSION__ < 300
+#extension GL_OES_standard_derivatives : enable
+#endif
 #ifdef GL_FRAGMENT_PRECISION_HIGH
    precision highp float;
 #else
    precision mediump float;
 #endif
 
+#include "kiss3d/lighting"
+#include "kiss3d/noise"
+#include "kiss3d/shadow"
+
 varying vec3 local_light_position;
+#ifdef HAS_TEXTURE
 varying vec2 tex_coord_v;
+#endif
+#ifdef HAS_VERTEX_COLORS
+varying vec3 vertex_color_v;
+#endif
+#ifdef HAS_LIGHTMAP
+varying vec2 lightmap_uv_v;
+uniform sampler2D lightmap;
+#endif
 varying vec3 normalInterp;
 varying vec3 vertPos;
+#ifdef RECEIVE_SHADOWS
+varying vec4 shadow_coord;
+uniform sampler2D shadow_map;
+uniform float shadow_texel_size;
+#ifdef SHADOW_CASCADES
+// Must be equal to `MAX_SHADOW_CASCADES`.
+#define MAX_SHADOW_CASCADES 4
+// The cascades of the shadow map, rendered in the tiles of a 2x2 atlas, with the view-space
+// distances where they end. See `ActiveShadowMap`.
+uniform int num_cascades;
+uniform mat4 cascade_view_proj[MAX_SHADOW_CASCADES];
+uniform vec4 cascade_splits;
+uniform int shadow_cascade_debug;
+#endif
+#ifdef SOFT_SHADOWS
+// See `ActiveShadowMap::pcss`.
+uniform vec4 shadow_pcss;
+#endif
+
+// The fraction of the main light reaching the fragment, from its clip-space coordinates seen
+// from the light, in the tile of the shadow map starting at `tile` of width `tile_size`.
+//
+// With soft shadows and cascades, `penumbra` is the width of the penumbra per unit of depth
+// between the casters and the fragment.
+float shadow_factor(vec4 coord, vec2 tile, float tile_size, float penumbra) {
+#ifdef SOFT_SHADOWS
+  // The points behind the light, or outside of the map, are lit.
+  if (coord.w <= 0.0) {
+    return 1.0;
+  }
+
+  vec3 p = coord.xyz / coord.w * 0.5 + 0.5;
+
+  if (p.x < 0.0 || p.x > 1.0 || p.y < 0.0 || p.y > 1.0) {
+    return 1.0;
+  }
+
+  float depth = min(p.z, 1.0) - 0.0005;
+  float rotation = kiss3d_interleaved_gradient_noise(gl_FragCoord.xy) * 6.2831853;
+  float max_radius = shadow_texel_size * 32.0;
+#ifdef SHADOW_CASCADES
+  // The light is infinitely far away, so the penumbra widens linearly with the depth between
+  // the casters and the fragment.
+  float search = clamp(depth * penumbra, shadow_texel_size, max_radius);
+  vec2 blockers = kiss3d_shadow_blockers(shadow_map, p.xy, tile, tile_size, depth, search,
+                                         rotation);
+  float width = (depth - blockers.x) * penumbra;
+#else
+  // The casters hiding the light from the fragment are within the cone from the fragment to the
+  // light, and the penumbra widens with the ratio of their distances to the light.
+  float znear = shadow_pcss.x;
+  float light_size = shadow_pcss.z;
+  float dist = kiss3d_linear_depth(depth, znear, shadow_pcss.y);
+  float search = clamp(light_size * (dist - znear) / (dist * znear), shadow_texel_size,
+                       max_radius);
+  vec2 blockers = kiss3d_shadow_blockers(shadow_map, p.xy, tile, tile_size, depth, search,
+                                         rotation);
+  float blocker_dist = kiss3d_linear_depth(blockers.x, znear, shadow_pcss.y);
+  float width = light_size * (dist - blocker_dist) / (blocker_dist * dist);
+#endif
+
+  if (blockers.y == 0.0) {
+    return 1.0;
+  }
+
+  float radius = clamp(width * 0.5, shadow_texel_size, max_radius);
+  return kiss3d_shadow_disk(shadow_map, p.xy, tile, tile_size, depth, radius, rotation);
+#else
+  return kiss3d_shadow_tile(shadow_map, coord, tile, tile_size, shadow_texel_size, 0.0005);
+#endif
+}
+
+#ifdef CONTACT_SHADOWS
+// The number of steps of the rays of the contact shadows.
+#define CONTACT_SHADOW_STEPS 16
+// The distances of the shadow casters to the camera divided by the far distance, seen with
+// `contact_proj`, and the length of the rays, the thickness of the surfaces, and the far
+// distance. See `ActiveContactShadows`.
+uniform sampler2D contact_depth;
+uniform mat4 contact_proj;
+uniform vec3 contact_shadows;
+
+// The fraction of the main light reaching the view-space position `pos`, from a short ray
+// marched toward the light along `light_dir` until it passes behind a surface seen by the camera.
+// The shadow fades with the distance between the fragment and that surface.
+float contact_shadow_factor(vec3 pos, vec3 light_dir) {
+  // Jittering the steps trades their banding for noise.
+  float offset = kiss3d_interleaved_gradient_noise(gl_FragCoord.xy);
+  // The fragment does not shadow itself, within the precision of the distances.
+  float bias = -pos.z * 0.002;
+
+  for (int i = 0; i < CONTACT_SHADOW_STEPS; i++) {
+    float t = (float(i) + offset) / float(CONTACT_SHADOW_STEPS);
+    vec3 p = pos + light_dir * t * contact_shadows.x;
+    vec4 clip = contact_proj * vec4(p, 1.0);
+
+    // The rays leaving the view are lit.
+    if (clip.w <= 0.0) {
+      break;
+    }
+
+    vec2 uv = clip.xy / clip.w * 0.5 + 0.5;
+
+    if (uv.x < 0.0 || uv.x > 1.0 || uv.y < 0.0 || uv.y > 1.0) {
+      break;
+    }
+
+    float surface = kiss3d_unpack_depth(texture2D(contact_depth, uv)) * contact_shadows.z;
+    float behind = -p.z - surface;
+
+    if (behind > bias && behind < contact_shadows.y) {
+      return t;
+    }
+  }
+
+  return 1.0;
+}
+#endif
+#endif
 
 uniform vec3 color;
+#ifdef HAS_TEXTURE
 uniform sampler2D tex;
+#endif
+uniform float opacity;
 const vec3 specColor = vec3(0.4, 0.4, 0.4);
 
+#ifdef LIGHTS
+// Must be equal to `light::MAX_LIGHTS`.
+#define MAX_LIGHTS 8
+// The lights added to the window, in view space. See `LightUniforms`.
+uniform int num_lights;
+uniform vec4 lights_position[MAX_LIGHTS];
+uniform vec3 lights_direction[MAX_LIGHTS];
+uniform vec2 lights_cone[MAX_LIGHTS];
+uniform vec3 lights_color[MAX_LIGHTS];
+uniform vec3 lights_attenuation[MAX_LIGHTS];
+#ifdef RECT_LIGHTS
+// The half-extents of the rectangular lights, zero for the other lights.
+uniform vec3 lights_half_width[MAX_LIGHTS];
+uniform vec3 lights_half_height[MAX_LIGHTS];
+#endif
+#endif
+
 void main() {
+#ifdef DITHER
+  // Screen-door transparency, with an interleaved gradient noise threshold.
+  if (opacity <= kiss3d_interleaved_gradient_noise(gl_FragCoord.xy)) {
+    discard;
+  }
+#endif
+
+#ifdef FLAT_SHADING
+  // The normal of the face, from the derivatives of the view-space position.
+  vec3 normal = normalize(cross(dFdx(vertPos), dFdy(vertPos)));
+#else
   vec3 normal = normalize(normalInterp);
+#endif
+
+#ifdef HAS_VERTEX_COLORS
+  vec3 base_color = color * vertex_color_v;
+#else
+  vec3 base_color = color;
+#endif
+
+#ifdef HAS_TEXTURE
+  vec4 tex_color = texture2D(tex, tex_coord_v);
+#else
+  vec4 tex_color = vec4(1.0);
+#endif
+
+#ifdef XRAY
+  // A ghost whose faces are fainter when seen head-on, highlighting its silhouette.
+  float fresnel = kiss3d_fresnel(normal, normalize(-vertPos), 2.0);
+  vec3 ghost = mix(base_color, vec3(1.0), fresnel * 0.5);
+  gl_FragColor = vec4(tex_color.rgb * ghost, mix(0.1, 0.8, fresnel) * opacity);
+#else
   vec3 lightDir = normalize(local_light_position - vertPos);
+  vec2 lighting = kiss3d_blinn_phong(normal, lightDir, normalize(-vertPos), 30.0);
+  float lambertian = lighting.x;
+  float specular = lighting.y;
 
-  float lambertian = max(dot(lightDir, normal), 0.0);
-  float specular = 0.0;
+#ifdef RECEIVE_SHADOWS
+#ifdef SHADOW_CASCADES
+  // The fragments beyond the last cascade are lit.
+  float lit = 1.0;
+  vec4 cascade_tint = vec4(0.0);
 
-  if(lambertian > 0.0) {
-    vec3 viewDir = normalize(-vertPos);
-    vec3 halfDir = normalize(lightDir + viewDir);
-    float specAngle = max(dot(halfDir, normal), 0.0);
-    specular = pow(specAngle, 30.0);
+  for (int i = 0; i < MAX_SHADOW_CASCADES; i++) {
+    if (i >= num_cascades) {
+      break;
+    }
+
+    if (-vertPos.z <= cascade_splits[i]) {
+      vec2 tile = vec2(mod(float(i), 2.0), floor(float(i) / 2.0)) * 0.5;
+#ifdef SOFT_SHADOWS
+      float penumbra = shadow_pcss[i];
+#else
+      float penumbra = 0.0;
+#endif
+      lit = shadow_factor(cascade_view_proj[i] * shadow_coord, tile, 0.5, penumbra);
+      cascade_tint = vec4(i == 0 ? vec3(1.0, 0.0, 0.0) :
+                          i == 1 ? vec3(0.0, 1.0, 0.0) :
+                          i == 2 ? vec3(0.0, 0.0, 1.0) : vec3(1.0, 1.0, 0.0), 0.4);
+      break;
+    }
+  }
+#else
+  float lit = shadow_factor(shadow_coord, vec2(0.0), 1.0, 0.0);
+#endif
+#ifdef CONTACT_SHADOWS
+  if (lambertian > 0.0) {
+    lit = min(lit, contact_shadow_factor(vertPos, lightDir));
   }
+#endif
+  lambertian *= lit;
+  specular *= lit;
+#endif
 
-  vec4 tex_color = texture2D(tex, tex_coord_v);
-  gl_FragColor = tex_color * vec4(color / 3.0 +
-                                  lambertian * color / 3.0 +
-                                  specular * specColor / 3.0, 1.0);
+#ifdef DITHER
+  float alpha = 1.0;
+#else
+  float alpha = opacity;
+#endif
+
+#if defined(HAS_LIGHTMAP) && defined(LIGHTMAP_LIGHTING)
+  // The baked lighting replaces the main light.
+  vec3 lit_color = base_color * texture2D(lightmap, lightmap_uv_v).rgb;
+#else
+  vec3 lit_color = base_color / 3.0 +
+                   lambertian * base_color / 3.0 +
+                   specular * specColor / 3.0;
+#endif
+
+#ifdef LIGHTS
+  for (int i = 0; i < MAX_LIGHTS; i++) {
+    if (i >= num_lights) {
+      break;
+    }
+
+    vec4 light_pos = lights_position[i];
+
+#ifdef RECT_LIGHTS
+    vec3 half_width = lights_half_width[i];
+    vec3 half_height = lights_half_height[i];
+
+    if (dot(half_width, half_width) > 0.0) {
+      float rect_dist = length(light_pos.xyz - vertPos);
+      vec3 rect_att = lights_attenuation[i];
+      float rect_attenuation = 1.0 /
+          (rect_att.x + rect_att.y * rect_dist + rect_att.z * rect_dist * rect_dist);
+      float rect_diffuse = kiss3d_rect_form_factor(vertPos, normal, light_pos.xyz,
+                                                   half_width, half_height);
+      // The specular highlight is lit by a single point of the rectangle, only on its front.
+      vec3 view_dir = normalize(-vertPos);
+      vec3 rect_point = kiss3d_rect_representative_point(vertPos, reflect(-view_dir, normal),
+                                                         light_pos.xyz, half_width, half_height);
+      float front = rect_diffuse > 0.0 ? 1.0 : 0.0;
+      float rect_specular =
+          kiss3d_blinn_phong(normal, normalize(rect_point - vertPos), view_dir, 30.0).y;
+      lit_color += (rect_diffuse * base_color + front * rect_specular * specColor) / 3.0 *
+                   lights_color[i] * rect_attenuation;
+      continue;
+    }
+#endif
+
+    // The direction toward the light, which is the position itself for directional lights.
+    vec3 to_light = light_pos.xyz - vertPos * light_pos.w;
+    float dist = length(to_light);
+    vec3 light_dir = to_light / dist;
+    vec3 att = lights_attenuation[i];
+    float attenuation = light_pos.w == 0.0 ? 1.0 : 1.0 / (att.x + att.y * dist + att.z * dist * dist);
+    vec2 cone = lights_cone[i];
+    attenuation *= smoothstep(cone.y, cone.x, dot(-light_dir, lights_direction[i]));
+
+    vec2 light_lighting = kiss3d_blinn_phong(normal, light_dir, normalize(-vertPos), 30.0);
+    lit_color += (light_lighting.x * base_color + light_lighting.y * specColor) / 3.0 *
+                 lights_color[i] * attenuation;
+  }
+#endif
+
+#if defined(HAS_LIGHTMAP) && !defined(LIGHTMAP_LIGHTING)
+  lit_color *= texture2D(lightmap, lightmap_uv_v).r;
+#endif
+
+#if defined(RECEIVE_SHADOWS) && defined(SHADOW_CASCADES)
+  if (shadow_cascade_debug != 0) {
+    lit_color = mix(lit_color, cascade_tint.rgb, cascade_tint.a);
+  }
+#endif
+
+  gl_FragColor = tex_color * vec4(lit_color, alpha);
+#endif
 }
@@ -1,22 +1,83 @@
 #version 100
 attribute vec3 position;
-attribute vec2 tex_coord;
 attribute vec3 normal;
+#ifdef HAS_TEXTURE
+attribute vec2 tex_coord;
+#endif
+#ifdef HAS_VERTEX_COLORS
+attribute vec3 vertex_color;
+#endif
+#ifdef HAS_LIGHTMAP
+attribute vec2 lightmap_uv;
+#endif
+#ifdef SKINNING
+// Must be equal to `resource::MAX_JOINTS`.
+#define MAX_JOINTS 64
+
+// The indices of the four joints moving the vertex, and their weights.
+attribute vec4 joints;
+attribute vec4 weights;
+// The transformation of the vertices by each joint, from their bind pose to their current pose.
+uniform mat4 joint_matrices[MAX_JOINTS];
+#endif
+
+#include "kiss3d/frame"
 
 uniform mat3 ntransform, scale;
-uniform mat4 proj, view, transform;
-uniform vec3 light_position;
+uniform mat4 transform;
 
 varying vec3 local_light_position;
+#ifdef HAS_TEXTURE
 varying vec2 tex_coord_v;
+#endif
+#ifdef HAS_VERTEX_COLORS
+varying vec3 vertex_color_v;
+#endif
+#ifdef HAS_LIGHTMAP
+varying vec2 lightmap_uv_v;
+#endif
 varying vec3 normalInterp;
 varying vec3 vertPos;
+#ifdef RECEIVE_SHADOWS
+#ifndef SHADOW_CASCADES
+uniform mat4 light_view_proj;
+#endif
+// The clip-space position seen from the light or, with cascades, the world position projected
+// by the cascade of each fragment.
+varying vec4 shadow_coord;
+#endif
 
 void main(){
-    gl_Position = proj * view * transform * vec4(scale * position, 1.0);
-    vec4 vertPos4 = view * transform * vec4(scale * position, 1.0);
+#ifdef SKINNING
+    mat4 skin = weights.x * joint_matrices[int(joints.x)] +
+                weights.y * joint_matrices[int(joints.y)] +
+                weights.z * joint_matrices[int(joints.z)] +
+                weights.w * joint_matrices[int(joints.w)];
+    vec3 local_position = (skin * vec4(position, 1.0)).xyz;
+    vec3 local_normal = mat3(skin) * normal;
+#else
+    vec3 local_position = position;
+    vec3 local_normal = normal;
+#endif
+    gl_Position = proj * view * transform * vec4(scale * local_position, 1.0);
+    vec4 vertPos4 = view * transform * vec4(scale * local_position, 1.0);
     vertPos = vec3(vertPos4) / vertPos4.w;
-    normalInterp = mat3(view) * ntransform * normal;
+    normalInterp = mat3(view) * ntransform * local_normal;
+#ifdef HAS_TEXTURE
     tex_coord_v = tex_coord;
+#endif
+#ifdef HAS_VERTEX_COLORS
+    vertex_color_v = vertex_color;
+#endif
+#ifdef HAS_LIGHTMAP
+    lightmap_uv_v = lightmap_uv;
+#endif
+#ifdef RECEIVE_SHADOWS
+#ifdef SHADOW_CASCADES
+    shadow_coord = transform * vec4(scale * local_position, 1.0);
+#else
+    shadow_coord = light_view_proj * transform * vec4(scale * local_position, 1.0);
+#endif
+#endif
     local_light_position = (view * vec4(light_position, 1.0)).xyz;
 }
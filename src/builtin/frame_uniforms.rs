@@ -0,0 +1,70 @@
+//! The uniform buffer holding the camera and light data shared by the programs of a frame.
+
+use crate::camera::Camera;
+use crate::context::Context;
+use crate::resource::{AllocationType, BufferType, GPUVec};
+use na::{Matrix4, Point3};
+use std::cell::RefCell;
+
+/// The uniform buffer binding point of the `Kiss3dFrame` uniform block.
+pub const FRAME_UNIFORMS_BINDING: u32 = 0;
+
+/// The name of the uniform block declared by the `kiss3d/frame` shader snippet.
+pub const FRAME_UNIFORM_BLOCK: &str = "Kiss3dFrame";
+
+// The std140 layout of the block: two matrices and a vec3 padded to a vec4.
+const FRAME_UNIFORMS_LEN: usize = 16 + 16 + 4;
+
+thread_local!(static FRAME_UNIFORMS: RefCell<FrameUniforms> = RefCell::new(FrameUniforms::new()));
+
+struct FrameUniforms {
+    buffer: GPUVec<f32>,
+    // The context generation of the last binding.
+    generation: Option<u64>,
+}
+
+impl FrameUniforms {
+    fn new() -> FrameUniforms {
+        let buffer = GPUVec::new(
+            vec![0.0; FRAME_UNIFORMS_LEN],
+            BufferType::Uniform,
+            AllocationType::DynamicDraw,
+        );
+
+        FrameUniforms {
+            buffer,
+            generation: None,
+        }
+    }
+
+    fn update(&mut self, proj: &Matrix4<f32>, view: &Matrix4<f32>, light: &Point3<f32>) {
+        let mut values = [0.0; FRAME_UNIFORMS_LEN];
+        values[..16].copy_from_slice(proj.as_slice());
+        values[16..32].copy_from_slice(view.as_slice());
+        values[32..35].copy_from_slice(light.coords.as_slice());
+
+        let generation = Context::generation();
+        let changed = self.buffer.data().as_ref().map(|d| &d[..]) != Some(&values[..]);
+
+        // Most programs of a frame share the same values: only upload and bind them once.
+        if changed || self.generation != Some(generation) {
+            if changed {
+                *self.buffer.data_mut() = Some(values.to_vec());
+            }
+
+            self.buffer.bind_base(FRAME_UNIFORMS_BINDING);
+            self.generation = Some(generation);
+        }
+    }
+}
+
+/// Sets the values of the `Kiss3dFrame` uniform block to the matrices of the given pass of the
+/// camera and to the light position.
+///
+/// The uniform buffer is only uploaded again when these values change, which usually happens
+/// once per frame and pass. The context must support uniform buffers.
+pub fn update_frame_uniforms(camera: &dyn Camera, pass: usize, light: &Point3<f32>) {
+    let (proj, view) = camera.pass_matrices(pass);
+
+    FRAME_UNIFORMS.with(|f| f.borrow_mut().update(&proj, &view, light))
+}
@@ -0,0 +1,14 @@
+// The amount of fog, from 0 to 1, growing linearly between the `start` and `end` distances.
+float kiss3d_fog_linear(float dist, float start, float end) {
+  return clamp((dist - start) / (end - start), 0.0, 1.0);
+}
+
+// The amount of fog, from 0 to 1, growing exponentially with the squared distance.
+float kiss3d_fog_exp2(float dist, float density) {
+  float d = density * dist;
+  return 1.0 - clamp(exp(-d * d), 0.0, 1.0);
+}
+
+vec3 kiss3d_apply_fog(vec3 color, vec3 fog_color, float fog) {
+  return mix(color, fog_color, fog);
+}
@@ -0,0 +1,13 @@
+// The camera and light of the frame, shared by all the programs through a uniform buffer when
+// the context supports it.
+#ifdef UNIFORM_BUFFERS
+layout(std140) uniform Kiss3dFrame {
+  mat4 proj;
+  mat4 view;
+  vec3 light_position;
+};
+#else
+uniform mat4 proj;
+uniform mat4 view;
+uniform vec3 light_position;
+#endif
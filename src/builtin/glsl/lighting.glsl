@@ -0,0 +1,66 @@
+// The diffuse and specular factors of the Blinn-Phong model, from unit vectors in the same space.
+vec2 kiss3d_blinn_phong(vec3 normal, vec3 light_dir, vec3 view_dir, float shininess) {
+  float lambertian = max(dot(light_dir, normal), 0.0);
+  float specular = 0.0;
+
+  if (lambertian > 0.0) {
+    vec3 half_dir = normalize(light_dir + view_dir);
+    specular = pow(max(dot(half_dir, normal), 0.0), shininess);
+  }
+
+  return vec2(lambertian, specular);
+}
+
+// A fresnel-like factor, 0 for surfaces seen head-on and 1 for surfaces seen edge-on.
+float kiss3d_fresnel(vec3 normal, vec3 view_dir, float power) {
+  return pow(1.0 - abs(dot(normal, view_dir)), power);
+}
+
+// The integral of the clamped cosine over the arc between two unit directions, as a vector along
+// the normal of the arc, divided by 2π. From the fitted approximation of linearly transformed
+// cosines, which is accurate for the arcs almost half a circle long.
+vec3 kiss3d_edge_integral(vec3 a, vec3 b) {
+  float x = dot(a, b);
+  float y = abs(x);
+  float u = 0.8543985 + (0.4965155 + 0.0145206 * y) * y;
+  float v = 3.4175940 + (4.1616724 + y) * y;
+  float theta_sin_theta = x > 0.0 ? u / v : 0.5 * inversesqrt(max(1.0 - x * x, 1.0e-7)) - u / v;
+  return cross(a, b) * theta_sin_theta;
+}
+
+// The fraction of the light of a uniform rectangle reaching a point at `pos` with the unit
+// normal `normal`. The rectangle is centered at `center`, with the perpendicular half-extents
+// `right` and `up`, and emits light on the side of `cross(right, up)`. All the vectors are in
+// the same space.
+float kiss3d_rect_form_factor(vec3 pos, vec3 normal, vec3 center, vec3 right, vec3 up) {
+  // The back of the rectangle is dark.
+  if (dot(pos - center, cross(right, up)) <= 0.0) {
+    return 0.0;
+  }
+
+  vec3 a = normalize(center - right - up - pos);
+  vec3 b = normalize(center + right - up - pos);
+  vec3 c = normalize(center + right + up - pos);
+  vec3 d = normalize(center - right + up - pos);
+  vec3 sum = kiss3d_edge_integral(a, b) + kiss3d_edge_integral(b, c) +
+             kiss3d_edge_integral(c, d) + kiss3d_edge_integral(d, a);
+
+  return max(-dot(sum, normal), 0.0);
+}
+
+// The point of a rectangle, as given to `kiss3d_rect_form_factor`, closest to the ray from
+// `pos` along `dir`, which lights the point at `pos` with the specular highlight of the
+// rectangle.
+vec3 kiss3d_rect_representative_point(vec3 pos, vec3 dir, vec3 center, vec3 right, vec3 up) {
+  vec3 n = cross(right, up);
+  float along = dot(dir, n);
+  // Where the ray hits the plane of the rectangle or, if it goes away from it, the projection
+  // of `pos` on that plane.
+  vec3 hit = along < 0.0 ? pos + dir * (dot(center - pos, n) / along)
+                         : pos - n * (dot(pos - center, n) / dot(n, n));
+  vec3 local = hit - center;
+  float x = clamp(dot(local, right) / dot(right, right), -1.0, 1.0);
+  float y = clamp(dot(local, up) / dot(up, up), -1.0, 1.0);
+
+  return center + right * x + up * y;
+}
@@ -0,0 +1,23 @@
+// Interleaved gradient noise, from 0 to 1, evenly distributed over neighboring pixels.
+float kiss3d_interleaved_gradient_noise(vec2 pixel) {
+  return fract(52.9829189 * fract(dot(pixel, vec2(0.06711056, 0.00583715))));
+}
+
+// A pseudo-random value from 0 to 1.
+float kiss3d_hash(vec2 p) {
+  return fract(sin(dot(p, vec2(12.9898, 78.233))) * 43758.5453);
+}
+
+// Value noise, from 0 to 1, smoothly interpolated between random values at integer coordinates.
+float kiss3d_value_noise(vec2 p) {
+  vec2 i = floor(p);
+  vec2 f = fract(p);
+  vec2 u = f * f * (3.0 - 2.0 * f);
+
+  float a = kiss3d_hash(i);
+  float b = kiss3d_hash(i + vec2(1.0, 0.0));
+  float c = kiss3d_hash(i + vec2(0.0, 1.0));
+  float d = kiss3d_hash(i + vec2(1.0, 1.0));
+
+  return mix(mix(a, b, u.x), mix(c, d, u.x), u.y);
+}
@@ -0,0 +1,93 @@
+// Packs a depth in [0, 1) into the four 8-bit channels of a color.
+vec4 kiss3d_pack_depth(float depth) {
+  vec4 bytes = fract(depth * vec4(1.0, 255.0, 65025.0, 16581375.0));
+  return bytes - bytes.yzww * vec4(1.0 / 255.0, 1.0 / 255.0, 1.0 / 255.0, 0.0);
+}
+
+// Unpacks a depth packed by `kiss3d_pack_depth`.
+float kiss3d_unpack_depth(vec4 bytes) {
+  return dot(bytes, vec4(1.0, 1.0 / 255.0, 1.0 / 65025.0, 1.0 / 16581375.0));
+}
+
+// The fraction of the light reaching a point, as `kiss3d_shadow`, for a shadow map rendered in the
+// square tile of a texture starting at `tile` and of width `tile_size`, in texture coordinates.
+float kiss3d_shadow_tile(sampler2D shadow_map, vec4 coord, vec2 tile, float tile_size,
+                         float texel_size, float bias) {
+  // The points behind the light, or outside of the map, are lit.
+  if (coord.w <= 0.0) {
+    return 1.0;
+  }
+
+  vec3 p = coord.xyz / coord.w * 0.5 + 0.5;
+
+  if (p.x < 0.0 || p.x > 1.0 || p.y < 0.0 || p.y > 1.0) {
+    return 1.0;
+  }
+
+  // The points beyond the far plane are still shadowed by the ones in front of them.
+  float depth = min(p.z, 1.0) - bias;
+  vec2 center = tile + p.xy * tile_size;
+  float lit = 0.0;
+
+  for (int i = -1; i <= 1; i++) {
+    for (int j = -1; j <= 1; j++) {
+      vec2 uv = center + vec2(float(i), float(j)) * texel_size * tile_size;
+      lit += depth > kiss3d_unpack_depth(texture2D(shadow_map, uv)) ? 0.0 : 1.0;
+    }
+  }
+
+  return lit / 9.0;
+}
+
+// The fraction of the light reaching a point, from its clip-space coordinates as seen from the
+// light, by comparing its depth with the 3x3 texels of a shadow map around it.
+float kiss3d_shadow(sampler2D shadow_map, vec4 coord, float texel_size, float bias) {
+  return kiss3d_shadow_tile(shadow_map, coord, vec2(0.0), 1.0, texel_size, bias);
+}
+
+// The distance from the light of a depth of a perspective shadow map, between `znear` and `zfar`.
+float kiss3d_linear_depth(float depth, float znear, float zfar) {
+  return 2.0 * znear * zfar / (zfar + znear - (depth * 2.0 - 1.0) * (zfar - znear));
+}
+
+// The `i`-th of `n` points spread evenly over the unit disk, rotated by `rotation`.
+vec2 kiss3d_vogel_disk(int i, int n, float rotation) {
+  float r = sqrt((float(i) + 0.5) / float(n));
+  float theta = float(i) * 2.3999632 + rotation;
+  return r * vec2(cos(theta), sin(theta));
+}
+
+// The average depth of the texels closer to the light than `depth` within the disk of radius
+// `radius` around the point `uv` of a tile of a shadow map, and the fraction of such texels.
+vec2 kiss3d_shadow_blockers(sampler2D shadow_map, vec2 uv, vec2 tile, float tile_size,
+                            float depth, float radius, float rotation) {
+  float sum = 0.0;
+  float count = 0.0;
+
+  for (int i = 0; i < 16; i++) {
+    // Clamped to the tile, for the cascades not to sample their neighbors.
+    vec2 p = clamp(uv + kiss3d_vogel_disk(i, 16, rotation) * radius, 0.0, 1.0);
+    float d = kiss3d_unpack_depth(texture2D(shadow_map, tile + p * tile_size));
+
+    if (d < depth) {
+      sum += d;
+      count += 1.0;
+    }
+  }
+
+  return count > 0.0 ? vec2(sum / count, count / 16.0) : vec2(1.0, 0.0);
+}
+
+// The fraction of the light reaching a point at `depth`, from the texels within the disk of
+// radius `radius` around the point `uv` of a tile of a shadow map.
+float kiss3d_shadow_disk(sampler2D shadow_map, vec2 uv, vec2 tile, float tile_size, float depth,
+                         float radius, float rotation) {
+  float lit = 0.0;
+
+  for (int i = 0; i < 32; i++) {
+    vec2 p = clamp(uv + kiss3d_vogel_disk(i, 32, rotation) * radius, 0.0, 1.0);
+    lit += depth > kiss3d_unpack_depth(texture2D(shadow_map, tile + p * tile_size)) ? 0.0 : 1.0;
+  }
+
+  return lit / 32.0;
+}
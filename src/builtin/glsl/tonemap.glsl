@@ -0,0 +1,16 @@
+vec3 kiss3d_tonemap_reinhard(vec3 color) {
+  return color / (1.0 + color);
+}
+
+// An approximation of the ACES filmic curve, by Krzysztof Narkowicz.
+vec3 kiss3d_tonemap_aces(vec3 color) {
+  return clamp((color * (2.51 * color + 0.03)) / (color * (2.43 * color + 0.59) + 0.14), 0.0, 1.0);
+}
+
+vec3 kiss3d_linear_to_srgb(vec3 color) {
+  return pow(color, vec3(1.0 / 2.2));
+}
+
+vec3 kiss3d_srgb_to_linear(vec3 color) {
+  return pow(color, vec3(2.2));
+}
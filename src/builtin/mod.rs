@@ -1,13 +1,25 @@
 //! Built-in geometries, shaders and effects.
 
+pub use self::debug_material::{DebugMaterial, DEBUG_FRAGMENT_SRC, DEBUG_VERTEX_SRC};
+pub use self::frame_uniforms::{
+    update_frame_uniforms, FRAME_UNIFORMS_BINDING, FRAME_UNIFORM_BLOCK,
+};
 pub use self::normals_material::{NormalsMaterial, NORMAL_FRAGMENT_SRC, NORMAL_VERTEX_SRC};
-pub use self::object_material::{ObjectMaterial, OBJECT_FRAGMENT_SRC, OBJECT_VERTEX_SRC};
+pub use self::object_material::{
+    ObjectFeatures, ObjectMaterial, OBJECT_FRAGMENT_SRC, OBJECT_VERTEX_SRC,
+};
 pub use self::uvs_material::{UvsMaterial, UVS_FRAGMENT_SRC, UVS_VERTEX_SRC};
+pub use self::vertex_color_material::{
+    VertexColorMaterial, VERTEX_COLOR_FRAGMENT_SRC, VERTEX_COLOR_VERTEX_SRC,
+};
 
 pub use self::planar_object_material::PlanarObjectMaterial;
 
+mod debug_material;
+mod frame_uniforms;
 mod normals_material;
 mod object_material;
 mod uvs_material;
+mod vertex_color_material;
 
 mod planar_object_material;
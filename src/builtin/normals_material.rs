@@ -23,7 +23,7 @@ pub struct NormalsMaterial {
 impl NormalsMaterial {
     /// Creates a new NormalsMaterial.
     pub fn new() -> NormalsMaterial {
-        let mut shader = Effect::new_from_str(NORMAL_VERTEX_SRC, NORMAL_FRAGMENT_SRC);
+        let mut shader = Effect::new_from_str(NORMAL_VERTEX_SRC, NORMAL_FRAGMENT_SRC).unwrap();
 
         shader.use_program();
 
@@ -1,64 +1,520 @@
+use crate::builtin::frame_uniforms::{
+    update_frame_uniforms, FRAME_UNIFORMS_BINDING, FRAME_UNIFORM_BLOCK,
+};
 use crate::camera::Camera;
 use crate::context::Context;
-use crate::light::Light;
+use crate::light::{self, Light, LightKind, LightSource, MAX_LIGHTS};
 use crate::resource::Material;
-use crate::resource::{Effect, Mesh, ShaderAttribute, ShaderUniform};
-use crate::scene::ObjectData;
-use na::{Isometry3, Matrix3, Matrix4, Point2, Point3, Vector3};
+use crate::resource::{
+    Effect, Mesh, ShaderAttribute, ShaderError, ShaderUniform, Texture, TextureManager, MAX_JOINTS,
+};
+use crate::scene::shadow_map;
+use crate::scene::{LightmapContent, ObjectData, TransparencyMode, MAX_SHADOW_CASCADES};
+use na::{Isometry3, Matrix3, Matrix4, Point2, Point3, Vector2, Vector3, Vector4};
+use std::collections::HashMap;
+use std::rc::Rc;
 
 #[path = "../error.rs"]
 mod error;
 
-/// The default material used to draw objects.
-pub struct ObjectMaterial {
+bitflags! {
+    /// The optional features of the default material.
+    ///
+    /// Each feature enables a part of the shaders of the material with a `#define` directive of
+    /// the same name. A program is compiled for each combination of features used by the drawn
+    /// objects, the first time one of them is drawn, so that the objects only pay for the
+    /// features they use.
+    pub struct ObjectFeatures: u32 {
+        /// The object has a texture other than the default white one.
+        const HAS_TEXTURE       = 0b0001;
+        /// The mesh of the object has per-vertex colors, multiplied with its color.
+        const HAS_VERTEX_COLORS = 0b0010;
+        /// The object is transparent, with `TransparencyMode::Dither`.
+        const DITHER            = 0b0100;
+        /// The object is rendered as an x-ray ghost.
+        const XRAY              = 0b1000;
+        /// The object is lit with the normals of its faces.
+        const FLAT_SHADING      = 0b1_0000;
+        /// The skinned mesh of the object is deformed by a skeleton, see `Object::set_skeleton`.
+        const SKINNING          = 0b10_0000;
+        /// The object is darkened by the shadows of the shadow map.
+        const RECEIVE_SHADOWS   = 0b100_0000;
+        /// The object is lit by the lights added with `Window::add_light`.
+        const LIGHTS            = 0b1000_0000;
+        /// The object has a lightmap, sampled with the lightmap texture coordinates of its mesh.
+        const HAS_LIGHTMAP      = 0b1_0000_0000;
+        /// The lightmap of the object stores its direct lighting instead of its occlusion.
+        const LIGHTMAP_LIGHTING = 0b10_0000_0000;
+        /// The shadow map is split into cascades, see `Window::set_shadow_cascades`.
+        const SHADOW_CASCADES   = 0b100_0000_0000;
+        /// The shadows are contact-hardening, see `ShadowFilter::Pcss`.
+        const SOFT_SHADOWS      = 0b1000_0000_0000;
+        /// The shadows are completed by contact shadows, see `Window::set_contact_shadows`.
+        const CONTACT_SHADOWS   = 0b1_0000_0000_0000;
+        /// The object is lit by rectangular lights, see `LightKind::Rect`.
+        const RECT_LIGHTS       = 0b10_0000_0000_0000;
+    }
+}
+
+impl ObjectFeatures {
+    /// The names of the preprocessor definitions enabling these features in the shaders.
+    pub fn defines(&self) -> Vec<&'static str> {
+        let names = [
+            (ObjectFeatures::HAS_TEXTURE, "HAS_TEXTURE"),
+            (ObjectFeatures::HAS_VERTEX_COLORS, "HAS_VERTEX_COLORS"),
+            (ObjectFeatures::DITHER, "DITHER"),
+            (ObjectFeatures::XRAY, "XRAY"),
+            (ObjectFeatures::FLAT_SHADING, "FLAT_SHADING"),
+            (ObjectFeatures::SKINNING, "SKINNING"),
+            (ObjectFeatures::RECEIVE_SHADOWS, "RECEIVE_SHADOWS"),
+            (ObjectFeatures::LIGHTS, "LIGHTS"),
+            (ObjectFeatures::HAS_LIGHTMAP, "HAS_LIGHTMAP"),
+            (ObjectFeatures::LIGHTMAP_LIGHTING, "LIGHTMAP_LIGHTING"),
+            (ObjectFeatures::SHADOW_CASCADES, "SHADOW_CASCADES"),
+            (ObjectFeatures::SOFT_SHADOWS, "SOFT_SHADOWS"),
+            (ObjectFeatures::CONTACT_SHADOWS, "CONTACT_SHADOWS"),
+            (ObjectFeatures::RECT_LIGHTS, "RECT_LIGHTS"),
+        ];
+
+        names
+            .iter()
+            .filter(|(feature, _)| self.contains(*feature))
+            .map(|(_, name)| *name)
+            .collect()
+    }
+}
+
+// The program of the default material for one combination of features.
+//
+// The attributes and uniforms only used by some features are optional. The camera and light
+// uniforms are absent if they are read from the shared frame uniform buffer.
+struct ObjectProgram {
     effect: Effect,
     pos: ShaderAttribute<Point3<f32>>,
     normal: ShaderAttribute<Vector3<f32>>,
-    tex_coord: ShaderAttribute<Point2<f32>>,
-    light: ShaderUniform<Point3<f32>>,
+    tex_coord: Option<ShaderAttribute<Point2<f32>>>,
+    vertex_color: Option<ShaderAttribute<Point3<f32>>>,
+    lightmap_uv: Option<ShaderAttribute<Point2<f32>>>,
+    lightmap: Option<ShaderUniform<i32>>,
+    joints: Option<ShaderAttribute<Vector4<f32>>>,
+    weights: Option<ShaderAttribute<Vector4<f32>>>,
+    joint_matrices: Vec<ShaderUniform<Matrix4<f32>>>,
+    light: Option<ShaderUniform<Point3<f32>>>,
     color: ShaderUniform<Point3<f32>>,
+    opacity: ShaderUniform<f32>,
     transform: ShaderUniform<Matrix4<f32>>,
     scale: ShaderUniform<Matrix3<f32>>,
     ntransform: ShaderUniform<Matrix3<f32>>,
-    proj: ShaderUniform<Matrix4<f32>>,
-    view: ShaderUniform<Matrix4<f32>>,
+    proj: Option<ShaderUniform<Matrix4<f32>>>,
+    view: Option<ShaderUniform<Matrix4<f32>>>,
+    light_view_proj: Option<ShaderUniform<Matrix4<f32>>>,
+    shadow_map: Option<ShaderUniform<i32>>,
+    shadow_texel_size: Option<ShaderUniform<f32>>,
+    num_cascades: Option<ShaderUniform<i32>>,
+    cascade_view_proj: Vec<ShaderUniform<Matrix4<f32>>>,
+    cascade_splits: Option<ShaderUniform<Vector4<f32>>>,
+    shadow_cascade_debug: Option<ShaderUniform<i32>>,
+    shadow_pcss: Option<ShaderUniform<Vector4<f32>>>,
+    contact_depth: Option<ShaderUniform<i32>>,
+    contact_proj: Option<ShaderUniform<Matrix4<f32>>>,
+    contact_shadows: Option<ShaderUniform<Vector3<f32>>>,
+    num_lights: Option<ShaderUniform<i32>>,
+    lights: Vec<LightUniforms>,
+    // The number of changes of the lights and the view matrix they were last uploaded with.
+    uploaded_lights: Option<(u64, Matrix4<f32>)>,
+    uses_frame_uniforms: bool,
 }
 
-impl ObjectMaterial {
-    /// Creates a new `ObjectMaterial`.
-    pub fn new() -> ObjectMaterial {
-        // load the effect
-        let mut effect = Effect::new_from_str(OBJECT_VERTEX_SRC, OBJECT_FRAGMENT_SRC);
+// The uniforms of one of the lights added to the window, in view space.
+struct LightUniforms {
+    // The position of the light, or the direction toward it with `w = 0` if it is directional.
+    position: ShaderUniform<Vector4<f32>>,
+    // The axis of the cone of a spot light.
+    direction: ShaderUniform<Vector3<f32>>,
+    // The cosines of the inner and outer angles of the cone of a spot light.
+    cone: ShaderUniform<Vector2<f32>>,
+    color: ShaderUniform<Vector3<f32>>,
+    attenuation: ShaderUniform<Vector3<f32>>,
+    // With `RECT_LIGHTS`, the half-extents of a rectangular light, zero for the other lights.
+    half_width: Option<ShaderUniform<Vector3<f32>>>,
+    half_height: Option<ShaderUniform<Vector3<f32>>>,
+}
+
+impl LightUniforms {
+    fn new(effect: &Effect, i: usize) -> Option<LightUniforms> {
+        let name = |field: &str| format!("lights_{}[{}]", field, i);
+
+        Some(LightUniforms {
+            position: effect.get_uniform(&name("position"))?,
+            direction: effect.get_uniform(&name("direction"))?,
+            cone: effect.get_uniform(&name("cone"))?,
+            color: effect.get_uniform(&name("color"))?,
+            attenuation: effect.get_uniform(&name("attenuation"))?,
+            half_width: effect.get_uniform(&name("half_width")),
+            half_height: effect.get_uniform(&name("half_height")),
+        })
+    }
+
+    fn upload(&mut self, light: &LightSource, view: &Matrix4<f32>) {
+        // The cone of the lights other than spot lights contains every direction.
+        let no_cone = Vector2::new(-2.0, -3.0);
+        let mut half_extents = (Vector3::zeros(), Vector3::zeros());
+        let (position, direction, cone) = match light.kind {
+            LightKind::Point(p) => (
+                view.transform_point(&p).to_homogeneous(),
+                Vector3::z(),
+                no_cone,
+            ),
+            LightKind::Directional(d) => {
+                let to_light = view.transform_vector(&-d).normalize();
+                (to_light.push(0.0), Vector3::z(), no_cone)
+            }
+            LightKind::Spot {
+                position,
+                direction,
+                inner_angle,
+                outer_angle,
+            } => {
+                let cos_outer = outer_angle.cos();
+                // The inner cone must be strictly inside the outer one to fade between them.
+                let cos_inner = inner_angle.cos().max(cos_outer + 1.0e-4);
+                (
+                    view.transform_point(&position).to_homogeneous(),
+                    view.transform_vector(&direction).normalize(),
+                    Vector2::new(cos_inner, cos_outer),
+                )
+            }
+            LightKind::Rect {
+                position,
+                right,
+                up,
+            } => {
+                half_extents = (view.transform_vector(&right), view.transform_vector(&up));
+                (
+                    view.transform_point(&position).to_homogeneous(),
+                    Vector3::z(),
+                    no_cone,
+                )
+            }
+        };
+
+        self.position.upload(&position);
+        self.direction.upload(&direction);
+        self.cone.upload(&cone);
+        self.color
+            .upload(&(light.color.to_render().coords * light.intensity));
+        self.attenuation.upload(&light.attenuation);
+
+        if let (Some(half_width), Some(half_height)) = (&mut self.half_width, &mut self.half_height)
+        {
+            half_width.upload(&half_extents.0);
+            half_height.upload(&half_extents.1);
+        }
+    }
+}
+
+impl ObjectProgram {
+    fn new(features: ObjectFeatures) -> ObjectProgram {
+        // load the effect, reading the camera and light from the frame uniform buffer if possible
+        let defines = features.defines();
+        let (mut effect, uses_frame_uniforms) = match Effect::new_with_uniform_buffers(
+            OBJECT_VERTEX_SRC,
+            OBJECT_FRAGMENT_SRC,
+            &defines,
+        ) {
+            Ok(mut effect) => {
+                let bound = effect.bind_uniform_block(FRAME_UNIFORM_BLOCK, FRAME_UNIFORMS_BINDING);
+                (effect, bound)
+            }
+            Err(ShaderError::UniformBuffersUnsupported) => (
+                Effect::new_from_str_with_defines(OBJECT_VERTEX_SRC, OBJECT_FRAGMENT_SRC, &defines)
+                    .unwrap(),
+                false,
+            ),
+            Err(e) => panic!("{}", e),
+        };
 
         effect.use_program();
 
         // get the variables locations
-        ObjectMaterial {
+        ObjectProgram {
             pos: effect.get_attrib("position").unwrap(),
             normal: effect.get_attrib("normal").unwrap(),
-            tex_coord: effect.get_attrib("tex_coord").unwrap(),
-            light: effect.get_uniform("light_position").unwrap(),
+            tex_coord: effect.get_attrib("tex_coord"),
+            vertex_color: effect.get_attrib("vertex_color"),
+            lightmap_uv: effect.get_attrib("lightmap_uv"),
+            lightmap: effect.get_uniform("lightmap"),
+            joints: effect.get_attrib("joints"),
+            weights: effect.get_attrib("weights"),
+            joint_matrices: (0..MAX_JOINTS)
+                .map_while(|i| effect.get_uniform(&format!("joint_matrices[{}]", i)))
+                .collect(),
+            light: effect.get_uniform("light_position"),
             color: effect.get_uniform("color").unwrap(),
+            opacity: effect.get_uniform("opacity").unwrap(),
             transform: effect.get_uniform("transform").unwrap(),
             scale: effect.get_uniform("scale").unwrap(),
             ntransform: effect.get_uniform("ntransform").unwrap(),
-            view: effect.get_uniform("view").unwrap(),
-            proj: effect.get_uniform("proj").unwrap(),
+            view: effect.get_uniform("view"),
+            proj: effect.get_uniform("proj"),
+            light_view_proj: effect.get_uniform("light_view_proj"),
+            shadow_map: effect.get_uniform("shadow_map"),
+            shadow_texel_size: effect.get_uniform("shadow_texel_size"),
+            num_cascades: effect.get_uniform("num_cascades"),
+            cascade_view_proj: (0..MAX_SHADOW_CASCADES)
+                .map_while(|i| effect.get_uniform(&format!("cascade_view_proj[{}]", i)))
+                .collect(),
+            cascade_splits: effect.get_uniform("cascade_splits"),
+            shadow_cascade_debug: effect.get_uniform("shadow_cascade_debug"),
+            shadow_pcss: effect.get_uniform("shadow_pcss"),
+            contact_depth: effect.get_uniform("contact_depth"),
+            contact_proj: effect.get_uniform("contact_proj"),
+            contact_shadows: effect.get_uniform("contact_shadows"),
+            num_lights: effect.get_uniform("num_lights"),
+            lights: (0..MAX_LIGHTS)
+                .map_while(|i| LightUniforms::new(&effect, i))
+                .collect(),
+            uploaded_lights: None,
+            uses_frame_uniforms,
             effect,
         }
     }
 
+    // Uploads the lights added to the window, if they or the view changed since the last upload.
+    fn upload_lights(&mut self, view: &Matrix4<f32>) {
+        let num_lights = match self.num_lights {
+            Some(ref mut num_lights) => num_lights,
+            None => return,
+        };
+        let uniforms = &mut self.lights;
+        let uploaded = &mut self.uploaded_lights;
+
+        light::with_light_sources(|lights, changes| {
+            if *uploaded != Some((changes, *view)) {
+                let num = lights.len().min(uniforms.len());
+                num_lights.upload(&(num as i32));
+
+                for (light, uniforms) in lights.iter().zip(uniforms.iter_mut()) {
+                    uniforms.upload(light, view);
+                }
+
+                *uploaded = Some((changes, *view));
+            }
+        })
+    }
+
+    // Uploads and binds the shadow map sampled by the default material.
+    fn upload_shadow_map(&mut self) {
+        let ctxt = Context::get();
+        let _ = shadow_map::with_active(|active| {
+            if let Some(ref mut light_view_proj) = self.light_view_proj {
+                light_view_proj.upload(&active.view_projs[0]);
+            }
+
+            if let Some(ref mut num_cascades) = self.num_cascades {
+                num_cascades.upload(&(active.splits.len() as i32));
+            }
+
+            for (uniform, m) in self.cascade_view_proj.iter_mut().zip(&active.view_projs) {
+                uniform.upload(m);
+            }
+
+            if let Some(ref mut cascade_splits) = self.cascade_splits {
+                let mut splits = Vector4::zeros();
+
+                for (split, s) in splits.iter_mut().zip(&active.splits) {
+                    *split = *s;
+                }
+
+                cascade_splits.upload(&splits);
+            }
+
+            if let Some(ref mut debug) = self.shadow_cascade_debug {
+                debug.upload(&(active.debug as i32));
+            }
+
+            if let (Some(uniform), Some(pcss)) = (&mut self.shadow_pcss, &active.pcss) {
+                uniform.upload(pcss);
+            }
+
+            if let Some(ref mut texel_size) = self.shadow_texel_size {
+                let size = if active.has_cascades() {
+                    shadow_map::SHADOW_MAP_SIZE / 2
+                } else {
+                    shadow_map::SHADOW_MAP_SIZE
+                };
+                texel_size.upload(&(1.0 / size as f32));
+            }
+
+            if let Some(ref mut sampler) = self.shadow_map {
+                sampler.upload(&1);
+                verify!(ctxt.active_texture(Context::TEXTURE1));
+                verify!(ctxt.bind_texture(Context::TEXTURE_2D, active.target.texture_id()));
+                verify!(ctxt.active_texture(Context::TEXTURE0));
+            }
+
+            if let (Some(sampler), Some(contact)) = (&mut self.contact_depth, &active.contact) {
+                sampler.upload(&3);
+                verify!(ctxt.active_texture(Context::TEXTURE3));
+                verify!(ctxt.bind_texture(Context::TEXTURE_2D, contact.target.texture_id()));
+                verify!(ctxt.active_texture(Context::TEXTURE0));
+
+                if let Some(ref mut proj) = self.contact_proj {
+                    proj.upload(&contact.proj);
+                }
+
+                if let Some(ref mut params) = self.contact_shadows {
+                    params.upload(&contact.params);
+                }
+            }
+        });
+    }
+
     fn activate(&mut self) {
         self.effect.use_program();
         self.pos.enable();
         self.normal.enable();
-        self.tex_coord.enable();
+
+        if let Some(ref mut tex_coord) = self.tex_coord {
+            tex_coord.enable();
+        }
+
+        if let Some(ref mut vertex_color) = self.vertex_color {
+            vertex_color.enable();
+        }
+
+        if let Some(ref mut lightmap_uv) = self.lightmap_uv {
+            lightmap_uv.enable();
+        }
+
+        if let (Some(joints), Some(weights)) = (&mut self.joints, &mut self.weights) {
+            joints.enable();
+            weights.enable();
+        }
     }
 
     fn deactivate(&mut self) {
         self.pos.disable();
         self.normal.disable();
-        self.tex_coord.disable();
+
+        if let Some(ref mut tex_coord) = self.tex_coord {
+            tex_coord.disable();
+        }
+
+        if let Some(ref mut vertex_color) = self.vertex_color {
+            vertex_color.disable();
+        }
+
+        if let Some(ref mut lightmap_uv) = self.lightmap_uv {
+            lightmap_uv.disable();
+        }
+
+        if let (Some(joints), Some(weights)) = (&mut self.joints, &mut self.weights) {
+            joints.disable();
+            weights.disable();
+        }
+    }
+}
+
+/// The default material used to draw objects.
+///
+/// Its shaders are specialized for the `ObjectFeatures` used by each object.
+pub struct ObjectMaterial {
+    // The programs compiled so far, for each combination of features.
+    programs: HashMap<ObjectFeatures, ObjectProgram>,
+    // The default texture, which does not need to be sampled.
+    default_texture: Option<Rc<Texture>>,
+}
+
+impl ObjectMaterial {
+    /// Creates a new `ObjectMaterial`.
+    ///
+    /// Its programs are compiled when the first objects using them are drawn.
+    pub fn new() -> ObjectMaterial {
+        ObjectMaterial {
+            programs: HashMap::new(),
+            default_texture: None,
+        }
+    }
+
+    /// The number of programs compiled so far, one for each combination of features of the
+    /// objects drawn.
+    pub fn num_programs(&self) -> usize {
+        self.programs.len()
+    }
+
+    // The features needed to draw an object.
+    fn features(&mut self, data: &ObjectData, mesh: &Mesh) -> ObjectFeatures {
+        let default_texture = self
+            .default_texture
+            .get_or_insert_with(|| TextureManager::get_global_manager(|tm| tm.get_default()));
+        let mut features = ObjectFeatures::empty();
+
+        if !Rc::ptr_eq(data.texture(), default_texture) {
+            features |= ObjectFeatures::HAS_TEXTURE;
+        }
+
+        if mesh.colors().is_some() {
+            features |= ObjectFeatures::HAS_VERTEX_COLORS;
+        }
+
+        if data.xray_enabled() {
+            features |= ObjectFeatures::XRAY;
+        } else if data.opacity() < 1.0 && data.transparency_mode() == TransparencyMode::Dither {
+            features |= ObjectFeatures::DITHER;
+        }
+
+        if data.flat_shading_enabled() {
+            features |= ObjectFeatures::FLAT_SHADING;
+        }
+
+        if data.skeleton().is_some() && mesh.is_skinned() {
+            features |= ObjectFeatures::SKINNING;
+        }
+
+        if data.receives_shadows() && !data.xray_enabled() {
+            if let Some(shadows) = shadow_map::with_active(|active| {
+                let mut shadows = ObjectFeatures::RECEIVE_SHADOWS;
+                shadows.set(ObjectFeatures::SHADOW_CASCADES, active.has_cascades());
+                shadows.set(ObjectFeatures::SOFT_SHADOWS, active.pcss.is_some());
+                shadows.set(ObjectFeatures::CONTACT_SHADOWS, active.contact.is_some());
+                shadows
+            }) {
+                features |= shadows;
+            }
+        }
+
+        if !data.xray_enabled() {
+            features |= light::with_light_sources(|lights, _| {
+                let mut features = ObjectFeatures::empty();
+                features.set(ObjectFeatures::LIGHTS, !lights.is_empty());
+                features.set(
+                    ObjectFeatures::RECT_LIGHTS,
+                    lights
+                        .iter()
+                        .any(|l| matches!(l.kind, LightKind::Rect { .. })),
+                );
+                features
+            });
+        }
+
+        if let Some(lightmap) = data.lightmap() {
+            if !data.xray_enabled() && mesh.lightmap_uvs().is_some() {
+                features |= ObjectFeatures::HAS_LIGHTMAP;
+
+                if lightmap.content() == LightmapContent::DirectLighting {
+                    features |= ObjectFeatures::LIGHTMAP_LIGHTING;
+                }
+            }
+        }
+
+        features
+    }
+}
+
+impl Default for ObjectMaterial {
+    fn default() -> ObjectMaterial {
+        ObjectMaterial::new()
     }
 }
 
@@ -74,21 +530,40 @@ impl Material for ObjectMaterial {
         mesh: &mut Mesh,
     ) {
         let ctxt = Context::get();
-        self.activate();
+        let features = self.features(data, mesh);
+        let program = self
+            .programs
+            .entry(features)
+            .or_insert_with(|| ObjectProgram::new(features));
+
+        program.activate();
 
         /*
          *
          * Setup camera and light.
          *
          */
-        camera.upload(pass, &mut self.proj, &mut self.view);
-
         let pos = match *light {
             Light::Absolute(ref p) => *p,
             Light::StickToCamera => camera.eye(),
         };
 
-        self.light.upload(&pos);
+        if program.uses_frame_uniforms {
+            update_frame_uniforms(camera, pass, &pos);
+        } else {
+            if let (Some(proj), Some(view)) = (&mut program.proj, &mut program.view) {
+                camera.upload(pass, proj, view);
+            }
+
+            if let Some(ref mut light) = program.light {
+                light.upload(&pos);
+            }
+        }
+
+        if features.contains(ObjectFeatures::LIGHTS) {
+            let (_, view) = camera.pass_matrices(pass);
+            program.upload_lights(&view);
+        }
 
         /*
          *
@@ -100,17 +575,78 @@ impl Material for ObjectMaterial {
         let formated_scale = Matrix3::from_diagonal(&Vector3::new(scale.x, scale.y, scale.z));
 
         unsafe {
-            self.transform.upload(&formated_transform);
-            self.ntransform.upload(&formated_ntransform);
-            self.scale.upload(&formated_scale);
+            program.transform.upload(&formated_transform);
+            program.ntransform.upload(&formated_ntransform);
+            program.scale.upload(&formated_scale);
+
+            mesh.bind_coords(&mut program.pos);
+            mesh.bind_normals(&mut program.normal);
+
+            if let Some(ref mut tex_coord) = program.tex_coord {
+                mesh.bind_uvs(tex_coord);
+            }
+
+            if let Some(ref mut vertex_color) = program.vertex_color {
+                let _ = mesh.bind_colors(vertex_color);
+            }
 
-            mesh.bind(&mut self.pos, &mut self.normal, &mut self.tex_coord);
+            if let Some(ref mut lightmap_uv) = program.lightmap_uv {
+                let _ = mesh.bind_lightmap_uvs(lightmap_uv);
+            }
 
-            verify!(ctxt.active_texture(Context::TEXTURE0));
-            verify!(ctxt.bind_texture(Context::TEXTURE_2D, Some(&*data.texture())));
+            if let (Some(joints), Some(weights)) = (&mut program.joints, &mut program.weights) {
+                let _ = mesh.bind_skin(joints, weights);
+            }
+
+            if let Some(skeleton) = data.skeleton() {
+                let matrices = skeleton.borrow().joint_matrices();
+
+                for (uniform, matrix) in program.joint_matrices.iter_mut().zip(&matrices) {
+                    uniform.upload(matrix);
+                }
+            }
+
+            mesh.bind_faces();
+
+            if features.contains(ObjectFeatures::HAS_TEXTURE) {
+                verify!(ctxt.active_texture(Context::TEXTURE0));
+                verify!(ctxt.bind_texture(Context::TEXTURE_2D, Some(data.texture())));
+            }
+
+            if let (Some(sampler), Some(lightmap)) = (&mut program.lightmap, data.lightmap()) {
+                sampler.upload(&2);
+                verify!(ctxt.active_texture(Context::TEXTURE2));
+                verify!(ctxt.bind_texture(Context::TEXTURE_2D, Some(&**lightmap.texture())));
+                verify!(ctxt.active_texture(Context::TEXTURE0));
+            }
+
+            if features.contains(ObjectFeatures::RECEIVE_SHADOWS) {
+                program.upload_shadow_map();
+            }
+
+            let xray = features.contains(ObjectFeatures::XRAY);
+            let blended = xray || data.is_blended();
+            program.opacity.upload(&data.opacity());
+
+            // Blended objects are drawn after the opaque ones, and must not hide each other.
+            if blended {
+                verify!(ctxt.enable(Context::BLEND));
+                verify!(ctxt.blend_func_separate(
+                    Context::SRC_ALPHA,
+                    Context::ONE_MINUS_SRC_ALPHA,
+                    Context::ONE,
+                    Context::ONE_MINUS_SRC_ALPHA,
+                ));
+                verify!(ctxt.depth_mask(false));
+            }
+
+            // X-rayed objects are not hidden by the objects in front of them.
+            if xray {
+                verify!(ctxt.disable(Context::DEPTH_TEST));
+            }
 
             if data.surface_rendering_active() {
-                self.color.upload(data.color());
+                program.color.upload(data.color());
 
                 if data.backface_culling_enabled() {
                     verify!(ctxt.enable(Context::CULL_FACE));
@@ -128,7 +664,8 @@ impl Material for ObjectMaterial {
             }
 
             if data.lines_width() != 0.0 {
-                self.color
+                program
+                    .color
                     .upload(data.lines_color().unwrap_or(data.color()));
 
                 verify!(ctxt.disable(Context::CULL_FACE));
@@ -154,7 +691,7 @@ impl Material for ObjectMaterial {
             }
 
             if data.points_size() != 0.0 {
-                self.color.upload(data.color());
+                program.color.upload(data.color());
 
                 verify!(ctxt.disable(Context::CULL_FACE));
                 ctxt.point_size(data.points_size());
@@ -175,16 +712,29 @@ impl Material for ObjectMaterial {
                 }
                 ctxt.point_size(1.0);
             }
+
+            if xray {
+                verify!(ctxt.enable(Context::DEPTH_TEST));
+            }
+
+            if blended {
+                verify!(ctxt.depth_mask(true));
+                verify!(ctxt.disable(Context::BLEND));
+            }
         }
 
         mesh.unbind();
-        self.deactivate();
+        program.deactivate();
     }
 }
 
 /// Vertex shader of the default object material.
+///
+/// It is specialized by the `#define` directives of `ObjectFeatures::defines`.
 pub static OBJECT_VERTEX_SRC: &str = A_VERY_LONG_STRING;
 /// Fragment shader of the default object material.
+///
+/// It is specialized by the `#define` directives of `ObjectFeatures::defines`.
 pub static OBJECT_FRAGMENT_SRC: &str = ANOTHER_VERY_LONG_STRING;
 
 const A_VERY_LONG_STRING: &str = include_str!("default.vert");
@@ -15,6 +15,8 @@ pub struct PlanarObjectMaterial {
     tex_coord: ShaderAttribute<Point2<f32>>,
     color: ShaderUniform<Point3<f32>>,
     scale: ShaderUniform<Matrix2<f32>>,
+    uv_offset: ShaderUniform<Vector2<f32>>,
+    uv_scale: ShaderUniform<Vector2<f32>>,
     model: ShaderUniform<Matrix3<f32>>,
     view: ShaderUniform<Matrix3<f32>>,
     proj: ShaderUniform<Matrix3<f32>>,
@@ -24,7 +26,7 @@ impl PlanarObjectMaterial {
     /// Creates a new `PlanarObjectMaterial`.
     pub fn new() -> PlanarObjectMaterial {
         // load the effect
-        let mut effect = Effect::new_from_str(OBJECT_VERTEX_SRC, OBJECT_FRAGMENT_SRC);
+        let mut effect = Effect::new_from_str(OBJECT_VERTEX_SRC, OBJECT_FRAGMENT_SRC).unwrap();
 
         effect.use_program();
 
@@ -34,6 +36,8 @@ impl PlanarObjectMaterial {
             tex_coord: effect.get_attrib("tex_coord").unwrap(),
             color: effect.get_uniform("color").unwrap(),
             scale: effect.get_uniform("scale").unwrap(),
+            uv_offset: effect.get_uniform("uv_offset").unwrap(),
+            uv_scale: effect.get_uniform("uv_scale").unwrap(),
             model: effect.get_uniform("model").unwrap(),
             view: effect.get_uniform("view").unwrap(),
             proj: effect.get_uniform("proj").unwrap(),
@@ -84,6 +88,10 @@ impl PlanarMaterial for PlanarObjectMaterial {
             self.model.upload(&formated_transform);
             self.scale.upload(&formated_scale);
 
+            let (uv_offset, uv_scale) = data.uv_transform();
+            self.uv_offset.upload(uv_offset);
+            self.uv_scale.upload(uv_scale);
+
             mesh.bind(&mut self.pos, &mut self.tex_coord);
 
             verify!(ctxt.active_texture(Context::TEXTURE0));
@@ -168,6 +176,7 @@ attribute vec2 position;
 attribute vec2 tex_coord;
 
 uniform mat2 scale;
+uniform vec2 uv_offset, uv_scale;
 uniform mat3 proj, view, model;
 
 varying vec2 tex_coord_v;
@@ -177,7 +186,7 @@ void main(){
     projected_pos.z = 0.0;
 
     gl_Position = vec4(projected_pos, 1.0);
-    tex_coord_v = tex_coord;
+    tex_coord_v = uv_offset + tex_coord * uv_scale;
 }";
 
 const ANOTHER_VERY_LONG_STRING: &str = "#version 100
@@ -23,7 +23,7 @@ pub struct UvsMaterial {
 impl UvsMaterial {
     /// Creates a new UvsMaterial.
     pub fn new() -> UvsMaterial {
-        let mut shader = Effect::new_from_str(UVS_VERTEX_SRC, UVS_FRAGMENT_SRC);
+        let mut shader = Effect::new_from_str(UVS_VERTEX_SRC, UVS_FRAGMENT_SRC).unwrap();
 
         shader.use_program();
 
@@ -0,0 +1,277 @@
+use crate::camera::Camera;
+use crate::context::Context;
+use crate::light::Light;
+use crate::resource::Material;
+use crate::resource::{Effect, Mesh, ShaderAttribute, ShaderUniform};
+use crate::scene::ObjectData;
+use na::{Isometry3, Matrix3, Matrix4, Point2, Point3, Vector3};
+
+#[path = "../error.rs"]
+mod error;
+
+/// A material that draws objects using their per-vertex colors.
+///
+/// Objects without vertex colors are drawn with their uniform color instead.
+pub struct VertexColorMaterial {
+    effect: Effect,
+    pos: ShaderAttribute<Point3<f32>>,
+    normal: ShaderAttribute<Vector3<f32>>,
+    tex_coord: ShaderAttribute<Point2<f32>>,
+    vertex_color: ShaderAttribute<Point3<f32>>,
+    use_vertex_color: ShaderUniform<f32>,
+    light: ShaderUniform<Point3<f32>>,
+    color: ShaderUniform<Point3<f32>>,
+    transform: ShaderUniform<Matrix4<f32>>,
+    scale: ShaderUniform<Matrix3<f32>>,
+    ntransform: ShaderUniform<Matrix3<f32>>,
+    proj: ShaderUniform<Matrix4<f32>>,
+    view: ShaderUniform<Matrix4<f32>>,
+}
+
+impl VertexColorMaterial {
+    /// Creates a new `VertexColorMaterial`.
+    pub fn new() -> VertexColorMaterial {
+        // load the effect
+        let mut effect =
+            Effect::new_from_str(VERTEX_COLOR_VERTEX_SRC, VERTEX_COLOR_FRAGMENT_SRC).unwrap();
+
+        effect.use_program();
+
+        // get the variables locations
+        VertexColorMaterial {
+            pos: effect.get_attrib("position").unwrap(),
+            normal: effect.get_attrib("normal").unwrap(),
+            tex_coord: effect.get_attrib("tex_coord").unwrap(),
+            vertex_color: effect.get_attrib("vertex_color").unwrap(),
+            use_vertex_color: effect.get_uniform("use_vertex_color").unwrap(),
+            light: effect.get_uniform("light_position").unwrap(),
+            color: effect.get_uniform("color").unwrap(),
+            transform: effect.get_uniform("transform").unwrap(),
+            scale: effect.get_uniform("scale").unwrap(),
+            ntransform: effect.get_uniform("ntransform").unwrap(),
+            view: effect.get_uniform("view").unwrap(),
+            proj: effect.get_uniform("proj").unwrap(),
+            effect,
+        }
+    }
+
+    fn activate(&mut self) {
+        self.effect.use_program();
+        self.pos.enable();
+        self.normal.enable();
+        self.tex_coord.enable();
+    }
+
+    fn deactivate(&mut self) {
+        self.pos.disable();
+        self.normal.disable();
+        self.tex_coord.disable();
+    }
+}
+
+impl Default for VertexColorMaterial {
+    fn default() -> VertexColorMaterial {
+        VertexColorMaterial::new()
+    }
+}
+
+impl Material for VertexColorMaterial {
+    fn render(
+        &mut self,
+        pass: usize,
+        transform: &Isometry3<f32>,
+        scale: &Vector3<f32>,
+        camera: &mut dyn Camera,
+        light: &Light,
+        data: &ObjectData,
+        mesh: &mut Mesh,
+    ) {
+        let ctxt = Context::get();
+        self.activate();
+
+        /*
+         *
+         * Setup camera and light.
+         *
+         */
+        camera.upload(pass, &mut self.proj, &mut self.view);
+
+        let pos = match *light {
+            Light::Absolute(ref p) => *p,
+            Light::StickToCamera => camera.eye(),
+        };
+
+        self.light.upload(&pos);
+
+        /*
+         *
+         * Setup object-related stuffs.
+         *
+         */
+        let formated_transform = transform.to_homogeneous();
+        let formated_ntransform = transform.rotation.to_rotation_matrix().into_inner();
+        let formated_scale = Matrix3::from_diagonal(&Vector3::new(scale.x, scale.y, scale.z));
+
+        unsafe {
+            self.transform.upload(&formated_transform);
+            self.ntransform.upload(&formated_ntransform);
+            self.scale.upload(&formated_scale);
+
+            mesh.bind(&mut self.pos, &mut self.normal, &mut self.tex_coord);
+
+            if mesh.bind_colors(&mut self.vertex_color) {
+                self.vertex_color.enable();
+                self.use_vertex_color.upload(&1.0);
+            } else {
+                self.vertex_color.disable();
+                self.use_vertex_color.upload(&0.0);
+            }
+
+            verify!(ctxt.active_texture(Context::TEXTURE0));
+            verify!(ctxt.bind_texture(Context::TEXTURE_2D, Some(data.texture())));
+
+            if data.surface_rendering_active() {
+                self.color.upload(data.color());
+
+                if data.backface_culling_enabled() {
+                    verify!(ctxt.enable(Context::CULL_FACE));
+                } else {
+                    verify!(ctxt.disable(Context::CULL_FACE));
+                }
+
+                let _ = verify!(ctxt.polygon_mode(Context::FRONT_AND_BACK, Context::FILL));
+                verify!(ctxt.draw_elements(
+                    Context::TRIANGLES,
+                    mesh.num_pts() as i32,
+                    Context::UNSIGNED_SHORT,
+                    0
+                ));
+            }
+
+            if data.lines_width() != 0.0 {
+                self.color
+                    .upload(data.lines_color().unwrap_or(data.color()));
+
+                verify!(ctxt.disable(Context::CULL_FACE));
+                ignore!(ctxt.line_width(data.lines_width()));
+
+                if verify!(ctxt.polygon_mode(Context::FRONT_AND_BACK, Context::LINE)) {
+                    verify!(ctxt.draw_elements(
+                        Context::TRIANGLES,
+                        mesh.num_pts() as i32,
+                        Context::UNSIGNED_SHORT,
+                        0
+                    ));
+                } else {
+                    mesh.bind_edges();
+                    verify!(ctxt.draw_elements(
+                        Context::LINES,
+                        mesh.num_pts() as i32 * 2,
+                        Context::UNSIGNED_SHORT,
+                        0
+                    ));
+                }
+                ctxt.line_width(1.0);
+            }
+
+            if data.points_size() != 0.0 {
+                self.color.upload(data.color());
+
+                verify!(ctxt.disable(Context::CULL_FACE));
+                ctxt.point_size(data.points_size());
+                if verify!(ctxt.polygon_mode(Context::FRONT_AND_BACK, Context::POINT)) {
+                    verify!(ctxt.draw_elements(
+                        Context::TRIANGLES,
+                        mesh.num_pts() as i32,
+                        Context::UNSIGNED_SHORT,
+                        0
+                    ));
+                } else {
+                    verify!(ctxt.draw_elements(
+                        Context::POINTS,
+                        mesh.num_pts() as i32,
+                        Context::UNSIGNED_SHORT,
+                        0
+                    ));
+                }
+                ctxt.point_size(1.0);
+            }
+        }
+
+        mesh.unbind();
+        self.vertex_color.disable();
+        self.deactivate();
+    }
+}
+
+/// Vertex shader of the vertex color material.
+pub static VERTEX_COLOR_VERTEX_SRC: &str = A_VERY_LONG_STRING;
+/// Fragment shader of the vertex color material.
+pub static VERTEX_COLOR_FRAGMENT_SRC: &str = ANOTHER_VERY_LONG_STRING;
+
+const A_VERY_LONG_STRING: &str = "#version 100
+attribute vec3 position;
+attribute vec2 tex_coord;
+attribute vec3 normal;
+attribute vec3 vertex_color;
+
+uniform mat3 ntransform, scale;
+uniform mat4 proj, view, transform;
+uniform vec3 light_position;
+
+varying vec3 local_light_position;
+varying vec2 tex_coord_v;
+varying vec3 normalInterp;
+varying vec3 vertPos;
+varying vec3 vertex_color_v;
+
+void main(){
+    gl_Position = proj * view * transform * vec4(scale * position, 1.0);
+    vec4 vertPos4 = view * transform * vec4(scale * position, 1.0);
+    vertPos = vec3(vertPos4) / vertPos4.w;
+    normalInterp = mat3(view) * ntransform * normal;
+    tex_coord_v = tex_coord;
+    vertex_color_v = vertex_color;
+    local_light_position = (view * vec4(light_position, 1.0)).xyz;
+}
+";
+
+const ANOTHER_VERY_LONG_STRING: &str = "#version 100
+#ifdef GL_FRAGMENT_PRECISION_HIGH
+   precision highp float;
+#else
+   precision mediump float;
+#endif
+
+varying vec3 local_light_position;
+varying vec2 tex_coord_v;
+varying vec3 normalInterp;
+varying vec3 vertPos;
+varying vec3 vertex_color_v;
+
+uniform vec3 color;
+uniform float use_vertex_color;
+uniform sampler2D tex;
+const vec3 specColor = vec3(0.4, 0.4, 0.4);
+
+void main() {
+  vec3 normal = normalize(normalInterp);
+  vec3 lightDir = normalize(local_light_position - vertPos);
+
+  float lambertian = max(dot(lightDir, normal), 0.0);
+  float specular = 0.0;
+
+  if(lambertian > 0.0) {
+    vec3 viewDir = normalize(-vertPos);
+    vec3 halfDir = normalize(lightDir + viewDir);
+    float specAngle = max(dot(halfDir, normal), 0.0);
+    specular = pow(specAngle, 30.0);
+  }
+
+  vec3 base_color = mix(color, vertex_color_v, use_vertex_color);
+  vec4 tex_color = texture2D(tex, tex_coord_v);
+  gl_FragColor = tex_color * vec4(base_color / 3.0 +
+                                  lambertian * base_color / 3.0 +
+                                  specular * specColor / 3.0, 1.0);
+}
+";
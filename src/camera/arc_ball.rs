@@ -1,7 +1,10 @@
+use crate::camera::projection;
 use crate::camera::Camera;
 use crate::event::{Action, Key, Modifiers, MouseButton, WindowEvent};
 use crate::resource::ShaderUniform;
+use crate::scene::{self, SceneNode};
 use crate::window::Canvas;
+use instant::Instant;
 use na::{self, Isometry3, Matrix4, Perspective3, Point3, Unit, UnitQuaternion, Vector2, Vector3};
 use std::f32;
 
@@ -13,8 +16,12 @@ use std::f32;
 /// * Left button press + drag - rotates the camera around the focus point
 /// * Right button press + drag - translates the focus point on the plane orthogonal to the view
 /// direction
+/// * Middle button press + drag - pans like the right button, see `rebind_pan_button`
 /// * Scroll in/out - zoom in/out
 /// * Enter key - set the focus point to the origin
+///
+/// The rotations can be limited with `set_pitch_range` and `set_yaw_range`, and continue for a
+/// while after the rotate button is released with `set_inertia`.
 #[derive(Clone, Debug)]
 pub struct ArcBall {
     /// The focus point.
@@ -38,15 +45,33 @@ pub struct ArcBall {
     min_pitch: f32,
     /// Maximum pitch of the camera.
     max_pitch: f32,
+    /// Minimum yaw of the camera.
+    min_yaw: f32,
+    /// Maximum yaw of the camera.
+    max_yaw: f32,
+    /// Whether the camera is kept upright, with a pitch between 0 and π.
+    roll_locked: bool,
+    /// Fraction of the rotation speed kept after each 60th of a second once the rotate button is
+    /// released. The default value is 0.0.
+    inertia: f32,
+    /// Speed of the rotation, in radians of yaw and pitch per second.
+    angular_velocity: Vector2<f32>,
+    /// Rotation made with the mouse since the last call to `update`.
+    frame_rotation: Vector2<f32>,
+    last_update: Option<Instant>,
     /// Distance change factor per unit scrolling. The default value is 1.01.
     dist_step: f32,
     rotate_button: Option<MouseButton>,
     rotate_modifiers: Option<Modifiers>,
     drag_button: Option<MouseButton>,
     drag_modifiers: Option<Modifiers>,
+    pan_button: Option<MouseButton>,
+    pan_modifiers: Option<Modifiers>,
     reset_key: Option<Key>,
 
     projection: Perspective3<f32>,
+    reversed_depth: bool,
+    infinite_far: bool,
     view: Matrix4<f32>,
     proj: Matrix4<f32>,
     proj_view: Matrix4<f32>,
@@ -54,11 +79,24 @@ pub struct ArcBall {
     last_cursor_pos: Vector2<f32>,
     last_framebuffer_size: Vector2<f32>,
     coord_system: CoordSystemRh,
+    /// Duration of the focus transitions started by `frame_node`, in seconds.
+    transition_duration: f32,
+    transition: Option<FocusTransition>,
+}
+
+// An animated change of the focus point and distance.
+#[derive(Clone, Copy, Debug)]
+struct FocusTransition {
+    from_at: Point3<f32>,
+    from_dist: f32,
+    to_at: Point3<f32>,
+    to_dist: f32,
+    start: Instant,
 }
 
 impl ArcBall {
     /// Create a new arc-ball camera.
-    pub fn new(eye: Point3<f32>, at: Point3<f32>) -> ArcBall {
+    pub fn new(eye: impl Into<Point3<f32>>, at: impl Into<Point3<f32>>) -> ArcBall {
         ArcBall::new_with_frustrum(f32::consts::PI / 4.0, 0.001, 1024.0, eye, at)
     }
 
@@ -67,8 +105,8 @@ impl ArcBall {
         fov: f32,
         znear: f32,
         zfar: f32,
-        eye: Point3<f32>,
-        at: Point3<f32>,
+        eye: impl Into<Point3<f32>>,
+        at: impl Into<Point3<f32>>,
     ) -> ArcBall {
         let mut res = ArcBall {
             at: Point3::new(0.0, 0.0, 0.0),
@@ -81,13 +119,24 @@ impl ArcBall {
             pitch_step: 0.005,
             min_pitch: 0.01,
             max_pitch: f32::consts::PI - 0.01,
+            min_yaw: f32::NEG_INFINITY,
+            max_yaw: f32::INFINITY,
+            roll_locked: true,
+            inertia: 0.0,
+            angular_velocity: na::zero(),
+            frame_rotation: na::zero(),
+            last_update: None,
             dist_step: 1.01,
             rotate_button: Some(MouseButton::Button1),
             rotate_modifiers: None,
             drag_button: Some(MouseButton::Button2),
             drag_modifiers: None,
+            pan_button: Some(MouseButton::Button3),
+            pan_modifiers: None,
             reset_key: Some(Key::Return),
             projection: Perspective3::new(800.0 / 600.0, fov, znear, zfar),
+            reversed_depth: false,
+            infinite_far: false,
             view: na::zero(),
             proj: na::zero(),
             proj_view: na::zero(),
@@ -95,6 +144,8 @@ impl ArcBall {
             last_framebuffer_size: Vector2::new(800.0, 600.0),
             last_cursor_pos: na::zero(),
             coord_system: CoordSystemRh::from_up_axis(Vector3::y_axis()),
+            transition_duration: 0.3,
+            transition: None,
         };
 
         res.look_at(eye, at);
@@ -108,8 +159,9 @@ impl ArcBall {
     }
 
     /// Get a mutable reference to the point the camera is looking at.
-    pub fn set_at(&mut self, at: Point3<f32>) {
-        self.at = at;
+    pub fn set_at(&mut self, at: impl Into<Point3<f32>>) {
+        self.transition = None;
+        self.at = at.into();
         self.update_projviews();
     }
 
@@ -120,6 +172,7 @@ impl ArcBall {
 
     /// Sets the camera `yaw`. Change this to modify the rotation along the `up` axis.
     pub fn set_yaw(&mut self, yaw: f32) {
+        self.angular_velocity = na::zero();
         self.yaw = yaw;
 
         self.update_restrictions();
@@ -133,6 +186,7 @@ impl ArcBall {
 
     /// Sets the camera `pitch`.
     pub fn set_pitch(&mut self, pitch: f32) {
+        self.angular_velocity = na::zero();
         self.pitch = pitch;
 
         self.update_restrictions();
@@ -159,6 +213,74 @@ impl ArcBall {
         self.max_pitch = max_pitch;
     }
 
+    /// The minimum and maximum pitch of the camera.
+    pub fn pitch_range(&self) -> (f32, f32) {
+        (self.min_pitch, self.max_pitch)
+    }
+
+    /// Sets the minimum and maximum pitch of the camera, in radians from the up axis.
+    ///
+    /// The default range is `(0.01, π - 0.01)`. While the roll is locked, the pitch also stays
+    /// between 0 and π whatever the range.
+    pub fn set_pitch_range(&mut self, min_pitch: f32, max_pitch: f32) {
+        self.min_pitch = min_pitch;
+        self.max_pitch = max_pitch;
+
+        self.update_restrictions();
+        self.update_projviews();
+    }
+
+    /// The minimum and maximum yaw of the camera.
+    pub fn yaw_range(&self) -> (f32, f32) {
+        (self.min_yaw, self.max_yaw)
+    }
+
+    /// Sets the minimum and maximum yaw of the camera, in radians around the up axis.
+    ///
+    /// The yaw is not wrapped around: rotating a full turn adds 2π to it. The yaw is unlimited by
+    /// default, i.e., the range is `(-∞, ∞)`.
+    pub fn set_yaw_range(&mut self, min_yaw: f32, max_yaw: f32) {
+        self.min_yaw = min_yaw;
+        self.max_yaw = max_yaw;
+
+        self.update_restrictions();
+        self.update_projviews();
+    }
+
+    /// Whether the camera is kept upright.
+    pub fn roll_locked(&self) -> bool {
+        self.roll_locked
+    }
+
+    /// Sets whether the camera is kept upright (default at `true`).
+    ///
+    /// While locked, the pitch stays between 0 and π, so that the camera never rolls over the
+    /// poles. Once unlocked, a pitch range wider than `[0, π]` lets the camera orbit over the
+    /// poles, rolling upside down on the other side, e.g., with
+    /// `set_pitch_range(f32::NEG_INFINITY, f32::INFINITY)`.
+    pub fn set_roll_locked(&mut self, locked: bool) {
+        self.roll_locked = locked;
+
+        self.update_restrictions();
+        self.update_projviews();
+    }
+
+    /// The fraction of the rotation speed kept after each 60th of a second once the rotate
+    /// button is released.
+    pub fn inertia(&self) -> f32 {
+        self.inertia
+    }
+
+    /// Sets the fraction of the rotation speed kept after each 60th of a second once the rotate
+    /// button is released (default at 0.0).
+    ///
+    /// With a non-zero inertia, the camera keeps rotating after a flick of the mouse, slowing
+    /// down until it stops, e.g., in about a second with `0.9`. The value is clamped to
+    /// `[0.0, 0.99]`, and `0.0` stops the camera as soon as the button is released.
+    pub fn set_inertia(&mut self, inertia: f32) {
+        self.inertia = inertia.clamp(0.0, 0.99);
+    }
+
     /// The distance from the camera position to its view point.
     pub fn dist(&self) -> f32 {
         self.dist
@@ -166,6 +288,7 @@ impl ArcBall {
 
     /// Move the camera such that it is at a given distance from the view point.
     pub fn set_dist(&mut self, dist: f32) {
+        self.transition = None;
         self.dist = dist;
 
         self.update_restrictions();
@@ -198,7 +321,8 @@ impl ArcBall {
     }
 
     /// Move and orient the camera such that it looks at a specific point.
-    pub fn look_at(&mut self, eye: Point3<f32>, at: Point3<f32>) {
+    pub fn look_at(&mut self, eye: impl Into<Point3<f32>>, at: impl Into<Point3<f32>>) {
+        let (eye, at) = (eye.into(), at.into());
         let dist = (eye - at).norm();
 
         let view_eye = self.coord_system.rotation_to_y_up * eye;
@@ -206,6 +330,8 @@ impl ArcBall {
         let pitch = ((view_eye.y - view_at.y) / dist).acos();
         let yaw = (view_eye.z - view_at.z).atan2(view_eye.x - view_at.x);
 
+        self.transition = None;
+        self.angular_velocity = na::zero();
         self.at = at;
         self.dist = dist;
         self.yaw = yaw;
@@ -215,6 +341,92 @@ impl ArcBall {
         self.update_projviews();
     }
 
+    /// Move and orient the camera such that it looks at a specific point, both given in
+    /// double-precision world coordinates.
+    ///
+    /// The points are converted relative to the current world origin, see
+    /// `scene::set_world_origin`.
+    pub fn look_at_f64(&mut self, eye: Point3<f64>, at: Point3<f64>) {
+        self.look_at(scene::to_render_coords(&eye), scene::to_render_coords(&at))
+    }
+
+    /// Moves the focus point and distance so that the objects of `node` and its children fill the
+    /// view.
+    ///
+    /// The camera keeps its orientation, and moves to the new position with a transition lasting
+    /// `transition_duration` seconds. `margin` is the fraction of the size of the node left empty
+    /// around it, e.g., `0.1` for 10%. Nothing happens if the node contains no object.
+    pub fn frame_node(&mut self, node: &SceneNode, margin: f32) {
+        if let Some(sphere) = node.world_bounding_sphere() {
+            self.frame_sphere(*sphere.center(), sphere.radius(), margin)
+        }
+    }
+
+    /// Moves the focus point and distance so that a sphere fills the view.
+    ///
+    /// See `frame_node` for details.
+    pub fn frame_sphere(&mut self, center: Point3<f32>, radius: f32, margin: f32) {
+        // The sphere must fit in the smallest of the horizontal and vertical fields of view.
+        let half_fovy = self.projection.fovy() / 2.0;
+        let half_fovx = (half_fovy.tan() * self.projection.aspect()).atan();
+        let half_fov = half_fovy.min(half_fovx);
+        let dist = (radius * (1.0 + margin) / half_fov.sin())
+            .max(self.min_dist)
+            .min(self.max_dist);
+
+        if self.transition_duration <= 0.0 {
+            self.at = center;
+            self.dist = dist;
+            self.transition = None;
+            self.update_projviews();
+        } else {
+            self.transition = Some(FocusTransition {
+                from_at: self.at,
+                from_dist: self.dist,
+                to_at: center,
+                to_dist: dist,
+                start: Instant::now(),
+            });
+        }
+    }
+
+    /// The duration of the transitions started by `frame_node`, in seconds.
+    pub fn transition_duration(&self) -> f32 {
+        self.transition_duration
+    }
+
+    /// Sets the duration of the transitions started by `frame_node`, in seconds (default at 0.3).
+    ///
+    /// Set to `0.0` to move the camera immediately.
+    pub fn set_transition_duration(&mut self, duration: f32) {
+        self.transition_duration = duration;
+    }
+
+    /// Whether a transition started by `frame_node` is in progress.
+    pub fn is_transitioning(&self) -> bool {
+        self.transition.is_some()
+    }
+
+    fn update_transition(&mut self) {
+        if let Some(t) = self.transition {
+            let s = (t.start.elapsed().as_secs_f32() / self.transition_duration).min(1.0);
+            // Ease in and out.
+            let s = s * s * (3.0 - 2.0 * s);
+
+            self.at = t.from_at + (t.to_at - t.from_at) * s;
+            // Interpolate the distance geometrically, so that the zoom speed looks constant.
+            self.dist = t.from_dist * (t.to_dist / t.from_dist).powf(s);
+
+            if s >= 1.0 {
+                self.at = t.to_at;
+                self.dist = t.to_dist;
+                self.transition = None;
+            }
+
+            self.update_projviews();
+        }
+    }
+
     /// Transformation applied by the camera without perspective.
     fn update_restrictions(&mut self) {
         if self.dist < self.min_dist {
@@ -232,6 +444,58 @@ impl ArcBall {
         if self.pitch > self.max_pitch {
             self.pitch = self.max_pitch
         }
+
+        if self.roll_locked {
+            self.pitch = self.pitch.clamp(POLE_MARGIN, f32::consts::PI - POLE_MARGIN)
+        }
+
+        if self.yaw < self.min_yaw {
+            self.yaw = self.min_yaw
+        }
+
+        if self.yaw > self.max_yaw {
+            self.yaw = self.max_yaw
+        }
+    }
+
+    // Keeps the camera rotating after the rotate button is released, slowing down with the
+    // inertia, and measures the speed of the rotation while it is pressed.
+    fn update_inertia(&mut self, canvas: &Canvas) {
+        let now = Instant::now();
+        let dt = self
+            .last_update
+            .map(|t| (now - t).as_secs_f32())
+            .unwrap_or(0.0);
+        let rotation = std::mem::replace(&mut self.frame_rotation, na::zero());
+        self.last_update = Some(now);
+
+        let rotating = self
+            .rotate_button
+            .is_some_and(|button| canvas.get_mouse_button(button) == Action::Press);
+
+        if rotating {
+            if dt > 0.0 {
+                // Averaged with the previous frames, since some frames receive no cursor motion.
+                self.angular_velocity = (self.angular_velocity + rotation / dt) / 2.0;
+            }
+            return;
+        }
+
+        if self.inertia <= 0.0 || self.angular_velocity == Vector2::zeros() {
+            self.angular_velocity = na::zero();
+            return;
+        }
+
+        self.yaw += self.angular_velocity.x * dt;
+        self.pitch += self.angular_velocity.y * dt;
+        self.angular_velocity *= self.inertia.powf(dt * 60.0);
+
+        if self.angular_velocity.norm_squared() < MIN_ANGULAR_VELOCITY * MIN_ANGULAR_VELOCITY {
+            self.angular_velocity = na::zero();
+        }
+
+        self.update_restrictions();
+        self.update_projviews();
     }
 
     /// The button used to rotate the ArcBall camera.
@@ -284,6 +548,42 @@ impl ArcBall {
         self.drag_button = new_button;
     }
 
+    /// The button used to pan the ArcBall camera.
+    pub fn pan_button(&self) -> Option<MouseButton> {
+        self.pan_button
+    }
+
+    /// Set the button used to pan the ArcBall camera, the middle button by default.
+    /// Use None to disable panning.
+    ///
+    /// Panning is checked before rotation and dragging: binding it to the rotate button with
+    /// some modifiers, e.g., `Modifiers::Shift`, pans with these modifiers and rotates without.
+    pub fn rebind_pan_button(&mut self, new_button: Option<MouseButton>) {
+        self.pan_button = new_button;
+    }
+
+    /// Modifiers that must be pressed for the camera panning to occur.
+    pub fn pan_modifiers(&self) -> Option<Modifiers> {
+        self.pan_modifiers
+    }
+
+    /// Sets the modifiers that must be pressed for the camera panning to occur.
+    ///
+    /// If this is set to `None`, then pressing any modifier will not prevent panning from occurring.
+    /// If this is different from `None` then panning will occur only if the exact specified set of modifiers is pressed.
+    pub fn set_pan_modifiers(&mut self, modifiers: Option<Modifiers>) {
+        self.pan_modifiers = modifiers
+    }
+
+    /// Moves the focus point the camera orbits around without moving the camera.
+    ///
+    /// Unlike `set_at`, which translates the camera with its focus point, this keeps the eye at
+    /// the same position and turns the camera towards the new focus point.
+    pub fn set_orbit_center(&mut self, at: impl Into<Point3<f32>>) {
+        let eye = self.eye();
+        self.look_at(eye, at)
+    }
+
     /// The key used to reset the ArcBall camera.
     pub fn reset_key(&self) -> Option<Key> {
         self.reset_key
@@ -296,16 +596,20 @@ impl ArcBall {
     }
 
     fn handle_left_button_displacement(&mut self, dpos: &Vector2<f32>) {
+        self.transition = None;
+        let (yaw, pitch) = (self.yaw, self.pitch);
         self.yaw += dpos.x * self.yaw_step;
         self.pitch -= dpos.y * self.pitch_step;
 
         self.update_restrictions();
         self.update_projviews();
+        self.frame_rotation += Vector2::new(self.yaw - yaw, self.pitch - pitch);
     }
 
     /// Performs a translation of the camera eye and focus.
     /// The delta coordinates are expected to be normalized to the [-1, 1] range.
     fn handle_right_button_displacement(&mut self, dpos_norm: &Vector2<f32>) {
+        self.transition = None;
         let eye = self.eye();
         let dir = (self.at - eye).normalize();
         let tangent = self.coord_system.up_axis.cross(&dir).normalize();
@@ -316,6 +620,7 @@ impl ArcBall {
     }
 
     fn handle_scroll(&mut self, off: f32) {
+        self.transition = None;
         // To "focus" the zoom towards the point under the cursor, first we
         // translate the camera to bring that point in the center of the view
         // and then undo the translation.
@@ -333,8 +638,36 @@ impl ArcBall {
         self.handle_right_button_displacement(&dpos);
     }
 
+    /// Makes the projection map the near plane to the greatest depth, and the far plane to the
+    /// smallest.
+    ///
+    /// Combined with an infinite far plane, this reduces the z-fighting between distant surfaces
+    /// of large scenes.
+    pub fn set_reversed_depth(&mut self, reversed: bool) {
+        self.reversed_depth = reversed;
+        self.update_projviews();
+    }
+
+    /// Moves the far clipping plane to infinity, so that no object is clipped because of its
+    /// distance to the camera.
+    ///
+    /// `clip_planes` still returns the `zfar` given at construction.
+    pub fn set_infinite_far_plane(&mut self, infinite: bool) {
+        self.infinite_far = infinite;
+        self.update_projviews();
+    }
+
+    /// Whether the far clipping plane is at infinity.
+    pub fn infinite_far_plane(&self) -> bool {
+        self.infinite_far
+    }
+
     fn update_projviews(&mut self) {
-        self.proj = *self.projection.as_matrix();
+        self.proj = projection::perspective_matrix(
+            &self.projection,
+            self.reversed_depth,
+            self.infinite_far,
+        );
         self.view = self.view_transform().to_homogeneous();
         self.proj_view = self.proj * self.view;
         self.inverse_proj_view = self.proj_view.try_inverse().unwrap();
@@ -343,8 +676,8 @@ impl ArcBall {
     /// Sets the up vector of this camera. Prefer using [`set_up_axis_dir`](#method.set_up_axis_dir)
     /// if your up vector is already normalized.
     #[inline]
-    pub fn set_up_axis(&mut self, up_axis: Vector3<f32>) {
-        self.set_up_axis_dir(Unit::new_normalize(up_axis));
+    pub fn set_up_axis(&mut self, up_axis: impl Into<Vector3<f32>>) {
+        self.set_up_axis_dir(Unit::new_normalize(up_axis.into()));
     }
 
     /// Sets the up-axis direction of this camera.
@@ -367,8 +700,28 @@ impl Camera for ArcBall {
         (self.projection.znear(), self.projection.zfar())
     }
 
+    fn reversed_depth(&self) -> bool {
+        self.reversed_depth
+    }
+
+    fn depth_range(&self) -> (f32, f32) {
+        projection::depth_range(&self.projection, self.reversed_depth, self.infinite_far)
+    }
+
     fn view_transform(&self) -> Isometry3<f32> {
-        Isometry3::look_at_rh(&self.eye(), &self.at, &self.coord_system.up_axis)
+        if self.roll_locked {
+            Isometry3::look_at_rh(&self.eye(), &self.at, &self.coord_system.up_axis)
+        } else {
+            // The direction the eye moves to when the pitch decreases, which turns upside down
+            // past the poles.
+            let up = Vector3::new(
+                -self.yaw.cos() * self.pitch.cos(),
+                self.pitch.sin(),
+                -self.yaw.sin() * self.pitch.cos(),
+            );
+            let up = self.coord_system.rotation_to_y_up.inverse() * up;
+            Isometry3::look_at_rh(&self.eye(), &self.at, &up)
+        }
     }
 
     fn eye(&self) -> Point3<f32> {
@@ -383,6 +736,18 @@ impl Camera for ArcBall {
         match *event {
             WindowEvent::CursorPos(x, y, modifiers) => {
                 let curr_pos = Vector2::new(x as f32, y as f32);
+                let panning = self.pan_button.is_some_and(|button| {
+                    canvas.get_mouse_button(button) == Action::Press
+                        && self.pan_modifiers.map(|m| m == modifiers).unwrap_or(true)
+                });
+
+                if panning {
+                    let dpos = curr_pos - self.last_cursor_pos;
+                    let dpos_norm = dpos.component_div(&self.last_framebuffer_size);
+                    self.handle_right_button_displacement(&dpos_norm);
+                    self.last_cursor_pos = curr_pos;
+                    return;
+                }
 
                 if let Some(rotate_button) = self.rotate_button {
                     if canvas.get_mouse_button(rotate_button) == Action::Press
@@ -409,6 +774,7 @@ impl Camera for ArcBall {
                 self.last_cursor_pos = curr_pos;
             }
             WindowEvent::Key(key, Action::Press, _) if Some(key) == self.reset_key => {
+                self.transition = None;
                 self.at = Point3::origin();
                 self.update_projviews();
             }
@@ -433,6 +799,10 @@ impl Camera for ArcBall {
         view.upload(&self.view);
     }
 
+    fn pass_matrices(&self, _: usize) -> (Matrix4<f32>, Matrix4<f32>) {
+        (self.proj, self.view)
+    }
+
     fn transformation(&self) -> Matrix4<f32> {
         self.proj_view
     }
@@ -441,9 +811,44 @@ impl Camera for ArcBall {
         self.inverse_proj_view
     }
 
-    fn update(&mut self, _: &Canvas) {}
+    fn update(&mut self, canvas: &Canvas) {
+        self.update_transition();
+        self.update_inertia(canvas);
+    }
+
+    fn look_along(&mut self, dir: &Vector3<f32>) {
+        let yaw = self.yaw;
+        let dir = dir.normalize();
+        let eye = self.at - dir * self.dist;
+        let at = self.at;
+
+        self.look_at(eye, at);
+
+        // The yaw is undefined when looking along the up axis: keep the current one.
+        if dir.dot(&self.coord_system.up_axis).abs() > 0.999 {
+            self.yaw = yaw;
+            self.update_projviews();
+        }
+    }
+
+    fn shift_origin(&mut self, offset: &Vector3<f64>) {
+        let offset = offset.cast::<f32>();
+        self.at -= offset;
+
+        if let Some(ref mut t) = self.transition {
+            t.from_at -= offset;
+            t.to_at -= offset;
+        }
+
+        self.update_projviews();
+    }
 }
 
+// The smallest angle between the view direction and the up axis while the roll is locked.
+const POLE_MARGIN: f32 = 1.0e-3;
+// The speed, in radians per second, below which a spinning camera stops.
+const MIN_ANGULAR_VELOCITY: f32 = 1.0e-3;
+
 #[derive(Clone, Copy, Debug)]
 struct CoordSystemRh {
     up_axis: Unit<Vector3<f32>>,
@@ -1,5 +1,6 @@
 use crate::event::WindowEvent;
 use crate::resource::ShaderUniform;
+use crate::scene;
 use crate::window::Canvas;
 use na::{Isometry3, Matrix4, Point2, Point3, Point4, Vector2, Vector3};
 
@@ -16,6 +17,13 @@ pub trait Camera {
      */
     /// The camera position.
     fn eye(&self) -> Point3<f32>; // FIXME: should this be here?
+    /// The camera position, in double-precision world coordinates.
+    ///
+    /// See `scene::set_world_origin` for details about double-precision coordinates.
+    #[inline]
+    fn eye_f64(&self) -> Point3<f64> {
+        scene::to_world_coords(&self.eye())
+    }
     /// The camera view transform.
     fn view_transform(&self) -> Isometry3<f32>;
     /// The transformation applied by the camera to transform a point in world coordinates to
@@ -26,6 +34,23 @@ pub trait Camera {
     fn inverse_transformation(&self) -> Matrix4<f32>;
     /// The clipping planes, aka. (`znear`, `zfar`).
     fn clip_planes(&self) -> (f32, f32); // FIXME: should this be here?
+    /// Whether the projection of this camera maps the near plane to the greatest depth, and the
+    /// far plane to the smallest.
+    ///
+    /// The depth buffer is then cleared to 0 instead of 1, and the fragments with the greatest
+    /// depth are kept instead of the ones with the smallest depth.
+    #[inline]
+    fn reversed_depth(&self) -> bool {
+        false
+    }
+    /// The depths, in normalized device coordinates, of the near and far clipping planes.
+    ///
+    /// If the far plane is at infinity, the second depth is the one of a plane at a finite
+    /// distance instead, so that the points at this depth can be unprojected.
+    #[inline]
+    fn depth_range(&self) -> (f32, f32) {
+        (-1.0, 1.0)
+    }
 
     /*
      * Update & upload
@@ -42,6 +67,18 @@ pub trait Camera {
         view: &mut ShaderUniform<Matrix4<f32>>,
     );
 
+    /// The projection and view matrices uploaded by `upload` for the given pass.
+    ///
+    /// The default implementation derives them from `transformation` and `view_transform`, which
+    /// is only correct for cameras uploading the same matrices at each pass.
+    #[inline]
+    fn pass_matrices(&self, _pass: usize) -> (Matrix4<f32>, Matrix4<f32>) {
+        let view = self.view_transform();
+        let proj = self.transformation() * view.inverse().to_homogeneous();
+
+        (proj, view.to_homogeneous())
+    }
+
     /// The number of passes required by this camera.
     #[inline]
     fn num_passes(&self) -> usize {
@@ -56,6 +93,19 @@ pub trait Camera {
     #[inline]
     fn render_complete(&self, _canvas: &Canvas) {}
 
+    /// Rotates the camera so that it looks along the direction `dir`.
+    ///
+    /// This is used to snap the camera to axis views. The default implementation does nothing.
+    #[inline]
+    fn look_along(&mut self, _dir: &Vector3<f32>) {}
+
+    /// Moves the camera by `-offset` after the world origin moved by `offset`, so that it keeps
+    /// the same position in double-precision world coordinates.
+    ///
+    /// The default implementation does nothing.
+    #[inline]
+    fn shift_origin(&mut self, _offset: &Vector3<f64>) {}
+
     /// Converts a 3d point to 2d screen coordinates, assuming the screen has the size `size`.
     fn project(&self, world_coord: &Point3<f32>, size: &Vector2<f32>) -> Vector2<f32> {
         let h_world_coord = world_coord.to_homogeneous();
@@ -82,8 +132,9 @@ pub trait Camera {
             2.0 * -window_coord.y / size.y + 1.0,
         );
 
-        let normalized_begin = Point4::new(normalized_coord.x, normalized_coord.y, -1.0, 1.0);
-        let normalized_end = Point4::new(normalized_coord.x, normalized_coord.y, 1.0, 1.0);
+        let (near, far) = self.depth_range();
+        let normalized_begin = Point4::new(normalized_coord.x, normalized_coord.y, near, 1.0);
+        let normalized_end = Point4::new(normalized_coord.x, normalized_coord.y, far, 1.0);
 
         let cam = self.inverse_transformation();
 
@@ -0,0 +1,282 @@
+//! Animation of a camera along keyframes.
+
+use instant::Instant;
+use na::{Point3, Unit, Vector3};
+use std::f32;
+
+use crate::camera::{ArcBall, FirstPerson, Projection};
+
+/// A function mapping the progress through a segment of a `CameraPath`, in `[0, 1]`, to the
+/// fraction of the way travelled between its keyframes.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum Easing {
+    /// A constant speed.
+    Linear,
+    /// Starts slowly and accelerates.
+    EaseIn,
+    /// Starts fast and decelerates.
+    EaseOut,
+    /// Starts slowly, accelerates, and decelerates before the end.
+    EaseInOut,
+}
+
+impl Easing {
+    /// The fraction of the way travelled after the progress `t`, clamped to `[0, 1]`.
+    pub fn apply(&self, t: f32) -> f32 {
+        let t = t.clamp(0.0, 1.0);
+
+        match *self {
+            Easing::Linear => t,
+            Easing::EaseIn => t * t,
+            Easing::EaseOut => t * (2.0 - t),
+            Easing::EaseInOut => t * t * (3.0 - 2.0 * t),
+        }
+    }
+}
+
+/// The position, orientation, and field of view of a camera.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct CameraPose {
+    /// The position of the camera.
+    pub eye: Point3<f32>,
+    /// The point the camera is looking at.
+    pub at: Point3<f32>,
+    /// The up axis of the camera.
+    pub up: Unit<Vector3<f32>>,
+    /// The vertical field of view of the camera, in radians.
+    pub fovy: f32,
+}
+
+impl CameraPose {
+    /// A pose looking from `eye` to `at`, with the `y` axis up and a field of view of `π / 4`.
+    pub fn new(eye: impl Into<Point3<f32>>, at: impl Into<Point3<f32>>) -> CameraPose {
+        CameraPose {
+            eye: eye.into(),
+            at: at.into(),
+            up: Vector3::y_axis(),
+            fovy: f32::consts::PI / 4.0,
+        }
+    }
+
+    /// Moves and orients `camera` to this pose.
+    ///
+    /// The field of view is only set if the projection of the camera is perspective.
+    pub fn apply_to_first_person(&self, camera: &mut FirstPerson) {
+        camera.set_up_axis_dir(self.up);
+        camera.look_at(self.eye, self.at);
+
+        if let Projection::Perspective { .. } = camera.projection() {
+            camera.set_projection(Projection::Perspective { fovy: self.fovy });
+        }
+    }
+
+    /// Moves and orients `camera` to this pose.
+    ///
+    /// The field of view of an `ArcBall` is fixed, so it is ignored.
+    pub fn apply_to_arc_ball(&self, camera: &mut ArcBall) {
+        camera.set_up_axis_dir(self.up);
+        camera.look_at(self.eye, self.at);
+    }
+}
+
+/// A pose of a `CameraPath`, reached at a given time.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct CameraKeyframe {
+    /// The time at which the pose is reached, in seconds since the start of the path.
+    pub time: f32,
+    /// The pose of the camera.
+    pub pose: CameraPose,
+    /// The easing of the segment from this keyframe to the next one.
+    pub easing: Easing,
+}
+
+impl CameraKeyframe {
+    /// A keyframe reaching `pose` at `time`, followed by a segment at a constant speed.
+    pub fn new(time: f32, pose: CameraPose) -> CameraKeyframe {
+        CameraKeyframe {
+            time,
+            pose,
+            easing: Easing::Linear,
+        }
+    }
+
+    /// This keyframe with the segment following it eased by `easing`.
+    pub fn with_easing(mut self, easing: Easing) -> CameraKeyframe {
+        self.easing = easing;
+        self
+    }
+}
+
+/// Keyframes interpolated to animate a camera, e.g., for a fly-through.
+///
+/// The eye and target positions follow a smooth curve through the keyframes, while the up axis
+/// and the field of view are interpolated linearly. The path is played with `play`, and its
+/// current pose applied to a camera at each frame:
+///
+/// ```no_run
+/// # use kiss3d::window::Window;
+/// # use kiss3d::camera::{CameraKeyframe, CameraPath, CameraPose, Easing, FirstPerson};
+/// # use kiss3d::nalgebra::Point3;
+/// let mut window = Window::new("Kiss3d: camera path");
+/// let mut camera = FirstPerson::new(Point3::new(0.0, 0.0, 10.0), Point3::origin());
+/// let mut path = CameraPath::new();
+///
+/// path.add_keyframe(CameraKeyframe::new(0.0, CameraPose::new([0.0, 0.0, 10.0], [0.0; 3])));
+/// path.add_keyframe(
+///     CameraKeyframe::new(2.0, CameraPose::new([10.0, 2.0, 0.0], [0.0; 3]))
+///         .with_easing(Easing::EaseInOut),
+/// );
+/// path.add_keyframe(CameraKeyframe::new(5.0, CameraPose::new([0.0, 5.0, -10.0], [0.0; 3])));
+/// path.play();
+///
+/// while window.render_with_camera(&mut camera) {
+///     if let Some(pose) = path.current_pose() {
+///         pose.apply_to_first_person(&mut camera);
+///     }
+/// }
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct CameraPath {
+    keyframes: Vec<CameraKeyframe>,
+    looping: bool,
+    start: Option<Instant>,
+}
+
+impl CameraPath {
+    /// Creates a path without keyframes.
+    pub fn new() -> CameraPath {
+        CameraPath::default()
+    }
+
+    /// Adds a keyframe to this path, keeping the keyframes sorted by time.
+    pub fn add_keyframe(&mut self, keyframe: CameraKeyframe) {
+        let i = self.keyframes.partition_point(|k| k.time <= keyframe.time);
+        self.keyframes.insert(i, keyframe)
+    }
+
+    /// The keyframes of this path, sorted by time.
+    pub fn keyframes(&self) -> &[CameraKeyframe] {
+        &self.keyframes
+    }
+
+    /// Removes all the keyframes of this path.
+    pub fn clear(&mut self) {
+        self.keyframes.clear()
+    }
+
+    /// The time of the last keyframe, in seconds.
+    pub fn duration(&self) -> f32 {
+        self.keyframes.last().map_or(0.0, |k| k.time)
+    }
+
+    /// Sets whether the path starts over once its last keyframe is reached.
+    ///
+    /// The first keyframe of a looping path should be at time `0`, and its pose be equal to the
+    /// one of the last keyframe.
+    pub fn set_looping(&mut self, looping: bool) {
+        self.looping = looping
+    }
+
+    /// Whether the path starts over once its last keyframe is reached.
+    pub fn is_looping(&self) -> bool {
+        self.looping
+    }
+
+    /// Starts playing this path from its beginning.
+    pub fn play(&mut self) {
+        self.start = Some(Instant::now())
+    }
+
+    /// Stops playing this path.
+    pub fn stop(&mut self) {
+        self.start = None
+    }
+
+    /// Whether this path is playing.
+    pub fn is_playing(&self) -> bool {
+        self.start.is_some()
+    }
+
+    /// The time elapsed since this path started playing, in seconds.
+    pub fn elapsed(&self) -> Option<f32> {
+        self.start.map(|start| start.elapsed().as_secs_f32())
+    }
+
+    /// The pose at the time elapsed since this path started playing, or `None` if it is not
+    /// playing.
+    ///
+    /// A path that is not looping stops playing once the pose of its last keyframe is returned.
+    pub fn current_pose(&mut self) -> Option<CameraPose> {
+        let elapsed = self.elapsed()?;
+        let duration = self.duration();
+
+        let time = if self.looping && duration > 0.0 {
+            elapsed % duration
+        } else {
+            if elapsed >= duration {
+                self.stop();
+            }
+
+            elapsed
+        };
+
+        self.pose_at(time)
+    }
+
+    /// The pose at `time`, in seconds since the start of the path, or `None` if the path has no
+    /// keyframe.
+    ///
+    /// The pose is the one of the first keyframe before it, and the one of the last keyframe
+    /// after it.
+    pub fn pose_at(&self, time: f32) -> Option<CameraPose> {
+        let keyframes = &self.keyframes;
+        let next = keyframes.partition_point(|k| k.time <= time);
+
+        if next == 0 {
+            return keyframes.first().map(|k| k.pose);
+        }
+
+        if next == keyframes.len() {
+            return keyframes.last().map(|k| k.pose);
+        }
+
+        let (k1, k2) = (&keyframes[next - 1], &keyframes[next]);
+        let span = k2.time - k1.time;
+        let t = if span > 0.0 {
+            k1.easing.apply((time - k1.time) / span)
+        } else {
+            1.0
+        };
+
+        // The neighbours of the segment shape the curve through its keyframes.
+        let k0 = &keyframes[next.saturating_sub(2)];
+        let k3 = &keyframes[(next + 1).min(keyframes.len() - 1)];
+        let up = k1.pose.up.into_inner().lerp(&k2.pose.up, t);
+
+        Some(CameraPose {
+            eye: catmull_rom(k0.pose.eye, k1.pose.eye, k2.pose.eye, k3.pose.eye, t),
+            at: catmull_rom(k0.pose.at, k1.pose.at, k2.pose.at, k3.pose.at, t),
+            up: Unit::try_new(up, 1.0e-6).unwrap_or(k2.pose.up),
+            fovy: k1.pose.fovy + (k2.pose.fovy - k1.pose.fovy) * t,
+        })
+    }
+}
+
+// The point at `t` on the Catmull-Rom spline through `p1` and `p2`, with the neighbours `p0` and
+// `p3`.
+fn catmull_rom(
+    p0: Point3<f32>,
+    p1: Point3<f32>,
+    p2: Point3<f32>,
+    p3: Point3<f32>,
+    t: f32,
+) -> Point3<f32> {
+    let (t2, t3) = (t * t, t * t * t);
+    let coords = (p1.coords * 2.0
+        + (p2.coords - p0.coords) * t
+        + (p0.coords * 2.0 - p1.coords * 5.0 + p2.coords * 4.0 - p3.coords) * t2
+        + (p1.coords * 3.0 - p0.coords - p2.coords * 3.0 + p3.coords) * t3)
+        * 0.5;
+
+    Point3::from(coords)
+}
@@ -1,13 +1,14 @@
+use crate::camera::projection::{self, Projection};
 use crate::camera::Camera;
-use crate::event::{Action, Key, MouseButton, WindowEvent};
+use crate::convert::IntoTranslation3;
+use crate::event::{Action, GamepadAxis, GamepadState, Key, MouseButton, WindowEvent};
 use crate::resource::ShaderUniform;
+use crate::scene::{self, SceneNode};
 use crate::window::Canvas;
-use na::{
-    self, Isometry3, Matrix4, Perspective3, Point3, Translation3, Unit, UnitQuaternion, Vector2,
-    Vector3,
-};
+use na::{self, Isometry3, Matrix4, Perspective3, Point3, Unit, UnitQuaternion, Vector2, Vector3};
 use num::Zero;
 use std::f32;
+use std::fmt;
 
 /// First-person camera mode.
 ///
@@ -15,6 +16,12 @@ use std::f32;
 ///   * Right button press + drag - translates the camera position on the plane orthogonal to the
 ///   view direction
 ///   * Scroll in/out - zoom in/out
+///
+/// The projection is perspective by default, and can be switched to orthographic with
+/// `set_projection`, e.g., for CAD-style views without perspective distortion.
+///
+/// The camera can be prevented from passing through the objects of a scene with
+/// `enable_collisions`.
 #[derive(Debug, Clone)]
 pub struct FirstPerson {
     eye: Point3<f32>,
@@ -32,17 +39,129 @@ pub struct FirstPerson {
     right_key: Option<Key>,
 
     projection: Perspective3<f32>,
+    // The height of the view volume, while the projection is orthographic.
+    orthographic_height: Option<f32>,
+    reversed_depth: bool,
+    infinite_far: bool,
     proj: Matrix4<f32>,
     view: Matrix4<f32>,
     proj_view: Matrix4<f32>,
     inverse_proj_view: Matrix4<f32>,
     last_cursor_pos: Vector2<f32>,
     coord_system: CoordSystemRh,
+    collision: Option<Collision>,
+    gamepad: Option<GamepadBindings>,
+}
+
+/// How a `FirstPerson` camera is controlled with a gamepad.
+///
+/// The left stick moves the camera, the right stick rotates it, and the right and left triggers
+/// move it up and down along the up axis. The sensitivities are applied per frame, at full
+/// deflection of the sticks and triggers.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct GamepadBindings {
+    /// The sideways and forward translations for a full deflection of the left stick. The
+    /// default value is `(0.5, 0.5)`.
+    pub move_sensitivity: Vector2<f32>,
+    /// The yaw and pitch increments, in radians, for a full deflection of the right stick. The
+    /// default value is `(0.04, 0.04)`.
+    pub look_sensitivity: Vector2<f32>,
+    /// The translation along the up axis for a fully pressed trigger. The default value is 0.5.
+    pub vertical_sensitivity: f32,
+    /// The deflection of the sticks below which they are considered at rest. The default value
+    /// is 0.15.
+    pub stick_deadzone: f32,
+    /// The value of the triggers below which they are considered released. The default value is
+    /// 0.05.
+    pub trigger_deadzone: f32,
+    /// Whether pushing the right stick upward makes the camera look down.
+    pub invert_look_y: bool,
+}
+
+impl Default for GamepadBindings {
+    fn default() -> Self {
+        GamepadBindings {
+            move_sensitivity: Vector2::new(0.5, 0.5),
+            look_sensitivity: Vector2::new(0.04, 0.04),
+            vertical_sensitivity: 0.5,
+            stick_deadzone: 0.15,
+            trigger_deadzone: 0.05,
+            invert_look_y: false,
+        }
+    }
+}
+
+// Removes the deadzone of a stick, and rescales the remaining deflection to `[0, 1]`.
+fn stick_deflection(x: f32, y: f32, deadzone: f32) -> Vector2<f32> {
+    let v = Vector2::new(x, y);
+    let norm = v.norm();
+
+    if norm <= deadzone || deadzone >= 1.0 {
+        Vector2::zeros()
+    } else {
+        v * ((norm.min(1.0) - deadzone) / (1.0 - deadzone) / norm)
+    }
+}
+
+fn trigger_value(value: f32, deadzone: f32) -> f32 {
+    if value <= deadzone || deadzone >= 1.0 {
+        0.0
+    } else {
+        (value.min(1.0) - deadzone) / (1.0 - deadzone)
+    }
+}
+
+// The scene a first person camera collides with.
+#[derive(Clone)]
+struct Collision {
+    scene: SceneNode,
+    radius: f32,
+}
+
+impl fmt::Debug for Collision {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Collision")
+            .field("radius", &self.radius)
+            .finish()
+    }
+}
+
+impl Collision {
+    // Moves a sphere by `delta`, sliding along the objects it hits.
+    fn slide(&self, from: Point3<f32>, delta: Vector3<f32>) -> Point3<f32> {
+        // Small steps, so that the sphere cannot go through thin walls.
+        let num_steps = (delta.norm() * 2.0 / self.radius).ceil().clamp(1.0, 64.0) as usize;
+        let step = delta / num_steps as f32;
+        let mut pos = from;
+
+        for _ in 0..num_steps {
+            pos += step;
+
+            // Push the sphere out of the objects, a few times to handle corners.
+            for _ in 0..4 {
+                let closest = match self.scene.closest_point(&pos, self.radius) {
+                    Some((_, closest)) => closest,
+                    None => break,
+                };
+                let dir = pos - closest;
+                let dist = dir.norm();
+
+                if dist < 1.0e-6 {
+                    pos -= step;
+                    break;
+                }
+
+                pos += dir * ((self.radius - dist) / dist);
+            }
+        }
+
+        pos
+    }
 }
 
 impl FirstPerson {
     /// Creates a first person camera with default sensitivity values.
-    pub fn new(eye: Point3<f32>, at: Point3<f32>) -> FirstPerson {
+    pub fn new(eye: impl Into<Point3<f32>>, at: impl Into<Point3<f32>>) -> FirstPerson {
         FirstPerson::new_with_frustrum(f32::consts::PI / 4.0, 0.1, 1024.0, eye, at)
     }
 
@@ -51,8 +170,8 @@ impl FirstPerson {
         fov: f32,
         znear: f32,
         zfar: f32,
-        eye: Point3<f32>,
-        at: Point3<f32>,
+        eye: impl Into<Point3<f32>>,
+        at: impl Into<Point3<f32>>,
     ) -> FirstPerson {
         let mut res = FirstPerson {
             eye: Point3::new(0.0, 0.0, 0.0),
@@ -68,12 +187,17 @@ impl FirstPerson {
             left_key: Some(Key::Left),
             right_key: Some(Key::Right),
             projection: Perspective3::new(800.0 / 600.0, fov, znear, zfar),
+            orthographic_height: None,
+            reversed_depth: false,
+            infinite_far: false,
             proj: na::zero(),
             view: na::zero(),
             proj_view: na::zero(),
             inverse_proj_view: na::zero(),
             last_cursor_pos: na::zero(),
             coord_system: CoordSystemRh::from_up_axis(Vector3::y_axis()),
+            collision: None,
+            gamepad: None,
         };
 
         res.look_at(eye, at);
@@ -105,6 +229,43 @@ impl FirstPerson {
         self.yaw_step = step;
     }
 
+    /// Prevents the camera from passing through the visible objects of `scene`.
+    ///
+    /// The camera is considered as a sphere of the given radius, which slides along the objects
+    /// when moved with the keyboard, the mouse, or the scroll wheel. The objects are tested
+    /// against their mesh, so large scenes should be given as a subtree containing only the
+    /// colliding objects, e.g., walls and floors.
+    pub fn enable_collisions(&mut self, scene: SceneNode, radius: f32) {
+        self.collision = Some(Collision { scene, radius });
+    }
+
+    /// Allows the camera to pass through objects again.
+    pub fn disable_collisions(&mut self) {
+        self.collision = None;
+    }
+
+    /// The radius of the sphere colliding with the scene, if collisions are enabled.
+    pub fn collision_radius(&self) -> Option<f32> {
+        self.collision.as_ref().map(|c| c.radius)
+    }
+
+    /// Enables or disables the control of this camera with the gamepad.
+    ///
+    /// The gamepad is read from `Canvas::gamepad` at each frame. It is disabled by default.
+    pub fn set_gamepad_bindings(&mut self, bindings: Option<GamepadBindings>) {
+        self.gamepad = bindings;
+    }
+
+    /// The gamepad bindings of this camera, if the gamepad is enabled.
+    pub fn gamepad_bindings(&self) -> Option<&GamepadBindings> {
+        self.gamepad.as_ref()
+    }
+
+    /// A mutable reference to the gamepad bindings of this camera, if the gamepad is enabled.
+    pub fn gamepad_bindings_mut(&mut self) -> Option<&mut GamepadBindings> {
+        self.gamepad.as_mut()
+    }
+
     /// Gets the translational increment per arrow press.
     #[inline]
     pub fn move_step(&self) -> f32 {
@@ -124,7 +285,8 @@ impl FirstPerson {
     }
 
     /// Changes the orientation and position of the camera to look at the specified point.
-    pub fn look_at(&mut self, eye: Point3<f32>, at: Point3<f32>) {
+    pub fn look_at(&mut self, eye: impl Into<Point3<f32>>, at: impl Into<Point3<f32>>) {
+        let (eye, at) = (eye.into(), at.into());
         let dist = (eye - at).norm();
 
         let view_eye = self.coord_system.rotation_to_y_up * eye;
@@ -138,6 +300,15 @@ impl FirstPerson {
         self.update_projviews();
     }
 
+    /// Changes the orientation and position of the camera to look at the specified point, both given in
+    /// double-precision world coordinates.
+    ///
+    /// The points are converted relative to the current world origin, see
+    /// `scene::set_world_origin`.
+    pub fn look_at_f64(&mut self, eye: Point3<f64>, at: Point3<f64>) {
+        self.look_at(scene::to_render_coords(&eye), scene::to_render_coords(&at))
+    }
+
     /// The point the camera is looking at.
     pub fn at(&self) -> Point3<f32> {
         let view_eye = self.coord_system.rotation_to_y_up * self.eye;
@@ -248,24 +419,151 @@ impl FirstPerson {
         let tangent = self.coord_system.up_axis.cross(&dir).normalize();
         let bitangent = dir.cross(&tangent);
 
-        self.eye = self.eye + tangent * (0.01 * dpos.x / 10.0) + bitangent * (0.01 * dpos.y / 10.0);
-        self.update_restrictions();
-        self.update_projviews();
+        self.move_eye(tangent * (0.01 * dpos.x / 10.0) + bitangent * (0.01 * dpos.y / 10.0));
     }
 
     #[doc(hidden)]
     pub fn handle_scroll(&mut self, yoff: f32) {
+        // Moving the eye does not change the apparent size of the objects.
+        if let Some(height) = self.orthographic_height {
+            self.orthographic_height = Some(height * (1.0 - 0.1 * yoff).clamp(0.5, 2.0));
+            self.update_projviews();
+            return;
+        }
+
         let front = self.observer_frame() * Vector3::z();
 
-        self.eye = self.eye + front * (self.move_step * yoff);
+        self.move_eye(front * (self.move_step * yoff));
+    }
+
+    fn update_gamepad(&mut self, pad: &GamepadState, bindings: &GamepadBindings) {
+        if !pad.is_connected() {
+            return;
+        }
+
+        let look = stick_deflection(
+            pad.axis(GamepadAxis::RightStickX),
+            pad.axis(GamepadAxis::RightStickY),
+            bindings.stick_deadzone,
+        );
+
+        if !look.is_zero() {
+            let look_y = if bindings.invert_look_y {
+                -look.y
+            } else {
+                look.y
+            };
+            self.yaw += look.x * bindings.look_sensitivity.x;
+            self.pitch -= look_y * bindings.look_sensitivity.y;
+            self.update_restrictions();
+            self.update_projviews();
+        }
+
+        let movement = stick_deflection(
+            pad.axis(GamepadAxis::LeftStickX),
+            pad.axis(GamepadAxis::LeftStickY),
+            bindings.stick_deadzone,
+        );
+        let vertical = trigger_value(
+            pad.axis(GamepadAxis::RightTrigger),
+            bindings.trigger_deadzone,
+        ) - trigger_value(
+            pad.axis(GamepadAxis::LeftTrigger),
+            bindings.trigger_deadzone,
+        );
+
+        if !movement.is_zero() || vertical != 0.0 {
+            let t = self.observer_frame();
+            let front = t * Vector3::z();
+            let right = t * Vector3::x();
+            let delta = front * (movement.y * bindings.move_sensitivity.y)
+                - right * (movement.x * bindings.move_sensitivity.x)
+                + self.coord_system.up_axis.into_inner()
+                    * (vertical * bindings.vertical_sensitivity);
+
+            self.move_eye(delta);
+        }
+    }
+
+    // Moves the eye by `delta`, sliding along the scene if collisions are enabled.
+    fn move_eye(&mut self, delta: Vector3<f32>) {
+        let eye = match self.collision {
+            Some(ref collision) => collision.slide(self.eye, delta),
+            None => self.eye + delta,
+        };
+
+        self.set_eye(eye);
+    }
+
+    /// Makes the projection map the near plane to the greatest depth, and the far plane to the
+    /// smallest.
+    ///
+    /// Combined with an infinite far plane, this reduces the z-fighting between distant surfaces
+    /// of large scenes.
+    pub fn set_reversed_depth(&mut self, reversed: bool) {
+        self.reversed_depth = reversed;
+        self.update_projviews();
+    }
+
+    /// The projection of this camera.
+    pub fn projection(&self) -> Projection {
+        match self.orthographic_height {
+            Some(height) => Projection::Orthographic { height },
+            None => Projection::Perspective {
+                fovy: self.projection.fovy(),
+            },
+        }
+    }
+
+    /// Sets the projection of this camera.
+    ///
+    /// Both projections use the clipping planes given at construction. While the projection is
+    /// orthographic, scrolling scales the height of the view volume instead of moving the camera.
+    pub fn set_projection(&mut self, projection: Projection) {
+        match projection {
+            Projection::Perspective { fovy } => {
+                self.projection.set_fovy(fovy);
+                self.orthographic_height = None;
+            }
+            Projection::Orthographic { height } => {
+                assert!(
+                    height > 0.0,
+                    "The height of the view volume must be positive."
+                );
+                self.orthographic_height = Some(height);
+            }
+        }
 
-        self.update_restrictions();
         self.update_projviews();
     }
 
+    /// Moves the far clipping plane to infinity, so that no object is clipped because of its
+    /// distance to the camera.
+    ///
+    /// `clip_planes` still returns the `zfar` given at construction. An orthographic projection
+    /// always keeps its far plane.
+    pub fn set_infinite_far_plane(&mut self, infinite: bool) {
+        self.infinite_far = infinite;
+        self.update_projviews();
+    }
+
+    /// Whether the far clipping plane is at infinity.
+    pub fn infinite_far_plane(&self) -> bool {
+        self.infinite_far
+    }
+
     fn update_projviews(&mut self) {
         self.view = self.view_transform().to_homogeneous();
-        self.proj = *self.projection.as_matrix();
+        self.proj = match self.orthographic_height {
+            Some(height) => {
+                projection::orthographic_matrix(&self.projection, height, self.reversed_depth)
+            }
+            None => projection::perspective_matrix(
+                &self.projection,
+                self.reversed_depth,
+                self.infinite_far,
+            ),
+        };
         self.proj_view = self.proj * self.view;
         let _ = self
             .proj_view
@@ -311,15 +609,15 @@ impl FirstPerson {
 
     /// Translates in-place this camera by `t`.
     #[inline]
-    pub fn translate_mut(&mut self, t: &Translation3<f32>) {
-        let new_eye = t * self.eye;
+    pub fn translate_mut(&mut self, t: &impl IntoTranslation3) {
+        let new_eye = t.into_translation3() * self.eye;
 
         self.set_eye(new_eye);
     }
 
     /// Translates this camera by `t`.
     #[inline]
-    pub fn translate(&self, t: &Translation3<f32>) -> FirstPerson {
+    pub fn translate(&self, t: &impl IntoTranslation3) -> FirstPerson {
         let mut res = self.clone();
         res.translate_mut(t);
         res
@@ -336,8 +634,8 @@ impl FirstPerson {
     /// Sets the up vector of this camera. Prefer using [`set_up_axis_dir`](#method.set_up_axis_dir)
     /// if your up vector is already normalized.
     #[inline]
-    pub fn set_up_axis(&mut self, up_axis: Vector3<f32>) {
-        self.set_up_axis_dir(Unit::new_normalize(up_axis));
+    pub fn set_up_axis(&mut self, up_axis: impl Into<Vector3<f32>>) {
+        self.set_up_axis_dir(Unit::new_normalize(up_axis.into()));
     }
 
     /// Sets the up-axis direction of this camera.
@@ -365,6 +663,18 @@ impl Camera for FirstPerson {
         (self.projection.znear(), self.projection.zfar())
     }
 
+    fn reversed_depth(&self) -> bool {
+        self.reversed_depth
+    }
+
+    fn depth_range(&self) -> (f32, f32) {
+        if self.orthographic_height.is_some() {
+            return projection::orthographic_depth_range(self.reversed_depth);
+        }
+
+        projection::depth_range(&self.projection, self.reversed_depth, self.infinite_far)
+    }
+
     /// The camera view transformation (i-e transformation without projection).
     fn view_transform(&self) -> Isometry3<f32> {
         Isometry3::look_at_rh(&self.eye, &self.at(), &self.coord_system.up_axis)
@@ -423,6 +733,10 @@ impl Camera for FirstPerson {
         view.upload(&self.view);
     }
 
+    fn pass_matrices(&self, _: usize) -> (Matrix4<f32>, Matrix4<f32>) {
+        (self.proj, self.view)
+    }
+
     fn update(&mut self, canvas: &Canvas) {
         let up = check_optional_key_state(canvas, self.up_key, Action::Press);
         let down = check_optional_key_state(canvas, self.down_key, Action::Press);
@@ -430,8 +744,21 @@ impl Camera for FirstPerson {
         let left = check_optional_key_state(canvas, self.left_key, Action::Press);
         let dir = self.move_dir(up, down, right, left);
 
-        let move_amount = dir * self.move_step;
-        self.translate_mut(&Translation3::from(move_amount));
+        self.move_eye(dir * self.move_step);
+
+        if let Some(bindings) = self.gamepad {
+            self.update_gamepad(canvas.gamepad(), &bindings);
+        }
+    }
+
+    fn look_along(&mut self, dir: &Vector3<f32>) {
+        let eye = self.eye;
+        self.look_at(eye, eye + dir.normalize());
+    }
+
+    fn shift_origin(&mut self, offset: &Vector3<f64>) {
+        self.eye -= offset.cast::<f32>();
+        self.update_projviews();
     }
 }
 
@@ -2,6 +2,7 @@ use std::f32;
 
 use na::{self, Isometry3, Matrix4, Perspective3, Point2, Point3, Vector2, Vector3};
 
+use crate::camera::projection;
 use crate::camera::Camera;
 use crate::context::Context;
 use crate::event::{Action, Key, MouseButton, WindowEvent};
@@ -41,6 +42,8 @@ pub struct FirstPersonStereo {
 
     /// Low level datas
     projection: Perspective3<f32>,
+    reversed_depth: bool,
+    infinite_far: bool,
     view_left: Matrix4<f32>,
     view_right: Matrix4<f32>,
     proj: Matrix4<f32>,
@@ -51,7 +54,11 @@ pub struct FirstPersonStereo {
 
 impl FirstPersonStereo {
     /// Creates a first person camera with default sensitivity values.
-    pub fn new(eye: Point3<f32>, at: Point3<f32>, ipd: f32) -> FirstPersonStereo {
+    pub fn new(
+        eye: impl Into<Point3<f32>>,
+        at: impl Into<Point3<f32>>,
+        ipd: f32,
+    ) -> FirstPersonStereo {
         FirstPersonStereo::new_with_frustrum(f32::consts::PI / 4.0, 0.1, 1024.0, eye, at, ipd)
     }
 
@@ -60,8 +67,8 @@ impl FirstPersonStereo {
         fov: f32,
         znear: f32,
         zfar: f32,
-        eye: Point3<f32>,
-        at: Point3<f32>,
+        eye: impl Into<Point3<f32>>,
+        at: impl Into<Point3<f32>>,
         ipd: f32,
     ) -> FirstPersonStereo {
         let mut res = FirstPersonStereo {
@@ -76,6 +83,8 @@ impl FirstPersonStereo {
             pitch_step: 0.005,
             move_step: 0.5,
             projection: Perspective3::new(800.0 / 600.0, fov, znear, zfar),
+            reversed_depth: false,
+            infinite_far: false,
             proj_view: na::zero(),
             inverse_proj_view: na::zero(),
             last_cursor_pos: Point2::origin(),
@@ -90,7 +99,8 @@ impl FirstPersonStereo {
     }
 
     /// Changes the orientation and position of the camera to look at the specified point.
-    pub fn look_at(&mut self, eye: Point3<f32>, at: Point3<f32>) {
+    pub fn look_at(&mut self, eye: impl Into<Point3<f32>>, at: impl Into<Point3<f32>>) {
+        let (eye, at) = (eye.into(), at.into());
         let dist = (eye - at).norm();
 
         let pitch = ((at.y - eye.y) / dist).acos();
@@ -168,10 +178,38 @@ impl FirstPersonStereo {
         self.update_projviews();
     }
 
+    /// Makes the projection map the near plane to the greatest depth, and the far plane to the
+    /// smallest.
+    ///
+    /// Combined with an infinite far plane, this reduces the z-fighting between distant surfaces
+    /// of large scenes.
+    pub fn set_reversed_depth(&mut self, reversed: bool) {
+        self.reversed_depth = reversed;
+        self.update_projviews();
+    }
+
+    /// Moves the far clipping plane to infinity, so that no object is clipped because of its
+    /// distance to the camera.
+    ///
+    /// `clip_planes` still returns the `zfar` given at construction.
+    pub fn set_infinite_far_plane(&mut self, infinite: bool) {
+        self.infinite_far = infinite;
+        self.update_projviews();
+    }
+
+    /// Whether the far clipping plane is at infinity.
+    pub fn infinite_far_plane(&self) -> bool {
+        self.infinite_far
+    }
+
     fn update_projviews(&mut self) {
-        self.proj_view = *self.projection.as_matrix() * self.view_transform().to_homogeneous();
+        self.proj = projection::perspective_matrix(
+            &self.projection,
+            self.reversed_depth,
+            self.infinite_far,
+        );
+        self.proj_view = self.proj * self.view_transform().to_homogeneous();
         self.inverse_proj_view = self.proj_view.try_inverse().unwrap();
-        self.proj = *self.projection.as_matrix();
         self.view_left = self.view_transform_left().to_homogeneous();
         self.view_right = self.view_transform_right().to_homogeneous();
     }
@@ -214,6 +252,14 @@ impl Camera for FirstPersonStereo {
         (self.projection.znear(), self.projection.zfar())
     }
 
+    fn reversed_depth(&self) -> bool {
+        self.reversed_depth
+    }
+
+    fn depth_range(&self) -> (f32, f32) {
+        projection::depth_range(&self.projection, self.reversed_depth, self.infinite_far)
+    }
+
     /// The imaginary middle eye camera view transformation (i-e transformation without projection).
     fn view_transform(&self) -> Isometry3<f32> {
         Isometry3::look_at_rh(&self.eye, &self.at(), &Vector3::y())
@@ -283,6 +329,12 @@ impl Camera for FirstPersonStereo {
         self.update_projviews();
     }
 
+    fn shift_origin(&mut self, offset: &Vector3<f64>) {
+        self.eye -= offset.cast::<f32>();
+        self.update_eyes_location();
+        self.update_projviews();
+    }
+
     fn upload(
         &self,
         pass: usize,
@@ -293,6 +345,10 @@ impl Camera for FirstPersonStereo {
         proj.upload(&self.proj);
     }
 
+    fn pass_matrices(&self, pass: usize) -> (Matrix4<f32>, Matrix4<f32>) {
+        (self.proj, self.view_eye(pass))
+    }
+
     fn num_passes(&self) -> usize {
         2usize
     }
@@ -1,3 +1,4 @@
+use crate::camera::projection;
 use crate::camera::Camera;
 use crate::event::WindowEvent;
 use crate::resource::ShaderUniform;
@@ -11,6 +12,8 @@ pub struct FixedView {
     projection: Perspective3<f32>,
     proj: Matrix4<f32>,
     inv_proj: Matrix4<f32>,
+    reversed_depth: bool,
+    infinite_far: bool,
 }
 
 impl FixedView {
@@ -25,11 +28,37 @@ impl FixedView {
             projection: Perspective3::new(800.0 / 600.0, fov, znear, zfar),
             proj: na::one(),
             inv_proj: na::one(),
+            reversed_depth: false,
+            infinite_far: false,
         }
     }
 
+    /// Makes the projection map the near plane to the greatest depth, and the far plane to the
+    /// smallest.
+    pub fn set_reversed_depth(&mut self, reversed: bool) {
+        self.reversed_depth = reversed;
+        self.update_projviews();
+    }
+
+    /// Moves the far clipping plane to infinity.
+    ///
+    /// `clip_planes` still returns the `zfar` given at construction.
+    pub fn set_infinite_far_plane(&mut self, infinite: bool) {
+        self.infinite_far = infinite;
+        self.update_projviews();
+    }
+
+    /// Whether the far clipping plane is at infinity.
+    pub fn infinite_far_plane(&self) -> bool {
+        self.infinite_far
+    }
+
     fn update_projviews(&mut self) {
-        self.proj = *self.projection.as_matrix();
+        self.proj = projection::perspective_matrix(
+            &self.projection,
+            self.reversed_depth,
+            self.infinite_far,
+        );
         let _ = self
             .proj
             .try_inverse()
@@ -42,6 +71,14 @@ impl Camera for FixedView {
         (self.projection.znear(), self.projection.zfar())
     }
 
+    fn reversed_depth(&self) -> bool {
+        self.reversed_depth
+    }
+
+    fn depth_range(&self) -> (f32, f32) {
+        projection::depth_range(&self.projection, self.reversed_depth, self.infinite_far)
+    }
+
     fn view_transform(&self) -> Isometry3<f32> {
         Isometry3::identity()
     }
@@ -72,6 +109,10 @@ impl Camera for FixedView {
         view.upload(&view_mat);
     }
 
+    fn pass_matrices(&self, _: usize) -> (Matrix4<f32>, Matrix4<f32>) {
+        (self.proj, Matrix4::identity())
+    }
+
     fn transformation(&self) -> Matrix4<f32> {
         self.proj
     }
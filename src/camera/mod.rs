@@ -2,13 +2,17 @@
 
 pub use self::arc_ball::ArcBall;
 pub use self::camera::Camera;
-pub use self::first_person::FirstPerson;
+pub use self::camera_path::{CameraKeyframe, CameraPath, CameraPose, Easing};
+pub use self::first_person::{FirstPerson, GamepadBindings};
 pub use self::first_person_stereo::FirstPersonStereo;
 pub use self::fixed_view::FixedView;
+pub use self::projection::Projection;
 
 mod arc_ball;
 #[doc(hidden)]
 pub mod camera;
+mod camera_path;
 mod first_person;
 mod first_person_stereo;
 mod fixed_view;
+mod projection;
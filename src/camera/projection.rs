@@ -0,0 +1,98 @@
+//! Perspective and orthographic projections, with reversed depths or an infinite far plane.
+
+use na::{Matrix4, Orthographic3, Perspective3};
+
+/// The kind of projection of a camera.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum Projection {
+    /// A perspective projection with the given vertical field of view, in radians.
+    Perspective {
+        /// The vertical field of view, in radians.
+        fovy: f32,
+    },
+    /// An orthographic projection of a view volume of the given height, in scene units.
+    ///
+    /// The width of the view volume follows the aspect ratio of the window.
+    Orthographic {
+        /// The height of the view volume, in scene units.
+        height: f32,
+    },
+}
+
+// The projection matrix of `projection`, with the depth mapping selected by the options.
+//
+// Reversed depths map the near plane to the normalized device depth 1, and the far plane to -1.
+// An infinite far plane ignores the `zfar` of `projection`.
+pub(crate) fn perspective_matrix(
+    projection: &Perspective3<f32>,
+    reversed_depth: bool,
+    infinite_far: bool,
+) -> Matrix4<f32> {
+    let mut proj = *projection.as_matrix();
+    let (znear, zfar) = (projection.znear(), projection.zfar());
+
+    // Only the row computing the depth differs from the standard projection.
+    let (m22, m23) = match (reversed_depth, infinite_far) {
+        (false, false) => return proj,
+        (false, true) => (-1.0, -2.0 * znear),
+        (true, false) => (
+            (zfar + znear) / (zfar - znear),
+            2.0 * zfar * znear / (zfar - znear),
+        ),
+        (true, true) => (1.0, 2.0 * znear),
+    };
+
+    proj[(2, 2)] = m22;
+    proj[(2, 3)] = m23;
+    proj
+}
+
+// The normalized device depths of the near plane, and of the plane at the distance `zfar` of
+// `projection`, which is finite even if the far plane is at infinity.
+pub(crate) fn depth_range(
+    projection: &Perspective3<f32>,
+    reversed_depth: bool,
+    infinite_far: bool,
+) -> (f32, f32) {
+    let far = if infinite_far {
+        1.0 - 2.0 * projection.znear() / projection.zfar()
+    } else {
+        1.0
+    };
+
+    if reversed_depth {
+        (1.0, -far)
+    } else {
+        (-1.0, far)
+    }
+}
+
+// The orthographic projection matrix of a view volume `height` units high, centered on the view
+// direction, with the clipping planes of `projection`.
+//
+// The depth of an orthographic projection is linear, so its far plane cannot be at infinity.
+pub(crate) fn orthographic_matrix(
+    projection: &Perspective3<f32>,
+    height: f32,
+    reversed_depth: bool,
+) -> Matrix4<f32> {
+    let (half_w, half_h) = (0.5 * height * projection.aspect(), 0.5 * height);
+    let (znear, zfar) = (projection.znear(), projection.zfar());
+    let mut proj = *Orthographic3::new(-half_w, half_w, -half_h, half_h, znear, zfar).as_matrix();
+
+    if reversed_depth {
+        proj[(2, 2)] = -proj[(2, 2)];
+        proj[(2, 3)] = -proj[(2, 3)];
+    }
+
+    proj
+}
+
+// The normalized device depths of the near and far planes of an orthographic projection.
+pub(crate) fn orthographic_depth_range(reversed_depth: bool) -> (f32, f32) {
+    if reversed_depth {
+        (1.0, -1.0)
+    } else {
+        (-1.0, 1.0)
+    }
+}
@@ -0,0 +1,364 @@
+//! Colors, with conversions from hexadecimal codes, names, and the HSV and HSL color spaces.
+//!
+//! All the methods taking a color accept anything convertible to a `Color`, e.g., a `Point3`, an
+//! array or a tuple of three components:
+//!
+//! ```no_run
+//! # use kiss3d::window::Window;
+//! # use kiss3d::nalgebra::Point3;
+//! use kiss3d::color::Color;
+//!
+//! let mut window = Window::new("Kiss3d: colors");
+//! let mut cube = window.add_cube(1.0, 1.0, 1.0);
+//!
+//! cube.set_color(Color::from_hex(0xff8800));
+//! cube.set_color(Color::named("steelblue").unwrap());
+//! cube.set_color(Color::from_hsv(200.0, 0.5, 0.9));
+//! cube.set_color((1.0, 0.0, 0.0));
+//! window.set_background_color(Color::BLACK.lerp(&Color::WHITE, 0.1));
+//! window.draw_line(&Point3::origin(), &Point3::new(1.0, 0.0, 0.0), &Color::RED);
+//! ```
+
+use na::{Point3, Vector3};
+
+/// A RGB color with components between `0.0` and `1.0`, encoded in the sRGB color space.
+///
+/// This is the space colors are usually picked in, e.g., with hexadecimal codes or color
+/// pickers. Use `from_linear` and `to_linear` to convert from and to linear intensities, e.g.,
+/// to blend colors physically.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Color {
+    /// The red component.
+    pub r: f32,
+    /// The green component.
+    pub g: f32,
+    /// The blue component.
+    pub b: f32,
+}
+
+// The CSS colors usable with `Color::named`.
+const NAMED: [(&str, u32); 24] = [
+    ("black", 0x000000),
+    ("white", 0xffffff),
+    ("red", 0xff0000),
+    ("green", 0x008000),
+    ("lime", 0x00ff00),
+    ("blue", 0x0000ff),
+    ("yellow", 0xffff00),
+    ("cyan", 0x00ffff),
+    ("magenta", 0xff00ff),
+    ("gray", 0x808080),
+    ("grey", 0x808080),
+    ("silver", 0xc0c0c0),
+    ("darkgray", 0xa9a9a9),
+    ("lightgray", 0xd3d3d3),
+    ("orange", 0xffa500),
+    ("purple", 0x800080),
+    ("pink", 0xffc0cb),
+    ("brown", 0xa52a2a),
+    ("navy", 0x000080),
+    ("teal", 0x008080),
+    ("olive", 0x808000),
+    ("maroon", 0x800000),
+    ("gold", 0xffd700),
+    ("steelblue", 0x4682b4),
+];
+
+impl Color {
+    /// Black.
+    pub const BLACK: Color = Color::new(0.0, 0.0, 0.0);
+    /// White.
+    pub const WHITE: Color = Color::new(1.0, 1.0, 1.0);
+    /// Pure red.
+    pub const RED: Color = Color::new(1.0, 0.0, 0.0);
+    /// Pure green.
+    pub const GREEN: Color = Color::new(0.0, 1.0, 0.0);
+    /// Pure blue.
+    pub const BLUE: Color = Color::new(0.0, 0.0, 1.0);
+    /// Yellow.
+    pub const YELLOW: Color = Color::new(1.0, 1.0, 0.0);
+    /// Cyan.
+    pub const CYAN: Color = Color::new(0.0, 1.0, 1.0);
+    /// Magenta.
+    pub const MAGENTA: Color = Color::new(1.0, 0.0, 1.0);
+    /// Medium gray.
+    pub const GRAY: Color = Color::new(0.5, 0.5, 0.5);
+
+    /// Creates a color from its sRGB components.
+    #[inline]
+    pub const fn new(r: f32, g: f32, b: f32) -> Color {
+        Color { r, g, b }
+    }
+
+    /// Creates a color from its 8-bits sRGB components.
+    #[inline]
+    pub fn from_rgb8(r: u8, g: u8, b: u8) -> Color {
+        Color::new(r as f32 / 255.0, g as f32 / 255.0, b as f32 / 255.0)
+    }
+
+    /// Creates a color from a hexadecimal code, e.g., `0xff8800`.
+    #[inline]
+    pub fn from_hex(hex: u32) -> Color {
+        Color::from_rgb8((hex >> 16) as u8, (hex >> 8) as u8, hex as u8)
+    }
+
+    /// Parses a hexadecimal color code, e.g., `"#ff8800"`, `"ff8800"` or `"#f80"`.
+    pub fn parse_hex(code: &str) -> Option<Color> {
+        let digits = code.strip_prefix('#').unwrap_or(code);
+
+        if !digits.chars().all(|c| c.is_ascii_hexdigit()) {
+            return None;
+        }
+
+        match digits.len() {
+            6 => u32::from_str_radix(digits, 16).ok().map(Color::from_hex),
+            3 => {
+                // Each digit is repeated, e.g., `f80` stands for `ff8800`.
+                let short = u32::from_str_radix(digits, 16).ok()?;
+                let expand = |d: u32| (d & 0xf) * 0x11;
+
+                Some(Color::from_hex(
+                    (expand(short >> 8) << 16) | (expand(short >> 4) << 8) | expand(short),
+                ))
+            }
+            _ => None,
+        }
+    }
+
+    /// The color with the given CSS name, e.g., `"orange"` or `"steelblue"`, or a hexadecimal
+    /// code starting with `#`.
+    ///
+    /// The names are case-insensitive. Only a subset of the CSS names are supported.
+    pub fn named(name: &str) -> Option<Color> {
+        if name.starts_with('#') {
+            return Color::parse_hex(name);
+        }
+
+        NAMED
+            .iter()
+            .find(|(n, _)| n.eq_ignore_ascii_case(name))
+            .map(|(_, hex)| Color::from_hex(*hex))
+    }
+
+    /// The hexadecimal code of this color, with its components clamped to `[0.0, 1.0]`.
+    pub fn to_hex(&self) -> u32 {
+        let [r, g, b] = self.to_rgb8();
+        ((r as u32) << 16) | ((g as u32) << 8) | b as u32
+    }
+
+    /// The 8-bits sRGB components of this color, clamped to `[0.0, 1.0]`.
+    pub fn to_rgb8(&self) -> [u8; 3] {
+        let byte = |c: f32| (c.clamp(0.0, 1.0) * 255.0).round() as u8;
+        [byte(self.r), byte(self.g), byte(self.b)]
+    }
+
+    /// Creates a color from its hue in degrees, saturation, and value.
+    ///
+    /// The saturation and value are between `0.0` and `1.0`. The hue wraps around.
+    pub fn from_hsv(hue: f32, saturation: f32, value: f32) -> Color {
+        let chroma = value * saturation;
+        Color::from_hue_chroma(hue, chroma, value - chroma)
+    }
+
+    /// The hue in degrees, saturation, and value of this color.
+    pub fn to_hsv(&self) -> (f32, f32, f32) {
+        let (hue, chroma, max, _) = self.hue_chroma();
+        let saturation = if max > 0.0 { chroma / max } else { 0.0 };
+
+        (hue, saturation, max)
+    }
+
+    /// Creates a color from its hue in degrees, saturation, and lightness.
+    ///
+    /// The saturation and lightness are between `0.0` and `1.0`. The hue wraps around.
+    pub fn from_hsl(hue: f32, saturation: f32, lightness: f32) -> Color {
+        let chroma = (1.0 - (2.0 * lightness - 1.0).abs()) * saturation;
+        Color::from_hue_chroma(hue, chroma, lightness - chroma / 2.0)
+    }
+
+    /// The hue in degrees, saturation, and lightness of this color.
+    pub fn to_hsl(&self) -> (f32, f32, f32) {
+        let (hue, chroma, max, min) = self.hue_chroma();
+        let lightness = (max + min) / 2.0;
+        let saturation = if lightness > 0.0 && lightness < 1.0 {
+            chroma / (1.0 - (2.0 * lightness - 1.0).abs())
+        } else {
+            0.0
+        };
+
+        (hue, saturation, lightness)
+    }
+
+    // The color of the given hue and chroma, with `offset` added to all its components.
+    fn from_hue_chroma(hue: f32, chroma: f32, offset: f32) -> Color {
+        let h = hue.rem_euclid(360.0) / 60.0;
+        let x = chroma * (1.0 - (h % 2.0 - 1.0).abs());
+        let (r, g, b) = match h as u32 {
+            0 => (chroma, x, 0.0),
+            1 => (x, chroma, 0.0),
+            2 => (0.0, chroma, x),
+            3 => (0.0, x, chroma),
+            4 => (x, 0.0, chroma),
+            _ => (chroma, 0.0, x),
+        };
+
+        Color::new(r + offset, g + offset, b + offset)
+    }
+
+    // The hue in degrees, chroma, and largest and smallest components of this color.
+    fn hue_chroma(&self) -> (f32, f32, f32, f32) {
+        let max = self.r.max(self.g).max(self.b);
+        let min = self.r.min(self.g).min(self.b);
+        let chroma = max - min;
+
+        let hue = if chroma == 0.0 {
+            0.0
+        } else if max == self.r {
+            60.0 * ((self.g - self.b) / chroma).rem_euclid(6.0)
+        } else if max == self.g {
+            60.0 * ((self.b - self.r) / chroma + 2.0)
+        } else {
+            60.0 * ((self.r - self.g) / chroma + 4.0)
+        };
+
+        (hue, chroma, max, min)
+    }
+
+    /// Creates a color from its linear intensities, encoding them in sRGB.
+    pub fn from_linear(r: f32, g: f32, b: f32) -> Color {
+        Color::new(linear_to_srgb(r), linear_to_srgb(g), linear_to_srgb(b))
+    }
+
+    /// The linear intensities of this color.
+    pub fn to_linear(&self) -> Vector3<f32> {
+        Vector3::new(
+            srgb_to_linear(self.r),
+            srgb_to_linear(self.g),
+            srgb_to_linear(self.b),
+        )
+    }
+
+    /// Interpolates linearly between the sRGB components of this color, at `t = 0.0`, and
+    /// `other`, at `t = 1.0`.
+    pub fn lerp(&self, other: &Color, t: f32) -> Color {
+        Color::new(
+            self.r + (other.r - self.r) * t,
+            self.g + (other.g - self.g) * t,
+            self.b + (other.b - self.b) * t,
+        )
+    }
+
+    /// Interpolates between the linear intensities of this color, at `t = 0.0`, and `other`, at
+    /// `t = 1.0`, which avoids the darker midpoints of `lerp`.
+    pub fn lerp_linear(&self, other: &Color, t: f32) -> Color {
+        let c = self.to_linear().lerp(&other.to_linear(), t);
+        Color::from_linear(c.x, c.y, c.z)
+    }
+
+    /// This color with its lightness increased by `amount`, in the HSL color space.
+    pub fn lighten(&self, amount: f32) -> Color {
+        let (h, s, l) = self.to_hsl();
+        Color::from_hsl(h, s, (l + amount).clamp(0.0, 1.0))
+    }
+
+    /// This color with its lightness decreased by `amount`, in the HSL color space.
+    pub fn darken(&self, amount: f32) -> Color {
+        self.lighten(-amount)
+    }
+
+    /// `n` colors of evenly spaced hues, with the given saturation and value.
+    ///
+    /// This gives distinct colors to a few objects, e.g., the parts of an assembly.
+    pub fn palette(n: usize, saturation: f32, value: f32) -> Vec<Color> {
+        (0..n)
+            .map(|i| Color::from_hsv(360.0 * i as f32 / n as f32, saturation, value))
+            .collect()
+    }
+
+    /// The color of the `i`-th element of an unbounded sequence of distinct colors.
+    ///
+    /// The hues are spaced by the golden angle, so consecutive colors are far apart whatever the
+    /// number of elements.
+    pub fn distinct(i: usize) -> Color {
+        const GOLDEN_ANGLE: f32 = 137.507_77;
+        Color::from_hsv(i as f32 * GOLDEN_ANGLE, 0.65, 0.95)
+    }
+
+    /// The components of this color as they are given to the shaders.
+    ///
+    /// The renderer currently works with sRGB-encoded colors, so these are the sRGB components.
+    #[inline]
+    pub fn to_render(&self) -> Point3<f32> {
+        Point3::new(self.r, self.g, self.b)
+    }
+}
+
+/// Converts an sRGB-encoded component to a linear intensity.
+#[inline]
+pub fn srgb_to_linear(c: f32) -> f32 {
+    if c <= 0.040_45 {
+        c / 12.92
+    } else {
+        ((c + 0.055) / 1.055).powf(2.4)
+    }
+}
+
+/// Converts a linear intensity to an sRGB-encoded component.
+#[inline]
+pub fn linear_to_srgb(c: f32) -> f32 {
+    if c <= 0.003_130_8 {
+        c * 12.92
+    } else {
+        1.055 * c.powf(1.0 / 2.4) - 0.055
+    }
+}
+
+impl From<Point3<f32>> for Color {
+    #[inline]
+    fn from(p: Point3<f32>) -> Color {
+        Color::new(p.x, p.y, p.z)
+    }
+}
+
+impl From<&Point3<f32>> for Color {
+    #[inline]
+    fn from(p: &Point3<f32>) -> Color {
+        Color::new(p.x, p.y, p.z)
+    }
+}
+
+impl From<Vector3<f32>> for Color {
+    #[inline]
+    fn from(v: Vector3<f32>) -> Color {
+        Color::new(v.x, v.y, v.z)
+    }
+}
+
+impl From<[f32; 3]> for Color {
+    #[inline]
+    fn from(c: [f32; 3]) -> Color {
+        Color::new(c[0], c[1], c[2])
+    }
+}
+
+impl From<(f32, f32, f32)> for Color {
+    #[inline]
+    fn from(c: (f32, f32, f32)) -> Color {
+        Color::new(c.0, c.1, c.2)
+    }
+}
+
+#[cfg(feature = "mint")]
+impl From<mint::Point3<f32>> for Color {
+    #[inline]
+    fn from(p: mint::Point3<f32>) -> Color {
+        Color::new(p.x, p.y, p.z)
+    }
+}
+
+impl From<Color> for Point3<f32> {
+    #[inline]
+    fn from(c: Color) -> Point3<f32> {
+        c.to_render()
+    }
+}
@@ -0,0 +1,148 @@
+//! Colormaps used to convert scalar values to colors.
+
+use na::Point3;
+
+/// A function mapping scalar values to colors.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Colormap {
+    /// The perceptually-uniform `viridis` colormap, from dark blue to yellow.
+    Viridis,
+    /// The perceptually-uniform `plasma` colormap, from dark blue to yellow through magenta.
+    Plasma,
+    /// The classic rainbow `jet` colormap, from dark blue to dark red.
+    Jet,
+    /// The `turbo` colormap, an improved rainbow colormap.
+    Turbo,
+    /// A palette of ten distinct colors for categorical data.
+    ///
+    /// Values are rounded to the nearest integer before being mapped to a color, wrapping
+    /// around if there are more than ten categories.
+    Categorical,
+    /// A colormap linearly interpolating between evenly spaced colors.
+    Custom(Vec<Point3<f32>>),
+}
+
+const VIRIDIS: [u32; 10] = [
+    0x440154, 0x482878, 0x3e4a89, 0x31688e, 0x26828e, 0x1f9e89, 0x35b779, 0x6dcd59, 0xb4de2c,
+    0xfde725,
+];
+
+const PLASMA: [u32; 10] = [
+    0x0d0887, 0x47039f, 0x7301a8, 0x9c179e, 0xbd3786, 0xd8576b, 0xed7953, 0xfa9e3b, 0xfdc926,
+    0xf0f921,
+];
+
+const CATEGORICAL: [u32; 10] = [
+    0x1f77b4, 0xff7f0e, 0x2ca02c, 0xd62728, 0x9467bd, 0x8c564b, 0xe377c2, 0x7f7f7f, 0xbcbd22,
+    0x17becf,
+];
+
+impl Colormap {
+    /// The color associated to `t`, clamped to `[0.0, 1.0]`.
+    pub fn color(&self, t: f32) -> Point3<f32> {
+        let t = if t.is_nan() { 0.0 } else { t.clamp(0.0, 1.0) };
+
+        match *self {
+            Colormap::Viridis => interpolate_hex(&VIRIDIS, t),
+            Colormap::Plasma => interpolate_hex(&PLASMA, t),
+            Colormap::Jet => {
+                let channel = |offset: f32| (1.5 - (4.0 * t - offset).abs()).clamp(0.0, 1.0);
+                Point3::new(channel(3.0), channel(2.0), channel(1.0))
+            }
+            Colormap::Turbo => {
+                // Polynomial approximation of the turbo colormap.
+                let r = 0.135_721_38
+                    + t * (4.615_392_6
+                        + t * (-42.660_32 + t * (132.131_08 + t * (-152.942_39 + t * 59.286_38))));
+                let g = 0.091_402_61
+                    + t * (2.194_188_4
+                        + t * (4.842_966_6
+                            + t * (-14.185_033 + t * (4.277_298_5 + t * 2.829_566))));
+                let b = 0.106_673_3
+                    + t * (12.641_946
+                        + t * (-60.582_05 + t * (110.362_77 + t * (-89.903_11 + t * 27.348_25))));
+                Point3::new(r.clamp(0.0, 1.0), g.clamp(0.0, 1.0), b.clamp(0.0, 1.0))
+            }
+            Colormap::Categorical => {
+                self.category((t * (CATEGORICAL.len() - 1) as f32).round() as usize)
+            }
+            Colormap::Custom(ref colors) => interpolate(colors, t),
+        }
+    }
+
+    /// The color associated to `value`, with `range.0` and `range.1` mapped to the two ends of
+    /// this colormap.
+    ///
+    /// For the categorical colormap, the value is used directly as the category index.
+    pub fn map(&self, value: f32, range: (f32, f32)) -> Point3<f32> {
+        if let Colormap::Categorical = *self {
+            return self.category(value.max(0.0).round() as usize);
+        }
+
+        let extent = range.1 - range.0;
+
+        if extent.abs() <= f32::EPSILON {
+            self.color(0.5)
+        } else {
+            self.color((value - range.0) / extent)
+        }
+    }
+
+    /// The color of the `i`-th category of the categorical palette.
+    pub fn category(&self, i: usize) -> Point3<f32> {
+        hex_to_color(CATEGORICAL[i % CATEGORICAL.len()])
+    }
+
+    /// Maps each value to a color.
+    ///
+    /// If `range` is `None`, the minimum and maximum of the values are used.
+    pub fn map_all(&self, values: &[f32], range: Option<(f32, f32)>) -> Vec<Point3<f32>> {
+        let range = range.unwrap_or_else(|| value_range(values));
+        values.iter().map(|v| self.map(*v, range)).collect()
+    }
+}
+
+/// The minimum and maximum of a set of values, ignoring NaNs.
+pub fn value_range(values: &[f32]) -> (f32, f32) {
+    let mut range = (f32::MAX, f32::MIN);
+
+    for v in values.iter().filter(|v| !v.is_nan()) {
+        range.0 = range.0.min(*v);
+        range.1 = range.1.max(*v);
+    }
+
+    if range.0 > range.1 {
+        (0.0, 1.0)
+    } else {
+        range
+    }
+}
+
+fn hex_to_color(hex: u32) -> Point3<f32> {
+    Point3::new(
+        ((hex >> 16) & 0xff) as f32,
+        ((hex >> 8) & 0xff) as f32,
+        (hex & 0xff) as f32,
+    ) / 255.0
+}
+
+fn interpolate_hex(colors: &[u32], t: f32) -> Point3<f32> {
+    let x = t * (colors.len() - 1) as f32;
+    let i = (x.floor() as usize).min(colors.len() - 2);
+    let a = hex_to_color(colors[i]);
+    let b = hex_to_color(colors[i + 1]);
+
+    a + (b - a) * (x - i as f32)
+}
+
+fn interpolate(colors: &[Point3<f32>], t: f32) -> Point3<f32> {
+    match colors.len() {
+        0 => Point3::new(1.0, 1.0, 1.0),
+        1 => colors[0],
+        n => {
+            let x = t * (n - 1) as f32;
+            let i = (x.floor() as usize).min(n - 2);
+            colors[i] + (colors[i + 1] - colors[i]) * (x - i as f32)
+        }
+    }
+}
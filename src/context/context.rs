@@ -1,8 +1,10 @@
 #![allow(missing_docs)]
 
+use std::cell::Cell;
+use std::sync::atomic::{AtomicU64, Ordering};
 use std::sync::Once;
 
-use crate::context::GLContext as ContextImpl;
+use crate::context::Backend as ContextImpl;
 use crate::resource::GLPrimitive;
 use na::{Matrix2, Matrix3, Matrix4};
 
@@ -18,8 +20,10 @@ pub struct Program(<ContextImpl as AbstractContext>::Program);
 pub struct Shader(<ContextImpl as AbstractContext>::Shader);
 pub struct Framebuffer(<ContextImpl as AbstractContext>::Framebuffer);
 pub struct Renderbuffer(<ContextImpl as AbstractContext>::Renderbuffer);
-pub struct Texture(<ContextImpl as AbstractContext>::Texture);
+pub struct Texture(Cell<<ContextImpl as AbstractContext>::Texture>);
 pub struct VertexArray(<ContextImpl as AbstractContext>::VertexArray);
+pub struct Query(<ContextImpl as AbstractContext>::Query);
+pub struct SavedState(<ContextImpl as AbstractContext>::SavedState);
 
 impl Drop for Buffer {
     fn drop(&mut self) {
@@ -32,8 +36,16 @@ impl Drop for Buffer {
     }
 }
 
+impl Drop for Query {
+    fn drop(&mut self) {
+        let ctxt = Context::get();
+        verify!(ctxt.delete_query(Some(self)))
+    }
+}
+
 static mut CONTEXT_SINGLETON: Option<Context> = None;
 static CONTEXT_INIT: Once = Once::new();
+static CONTEXT_GENERATION: AtomicU64 = AtomicU64::new(0);
 
 #[derive(Clone)]
 pub struct Context {
@@ -45,6 +57,9 @@ impl Context {
     pub const INT: u32 = ContextImpl::INT;
     pub const UNSIGNED_INT: u32 = ContextImpl::UNSIGNED_INT;
     pub const UNSIGNED_SHORT: u32 = ContextImpl::UNSIGNED_SHORT;
+    pub const SHORT: u32 = ContextImpl::SHORT;
+    pub const BYTE: u32 = ContextImpl::BYTE;
+    pub const HALF_FLOAT: u32 = ContextImpl::HALF_FLOAT;
     pub const STATIC_DRAW: u32 = ContextImpl::STATIC_DRAW;
     pub const DYNAMIC_DRAW: u32 = ContextImpl::DYNAMIC_DRAW;
     pub const STREAM_DRAW: u32 = ContextImpl::STREAM_DRAW;
@@ -53,6 +68,7 @@ impl Context {
     pub const VERTEX_SHADER: u32 = ContextImpl::VERTEX_SHADER;
     pub const FRAGMENT_SHADER: u32 = ContextImpl::FRAGMENT_SHADER;
     pub const COMPILE_STATUS: u32 = ContextImpl::COMPILE_STATUS;
+    pub const LINK_STATUS: u32 = ContextImpl::LINK_STATUS;
     pub const FRAMEBUFFER: u32 = ContextImpl::FRAMEBUFFER;
     pub const RENDERBUFFER: u32 = ContextImpl::RENDERBUFFER;
     pub const DEPTH_ATTACHMENT: u32 = ContextImpl::DEPTH_ATTACHMENT;
@@ -72,6 +88,8 @@ impl Context {
     pub const RGBA: u32 = ContextImpl::RGBA;
     pub const TEXTURE0: u32 = ContextImpl::TEXTURE0;
     pub const TEXTURE1: u32 = ContextImpl::TEXTURE1;
+    pub const TEXTURE2: u32 = ContextImpl::TEXTURE2;
+    pub const TEXTURE3: u32 = ContextImpl::TEXTURE3;
     pub const REPEAT: u32 = ContextImpl::REPEAT;
     pub const MIRRORED_REPEAT: u32 = ContextImpl::MIRRORED_REPEAT;
     pub const LINEAR_MIPMAP_LINEAR: u32 = ContextImpl::LINEAR_MIPMAP_LINEAR;
@@ -86,11 +104,14 @@ impl Context {
     pub const TRIANGLE_STRIP: u32 = ContextImpl::TRIANGLE_STRIP;
     pub const COLOR_BUFFER_BIT: u32 = ContextImpl::COLOR_BUFFER_BIT;
     pub const DEPTH_BUFFER_BIT: u32 = ContextImpl::DEPTH_BUFFER_BIT;
+    pub const STENCIL_BUFFER_BIT: u32 = ContextImpl::STENCIL_BUFFER_BIT;
     pub const CCW: u32 = ContextImpl::CCW;
     pub const DEPTH_TEST: u32 = ContextImpl::DEPTH_TEST;
     pub const SCISSOR_TEST: u32 = ContextImpl::SCISSOR_TEST;
     pub const PROGRAM_POINT_SIZE: u32 = ContextImpl::PROGRAM_POINT_SIZE;
     pub const LEQUAL: u32 = ContextImpl::LEQUAL;
+    pub const GEQUAL: u32 = ContextImpl::GEQUAL;
+    pub const POLYGON_OFFSET_FILL: u32 = ContextImpl::POLYGON_OFFSET_FILL;
     pub const BACK: u32 = ContextImpl::BACK;
     pub const PACK_ALIGNMENT: u32 = ContextImpl::PACK_ALIGNMENT;
     pub const BLEND: u32 = ContextImpl::BLEND;
@@ -100,14 +121,27 @@ impl Context {
     pub const UNPACK_ALIGNMENT: u32 = ContextImpl::UNPACK_ALIGNMENT;
     pub const ALPHA: u32 = ContextImpl::ALPHA;
     pub const RED: u32 = ContextImpl::RED;
-
-    pub fn init(get_ctxt: impl Fn() -> glow::Context) {
+    pub const TIME_ELAPSED: u32 = ContextImpl::TIME_ELAPSED;
+    pub const QUERY_RESULT: u32 = ContextImpl::QUERY_RESULT;
+    pub const QUERY_RESULT_AVAILABLE: u32 = ContextImpl::QUERY_RESULT_AVAILABLE;
+    pub const ANY_SAMPLES_PASSED: u32 = ContextImpl::ANY_SAMPLES_PASSED;
+    pub const MAP_WRITE_BIT: u32 = ContextImpl::MAP_WRITE_BIT;
+    pub const MAP_INVALIDATE_BUFFER_BIT: u32 = ContextImpl::MAP_INVALIDATE_BUFFER_BIT;
+    pub const UNIFORM_BUFFER: u32 = ContextImpl::UNIFORM_BUFFER;
+    pub const GEOMETRY_SHADER: u32 = ContextImpl::GEOMETRY_SHADER;
+    pub const TRANSFORM_FEEDBACK_BUFFER: u32 = ContextImpl::TRANSFORM_FEEDBACK_BUFFER;
+    pub const TRANSFORM_FEEDBACK_PRIMITIVES_WRITTEN: u32 =
+        ContextImpl::TRANSFORM_FEEDBACK_PRIMITIVES_WRITTEN;
+    pub const INTERLEAVED_ATTRIBS: u32 = ContextImpl::INTERLEAVED_ATTRIBS;
+    pub const RASTERIZER_DISCARD: u32 = ContextImpl::RASTERIZER_DISCARD;
+
+    /// Initializes the global context with the backend created by `get_ctxt`.
+    ///
+    /// Only the first call has an effect.
+    pub fn init(get_ctxt: impl FnOnce() -> ContextImpl) {
         unsafe {
             CONTEXT_INIT.call_once(|| {
-                let ctxt = get_ctxt();
-                CONTEXT_SINGLETON = Some(Context {
-                    ctxt: ContextImpl::new(ctxt),
-                });
+                CONTEXT_SINGLETON = Some(Context { ctxt: get_ctxt() });
             });
         }
     }
@@ -120,6 +154,19 @@ impl Context {
         }
     }
 
+    /// A counter incremented each time the context is restored after having been lost.
+    ///
+    /// Resources created with a different generation no longer exist on the GPU.
+    pub fn generation() -> u64 {
+        CONTEXT_GENERATION.load(Ordering::Relaxed)
+    }
+
+    /// Signals that the context has been restored after having been lost: every GPU resource
+    /// created before this call must be re-created.
+    pub(crate) fn invalidate_resources() {
+        let _ = CONTEXT_GENERATION.fetch_add(1, Ordering::Relaxed);
+    }
+
     pub fn get_error(&self) -> GLenum {
         self.ctxt.get_error()
     }
@@ -222,6 +269,72 @@ impl Context {
         self.ctxt.buffer_sub_data(target, offset, data)
     }
 
+    pub fn supports_buffer_mapping(&self) -> bool {
+        self.ctxt.supports_buffer_mapping()
+    }
+
+    /// Maps a range of the buffer bound to `target`. Returns a null pointer on failure.
+    ///
+    /// # Safety
+    /// The returned pointer must not be used after the buffer is unmapped.
+    pub unsafe fn map_buffer_range(
+        &self,
+        target: GLenum,
+        offset: usize,
+        length: usize,
+        access: GLenum,
+    ) -> *mut u8 {
+        self.ctxt.map_buffer_range(target, offset, length, access)
+    }
+
+    pub fn unmap_buffer(&self, target: GLenum) {
+        self.ctxt.unmap_buffer(target)
+    }
+
+    /// The `#version` directive of the shaders able to declare uniform blocks, or `None` if
+    /// uniform buffers are not supported.
+    pub fn uniform_buffer_glsl_version(&self) -> Option<&'static str> {
+        self.ctxt.uniform_buffer_glsl_version()
+    }
+
+    pub fn supports_uniform_buffers(&self) -> bool {
+        self.uniform_buffer_glsl_version().is_some()
+    }
+
+    pub fn geometry_shader_glsl_version(&self) -> Option<&'static str> {
+        self.ctxt.geometry_shader_glsl_version()
+    }
+
+    pub fn supports_geometry_shaders(&self) -> bool {
+        self.geometry_shader_glsl_version().is_some()
+    }
+
+    pub fn transform_feedback_varyings(&self, program: &Program, varyings: &[&str], mode: GLenum) {
+        self.ctxt
+            .transform_feedback_varyings(&program.0, varyings, mode)
+    }
+
+    pub fn begin_transform_feedback(&self, primitive_mode: GLenum) {
+        self.ctxt.begin_transform_feedback(primitive_mode)
+    }
+
+    pub fn end_transform_feedback(&self) {
+        self.ctxt.end_transform_feedback()
+    }
+
+    pub fn bind_buffer_base(&self, target: GLenum, index: u32, buffer: Option<&Buffer>) {
+        self.ctxt
+            .bind_buffer_base(target, index, buffer.map(|e| &e.0))
+    }
+
+    pub fn get_uniform_block_index(&self, program: &Program, name: &str) -> Option<u32> {
+        self.ctxt.get_uniform_block_index(&program.0, name)
+    }
+
+    pub fn uniform_block_binding(&self, program: &Program, index: u32, binding: u32) {
+        self.ctxt.uniform_block_binding(&program.0, index, binding)
+    }
+
     pub fn create_shader(&self, type_: GLenum) -> Option<Shader> {
         self.ctxt.create_shader(type_).map(Shader)
     }
@@ -273,6 +386,14 @@ impl Context {
         self.ctxt.get_shader_info_log(&shader.0)
     }
 
+    pub fn get_program_parameter_int(&self, program: &Program, pname: GLenum) -> Option<i32> {
+        self.ctxt.get_program_parameter_int(&program.0, pname)
+    }
+
+    pub fn get_program_info_log(&self, program: &Program) -> Option<String> {
+        self.ctxt.get_program_info_log(&program.0)
+    }
+
     pub fn vertex_attrib_pointer(
         &self,
         index: u32,
@@ -312,6 +433,11 @@ impl Context {
         self.ctxt.scissor(x, y, width, height)
     }
 
+    /// The `[x, y, width, height]` rectangle of the scissor test.
+    pub fn scissor_box(&self) -> [i32; 4] {
+        self.ctxt.scissor_box()
+    }
+
     pub fn create_framebuffer(&self) -> Option<Framebuffer> {
         self.ctxt.create_framebuffer().map(Framebuffer)
     }
@@ -325,6 +451,11 @@ impl Context {
             .bind_framebuffer(target, framebuffer.map(|e| &e.0))
     }
 
+    /// The framebuffer last bound to `FRAMEBUFFER` through this context, `None` for the screen.
+    pub fn framebuffer_binding(&self) -> Option<Framebuffer> {
+        self.ctxt.framebuffer_binding().map(Framebuffer)
+    }
+
     pub fn delete_framebuffer(&self, framebuffer: Option<&Framebuffer>) {
         self.ctxt.delete_framebuffer(framebuffer.map(|e| &e.0))
     }
@@ -337,8 +468,9 @@ impl Context {
         texture: Option<&Texture>,
         level: i32,
     ) {
+        let texture = texture.map(|e| e.0.get());
         self.ctxt
-            .framebuffer_texture2d(target, attachment, textarget, texture.map(|e| &e.0), level)
+            .framebuffer_texture2d(target, attachment, textarget, texture.as_ref(), level)
     }
 
     pub fn create_renderbuffer(&self) -> Option<Renderbuffer> {
@@ -372,7 +504,8 @@ impl Context {
     }
 
     pub fn bind_texture(&self, target: GLenum, texture: Option<&Texture>) {
-        self.ctxt.bind_texture(target, texture.map(|e| &e.0))
+        let texture = texture.map(|e| e.0.get());
+        self.ctxt.bind_texture(target, texture.as_ref())
     }
 
     pub fn tex_image2d(
@@ -442,15 +575,26 @@ impl Context {
     }
 
     pub fn is_texture(&self, texture: Option<&Texture>) -> bool {
-        self.ctxt.is_texture(texture.map(|e| &e.0))
+        let texture = texture.map(|e| e.0.get());
+        self.ctxt.is_texture(texture.as_ref())
     }
 
     pub fn create_texture(&self) -> Option<Texture> {
-        self.ctxt.create_texture().map(|e| Texture(e))
+        self.ctxt.create_texture().map(|e| Texture(Cell::new(e)))
+    }
+
+    /// Makes `texture` refer to a new, empty, texture, without deleting the previous one.
+    ///
+    /// This is used to re-create textures lost with the context.
+    pub(crate) fn recreate_texture(&self, texture: &Texture) {
+        if let Some(e) = self.ctxt.create_texture() {
+            texture.0.set(e)
+        }
     }
 
     pub fn delete_texture(&self, texture: Option<&Texture>) {
-        self.ctxt.delete_texture(texture.map(|e| &e.0))
+        let texture = texture.map(|e| e.0.get());
+        self.ctxt.delete_texture(texture.as_ref())
     }
 
     pub fn active_texture(&self, texture: GLenum) {
@@ -465,6 +609,28 @@ impl Context {
         self.ctxt.disable(cap)
     }
 
+    /// Forgets the cached GL state, so that the next state changes are all forwarded to GL.
+    ///
+    /// This must be called after modifying the GL state without using this context.
+    pub fn reset_state_cache(&self) {
+        self.ctxt.reset_state_cache()
+    }
+
+    /// Saves the part of the GL state the renderer relies on: the enabled capabilities, the
+    /// viewport and scissor rectangles, the depth, blending, and culling settings, and the
+    /// bound framebuffer and vertex array.
+    pub fn save_state(&self) -> SavedState {
+        SavedState(self.ctxt.save_state())
+    }
+
+    /// Restores a GL state saved by `save_state`, after it has been modified without using this
+    /// context, e.g., by another renderer.
+    ///
+    /// The cached state is reset as by `reset_state_cache`.
+    pub fn restore_state(&self, state: &SavedState) {
+        self.ctxt.restore_state(&state.0)
+    }
+
     pub fn draw_elements(&self, mode: GLenum, count: i32, type_: GLenum, offset: GLintptr) {
         self.ctxt.draw_elements(mode, count, type_, offset)
     }
@@ -473,6 +639,22 @@ impl Context {
         self.ctxt.draw_arrays(mode, first, count)
     }
 
+    pub fn draw_elements_instanced(
+        &self,
+        mode: GLenum,
+        count: i32,
+        type_: GLenum,
+        offset: GLintptr,
+        instance_count: i32,
+    ) {
+        self.ctxt
+            .draw_elements_instanced(mode, count, type_, offset, instance_count)
+    }
+
+    pub fn vertex_attrib_divisor(&self, index: u32, divisor: u32) {
+        self.ctxt.vertex_attrib_divisor(index, divisor)
+    }
+
     pub fn point_size(&self, size: f32) {
         self.ctxt.point_size(size)
     }
@@ -489,6 +671,10 @@ impl Context {
         self.ctxt.clear_color(r, g, b, a)
     }
 
+    pub fn clear_depth(&self, depth: f32) {
+        self.ctxt.clear_depth(depth)
+    }
+
     pub fn polygon_mode(&self, face: GLenum, mode: GLenum) -> bool {
         self.ctxt.polygon_mode(face, mode)
     }
@@ -517,6 +703,19 @@ impl Context {
         self.ctxt.read_pixels(x, y, width, height, format, pixels)
     }
 
+    pub fn read_pixels_f32(
+        &self,
+        x: i32,
+        y: i32,
+        width: i32,
+        height: i32,
+        format: GLenum,
+        pixels: &mut [f32],
+    ) {
+        self.ctxt
+            .read_pixels_f32(x, y, width, height, format, pixels)
+    }
+
     pub fn pixel_storei(&self, pname: GLenum, param: i32) {
         self.ctxt.pixel_storei(pname, param)
     }
@@ -531,13 +730,63 @@ impl Context {
         self.ctxt
             .blend_func_separate(src_rgb, dst_rgb, src_alpha, dst_alpha)
     }
+
+    pub fn supports_timer_queries(&self) -> bool {
+        self.ctxt.supports_timer_queries()
+    }
+
+    pub fn supports_occlusion_queries(&self) -> bool {
+        self.ctxt.supports_occlusion_queries()
+    }
+
+    pub fn supports_instancing(&self) -> bool {
+        self.ctxt.supports_instancing()
+    }
+
+    pub fn color_mask(&self, r: bool, g: bool, b: bool, a: bool) {
+        self.ctxt.color_mask(r, g, b, a)
+    }
+
+    pub fn depth_mask(&self, write: bool) {
+        self.ctxt.depth_mask(write)
+    }
+
+    pub fn polygon_offset(&self, factor: f32, units: f32) {
+        self.ctxt.polygon_offset(factor, units)
+    }
+
+    pub fn create_query(&self) -> Option<Query> {
+        self.ctxt.create_query().map(Query)
+    }
+
+    pub fn delete_query(&self, query: Option<&Query>) {
+        self.ctxt.delete_query(query.map(|e| &e.0))
+    }
+
+    pub fn begin_query(&self, target: GLenum, query: &Query) {
+        self.ctxt.begin_query(target, &query.0)
+    }
+
+    pub fn end_query(&self, target: GLenum) {
+        self.ctxt.end_query(target)
+    }
+
+    pub fn get_query_parameter_u32(&self, query: &Query, pname: GLenum) -> u32 {
+        self.ctxt.get_query_parameter_u32(&query.0, pname)
+    }
 }
 
-pub(crate) trait AbstractContextConst {
+/// The enums of a rendering backend.
+///
+/// These follow the naming of OpenGL; a non-GL backend maps them to its own values.
+pub trait AbstractContextConst {
     const FLOAT: u32;
     const INT: u32;
     const UNSIGNED_INT: u32;
     const UNSIGNED_SHORT: u32;
+    const SHORT: u32;
+    const BYTE: u32;
+    const HALF_FLOAT: u32;
     const STATIC_DRAW: u32;
     const DYNAMIC_DRAW: u32;
     const STREAM_DRAW: u32;
@@ -546,6 +795,7 @@ pub(crate) trait AbstractContextConst {
     const VERTEX_SHADER: u32;
     const FRAGMENT_SHADER: u32;
     const COMPILE_STATUS: u32;
+    const LINK_STATUS: u32;
     const FRAMEBUFFER: u32;
     const RENDERBUFFER: u32;
     const DEPTH_ATTACHMENT: u32;
@@ -565,6 +815,8 @@ pub(crate) trait AbstractContextConst {
     const RGBA: u32;
     const TEXTURE0: u32;
     const TEXTURE1: u32;
+    const TEXTURE2: u32;
+    const TEXTURE3: u32;
     const REPEAT: u32;
     const MIRRORED_REPEAT: u32;
     const LINEAR_MIPMAP_LINEAR: u32;
@@ -579,11 +831,14 @@ pub(crate) trait AbstractContextConst {
     const TRIANGLE_STRIP: u32;
     const COLOR_BUFFER_BIT: u32;
     const DEPTH_BUFFER_BIT: u32;
+    const STENCIL_BUFFER_BIT: u32;
     const CCW: u32;
     const DEPTH_TEST: u32;
     const SCISSOR_TEST: u32;
     const PROGRAM_POINT_SIZE: u32;
     const LEQUAL: u32;
+    const GEQUAL: u32;
+    const POLYGON_OFFSET_FILL: u32;
     const BACK: u32;
     const PACK_ALIGNMENT: u32;
     const BLEND: u32;
@@ -593,17 +848,37 @@ pub(crate) trait AbstractContextConst {
     const UNPACK_ALIGNMENT: u32;
     const ALPHA: u32;
     const RED: u32;
+    const TIME_ELAPSED: u32;
+    const QUERY_RESULT: u32;
+    const QUERY_RESULT_AVAILABLE: u32;
+    const ANY_SAMPLES_PASSED: u32;
+    const MAP_WRITE_BIT: u32;
+    const MAP_INVALIDATE_BUFFER_BIT: u32;
+    const UNIFORM_BUFFER: u32;
+    const GEOMETRY_SHADER: u32;
+    const TRANSFORM_FEEDBACK_BUFFER: u32;
+    const TRANSFORM_FEEDBACK_PRIMITIVES_WRITTEN: u32;
+    const INTERLEAVED_ATTRIBS: u32;
+    const RASTERIZER_DISCARD: u32;
 }
 
-pub(crate) trait AbstractContext {
+/// The operations of a rendering backend.
+///
+/// This follows the OpenGL ES 2.0 API, with a few extensions, on which every renderer of kiss3d
+/// is built. A backend that is not based on OpenGL has to emulate its state machine, e.g., by
+/// recording the bound buffers, textures, and program, and building its pipelines and command
+/// buffers on draw calls.
+pub trait AbstractContext {
     type UniformLocation;
     type Buffer;
     type Shader;
     type Program;
-    type Texture;
+    type Texture: Copy;
     type Framebuffer;
     type Renderbuffer;
     type VertexArray;
+    type Query;
+    type SavedState;
 
     fn get_error(&self) -> GLenum;
     fn uniform_matrix2fv(
@@ -642,6 +917,25 @@ pub(crate) trait AbstractContext {
     fn buffer_data_uninitialized(&self, target: GLenum, len: usize, usage: GLenum);
     fn buffer_data<T: GLPrimitive>(&self, target: GLenum, data: &[T], usage: GLenum);
     fn buffer_sub_data<T: GLPrimitive>(&self, target: GLenum, offset: u32, data: &[T]);
+    fn supports_buffer_mapping(&self) -> bool;
+    /// # Safety
+    /// The returned pointer must not be used after the buffer is unmapped.
+    unsafe fn map_buffer_range(
+        &self,
+        target: GLenum,
+        offset: usize,
+        length: usize,
+        access: GLenum,
+    ) -> *mut u8;
+    fn unmap_buffer(&self, target: GLenum);
+    fn uniform_buffer_glsl_version(&self) -> Option<&'static str>;
+    fn geometry_shader_glsl_version(&self) -> Option<&'static str>;
+    fn transform_feedback_varyings(&self, program: &Self::Program, varyings: &[&str], mode: GLenum);
+    fn begin_transform_feedback(&self, primitive_mode: GLenum);
+    fn end_transform_feedback(&self);
+    fn bind_buffer_base(&self, target: GLenum, index: u32, buffer: Option<&Self::Buffer>);
+    fn get_uniform_block_index(&self, program: &Self::Program, name: &str) -> Option<u32>;
+    fn uniform_block_binding(&self, program: &Self::Program, index: u32, binding: u32);
 
     fn create_shader(&self, type_: GLenum) -> Option<Self::Shader>;
     fn create_program(&self) -> Option<Self::Program>;
@@ -656,6 +950,8 @@ pub(crate) trait AbstractContext {
     fn attach_shader(&self, program: &Self::Program, shader: &Self::Shader);
     fn get_shader_parameter_int(&self, shader: &Self::Shader, pname: GLenum) -> Option<i32>;
     fn get_shader_info_log(&self, shader: &Self::Shader) -> Option<String>;
+    fn get_program_parameter_int(&self, program: &Self::Program, pname: GLenum) -> Option<i32>;
+    fn get_program_info_log(&self, program: &Self::Program) -> Option<String>;
     fn vertex_attrib_pointer(
         &self,
         index: u32,
@@ -677,9 +973,11 @@ pub(crate) trait AbstractContext {
 
     fn viewport(&self, x: i32, y: i32, width: i32, height: i32);
     fn scissor(&self, x: i32, y: i32, width: i32, height: i32);
+    fn scissor_box(&self) -> [i32; 4];
     fn create_framebuffer(&self) -> Option<Self::Framebuffer>;
     fn is_framebuffer(&self, framebuffer: Option<&Self::Framebuffer>) -> bool;
     fn bind_framebuffer(&self, target: GLenum, framebuffer: Option<&Self::Framebuffer>);
+    fn framebuffer_binding(&self) -> Option<Self::Framebuffer>;
     fn delete_framebuffer(&self, framebuffer: Option<&Self::Framebuffer>);
     fn framebuffer_texture2d(
         &self,
@@ -743,15 +1041,28 @@ pub(crate) trait AbstractContext {
 
     fn enable(&self, cap: GLenum);
     fn disable(&self, cap: GLenum);
+    fn reset_state_cache(&self);
+    fn save_state(&self) -> Self::SavedState;
+    fn restore_state(&self, state: &Self::SavedState);
 
     fn draw_elements(&self, mode: GLenum, count: i32, type_: GLenum, offset: GLintptr);
     fn draw_arrays(&self, mode: GLenum, first: i32, count: i32);
+    fn draw_elements_instanced(
+        &self,
+        mode: GLenum,
+        count: i32,
+        type_: GLenum,
+        offset: GLintptr,
+        instance_count: i32,
+    );
+    fn vertex_attrib_divisor(&self, index: u32, divisor: u32);
 
     fn point_size(&self, size: f32);
     fn line_width(&self, size: f32);
 
     fn clear(&self, mask: u32);
     fn clear_color(&self, r: f32, g: f32, b: f32, a: f32);
+    fn clear_depth(&self, depth: f32);
 
     fn polygon_mode(&self, face: GLenum, mode: GLenum) -> bool;
 
@@ -768,8 +1079,29 @@ pub(crate) trait AbstractContext {
         format: GLenum,
         pixels: Option<&mut [u8]>,
     );
+    fn read_pixels_f32(
+        &self,
+        x: i32,
+        y: i32,
+        width: i32,
+        height: i32,
+        format: GLenum,
+        pixels: &mut [f32],
+    );
     fn pixel_storei(&self, pname: GLenum, param: i32);
 
+    fn supports_timer_queries(&self) -> bool;
+    fn supports_occlusion_queries(&self) -> bool;
+    fn supports_instancing(&self) -> bool;
+    fn color_mask(&self, r: bool, g: bool, b: bool, a: bool);
+    fn depth_mask(&self, write: bool);
+    fn polygon_offset(&self, factor: f32, units: f32);
+    fn create_query(&self) -> Option<Self::Query>;
+    fn delete_query(&self, query: Option<&Self::Query>);
+    fn begin_query(&self, target: GLenum, query: &Self::Query);
+    fn end_query(&self, target: GLenum);
+    fn get_query_parameter_u32(&self, query: &Self::Query, pname: GLenum) -> u32;
+
     fn blend_func_separate(
         &self,
         src_rgb: GLenum,
@@ -1,4 +1,7 @@
+use std::cell::RefCell;
+use std::collections::HashMap;
 use std::mem;
+use std::rc::Rc;
 use std::sync::Arc;
 
 use crate::context::{AbstractContext, AbstractContextConst, GLenum, GLintptr};
@@ -10,11 +13,77 @@ use na::{Matrix2, Matrix3, Matrix4};
 #[path = "../error.rs"]
 mod error;
 
+/// A shadow copy of the OpenGL state, used to skip redundant state changes.
+///
+/// A `None` entry means that the actual state is unknown, in which case the GL call is always
+/// performed.
+#[derive(Default)]
+struct StateCache {
+    program: Option<Option<<Context as HasContext>::Program>>,
+    active_texture: Option<GLenum>,
+    // Bound textures, indexed by texture unit and target.
+    textures: HashMap<(GLenum, GLenum), Option<<Context as HasContext>::Texture>>,
+    capabilities: HashMap<GLenum, bool>,
+    scissor: Option<[i32; 4]>,
+}
+
+/// The objects last bound through this context, which the GL state cannot be queried for.
+#[derive(Default)]
+struct Bindings {
+    framebuffer: Option<<Context as HasContext>::Framebuffer>,
+    vertex_array: Option<<Context as HasContext>::VertexArray>,
+}
+
+// The capabilities saved by `save_state`.
+#[cfg(not(target_arch = "wasm32"))]
+const SAVED_CAPABILITIES: [GLenum; 7] = [
+    glow::DEPTH_TEST,
+    glow::BLEND,
+    glow::CULL_FACE,
+    glow::SCISSOR_TEST,
+    glow::STENCIL_TEST,
+    glow::POLYGON_OFFSET_FILL,
+    glow::PROGRAM_POINT_SIZE,
+];
+#[cfg(target_arch = "wasm32")]
+const SAVED_CAPABILITIES: [GLenum; 6] = [
+    glow::DEPTH_TEST,
+    glow::BLEND,
+    glow::CULL_FACE,
+    glow::SCISSOR_TEST,
+    glow::STENCIL_TEST,
+    glow::POLYGON_OFFSET_FILL,
+];
+
+/// The part of the GL state saved by `save_state`, the one the renderer relies on.
+pub struct GLState {
+    capabilities: Vec<(GLenum, bool)>,
+    viewport: [i32; 4],
+    scissor: [i32; 4],
+    depth_mask: bool,
+    depth_func: u32,
+    // The source and destination factors of the colors and alphas.
+    blend: [u32; 4],
+    cull_face: u32,
+    front_face: u32,
+    active_texture: u32,
+    clear_color: [f32; 4],
+    clear_depth: f32,
+    line_width: f32,
+    framebuffer: Option<<Context as HasContext>::Framebuffer>,
+    vertex_array: Option<<Context as HasContext>::VertexArray>,
+}
+
 /// An OpenGL context.
 #[derive(Clone)]
 pub struct GLContext {
     /// The underlying glow context.
+    ///
+    /// If the GL state is modified through this context directly, `reset_state_cache` must be
+    /// called afterward.
     pub context: Arc<Context>,
+    state: Rc<RefCell<StateCache>>,
+    bindings: Rc<RefCell<Bindings>>,
 }
 
 impl GLContext {
@@ -22,8 +91,14 @@ impl GLContext {
     pub fn new(ctxt: Context) -> Self {
         Self {
             context: Arc::new(ctxt),
+            state: Rc::new(RefCell::new(StateCache::default())),
+            bindings: Rc::new(RefCell::new(Bindings::default())),
         }
     }
+
+    fn set_capability(&self, cap: GLenum, enabled: bool) -> bool {
+        self.state.borrow_mut().capabilities.insert(cap, enabled) != Some(enabled)
+    }
 }
 
 impl AbstractContextConst for GLContext {
@@ -31,6 +106,9 @@ impl AbstractContextConst for GLContext {
     const INT: u32 = glow::INT;
     const UNSIGNED_INT: u32 = glow::UNSIGNED_INT;
     const UNSIGNED_SHORT: u32 = glow::UNSIGNED_SHORT;
+    const SHORT: u32 = glow::SHORT;
+    const BYTE: u32 = glow::BYTE;
+    const HALF_FLOAT: u32 = glow::HALF_FLOAT;
     const STATIC_DRAW: u32 = glow::STATIC_DRAW;
     const DYNAMIC_DRAW: u32 = glow::DYNAMIC_DRAW;
     const STREAM_DRAW: u32 = glow::STREAM_DRAW;
@@ -39,6 +117,7 @@ impl AbstractContextConst for GLContext {
     const VERTEX_SHADER: u32 = glow::VERTEX_SHADER;
     const FRAGMENT_SHADER: u32 = glow::FRAGMENT_SHADER;
     const COMPILE_STATUS: u32 = glow::COMPILE_STATUS;
+    const LINK_STATUS: u32 = glow::LINK_STATUS;
     const FRAMEBUFFER: u32 = glow::FRAMEBUFFER;
     const RENDERBUFFER: u32 = glow::RENDERBUFFER;
     const DEPTH_ATTACHMENT: u32 = glow::DEPTH_ATTACHMENT;
@@ -58,6 +137,8 @@ impl AbstractContextConst for GLContext {
     const RGBA: u32 = glow::RGBA;
     const TEXTURE0: u32 = glow::TEXTURE0;
     const TEXTURE1: u32 = glow::TEXTURE1;
+    const TEXTURE2: u32 = glow::TEXTURE2;
+    const TEXTURE3: u32 = glow::TEXTURE3;
     const REPEAT: u32 = glow::REPEAT;
     const MIRRORED_REPEAT: u32 = glow::MIRRORED_REPEAT;
     const LINEAR_MIPMAP_LINEAR: u32 = glow::LINEAR_MIPMAP_LINEAR;
@@ -69,10 +150,13 @@ impl AbstractContextConst for GLContext {
     const TRIANGLE_STRIP: u32 = glow::TRIANGLE_STRIP;
     const COLOR_BUFFER_BIT: u32 = glow::COLOR_BUFFER_BIT;
     const DEPTH_BUFFER_BIT: u32 = glow::DEPTH_BUFFER_BIT;
+    const STENCIL_BUFFER_BIT: u32 = glow::STENCIL_BUFFER_BIT;
     const CCW: u32 = glow::CCW;
     const DEPTH_TEST: u32 = glow::DEPTH_TEST;
     const SCISSOR_TEST: u32 = glow::SCISSOR_TEST;
     const LEQUAL: u32 = glow::LEQUAL;
+    const GEQUAL: u32 = glow::GEQUAL;
+    const POLYGON_OFFSET_FILL: u32 = glow::POLYGON_OFFSET_FILL;
     const BACK: u32 = glow::BACK;
     const PACK_ALIGNMENT: u32 = glow::PACK_ALIGNMENT;
     const PROGRAM_POINT_SIZE: u32 = glow::PROGRAM_POINT_SIZE;
@@ -89,6 +173,18 @@ impl AbstractContextConst for GLContext {
     const RED: u32 = glow::RED;
     #[cfg(target_arch = "wasm32")]
     const RED: u32 = glow::LUMINANCE; // WebGL 1
+    const TIME_ELAPSED: u32 = glow::TIME_ELAPSED;
+    const QUERY_RESULT: u32 = glow::QUERY_RESULT;
+    const QUERY_RESULT_AVAILABLE: u32 = glow::QUERY_RESULT_AVAILABLE;
+    const ANY_SAMPLES_PASSED: u32 = glow::ANY_SAMPLES_PASSED;
+    const MAP_WRITE_BIT: u32 = glow::MAP_WRITE_BIT;
+    const MAP_INVALIDATE_BUFFER_BIT: u32 = glow::MAP_INVALIDATE_BUFFER_BIT;
+    const UNIFORM_BUFFER: u32 = glow::UNIFORM_BUFFER;
+    const GEOMETRY_SHADER: u32 = glow::GEOMETRY_SHADER;
+    const TRANSFORM_FEEDBACK_BUFFER: u32 = glow::TRANSFORM_FEEDBACK_BUFFER;
+    const TRANSFORM_FEEDBACK_PRIMITIVES_WRITTEN: u32 = glow::TRANSFORM_FEEDBACK_PRIMITIVES_WRITTEN;
+    const INTERLEAVED_ATTRIBS: u32 = glow::INTERLEAVED_ATTRIBS;
+    const RASTERIZER_DISCARD: u32 = glow::RASTERIZER_DISCARD;
 }
 
 impl AbstractContext for GLContext {
@@ -100,6 +196,8 @@ impl AbstractContext for GLContext {
     type Renderbuffer = <Context as HasContext>::Renderbuffer;
     type Texture = <Context as HasContext>::Texture;
     type VertexArray = <Context as HasContext>::VertexArray;
+    type Query = <Context as HasContext>::Query;
+    type SavedState = GLState;
 
     fn get_error(&self) -> GLenum {
         unsafe { self.context.get_error() }
@@ -189,6 +287,7 @@ impl AbstractContext for GLContext {
     }
 
     fn bind_vertex_array(&self, vertex_array: Option<&Self::VertexArray>) {
+        self.bindings.borrow_mut().vertex_array = vertex_array.cloned();
         unsafe { self.context.bind_vertex_array(vertex_array.cloned()) }
     }
 
@@ -237,6 +336,106 @@ impl AbstractContext for GLContext {
         }
     }
 
+    #[cfg(not(target_arch = "wasm32"))]
+    fn supports_buffer_mapping(&self) -> bool {
+        let version = self.context.version();
+        let extensions = self.context.supported_extensions();
+
+        (version.major, version.minor) >= (3, 0)
+            || extensions.contains("GL_ARB_map_buffer_range")
+            || extensions.contains("GL_EXT_map_buffer_range")
+    }
+
+    #[cfg(target_arch = "wasm32")]
+    fn supports_buffer_mapping(&self) -> bool {
+        // WebGL does not support mapping buffers.
+        false
+    }
+
+    unsafe fn map_buffer_range(
+        &self,
+        target: GLenum,
+        offset: usize,
+        length: usize,
+        access: GLenum,
+    ) -> *mut u8 {
+        self.context
+            .map_buffer_range(target, offset as i32, length as i32, access)
+    }
+
+    fn unmap_buffer(&self, target: GLenum) {
+        unsafe { self.context.unmap_buffer(target) }
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    fn uniform_buffer_glsl_version(&self) -> Option<&'static str> {
+        let version = self.context.version();
+
+        if version.is_embedded {
+            (version.major >= 3).then_some("#version 300 es")
+        } else {
+            ((version.major, version.minor) >= (3, 1)).then_some("#version 140")
+        }
+    }
+
+    #[cfg(target_arch = "wasm32")]
+    fn uniform_buffer_glsl_version(&self) -> Option<&'static str> {
+        // WebGL 1 does not support uniform buffers.
+        None
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    fn geometry_shader_glsl_version(&self) -> Option<&'static str> {
+        let version = self.context.version();
+
+        if version.is_embedded {
+            ((version.major, version.minor) >= (3, 2)).then_some("#version 320 es")
+        } else {
+            ((version.major, version.minor) >= (3, 2)).then_some("#version 150")
+        }
+    }
+
+    #[cfg(target_arch = "wasm32")]
+    fn geometry_shader_glsl_version(&self) -> Option<&'static str> {
+        // WebGL does not support geometry shaders.
+        None
+    }
+
+    fn transform_feedback_varyings(
+        &self,
+        program: &Self::Program,
+        varyings: &[&str],
+        mode: GLenum,
+    ) {
+        unsafe {
+            self.context
+                .transform_feedback_varyings(*program, varyings, mode)
+        }
+    }
+
+    fn begin_transform_feedback(&self, primitive_mode: GLenum) {
+        unsafe { self.context.begin_transform_feedback(primitive_mode) }
+    }
+
+    fn end_transform_feedback(&self) {
+        unsafe { self.context.end_transform_feedback() }
+    }
+
+    fn bind_buffer_base(&self, target: GLenum, index: u32, buffer: Option<&Self::Buffer>) {
+        unsafe {
+            self.context
+                .bind_buffer_base(target, index, buffer.cloned())
+        }
+    }
+
+    fn get_uniform_block_index(&self, program: &Self::Program, name: &str) -> Option<u32> {
+        unsafe { self.context.get_uniform_block_index(*program, name) }
+    }
+
+    fn uniform_block_binding(&self, program: &Self::Program, index: u32, binding: u32) {
+        unsafe { self.context.uniform_block_binding(*program, index, binding) }
+    }
+
     fn create_shader(&self, type_: GLenum) -> Option<Self::Shader> {
         unsafe { self.context.create_shader(type_).ok() }
     }
@@ -247,6 +446,12 @@ impl AbstractContext for GLContext {
 
     fn delete_program(&self, program: Option<&Self::Program>) {
         if let Some(p) = program {
+            let mut state = self.state.borrow_mut();
+
+            if state.program == Some(Some(*p)) {
+                state.program = None;
+            }
+
             unsafe { self.context.delete_program(*p) }
         }
     }
@@ -286,7 +491,13 @@ impl AbstractContext for GLContext {
     }
 
     fn use_program(&self, program: Option<&Self::Program>) {
-        unsafe { self.context.use_program(program.cloned()) }
+        let program = program.cloned();
+        let mut state = self.state.borrow_mut();
+
+        if state.program != Some(program) {
+            state.program = Some(program);
+            unsafe { self.context.use_program(program) }
+        }
     }
 
     fn attach_shader(&self, program: &Self::Program, shader: &Self::Shader) {
@@ -307,6 +518,20 @@ impl AbstractContext for GLContext {
         unsafe { Some(self.context.get_shader_info_log(*shader)) }
     }
 
+    fn get_program_parameter_int(&self, program: &Self::Program, _pname: GLenum) -> Option<i32> {
+        unsafe {
+            if self.context.get_program_link_status(*program) {
+                Some(1)
+            } else {
+                Some(0)
+            }
+        }
+    }
+
+    fn get_program_info_log(&self, program: &Self::Program) -> Option<String> {
+        unsafe { Some(self.context.get_program_info_log(*program)) }
+    }
+
     fn vertex_attrib_pointer(
         &self,
         index: u32,
@@ -357,9 +582,23 @@ impl AbstractContext for GLContext {
     }
 
     fn scissor(&self, x: i32, y: i32, width: i32, height: i32) {
+        self.state.borrow_mut().scissor = Some([x, y, width, height]);
         unsafe { self.context.scissor(x, y, width, height) }
     }
 
+    fn scissor_box(&self) -> [i32; 4] {
+        if let Some(scissor) = self.state.borrow().scissor {
+            return scissor;
+        }
+
+        let mut scissor = [0; 4];
+        unsafe {
+            self.context
+                .get_parameter_i32_slice(glow::SCISSOR_BOX, &mut scissor)
+        };
+        scissor
+    }
+
     fn create_framebuffer(&self) -> Option<Self::Framebuffer> {
         unsafe { self.context.create_framebuffer().ok() }
     }
@@ -369,9 +608,17 @@ impl AbstractContext for GLContext {
     }
 
     fn bind_framebuffer(&self, target: GLenum, framebuffer: Option<&Self::Framebuffer>) {
+        if target == glow::FRAMEBUFFER {
+            self.bindings.borrow_mut().framebuffer = framebuffer.cloned();
+        }
+
         unsafe { self.context.bind_framebuffer(target, framebuffer.cloned()) }
     }
 
+    fn framebuffer_binding(&self) -> Option<Self::Framebuffer> {
+        self.bindings.borrow().framebuffer
+    }
+
     fn delete_framebuffer(&self, framebuffer: Option<&Self::Framebuffer>) {
         if let Some(f) = framebuffer {
             unsafe { self.context.delete_framebuffer(*f) }
@@ -441,7 +688,17 @@ impl AbstractContext for GLContext {
     }
 
     fn bind_texture(&self, target: GLenum, texture: Option<&Self::Texture>) {
-        unsafe { self.context.bind_texture(target, texture.cloned()) }
+        let texture = texture.cloned();
+        let mut state = self.state.borrow_mut();
+
+        match state.active_texture {
+            Some(unit) => {
+                if state.textures.insert((unit, target), texture) != Some(texture) {
+                    unsafe { self.context.bind_texture(target, texture) }
+                }
+            }
+            None => unsafe { self.context.bind_texture(target, texture) },
+        }
     }
 
     fn tex_image2d(
@@ -546,20 +803,122 @@ impl AbstractContext for GLContext {
 
     fn delete_texture(&self, texture: Option<&Self::Texture>) {
         if let Some(t) = texture {
+            // Deleting a texture unbinds it.
+            for bound in self.state.borrow_mut().textures.values_mut() {
+                if *bound == Some(*t) {
+                    *bound = None;
+                }
+            }
+
             unsafe { self.context.delete_texture(t.clone()) }
         }
     }
 
     fn active_texture(&self, texture: GLenum) {
-        unsafe { self.context.active_texture(texture) }
+        let mut state = self.state.borrow_mut();
+
+        if state.active_texture != Some(texture) {
+            state.active_texture = Some(texture);
+            unsafe { self.context.active_texture(texture) }
+        }
     }
 
     fn enable(&self, cap: GLenum) {
-        unsafe { self.context.enable(cap) }
+        if self.set_capability(cap, true) {
+            unsafe { self.context.enable(cap) }
+        }
     }
 
     fn disable(&self, cap: GLenum) {
-        unsafe { self.context.disable(cap) }
+        if self.set_capability(cap, false) {
+            unsafe { self.context.disable(cap) }
+        }
+    }
+
+    fn reset_state_cache(&self) {
+        *self.state.borrow_mut() = StateCache::default();
+    }
+
+    fn save_state(&self) -> GLState {
+        let bindings = self.bindings.borrow();
+        let mut viewport = [0; 4];
+        let mut clear_color = [0.0; 4];
+
+        unsafe {
+            let gl = &self.context;
+            let capabilities = SAVED_CAPABILITIES
+                .iter()
+                .map(|cap| (*cap, gl.is_enabled(*cap)))
+                .collect();
+            gl.get_parameter_i32_slice(glow::VIEWPORT, &mut viewport);
+            gl.get_parameter_f32_slice(glow::COLOR_CLEAR_VALUE, &mut clear_color);
+            let param = |p| gl.get_parameter_i32(p) as u32;
+
+            GLState {
+                capabilities,
+                viewport,
+                scissor: self.scissor_box(),
+                depth_mask: gl.get_parameter_i32(glow::DEPTH_WRITEMASK) != 0,
+                depth_func: param(glow::DEPTH_FUNC),
+                blend: [
+                    param(glow::BLEND_SRC_RGB),
+                    param(glow::BLEND_DST_RGB),
+                    param(glow::BLEND_SRC_ALPHA),
+                    param(glow::BLEND_DST_ALPHA),
+                ],
+                cull_face: param(glow::CULL_FACE_MODE),
+                front_face: param(glow::FRONT_FACE),
+                active_texture: param(glow::ACTIVE_TEXTURE),
+                clear_color,
+                clear_depth: gl.get_parameter_f32(glow::DEPTH_CLEAR_VALUE),
+                line_width: gl.get_parameter_f32(glow::LINE_WIDTH),
+                framebuffer: bindings.framebuffer,
+                vertex_array: bindings.vertex_array,
+            }
+        }
+    }
+
+    fn restore_state(&self, state: &GLState) {
+        // The state has been modified behind the cache.
+        self.reset_state_cache();
+
+        unsafe {
+            let gl = &self.context;
+
+            for (cap, enabled) in &state.capabilities {
+                if *enabled {
+                    gl.enable(*cap)
+                } else {
+                    gl.disable(*cap)
+                }
+            }
+
+            let [x, y, w, h] = state.viewport;
+            gl.viewport(x, y, w, h);
+            let [x, y, w, h] = state.scissor;
+            gl.scissor(x, y, w, h);
+            gl.depth_mask(state.depth_mask);
+            gl.depth_func(state.depth_func);
+            let [src_rgb, dst_rgb, src_alpha, dst_alpha] = state.blend;
+            gl.blend_func_separate(src_rgb, dst_rgb, src_alpha, dst_alpha);
+            gl.cull_face(state.cull_face);
+            gl.front_face(state.front_face);
+            gl.active_texture(state.active_texture);
+            let [r, g, b, a] = state.clear_color;
+            gl.clear_color(r, g, b, a);
+            gl.clear_depth_f32(state.clear_depth);
+            gl.line_width(state.line_width);
+            gl.bind_framebuffer(glow::FRAMEBUFFER, state.framebuffer);
+            gl.bind_vertex_array(state.vertex_array);
+            gl.use_program(None);
+            gl.bind_buffer(glow::ARRAY_BUFFER, None);
+        }
+
+        let mut cache = self.state.borrow_mut();
+        cache.scissor = Some(state.scissor);
+        cache.program = Some(None);
+        cache.active_texture = Some(state.active_texture);
+        cache.capabilities = state.capabilities.iter().cloned().collect();
     }
 
     fn draw_elements(&self, mode: GLenum, count: i32, type_: GLenum, offset: GLintptr) {
@@ -573,6 +932,24 @@ impl AbstractContext for GLContext {
         unsafe { self.context.draw_arrays(mode, first, count) }
     }
 
+    fn draw_elements_instanced(
+        &self,
+        mode: GLenum,
+        count: i32,
+        type_: GLenum,
+        offset: GLintptr,
+        instance_count: i32,
+    ) {
+        unsafe {
+            self.context
+                .draw_elements_instanced(mode, count, type_, offset as i32, instance_count)
+        }
+    }
+
+    fn vertex_attrib_divisor(&self, index: u32, divisor: u32) {
+        unsafe { self.context.vertex_attrib_divisor(index, divisor) }
+    }
+
     fn point_size(&self, _size: f32) {
         //        unsafe { self.context.point_size(size) }
     }
@@ -589,6 +966,10 @@ impl AbstractContext for GLContext {
         unsafe { self.context.clear_color(r, g, b, a) }
     }
 
+    fn clear_depth(&self, depth: f32) {
+        unsafe { self.context.clear_depth_f32(depth) }
+    }
+
     fn polygon_mode(&self, face: GLenum, mode: GLenum) -> bool {
         unsafe {
             self.context.polygon_mode(face, mode);
@@ -634,6 +1015,31 @@ impl AbstractContext for GLContext {
         }
     }
 
+    fn read_pixels_f32(
+        &self,
+        x: i32,
+        y: i32,
+        width: i32,
+        height: i32,
+        format: GLenum,
+        pixels: &mut [f32],
+    ) {
+        let len = size_of_val(pixels);
+
+        unsafe {
+            let bytes = std::slice::from_raw_parts_mut(pixels.as_mut_ptr() as *mut u8, len);
+            self.context.read_pixels(
+                x,
+                y,
+                width,
+                height,
+                format,
+                Self::FLOAT,
+                glow::PixelPackData::Slice(bytes),
+            );
+        }
+    }
+
     fn pixel_storei(&self, pname: GLenum, param: i32) {
         unsafe { self.context.pixel_store_i32(pname, param) }
     }
@@ -650,4 +1056,95 @@ impl AbstractContext for GLContext {
                 .blend_func_separate(src_rgb, dst_rgb, src_alpha, dst_alpha)
         }
     }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    fn supports_timer_queries(&self) -> bool {
+        let version = self.context.version();
+
+        !version.is_embedded && (version.major, version.minor) >= (3, 3)
+            || self
+                .context
+                .supported_extensions()
+                .contains("GL_ARB_timer_query")
+    }
+
+    #[cfg(target_arch = "wasm32")]
+    fn supports_timer_queries(&self) -> bool {
+        // WebGL 1 does not support queries.
+        false
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    fn supports_occlusion_queries(&self) -> bool {
+        let version = self.context.version();
+        let required = if version.is_embedded { (3, 0) } else { (3, 3) };
+
+        (version.major, version.minor) >= required
+            || self
+                .context
+                .supported_extensions()
+                .contains("GL_ARB_occlusion_query2")
+    }
+
+    #[cfg(target_arch = "wasm32")]
+    fn supports_occlusion_queries(&self) -> bool {
+        // WebGL 1 does not support queries.
+        false
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    fn supports_instancing(&self) -> bool {
+        let version = self.context.version();
+        let required = if version.is_embedded { (3, 0) } else { (3, 3) };
+
+        (version.major, version.minor) >= required
+            || self
+                .context
+                .supported_extensions()
+                .contains("GL_ARB_instanced_arrays")
+    }
+
+    #[cfg(target_arch = "wasm32")]
+    fn supports_instancing(&self) -> bool {
+        // WebGL 2 is reported as OpenGL ES 3.0.
+        self.context.version().major >= 3
+            || self
+                .context
+                .supported_extensions()
+                .contains("ANGLE_instanced_arrays")
+    }
+
+    fn color_mask(&self, r: bool, g: bool, b: bool, a: bool) {
+        unsafe { self.context.color_mask(r, g, b, a) }
+    }
+
+    fn depth_mask(&self, write: bool) {
+        unsafe { self.context.depth_mask(write) }
+    }
+
+    fn polygon_offset(&self, factor: f32, units: f32) {
+        unsafe { self.context.polygon_offset(factor, units) }
+    }
+
+    fn create_query(&self) -> Option<Self::Query> {
+        unsafe { self.context.create_query().ok() }
+    }
+
+    fn delete_query(&self, query: Option<&Self::Query>) {
+        if let Some(q) = query {
+            unsafe { self.context.delete_query(*q) }
+        }
+    }
+
+    fn begin_query(&self, target: GLenum, query: &Self::Query) {
+        unsafe { self.context.begin_query(target, *query) }
+    }
+
+    fn end_query(&self, target: GLenum) {
+        unsafe { self.context.end_query(target) }
+    }
+
+    fn get_query_parameter_u32(&self, query: &Self::Query, pname: GLenum) -> u32 {
+        unsafe { self.context.get_query_parameter_u32(*query, pname) }
+    }
 }
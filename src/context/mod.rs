@@ -1,6 +1,16 @@
 //! Abstractions over OpenGL/WebGL contexts.
+//!
+//! Every GPU call of kiss3d goes through the `Context` singleton, which forwards it to the
+//! `Backend` selected at compile-time. A backend implements the public `AbstractContext` and
+//! `AbstractContextConst` traits of this module, mapping the GL-like calls and enums of
+//! `Context` to its own API: GPU objects are opaque handles of its associated types, and the
+//! shaders are GLSL ES 1.0 sources. `GLContext` is the only backend for now, used for both
+//! OpenGL and WebGL.
 
 pub use self::context::*;
 pub use self::gl_context::GLContext;
 mod context;
 mod gl_context;
+
+/// The backend used by `Context`.
+pub type Backend = GLContext;
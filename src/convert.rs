@@ -0,0 +1,80 @@
+//! Conversions accepted by the public API, allowing math types of other crates.
+//!
+//! Points, vectors and colors are taken as `Into<Point3<f32>>`, `Into<Vector3<f32>>`, etc. so
+//! that, with the `mint` feature enabled, the `mint` types of glam, cgmath or ultraviolet can be
+//! passed directly. The types returned by kiss3d are nalgebra ones, which convert to their `mint`
+//! equivalent with `.into()`.
+//!
+//! Translations and rotations have no `mint` equivalent implementing `Into` toward nalgebra, so
+//! they are taken through the `IntoTranslation3` and `IntoUnitQuaternion` traits instead.
+//!
+//! ```ignore
+//! let position = glam::Vec3::new(0.0, 1.0, 0.0);
+//! let rotation = glam::Quat::from_rotation_y(0.5);
+//!
+//! node.set_local_translation(mint::Vector3::from(position));
+//! node.set_local_rotation(mint::Quaternion::from(rotation));
+//! window.draw_point(&mint::Point3::from(position), &Point3::new(1.0, 0.0, 0.0));
+//!
+//! let eye: mint::Point3<f32> = camera.eye().into();
+//! ```
+
+use na::{Quaternion, Translation3, UnitQuaternion, Vector3};
+
+/// A type convertible to a translation.
+pub trait IntoTranslation3: Copy {
+    /// Converts `self` to a translation.
+    fn into_translation3(self) -> Translation3<f32>;
+}
+
+impl IntoTranslation3 for Translation3<f32> {
+    #[inline]
+    fn into_translation3(self) -> Translation3<f32> {
+        self
+    }
+}
+
+impl IntoTranslation3 for Vector3<f32> {
+    #[inline]
+    fn into_translation3(self) -> Translation3<f32> {
+        Translation3::from(self)
+    }
+}
+
+#[cfg(feature = "mint")]
+impl IntoTranslation3 for mint::Vector3<f32> {
+    #[inline]
+    fn into_translation3(self) -> Translation3<f32> {
+        Translation3::new(self.x, self.y, self.z)
+    }
+}
+
+/// A type convertible to a rotation.
+pub trait IntoUnitQuaternion: Copy {
+    /// Converts `self` to a unit quaternion.
+    fn into_unit_quaternion(self) -> UnitQuaternion<f32>;
+}
+
+impl IntoUnitQuaternion for UnitQuaternion<f32> {
+    #[inline]
+    fn into_unit_quaternion(self) -> UnitQuaternion<f32> {
+        self
+    }
+}
+
+impl IntoUnitQuaternion for Quaternion<f32> {
+    /// Normalizes the quaternion.
+    #[inline]
+    fn into_unit_quaternion(self) -> UnitQuaternion<f32> {
+        UnitQuaternion::from_quaternion(self)
+    }
+}
+
+#[cfg(feature = "mint")]
+impl IntoUnitQuaternion for mint::Quaternion<f32> {
+    /// Normalizes the quaternion.
+    #[inline]
+    fn into_unit_quaternion(self) -> UnitQuaternion<f32> {
+        UnitQuaternion::from_quaternion(self.into())
+    }
+}
@@ -0,0 +1,75 @@
+/// An analog axis of a gamepad.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum GamepadAxis {
+    /// The horizontal axis of the left stick, positive to the right.
+    LeftStickX,
+    /// The vertical axis of the left stick, positive upward.
+    LeftStickY,
+    /// The horizontal axis of the right stick, positive to the right.
+    RightStickX,
+    /// The vertical axis of the right stick, positive upward.
+    RightStickY,
+    /// The left trigger, from `0.0` (released) to `1.0` (fully pressed).
+    LeftTrigger,
+    /// The right trigger, from `0.0` (released) to `1.0` (fully pressed).
+    RightTrigger,
+}
+
+impl GamepadAxis {
+    /// All the gamepad axes.
+    pub const ALL: [GamepadAxis; 6] = [
+        GamepadAxis::LeftStickX,
+        GamepadAxis::LeftStickY,
+        GamepadAxis::RightStickX,
+        GamepadAxis::RightStickY,
+        GamepadAxis::LeftTrigger,
+        GamepadAxis::RightTrigger,
+    ];
+
+    fn index(self) -> usize {
+        self as usize
+    }
+}
+
+/// The state of the axes of a gamepad.
+///
+/// With the `gamepad` feature, the state of the first connected gamepad is read at each frame,
+/// and available with `Canvas::gamepad`. Without it, the state can be provided by the application
+/// with `Window::set_gamepad_state`, e.g., from the browser Gamepad API.
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
+pub struct GamepadState {
+    connected: bool,
+    axes: [f32; 6],
+}
+
+impl GamepadState {
+    /// The state of a disconnected gamepad, with all the axes at rest.
+    pub fn new() -> GamepadState {
+        GamepadState::default()
+    }
+
+    /// Whether a gamepad is connected.
+    pub fn is_connected(&self) -> bool {
+        self.connected
+    }
+
+    /// Marks the gamepad as connected or disconnected.
+    pub fn set_connected(&mut self, connected: bool) {
+        self.connected = connected
+    }
+
+    /// The value of an axis, between `-1.0` and `1.0` for sticks, and `0.0` and `1.0` for
+    /// triggers. Always `0.0` if no gamepad is connected.
+    pub fn axis(&self, axis: GamepadAxis) -> f32 {
+        if self.connected {
+            self.axes[axis.index()]
+        } else {
+            0.0
+        }
+    }
+
+    /// Sets the value of an axis.
+    pub fn set_axis(&mut self, axis: GamepadAxis, value: f32) {
+        self.axes[axis.index()] = value
+    }
+}
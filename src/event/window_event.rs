@@ -17,6 +17,13 @@ pub enum WindowEvent {
     Char(char),
     CharModifiers(char, Modifiers),
     Touch(u64, f64, f64, TouchAction, Modifiers),
+    /// The rendering context has been lost: nothing is rendered until it is restored.
+    ///
+    /// This is only emitted by WebGL canvases, see `Window::is_context_lost`.
+    ContextLost,
+    /// The rendering context has been restored, and the resources managed by kiss3d uploaded
+    /// again.
+    ContextRestored,
 }
 
 use WindowEvent::*;
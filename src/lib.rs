@@ -44,7 +44,7 @@ fn main() {
     let mut window = Window::new("Kiss3d: cube");
     let mut c      = window.add_cube(1.0, 1.0, 1.0);
 
-    c.set_color(1.0, 0.0, 0.0);
+    c.set_color((1.0, 0.0, 0.0));
 
     window.set_light(Light::StickToCamera);
 
@@ -82,7 +82,7 @@ fn main() {
     let mut window = Window::new("Kiss3d: wasm example");
     let mut c = window.add_cube(1.0, 1.0, 1.0);
 
-    c.set_color(1.0, 0.0, 0.0);
+    c.set_color((1.0, 0.0, 0.0));
 
     window.set_light(Light::StickToCamera);
 
@@ -144,9 +144,15 @@ pub extern crate conrod_core as conrod;
 #[cfg(not(target_arch = "wasm32"))]
 extern crate glutin;
 extern crate instant;
+extern crate log;
+#[cfg(feature = "parallel")]
+extern crate rayon;
 #[cfg(feature = "conrod")]
 pub use conrod::widget_ids;
+#[cfg(feature = "mint")]
+pub use mint;
 
+pub use crate::load_error::Error;
 pub use nalgebra;
 pub use ncollide3d;
 
@@ -155,18 +161,32 @@ pub use crate::renderer::line_renderer;
 #[deprecated(note = "Use the `renderer` module instead.")]
 pub use crate::renderer::point_renderer;
 
+pub mod annotations;
+#[cfg(all(feature = "audio", not(target_arch = "wasm32")))]
+pub mod audio;
 pub mod builtin;
 pub mod camera;
+pub mod color;
+pub mod colormap;
 pub mod context;
+pub mod convert;
 mod error;
 pub mod event;
 pub mod light;
+mod load_error;
 pub mod loader;
+pub mod measure;
+pub mod overlay;
 pub mod planar_camera;
 pub mod planar_line_renderer;
 pub mod post_processing;
+pub mod procedural;
+#[cfg(all(feature = "remote", not(target_arch = "wasm32")))]
+pub mod remote;
 pub mod renderer;
 pub mod resource;
 pub mod scene;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod testing;
 pub mod text;
 pub mod window;
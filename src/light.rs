@@ -1,6 +1,16 @@
 //! Lights.
 
-use na::Point3;
+use crate::color::Color;
+use na::{Point3, Vector3};
+use std::cell::RefCell;
+
+/// The maximum number of lights added with `Window::add_light` that light the default material.
+///
+/// The lights added beyond it are ignored.
+pub const MAX_LIGHTS: usize = 8;
+
+// The lights added to the window being rendered, with a counter incremented when they change.
+thread_local!(static LIGHT_SOURCES: RefCell<(Vec<LightSource>, u64)> = const { RefCell::new((Vec::new(), 0)) });
 
 /// The light configuration.
 #[derive(Clone)]
@@ -10,3 +20,204 @@ pub enum Light {
     /// A light superimposed with the camera position.
     StickToCamera,
 }
+
+/// The shape of the light emitted by a `LightSource`.
+#[derive(Clone, Debug, PartialEq)]
+pub enum LightKind {
+    /// A light emitted in every direction from a world position.
+    Point(Point3<f32>),
+    /// A light infinitely far away, whose rays all travel along a world direction.
+    Directional(Vector3<f32>),
+    /// A light emitted from a world position within a cone around a direction.
+    ///
+    /// The light fades out between the inner and the outer angles, in radians, between the
+    /// direction and the sides of the cone.
+    Spot {
+        /// The world position of the light.
+        position: Point3<f32>,
+        /// The direction of the axis of the cone.
+        direction: Vector3<f32>,
+        /// The angle within which the light is not faded out.
+        inner_angle: f32,
+        /// The angle beyond which there is no light.
+        outer_angle: f32,
+    },
+    /// A light emitted by a rectangle, e.g., a softbox or a window, on the side its normal
+    /// `right × up` points to.
+    ///
+    /// The diffuse lighting is the exact integral of the cosine-weighted light over the
+    /// rectangle, which linearly transformed cosines reduce to for diffuse surfaces. The specular
+    /// highlights are approximated with the point of the rectangle closest to the reflected view
+    /// ray. The rectangle is not clipped to the horizon of the lit points, so it lights the
+    /// points it is partly below a bit too much.
+    Rect {
+        /// The world position of the center of the rectangle.
+        position: Point3<f32>,
+        /// Half the width of the rectangle, along its first axis.
+        right: Vector3<f32>,
+        /// Half the height of the rectangle, along its second axis.
+        up: Vector3<f32>,
+    },
+}
+
+/// A colored light added to a window with `Window::add_light`, in addition to its main light.
+#[derive(Clone, Debug, PartialEq)]
+pub struct LightSource {
+    /// The shape and placement of the light.
+    pub kind: LightKind,
+    /// The color of the light.
+    pub color: Color,
+    /// The factor the color of the light is multiplied by.
+    pub intensity: f32,
+    /// The constant, linear and quadratic coefficients of the attenuation of the light with the
+    /// distance `d`, which divides the light by `x + y * d + z * d²`.
+    ///
+    /// Directional lights are not attenuated.
+    pub attenuation: Vector3<f32>,
+}
+
+impl LightSource {
+    /// A white point light at `position`, not attenuated with distance.
+    pub fn point(position: Point3<f32>) -> LightSource {
+        LightSource::new(LightKind::Point(position))
+    }
+
+    /// A white directional light whose rays travel along `direction`.
+    pub fn directional(direction: Vector3<f32>) -> LightSource {
+        LightSource::new(LightKind::Directional(direction))
+    }
+
+    /// A white spot light at `position`, lighting the cone of half-angle `angle` around
+    /// `direction` with a soft edge.
+    pub fn spot(position: Point3<f32>, direction: Vector3<f32>, angle: f32) -> LightSource {
+        LightSource::new(LightKind::Spot {
+            position,
+            direction,
+            inner_angle: angle * 0.8,
+            outer_angle: angle,
+        })
+    }
+
+    /// A white rectangular light of the given size centered at `position`, lighting along
+    /// `direction`.
+    ///
+    /// The width of the rectangle is horizontal, unless `direction` is vertical, in which case
+    /// it is along the `x` axis.
+    pub fn rect(
+        position: Point3<f32>,
+        direction: Vector3<f32>,
+        width: f32,
+        height: f32,
+    ) -> LightSource {
+        let normal = direction.normalize();
+        let right = Vector3::y()
+            .cross(&normal)
+            .try_normalize(1.0e-6)
+            .unwrap_or_else(Vector3::x);
+        let up = normal.cross(&right);
+
+        LightSource::new(LightKind::Rect {
+            position,
+            right: right * (width / 2.0),
+            up: up * (height / 2.0),
+        })
+    }
+
+    fn new(kind: LightKind) -> LightSource {
+        LightSource {
+            kind,
+            color: Color::WHITE,
+            intensity: 1.0,
+            attenuation: Vector3::new(1.0, 0.0, 0.0),
+        }
+    }
+
+    /// Sets the color of this light.
+    pub fn with_color(mut self, color: impl Into<Color>) -> LightSource {
+        self.color = color.into();
+        self
+    }
+
+    /// Sets the factor the color of this light is multiplied by.
+    pub fn with_intensity(mut self, intensity: f32) -> LightSource {
+        self.intensity = intensity;
+        self
+    }
+
+    /// Sets the constant, linear and quadratic attenuation coefficients of this light.
+    pub fn with_attenuation(mut self, constant: f32, linear: f32, quadratic: f32) -> LightSource {
+        self.attenuation = Vector3::new(constant, linear, quadratic);
+        self
+    }
+
+    /// The world position of this light, if it is not directional.
+    pub fn position(&self) -> Option<Point3<f32>> {
+        match self.kind {
+            LightKind::Point(p)
+            | LightKind::Spot { position: p, .. }
+            | LightKind::Rect { position: p, .. } => Some(p),
+            LightKind::Directional(_) => None,
+        }
+    }
+}
+
+// The fraction of the light of a uniform rectangle reaching a point of normal `n`, i.e., the
+// integral of the cosine-weighted light over the rectangle, divided by π. See `LightKind::Rect`.
+pub(crate) fn rect_form_factor(
+    p: &Point3<f32>,
+    n: &Vector3<f32>,
+    position: &Point3<f32>,
+    right: &Vector3<f32>,
+    up: &Vector3<f32>,
+) -> f32 {
+    // The back of the rectangle is dark.
+    if (p - position).dot(&right.cross(up)) <= 0.0 {
+        return 0.0;
+    }
+
+    let corners = [
+        position - right - up,
+        position + right - up,
+        position + right + up,
+        position - right + up,
+    ];
+    let dirs: Vec<_> = corners.iter().map(|c| (c - p).normalize()).collect();
+    let mut sum = Vector3::zeros();
+
+    for i in 0..4 {
+        let (a, b) = (&dirs[i], &dirs[(i + 1) % 4]);
+        let theta = a.dot(b).clamp(-1.0, 1.0).acos();
+
+        if let Some(axis) = a.cross(b).try_normalize(1.0e-12) {
+            sum += axis * theta;
+        }
+    }
+
+    (-sum.dot(n) / (2.0 * std::f32::consts::PI)).max(0.0)
+}
+
+/// The identifier of a light added to a window.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct LightId(pub(crate) u64);
+
+// Sets the lights of the window being rendered, at most `MAX_LIGHTS` of them.
+pub(crate) fn set_light_sources<'a>(lights: impl Iterator<Item = &'a LightSource>) {
+    let lights: Vec<_> = lights.take(MAX_LIGHTS).cloned().collect();
+
+    LIGHT_SOURCES.with(|l| {
+        let mut l = l.borrow_mut();
+
+        if l.0 != lights {
+            l.0 = lights;
+            l.1 += 1;
+        }
+    })
+}
+
+// Calls `f` with the lights of the window being rendered, and the number of times they changed.
+pub(crate) fn with_light_sources<T>(f: impl FnOnce(&[LightSource], u64) -> T) -> T {
+    LIGHT_SOURCES.with(|l| {
+        let l = l.borrow();
+        f(&l.0, l.1)
+    })
+}
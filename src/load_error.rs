@@ -0,0 +1,65 @@
+//! The errors raised while loading resources.
+
+use std::fmt;
+use std::io;
+use std::path::PathBuf;
+
+use crate::resource::ShaderError;
+
+/// An error raised while loading a model, a texture, a shader, or a font.
+#[derive(Debug)]
+pub enum Error {
+    /// A file could not be read, or its content is malformed.
+    Io(PathBuf, io::Error),
+    /// An image could not be decoded.
+    Image(PathBuf, image::ImageError),
+    /// A decoded image could not be uploaded as a texture.
+    Texture(PathBuf, &'static str),
+    /// A shader could not be created.
+    Shader(ShaderError),
+    /// A font could not be parsed.
+    Font(rusttype::Error),
+    /// A glTF file could not be parsed.
+    #[cfg(feature = "gltf")]
+    Gltf(PathBuf, gltf::Error),
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Error::Io(path, e) => write!(f, "unable to read {}: {}", path.display(), e),
+            Error::Image(path, e) => write!(f, "unable to decode {}: {}", path.display(), e),
+            Error::Texture(path, e) => write!(f, "unable to upload {}: {}", path.display(), e),
+            Error::Shader(e) => write!(f, "{}", e),
+            Error::Font(e) => write!(f, "unable to parse the font: {}", e),
+            #[cfg(feature = "gltf")]
+            Error::Gltf(path, e) => write!(f, "unable to parse {}: {}", path.display(), e),
+        }
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Error::Io(_, e) => Some(e),
+            Error::Image(_, e) => Some(e),
+            Error::Texture(..) => None,
+            Error::Shader(e) => Some(e),
+            Error::Font(e) => Some(e),
+            #[cfg(feature = "gltf")]
+            Error::Gltf(_, e) => Some(e),
+        }
+    }
+}
+
+impl From<ShaderError> for Error {
+    fn from(e: ShaderError) -> Error {
+        Error::Shader(e)
+    }
+}
+
+impl From<rusttype::Error> for Error {
+    fn from(e: rusttype::Error) -> Error {
+        Error::Font(e)
+    }
+}
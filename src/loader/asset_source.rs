@@ -0,0 +1,316 @@
+//! Sources the assets are read from.
+//!
+//! Every file read by kiss3d, e.g., models, textures, fonts, shaders, and sounds, is read
+//! through the global asset source. By default, it is the filesystem, but it can be replaced
+//! so that an application can be packaged with its assets, or deployed on the web:
+//!
+//! ```no_run
+//! # use std::path::Path;
+//! # use kiss3d::loader::asset_source::{self, EmbeddedSource};
+//! # use kiss3d::window::Window;
+//! let mut assets = EmbeddedSource::new();
+//! assets.insert(
+//!     "media/rust_logo.obj",
+//!     &include_bytes!("../../examples/media/rust_logo/rust_logo.obj")[..],
+//! );
+//! assets.insert(
+//!     "media/rust_logo.mtl",
+//!     &include_bytes!("../../examples/media/rust_logo/rust_logo.mtl")[..],
+//! );
+//! asset_source::set_global(assets);
+//!
+//! let mut window = Window::new("Kiss3d: embedded assets");
+//! let path = Path::new("media/rust_logo.obj");
+//! window.add_obj(path, Path::new("media"), [0.1, 0.1, 0.1]).unwrap();
+//! ```
+
+use std::borrow::Cow;
+use std::collections::HashMap;
+use std::fs::{self, File};
+use std::io::{Cursor, Error as IoError, ErrorKind, Read, Result as IoResult};
+use std::path::{Component, Path, PathBuf};
+use std::sync::{Arc, RwLock};
+
+use image::{DynamicImage, ImageError, ImageFormat, ImageResult};
+
+#[cfg(feature = "zip")]
+use std::io::Seek;
+#[cfg(feature = "zip")]
+use std::sync::Mutex;
+
+// The source replacing the filesystem, if any.
+static GLOBAL_SOURCE: RwLock<Option<Arc<dyn AssetSource>>> = RwLock::new(None);
+
+/// A source of assets, identified by their paths.
+///
+/// Sources are shared with the threads loading assets in the background, e.g., the texture
+/// streamer, so they must be `Send` and `Sync`.
+pub trait AssetSource: Send + Sync {
+    /// Reads the whole content of the asset at `path`.
+    fn read(&self, path: &Path) -> IoResult<Vec<u8>>;
+
+    /// Opens the asset at `path` for reading, returning it with its size in bytes if known.
+    ///
+    /// The default implementation reads the whole asset with `read`.
+    fn open(&self, path: &Path) -> IoResult<(Box<dyn Read>, Option<u64>)> {
+        let bytes = self.read(path)?;
+        let len = bytes.len() as u64;
+
+        Ok((Box::new(Cursor::new(bytes)), Some(len)))
+    }
+}
+
+/// Replaces the source every asset is read from.
+///
+/// Assets already loaded are not affected.
+pub fn set_global(source: impl AssetSource + 'static) {
+    *GLOBAL_SOURCE.write().unwrap() = Some(Arc::new(source));
+}
+
+/// Reads the assets from the filesystem again, relative to the working directory.
+pub fn reset_global() {
+    *GLOBAL_SOURCE.write().unwrap() = None;
+}
+
+/// Reads the whole content of the asset at `path` from the global source.
+pub fn read(path: &Path) -> IoResult<Vec<u8>> {
+    match global() {
+        Some(source) => source.read(path),
+        None => fs::read(path),
+    }
+}
+
+/// Opens the asset at `path` from the global source, returning it with its size in bytes if
+/// known.
+pub fn open(path: &Path) -> IoResult<(Box<dyn Read>, Option<u64>)> {
+    match global() {
+        Some(source) => source.open(path),
+        None => DirectorySource::new("").open(path),
+    }
+}
+
+/// Reads the content of the asset at `path` from the global source as an UTF-8 string.
+pub fn read_to_string(path: &Path) -> IoResult<String> {
+    String::from_utf8(read(path)?).map_err(|e| IoError::new(ErrorKind::InvalidData, e))
+}
+
+// Reads and decodes the image at `path` from the global source.
+pub(crate) fn read_image(path: &Path) -> ImageResult<DynamicImage> {
+    let bytes = read(path).map_err(ImageError::IoError)?;
+    decode_image(&bytes, path)
+}
+
+// Decodes the content of the image file at `path`, its format being deduced from its extension,
+// or else from its content.
+pub(crate) fn decode_image(bytes: &[u8], path: &Path) -> ImageResult<DynamicImage> {
+    match ImageFormat::from_path(path) {
+        Ok(format) => image::load_from_memory_with_format(bytes, format),
+        Err(_) => image::load_from_memory(bytes),
+    }
+}
+
+// The source is cloned out of the lock so that it is not held while reading.
+fn global() -> Option<Arc<dyn AssetSource>> {
+    GLOBAL_SOURCE.read().unwrap().clone()
+}
+
+// The components of `path` joined with `/`, without the `.` components.
+fn normalize(path: &Path) -> String {
+    let components: Vec<_> = path
+        .components()
+        .filter(|c| *c != Component::CurDir)
+        .map(|c| c.as_os_str().to_string_lossy())
+        .collect();
+
+    components.join("/")
+}
+
+fn not_found(path: &Path) -> IoError {
+    IoError::new(
+        ErrorKind::NotFound,
+        format!("no asset at {}", path.display()),
+    )
+}
+
+/// Assets read from the files of a directory.
+#[derive(Clone, Debug)]
+pub struct DirectorySource {
+    root: PathBuf,
+}
+
+impl DirectorySource {
+    /// A source reading the assets from the files relative to `root`.
+    ///
+    /// Absolute paths are read as is.
+    pub fn new(root: impl Into<PathBuf>) -> DirectorySource {
+        DirectorySource { root: root.into() }
+    }
+
+    /// The directory the assets are read from.
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+}
+
+impl AssetSource for DirectorySource {
+    fn read(&self, path: &Path) -> IoResult<Vec<u8>> {
+        fs::read(self.root.join(path))
+    }
+
+    fn open(&self, path: &Path) -> IoResult<(Box<dyn Read>, Option<u64>)> {
+        let file = File::open(self.root.join(path))?;
+        let len = file.metadata().ok().map(|m| m.len());
+
+        Ok((Box::new(file), len))
+    }
+}
+
+/// Assets stored in memory, e.g., embedded in the binary with `include_bytes!`.
+///
+/// The assets that are not found in memory are read from the `fallback` source, if any.
+#[derive(Default)]
+pub struct EmbeddedSource {
+    files: HashMap<String, Cow<'static, [u8]>>,
+    fallback: Option<Box<dyn AssetSource>>,
+}
+
+impl EmbeddedSource {
+    /// A source without any asset.
+    pub fn new() -> EmbeddedSource {
+        EmbeddedSource::default()
+    }
+
+    /// A source without any asset, reading the assets it does not have from `fallback`.
+    pub fn with_fallback(fallback: impl AssetSource + 'static) -> EmbeddedSource {
+        EmbeddedSource {
+            files: HashMap::new(),
+            fallback: Some(Box::new(fallback)),
+        }
+    }
+
+    /// Adds the asset at `path`, replacing the one already there.
+    pub fn insert(&mut self, path: impl AsRef<Path>, data: impl Into<Cow<'static, [u8]>>) {
+        let _ = self.files.insert(normalize(path.as_ref()), data.into());
+    }
+
+    /// Whether this source has an asset at `path`, without looking at the fallback.
+    pub fn contains(&self, path: &Path) -> bool {
+        self.files.contains_key(&normalize(path))
+    }
+}
+
+impl AssetSource for EmbeddedSource {
+    fn read(&self, path: &Path) -> IoResult<Vec<u8>> {
+        match (self.files.get(&normalize(path)), &self.fallback) {
+            (Some(data), _) => Ok(data.to_vec()),
+            (None, Some(fallback)) => fallback.read(path),
+            (None, None) => Err(not_found(path)),
+        }
+    }
+}
+
+#[cfg(feature = "zip")]
+trait ReadSeek: Read + Seek + Send {}
+
+#[cfg(feature = "zip")]
+impl<T: Read + Seek + Send> ReadSeek for T {}
+
+/// Assets packaged in a zip archive.
+///
+/// The paths of the assets are relative to the root of the archive.
+#[cfg(feature = "zip")]
+pub struct ZipSource {
+    archive: Mutex<zip::ZipArchive<Box<dyn ReadSeek>>>,
+}
+
+#[cfg(feature = "zip")]
+impl ZipSource {
+    /// A source reading the assets from the zip archive at `path` on the filesystem.
+    pub fn open(path: &Path) -> IoResult<ZipSource> {
+        ZipSource::new(Box::new(File::open(path)?))
+    }
+
+    /// A source reading the assets from the content of a zip archive.
+    pub fn from_bytes(data: impl Into<Cow<'static, [u8]>>) -> IoResult<ZipSource> {
+        ZipSource::new(Box::new(Cursor::new(data.into())))
+    }
+
+    fn new(reader: Box<dyn ReadSeek>) -> IoResult<ZipSource> {
+        Ok(ZipSource {
+            archive: Mutex::new(zip::ZipArchive::new(reader)?),
+        })
+    }
+}
+
+#[cfg(feature = "zip")]
+impl AssetSource for ZipSource {
+    fn read(&self, path: &Path) -> IoResult<Vec<u8>> {
+        let mut archive = self.archive.lock().unwrap();
+        let mut file = match archive.by_name(&normalize(path)) {
+            Ok(file) => file,
+            Err(zip::result::ZipError::FileNotFound) => return Err(not_found(path)),
+            Err(e) => return Err(e.into()),
+        };
+        let mut bytes = Vec::with_capacity(file.size() as usize);
+        let _ = file.read_to_end(&mut bytes)?;
+
+        Ok(bytes)
+    }
+}
+
+/// Assets downloaded from a web server, for applications running in a browser.
+///
+/// The requests are synchronous, so that the assets are loaded like on the other platforms, but
+/// block the page while they are downloaded.
+#[cfg(target_arch = "wasm32")]
+#[derive(Clone, Debug)]
+pub struct HttpSource {
+    base_url: String,
+}
+
+#[cfg(target_arch = "wasm32")]
+impl HttpSource {
+    /// A source downloading the assets from the URLs relative to `base_url`.
+    pub fn new(base_url: impl Into<String>) -> HttpSource {
+        HttpSource {
+            base_url: base_url.into(),
+        }
+    }
+}
+
+#[cfg(target_arch = "wasm32")]
+impl AssetSource for HttpSource {
+    fn read(&self, path: &Path) -> IoResult<Vec<u8>> {
+        let js_error = |e: wasm_bindgen::JsValue| IoError::other(format!("{:?}", e));
+        let url = format!(
+            "{}/{}",
+            self.base_url.trim_end_matches('/'),
+            normalize(path)
+        );
+        let request = web_sys::XmlHttpRequest::new().map_err(js_error)?;
+
+        request
+            .open_with_async("GET", &url, false)
+            .map_err(js_error)?;
+        // Synchronous requests cannot return binary data, so each byte is read as a character.
+        request
+            .override_mime_type("text/plain; charset=x-user-defined")
+            .map_err(js_error)?;
+        request.send().map_err(js_error)?;
+
+        match request.status().map_err(js_error)? {
+            200..=299 => {}
+            404 => return Err(not_found(path)),
+            status => {
+                return Err(IoError::other(format!(
+                    "unable to download {}: status {}",
+                    url, status
+                )))
+            }
+        }
+
+        let text = request.response_text().map_err(js_error)?;
+
+        Ok(text.unwrap_or_default().chars().map(|c| c as u8).collect())
+    }
+}
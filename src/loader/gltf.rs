@@ -0,0 +1,359 @@
+//! glTF 2.0 loader.
+//!
+//! Imports the default scene of a `.gltf` or `.glb` file, with its node hierarchy, meshes,
+//! base colors, and base color textures. Animations, skins, morph targets, cameras, and the
+//! other material properties are ignored.
+
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::io::{Error as IoError, ErrorKind};
+use std::path::{Path, PathBuf};
+use std::rc::Rc;
+
+use base64::Engine;
+use gltf::image::Source as ImageSource;
+use gltf::material::AlphaMode;
+use gltf::mesh::Mode;
+use gltf::Gltf;
+use na::{Isometry3, Point2, Point3, Quaternion, Translation3, UnitQuaternion, Vector3};
+
+use crate::color::Color;
+use crate::context::Texture;
+use crate::load_error::Error;
+use crate::loader::asset_source;
+use crate::loader::import::{self, ImportOptions};
+use crate::resource::{MaterialManager, Mesh, TextureManager};
+use crate::scene::{Object, SceneNode, TransparencyMode};
+
+// The meshes of a primitive, with the index of its material.
+type Primitive = (Vec<Rc<RefCell<Mesh>>>, Option<usize>);
+
+/// Adds the default scene of the glTF or GLB file at `path` to the children of `parent`, its
+/// coordinates being converted as specified by `import_options`.
+///
+/// Returns the node created as the root of the scene, named after the file, or an error if the
+/// file or one of its buffers or images cannot be read or is malformed.
+pub fn add_to(
+    parent: &mut SceneNode,
+    path: &Path,
+    import_options: &ImportOptions,
+) -> Result<SceneNode, Error> {
+    let bytes = asset_source::read(path).map_err(|e| Error::Io(path.to_path_buf(), e))?;
+    let gltf = Gltf::from_slice(&bytes).map_err(|e| Error::Gltf(path.to_path_buf(), e))?;
+    let base_dir = path.parent().unwrap_or_else(|| Path::new(""));
+    let buffers = load_buffers(&gltf, path, base_dir)?;
+    let mut loader = Loader {
+        path,
+        base_dir,
+        buffers,
+        textures: HashMap::new(),
+        meshes: HashMap::new(),
+        import_options,
+    };
+
+    let scene = gltf.default_scene().or_else(|| gltf.scenes().next());
+    let mut root = SceneNode::new_empty();
+
+    if let Some(name) = path.file_name().and_then(|n| n.to_str()) {
+        root.set_name(name);
+    }
+
+    if let Some(scene) = &scene {
+        for node in scene.nodes() {
+            loader.add_node(&gltf, &mut root, &node)?;
+        }
+    }
+
+    if !import_options.is_identity() {
+        root.data_mut().set_import_options(*import_options);
+    }
+
+    // The root is only added once everything is loaded, so that no node is left behind on error.
+    parent.add_child(root.clone());
+
+    Ok(root)
+}
+
+struct Loader<'a> {
+    path: &'a Path,
+    base_dir: &'a Path,
+    buffers: Vec<Vec<u8>>,
+    // The textures, by image index.
+    textures: HashMap<usize, Rc<Texture>>,
+    // The primitives of each mesh, shared by the nodes instantiating it.
+    meshes: HashMap<usize, Vec<Primitive>>,
+    import_options: &'a ImportOptions,
+}
+
+impl<'a> Loader<'a> {
+    fn load_mesh(&self, mesh: &gltf::Mesh) -> Result<Vec<Primitive>, Error> {
+        let mut primitives = Vec::new();
+
+        for primitive in mesh.primitives() {
+            if primitive.mode() != Mode::Triangles {
+                println!(
+                    "Warning: {}: ignoring a primitive of mesh {} that is not made of triangles.",
+                    self.path.display(),
+                    mesh.index()
+                );
+                continue;
+            }
+
+            let reader =
+                primitive.reader(|buffer| self.buffers.get(buffer.index()).map(|b| &b[..]));
+            let coords: Vec<Point3<f32>> = match reader.read_positions() {
+                Some(positions) => positions.map(Point3::from).collect(),
+                None => continue,
+            };
+            let normals: Option<Vec<Vector3<f32>>> = reader
+                .read_normals()
+                .map(|normals| normals.map(Vector3::from).collect());
+            let uvs: Option<Vec<Point2<f32>>> = reader
+                .read_tex_coords(0)
+                .map(|uvs| uvs.into_f32().map(Point2::from).collect());
+            let indices: Vec<u32> = match reader.read_indices() {
+                Some(indices) => indices.into_u32().collect(),
+                None => (0..coords.len() as u32).collect(),
+            };
+
+            if indices.iter().any(|i| *i as usize >= coords.len()) {
+                return Err(malformed(self.path, "a vertex index is out of bounds"));
+            }
+
+            let meshes = split_meshes(&coords, &normals, &uvs, &indices);
+
+            import::convert_meshes(meshes.iter(), self.import_options);
+
+            primitives.push((
+                meshes
+                    .into_iter()
+                    .map(|m| Rc::new(RefCell::new(m)))
+                    .collect(),
+                primitive.material().index(),
+            ));
+        }
+
+        Ok(primitives)
+    }
+
+    fn add_node(
+        &mut self,
+        gltf: &Gltf,
+        parent: &mut SceneNode,
+        node: &gltf::Node,
+    ) -> Result<(), Error> {
+        let (translation, rotation, scale) = node.transform().decomposed();
+        let [x, y, z, w] = rotation;
+        let transform = Isometry3::from_parts(
+            Translation3::from(Vector3::from(translation)),
+            UnitQuaternion::from_quaternion(Quaternion::new(w, x, y, z)),
+        );
+        let (transform, scale) = self
+            .import_options
+            .convert_transform(&transform, &Vector3::from(scale));
+        let mut group = SceneNode::new(scale, transform, None);
+
+        if let Some(name) = node.name() {
+            group.set_name(name);
+        }
+
+        parent.add_child(group.clone());
+
+        if let Some(mesh) = node.mesh() {
+            if !self.meshes.contains_key(&mesh.index()) {
+                let primitives = self.load_mesh(&mesh)?;
+                let _ = self.meshes.insert(mesh.index(), primitives);
+            }
+
+            let primitives = self.meshes[&mesh.index()].clone();
+
+            for (meshes, material) in primitives {
+                for mesh in meshes {
+                    let object = self.create_object(gltf, mesh, material)?;
+                    let _ = group.add_object(Vector3::from_element(1.0), na::one(), object);
+                }
+            }
+        }
+
+        for child in node.children() {
+            self.add_node(gltf, &mut group, &child)?;
+        }
+
+        Ok(())
+    }
+
+    fn create_object(
+        &mut self,
+        gltf: &Gltf,
+        mesh: Rc<RefCell<Mesh>>,
+        material: Option<usize>,
+    ) -> Result<Object, Error> {
+        let tex = TextureManager::get_global_manager(|tm| tm.get_default());
+        let mat = MaterialManager::get_global_manager(|mm| mm.get_default());
+        let mut object = Object::new(mesh, 1.0, 1.0, 1.0, tex, mat);
+
+        let material = match material.and_then(|i| gltf.materials().nth(i)) {
+            Some(material) => material,
+            None => return Ok(object),
+        };
+
+        let pbr = material.pbr_metallic_roughness();
+        let [r, g, b, a] = pbr.base_color_factor();
+
+        // The factors of glTF are linear.
+        object.set_color(Color::from_linear(r, g, b));
+        object.enable_backface_culling(!material.double_sided());
+
+        if material.alpha_mode() == AlphaMode::Blend && a < 1.0 {
+            object.set_opacity(a);
+            object.set_transparency_mode(TransparencyMode::Blend);
+        }
+
+        if let Some(info) = pbr.base_color_texture() {
+            let texture = self.load_texture(&info.texture().source())?;
+            object.set_texture(texture);
+        }
+
+        Ok(object)
+    }
+
+    fn load_texture(&mut self, image: &gltf::Image) -> Result<Rc<Texture>, Error> {
+        if let Some(texture) = self.textures.get(&image.index()) {
+            return Ok(texture.clone());
+        }
+
+        let (bytes, image_path) = match image.source() {
+            ImageSource::View { view, .. } => {
+                let buffer = &self.buffers[view.buffer().index()];
+                let range = view.offset()..view.offset() + view.length();
+                let bytes = buffer
+                    .get(range)
+                    .ok_or_else(|| malformed(self.path, "a buffer view is out of bounds"))?;
+
+                (bytes.to_vec(), self.path.to_path_buf())
+            }
+            ImageSource::Uri { uri, .. } => load_uri(uri, self.path, self.base_dir)?,
+        };
+
+        let decoded =
+            image::load_from_memory(&bytes).map_err(|e| Error::Image(image_path.clone(), e))?;
+        let name = format!("{}#{}", self.path.display(), image.index());
+        let mut decoded = Some(decoded);
+        let texture =
+            TextureManager::get_global_manager(|tm| tm.add_image(decoded.take().unwrap(), &name));
+
+        let _ = self.textures.insert(image.index(), texture.clone());
+
+        Ok(texture)
+    }
+}
+
+// Reads the buffers of `gltf`, from the binary chunk of a GLB file, data URIs, or files.
+fn load_buffers(gltf: &Gltf, path: &Path, base_dir: &Path) -> Result<Vec<Vec<u8>>, Error> {
+    gltf.buffers()
+        .map(|buffer| {
+            let bytes = match buffer.source() {
+                gltf::buffer::Source::Bin => gltf
+                    .blob
+                    .clone()
+                    .ok_or_else(|| malformed(path, "the binary chunk is missing"))?,
+                gltf::buffer::Source::Uri(uri) => load_uri(uri, path, base_dir)?.0,
+            };
+
+            if bytes.len() < buffer.length() {
+                return Err(malformed(path, "a buffer is shorter than its length"));
+            }
+
+            Ok(bytes)
+        })
+        .collect()
+}
+
+// Reads the content of a data URI, or of a file relative to `base_dir`, and returns it with the
+// path to report errors with.
+fn load_uri(uri: &str, path: &Path, base_dir: &Path) -> Result<(Vec<u8>, PathBuf), Error> {
+    if let Some(data) = uri.strip_prefix("data:") {
+        let encoded = match data.split_once(";base64,") {
+            Some((_, encoded)) => encoded,
+            None => return Err(malformed(path, "a data URI is not encoded in base64")),
+        };
+        let bytes = base64::engine::general_purpose::STANDARD
+            .decode(encoded)
+            .map_err(|_| malformed(path, "a data URI is not valid base64"))?;
+
+        Ok((bytes, path.to_path_buf()))
+    } else {
+        let file = base_dir.join(uri.replace("%20", " "));
+        let bytes = asset_source::read(&file).map_err(|e| Error::Io(file.clone(), e))?;
+
+        Ok((bytes, file))
+    }
+}
+
+fn malformed(path: &Path, err: &str) -> Error {
+    Error::Io(
+        path.to_path_buf(),
+        IoError::new(ErrorKind::InvalidData, err),
+    )
+}
+
+// Builds the meshes of an indexed triangle list, split so that each one can index its vertices
+// with `u16`.
+fn split_meshes(
+    coords: &[Point3<f32>],
+    normals: &Option<Vec<Vector3<f32>>>,
+    uvs: &Option<Vec<Point2<f32>>>,
+    indices: &[u32],
+) -> Vec<Mesh> {
+    const MAX_VERTICES: usize = u16::MAX as usize + 1;
+
+    let mut meshes = Vec::new();
+    let mut remap: HashMap<u32, u16> = HashMap::new();
+    let mut vertices: Vec<u32> = Vec::new();
+    let mut faces: Vec<Point3<u16>> = Vec::new();
+
+    let mut flush = |vertices: &mut Vec<u32>, faces: &mut Vec<Point3<u16>>| {
+        let chunk_coords = vertices.iter().map(|i| coords[*i as usize]).collect();
+        let chunk_normals = normals
+            .as_ref()
+            .map(|ns| vertices.iter().map(|i| ns[*i as usize]).collect());
+        let chunk_uvs = uvs
+            .as_ref()
+            .map(|us| vertices.iter().map(|i| us[*i as usize]).collect());
+
+        meshes.push(Mesh::new(
+            chunk_coords,
+            std::mem::take(faces),
+            chunk_normals,
+            chunk_uvs,
+            false,
+        ));
+        vertices.clear();
+    };
+
+    for triangle in indices.chunks_exact(3) {
+        let missing = triangle.iter().filter(|i| !remap.contains_key(*i)).count();
+
+        if vertices.len() + missing > MAX_VERTICES {
+            flush(&mut vertices, &mut faces);
+            remap.clear();
+        }
+
+        let mut face = [0u16; 3];
+
+        for (k, i) in triangle.iter().enumerate() {
+            face[k] = *remap.entry(*i).or_insert_with(|| {
+                vertices.push(*i);
+                (vertices.len() - 1) as u16
+            });
+        }
+
+        faces.push(Point3::from(face));
+    }
+
+    if !faces.is_empty() {
+        flush(&mut vertices, &mut faces);
+    }
+
+    meshes
+}
@@ -0,0 +1,181 @@
+//! Conversion of imported assets to the coordinate system of kiss3d.
+
+#[cfg(feature = "gltf")]
+use na::{Isometry3, Rotation3, Translation3, UnitQuaternion};
+use na::{Matrix3, Point3, Vector3};
+use std::sync::Arc;
+
+use crate::resource::Mesh;
+
+/// The axis pointing up in the coordinate system of an imported asset.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum UpAxis {
+    /// The `y` axis points up, as in kiss3d.
+    Y,
+    /// The `z` axis points up, as in Blender and most CAD packages.
+    Z,
+}
+
+/// The handedness of the coordinate system of an imported asset.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum Handedness {
+    /// A right-handed coordinate system, as in kiss3d.
+    Right,
+    /// A left-handed coordinate system, e.g., as in Unity or Unreal Engine.
+    Left,
+}
+
+/// How the coordinates of an imported asset are converted to the coordinate system of kiss3d,
+/// which is right-handed with the `y` axis pointing up.
+///
+/// The conversion is applied to the vertices and normals of the imported meshes, and the options
+/// are stored on the node created for the asset, see `SceneNodeData::import_options`.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct ImportOptions {
+    /// The axis pointing up in the asset.
+    pub up_axis: UpAxis,
+    /// The handedness of the coordinate system of the asset.
+    ///
+    /// Left-handed coordinates are mirrored along their depth axis, i.e., `z` if `y` is up and
+    /// `y` if `z` is up, and the winding of their triangles is reversed.
+    pub handedness: Handedness,
+    /// The size of a unit of the asset in the units of the scene, e.g., `0.001` for an asset in
+    /// millimeters in a scene in meters.
+    pub unit_scale: f32,
+}
+
+impl ImportOptions {
+    /// Options leaving the coordinates unchanged.
+    pub fn new() -> ImportOptions {
+        ImportOptions {
+            up_axis: UpAxis::Y,
+            handedness: Handedness::Right,
+            unit_scale: 1.0,
+        }
+    }
+
+    /// Options converting right-handed coordinates with the `z` axis pointing up, e.g., from
+    /// Blender or most CAD packages.
+    pub fn z_up() -> ImportOptions {
+        ImportOptions {
+            up_axis: UpAxis::Z,
+            ..ImportOptions::new()
+        }
+    }
+
+    /// Whether these options leave the coordinates unchanged.
+    pub fn is_identity(&self) -> bool {
+        *self == ImportOptions::new()
+    }
+
+    /// The linear transformation applied to the coordinates of the asset.
+    pub fn matrix(&self) -> Matrix3<f32> {
+        self.orientation() * self.unit_scale
+    }
+
+    // The rotation and reflection part of the conversion, applied to the normals.
+    fn orientation(&self) -> Matrix3<f32> {
+        let mirror = match (self.handedness, self.up_axis) {
+            (Handedness::Right, _) => Vector3::new(1.0, 1.0, 1.0),
+            (Handedness::Left, UpAxis::Y) => Vector3::new(1.0, 1.0, -1.0),
+            (Handedness::Left, UpAxis::Z) => Vector3::new(1.0, -1.0, 1.0),
+        };
+
+        // Maps `z` to `y` and `y` to `-z`, so that the `x` axis is unchanged.
+        let up = match self.up_axis {
+            UpAxis::Y => Matrix3::identity(),
+            UpAxis::Z => Matrix3::new(1.0, 0.0, 0.0, 0.0, 0.0, 1.0, 0.0, -1.0, 0.0),
+        };
+
+        up * Matrix3::from_diagonal(&mirror)
+    }
+
+    // Converts the local transformation and scale of a node, so that it transforms converted
+    // coordinates as the original one transformed the original coordinates.
+    //
+    // The orientation only permutes and negates the axes, so it maps a rotation to a rotation,
+    // and a scale along the axes to another scale along the axes.
+    #[cfg(feature = "gltf")]
+    pub(crate) fn convert_transform(
+        &self,
+        transform: &Isometry3<f32>,
+        scale: &Vector3<f32>,
+    ) -> (Isometry3<f32>, Vector3<f32>) {
+        let orientation = self.orientation();
+        let translation = self.matrix() * transform.translation.vector;
+        let rotation = Rotation3::from_matrix_unchecked(
+            orientation
+                * transform.rotation.to_rotation_matrix().matrix()
+                * orientation.transpose(),
+        );
+        let scale = orientation.abs() * scale;
+
+        (
+            Isometry3::from_parts(
+                Translation3::from(translation),
+                UnitQuaternion::from_rotation_matrix(&rotation),
+            ),
+            scale,
+        )
+    }
+}
+
+impl Default for ImportOptions {
+    fn default() -> ImportOptions {
+        ImportOptions::new()
+    }
+}
+
+// Converts the vertices, normals, and faces of `meshes`, which may share their vertices and
+// normals, to the coordinate system of kiss3d.
+pub(crate) fn convert_meshes<'a>(
+    meshes: impl IntoIterator<Item = &'a Mesh>,
+    options: &ImportOptions,
+) {
+    if options.is_identity() {
+        return;
+    }
+
+    let matrix = options.matrix();
+    let orientation = options.orientation();
+    let mirrored = options.handedness == Handedness::Left;
+    let mut converted_coords = Vec::new();
+    let mut converted_normals = Vec::new();
+
+    for mesh in meshes {
+        if !converted_coords
+            .iter()
+            .any(|c| Arc::ptr_eq(c, mesh.coords()))
+        {
+            converted_coords.push(mesh.coords().clone());
+
+            for coords in mesh.coords().write().unwrap().data_mut().iter_mut() {
+                for pt in coords.iter_mut() {
+                    *pt = (matrix * pt.coords).into();
+                }
+            }
+        }
+
+        if !converted_normals
+            .iter()
+            .any(|n| Arc::ptr_eq(n, mesh.normals()))
+        {
+            converted_normals.push(mesh.normals().clone());
+
+            for normals in mesh.normals().write().unwrap().data_mut().iter_mut() {
+                for n in normals.iter_mut() {
+                    *n = orientation * *n;
+                }
+            }
+        }
+
+        // A reflection reverses the winding of the triangles.
+        if mirrored {
+            for faces in mesh.faces().write().unwrap().data_mut().iter_mut() {
+                for f in faces.iter_mut() {
+                    *f = Point3::new(f.x, f.z, f.y);
+                }
+            }
+        }
+    }
+}
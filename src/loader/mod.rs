@@ -1,4 +1,9 @@
 //! File loading.
 
+pub mod asset_source;
+#[cfg(feature = "gltf")]
+pub mod gltf;
+pub mod import;
 pub mod mtl;
 pub mod obj;
+pub mod progress;
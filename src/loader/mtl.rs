@@ -1,10 +1,9 @@
 //! Simplistic mtl loader.
 
+use crate::loader::asset_source;
 use crate::loader::obj;
 use crate::loader::obj::Words;
 use na::Vector3;
-use std::fs::File;
-use std::io::Read;
 use std::io::Result as IoResult;
 use std::mem;
 use std::path::Path;
@@ -16,13 +15,7 @@ fn error(line: usize, err: &str) -> ! {
 
 /// Parses a mtl file.
 pub fn parse_file(path: &Path) -> IoResult<Vec<MtlMaterial>> {
-    match File::open(path) {
-        Ok(mut file) => {
-            let mut sfile = String::new();
-            file.read_to_string(&mut sfile).map(|_| parse(&sfile[..]))
-        }
-        Err(e) => Err(e),
-    }
+    asset_source::read_to_string(path).map(|sfile| parse(&sfile[..]))
 }
 
 /// Parses a string representing a mtl file.
@@ -1,16 +1,18 @@
 //! Simplistic obj loader.
 
+use crate::loader::asset_source;
+use crate::loader::import::{self, ImportOptions};
 use crate::loader::mtl;
 use crate::loader::mtl::MtlMaterial;
+use crate::loader::progress::{self, LoadProgress};
 use crate::resource::GPUVec;
-use crate::resource::{AllocationType, BufferType, Mesh};
+use crate::resource::{AllocationType, BufferType, Mesh, MeshLoadOptions};
 use na::{Point2, Point3, Vector3};
 use num::Bounded;
 use std::collections::hash_map::Entry;
 use std::collections::HashMap;
-use std::fs::File;
-use std::io::Read;
 use std::io::Result as IoResult;
+use std::io::{Error as IoError, ErrorKind};
 use std::iter::repeat;
 use std::iter::Filter;
 use std::path::{Path, PathBuf};
@@ -44,8 +46,8 @@ pub fn split_words(s: &str) -> Words {
     s.split(is_whitespace).filter(is_not_empty)
 }
 
-fn error(line: usize, err: &str) -> ! {
-    panic!("At line {}: {}", line, err)
+fn error(line: usize, err: &str) -> String {
+    format!("At line {}: {}", line, err)
 }
 
 fn warn(line: usize, err: &str) {
@@ -58,22 +60,147 @@ pub fn parse_file(
     mtl_base_dir: &Path,
     basename: &str,
 ) -> IoResult<Vec<(String, Mesh, Option<MtlMaterial>)>> {
-    match File::open(path) {
-        Ok(mut file) => {
-            let mut sfile = String::new();
-            file.read_to_string(&mut sfile)
-                .map(|_| parse(&sfile[..], mtl_base_dir, basename))
+    let sfile = asset_source::read_to_string(path)?;
+    parse_checked(&sfile[..], basename, &mut |f| read_mtl(mtl_base_dir, f))
+        .map_err(|e| IoError::new(ErrorKind::InvalidData, e))
+}
+
+/// Parses an obj file, the meshes being stored on the GPU as specified by `options`.
+pub fn parse_file_with_options(
+    path: &Path,
+    mtl_base_dir: &Path,
+    basename: &str,
+    options: &MeshLoadOptions,
+) -> IoResult<Vec<(String, Mesh, Option<MtlMaterial>)>> {
+    parse_file_with_import_options(path, mtl_base_dir, basename, options, &ImportOptions::new())
+}
+
+/// Parses an obj file, the meshes being converted to the coordinate system of kiss3d as
+/// specified by `import_options`, and stored on the GPU as specified by `options`.
+pub fn parse_file_with_import_options(
+    path: &Path,
+    mtl_base_dir: &Path,
+    basename: &str,
+    options: &MeshLoadOptions,
+    import_options: &ImportOptions,
+) -> IoResult<Vec<(String, Mesh, Option<MtlMaterial>)>> {
+    parse_file_with_progress(
+        path,
+        mtl_base_dir,
+        basename,
+        options,
+        import_options,
+        &mut |_| {},
+    )
+}
+
+/// Parses an obj file like `parse_file_with_import_options`, reporting the bytes read and the
+/// meshes built to `progress`.
+pub fn parse_file_with_progress(
+    path: &Path,
+    mtl_base_dir: &Path,
+    basename: &str,
+    options: &MeshLoadOptions,
+    import_options: &ImportOptions,
+    progress: &mut dyn FnMut(LoadProgress),
+) -> IoResult<Vec<(String, Mesh, Option<MtlMaterial>)>> {
+    let bytes = progress::read_file(path, progress)?;
+    let string = String::from_utf8(bytes).map_err(|e| IoError::new(ErrorKind::InvalidData, e))?;
+    let mut meshes = parse_checked(&string, basename, &mut |f| read_mtl(mtl_base_dir, f))
+        .map_err(|e| IoError::new(ErrorKind::InvalidData, e))?;
+
+    for (i, (name, _, _)) in meshes.iter().enumerate() {
+        progress(LoadProgress::MeshParsed {
+            name,
+            parsed: i + 1,
+            total: meshes.len(),
+        });
+    }
+
+    import::convert_meshes(meshes.iter().map(|m| &m.1), import_options);
+    apply_load_options(&mut meshes, options);
+    Ok(meshes)
+}
+
+/// Parses a string representing an obj file, the meshes being stored on the GPU as specified by
+/// `options`.
+pub fn parse_with_options(
+    string: &str,
+    mtl_base_dir: &Path,
+    basename: &str,
+    options: &MeshLoadOptions,
+) -> Vec<(String, Mesh, Option<MtlMaterial>)> {
+    let mut meshes = parse(string, mtl_base_dir, basename);
+    apply_load_options(&mut meshes, options);
+    meshes
+}
+
+// The meshes of an obj file share their vertices, so they also share their packed buffers.
+fn apply_load_options(
+    meshes: &mut [(String, Mesh, Option<MtlMaterial>)],
+    options: &MeshLoadOptions,
+) {
+    if let Some(((_, first, _), others)) = meshes.split_first_mut() {
+        first.set_load_options(options);
+
+        for (_, mesh, _) in others {
+            mesh.share_packed_buffers(first);
         }
-        Err(e) => Err(e),
     }
 }
 
 /// Parses a string representing an obj file.
+///
+/// Panics if the string is malformed.
 pub fn parse(
     string: &str,
     mtl_base_dir: &Path,
     basename: &str,
 ) -> Vec<(String, Mesh, Option<MtlMaterial>)> {
+    parse_checked(string, basename, &mut |f| read_mtl(mtl_base_dir, f))
+        .unwrap_or_else(|e| panic!("{}", e))
+}
+
+/// Parses the content of an obj file without touching the filesystem, e.g., a file embedded in
+/// the binary with `include_bytes!`.
+///
+/// `mtl_resolver` is called with the name of each material library referenced by the file, and
+/// returns its content, or `None` if it is not available. Returns an error if the content is not
+/// a valid obj file.
+pub fn parse_from_memory(
+    data: &[u8],
+    basename: &str,
+    options: &MeshLoadOptions,
+    import_options: &ImportOptions,
+    mtl_resolver: &mut dyn FnMut(&str) -> Option<Vec<u8>>,
+) -> IoResult<Vec<(String, Mesh, Option<MtlMaterial>)>> {
+    let string = std::str::from_utf8(data).map_err(|e| IoError::new(ErrorKind::InvalidData, e))?;
+    let mut meshes = parse_checked(string, basename, &mut |filename| {
+        let bytes = mtl_resolver(filename).ok_or_else(|| {
+            IoError::new(
+                ErrorKind::NotFound,
+                format!("unresolved mtllib {}", filename),
+            )
+        })?;
+        let string =
+            String::from_utf8(bytes).map_err(|e| IoError::new(ErrorKind::InvalidData, e))?;
+
+        Ok(mtl::parse(&string))
+    })
+    .map_err(|e| IoError::new(ErrorKind::InvalidData, e))?;
+
+    import::convert_meshes(meshes.iter().map(|m| &m.1), import_options);
+    apply_load_options(&mut meshes, options);
+    Ok(meshes)
+}
+
+// Parses a string representing an obj file, or returns the error of its first malformed line.
+// The material libraries are read by `load_mtl`, given their file names.
+fn parse_checked(
+    string: &str,
+    basename: &str,
+    load_mtl: &mut dyn FnMut(&str) -> IoResult<Vec<MtlMaterial>>,
+) -> Result<Vec<(String, Mesh, Option<MtlMaterial>)>, String> {
     let mut coords: Vec<Coord> = Vec::new();
     let mut normals: Vec<Normal> = Vec::new();
     let mut uvs: Vec<UV> = Vec::new();
@@ -97,10 +224,10 @@ pub fn parse(
             Some(w) => {
                 if !w.is_empty() && w.as_bytes()[0] != b'#' {
                     match w {
-                        "v" => coords.push(Point3::from(parse_v_or_vn(l, words))),
+                        "v" => coords.push(Point3::from(parse_v_or_vn(l, words)?)),
                         "vn" => {
                             if !ignore_normals {
-                                normals.push(parse_v_or_vn(l, words))
+                                normals.push(parse_v_or_vn(l, words)?)
                             }
                         }
                         "f" => parse_f(
@@ -113,10 +240,10 @@ pub fn parse(
                             &mut ignore_normals,
                             &mut groups_ids,
                             curr_group,
-                        ),
+                        )?,
                         "vt" => {
                             if !ignore_uvs {
-                                uvs.push(parse_vt(l, words))
+                                uvs.push(parse_vt(l, words)?)
                             }
                         }
                         "g" => {
@@ -125,7 +252,7 @@ pub fn parse(
                                 .as_ref()
                                 .map(|mtl| group2mtl.insert(curr_group, mtl.clone()));
                         }
-                        "mtllib" => parse_mtllib(l, words, mtl_base_dir, &mut mtllib),
+                        "mtllib" => parse_mtllib(l, words, load_mtl, &mut mtllib),
                         "usemtl" => {
                             curr_group = parse_usemtl(
                                 l,
@@ -155,14 +282,14 @@ pub fn parse(
         println!("Warning: some normals are missing. Dropping normals infos for every vertex.");
     }
 
-    reformat(
+    Ok(reformat(
         coords,
         if ignore_normals { None } else { Some(normals) },
         if ignore_uvs { None } else { Some(uvs) },
         groups_ids,
         groups,
         group2mtl,
-    )
+    ))
 }
 
 fn parse_usemtl<'a>(
@@ -221,17 +348,13 @@ fn parse_usemtl<'a>(
 fn parse_mtllib<'a>(
     l: usize,
     ws: Words<'a>,
-    mtl_base_dir: &Path,
+    load_mtl: &mut dyn FnMut(&str) -> IoResult<Vec<MtlMaterial>>,
     mtllib: &mut HashMap<String, MtlMaterial>,
 ) {
     let filename: Vec<&'a str> = ws.collect();
     let filename = filename.join(" ");
 
-    let mut path = PathBuf::new();
-    path.push(mtl_base_dir);
-    path.push(filename);
-
-    let ms = mtl::parse_file(&path);
+    let ms = load_mtl(&filename);
 
     match ms {
         Ok(ms) => {
@@ -243,29 +366,34 @@ fn parse_mtllib<'a>(
     }
 }
 
-fn parse_v_or_vn(l: usize, mut ws: Words) -> Vector3<f32> {
+fn read_mtl(mtl_base_dir: &Path, filename: &str) -> IoResult<Vec<MtlMaterial>> {
+    let mut path = PathBuf::new();
+    path.push(mtl_base_dir);
+    path.push(filename);
+
+    mtl::parse_file(&path)
+}
+
+fn parse_v_or_vn(l: usize, mut ws: Words) -> Result<Vector3<f32>, String> {
     let sx = ws
         .next()
-        .unwrap_or_else(|| error(l, "3 components were expected, found 0."));
+        .ok_or_else(|| error(l, "3 components were expected, found 0."))?;
     let sy = ws
         .next()
-        .unwrap_or_else(|| error(l, "3 components were expected, found 1."));
+        .ok_or_else(|| error(l, "3 components were expected, found 1."))?;
     let sz = ws
         .next()
-        .unwrap_or_else(|| error(l, "3 components were expected, found 2."));
+        .ok_or_else(|| error(l, "3 components were expected, found 2."))?;
 
     let x: Result<f32, _> = FromStr::from_str(sx);
     let y: Result<f32, _> = FromStr::from_str(sy);
     let z: Result<f32, _> = FromStr::from_str(sz);
 
-    let x =
-        x.unwrap_or_else(|e| error(l, &format!("failed to parse `{}' as a f32: {}", sx, e)[..]));
-    let y =
-        y.unwrap_or_else(|e| error(l, &format!("failed to parse `{}' as a f32: {}", sy, e)[..]));
-    let z =
-        z.unwrap_or_else(|e| error(l, &format!("failed to parse `{}' as a f32: {}", sz, e)[..]));
+    let x = x.map_err(|e| error(l, &format!("failed to parse `{}' as a f32: {}", sx, e)[..]))?;
+    let y = y.map_err(|e| error(l, &format!("failed to parse `{}' as a f32: {}", sy, e)[..]))?;
+    let z = z.map_err(|e| error(l, &format!("failed to parse `{}' as a f32: {}", sz, e)[..]))?;
 
-    Vector3::new(x, y, z)
+    Ok(Vector3::new(x, y, z))
 }
 
 fn parse_f<'a>(
@@ -278,7 +406,7 @@ fn parse_f<'a>(
     ignore_normals: &mut bool,
     groups_ids: &mut Vec<Vec<Point3<u16>>>,
     curr_group: usize,
-) {
+) -> Result<(), String> {
     // Four formats possible: v   v/t   v//n   v/t/n
     let mut i = 0;
     for word in ws {
@@ -289,7 +417,12 @@ fn parse_f<'a>(
                 let idx: Result<i32, _> = FromStr::from_str(w);
                 match idx {
                     Ok(id) => curr_ids[i] = id - 1,
-                    Err(e) => error(l, &format!("failed to parse `{}' as a i32: {}", w, e)[..]),
+                    Err(e) => {
+                        return Err(error(
+                            l,
+                            &format!("failed to parse `{}' as a i32: {}", w, e)[..],
+                        ))
+                    }
                 }
             }
         }
@@ -334,7 +467,10 @@ fn parse_f<'a>(
             z = curr_ids.z;
         }
 
-        assert!(x >= 0 && y >= 0 && z >= 0);
+        if x < 0 || y < 0 || z < 0 {
+            return Err(error(l, "a relative index is out of bounds."));
+        }
+
         groups_ids[curr_group].push(Point3::new(x as u16, y as u16, z as u16));
 
         i += 1;
@@ -347,29 +483,29 @@ fn parse_f<'a>(
             groups_ids[curr_group].push(last);
         }
     }
+
+    Ok(())
 }
 
-fn parse_vt(l: usize, mut ws: Words) -> UV {
+fn parse_vt(l: usize, mut ws: Words) -> Result<UV, String> {
     let _0 = "0.0";
     let sx = ws
         .next()
-        .unwrap_or_else(|| error(l, "at least 2 components were expected, found 0."));
+        .ok_or_else(|| error(l, "at least 2 components were expected, found 0."))?;
     let sy = ws
         .next()
-        .unwrap_or_else(|| error(l, "at least 2 components were expected, found 1."));
+        .ok_or_else(|| error(l, "at least 2 components were expected, found 1."))?;
     // let sz  = ws.next().unwrap_or(_0);
 
     let x: Result<f32, _> = FromStr::from_str(sx);
     let y: Result<f32, _> = FromStr::from_str(sy);
     // let z: Option<f32> = FromStr::from_str(sz);
 
-    let x =
-        x.unwrap_or_else(|e| error(l, &format!("failed to parse `{}' as a f32: {}", sx, e)[..]));
-    let y =
-        y.unwrap_or_else(|e| error(l, &format!("failed to parse `{}' as a f32: {}", sy, e)[..]));
+    let x = x.map_err(|e| error(l, &format!("failed to parse `{}' as a f32: {}", sx, e)[..]))?;
+    let y = y.map_err(|e| error(l, &format!("failed to parse `{}' as a f32: {}", sy, e)[..]))?;
     // let z = z.unwrap_or_else(|| error(l, "failed to parse `" + sz + "' as a f32."));
 
-    Point2::new(x, y)
+    Ok(Point2::new(x, y))
 }
 
 fn parse_g<'a>(
@@ -0,0 +1,102 @@
+//! Progress reporting of the loading of large assets.
+//!
+//! The loading functions taking a progress callback call it repeatedly while they read, parse,
+//! and decode their files, so that an application can show a loading bar. A callback can also
+//! forward the reports to another thread through a channel:
+//!
+//! ```no_run
+//! # use std::path::Path;
+//! # use std::sync::mpsc;
+//! # use kiss3d::loader::progress::LoadProgress;
+//! # use kiss3d::loader::import::ImportOptions;
+//! # use kiss3d::resource::MeshLoadOptions;
+//! # use kiss3d::window::Window;
+//! let mut window = Window::new("Kiss3d: progress");
+//! let (sender, receiver) = mpsc::channel();
+//! let path = Path::new("media/city.obj");
+//!
+//! window.add_obj_with_progress(
+//!     path,
+//!     Path::new("media"),
+//!     [1.0, 1.0, 1.0],
+//!     &MeshLoadOptions::new(),
+//!     &ImportOptions::new(),
+//!     &mut |progress| {
+//!         if let LoadProgress::BytesRead { read, total, .. } = progress {
+//!             let _ = sender.send(read as f32 / total.max(1) as f32);
+//!         }
+//!     },
+//! )
+//! .unwrap();
+//! ```
+
+use std::io::{Read, Result as IoResult};
+use std::path::Path;
+
+use crate::loader::asset_source;
+
+// The number of bytes read between two progress reports.
+const CHUNK_SIZE: usize = 1 << 20;
+
+/// A step of the loading of an asset.
+///
+/// The counts are relative to the loading operation the callback was given to.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum LoadProgress<'a> {
+    /// `read` bytes of the `total` bytes of a file have been read.
+    BytesRead {
+        /// The file being read.
+        path: &'a Path,
+        /// The number of bytes read so far.
+        read: u64,
+        /// The size of the file.
+        total: u64,
+    },
+    /// A mesh has been built from a parsed file.
+    MeshParsed {
+        /// The name of the mesh.
+        name: &'a str,
+        /// The number of meshes built so far.
+        parsed: usize,
+        /// The number of meshes of the file.
+        total: usize,
+    },
+    /// A texture has been decoded and uploaded to the GPU.
+    TextureDecoded {
+        /// The file the texture was read from.
+        path: &'a Path,
+        /// The number of textures decoded so far.
+        decoded: usize,
+        /// The number of textures to decode.
+        total: usize,
+    },
+}
+
+// Reads the whole file at `path`, reporting the bytes read to `progress`.
+pub(crate) fn read_file(path: &Path, progress: &mut dyn FnMut(LoadProgress)) -> IoResult<Vec<u8>> {
+    let (mut file, total) = asset_source::open(path)?;
+    let total = total.unwrap_or(0);
+    let mut bytes = Vec::with_capacity(total as usize);
+    let mut chunk = vec![0; CHUNK_SIZE];
+
+    progress(LoadProgress::BytesRead {
+        path,
+        read: 0,
+        total,
+    });
+
+    loop {
+        let len = file.read(&mut chunk)?;
+
+        if len == 0 {
+            return Ok(bytes);
+        }
+
+        bytes.extend_from_slice(&chunk[..len]);
+        progress(LoadProgress::BytesRead {
+            path,
+            read: bytes.len() as u64,
+            total: total.max(bytes.len() as u64),
+        });
+    }
+}
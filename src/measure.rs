@@ -0,0 +1,701 @@
+//! Retained measurement annotations: distances, angles and radii.
+//!
+//! Measurements are added to the `Measurements` of a window and drawn at every frame until
+//! removed. Their lines, arcs and markers are drawn with a constant width in pixels and
+//! anti-aliased edges, and their labels are drawn on top of everything:
+//!
+//! ```no_run
+//! # use kiss3d::window::Window;
+//! # use kiss3d::nalgebra::Point3;
+//! # use kiss3d::measure::Measurement;
+//! let mut window = Window::new("Measures");
+//! let a = Point3::new(0.0, 0.0, 0.0);
+//! let b = Point3::new(1.0, 0.0, 0.0);
+//! let _ = window.measurements_mut().add(Measurement::Distance { a, b });
+//!
+//! while window.render() {}
+//! ```
+//!
+//! The `Occlusion` of a measurement controls whether the parts hidden by the scene are drawn.
+
+use na::{Matrix4, Point3, Vector2, Vector3, Vector4};
+use std::f32::consts::PI;
+
+use crate::camera::Camera;
+use crate::context::Context;
+use crate::overlay::OverlayContext;
+use crate::resource::{AllocationType, BufferType, Effect, GPUVec, ShaderAttribute, ShaderUniform};
+
+#[path = "error.rs"]
+mod error;
+
+/// How the parts of a measurement hidden by the scene are drawn.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Occlusion {
+    /// Hidden parts are not drawn.
+    Hide,
+    /// Hidden parts are drawn faded.
+    Dim,
+    /// The measurement is entirely drawn on top of the scene.
+    Ignore,
+}
+
+/// The appearance of a measurement.
+#[derive(Clone, Debug, PartialEq)]
+pub struct MeasureStyle {
+    /// The color of the lines, markers and label.
+    pub color: Point3<f32>,
+    /// The width of the lines, in logical pixels.
+    pub line_width: f32,
+    /// The radius of the markers at the measured points, in logical pixels.
+    pub marker_radius: f32,
+    /// The radius of the arcs of angle measurements, in logical pixels.
+    pub arc_radius: f32,
+    /// The size of the label font, in logical pixels.
+    pub label_size: f32,
+    /// The number of decimals of the label.
+    pub precision: usize,
+    /// The unit appended to the labels of distances and radii, e.g. `"mm"`.
+    pub unit: String,
+    /// How the parts hidden by the scene are drawn.
+    pub occlusion: Occlusion,
+}
+
+impl Default for MeasureStyle {
+    fn default() -> Self {
+        MeasureStyle {
+            color: Point3::new(1.0, 0.8, 0.0),
+            line_width: 2.0,
+            marker_radius: 5.0,
+            arc_radius: 30.0,
+            label_size: 18.0,
+            precision: 3,
+            unit: String::new(),
+            occlusion: Occlusion::Dim,
+        }
+    }
+}
+
+/// A quantity measured between points of the scene.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Measurement {
+    /// The distance between two points.
+    Distance {
+        /// The first point.
+        a: Point3<f32>,
+        /// The second point.
+        b: Point3<f32>,
+    },
+    /// The angle between the segments `[vertex, a]` and `[vertex, b]`.
+    Angle {
+        /// The vertex of the angle.
+        vertex: Point3<f32>,
+        /// A point on the first side of the angle.
+        a: Point3<f32>,
+        /// A point on the second side of the angle.
+        b: Point3<f32>,
+    },
+    /// The radius of a circle.
+    Radius {
+        /// The center of the circle.
+        center: Point3<f32>,
+        /// The normal of the plane of the circle.
+        normal: Vector3<f32>,
+        /// The radius of the circle.
+        radius: f32,
+    },
+}
+
+impl Measurement {
+    /// The measured value: a length for distances and radii, and an angle in degrees for angles.
+    pub fn value(&self) -> f32 {
+        match *self {
+            Measurement::Distance { a, b } => na::distance(&a, &b),
+            Measurement::Angle { vertex, a, b } => (a - vertex).angle(&(b - vertex)).to_degrees(),
+            Measurement::Radius { radius, .. } => radius,
+        }
+    }
+
+    /// The text of the label of this measurement.
+    pub fn label(&self, style: &MeasureStyle) -> String {
+        let value = self.value();
+        let precision = style.precision;
+        let unit = if style.unit.is_empty() {
+            String::new()
+        } else {
+            format!(" {}", style.unit)
+        };
+
+        match *self {
+            Measurement::Distance { .. } => format!("{:.*}{}", precision, value, unit),
+            Measurement::Angle { .. } => format!("{:.*}°", precision.min(2), value),
+            Measurement::Radius { .. } => format!("R {:.*}{}", precision, value, unit),
+        }
+    }
+
+    // The point where the label is drawn.
+    fn label_anchor(&self) -> Point3<f32> {
+        match *self {
+            Measurement::Distance { a, b } => na::center(&a, &b),
+            Measurement::Angle { vertex, .. } => vertex,
+            Measurement::Radius { center, .. } => center,
+        }
+    }
+}
+
+/// The identifier of a measurement added to `Measurements`.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct MeasurementId(u64);
+
+/// The set of measurements drawn by a window.
+pub struct Measurements {
+    /// The style given to the measurements added with `add`.
+    pub default_style: MeasureStyle,
+    items: Vec<(MeasurementId, Measurement, MeasureStyle)>,
+    next_id: u64,
+}
+
+impl Default for Measurements {
+    fn default() -> Self {
+        Measurements::new()
+    }
+}
+
+impl Measurements {
+    /// Creates an empty set of measurements.
+    pub fn new() -> Measurements {
+        Measurements {
+            default_style: MeasureStyle::default(),
+            items: Vec::new(),
+            next_id: 0,
+        }
+    }
+
+    /// Adds a measurement drawn with the default style.
+    pub fn add(&mut self, measurement: Measurement) -> MeasurementId {
+        let style = self.default_style.clone();
+        self.add_with_style(measurement, style)
+    }
+
+    /// Adds a measurement drawn with a specific style.
+    pub fn add_with_style(
+        &mut self,
+        measurement: Measurement,
+        style: MeasureStyle,
+    ) -> MeasurementId {
+        let id = MeasurementId(self.next_id);
+        self.next_id += 1;
+        self.items.push((id, measurement, style));
+        id
+    }
+
+    /// Removes a measurement. Returns it if it existed.
+    pub fn remove(&mut self, id: MeasurementId) -> Option<Measurement> {
+        let i = self.items.iter().position(|item| item.0 == id)?;
+        Some(self.items.remove(i).1)
+    }
+
+    /// Removes all the measurements.
+    pub fn clear(&mut self) {
+        self.items.clear()
+    }
+
+    /// The number of measurements.
+    pub fn len(&self) -> usize {
+        self.items.len()
+    }
+
+    /// Whether there is no measurement.
+    pub fn is_empty(&self) -> bool {
+        self.items.is_empty()
+    }
+
+    /// The measurement with the given identifier.
+    pub fn get(&self, id: MeasurementId) -> Option<&Measurement> {
+        self.items
+            .iter()
+            .find(|item| item.0 == id)
+            .map(|item| &item.1)
+    }
+
+    /// A mutable reference to the measurement with the given identifier, e.g., to move one of
+    /// its points.
+    pub fn get_mut(&mut self, id: MeasurementId) -> Option<&mut Measurement> {
+        self.items
+            .iter_mut()
+            .find(|item| item.0 == id)
+            .map(|item| &mut item.1)
+    }
+
+    /// A mutable reference to the style of the measurement with the given identifier.
+    pub fn style_mut(&mut self, id: MeasurementId) -> Option<&mut MeasureStyle> {
+        self.items
+            .iter_mut()
+            .find(|item| item.0 == id)
+            .map(|item| &mut item.2)
+    }
+
+    /// Iterates through the identifiers and values of all the measurements.
+    pub fn iter(&self) -> impl Iterator<Item = (MeasurementId, &Measurement)> {
+        self.items.iter().map(|item| (item.0, &item.1))
+    }
+
+    pub(crate) fn draw_labels(&self, camera: &dyn Camera, ctxt: &mut OverlayContext) {
+        let size = ctxt.size();
+        let scale = ctxt.scale();
+        let projector = Projector::new(camera, size);
+
+        for (_, measurement, style) in &self.items {
+            let pos = match projector.project(&measurement.label_anchor()) {
+                Some(pos) => pos,
+                None => continue,
+            };
+
+            // Projected coordinates are relative to the center of the window, with y upward.
+            let mut pos = na::Point2::new(size.x / 2.0 + pos.x, size.y / 2.0 - pos.y);
+
+            if let Measurement::Angle { vertex, a, b } = *measurement {
+                // Place the label beyond the arc, along its bisector.
+                if let Some((a0, delta)) = projector.screen_angles(&vertex, &a, &b) {
+                    let bisector = a0 + delta / 2.0;
+                    let dist = (style.arc_radius + style.label_size * 0.5) * scale;
+                    pos.x += bisector.cos() * dist;
+                    pos.y -= bisector.sin() * dist;
+                }
+            } else {
+                pos.x += style.marker_radius * scale;
+                pos.y -= (style.marker_radius + style.label_size) * scale;
+            }
+
+            ctxt.draw_text(
+                &measurement.label(style),
+                &pos,
+                style.label_size,
+                &style.color,
+            );
+        }
+    }
+}
+
+// Projects world points to pixels, relative to the center of the window with the y axis
+// pointing upward.
+struct Projector {
+    transformation: Matrix4<f32>,
+    half_size: Vector2<f32>,
+}
+
+impl Projector {
+    fn new(camera: &dyn Camera, size: Vector2<f32>) -> Projector {
+        Projector {
+            transformation: camera.transformation(),
+            half_size: size / 2.0,
+        }
+    }
+
+    // Returns `None` for points behind the camera.
+    fn project(&self, pt: &Point3<f32>) -> Option<Vector2<f32>> {
+        let h = self.transformation * pt.to_homogeneous();
+
+        if h.w <= 1.0e-6 {
+            return None;
+        }
+
+        Some(Vector2::new(
+            h.x / h.w * self.half_size.x,
+            h.y / h.w * self.half_size.y,
+        ))
+    }
+
+    // The screen-space angle of the side `[vertex, a]`, and the signed angle from it to the side
+    // `[vertex, b]`, along the shortest way.
+    fn screen_angles(
+        &self,
+        vertex: &Point3<f32>,
+        a: &Point3<f32>,
+        b: &Point3<f32>,
+    ) -> Option<(f32, f32)> {
+        let v = self.project(vertex)?;
+        let da = self.project(a)? - v;
+        let db = self.project(b)? - v;
+
+        if da.norm_squared() < 1.0e-6 || db.norm_squared() < 1.0e-6 {
+            return None;
+        }
+
+        let a0 = da.y.atan2(da.x);
+        let mut delta = db.y.atan2(db.x) - a0;
+
+        if delta > PI {
+            delta -= 2.0 * PI
+        } else if delta < -PI {
+            delta += 2.0 * PI
+        }
+
+        Some((a0, delta))
+    }
+}
+
+// Vertices of the measurements: each vertex is anchored to a world-space position, moved by an
+// offset in pixels, and carries its distance to the edge of its shape for anti-aliasing.
+struct Batch<'a> {
+    data: &'a mut Vec<f32>,
+    projector: &'a Projector,
+    scale: f32,
+}
+
+impl<'a> Batch<'a> {
+    fn push(
+        &mut self,
+        pos: &Point3<f32>,
+        offset: Vector2<f32>,
+        edge: f32,
+        half: f32,
+        c: &Vector4<f32>,
+    ) {
+        self.data.extend_from_slice(&[
+            pos.x, pos.y, pos.z, offset.x, offset.y, edge, half, c.x, c.y, c.z, c.w,
+        ])
+    }
+
+    // A line between two world-space points, with a width in logical pixels.
+    fn line(&mut self, a: &Point3<f32>, b: &Point3<f32>, width: f32, color: &Vector4<f32>) {
+        let (sa, sb) = match (self.projector.project(a), self.projector.project(b)) {
+            (Some(sa), Some(sb)) => (sa, sb),
+            _ => return,
+        };
+
+        let dir = match (sb - sa).try_normalize(1.0e-6) {
+            Some(dir) => dir,
+            None => return,
+        };
+
+        let half = width * self.scale / 2.0;
+        // One more pixel for the anti-aliased fringe.
+        let extent = half + 1.0;
+        let n = Vector2::new(-dir.y, dir.x) * extent;
+        let cap = dir * half;
+
+        let quad = [
+            (a, n - cap, extent),
+            (a, -n - cap, -extent),
+            (b, n + cap, extent),
+            (b, -n + cap, -extent),
+        ];
+
+        for i in &[0, 1, 2, 2, 1, 3] {
+            let (pos, offset, edge) = quad[*i];
+            self.push(pos, offset, edge, half, color);
+        }
+    }
+
+    // A screen-space arc of a circle centered at a world-space point, with a radius and width in
+    // logical pixels. A full circle with a large width is an annulus.
+    fn arc(
+        &mut self,
+        center: &Point3<f32>,
+        radius: f32,
+        start: f32,
+        sweep: f32,
+        width: f32,
+        color: &Vector4<f32>,
+    ) {
+        let radius = radius * self.scale;
+        let half = width * self.scale / 2.0;
+        let extent = half + 1.0;
+        let n = segments(radius, sweep);
+        let ring = |i: usize| {
+            let angle = start + sweep * i as f32 / n as f32;
+            Vector2::new(angle.cos(), angle.sin())
+        };
+
+        for i in 0..n {
+            let (d0, d1) = (ring(i), ring(i + 1));
+            let quad = [
+                (d0 * (radius - extent), -extent),
+                (d0 * (radius + extent), extent),
+                (d1 * (radius - extent), -extent),
+                (d1 * (radius + extent), extent),
+            ];
+
+            for j in &[0, 1, 2, 2, 1, 3] {
+                let (offset, edge) = quad[*j];
+                self.push(center, offset, edge, half, color);
+            }
+        }
+    }
+
+    // A filled screen-space circular sector centered at a world-space point, with a radius in
+    // logical pixels. A sweep of `2π` gives a disk.
+    fn sector(
+        &mut self,
+        center: &Point3<f32>,
+        radius: f32,
+        start: f32,
+        sweep: f32,
+        color: &Vector4<f32>,
+    ) {
+        let radius = radius * self.scale;
+        let extent = radius + 1.0;
+        let n = segments(radius, sweep);
+
+        for i in 0..n {
+            let a0 = start + sweep * i as f32 / n as f32;
+            let a1 = start + sweep * (i + 1) as f32 / n as f32;
+            let d0 = Vector2::new(a0.cos(), a0.sin()) * extent;
+            let d1 = Vector2::new(a1.cos(), a1.sin()) * extent;
+
+            self.push(center, Vector2::zeros(), 0.0, radius, color);
+            self.push(center, d0, extent, radius, color);
+            self.push(center, d1, extent, radius, color);
+        }
+    }
+
+    fn measurement(&mut self, measurement: &Measurement, style: &MeasureStyle) {
+        let color = style.color.coords.push(1.0);
+        let fill = style.color.coords.push(0.25);
+        let width = style.line_width;
+        let marker = style.marker_radius;
+
+        match *measurement {
+            Measurement::Distance { a, b } => {
+                self.line(&a, &b, width, &color);
+                self.arc(&a, marker, 0.0, 2.0 * PI, width, &color);
+                self.arc(&b, marker, 0.0, 2.0 * PI, width, &color);
+            }
+            Measurement::Angle { vertex, a, b } => {
+                self.line(&vertex, &a, width, &color);
+                self.line(&vertex, &b, width, &color);
+
+                if let Some((start, sweep)) = self.projector.screen_angles(&vertex, &a, &b) {
+                    self.sector(&vertex, style.arc_radius, start, sweep, &fill);
+                    self.arc(&vertex, style.arc_radius, start, sweep, width, &color);
+                }
+            }
+            Measurement::Radius {
+                center,
+                normal,
+                radius,
+            } => {
+                let normal = match normal.try_normalize(1.0e-6) {
+                    Some(normal) => normal,
+                    None => return,
+                };
+                let u = normal.cross(&orthogonal(&normal)).normalize() * radius;
+                let v = normal.cross(&u);
+                let n = 64;
+                let rim = |i: usize| {
+                    let angle = 2.0 * PI * i as f32 / n as f32;
+                    center + u * angle.cos() + v * angle.sin()
+                };
+
+                for i in 0..n {
+                    self.line(&rim(i), &rim(i + 1), width, &color);
+                }
+
+                self.line(&center, &rim(0), width, &color);
+                self.sector(&center, marker * 0.6, 0.0, 2.0 * PI, &color);
+            }
+        }
+    }
+}
+
+// The number of segments approximating an arc with a radius in pixels.
+fn segments(radius: f32, sweep: f32) -> usize {
+    ((sweep.abs() * radius / 4.0).ceil() as usize).clamp(8, 128)
+}
+
+// Any vector orthogonal to `v`.
+fn orthogonal(v: &Vector3<f32>) -> Vector3<f32> {
+    if v.x.abs() < 0.9 {
+        v.cross(&Vector3::x())
+    } else {
+        v.cross(&Vector3::y())
+    }
+}
+
+/// Draws the lines, arcs and markers of the measurements.
+pub(crate) struct MeasureRenderer {
+    shader: Effect,
+    pos: ShaderAttribute<Point3<f32>>,
+    offset: ShaderAttribute<Vector2<f32>>,
+    edge: ShaderAttribute<Vector2<f32>>,
+    color: ShaderAttribute<Vector4<f32>>,
+    view: ShaderUniform<Matrix4<f32>>,
+    proj: ShaderUniform<Matrix4<f32>>,
+    viewport: ShaderUniform<Vector2<f32>>,
+    alpha: ShaderUniform<f32>,
+    vertices: GPUVec<f32>,
+}
+
+// The number of floats per vertex.
+const VERTEX_SIZE: usize = 11;
+
+impl MeasureRenderer {
+    pub fn new() -> MeasureRenderer {
+        let mut shader = Effect::new_from_str(MEASURE_VERTEX_SRC, MEASURE_FRAGMENT_SRC).unwrap();
+
+        shader.use_program();
+
+        MeasureRenderer {
+            pos: shader
+                .get_attrib("position")
+                .expect("Failed to get shader attribute."),
+            offset: shader
+                .get_attrib("offset")
+                .expect("Failed to get shader attribute."),
+            edge: shader
+                .get_attrib("edge")
+                .expect("Failed to get shader attribute."),
+            color: shader
+                .get_attrib("color")
+                .expect("Failed to get shader attribute."),
+            view: shader
+                .get_uniform("view")
+                .expect("Failed to get shader uniform."),
+            proj: shader
+                .get_uniform("proj")
+                .expect("Failed to get shader uniform."),
+            viewport: shader
+                .get_uniform("viewport")
+                .expect("Failed to get shader uniform."),
+            alpha: shader
+                .get_uniform("alpha")
+                .expect("Failed to get shader uniform."),
+            vertices: GPUVec::new(Vec::new(), BufferType::Array, AllocationType::StreamDraw),
+            shader,
+        }
+    }
+
+    pub fn render(
+        &mut self,
+        pass: usize,
+        camera: &mut dyn Camera,
+        measurements: &Measurements,
+        size: Vector2<f32>,
+        scale: f32,
+    ) {
+        if measurements.is_empty() {
+            return;
+        }
+
+        let projector = Projector::new(camera, size);
+        let ctxt = Context::get();
+
+        self.shader.use_program();
+        self.pos.enable();
+        self.offset.enable();
+        self.edge.enable();
+        self.color.enable();
+
+        camera.upload(pass, &mut self.proj, &mut self.view);
+        self.viewport.upload(&size);
+
+        verify!(ctxt.enable(Context::BLEND));
+        verify!(ctxt.blend_func_separate(
+            Context::SRC_ALPHA,
+            Context::ONE_MINUS_SRC_ALPHA,
+            Context::ONE,
+            Context::ONE_MINUS_SRC_ALPHA,
+        ));
+        verify!(ctxt.depth_mask(false));
+
+        for occlusion in &[Occlusion::Hide, Occlusion::Dim, Occlusion::Ignore] {
+            if let Some(data) = self.vertices.data_mut() {
+                data.clear();
+
+                let mut batch = Batch {
+                    data,
+                    projector: &projector,
+                    scale,
+                };
+
+                for (_, measurement, style) in &measurements.items {
+                    if style.occlusion == *occlusion {
+                        batch.measurement(measurement, style);
+                    }
+                }
+            }
+
+            let num_vertices = self.vertices.len() / VERTEX_SIZE;
+
+            if num_vertices == 0 {
+                continue;
+            }
+
+            unsafe {
+                let strides = VERTEX_SIZE - 1;
+                self.pos
+                    .bind_sub_buffer_generic(&mut self.vertices, strides, 0);
+                self.offset
+                    .bind_sub_buffer_generic(&mut self.vertices, strides, 3);
+                self.edge
+                    .bind_sub_buffer_generic(&mut self.vertices, strides, 5);
+                self.color
+                    .bind_sub_buffer_generic(&mut self.vertices, strides, 7);
+            }
+
+            // Hidden parts are drawn faded first, then the visible parts over them.
+            if *occlusion != Occlusion::Hide {
+                let alpha = if *occlusion == Occlusion::Dim {
+                    0.3
+                } else {
+                    1.0
+                };
+                self.alpha.upload(&alpha);
+                verify!(ctxt.disable(Context::DEPTH_TEST));
+                verify!(ctxt.draw_arrays(Context::TRIANGLES, 0, num_vertices as i32));
+                verify!(ctxt.enable(Context::DEPTH_TEST));
+            }
+
+            if *occlusion != Occlusion::Ignore {
+                self.alpha.upload(&1.0);
+                verify!(ctxt.draw_arrays(Context::TRIANGLES, 0, num_vertices as i32));
+            }
+        }
+
+        verify!(ctxt.depth_mask(true));
+        verify!(ctxt.disable(Context::BLEND));
+
+        self.pos.disable();
+        self.offset.disable();
+        self.edge.disable();
+        self.color.disable();
+    }
+}
+
+static MEASURE_VERTEX_SRC: &str = "#version 100
+    attribute vec3 position;
+    attribute vec2 offset;
+    attribute vec2 edge;
+    attribute vec4 color;
+    uniform   mat4 proj;
+    uniform   mat4 view;
+    uniform   vec2 viewport;
+    varying   vec2 vEdge;
+    varying   vec4 vColor;
+    void main() {
+        vec4 p = proj * view * vec4(position, 1.0);
+        p.xy += offset * 2.0 / viewport * p.w;
+        // Pull the measurements slightly toward the camera, so that those lying on surfaces
+        // are not hidden by them.
+        p.z -= 0.0005 * p.w;
+        gl_Position = p;
+        vEdge = edge;
+        vColor = color;
+    }";
+
+static MEASURE_FRAGMENT_SRC: &str = "#version 100
+#ifdef GL_FRAGMENT_PRECISION_HIGH
+   precision highp float;
+#else
+   precision mediump float;
+#endif
+
+    uniform float alpha;
+    varying vec2  vEdge;
+    varying vec4  vColor;
+    void main() {
+        // vEdge.x is the distance to the center of the shape, vEdge.y its half width.
+        float coverage = clamp(vEdge.y + 0.5 - abs(vEdge.x), 0.0, 1.0);
+        gl_FragColor = vec4(vColor.rgb, vColor.a * coverage * alpha);
+    }";
@@ -0,0 +1,135 @@
+use na::{Point2, Point3, Vector2, Vector3};
+
+use crate::camera::Camera;
+use crate::overlay::OverlayContext;
+
+/// An orientation gizmo showing the world axes in a corner of the window.
+///
+/// When clickable, clicking the end of an axis snaps the camera so that it looks at the scene
+/// from that axis, using `Camera::look_along`.
+#[derive(Clone, Debug)]
+pub struct AxisGizmo {
+    /// The length of the axes, in logical pixels.
+    pub size: f32,
+    /// The distance between the center of the gizmo and the bottom-left corner of the window,
+    /// in logical pixels.
+    pub margin: f32,
+    /// The colors of the x, y, and z axes.
+    pub colors: [Point3<f32>; 3],
+    /// Whether clicking an axis snaps the camera to it.
+    pub clickable: bool,
+}
+
+impl AxisGizmo {
+    /// Creates a clickable gizmo with red, green, and blue axes.
+    pub fn new() -> AxisGizmo {
+        AxisGizmo {
+            size: 40.0,
+            margin: 60.0,
+            colors: [
+                Point3::new(1.0, 0.2, 0.2),
+                Point3::new(0.2, 1.0, 0.2),
+                Point3::new(0.3, 0.5, 1.0),
+            ],
+            clickable: true,
+        }
+    }
+
+    /// The world-space axis whose end is under the given window coordinates, if any.
+    ///
+    /// Returned axes are unit vectors, possibly pointing toward negative coordinates.
+    pub fn axis_at(
+        &self,
+        camera: &dyn Camera,
+        window_coord: &Point2<f32>,
+        window_size: &Vector2<f32>,
+        scale_factor: f32,
+    ) -> Option<Vector3<f32>> {
+        let center = self.center(window_size, scale_factor);
+        let pick_radius = 8.0 * scale_factor;
+        let mut best: Option<(f32, Vector3<f32>)> = None;
+
+        for (axis, end, depth) in self.axes(camera, &center, scale_factor) {
+            if na::distance(&end, window_coord) <= pick_radius
+                && best.map(|b| depth > b.0).unwrap_or(true)
+            {
+                best = Some((depth, axis));
+            }
+        }
+
+        best.map(|b| b.1)
+    }
+
+    pub(crate) fn draw(&self, camera: &dyn Camera, ctxt: &mut OverlayContext) {
+        let scale = ctxt.scale();
+        let center = self.center(&ctxt.size(), scale);
+
+        for (i, (_, end, _)) in self.axes(camera, &center, scale).into_iter().enumerate() {
+            let positive = i % 2 == 0;
+            let color = if positive {
+                self.colors[i / 2]
+            } else {
+                self.colors[i / 2] * 0.5
+            };
+
+            ctxt.draw_line(&center, &end, &color);
+
+            if positive {
+                let label = ["X", "Y", "Z"][i / 2];
+                let pos = end + (end - center) * 0.2 - Vector2::new(5.0, 8.0) * scale;
+                ctxt.draw_text(label, &pos, 16.0, &color);
+            } else if self.clickable {
+                // Mark the clickable ends of the negative axes with a small cross.
+                let d = 2.0 * scale;
+                ctxt.draw_line(
+                    &(end - Vector2::new(d, 0.0)),
+                    &(end + Vector2::new(d, 0.0)),
+                    &color,
+                );
+                ctxt.draw_line(
+                    &(end - Vector2::new(0.0, d)),
+                    &(end + Vector2::new(0.0, d)),
+                    &color,
+                );
+            }
+        }
+    }
+
+    fn center(&self, window_size: &Vector2<f32>, scale_factor: f32) -> Point2<f32> {
+        let margin = self.margin * scale_factor;
+        Point2::new(margin, window_size.y - margin)
+    }
+
+    // The world axis, the end point in window coordinates, and the depth toward the viewer, of
+    // each of the six half-axes.
+    fn axes(
+        &self,
+        camera: &dyn Camera,
+        center: &Point2<f32>,
+        scale_factor: f32,
+    ) -> Vec<(Vector3<f32>, Point2<f32>, f32)> {
+        let rot = camera.view_transform().rotation;
+        let len = self.size * scale_factor;
+        let mut res = Vec::with_capacity(6);
+
+        for i in 0..3 {
+            for sign in &[1.0f32, -1.0] {
+                let mut axis = Vector3::zeros();
+                axis[i] = *sign;
+
+                let v = rot * axis;
+                let l = if *sign > 0.0 { len } else { len * 0.6 };
+                let end = center + Vector2::new(v.x, -v.y) * l;
+                res.push((axis, end, v.z));
+            }
+        }
+
+        res
+    }
+}
+
+impl Default for AxisGizmo {
+    fn default() -> AxisGizmo {
+        AxisGizmo::new()
+    }
+}
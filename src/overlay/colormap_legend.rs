@@ -0,0 +1,95 @@
+use na::{Point2, Point3, Vector2};
+
+use crate::colormap::Colormap;
+use crate::overlay::OverlayContext;
+
+/// A vertical color bar showing the values associated to the colors of a colormap.
+///
+/// The bar is drawn along the right edge of the window, with the minimum value at the bottom.
+#[derive(Clone, Debug)]
+pub struct ColormapLegend {
+    /// The colormap displayed by the legend.
+    pub colormap: Colormap,
+    /// The values mapped to the bottom and to the top of the bar.
+    pub range: (f32, f32),
+    /// The text displayed above the bar.
+    pub title: String,
+    /// The length of the bar, in logical pixels.
+    pub length: f32,
+    /// The width of the bar, in logical pixels.
+    pub width: f32,
+    /// The distance between the bar and the top-right corner of the window, in logical pixels.
+    pub margin: f32,
+    /// The color of the labels.
+    pub text_color: Point3<f32>,
+}
+
+impl ColormapLegend {
+    /// Creates a legend for the given colormap and range of values.
+    pub fn new(colormap: Colormap, range: (f32, f32)) -> ColormapLegend {
+        ColormapLegend {
+            colormap,
+            range,
+            title: String::new(),
+            length: 200.0,
+            width: 16.0,
+            margin: 30.0,
+            text_color: Point3::new(1.0, 1.0, 1.0),
+        }
+    }
+
+    pub(crate) fn draw(&self, ctxt: &mut OverlayContext) {
+        let size = ctxt.size();
+        let scale = ctxt.scale();
+        let margin = self.margin * scale;
+        let width = self.width * scale;
+        let length = self.length * scale;
+        let top_right = Point2::new(size.x - margin, margin + 20.0 * scale);
+        let top_left = top_right - Vector2::x() * width;
+
+        // The bar is made of one horizontal line per physical pixel.
+        let nlines = length.ceil().max(1.0) as usize;
+
+        for i in 0..=nlines {
+            let t = i as f32 / nlines as f32;
+            let y = length * (1.0 - t);
+            let value = self.range.0 + (self.range.1 - self.range.0) * t;
+            let color = self.colormap.map(value, self.range);
+
+            ctxt.draw_line(
+                &(top_left + Vector2::y() * y),
+                &(top_right + Vector2::y() * y),
+                &color,
+            );
+        }
+
+        let label_x = width + 90.0 * scale;
+        let max_pos = top_right - Vector2::new(label_x, 8.0 * scale);
+        let min_pos = max_pos + Vector2::y() * length;
+        ctxt.draw_text(
+            &format_value(self.range.1),
+            &max_pos,
+            14.0,
+            &self.text_color,
+        );
+        ctxt.draw_text(
+            &format_value(self.range.0),
+            &min_pos,
+            14.0,
+            &self.text_color,
+        );
+
+        if !self.title.is_empty() {
+            let pos = top_left - Vector2::new(0.0, 24.0 * scale);
+            ctxt.draw_text(&self.title, &pos, 16.0, &self.text_color);
+        }
+    }
+}
+
+fn format_value(value: f32) -> String {
+    if value != 0.0 && (value.abs() >= 1.0e4 || value.abs() < 1.0e-2) {
+        format!("{:.2e}", value)
+    } else {
+        format!("{:.2}", value)
+    }
+}
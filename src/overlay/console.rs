@@ -0,0 +1,227 @@
+use na::{Point2, Point3};
+use std::collections::VecDeque;
+use std::sync::{Arc, Mutex};
+
+use crate::event::{Action, Key, WindowEvent};
+use crate::overlay::OverlayContext;
+
+/// A line displayed by the console.
+#[derive(Clone, Debug)]
+pub struct ConsoleLine {
+    /// The text of the line.
+    pub text: String,
+    /// The color of the line.
+    pub color: Point3<f32>,
+}
+
+type CommandHandler = Box<dyn FnMut(&str) -> Option<String>>;
+
+struct ConsoleBuffer {
+    lines: VecDeque<ConsoleLine>,
+    max_lines: usize,
+}
+
+impl ConsoleBuffer {
+    fn push(&mut self, text: &str, color: &Point3<f32>) {
+        for line in text.lines() {
+            self.lines.push_back(ConsoleLine {
+                text: line.to_string(),
+                color: *color,
+            });
+        }
+
+        self.truncate();
+    }
+
+    fn truncate(&mut self) {
+        while self.lines.len() > self.max_lines {
+            let _ = self.lines.pop_front();
+        }
+    }
+}
+
+/// A toggleable on-screen console displaying recent log lines.
+///
+/// Lines can be added with `print`, or collected from the `log` crate after calling
+/// `install_logger`. If a command handler is set, the console also accepts typed commands while
+/// it is open: the handler is called when `Return` is pressed, and the string it returns (if any)
+/// is printed.
+pub struct Console {
+    buffer: Arc<Mutex<ConsoleBuffer>>,
+    visible: bool,
+    toggle_key: Option<Key>,
+    input: String,
+    command_handler: Option<CommandHandler>,
+    text_size: f32,
+}
+
+impl Console {
+    /// Creates a new, hidden, console toggled by the `Grave` key.
+    pub fn new() -> Console {
+        Console {
+            buffer: Arc::new(Mutex::new(ConsoleBuffer {
+                lines: VecDeque::new(),
+                max_lines: 16,
+            })),
+            visible: false,
+            toggle_key: Some(Key::Grave),
+            input: String::new(),
+            command_handler: None,
+            text_size: 14.0,
+        }
+    }
+
+    /// Whether the console is currently displayed.
+    pub fn is_visible(&self) -> bool {
+        self.visible
+    }
+
+    /// Shows or hides the console.
+    pub fn set_visible(&mut self, visible: bool) {
+        self.visible = visible;
+    }
+
+    /// The key used to show or hide the console.
+    pub fn toggle_key(&self) -> Option<Key> {
+        self.toggle_key
+    }
+
+    /// Sets the key used to show or hide the console. Use `None` to disable it.
+    pub fn rebind_toggle_key(&mut self, key: Option<Key>) {
+        self.toggle_key = key;
+    }
+
+    /// The maximum number of lines kept by the console.
+    pub fn max_lines(&self) -> usize {
+        self.buffer.lock().unwrap().max_lines
+    }
+
+    /// Sets the maximum number of lines kept by the console.
+    pub fn set_max_lines(&mut self, max_lines: usize) {
+        let mut buffer = self.buffer.lock().unwrap();
+        buffer.max_lines = max_lines.max(1);
+        buffer.truncate();
+    }
+
+    /// Sets the size of the text, in logical pixels.
+    pub fn set_text_size(&mut self, size: f32) {
+        self.text_size = size;
+    }
+
+    /// Sets the closure called with each typed command.
+    pub fn set_command_handler<F: FnMut(&str) -> Option<String> + 'static>(&mut self, handler: F) {
+        self.command_handler = Some(Box::new(handler));
+    }
+
+    /// Appends a line to the console.
+    pub fn print(&mut self, text: &str, color: &Point3<f32>) {
+        self.buffer.lock().unwrap().push(text, color);
+    }
+
+    /// Removes every line from the console.
+    pub fn clear(&mut self) {
+        self.buffer.lock().unwrap().lines.clear();
+    }
+
+    /// Installs a global logger forwarding the records of the `log` crate to this console.
+    ///
+    /// Fails if another logger has already been installed.
+    pub fn install_logger(&self, level: log::LevelFilter) -> Result<(), log::SetLoggerError> {
+        let logger = ConsoleLogger {
+            buffer: self.buffer.clone(),
+            level,
+        };
+
+        log::set_boxed_logger(Box::new(logger)).map(|_| log::set_max_level(level))
+    }
+
+    /// Handles an event. Returns `true` if the event has been consumed by the console.
+    pub fn handle_event(&mut self, event: &WindowEvent) -> bool {
+        match *event {
+            WindowEvent::Key(key, Action::Press, _) if Some(key) == self.toggle_key => {
+                self.visible = !self.visible;
+                true
+            }
+            // The character of the toggle key is ignored.
+            WindowEvent::Char('`') if self.toggle_key == Some(Key::Grave) => true,
+            _ if !self.visible || self.command_handler.is_none() => false,
+            WindowEvent::Char(c) if !c.is_control() => {
+                self.input.push(c);
+                true
+            }
+            WindowEvent::Key(Key::Back, Action::Press, _) => {
+                let _ = self.input.pop();
+                true
+            }
+            WindowEvent::Key(Key::Return, Action::Press, _) => {
+                let command = std::mem::take(&mut self.input);
+                self.print(&format!("> {}", command), &Point3::new(0.6, 0.6, 0.6));
+
+                if let Some(ref mut handler) = self.command_handler {
+                    if let Some(answer) = handler(&command) {
+                        let color = Point3::new(1.0, 1.0, 1.0);
+                        self.buffer.lock().unwrap().push(&answer, &color);
+                    }
+                }
+
+                true
+            }
+            _ => event.is_keyboard_event(),
+        }
+    }
+
+    pub(crate) fn draw(&self, ctxt: &mut OverlayContext) {
+        if !self.visible {
+            return;
+        }
+
+        let scale = ctxt.scale();
+        let line_height = self.text_size * 1.3 * scale;
+        let mut pos = Point2::new(8.0 * scale, 8.0 * scale);
+
+        for line in self.buffer.lock().unwrap().lines.iter() {
+            ctxt.draw_text(&line.text, &pos, self.text_size, &line.color);
+            pos.y += line_height;
+        }
+
+        if self.command_handler.is_some() {
+            let prompt = format!("> {}_", self.input);
+            ctxt.draw_text(&prompt, &pos, self.text_size, &Point3::new(1.0, 1.0, 0.4));
+        }
+    }
+}
+
+impl Default for Console {
+    fn default() -> Console {
+        Console::new()
+    }
+}
+
+struct ConsoleLogger {
+    buffer: Arc<Mutex<ConsoleBuffer>>,
+    level: log::LevelFilter,
+}
+
+impl log::Log for ConsoleLogger {
+    fn enabled(&self, metadata: &log::Metadata) -> bool {
+        metadata.level() <= self.level
+    }
+
+    fn log(&self, record: &log::Record) {
+        if !self.enabled(record.metadata()) {
+            return;
+        }
+
+        let color = match record.level() {
+            log::Level::Error => Point3::new(1.0, 0.3, 0.3),
+            log::Level::Warn => Point3::new(1.0, 0.8, 0.2),
+            log::Level::Info => Point3::new(1.0, 1.0, 1.0),
+            log::Level::Debug | log::Level::Trace => Point3::new(0.6, 0.6, 0.6),
+        };
+        let text = format!("[{}] {}", record.level(), record.args());
+
+        self.buffer.lock().unwrap().push(&text, &color);
+    }
+
+    fn flush(&self) {}
+}
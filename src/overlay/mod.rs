@@ -0,0 +1,13 @@
+//! Built-in screen-space widgets drawn on top of the scene.
+
+pub use self::axis_gizmo::AxisGizmo;
+pub use self::colormap_legend::ColormapLegend;
+pub use self::console::{Console, ConsoleLine};
+pub(crate) use self::overlay_context::OverlayContext;
+pub use self::scale_bar::ScaleBar;
+
+mod axis_gizmo;
+mod colormap_legend;
+mod console;
+mod overlay_context;
+mod scale_bar;
@@ -0,0 +1,77 @@
+use na::{Point2, Point3, Vector2};
+
+use crate::planar_line_renderer::PlanarLineRenderer;
+use crate::text::{Font, TextRenderer};
+
+/// Helper used by the overlay widgets to draw in window coordinates.
+///
+/// Window coordinates are expressed in physical pixels, with the origin at the top-left corner
+/// of the window and the y axis pointing downward.
+pub(crate) struct OverlayContext<'a> {
+    lines: &'a mut PlanarLineRenderer,
+    text: &'a mut TextRenderer,
+    size: Vector2<f32>,
+    scale: f32,
+}
+
+impl<'a> OverlayContext<'a> {
+    pub fn new(
+        lines: &'a mut PlanarLineRenderer,
+        text: &'a mut TextRenderer,
+        size: Vector2<f32>,
+        scale: f32,
+    ) -> OverlayContext<'a> {
+        OverlayContext {
+            lines,
+            text,
+            size,
+            scale,
+        }
+    }
+
+    /// The size of the window, in physical pixels.
+    pub fn size(&self) -> Vector2<f32> {
+        self.size
+    }
+
+    /// The scale factor of the window.
+    pub fn scale(&self) -> f32 {
+        self.scale
+    }
+
+    /// Draws a line between two points given in window coordinates.
+    pub fn draw_line(&mut self, a: &Point2<f32>, b: &Point2<f32>, color: &Point3<f32>) {
+        let a = self.to_planar(a);
+        let b = self.to_planar(b);
+        self.lines.draw_line(a, b, *color);
+    }
+
+    /// Draws text with its top-left corner at the given window coordinates.
+    pub fn draw_text(&mut self, text: &str, pos: &Point2<f32>, size: f32, color: &Point3<f32>) {
+        self.text
+            .draw_text(text, pos, size * self.scale, &Font::default(), color);
+    }
+
+    /// The size, in physical pixels, of a text drawn with `draw_text`.
+    pub fn text_size(&self, text: &str, size: f32) -> Vector2<f32> {
+        let font = Font::default();
+        let scale = rusttype::Scale::uniform(size * self.scale);
+        let vmetrics = font.font().v_metrics(scale);
+        let width = font
+            .font()
+            .layout(text, scale, rusttype::point(0.0, 0.0))
+            .last()
+            .map(|g| g.position().x + g.unpositioned().h_metrics().advance_width)
+            .unwrap_or(0.0);
+
+        Vector2::new(width, vmetrics.ascent - vmetrics.descent)
+    }
+
+    // Converts window coordinates to the coordinates of a `FixedView` planar camera.
+    fn to_planar(&self, pt: &Point2<f32>) -> Point2<f32> {
+        Point2::new(
+            (pt.x - self.size.x / 2.0) / self.scale,
+            (self.size.y / 2.0 - pt.y) / self.scale,
+        )
+    }
+}
@@ -0,0 +1,109 @@
+use na::{Point2, Point3, Vector2};
+
+use crate::camera::Camera;
+use crate::overlay::OverlayContext;
+
+/// A scale bar showing a round world-space length in a corner of the window.
+///
+/// The length represented by one pixel is measured at the depth of `reference`, so the bar
+/// updates as the camera zooms in and out.
+#[derive(Clone, Debug)]
+pub struct ScaleBar {
+    /// The maximum length of the bar, in logical pixels.
+    pub max_length: f32,
+    /// The distance between the bar and the bottom-right corner of the window, in logical
+    /// pixels.
+    pub margin: f32,
+    /// The color of the bar and of its label.
+    pub color: Point3<f32>,
+    /// The point at which the scale is measured.
+    pub reference: Point3<f32>,
+    /// The unit appended to the label, e.g. `"m"`.
+    pub unit: String,
+}
+
+impl ScaleBar {
+    /// Creates a white scale bar measured at the world origin.
+    pub fn new() -> ScaleBar {
+        ScaleBar {
+            max_length: 150.0,
+            margin: 20.0,
+            color: Point3::new(1.0, 1.0, 1.0),
+            reference: Point3::origin(),
+            unit: String::new(),
+        }
+    }
+
+    /// The world-space length covered by one physical pixel at the depth of `reference`.
+    pub fn world_length_per_pixel(
+        &self,
+        camera: &dyn Camera,
+        window_size: &Vector2<f32>,
+    ) -> Option<f32> {
+        let probe = 100.0;
+        let center = Point2::from(window_size / 2.0);
+        let (o0, d0) = camera.unproject(&center, window_size);
+        let (o1, d1) = camera.unproject(&(center + Vector2::x() * probe), window_size);
+        let depth = (self.reference - o0).dot(&d0);
+        let denom = d1.dot(&d0);
+
+        if depth <= 0.0 || denom.abs() < 1.0e-6 {
+            return None;
+        }
+
+        let p0 = o0 + d0 * depth;
+        let p1 = o1 + d1 * ((p0 - o1).dot(&d0) / denom);
+        let res = na::distance(&p0, &p1) / probe;
+
+        if res.is_finite() && res > 0.0 {
+            Some(res)
+        } else {
+            None
+        }
+    }
+
+    pub(crate) fn draw(&self, camera: &dyn Camera, ctxt: &mut OverlayContext) {
+        let size = ctxt.size();
+        let scale = ctxt.scale();
+        let per_pixel = match self.world_length_per_pixel(camera, &size) {
+            Some(l) => l,
+            None => return,
+        };
+
+        // Pick the largest 1, 2, or 5 times a power of ten that fits the bar.
+        let max_world = self.max_length * scale * per_pixel;
+        let exponent = max_world.log10().floor();
+        let magnitude = 10.0f32.powf(exponent);
+        let mantissa = [5.0, 2.0, 1.0]
+            .iter()
+            .cloned()
+            .find(|m| m * magnitude <= max_world)
+            .unwrap_or(1.0);
+        let world = mantissa * magnitude;
+        let pixels = world / per_pixel;
+
+        let margin = self.margin * scale;
+        let tick = 6.0 * scale;
+        let right = Point2::new(size.x - margin, size.y - margin);
+        let left = right - Vector2::x() * pixels;
+
+        ctxt.draw_line(&left, &right, &self.color);
+        ctxt.draw_line(&left, &(left - Vector2::y() * tick), &self.color);
+        ctxt.draw_line(&right, &(right - Vector2::y() * tick), &self.color);
+
+        let precision = (-exponent).max(0.0) as usize;
+        let label = if self.unit.is_empty() {
+            format!("{:.*}", precision, world)
+        } else {
+            format!("{:.*} {}", precision, world, self.unit)
+        };
+        let pos = left - Vector2::new(0.0, tick + 20.0 * scale);
+        ctxt.draw_text(&label, &pos, 16.0, &self.color);
+    }
+}
+
+impl Default for ScaleBar {
+    fn default() -> ScaleBar {
+        ScaleBar::new()
+    }
+}
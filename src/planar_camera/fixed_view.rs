@@ -61,4 +61,14 @@ impl PlanarCamera for FixedView {
         let unprojected_hom = self.inv_proj * normalized_coords.to_homogeneous();
         Point2::from_homogeneous(unprojected_hom).unwrap()
     }
+
+    fn project(&self, world_coord: &Point2<f32>, size: &Vector2<f32>) -> Point2<f32> {
+        let projected_hom = self.proj * world_coord.to_homogeneous();
+        let normalized_coords = Point2::from_homogeneous(projected_hom).unwrap();
+
+        Point2::new(
+            (normalized_coords.x + 1.0) * size.x / 2.0,
+            (1.0 - normalized_coords.y) * size.y / 2.0,
+        )
+    }
 }
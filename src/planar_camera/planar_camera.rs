@@ -27,4 +27,10 @@ pub trait PlanarCamera {
 
     /// Computes the 2D world-space coordiates corresponding to the given screen-space coordiates.
     fn unproject(&self, window_coord: &Point2<f32>, window_size: &Vector2<f32>) -> Point2<f32>;
+
+    /// Computes the screen-space coordinates corresponding to the given 2D world-space
+    /// coordinates.
+    ///
+    /// The screen-space origin is at the top-left corner of the window.
+    fn project(&self, world_coord: &Point2<f32>, window_size: &Vector2<f32>) -> Point2<f32>;
 }
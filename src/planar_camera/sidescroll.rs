@@ -15,7 +15,14 @@ pub struct Sidescroll {
 
     /// Increment of the zoomance per unit scrolling. The default value is 40.0.
     zoom_step: f32,
+    min_zoom: f32,
+    max_zoom: f32,
     drag_button: Option<MouseButton>,
+    /// Fraction of the panning velocity kept at each frame after the drag button is released.
+    inertia: f32,
+    velocity: Vector2<f32>,
+    drag_delta: Vector2<f32>,
+    viewport: Vector2<f32>,
 
     view: Matrix3<f32>,
     proj: Matrix3<f32>,
@@ -31,7 +38,13 @@ impl Sidescroll {
             at: Point2::origin(),
             zoom: 1.0,
             zoom_step: 0.9,
+            min_zoom: 0.00001,
+            max_zoom: f32::MAX,
             drag_button: Some(MouseButton::Button2),
+            inertia: 0.0,
+            velocity: na::zero(),
+            drag_delta: na::zero(),
+            viewport: Vector2::new(800.0, 600.0),
             view: na::one(),
             proj: na::one(),
             scaled_proj: na::one(),
@@ -68,17 +81,86 @@ impl Sidescroll {
         self.update_projviews();
     }
 
+    /// The minimum zoom of the camera.
+    pub fn min_zoom(&self) -> f32 {
+        self.min_zoom
+    }
+
+    /// Sets the minimum zoom of the camera.
+    pub fn set_min_zoom(&mut self, min_zoom: f32) {
+        self.min_zoom = min_zoom.max(0.00001);
+
+        self.update_restrictions();
+        self.update_projviews();
+    }
+
+    /// The maximum zoom of the camera.
+    pub fn max_zoom(&self) -> f32 {
+        self.max_zoom
+    }
+
+    /// Sets the maximum zoom of the camera.
+    pub fn set_max_zoom(&mut self, max_zoom: f32) {
+        self.max_zoom = max_zoom;
+
+        self.update_restrictions();
+        self.update_projviews();
+    }
+
+    /// The fraction of the panning velocity kept at each frame after a drag ends.
+    pub fn inertia(&self) -> f32 {
+        self.inertia
+    }
+
+    /// Sets the fraction of the panning velocity kept at each frame after a drag ends.
+    ///
+    /// The value is clamped to `[0.0, 1.0)`. Set it to `0.0` (the default) to disable inertia.
+    pub fn set_inertia(&mut self, inertia: f32) {
+        self.inertia = inertia.clamp(0.0, 0.999);
+
+        if self.inertia == 0.0 {
+            self.velocity = na::zero();
+        }
+    }
+
     /// Move the camera such that it is centered on a specific point.
     pub fn look_at(&mut self, at: Point2<f32>, zoom: f32) {
         self.at = at;
         self.zoom = zoom;
+        self.update_restrictions();
         self.update_projviews();
     }
 
+    /// Move and zoom the camera such that the rectangle `[mins, maxs]` fits the window.
+    pub fn zoom_to_rect(&mut self, mins: Point2<f32>, maxs: Point2<f32>) {
+        let extents = maxs - mins;
+        let zoom_x = self.viewport.x / extents.x.abs().max(f32::EPSILON);
+        let zoom_y = self.viewport.y / extents.y.abs().max(f32::EPSILON);
+
+        self.velocity = na::zero();
+        self.look_at(na::center(&mins, &maxs), zoom_x.min(zoom_y));
+    }
+
+    /// Converts a point from screen-space (origin at the top-left corner of the window) to
+    /// world-space, using the last known size of the window.
+    pub fn screen_to_world(&self, screen_coord: &Point2<f32>) -> Point2<f32> {
+        self.unproject(screen_coord, &self.viewport)
+    }
+
+    /// Converts a point from world-space to screen-space (origin at the top-left corner of the
+    /// window), using the last known size of the window.
+    pub fn world_to_screen(&self, world_coord: &Point2<f32>) -> Point2<f32> {
+        self.project(world_coord, &self.viewport)
+    }
+
     /// Transformation applied by the camera without perspective.
     fn update_restrictions(&mut self) {
-        if self.zoom < 0.00001 {
-            self.zoom = 0.00001
+        if self.zoom < self.min_zoom {
+            self.zoom = self.min_zoom
+        }
+
+        if self.zoom > self.max_zoom {
+            self.zoom = self.max_zoom
         }
     }
 
@@ -96,8 +178,9 @@ impl Sidescroll {
     /// Move the camera based on drag from right mouse button
     /// `dpos` is assumed to be in window space so the y-axis is flipped
     fn handle_right_button_displacement(&mut self, dpos: &Vector2<f32>) {
-        self.at.x -= dpos.x / self.zoom;
-        self.at.y += dpos.y / self.zoom;
+        let delta = Vector2::new(-dpos.x, dpos.y) / self.zoom;
+        self.at += delta;
+        self.drag_delta += delta;
         self.update_projviews();
     }
 
@@ -137,6 +220,7 @@ impl PlanarCamera for Sidescroll {
             }
             WindowEvent::Scroll(_, off, _) => self.handle_scroll(off as f32),
             WindowEvent::FramebufferSize(w, h) => {
+                self.viewport = Vector2::new(w as f32, h as f32);
                 self.proj = Matrix3::new(
                     2.0 * (scale as f32) / (w as f32),
                     0.0,
@@ -164,7 +248,22 @@ impl PlanarCamera for Sidescroll {
         view.upload(&self.view);
     }
 
-    fn update(&mut self, _: &Canvas) {}
+    fn update(&mut self, canvas: &Canvas) {
+        let dragging = self
+            .drag_button
+            .map(|b| canvas.get_mouse_button(b) == Action::Press)
+            .unwrap_or(false);
+
+        if dragging {
+            self.velocity = self.drag_delta;
+        } else if self.inertia > 0.0 && self.velocity.norm_squared() > 1.0e-10 {
+            self.at += self.velocity;
+            self.velocity *= self.inertia;
+            self.update_projviews();
+        }
+
+        self.drag_delta = na::zero();
+    }
 
     /// Calculate the global position of the given window coordinate
     fn unproject(&self, window_coord: &Point2<f32>, size: &Vector2<f32>) -> Point2<f32> {
@@ -181,4 +280,14 @@ impl PlanarCamera for Sidescroll {
         // Convert from screen space to global space
         Point2::from_homogeneous(unprojected_hom).unwrap() + self.at.coords
     }
+
+    fn project(&self, world_coord: &Point2<f32>, size: &Vector2<f32>) -> Point2<f32> {
+        let projected_hom = self.scaled_proj * (world_coord - self.at.coords).to_homogeneous();
+        let normalized_coords = Point2::from_homogeneous(projected_hom).unwrap();
+
+        Point2::new(
+            (normalized_coords.x + 1.0) * size.x / 2.0,
+            (1.0 - normalized_coords.y) * size.y / 2.0,
+        )
+    }
 }
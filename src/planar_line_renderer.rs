@@ -4,6 +4,7 @@ use crate::context::Context;
 use crate::planar_camera::PlanarCamera;
 use crate::resource::{AllocationType, BufferType, Effect, GPUVec, ShaderAttribute, ShaderUniform};
 use na::{Matrix3, Point2, Point3};
+use std::hash::Hasher;
 
 #[path = "error.rs"]
 mod error;
@@ -23,7 +24,7 @@ pub struct PlanarLineRenderer {
 impl PlanarLineRenderer {
     /// Creates a new lines manager.
     pub fn new() -> PlanarLineRenderer {
-        let mut shader = Effect::new_from_str(LINES_VERTEX_SRC, LINES_FRAGMENT_SRC);
+        let mut shader = Effect::new_from_str(LINES_VERTEX_SRC, LINES_FRAGMENT_SRC).unwrap();
 
         shader.use_program();
 
@@ -65,6 +66,38 @@ impl PlanarLineRenderer {
         }
     }
 
+    // The endpoints and colors of the lines queued for the next frame.
+    pub(crate) fn queued(&self) -> (Vec<Point2<f32>>, Vec<Point3<f32>>) {
+        (
+            self.lines.data().clone().unwrap_or_default(),
+            self.colors.data().clone().unwrap_or_default(),
+        )
+    }
+
+    // Feeds the lines queued for the next frame to `state`, to detect that they changed.
+    pub(crate) fn hash_queued<H: Hasher>(&self, state: &mut H) {
+        for lines in self.lines.data().iter() {
+            for p in lines {
+                state.write_u32(p.x.to_bits());
+                state.write_u32(p.y.to_bits());
+            }
+        }
+
+        for colors in self.colors.data().iter() {
+            for c in colors {
+                state.write_u32(c.x.to_bits());
+                state.write_u32(c.y.to_bits());
+                state.write_u32(c.z.to_bits());
+            }
+        }
+    }
+
+    // Replaces the lines queued for the next frame, e.g., after a frame rendered for a snapshot.
+    pub(crate) fn requeue(&mut self, (lines, colors): (Vec<Point2<f32>>, Vec<Point3<f32>>)) {
+        *self.lines.data_mut() = Some(lines);
+        *self.colors.data_mut() = Some(colors);
+    }
+
     /// Actually draws the lines.
     pub fn render(&mut self, camera: &mut dyn PlanarCamera) {
         if self.lines.len() == 0 {
@@ -0,0 +1,200 @@
+//! Post-processing effect defined by a fragment shader applied to the whole screen.
+
+use na::Vector2;
+
+use crate::context::Context;
+use crate::post_processing::PostProcessingEffect;
+use crate::resource::{
+    AllocationType, BufferType, Effect, GLPrimitive, GPUVec, RenderTarget, ShaderAttribute,
+    ShaderError, ShaderUniform,
+};
+
+#[path = "../error.rs"]
+mod error;
+
+/// Post processing effect which runs a user-provided fragment shader on each pixel of the screen.
+///
+/// The fragment shader is written in GLSL ES 1.00, and may declare any of the following inputs,
+/// set by the effect:
+///
+/// * `varying vec2 f_texcoord` - the texture coordinates of the pixel in the rendered scene.
+/// * `uniform sampler2D fbo_texture` - the colors of the rendered scene.
+/// * `uniform sampler2D fbo_depth` - the depths of the rendered scene, between 0 and 1. Not
+///   available on WebGL.
+/// * `uniform vec2 resolution` - the size of the screen, in pixels.
+/// * `uniform float time` - the time elapsed since the effect was created, in seconds.
+/// * `uniform float znear` and `uniform float zfar` - the clipping planes of the camera.
+///
+/// Other uniforms are set with `set_uniform`. Declared inputs unused by the shader are ignored.
+///
+/// # Example
+/// ```no_run
+/// # use kiss3d::post_processing::FullScreenQuadEffect;
+/// let mut vignette = FullScreenQuadEffect::from_fragment_shader(
+///     "#version 100
+///     precision mediump float;
+///     uniform sampler2D fbo_texture;
+///     uniform float strength;
+///     varying vec2 f_texcoord;
+///
+///     void main() {
+///         float d = distance(f_texcoord, vec2(0.5));
+///         vec4 color = texture2D(fbo_texture, f_texcoord);
+///         gl_FragColor = vec4(color.rgb * (1.0 - strength * d * d), color.a);
+///     }",
+/// )
+/// .unwrap();
+/// vignette.set_uniform("strength", 1.5f32);
+/// ```
+pub struct FullScreenQuadEffect {
+    shader: Effect,
+    time: f32,
+    size: Vector2<f32>,
+    clip_planes: (f32, f32),
+    fbo_texture: Option<ShaderUniform<i32>>,
+    #[cfg(not(target_arch = "wasm32"))]
+    fbo_depth: Option<ShaderUniform<i32>>,
+    resolution: Option<ShaderUniform<Vector2<f32>>>,
+    time_uniform: Option<ShaderUniform<f32>>,
+    znear: Option<ShaderUniform<f32>>,
+    zfar: Option<ShaderUniform<f32>>,
+    // The uniforms set by the user, each with a closure uploading its last value.
+    uniforms: Vec<(String, Box<dyn FnMut()>)>,
+    v_coord: ShaderAttribute<Vector2<f32>>,
+    fbo_vertices: GPUVec<Vector2<f32>>,
+}
+
+impl FullScreenQuadEffect {
+    /// Creates a new post processing effect running the given fragment shader.
+    ///
+    /// Fails if the shader does not compile, or does not link with the vertex shader of the
+    /// effect, e.g., if it declares `f_texcoord` with another type.
+    pub fn from_fragment_shader(fshader: &str) -> Result<FullScreenQuadEffect, ShaderError> {
+        let fbo_vertices: Vec<Vector2<f32>> = vec![
+            Vector2::new(-1.0, -1.0),
+            Vector2::new(1.0, -1.0),
+            Vector2::new(-1.0, 1.0),
+            Vector2::new(1.0, 1.0),
+        ];
+
+        let mut fbo_vertices =
+            GPUVec::new(fbo_vertices, BufferType::Array, AllocationType::StaticDraw);
+        fbo_vertices.load_to_gpu();
+        fbo_vertices.unload_from_ram();
+
+        let mut shader = Effect::new_from_str(VERTEX_SHADER, fshader)?;
+
+        shader.use_program();
+
+        Ok(FullScreenQuadEffect {
+            time: 0.0,
+            size: Vector2::new(1.0, 1.0),
+            clip_planes: (0.1, 1024.0),
+            fbo_texture: shader.get_uniform("fbo_texture"),
+            #[cfg(not(target_arch = "wasm32"))]
+            fbo_depth: shader.get_uniform("fbo_depth"),
+            resolution: shader.get_uniform("resolution"),
+            time_uniform: shader.get_uniform("time"),
+            znear: shader.get_uniform("znear"),
+            zfar: shader.get_uniform("zfar"),
+            uniforms: Vec::new(),
+            v_coord: shader.get_attrib("v_coord").unwrap(),
+            fbo_vertices,
+            shader,
+        })
+    }
+
+    /// Sets the value of a uniform of the fragment shader, uploaded each time the effect is drawn.
+    ///
+    /// The type of `value` must match the type of the uniform, e.g., `f32` for a `float`, `i32`
+    /// for an `int`, or `Vector3<f32>` for a `vec3`. Returns `false`, and does nothing, if the
+    /// shader has no active uniform named `name`.
+    pub fn set_uniform<T: GLPrimitive + 'static>(&mut self, name: &str, value: T) -> bool {
+        let mut uniform = match self.shader.get_uniform::<T>(name) {
+            Some(uniform) => uniform,
+            None => return false,
+        };
+
+        let upload = Box::new(move || uniform.upload(&value));
+
+        match self.uniforms.iter_mut().find(|(n, _)| n == name) {
+            Some(entry) => entry.1 = upload,
+            None => self.uniforms.push((name.to_string(), upload)),
+        }
+
+        true
+    }
+
+    /// The time elapsed since the effect was created, in seconds, as given to the shader.
+    pub fn time(&self) -> f32 {
+        self.time
+    }
+}
+
+impl PostProcessingEffect for FullScreenQuadEffect {
+    fn update(&mut self, dt: f32, w: f32, h: f32, znear: f32, zfar: f32) {
+        self.time += dt;
+        self.size = Vector2::new(w, h);
+        self.clip_planes = (znear, zfar);
+    }
+
+    fn draw(&mut self, target: &RenderTarget) {
+        let ctxt = Context::get();
+        self.v_coord.enable();
+
+        /*
+         * Finalize draw
+         */
+        self.shader.use_program();
+        verify!(ctxt.clear_color(0.0, 0.0, 0.0, 1.0));
+        verify!(ctxt.clear(Context::COLOR_BUFFER_BIT | Context::DEPTH_BUFFER_BIT));
+
+        #[cfg(not(target_arch = "wasm32"))]
+        if let Some(ref mut fbo_depth) = self.fbo_depth {
+            verify!(ctxt.active_texture(Context::TEXTURE1));
+            verify!(ctxt.bind_texture(
+                Context::TEXTURE_2D,
+                target.depth_id().and_then(|id| id.as_ref().left())
+            ));
+            fbo_depth.upload(&1);
+        }
+
+        verify!(ctxt.active_texture(Context::TEXTURE0));
+        verify!(ctxt.bind_texture(Context::TEXTURE_2D, target.texture_id()));
+
+        if let Some(ref mut fbo_texture) = self.fbo_texture {
+            fbo_texture.upload(&0);
+        }
+        if let Some(ref mut resolution) = self.resolution {
+            resolution.upload(&self.size);
+        }
+        if let Some(ref mut time) = self.time_uniform {
+            time.upload(&self.time);
+        }
+        if let Some(ref mut znear) = self.znear {
+            znear.upload(&self.clip_planes.0);
+        }
+        if let Some(ref mut zfar) = self.zfar {
+            zfar.upload(&self.clip_planes.1);
+        }
+
+        for (_, upload) in &mut self.uniforms {
+            upload();
+        }
+
+        self.v_coord.bind(&mut self.fbo_vertices);
+
+        verify!(ctxt.draw_arrays(Context::TRIANGLE_STRIP, 0, 4));
+
+        self.v_coord.disable();
+    }
+}
+
+static VERTEX_SHADER: &str = "#version 100
+    attribute vec2 v_coord;
+    varying vec2   f_texcoord;
+
+    void main(void) {
+      gl_Position = vec4(v_coord, 0.0, 1.0);
+      f_texcoord  = (v_coord + 1.0) / 2.0;
+    }";
@@ -34,7 +34,7 @@ impl Grayscales {
         fbo_vertices.load_to_gpu();
         fbo_vertices.unload_from_ram();
 
-        let mut shader = Effect::new_from_str(VERTEX_SHADER, FRAGMENT_SHADER);
+        let mut shader = Effect::new_from_str(VERTEX_SHADER, FRAGMENT_SHADER).unwrap();
 
         shader.use_program();
 
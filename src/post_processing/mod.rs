@@ -1,14 +1,18 @@
 //! Post-processing effects.
 
+pub use crate::post_processing::full_screen_quad_effect::FullScreenQuadEffect;
 pub use crate::post_processing::grayscales::Grayscales;
 pub use crate::post_processing::oculus_stereo::OculusStereo;
+pub use crate::post_processing::post_processing_chain::PostProcessingChain;
 pub use crate::post_processing::post_processing_effect::PostProcessingEffect;
 #[cfg(not(target_arch = "wasm32"))]
 pub use crate::post_processing::sobel_edge_highlight::SobelEdgeHighlight;
 pub use crate::post_processing::waves::Waves;
 
+mod full_screen_quad_effect;
 mod grayscales;
 mod oculus_stereo;
+mod post_processing_chain;
 pub mod post_processing_effect;
 #[cfg(not(target_arch = "wasm32"))]
 mod sobel_edge_highlight;
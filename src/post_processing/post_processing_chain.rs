@@ -0,0 +1,170 @@
+//! Post-processing effect applying several effects one after the other.
+
+use crate::context::Context;
+use crate::post_processing::{FullScreenQuadEffect, PostProcessingEffect};
+use crate::resource::{FramebufferManager, RenderTarget};
+
+#[path = "../error.rs"]
+mod error;
+
+/// Post processing effect which applies a sequence of effects, each one to the image drawn by
+/// the previous one.
+///
+/// The intermediate images are drawn alternately into two offscreen buffers of the size of the
+/// screen. Only the first effect can read the depths of the scene: the depth buffers given to the
+/// next ones are empty. An empty chain draws the scene unchanged.
+///
+/// # Example
+/// ```no_run
+/// # use kiss3d::post_processing::{Grayscales, PostProcessingChain, SobelEdgeHighlight};
+/// # use kiss3d::window::Window;
+/// let mut window = Window::new("Kiss3d: post-processing chain");
+/// let mut chain = PostProcessingChain::new();
+/// chain.push(SobelEdgeHighlight::new(4.0));
+/// chain.push(Grayscales::new());
+///
+/// while window.render_with_effect(&mut chain) {}
+/// ```
+pub struct PostProcessingChain {
+    effects: Vec<Box<dyn PostProcessingEffect>>,
+    // The buffers the effects but the last one draw into, with their size.
+    targets: Option<([RenderTarget; 2], (usize, usize))>,
+    size: (usize, usize),
+    framebuffers: FramebufferManager,
+    // Draws the scene unchanged if the chain is empty.
+    passthrough: Option<FullScreenQuadEffect>,
+    // The context generation the buffers were created with.
+    generation: u64,
+}
+
+impl PostProcessingChain {
+    /// Creates a new empty chain of post processing effects.
+    pub fn new() -> PostProcessingChain {
+        PostProcessingChain {
+            effects: Vec::new(),
+            targets: None,
+            size: (1, 1),
+            framebuffers: FramebufferManager::new(),
+            passthrough: None,
+            generation: Context::generation(),
+        }
+    }
+
+    /// Appends an effect to the end of this chain, applied after the effects already in it.
+    pub fn push<E: PostProcessingEffect + 'static>(&mut self, effect: E) {
+        self.effects.push(Box::new(effect))
+    }
+
+    /// Removes the last effect of this chain, and returns it.
+    pub fn pop(&mut self) -> Option<Box<dyn PostProcessingEffect>> {
+        self.effects.pop()
+    }
+
+    /// Removes every effect of this chain.
+    pub fn clear(&mut self) {
+        self.effects.clear()
+    }
+
+    /// The number of effects of this chain.
+    pub fn len(&self) -> usize {
+        self.effects.len()
+    }
+
+    /// Whether this chain has no effect.
+    pub fn is_empty(&self) -> bool {
+        self.effects.is_empty()
+    }
+}
+
+impl Default for PostProcessingChain {
+    fn default() -> PostProcessingChain {
+        PostProcessingChain::new()
+    }
+}
+
+impl PostProcessingEffect for PostProcessingChain {
+    fn update(&mut self, dt: f32, w: f32, h: f32, znear: f32, zfar: f32) {
+        self.size = ((w as usize).max(1), (h as usize).max(1));
+
+        for effect in &mut self.effects {
+            effect.update(dt, w, h, znear, zfar);
+        }
+    }
+
+    fn draw(&mut self, target: &RenderTarget) {
+        if self.effects.is_empty() {
+            let passthrough = self.passthrough.get_or_insert_with(|| {
+                FullScreenQuadEffect::from_fragment_shader(PASSTHROUGH_FRAGMENT_SHADER).unwrap()
+            });
+            passthrough.draw(target);
+            return;
+        }
+
+        let size = self.size;
+
+        if self.generation != Context::generation() {
+            // The buffers have been lost with the context.
+            self.targets = None;
+            self.framebuffers = FramebufferManager::new();
+            self.generation = Context::generation();
+        }
+
+        if self.effects.len() > 1 {
+            match self.targets {
+                Some((ref mut targets, ref mut curr)) if *curr != size => {
+                    for t in targets.iter_mut() {
+                        t.resize(size.0 as f32, size.1 as f32);
+                    }
+                    *curr = size;
+                }
+                Some(_) => {}
+                None => {
+                    let new_target =
+                        || FramebufferManager::new_render_target(size.0, size.1, false);
+                    self.targets = Some(([new_target(), new_target()], size));
+                }
+            }
+        }
+
+        let ctxt = Context::get();
+        // The framebuffer the last effect draws into.
+        let output = ctxt.framebuffer_binding();
+        let last = self.effects.len() - 1;
+        let mut input = target;
+
+        for (i, effect) in self.effects.iter_mut().enumerate() {
+            // Effects sample their input with the active texture unit.
+            verify!(ctxt.active_texture(Context::TEXTURE0));
+
+            match self.targets {
+                Some((ref targets, _)) if i < last => {
+                    self.framebuffers.select(&targets[i % 2]);
+                    effect.draw(input);
+                    input = &targets[i % 2];
+                }
+                _ => {
+                    // Unbinds the framebuffer of the manager through it, for it to bind it again.
+                    self.framebuffers.select(&FramebufferManager::screen());
+                    verify!(ctxt.bind_framebuffer(Context::FRAMEBUFFER, output.as_ref()));
+                    effect.draw(input);
+                }
+            }
+        }
+
+        verify!(ctxt.active_texture(Context::TEXTURE0));
+    }
+}
+
+static PASSTHROUGH_FRAGMENT_SHADER: &str = "#version 100
+#ifdef GL_FRAGMENT_PRECISION_HIGH
+   precision highp float;
+#else
+   precision mediump float;
+#endif
+
+    uniform sampler2D fbo_texture;
+    varying vec2      f_texcoord;
+
+    void main(void) {
+      gl_FragColor = texture2D(fbo_texture, f_texcoord);
+    }";
@@ -4,16 +4,28 @@ use crate::resource::RenderTarget;
 
 /// Trait of post processing effect.
 ///
-/// One post-processing effect can be used at a time. It is executed once the scene has been
-/// rendered on a texture.
+/// One post-processing effect can be used at a time, e.g., with `Window::render_with_effect`. It
+/// is executed once the scene has been rendered on a texture, and draws the final image on the
+/// screen from that texture, before the overlays and texts are drawn.
+///
+/// Effects written only in GLSL can use `FullScreenQuadEffect`, which implements this trait for a
+/// fragment shader.
 pub trait PostProcessingEffect {
-    /// Updates the post processing effect.
+    /// Updates the post processing effect, once per frame before it is drawn.
+    ///
+    /// # Arguments:
+    /// * `dt` - the time elapsed since the last frame, in seconds.
+    /// * `w` - the width of the screen, in pixels.
+    /// * `h` - the height of the screen, in pixels.
+    /// * `znear` - the near clipping plane of the camera.
+    /// * `zfar` - the far clipping plane of the camera.
     fn update(&mut self, dt: f32, w: f32, h: f32, znear: f32, zfar: f32);
     /// Render the effect.
     ///
+    /// The framebuffer receiving the final image, usually the screen, is bound when this is called.
+    ///
     /// # Arguments:
-    /// * `shader_manager` - manager to switch between the different shaders.
-    /// * `fbo_texture` - id to the texture containing the last scene drawn.
-    /// * `fbo_depth` - the depth buffer as a texture.
+    /// * `target` - the render target containing the last scene drawn. Its color and depth
+    ///   buffers are given by `RenderTarget::texture_id` and `RenderTarget::depth_id`.
     fn draw(&mut self, target: &RenderTarget);
 }
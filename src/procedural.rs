@@ -0,0 +1,235 @@
+//! Procedural generation of geometry, complementing `ncollide3d::procedural`.
+
+use std::f32;
+
+use na::{Point2, Point3, Vector3};
+use ncollide3d::procedural::{IndexBuffer, TriMesh};
+
+/// A line following the flow of a vector field.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Streamline {
+    /// The points of the line, starting at its seed.
+    pub points: Vec<Point3<f32>>,
+    /// The norm of the vector field at each point of the line.
+    pub speeds: Vec<f32>,
+}
+
+impl Streamline {
+    /// The length of this line.
+    pub fn length(&self) -> f32 {
+        self.points
+            .windows(2)
+            .map(|w| na::distance(&w[0], &w[1]))
+            .sum()
+    }
+
+    /// A tube of the given radius following this line, with `sides` vertices around each point.
+    ///
+    /// The tube is open at both ends. Its `v` texture coordinate goes from `0` at the seed to `1`
+    /// at the end of the line. Kiss3d meshes being limited to 65536 vertices, the line should
+    /// have less than `65536 / sides` points for the tube to be added to a scene.
+    pub fn tube(&self, radius: f32, sides: usize) -> TriMesh<f32> {
+        assert!(sides >= 3, "A tube must have at least three sides.");
+
+        let n = self.points.len();
+        let coords: Vec<_> = tube_vertices(&self.points, radius, sides).collect();
+        let normals = coords
+            .iter()
+            .enumerate()
+            .map(|(i, pt)| (pt - self.points[i / sides]) / radius)
+            .collect();
+        let uvs = (0..n)
+            .flat_map(|k| {
+                let v = k as f32 / (n - 1).max(1) as f32;
+                (0..sides).map(move |s| Point2::new(s as f32 / sides as f32, v))
+            })
+            .collect();
+        let mut faces = Vec::new();
+
+        for k in 0..n.saturating_sub(1) {
+            for s in 0..sides {
+                let a = (k * sides + s) as u32;
+                let b = (k * sides + (s + 1) % sides) as u32;
+                let (c, d) = (a + sides as u32, b + sides as u32);
+                faces.push(Point3::new(a, b, c));
+                faces.push(Point3::new(b, d, c));
+            }
+        }
+
+        TriMesh::new(
+            coords,
+            Some(normals),
+            Some(uvs),
+            Some(IndexBuffer::Unified(faces)),
+        )
+    }
+}
+
+// The maximum number of integration steps of a streamline.
+const MAX_STREAMLINE_STEPS: usize = 100_000;
+
+/// Integrates the streamlines of a vector field starting at the given seeds.
+///
+/// Each line is traced with fourth-order Runge-Kutta steps of length `step` along the normalized
+/// field, a negative `step` tracing the lines backward. A line stops when its length reaches
+/// `max_len`, after 100000 steps, or where the field vanishes or is not finite.
+///
+/// Panics if `step` is zero or not finite, or if `max_len` is not finite and positive.
+///
+/// ```no_run
+/// # use kiss3d::window::Window;
+/// # use kiss3d::procedural;
+/// # use kiss3d::nalgebra::{Point3, Vector3};
+/// let mut window = Window::new("Kiss3d: streamlines");
+/// let vortex = |p: &Point3<f32>| Vector3::new(-p.z, 0.2, p.x);
+/// let seeds: Vec<_> = (1..5).map(|i| Point3::new(i as f32 * 0.2, 0.0, 0.0)).collect();
+///
+/// for line in procedural::streamlines(vortex, &seeds, 0.02, 5.0) {
+///     let mut tube = window.add_trimesh(line.tube(0.01, 6), Vector3::repeat(1.0));
+///     tube.set_color([0.2, 0.6, 1.0]);
+/// }
+///
+/// while window.render() {}
+/// ```
+pub fn streamlines(
+    mut field: impl FnMut(&Point3<f32>) -> Vector3<f32>,
+    seeds: &[Point3<f32>],
+    step: f32,
+    max_len: f32,
+) -> Vec<Streamline> {
+    assert!(
+        step != 0.0 && step.is_finite(),
+        "The integration step must be finite and non-zero."
+    );
+    assert!(
+        max_len > 0.0 && max_len.is_finite(),
+        "The maximum length of the streamlines must be finite and positive."
+    );
+
+    let max_steps = ((max_len / step.abs()).ceil() as usize).min(MAX_STREAMLINE_STEPS);
+    let mut direction = |p: &Point3<f32>| {
+        let v = field(p);
+
+        if v.iter().all(|x| x.is_finite()) {
+            v.try_normalize(1.0e-12).map(|dir| (dir, v.norm()))
+        } else {
+            None
+        }
+    };
+
+    seeds
+        .iter()
+        .map(|seed| {
+            let mut line = Streamline {
+                points: Vec::new(),
+                speeds: Vec::new(),
+            };
+            let mut p = *seed;
+            let mut current = direction(&p);
+            let mut len = 0.0;
+
+            line.points.push(p);
+            line.speeds.push(current.map_or(0.0, |(_, speed)| speed));
+
+            for _ in 0..max_steps {
+                let k1 = match current {
+                    Some((dir, _)) => dir,
+                    None => break,
+                };
+                let h = step.min(max_len - len).max(-(max_len - len));
+                let k2 = match direction(&(p + k1 * (h / 2.0))) {
+                    Some((dir, _)) => dir,
+                    None => break,
+                };
+                let k3 = match direction(&(p + k2 * (h / 2.0))) {
+                    Some((dir, _)) => dir,
+                    None => break,
+                };
+                let k4 = match direction(&(p + k3 * h)) {
+                    Some((dir, _)) => dir,
+                    None => break,
+                };
+
+                let next = p + (k1 + k2 * 2.0 + k3 * 2.0 + k4) * (h / 6.0);
+                len += na::distance(&p, &next);
+                p = next;
+                current = direction(&p);
+
+                line.points.push(p);
+                line.speeds.push(current.map_or(0.0, |(_, speed)| speed));
+
+                if len >= max_len {
+                    break;
+                }
+            }
+
+            line
+        })
+        .collect()
+}
+
+// The rings of vertices of a tube following the given line.
+//
+// The frames of the rings are propagated along the line by parallel transport, so that the tube
+// does not twist.
+pub(crate) fn tube_vertices(
+    line: &[Point3<f32>],
+    radius: f32,
+    sides: usize,
+) -> impl Iterator<Item = Point3<f32>> + '_ {
+    let mut normal: Option<Vector3<f32>> = None;
+
+    (0..line.len()).flat_map(move |k| {
+        let next = line[(k + 1).min(line.len() - 1)];
+        let prev = line[k.saturating_sub(1)];
+        let tangent = (next - prev)
+            .try_normalize(1.0e-12)
+            .unwrap_or_else(Vector3::y);
+        let n = normal
+            .and_then(|n| (n - tangent * tangent.dot(&n)).try_normalize(1.0e-12))
+            .unwrap_or_else(|| {
+                let axis = if tangent.x.abs() < 0.9 {
+                    Vector3::x()
+                } else {
+                    Vector3::z()
+                };
+                tangent.cross(&axis).normalize()
+            });
+        let b = tangent.cross(&n);
+        normal = Some(n);
+
+        let center = line[k];
+        (0..sides).map(move |s| {
+            let angle = s as f32 / sides as f32 * f32::consts::PI * 2.0;
+            center + (n * angle.cos() + b * angle.sin()) * radius
+        })
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{streamlines, MAX_STREAMLINE_STEPS};
+    use na::{Point3, Vector3};
+
+    fn uniform(_: &Point3<f32>) -> Vector3<f32> {
+        Vector3::x()
+    }
+
+    #[test]
+    fn streamlines_with_tiny_steps_are_capped() {
+        let lines = streamlines(uniform, &[Point3::origin()], 1.0e-30, 1.0);
+        assert_eq!(lines[0].points.len(), MAX_STREAMLINE_STEPS + 1);
+    }
+
+    #[test]
+    #[should_panic]
+    fn streamlines_with_an_infinite_length_are_rejected() {
+        let _ = streamlines(uniform, &[Point3::origin()], 0.1, f32::INFINITY);
+    }
+
+    #[test]
+    #[should_panic]
+    fn streamlines_with_a_non_finite_step_are_rejected() {
+        let _ = streamlines(uniform, &[Point3::origin()], f32::NAN, 1.0);
+    }
+}
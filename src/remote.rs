@@ -0,0 +1,425 @@
+//! A viewer mode where other processes stream scene commands to a window over TCP.
+//!
+//! The protocol is made of JSON objects, one per line, each describing a `RemoteCommand`:
+//!
+//! ```text
+//! {"cmd": "add_cube", "id": "base", "size": [1.0, 0.2, 1.0]}
+//! {"cmd": "set_transform", "id": "base", "translation": [0.0, 1.0, 0.0], "rotation": [0.0, 0.0, 0.0, 1.0]}
+//! {"cmd": "set_color", "id": "base", "color": [1.0, 0.0, 0.0]}
+//! {"cmd": "draw_lines", "id": "path", "lines": [[[0.0, 0.0, 0.0], [1.0, 1.0, 1.0]]], "color": [0.0, 1.0, 0.0]}
+//! {"cmd": "remove", "id": "base"}
+//! ```
+//!
+//! so any language able to open a socket can drive the viewer, for example a simulation running
+//! in its own process. Rust programs can use `RemoteClient`.
+//!
+//! Unlike the rest of the scene, lines and points sent with `draw_lines` and `draw_points`
+//! persist: they are drawn at every frame until replaced by another command with the same
+//! identifier, or removed.
+//!
+//! Valid commands get no reply. A command that cannot be parsed or applied, e.g., a mesh with
+//! out-of-bounds indices, is ignored and answered with a line of the form:
+//!
+//! ```text
+//! {"error": "remote mesh `hull` has more than 65536 vertices"}
+//! ```
+
+use na::{Point3, Quaternion, Translation3, UnitQuaternion, Vector3};
+use ncollide3d::procedural::{IndexBuffer, TriMesh};
+use std::collections::HashMap;
+use std::io::{self, BufRead, BufReader, Write};
+use std::net::{SocketAddr, TcpListener, TcpStream, ToSocketAddrs};
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::thread;
+
+use crate::scene::SceneNode;
+use crate::window::Window;
+
+// The meshes are drawn with 16-bit indices.
+const MAX_MESH_VERTICES: usize = u16::MAX as usize + 1;
+
+/// A command sent to a `RemoteViewer`.
+///
+/// Objects are designated by identifiers chosen by the client. Adding an object with an
+/// identifier already in use replaces the previous object.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "cmd", rename_all = "snake_case")]
+pub enum RemoteCommand {
+    /// Adds a cuboid with the given extents.
+    AddCube {
+        /// The identifier of the new object.
+        id: String,
+        /// The extents of the cuboid along each axis.
+        size: [f32; 3],
+    },
+    /// Adds a sphere.
+    AddSphere {
+        /// The identifier of the new object.
+        id: String,
+        /// The radius of the sphere.
+        radius: f32,
+    },
+    /// Adds a triangle mesh.
+    AddMesh {
+        /// The identifier of the new object.
+        id: String,
+        /// The vertices of the mesh, at most 65536.
+        coords: Vec<[f32; 3]>,
+        /// The indices of the vertices of each triangle.
+        faces: Vec<[u32; 3]>,
+    },
+    /// Sets the transformation of an object. Omitted fields are left unchanged.
+    SetTransform {
+        /// The identifier of the object.
+        id: String,
+        /// The new translation.
+        #[serde(default)]
+        translation: Option<[f32; 3]>,
+        /// The new rotation, as a quaternion with its components in the order `[i, j, k, w]`.
+        #[serde(default)]
+        rotation: Option<[f32; 4]>,
+        /// The new scaling factors.
+        #[serde(default)]
+        scale: Option<[f32; 3]>,
+    },
+    /// Sets the color of an object.
+    SetColor {
+        /// The identifier of the object.
+        id: String,
+        /// The new color.
+        color: [f32; 3],
+    },
+    /// Shows or hides an object.
+    SetVisible {
+        /// The identifier of the object.
+        id: String,
+        /// Whether the object is rendered.
+        visible: bool,
+    },
+    /// Draws a set of lines at every frame, replacing the lines with the same identifier.
+    DrawLines {
+        /// The identifier of the set of lines.
+        id: String,
+        /// The end points of each line.
+        lines: Vec<[[f32; 3]; 2]>,
+        /// The color of the lines.
+        color: [f32; 3],
+    },
+    /// Draws a set of points at every frame, replacing the points with the same identifier.
+    DrawPoints {
+        /// The identifier of the set of points.
+        id: String,
+        /// The points.
+        points: Vec<[f32; 3]>,
+        /// The color of the points.
+        color: [f32; 3],
+    },
+    /// Removes the object, lines or points with the given identifier.
+    Remove {
+        /// The identifier of the element to remove.
+        id: String,
+    },
+    /// Removes everything added by the clients.
+    Clear,
+}
+
+impl RemoteCommand {
+    // Checks that the command can be applied.
+    fn validate(&self) -> Result<(), String> {
+        match self {
+            RemoteCommand::AddMesh { id, coords, faces } => {
+                if coords.len() > MAX_MESH_VERTICES {
+                    Err(format!(
+                        "remote mesh `{}` has more than {} vertices",
+                        id, MAX_MESH_VERTICES
+                    ))
+                } else if faces.iter().flatten().any(|i| *i as usize >= coords.len()) {
+                    Err(format!("remote mesh `{}` has out-of-bounds indices", id))
+                } else {
+                    Ok(())
+                }
+            }
+            _ => Ok(()),
+        }
+    }
+}
+
+type Lines = (Vec<[[f32; 3]; 2]>, Point3<f32>);
+type Points = (Vec<[f32; 3]>, Point3<f32>);
+
+/// A server applying the commands streamed by other processes to a window.
+///
+/// ```no_run
+/// # use kiss3d::window::Window;
+/// # use kiss3d::remote::RemoteViewer;
+/// let mut window = Window::new("Remote viewer");
+/// let mut viewer = RemoteViewer::bind("127.0.0.1:4455").unwrap();
+///
+/// while window.render() {
+///     let _ = viewer.update(&mut window);
+/// }
+/// ```
+pub struct RemoteViewer {
+    commands: Receiver<RemoteCommand>,
+    local_addr: SocketAddr,
+    nodes: HashMap<String, SceneNode>,
+    lines: HashMap<String, Lines>,
+    points: HashMap<String, Points>,
+}
+
+impl RemoteViewer {
+    /// Starts listening for clients at the given address.
+    ///
+    /// Any number of clients may be connected at the same time. Their commands are received on
+    /// background threads and applied by `update`.
+    pub fn bind<A: ToSocketAddrs>(addr: A) -> io::Result<RemoteViewer> {
+        let listener = TcpListener::bind(addr)?;
+        let local_addr = listener.local_addr()?;
+        let (sender, commands) = mpsc::channel();
+
+        let _ = thread::spawn(move || accept_clients(listener, sender));
+
+        Ok(RemoteViewer {
+            commands,
+            local_addr,
+            nodes: HashMap::new(),
+            lines: HashMap::new(),
+            points: HashMap::new(),
+        })
+    }
+
+    /// The address the server is listening at.
+    pub fn local_addr(&self) -> SocketAddr {
+        self.local_addr
+    }
+
+    /// The node of the object added with the identifier `id`.
+    pub fn node(&self, id: &str) -> Option<&SceneNode> {
+        self.nodes.get(id)
+    }
+
+    /// Applies the commands received since the last call and draws the persistent lines and
+    /// points.
+    ///
+    /// Call this once per frame, before rendering. Returns the number of commands applied.
+    pub fn update(&mut self, window: &mut Window) -> usize {
+        let commands: Vec<_> = self.commands.try_iter().collect();
+        let num_commands = commands.len();
+
+        for command in commands {
+            self.apply(window, command);
+        }
+
+        for (lines, color) in self.lines.values() {
+            for [a, b] in lines {
+                window.draw_line(&Point3::from(*a), &Point3::from(*b), color);
+            }
+        }
+
+        for (points, color) in self.points.values() {
+            for pt in points {
+                window.draw_point(&Point3::from(*pt), color);
+            }
+        }
+
+        num_commands
+    }
+
+    /// Applies a single command, as if it had been received from a client.
+    ///
+    /// A command that cannot be applied is ignored with a warning.
+    pub fn apply(&mut self, window: &mut Window, command: RemoteCommand) {
+        if let Err(e) = command.validate() {
+            println!("Warning: {}.", e);
+            return;
+        }
+
+        match command {
+            RemoteCommand::AddCube { id, size } => {
+                let node = window.add_cube(size[0], size[1], size[2]);
+                self.insert_node(window, id, node);
+            }
+            RemoteCommand::AddSphere { id, radius } => {
+                let node = window.add_sphere(radius);
+                self.insert_node(window, id, node);
+            }
+            RemoteCommand::AddMesh { id, coords, faces } => {
+                let coords = coords.into_iter().map(Point3::from).collect();
+                let faces = faces.into_iter().map(Point3::from).collect();
+                let mesh = TriMesh::new(coords, None, None, Some(IndexBuffer::Unified(faces)));
+                let node = window.add_trimesh(mesh, Vector3::from_element(1.0));
+                self.insert_node(window, id, node);
+            }
+            RemoteCommand::SetTransform {
+                id,
+                translation,
+                rotation,
+                scale,
+            } => {
+                if let Some(node) = self.node_mut(&id) {
+                    if let Some(t) = translation {
+                        node.set_local_translation(Translation3::new(t[0], t[1], t[2]));
+                    }
+
+                    if let Some(r) = rotation {
+                        let q = Quaternion::new(r[3], r[0], r[1], r[2]);
+                        node.set_local_rotation(UnitQuaternion::from_quaternion(q));
+                    }
+
+                    if let Some(s) = scale {
+                        node.set_local_scale(s[0], s[1], s[2]);
+                    }
+                }
+            }
+            RemoteCommand::SetColor { id, color } => {
+                if let Some(node) = self.node_mut(&id) {
+                    node.set_color(color);
+                }
+            }
+            RemoteCommand::SetVisible { id, visible } => {
+                if let Some(node) = self.node_mut(&id) {
+                    node.set_visible(visible);
+                }
+            }
+            RemoteCommand::DrawLines { id, lines, color } => {
+                let _ = self.lines.insert(id, (lines, Point3::from(color)));
+            }
+            RemoteCommand::DrawPoints { id, points, color } => {
+                let _ = self.points.insert(id, (points, Point3::from(color)));
+            }
+            RemoteCommand::Remove { id } => {
+                if let Some(mut node) = self.nodes.remove(&id) {
+                    window.remove_node(&mut node);
+                }
+
+                let _ = self.lines.remove(&id);
+                let _ = self.points.remove(&id);
+            }
+            RemoteCommand::Clear => {
+                for (_, mut node) in self.nodes.drain() {
+                    window.remove_node(&mut node);
+                }
+
+                self.lines.clear();
+                self.points.clear();
+            }
+        }
+    }
+
+    fn insert_node(&mut self, window: &mut Window, id: String, node: SceneNode) {
+        if let Some(mut old) = self.nodes.insert(id, node) {
+            window.remove_node(&mut old);
+        }
+    }
+
+    fn node_mut(&mut self, id: &str) -> Option<&mut SceneNode> {
+        let node = self.nodes.get_mut(id);
+
+        if node.is_none() {
+            println!("Warning: unknown remote object `{}`.", id);
+        }
+
+        node
+    }
+}
+
+// Runs on a background thread: spawns a thread reading the commands of each new client.
+fn accept_clients(listener: TcpListener, sender: Sender<RemoteCommand>) {
+    for stream in listener.incoming() {
+        let stream = match stream {
+            Ok(stream) => stream,
+            Err(e) => {
+                println!("Warning: unable to accept a remote client: {}", e);
+                continue;
+            }
+        };
+
+        let sender = sender.clone();
+        let _ = thread::spawn(move || read_commands(stream, sender));
+    }
+}
+
+fn read_commands(stream: TcpStream, sender: Sender<RemoteCommand>) {
+    let mut replies = match stream.try_clone() {
+        Ok(replies) => replies,
+        Err(_) => return,
+    };
+
+    for line in BufReader::new(stream).lines() {
+        let line = match line {
+            Ok(line) => line,
+            Err(_) => return,
+        };
+
+        if line.trim().is_empty() {
+            continue;
+        }
+
+        let command = serde_json::from_str::<RemoteCommand>(&line)
+            .map_err(|e| format!("invalid remote command: {}", e))
+            .and_then(|command| command.validate().map(|_| command));
+
+        match command {
+            Ok(command) => {
+                if sender.send(command).is_err() {
+                    // The viewer has been dropped.
+                    return;
+                }
+            }
+            Err(e) => {
+                let mut reply = serde_json::to_vec(&serde_json::json!({ "error": e })).unwrap();
+                reply.push(b'\n');
+
+                if replies.write_all(&reply).is_err() {
+                    return;
+                }
+            }
+        }
+    }
+}
+
+/// A connection to a `RemoteViewer`, for Rust programs.
+pub struct RemoteClient {
+    stream: TcpStream,
+}
+
+impl RemoteClient {
+    /// Connects to the viewer listening at the given address.
+    pub fn connect<A: ToSocketAddrs>(addr: A) -> io::Result<RemoteClient> {
+        let stream = TcpStream::connect(addr)?;
+        stream.set_nodelay(true)?;
+        Ok(RemoteClient { stream })
+    }
+
+    /// Sends a command to the viewer.
+    pub fn send(&mut self, command: &RemoteCommand) -> io::Result<()> {
+        let mut line = serde_json::to_vec(command)?;
+        line.push(b'\n');
+        self.stream.write_all(&line)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{RemoteClient, RemoteCommand, RemoteViewer};
+    use std::io::{BufRead, BufReader};
+
+    #[test]
+    fn meshes_with_too_many_vertices_are_rejected() {
+        let viewer = RemoteViewer::bind("127.0.0.1:0").unwrap();
+        let mut client = RemoteClient::connect(viewer.local_addr()).unwrap();
+        let command = RemoteCommand::AddMesh {
+            id: "mesh".to_string(),
+            coords: vec![[0.0; 3]; u16::MAX as usize + 2],
+            faces: vec![[0, 1, u16::MAX as u32 + 1]],
+        };
+        client.send(&command).unwrap();
+
+        let mut reply = String::new();
+        let _ = BufReader::new(&client.stream)
+            .read_line(&mut reply)
+            .unwrap();
+        assert!(reply.contains("more than 65536 vertices"), "{}", reply);
+        assert!(viewer.commands.try_recv().is_err());
+    }
+}
@@ -0,0 +1,265 @@
+//! A backdrop made of a vertical gradient and of a ground disc fading into the background.
+
+use na::{Point2, Point3, Point4, Vector2, Vector3};
+
+use crate::camera::Camera;
+use crate::color::Color;
+use crate::context::Context;
+use crate::resource::{AllocationType, BufferType, Effect, GPUVec, ShaderAttribute, ShaderUniform};
+
+#[path = "../error.rs"]
+mod error;
+
+/// A disc on an horizontal ground plane, fading into the background at its border.
+///
+/// This gives a studio-like floor to the scene, without any visible horizon. The ground is
+/// drawn behind the scene: objects are never hidden by it, even if they are below the plane.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct GroundFade {
+    /// The color of the ground.
+    pub color: Color,
+    /// The `y` coordinate of the ground plane.
+    pub height: f32,
+    /// The center of the disc, given by its `x` and `z` coordinates.
+    pub center: Point2<f32>,
+    /// The radius of the part of the disc with an opaque color.
+    pub inner_radius: f32,
+    /// The radius past which the ground is completely faded into the background.
+    pub outer_radius: f32,
+}
+
+impl GroundFade {
+    /// A ground disc centered at the origin, starting to fade at half its radius.
+    pub fn new(color: impl Into<Color>, height: f32, radius: f32) -> GroundFade {
+        GroundFade {
+            color: color.into(),
+            height,
+            center: Point2::origin(),
+            inner_radius: radius / 2.0,
+            outer_radius: radius,
+        }
+    }
+}
+
+// The layers drawn by the shader.
+const LAYER_GRADIENT: i32 = 0;
+const LAYER_GROUND: i32 = 1;
+
+const CORNERS: [(f32, f32); 4] = [(-1.0, -1.0), (1.0, -1.0), (-1.0, 1.0), (1.0, 1.0)];
+
+// Draws the background gradient and the ground disc behind the scene.
+pub(crate) struct Backdrop {
+    gradient: Option<(Color, Color)>,
+    ground: Option<GroundFade>,
+    shader: Effect,
+    position: ShaderAttribute<Vector2<f32>>,
+    origin: ShaderAttribute<Point3<f32>>,
+    direction: ShaderAttribute<Vector3<f32>>,
+    layer: ShaderUniform<i32>,
+    top: ShaderUniform<Point3<f32>>,
+    bottom: ShaderUniform<Point3<f32>>,
+    ground_color: ShaderUniform<Point3<f32>>,
+    ground_plane: ShaderUniform<Point3<f32>>,
+    ground_radii: ShaderUniform<Vector2<f32>>,
+    positions: GPUVec<Vector2<f32>>,
+    origins: GPUVec<Point3<f32>>,
+    directions: GPUVec<Vector3<f32>>,
+}
+
+impl Backdrop {
+    pub fn new() -> Backdrop {
+        let positions = CORNERS.iter().map(|c| Vector2::new(c.0, c.1)).collect();
+        let mut shader = Effect::new_from_str(BACKDROP_VERTEX_SRC, BACKDROP_FRAGMENT_SRC).unwrap();
+
+        shader.use_program();
+
+        Backdrop {
+            gradient: None,
+            ground: None,
+            position: shader
+                .get_attrib("position")
+                .expect("Failed to get shader attribute."),
+            origin: shader
+                .get_attrib("origin")
+                .expect("Failed to get shader attribute."),
+            direction: shader
+                .get_attrib("direction")
+                .expect("Failed to get shader attribute."),
+            layer: shader
+                .get_uniform("layer")
+                .expect("Failed to get shader uniform."),
+            top: shader
+                .get_uniform("top")
+                .expect("Failed to get shader uniform."),
+            bottom: shader
+                .get_uniform("bottom")
+                .expect("Failed to get shader uniform."),
+            ground_color: shader
+                .get_uniform("ground_color")
+                .expect("Failed to get shader uniform."),
+            ground_plane: shader
+                .get_uniform("ground_plane")
+                .expect("Failed to get shader uniform."),
+            ground_radii: shader
+                .get_uniform("ground_radii")
+                .expect("Failed to get shader uniform."),
+            positions: GPUVec::new(positions, BufferType::Array, AllocationType::StaticDraw),
+            origins: GPUVec::new(
+                vec![Point3::origin(); 4],
+                BufferType::Array,
+                AllocationType::StreamDraw,
+            ),
+            directions: GPUVec::new(
+                vec![Vector3::zeros(); 4],
+                BufferType::Array,
+                AllocationType::StreamDraw,
+            ),
+            shader,
+        }
+    }
+
+    pub fn set_gradient(&mut self, gradient: Option<(Color, Color)>) {
+        self.gradient = gradient;
+    }
+
+    pub fn set_ground(&mut self, ground: Option<GroundFade>) {
+        self.ground = ground;
+    }
+
+    pub fn gradient(&self) -> Option<(Color, Color)> {
+        self.gradient
+    }
+
+    pub fn ground(&self) -> Option<GroundFade> {
+        self.ground
+    }
+
+    // Whether there is nothing to draw.
+    pub fn is_empty(&self) -> bool {
+        self.gradient.is_none() && self.ground.is_none()
+    }
+
+    // Draws the gradient, replacing the background color.
+    pub fn render_gradient(&mut self, camera: &dyn Camera) {
+        if let Some((top, bottom)) = self.gradient {
+            self.shader.use_program();
+            self.top.upload(&top.to_render());
+            self.bottom.upload(&bottom.to_render());
+            self.render_layer(camera, LAYER_GRADIENT);
+        }
+    }
+
+    // Draws the ground disc, blended with what has already been drawn.
+    pub fn render_ground(&mut self, camera: &dyn Camera) {
+        if let Some(ground) = self.ground {
+            let plane = Point3::new(ground.center.x, ground.height, ground.center.y);
+            let radii = Vector2::new(ground.inner_radius, ground.outer_radius);
+
+            self.shader.use_program();
+            self.ground_color.upload(&ground.color.to_render());
+            self.ground_plane.upload(&plane);
+            self.ground_radii.upload(&radii);
+
+            let ctxt = Context::get();
+            verify!(ctxt.enable(Context::BLEND));
+            verify!(ctxt.blend_func_separate(
+                Context::SRC_ALPHA,
+                Context::ONE_MINUS_SRC_ALPHA,
+                Context::ONE,
+                Context::ONE_MINUS_SRC_ALPHA,
+            ));
+            self.render_layer(camera, LAYER_GROUND);
+            verify!(ctxt.disable(Context::BLEND));
+        }
+    }
+
+    fn render_layer(&mut self, camera: &dyn Camera, layer: i32) {
+        // The view rays at the corners of the screen, which are interpolated linearly
+        // in-between.
+        let inv = camera.inverse_transformation();
+        let (near_depth, far_depth) = camera.depth_range();
+
+        if let (Some(origins), Some(dirs)) = (self.origins.data_mut(), self.directions.data_mut()) {
+            for ((origin, dir), corner) in origins.iter_mut().zip(dirs.iter_mut()).zip(&CORNERS) {
+                let near = inv * Point4::new(corner.0, corner.1, near_depth, 1.0);
+                let far = inv * Point4::new(corner.0, corner.1, far_depth, 1.0);
+                *origin = near.xyz() / near.w;
+                *dir = far.xyz() / far.w - *origin;
+            }
+        }
+
+        let ctxt = Context::get();
+        self.shader.use_program();
+        self.position.enable();
+        self.origin.enable();
+        self.direction.enable();
+
+        verify!(ctxt.disable(Context::DEPTH_TEST));
+        verify!(ctxt.depth_mask(false));
+
+        self.layer.upload(&layer);
+        self.position.bind(&mut self.positions);
+        self.origin.bind(&mut self.origins);
+        self.direction.bind(&mut self.directions);
+
+        verify!(ctxt.draw_arrays(Context::TRIANGLE_STRIP, 0, 4));
+
+        verify!(ctxt.depth_mask(true));
+        verify!(ctxt.enable(Context::DEPTH_TEST));
+
+        self.position.disable();
+        self.origin.disable();
+        self.direction.disable();
+    }
+}
+
+static BACKDROP_VERTEX_SRC: &str = "#version 100
+    attribute vec2 position;
+    attribute vec3 origin;
+    attribute vec3 direction;
+    varying float f_height;
+    varying vec3 f_origin;
+    varying vec3 f_direction;
+
+    void main() {
+        gl_Position = vec4(position, 0.0, 1.0);
+        f_height = (position.y + 1.0) / 2.0;
+        f_origin = origin;
+        f_direction = direction;
+    }";
+
+static BACKDROP_FRAGMENT_SRC: &str = "#version 100
+#ifdef GL_FRAGMENT_PRECISION_HIGH
+   precision highp float;
+#else
+   precision mediump float;
+#endif
+
+    uniform int layer;
+    uniform vec3 top;
+    uniform vec3 bottom;
+    uniform vec3 ground_color;
+    uniform vec3 ground_plane;
+    uniform vec2 ground_radii;
+    varying float f_height;
+    varying vec3 f_origin;
+    varying vec3 f_direction;
+
+    void main() {
+        if (layer == 0) {
+            gl_FragColor = vec4(mix(bottom, top, f_height), 1.0);
+            return;
+        }
+
+        // Intersect the view ray with the ground plane.
+        float t = (ground_plane.y - f_origin.y) / f_direction.y;
+
+        if (abs(f_direction.y) < 1.0e-6 || t < 0.0) {
+            discard;
+        }
+
+        vec3 hit = f_origin + f_direction * t;
+        float dist = length(hit.xz - ground_plane.xz);
+        float alpha = 1.0 - smoothstep(ground_radii.x, max(ground_radii.y, ground_radii.x + 1.0e-6), dist);
+        gl_FragColor = vec4(ground_color, alpha);
+    }";
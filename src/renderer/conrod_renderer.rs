@@ -60,9 +60,10 @@ impl ConrodRenderer {
         //
         // Create shaders.
         //
-        let triangle_shader = Effect::new_from_str(TRIANGLES_VERTEX_SRC, TRIANGLES_FRAGMENT_SRC);
-        let text_shader = Effect::new_from_str(TEXT_VERTEX_SRC, TEXT_FRAGMENT_SRC);
-        let image_shader = Effect::new_from_str(IMAGE_VERTEX_SRC, IMAGE_FRAGMENT_SRC);
+        let triangle_shader =
+            Effect::new_from_str(TRIANGLES_VERTEX_SRC, TRIANGLES_FRAGMENT_SRC).unwrap();
+        let text_shader = Effect::new_from_str(TEXT_VERTEX_SRC, TEXT_FRAGMENT_SRC).unwrap();
+        let image_shader = Effect::new_from_str(IMAGE_VERTEX_SRC, IMAGE_FRAGMENT_SRC).unwrap();
 
         //
         // Initialize UI with the default font.
@@ -90,41 +91,7 @@ impl ConrodRenderer {
         let texture = verify!(ctxt
             .create_texture()
             .expect("Font texture creation failed."));
-        verify!(ctxt.bind_texture(Context::TEXTURE_2D, Some(&texture)));
-        verify!(ctxt.tex_image2d(
-            Context::TEXTURE_2D,
-            0,
-            Context::RED as i32,
-            atlas_width as i32,
-            atlas_height as i32,
-            0,
-            Context::RED,
-            None
-        ));
-
-        /* Clamp to the edge to avoid artifacts when scaling. */
-        verify!(ctxt.tex_parameteri(
-            Context::TEXTURE_2D,
-            Context::TEXTURE_WRAP_S,
-            Context::CLAMP_TO_EDGE as i32
-        ));
-        verify!(ctxt.tex_parameteri(
-            Context::TEXTURE_2D,
-            Context::TEXTURE_WRAP_T,
-            Context::CLAMP_TO_EDGE as i32
-        ));
-
-        /* Linear filtering usually looks best for text. */
-        verify!(ctxt.tex_parameteri(
-            Context::TEXTURE_2D,
-            Context::TEXTURE_MIN_FILTER,
-            Context::LINEAR as i32
-        ));
-        verify!(ctxt.tex_parameteri(
-            Context::TEXTURE_2D,
-            Context::TEXTURE_MAG_FILTER,
-            Context::LINEAR as i32
-        ));
+        init_atlas_texture(&texture, atlas_width, atlas_height);
 
         ConrodRenderer {
             ui,
@@ -580,6 +547,57 @@ impl ConrodRenderer {
         verify!(ctxt.disable(Context::BLEND));
         verify!(ctxt.scissor(0, 0, width as i32, height as i32));
     }
+
+    /// Re-creates the glyph atlas after the context has been restored.
+    pub(crate) fn restore(&mut self) {
+        let (width, height) = self.cache.dimensions();
+        self.cache = Cache::builder().dimensions(width, height).build();
+
+        let ctxt = Context::get();
+        ctxt.recreate_texture(&self.texture);
+        verify!(ctxt.pixel_storei(Context::UNPACK_ALIGNMENT, 1));
+        init_atlas_texture(&self.texture, width, height);
+    }
+}
+
+// Allocates the glyph atlas and configures its sampling.
+fn init_atlas_texture(texture: &Texture, width: u32, height: u32) {
+    let ctxt = Context::get();
+    verify!(ctxt.bind_texture(Context::TEXTURE_2D, Some(texture)));
+    verify!(ctxt.tex_image2d(
+        Context::TEXTURE_2D,
+        0,
+        Context::RED as i32,
+        width as i32,
+        height as i32,
+        0,
+        Context::RED,
+        None
+    ));
+
+    /* Clamp to the edge to avoid artifacts when scaling. */
+    verify!(ctxt.tex_parameteri(
+        Context::TEXTURE_2D,
+        Context::TEXTURE_WRAP_S,
+        Context::CLAMP_TO_EDGE as i32
+    ));
+    verify!(ctxt.tex_parameteri(
+        Context::TEXTURE_2D,
+        Context::TEXTURE_WRAP_T,
+        Context::CLAMP_TO_EDGE as i32
+    ));
+
+    /* Linear filtering usually looks best for text. */
+    verify!(ctxt.tex_parameteri(
+        Context::TEXTURE_2D,
+        Context::TEXTURE_MIN_FILTER,
+        Context::LINEAR as i32
+    ));
+    verify!(ctxt.tex_parameteri(
+        Context::TEXTURE_2D,
+        Context::TEXTURE_MAG_FILTER,
+        Context::LINEAR as i32
+    ));
 }
 
 static TRIANGLES_VERTEX_SRC: &str = "#version 100
@@ -0,0 +1,291 @@
+//! Debug rendering of collision shapes and contacts with immediate-mode lines and points.
+//!
+//! Any type able to draw lines and points implements the `DebugRenderBackend` trait and gains
+//! methods drawing ncollide shapes, collision worlds and contacts. It is implemented by `Window`,
+//! so a whole collision world is drawn with a single call per frame:
+//!
+//! ```no_run
+//! # use kiss3d::window::Window;
+//! # use kiss3d::ncollide3d::pipeline::CollisionWorld;
+//! use kiss3d::renderer::DebugRenderBackend;
+//! use kiss3d::nalgebra::Point3;
+//!
+//! # let world: CollisionWorld<f32, ()> = CollisionWorld::new(0.02);
+//! let mut window = Window::new("Debug render");
+//!
+//! while window.render() {
+//!     window.draw_collision_world(&world, &Point3::new(0.0, 1.0, 0.0));
+//! }
+//! ```
+//!
+//! Other physics engines only need to forward their line drawing callbacks. For example with the
+//! `DebugRenderBackend` of rapier, which already computes the lines to draw:
+//!
+//! ```ignore
+//! struct RapierBackend<'a>(&'a mut Window);
+//!
+//! impl<'a> rapier3d::pipeline::DebugRenderBackend for RapierBackend<'a> {
+//!     fn draw_line(&mut self, _: DebugRenderObject, a: Point<Real>, b: Point<Real>, color: [f32; 4]) {
+//!         let color = Point3::new(color[0], color[1], color[2]);
+//!         kiss3d::renderer::DebugRenderBackend::draw_line(self.0, &a, &b, &color)
+//!     }
+//! }
+//! ```
+
+use crate::window::Window;
+use na::{Isometry3, Point3, Vector3};
+use ncollide3d::bounding_volume::AABB;
+use ncollide3d::pipeline::CollisionWorld;
+use ncollide3d::query::Contact;
+use ncollide3d::shape::{
+    Ball, Capsule, Compound, ConvexHull, Cuboid, Plane, Polyline, Segment, Shape, TriMesh, Triangle,
+};
+use ncollide3d::transformation;
+use std::f32::consts::PI;
+
+// The number of segments of the circles approximating round shapes.
+const NSUBDIVS: usize = 24;
+
+/// A target of debug rendering, able to draw lines and points for the next frame.
+pub trait DebugRenderBackend {
+    /// Draws a line from `a` to `b` during the next frame.
+    fn draw_line(&mut self, a: &Point3<f32>, b: &Point3<f32>, color: &Point3<f32>);
+
+    /// Draws a point during the next frame.
+    fn draw_point(&mut self, pt: &Point3<f32>, color: &Point3<f32>);
+
+    /// Draws the edges of an axis-aligned bounding box.
+    fn draw_aabb(&mut self, aabb: &AABB<f32>, color: &Point3<f32>) {
+        draw_box(self, &Isometry3::identity(), &aabb.mins, &aabb.maxs, color);
+    }
+
+    /// Draws the axes of a frame, colored red, green and blue for `x`, `y` and `z`.
+    fn draw_frame(&mut self, pos: &Isometry3<f32>, size: f32) {
+        let origin = Point3::from(pos.translation.vector);
+        let axes = [
+            (Vector3::x(), Point3::new(1.0, 0.0, 0.0)),
+            (Vector3::y(), Point3::new(0.0, 1.0, 0.0)),
+            (Vector3::z(), Point3::new(0.0, 0.0, 1.0)),
+        ];
+
+        for (axis, color) in axes.iter() {
+            self.draw_line(&origin, &(origin + pos.rotation * axis * size), color);
+        }
+    }
+
+    /// Draws a contact: its two points and its normal, scaled by the penetration depth.
+    fn draw_contact(&mut self, contact: &Contact<f32>, color: &Point3<f32>) {
+        self.draw_point(&contact.world1, color);
+        self.draw_point(&contact.world2, color);
+
+        let length = contact.depth.abs().max(0.1);
+        let tip = contact.world1 + contact.normal.into_inner() * length;
+        self.draw_line(&contact.world1, &tip, color);
+    }
+
+    /// Draws the marker of a point light: three circles of radius `size` around its position,
+    /// crossed by rays along the axes.
+    fn draw_light(&mut self, pos: &Point3<f32>, size: f32, color: &Point3<f32>) {
+        let (x, y, z) = (Vector3::x(), Vector3::y(), Vector3::z());
+        let id = Isometry3::identity();
+        draw_circle(self, &id, pos, &x, &y, size, color);
+        draw_circle(self, &id, pos, &y, &z, size, color);
+        draw_circle(self, &id, pos, &z, &x, size, color);
+
+        for axis in &[x, y, z] {
+            self.draw_line(
+                &(pos - axis * size * 2.0),
+                &(pos + axis * size * 2.0),
+                color,
+            );
+        }
+    }
+
+    /// Draws the wireframe of a shape at the position `pos`.
+    ///
+    /// Balls, cuboids, capsules, segments, triangles, convex hulls, triangle
+    /// meshes, polylines, planes and compound shapes are drawn with their actual geometry. Other
+    /// shapes are drawn as their axis-aligned bounding box.
+    fn draw_shape(&mut self, shape: &dyn Shape<f32>, pos: &Isometry3<f32>, color: &Point3<f32>) {
+        if let Some(s) = shape.as_shape::<Ball<f32>>() {
+            let (o, r) = (Point3::origin(), s.radius);
+            let (x, y, z) = (Vector3::x(), Vector3::y(), Vector3::z());
+            draw_circle(self, pos, &o, &x, &y, r, color);
+            draw_circle(self, pos, &o, &y, &z, r, color);
+            draw_circle(self, pos, &o, &z, &x, r, color);
+        } else if let Some(s) = shape.as_shape::<Cuboid<f32>>() {
+            let he = s.half_extents;
+            draw_box(self, pos, &Point3::from(-he), &Point3::from(he), color);
+        } else if let Some(s) = shape.as_shape::<Capsule<f32>>() {
+            let (h, r) = (s.half_height, s.radius);
+            let (x, y, z) = (Vector3::x(), Vector3::y(), Vector3::z());
+            draw_round_sides(self, pos, h, r, color);
+
+            // The caps, drawn as full circles in two vertical planes.
+            for center in &[Point3::new(0.0, h, 0.0), Point3::new(0.0, -h, 0.0)] {
+                draw_circle(self, pos, center, &x, &y, r, color);
+                draw_circle(self, pos, center, &z, &y, r, color);
+            }
+        } else if let Some(s) = shape.as_shape::<Segment<f32>>() {
+            self.draw_line(&(pos * s.a), &(pos * s.b), color);
+        } else if let Some(s) = shape.as_shape::<Triangle<f32>>() {
+            let (a, b, c) = (pos * s.a, pos * s.b, pos * s.c);
+            self.draw_line(&a, &b, color);
+            self.draw_line(&b, &c, color);
+            self.draw_line(&c, &a, color);
+        } else if let Some(s) = shape.as_shape::<ConvexHull<f32>>() {
+            let hull = transformation::convex_hull(s.points());
+            let coords = &hull.coords;
+
+            for t in hull.flat_indices().chunks(3) {
+                for i in 0..3 {
+                    let a = pos * coords[t[i] as usize];
+                    let b = pos * coords[t[(i + 1) % 3] as usize];
+                    self.draw_line(&a, &b, color);
+                }
+            }
+        } else if let Some(s) = shape.as_shape::<TriMesh<f32>>() {
+            let points = s.points();
+
+            for edge in s.edges() {
+                let a = pos * points[edge.indices.x];
+                let b = pos * points[edge.indices.y];
+                self.draw_line(&a, &b, color);
+            }
+        } else if let Some(s) = shape.as_shape::<Polyline<f32>>() {
+            let points = s.points();
+
+            for edge in s.edges() {
+                let a = pos * points[edge.indices.x];
+                let b = pos * points[edge.indices.y];
+                self.draw_line(&a, &b, color);
+            }
+        } else if let Some(s) = shape.as_shape::<Plane<f32>>() {
+            // Draw a square on the plane, and its normal.
+            let normal = s.normal.into_inner();
+            let tangent = if normal.x.abs() < 0.9 {
+                normal.cross(&Vector3::x()).normalize()
+            } else {
+                normal.cross(&Vector3::y()).normalize()
+            };
+            let bitangent = normal.cross(&tangent);
+            let origin = Point3::origin();
+            let corners = [
+                pos * (origin + (tangent + bitangent) * 5.0),
+                pos * (origin + (tangent - bitangent) * 5.0),
+                pos * (origin + (-tangent - bitangent) * 5.0),
+                pos * (origin + (-tangent + bitangent) * 5.0),
+            ];
+
+            for i in 0..4 {
+                self.draw_line(&corners[i], &corners[(i + 1) % 4], color);
+            }
+
+            self.draw_line(&(pos * origin), &(pos * (origin + normal)), color);
+        } else if let Some(s) = shape.as_shape::<Compound<f32>>() {
+            for (part_pos, part) in s.shapes() {
+                self.draw_shape(part.as_ref(), &(pos * part_pos), color);
+            }
+        } else {
+            self.draw_aabb(&shape.aabb(pos), color);
+        }
+    }
+
+    /// Draws all the objects of a collision world and their contacts.
+    ///
+    /// The objects are drawn with `color`, and the contacts in red. Call this once per frame.
+    fn draw_collision_world<T>(&mut self, world: &CollisionWorld<f32, T>, color: &Point3<f32>)
+    where
+        Self: Sized,
+    {
+        for (_, co) in world.collision_objects() {
+            self.draw_shape(co.shape().as_ref(), co.position(), color);
+        }
+
+        let contact_color = Point3::new(1.0, 0.0, 0.0);
+
+        for (_, _, _, manifold) in world.contact_pairs(true) {
+            for tracked in manifold.contacts() {
+                self.draw_contact(&tracked.contact, &contact_color);
+            }
+        }
+    }
+}
+
+impl DebugRenderBackend for Window {
+    #[inline]
+    fn draw_line(&mut self, a: &Point3<f32>, b: &Point3<f32>, color: &Point3<f32>) {
+        Window::draw_line(self, a, b, color)
+    }
+
+    #[inline]
+    fn draw_point(&mut self, pt: &Point3<f32>, color: &Point3<f32>) {
+        Window::draw_point(self, pt, color)
+    }
+}
+
+fn draw_box<B: DebugRenderBackend + ?Sized>(
+    backend: &mut B,
+    pos: &Isometry3<f32>,
+    mins: &Point3<f32>,
+    maxs: &Point3<f32>,
+    color: &Point3<f32>,
+) {
+    let corner = |i: usize| {
+        pos * Point3::new(
+            if i & 1 == 0 { mins.x } else { maxs.x },
+            if i & 2 == 0 { mins.y } else { maxs.y },
+            if i & 4 == 0 { mins.z } else { maxs.z },
+        )
+    };
+
+    for i in 0..8 {
+        for bit in &[1, 2, 4] {
+            if i & bit == 0 {
+                backend.draw_line(&corner(i), &corner(i | bit), color);
+            }
+        }
+    }
+}
+
+// Draws the circle of the plane spanned by `u` and `v`.
+fn draw_circle<B: DebugRenderBackend + ?Sized>(
+    backend: &mut B,
+    pos: &Isometry3<f32>,
+    center: &Point3<f32>,
+    u: &Vector3<f32>,
+    v: &Vector3<f32>,
+    radius: f32,
+    color: &Point3<f32>,
+) {
+    let point = |i: usize| {
+        let angle = 2.0 * PI * i as f32 / NSUBDIVS as f32;
+        pos * (center + u * (angle.cos() * radius) + v * (angle.sin() * radius))
+    };
+
+    for i in 0..NSUBDIVS {
+        backend.draw_line(&point(i), &point(i + 1), color);
+    }
+}
+
+// Draws the sides of a cylinder aligned with the `y` axis.
+fn draw_round_sides<B: DebugRenderBackend + ?Sized>(
+    backend: &mut B,
+    pos: &Isometry3<f32>,
+    half_height: f32,
+    radius: f32,
+    color: &Point3<f32>,
+) {
+    let top = Point3::new(0.0, half_height, 0.0);
+    let bottom = Point3::new(0.0, -half_height, 0.0);
+    let (x, z) = (Vector3::x(), Vector3::z());
+
+    draw_circle(backend, pos, &top, &x, &z, radius, color);
+    draw_circle(backend, pos, &bottom, &x, &z, radius, color);
+
+    for dir in &[x, z, -x, -z] {
+        let a = pos * (top + dir * radius);
+        let b = pos * (bottom + dir * radius);
+        backend.draw_line(&a, &b, color);
+    }
+}
@@ -0,0 +1,187 @@
+//! Frustum culling of instances on the GPU.
+
+use na::{Matrix4, Point3, Vector3};
+
+use crate::context::{Context, Query};
+use crate::resource::{Effect, GPUVec, ShaderAttribute, ShaderUniform};
+
+#[path = "../error.rs"]
+mod error;
+
+// Culls arrows against the view frustum on the GPU, and writes the visible ones, compacted, to a
+// buffer read by the instanced draw call.
+//
+// A geometry shader emits one point per visible arrow, recorded by transform feedback. The
+// number of points recorded is then read back with a query, which waits for the culling pass.
+pub(crate) struct InstanceCuller {
+    effect: Effect,
+    origin: ShaderAttribute<Point3<f32>>,
+    vector: ShaderAttribute<Vector3<f32>>,
+    color: ShaderAttribute<Point3<f32>>,
+    proj_view: ShaderUniform<Matrix4<f32>>,
+    scale: ShaderUniform<f32>,
+    width: ShaderUniform<f32>,
+    // The query counting the recorded points, with the context generation it was created with.
+    query: Option<(Query, u64)>,
+}
+
+impl InstanceCuller {
+    // Returns `None` if the context does not support geometry shaders.
+    pub fn new() -> Option<InstanceCuller> {
+        let mut effect = Effect::new_with_feedback(
+            CULLING_VERTEX_SRC,
+            CULLING_GEOMETRY_SRC,
+            CULLING_FRAGMENT_SRC,
+            &["culled_origin", "culled_vector", "culled_color"],
+        )
+        .ok()?;
+
+        effect.use_program();
+
+        Some(InstanceCuller {
+            origin: effect.get_attrib("origin")?,
+            vector: effect.get_attrib("vector")?,
+            color: effect.get_attrib("color")?,
+            proj_view: effect.get_uniform("proj_view")?,
+            scale: effect.get_uniform("scale")?,
+            width: effect.get_uniform("width")?,
+            query: None,
+            effect,
+        })
+    }
+
+    // Writes to `output` the origin, vector, and color, interleaved, of each arrow whose bounding
+    // box intersects the frustum of `proj_view`. Returns the number of arrows written.
+    #[allow(clippy::too_many_arguments)]
+    pub fn cull(
+        &mut self,
+        proj_view: &Matrix4<f32>,
+        scale: f32,
+        width: f32,
+        origins: &mut GPUVec<Point3<f32>>,
+        vectors: &mut GPUVec<Vector3<f32>>,
+        colors: &mut GPUVec<Point3<f32>>,
+        output: &mut GPUVec<Point3<f32>>,
+    ) -> usize {
+        let ctxt = Context::get();
+        let len = origins.len();
+
+        if output.len() < len * 3 {
+            *output.data_mut() = Some(vec![Point3::origin(); len * 3]);
+        }
+
+        let generation = Context::generation();
+
+        if self.query.as_ref().map(|q| q.1) != Some(generation) {
+            // A query of a previous generation has been lost with the context.
+            if let Some((query, _)) = self.query.take() {
+                std::mem::forget(query);
+            }
+
+            self.query = verify!(ctxt.create_query()).map(|q| (q, generation));
+        }
+
+        let query = match &self.query {
+            Some((query, _)) => query,
+            None => return 0,
+        };
+
+        self.effect.use_program();
+        self.proj_view.upload(proj_view);
+        self.scale.upload(&scale);
+        self.width.upload(&width);
+        self.origin.enable();
+        self.vector.enable();
+        self.color.enable();
+        self.origin.bind(origins);
+        self.vector.bind(vectors);
+        self.color.bind(colors);
+        output.bind_base(0);
+
+        verify!(ctxt.enable(Context::RASTERIZER_DISCARD));
+        verify!(ctxt.begin_query(Context::TRANSFORM_FEEDBACK_PRIMITIVES_WRITTEN, query));
+        verify!(ctxt.begin_transform_feedback(Context::POINTS));
+        verify!(ctxt.draw_arrays(Context::POINTS, 0, len as i32));
+        verify!(ctxt.end_transform_feedback());
+        verify!(ctxt.end_query(Context::TRANSFORM_FEEDBACK_PRIMITIVES_WRITTEN));
+        verify!(ctxt.disable(Context::RASTERIZER_DISCARD));
+        verify!(ctxt.bind_buffer_base(Context::TRANSFORM_FEEDBACK_BUFFER, 0, None));
+
+        self.origin.disable();
+        self.vector.disable();
+        self.color.disable();
+
+        // Waits for the culling pass to complete.
+        verify!(ctxt.get_query_parameter_u32(query, Context::QUERY_RESULT)) as usize
+    }
+}
+
+// Must match the arrow geometry of the vector field renderer: the arrow heads are `width` wide.
+static CULLING_VERTEX_SRC: &str = "
+    in vec3 origin;
+    in vec3 vector;
+    in vec3 color;
+    uniform mat4 proj_view;
+    uniform float scale;
+    uniform float width;
+    out vec3 v_origin;
+    out vec3 v_vector;
+    out vec3 v_color;
+    out float v_visible;
+
+    void main() {
+        vec3 tip = origin + vector * scale;
+        vec3 mins = min(origin, tip) - vec3(0.5 * width);
+        vec3 maxs = max(origin, tip) + vec3(0.5 * width);
+        vec3 all_below = vec3(1.0);
+        vec3 all_above = vec3(1.0);
+
+        // The box is outside if all its corners are outside of the same clipping plane.
+        for (int i = 0; i < 8; i++) {
+            vec3 corner = vec3(
+                (i & 1) != 0 ? maxs.x : mins.x,
+                (i & 2) != 0 ? maxs.y : mins.y,
+                (i & 4) != 0 ? maxs.z : mins.z
+            );
+            vec4 clip = proj_view * vec4(corner, 1.0);
+            all_below *= step(clip.xyz, -clip.www);
+            all_above *= step(clip.www, clip.xyz);
+        }
+
+        v_origin = origin;
+        v_vector = vector;
+        v_color = color;
+        v_visible = dot(all_below + all_above, vec3(1.0)) > 0.0 ? 0.0 : 1.0;
+        gl_Position = vec4(0.0, 0.0, 0.0, 1.0);
+    }";
+
+static CULLING_GEOMETRY_SRC: &str = "
+    layout(points) in;
+    layout(points, max_vertices = 1) out;
+    in vec3 v_origin[];
+    in vec3 v_vector[];
+    in vec3 v_color[];
+    in float v_visible[];
+    out vec3 culled_origin;
+    out vec3 culled_vector;
+    out vec3 culled_color;
+
+    void main() {
+        if (v_visible[0] > 0.5) {
+            culled_origin = v_origin[0];
+            culled_vector = v_vector[0];
+            culled_color = v_color[0];
+            gl_Position = vec4(0.0, 0.0, 0.0, 1.0);
+            EmitVertex();
+            EndPrimitive();
+        }
+    }";
+
+// Never executed because of the rasterizer discard, but required by OpenGL ES.
+static CULLING_FRAGMENT_SRC: &str = "
+    precision highp float;
+    out vec4 frag_color;
+
+    void main() {
+        frag_color = vec4(1.0);
+    }";
@@ -0,0 +1,396 @@
+//! A renderer of many copies of the same mesh.
+
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use na::{Isometry3, Matrix4, Point3, Vector3, Vector4};
+
+use crate::camera::Camera;
+use crate::color::Color;
+use crate::context::Context;
+use crate::light::Light;
+use crate::resource::{
+    AllocationType, BufferType, Effect, GPUVec, Mesh, ShaderAttribute, ShaderUniform,
+};
+
+#[path = "../error.rs"]
+mod error;
+
+/// The placement and color of one of the copies of the mesh of an `InstancedMesh`.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Instance {
+    /// The position and orientation of the copy.
+    pub transform: Isometry3<f32>,
+    /// The scale of the copy along the axes of the mesh, applied before `transform`.
+    pub scale: Vector3<f32>,
+    /// The color of the copy.
+    pub color: Color,
+}
+
+impl Instance {
+    /// A white copy of the mesh placed with `transform`, and not scaled.
+    pub fn new(transform: Isometry3<f32>) -> Instance {
+        Instance {
+            transform,
+            scale: Vector3::repeat(1.0),
+            color: Color::WHITE,
+        }
+    }
+
+    /// Sets the scale of this copy along the axes of the mesh.
+    pub fn with_scale(mut self, scale: Vector3<f32>) -> Instance {
+        self.scale = scale;
+        self
+    }
+
+    /// Sets the color of this copy.
+    pub fn with_color(mut self, color: impl Into<Color>) -> Instance {
+        self.color = color.into();
+        self
+    }
+
+    // The rows of the 3x4 matrix transforming the points of the mesh.
+    fn rows(&self) -> [Vector4<f32>; 3] {
+        let m = self.transform.to_homogeneous() * Matrix4::new_nonuniform_scaling(&self.scale);
+        [
+            m.row(0).transpose(),
+            m.row(1).transpose(),
+            m.row(2).transpose(),
+        ]
+    }
+}
+
+struct InstancedMeshData {
+    mesh: Rc<RefCell<Mesh>>,
+    // The three rows of the transformation of each instance, one after the other.
+    transforms: GPUVec<Vector4<f32>>,
+    colors: GPUVec<Point3<f32>>,
+    visible: bool,
+}
+
+/// Copies of the same mesh, e.g., the trees of a forest or the particles of a fluid.
+///
+/// All the copies are drawn with a single instanced draw call when the platform supports it,
+/// which is much faster than drawing one scene node per copy. The copies are lit by the light of
+/// the window like the objects of the scene, but are neither textured nor shadowed, and are not
+/// part of the scene graph, so they are not picked.
+///
+/// This is a handle to the copies drawn by the window that created them with
+/// `Window::add_instanced_mesh`: clones of it refer to the same copies.
+#[derive(Clone)]
+pub struct InstancedMesh {
+    data: Rc<RefCell<InstancedMeshData>>,
+}
+
+impl InstancedMesh {
+    fn new(mesh: Rc<RefCell<Mesh>>) -> InstancedMesh {
+        let data = InstancedMeshData {
+            mesh,
+            transforms: GPUVec::new(Vec::new(), BufferType::Array, AllocationType::DynamicDraw),
+            colors: GPUVec::new(Vec::new(), BufferType::Array, AllocationType::DynamicDraw),
+            visible: true,
+        };
+
+        InstancedMesh {
+            data: Rc::new(RefCell::new(data)),
+        }
+    }
+
+    /// Replaces the copies of the mesh.
+    ///
+    /// The buffers of the previous copies are reused.
+    pub fn set_instances(&mut self, instances: &[Instance]) {
+        let mut data = self.data.borrow_mut();
+        let transforms = data.transforms.data_mut().get_or_insert_with(Vec::new);
+        transforms.clear();
+        transforms.extend(instances.iter().flat_map(|i| i.rows()));
+
+        let colors = data.colors.data_mut().get_or_insert_with(Vec::new);
+        colors.clear();
+        colors.extend(instances.iter().map(|i| i.color.to_render()));
+    }
+
+    /// Replaces the `i`-th copy of the mesh.
+    pub fn set_instance(&mut self, i: usize, instance: &Instance) {
+        let mut data = self.data.borrow_mut();
+        assert!(i < data.colors.len(), "Instance index out of bounds.");
+
+        if let Some(transforms) = data.transforms.data_mut() {
+            transforms[i * 3..i * 3 + 3].copy_from_slice(&instance.rows());
+        }
+
+        if let Some(colors) = data.colors.data_mut() {
+            colors[i] = instance.color.to_render();
+        }
+    }
+
+    /// The number of copies of the mesh.
+    pub fn len(&self) -> usize {
+        self.data.borrow().colors.len()
+    }
+
+    /// Whether there is no copy of the mesh.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// The mesh drawn by this set of copies.
+    pub fn mesh(&self) -> Rc<RefCell<Mesh>> {
+        self.data.borrow().mesh.clone()
+    }
+
+    /// Sets whether the copies are drawn.
+    pub fn set_visible(&mut self, visible: bool) {
+        self.data.borrow_mut().visible = visible;
+    }
+
+    /// Whether the copies are drawn.
+    pub fn is_visible(&self) -> bool {
+        self.data.borrow().visible
+    }
+}
+
+// The inputs of the shader specific to each instance: vertex attributes read once per instance
+// if instancing is supported, uniforms set before drawing each instance otherwise.
+enum InstanceInputs {
+    Instanced {
+        rows: [ShaderAttribute<Vector4<f32>>; 3],
+        color: ShaderAttribute<Point3<f32>>,
+    },
+    PerInstance {
+        rows: [ShaderUniform<Vector4<f32>>; 3],
+        color: ShaderUniform<Point3<f32>>,
+    },
+}
+
+// Draws the instanced meshes of a window.
+pub(crate) struct InstancedMeshRenderer {
+    meshes: Vec<InstancedMesh>,
+    shader: Effect,
+    pos: ShaderAttribute<Point3<f32>>,
+    normal: ShaderAttribute<Vector3<f32>>,
+    inputs: InstanceInputs,
+    proj: ShaderUniform<Matrix4<f32>>,
+    view: ShaderUniform<Matrix4<f32>>,
+    light: ShaderUniform<Point3<f32>>,
+}
+
+impl InstancedMeshRenderer {
+    pub fn new() -> InstancedMeshRenderer {
+        let instanced = Context::get().supports_instancing();
+        let defines: &[&str] = if instanced { &["INSTANCED"] } else { &[] };
+        let mut shader = Effect::new_from_str_with_defines(
+            INSTANCED_MESH_VERTEX_SRC,
+            INSTANCED_MESH_FRAGMENT_SRC,
+            defines,
+        )
+        .unwrap();
+
+        shader.use_program();
+
+        let inputs = if instanced {
+            InstanceInputs::Instanced {
+                rows: [
+                    shader.get_attrib("instance_row0").unwrap(),
+                    shader.get_attrib("instance_row1").unwrap(),
+                    shader.get_attrib("instance_row2").unwrap(),
+                ],
+                color: shader.get_attrib("instance_color").unwrap(),
+            }
+        } else {
+            InstanceInputs::PerInstance {
+                rows: [
+                    shader.get_uniform("instance_row0").unwrap(),
+                    shader.get_uniform("instance_row1").unwrap(),
+                    shader.get_uniform("instance_row2").unwrap(),
+                ],
+                color: shader.get_uniform("instance_color").unwrap(),
+            }
+        };
+
+        InstancedMeshRenderer {
+            meshes: Vec::new(),
+            pos: shader.get_attrib("position").unwrap(),
+            normal: shader.get_attrib("normal").unwrap(),
+            inputs,
+            proj: shader.get_uniform("proj").unwrap(),
+            view: shader.get_uniform("view").unwrap(),
+            light: shader.get_uniform("light_position").unwrap(),
+            shader,
+        }
+    }
+
+    pub fn add(&mut self, mesh: Rc<RefCell<Mesh>>, instances: &[Instance]) -> InstancedMesh {
+        let mut instanced = InstancedMesh::new(mesh);
+        instanced.set_instances(instances);
+        self.meshes.push(instanced.clone());
+        instanced
+    }
+
+    pub fn remove(&mut self, mesh: &InstancedMesh) {
+        self.meshes.retain(|m| !Rc::ptr_eq(&m.data, &mesh.data));
+    }
+
+    pub fn render(&mut self, pass: usize, camera: &mut dyn Camera, light: &Light) {
+        let has_instances = self.meshes.iter().any(|m| {
+            let data = m.data.borrow();
+            data.visible && data.colors.len() != 0
+        });
+
+        if !has_instances {
+            return;
+        }
+
+        let ctxt = Context::get();
+        self.shader.use_program();
+        self.pos.enable();
+        self.normal.enable();
+
+        camera.upload(pass, &mut self.proj, &mut self.view);
+
+        let pos = match *light {
+            Light::Absolute(ref p) => *p,
+            Light::StickToCamera => camera.eye(),
+        };
+        self.light.upload(&pos);
+
+        verify!(ctxt.enable(Context::CULL_FACE));
+        let _ = verify!(ctxt.polygon_mode(Context::FRONT_AND_BACK, Context::FILL));
+
+        for instanced in &self.meshes {
+            let mut data = instanced.data.borrow_mut();
+            let data = &mut *data;
+            let num_instances = data.colors.len();
+
+            if !data.visible || num_instances == 0 {
+                continue;
+            }
+
+            let mut mesh = data.mesh.borrow_mut();
+            mesh.bind_coords(&mut self.pos);
+            mesh.bind_normals(&mut self.normal);
+            let num_pts = mesh.num_pts() as i32;
+
+            match &mut self.inputs {
+                InstanceInputs::Instanced { rows, color } => {
+                    for (i, row) in rows.iter_mut().enumerate() {
+                        row.enable();
+                        row.bind_sub_buffer(&mut data.transforms, 2, i);
+                        row.set_divisor(1);
+                    }
+
+                    color.enable();
+                    color.bind(&mut data.colors);
+                    color.set_divisor(1);
+
+                    // Binding the attributes may have changed the bound element array.
+                    mesh.bind_faces();
+                    verify!(ctxt.draw_elements_instanced(
+                        Context::TRIANGLES,
+                        num_pts,
+                        Context::UNSIGNED_SHORT,
+                        0,
+                        num_instances as i32,
+                    ));
+
+                    for row in rows.iter_mut() {
+                        row.set_divisor(0);
+                        row.disable();
+                    }
+
+                    color.set_divisor(0);
+                    color.disable();
+                }
+                InstanceInputs::PerInstance { rows, color } => {
+                    mesh.bind_faces();
+
+                    if let (Some(transforms), Some(colors)) =
+                        (data.transforms.data(), data.colors.data())
+                    {
+                        for (m, c) in transforms.chunks(3).zip(colors) {
+                            for (row, r) in rows.iter_mut().zip(m) {
+                                row.upload(r);
+                            }
+
+                            color.upload(c);
+                            verify!(ctxt.draw_elements(
+                                Context::TRIANGLES,
+                                num_pts,
+                                Context::UNSIGNED_SHORT,
+                                0
+                            ));
+                        }
+                    }
+                }
+            }
+        }
+
+        self.pos.disable();
+        self.normal.disable();
+    }
+}
+
+static INSTANCED_MESH_VERTEX_SRC: &str = "#version 100
+    attribute vec3 position;
+    attribute vec3 normal;
+#ifdef INSTANCED
+    attribute vec4 instance_row0;
+    attribute vec4 instance_row1;
+    attribute vec4 instance_row2;
+    attribute vec3 instance_color;
+#else
+    uniform vec4 instance_row0;
+    uniform vec4 instance_row1;
+    uniform vec4 instance_row2;
+    uniform vec3 instance_color;
+#endif
+    uniform mat4 proj;
+    uniform mat4 view;
+    uniform vec3 light_position;
+    varying vec3 normal_v;
+    varying vec3 view_pos;
+    varying vec3 light_v;
+    varying vec3 color_v;
+
+    void main() {
+        vec4 p = vec4(position, 1.0);
+        vec3 world = vec3(dot(instance_row0, p), dot(instance_row1, p), dot(instance_row2, p));
+
+        // The cofactors of the linear part of the transformation are proportional to its
+        // inverse transpose, which transforms the normals.
+        vec3 c0 = vec3(instance_row0.x, instance_row1.x, instance_row2.x);
+        vec3 c1 = vec3(instance_row0.y, instance_row1.y, instance_row2.y);
+        vec3 c2 = vec3(instance_row0.z, instance_row1.z, instance_row2.z);
+        vec3 world_normal = mat3(cross(c1, c2), cross(c2, c0), cross(c0, c1)) * normal;
+
+        vec4 view_pos4 = view * vec4(world, 1.0);
+        view_pos = view_pos4.xyz;
+        normal_v = mat3(view) * world_normal;
+        light_v = (view * vec4(light_position, 1.0)).xyz;
+        color_v = instance_color;
+        gl_Position = proj * view_pos4;
+    }";
+
+// Lit as the default material.
+static INSTANCED_MESH_FRAGMENT_SRC: &str = "#version 100
+#ifdef GL_FRAGMENT_PRECISION_HIGH
+   precision highp float;
+#else
+   precision mediump float;
+#endif
+
+#include \"kiss3d/lighting\"
+
+    varying vec3 normal_v;
+    varying vec3 view_pos;
+    varying vec3 light_v;
+    varying vec3 color_v;
+    const vec3 spec_color = vec3(0.4, 0.4, 0.4);
+
+    void main() {
+        vec3 normal = normalize(normal_v);
+        vec3 light_dir = normalize(light_v - view_pos);
+        vec2 lighting = kiss3d_blinn_phong(normal, light_dir, normalize(-view_pos), 30.0);
+        vec3 color = color_v / 3.0 + lighting.x * color_v / 3.0 + lighting.y * spec_color / 3.0;
+        gl_FragColor = vec4(color, 1.0);
+    }";
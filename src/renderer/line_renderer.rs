@@ -23,7 +23,7 @@ pub struct LineRenderer {
 impl LineRenderer {
     /// Creates a new lines manager.
     pub fn new() -> LineRenderer {
-        let mut shader = Effect::new_from_str(LINES_VERTEX_SRC, LINES_FRAGMENT_SRC);
+        let mut shader = Effect::new_from_str(LINES_VERTEX_SRC, LINES_FRAGMENT_SRC).unwrap();
 
         shader.use_program();
 
@@ -68,6 +68,16 @@ impl LineRenderer {
             f32::EPSILON, /* Gl will usually round this to 1 pixel */
         );
     }
+
+    // The endpoints and colors of the lines queued for the next frame.
+    pub(crate) fn queued(&self) -> Vec<Point3<f32>> {
+        self.lines.data().clone().unwrap_or_default()
+    }
+
+    // Replaces the lines queued for the next frame, e.g., after a frame rendered for a snapshot.
+    pub(crate) fn requeue(&mut self, lines: Vec<Point3<f32>>) {
+        *self.lines.data_mut() = Some(lines)
+    }
 }
 
 impl Renderer for LineRenderer {
@@ -1,13 +1,34 @@
 //! Structures responsible for rendering elements other than kiss3d's meshes.
 
+pub(crate) use self::backdrop::Backdrop;
+pub use self::backdrop::GroundFade;
 #[cfg(feature = "conrod")]
 pub use self::conrod_renderer::ConrodRenderer;
+pub use self::debug_render::DebugRenderBackend;
+pub(crate) use self::instanced_mesh::InstancedMeshRenderer;
+pub use self::instanced_mesh::{Instance, InstancedMesh};
 pub use self::line_renderer::LineRenderer;
+pub(crate) use self::overlay_layer::OverlayLayer;
+pub(crate) use self::panorama_background::PanoramaBackground;
+pub use self::panorama_background::PanoramaProjection;
 pub use self::point_renderer::PointRenderer;
 pub use self::renderer::Renderer;
+pub(crate) use self::resampler::Resampler;
+pub(crate) use self::trail_renderer::TrailRenderer;
+pub use self::vector_field::VectorField;
+pub(crate) use self::vector_field::VectorFieldRenderer;
 
+mod backdrop;
 #[cfg(feature = "conrod")]
 mod conrod_renderer;
+pub mod debug_render;
+mod instance_culler;
+mod instanced_mesh;
 pub mod line_renderer;
+mod overlay_layer;
+mod panorama_background;
 pub mod point_renderer;
 mod renderer;
+mod resampler;
+mod trail_renderer;
+mod vector_field;
@@ -0,0 +1,139 @@
+//! Caching of the planar scene, overlays, and texts drawn over the 3D scene.
+
+use na::Vector2;
+
+use crate::context::Context;
+use crate::resource::{
+    AllocationType, BufferType, Effect, FramebufferManager, GPUVec, RenderTarget, ShaderAttribute,
+    ShaderUniform,
+};
+
+#[path = "../error.rs"]
+mod error;
+
+// An offscreen layer holding the 2D content drawn over the 3D scene, rendered again only when
+// this content changes, and composited over each frame.
+//
+// The content is identified by a fingerprint computed by the window from everything it is drawn
+// from, so the layer does not need to know what it contains.
+pub(crate) struct OverlayLayer {
+    target: Option<(RenderTarget, (u32, u32))>,
+    fingerprint: Option<u64>,
+    shader: Effect,
+    v_coord: ShaderAttribute<Vector2<f32>>,
+    layer: ShaderUniform<i32>,
+    vertices: GPUVec<Vector2<f32>>,
+}
+
+impl OverlayLayer {
+    pub fn new() -> OverlayLayer {
+        let vertices = vec![
+            Vector2::new(-1.0, -1.0),
+            Vector2::new(1.0, -1.0),
+            Vector2::new(-1.0, 1.0),
+            Vector2::new(1.0, 1.0),
+        ];
+
+        let mut shader = Effect::new_from_str(VERTEX_SHADER, FRAGMENT_SHADER).unwrap();
+
+        shader.use_program();
+
+        OverlayLayer {
+            target: None,
+            fingerprint: None,
+            v_coord: shader.get_attrib("v_coord").unwrap(),
+            layer: shader.get_uniform("layer").unwrap(),
+            vertices: GPUVec::new(vertices, BufferType::Array, AllocationType::StaticDraw),
+            shader,
+        }
+    }
+
+    // Forces the layer to be rendered again at the next frame.
+    pub fn invalidate(&mut self) {
+        self.fingerprint = None
+    }
+
+    // Whether the content identified by `fingerprint`, at the given size, differs from the one
+    // of the layer. The layer is then resized and considered up to date, so it must be rendered
+    // again by the caller.
+    pub fn update(&mut self, fingerprint: u64, size: (u32, u32)) -> bool {
+        match self.target {
+            Some((ref mut target, ref mut curr)) if *curr != size => {
+                target.resize(size.0 as f32, size.1 as f32);
+                *curr = size;
+                self.fingerprint = None;
+            }
+            Some(_) => {}
+            None => {
+                let target =
+                    FramebufferManager::new_render_target(size.0 as usize, size.1 as usize, false);
+                self.target = Some((target, size));
+                self.fingerprint = None;
+            }
+        }
+
+        self.fingerprint.replace(fingerprint) != Some(fingerprint)
+    }
+
+    // The render target the layer is rendered into, with a transparent background.
+    pub fn target(&self) -> Option<&RenderTarget> {
+        self.target.as_ref().map(|t| &t.0)
+    }
+
+    // Draws the layer over the whole viewport of the current framebuffer.
+    pub fn composite(&mut self) {
+        let target = match self.target {
+            Some((ref target, _)) => target,
+            None => return,
+        };
+
+        let ctxt = Context::get();
+
+        self.shader.use_program();
+        self.v_coord.enable();
+
+        verify!(ctxt.disable(Context::DEPTH_TEST));
+        verify!(ctxt.enable(Context::BLEND));
+        // The colors of the layer are already multiplied by their alpha.
+        verify!(ctxt.blend_func_separate(
+            Context::ONE,
+            Context::ONE_MINUS_SRC_ALPHA,
+            Context::ONE,
+            Context::ONE_MINUS_SRC_ALPHA,
+        ));
+        verify!(ctxt.active_texture(Context::TEXTURE0));
+        verify!(ctxt.bind_texture(Context::TEXTURE_2D, target.texture_id()));
+
+        self.layer.upload(&0);
+        self.v_coord.bind(&mut self.vertices);
+
+        verify!(ctxt.draw_arrays(Context::TRIANGLE_STRIP, 0, 4));
+
+        verify!(ctxt.disable(Context::BLEND));
+        verify!(ctxt.enable(Context::DEPTH_TEST));
+        self.v_coord.disable();
+    }
+}
+
+static VERTEX_SHADER: &str = "#version 100
+    attribute vec2 v_coord;
+    varying vec2   f_texcoord;
+
+    void main(void) {
+      gl_Position = vec4(v_coord, 0.0, 1.0);
+      f_texcoord  = (v_coord + 1.0) / 2.0;
+    }";
+
+static FRAGMENT_SHADER: &str = "#version 100
+#ifdef GL_FRAGMENT_PRECISION_HIGH
+   precision highp float;
+#else
+   precision mediump float;
+#endif
+
+    uniform sampler2D layer;
+    varying vec2      f_texcoord;
+
+    void main(void) {
+      gl_FragColor = texture2D(layer, f_texcoord);
+    }";
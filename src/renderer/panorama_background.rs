@@ -0,0 +1,177 @@
+//! A background displaying a panoramic image around the camera.
+
+use std::rc::Rc;
+
+use na::{Point4, Vector2, Vector3};
+
+use crate::camera::Camera;
+use crate::context::Context;
+use crate::resource::{
+    AllocationType, BufferType, Effect, GPUVec, ShaderAttribute, ShaderUniform, Texture,
+};
+
+#[path = "../error.rs"]
+mod error;
+
+/// How a panoramic image is mapped to the directions around the camera.
+///
+/// The center of the image is seen when looking along the `-z` axis, the `+x` axis is a quarter
+/// of the image to its right, and the `y` axis points up. This matches the images produced by
+/// `Window::snap_panorama`.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum PanoramaProjection {
+    /// An equirectangular image, covering 360° horizontally and 180° vertically.
+    Spherical,
+    /// A cylindrical image, covering 360° horizontally and `vertical_fov` radians vertically.
+    ///
+    /// The directions above and below the image show the background color.
+    Cylindrical {
+        /// The vertical field of view of the image, in radians.
+        vertical_fov: f32,
+    },
+}
+
+// Draws a panoramic image behind the scene.
+pub(crate) struct PanoramaBackground {
+    shader: Effect,
+    position: ShaderAttribute<Vector2<f32>>,
+    direction: ShaderAttribute<Vector3<f32>>,
+    panorama: ShaderUniform<i32>,
+    cylindrical: ShaderUniform<i32>,
+    tan_half_vfov: ShaderUniform<f32>,
+    background: ShaderUniform<Vector3<f32>>,
+    positions: GPUVec<Vector2<f32>>,
+    directions: GPUVec<Vector3<f32>>,
+    texture: Rc<Texture>,
+    projection: PanoramaProjection,
+}
+
+const CORNERS: [(f32, f32); 4] = [(-1.0, -1.0), (1.0, -1.0), (-1.0, 1.0), (1.0, 1.0)];
+
+impl PanoramaBackground {
+    pub fn new(texture: Rc<Texture>, projection: PanoramaProjection) -> PanoramaBackground {
+        let positions = CORNERS.iter().map(|c| Vector2::new(c.0, c.1)).collect();
+        let mut shader = Effect::new_from_str(PANORAMA_VERTEX_SRC, PANORAMA_FRAGMENT_SRC).unwrap();
+
+        shader.use_program();
+
+        PanoramaBackground {
+            position: shader
+                .get_attrib("position")
+                .expect("Failed to get shader attribute."),
+            direction: shader
+                .get_attrib("direction")
+                .expect("Failed to get shader attribute."),
+            panorama: shader
+                .get_uniform("panorama")
+                .expect("Failed to get shader uniform."),
+            cylindrical: shader
+                .get_uniform("cylindrical")
+                .expect("Failed to get shader uniform."),
+            tan_half_vfov: shader
+                .get_uniform("tan_half_vfov")
+                .expect("Failed to get shader uniform."),
+            background: shader
+                .get_uniform("background")
+                .expect("Failed to get shader uniform."),
+            positions: GPUVec::new(positions, BufferType::Array, AllocationType::StaticDraw),
+            directions: GPUVec::new(
+                vec![Vector3::zeros(); 4],
+                BufferType::Array,
+                AllocationType::StreamDraw,
+            ),
+            shader,
+            texture,
+            projection,
+        }
+    }
+
+    pub fn render(&mut self, camera: &dyn Camera, background: &Vector3<f32>) {
+        // The directions of the view rays at the corners of the screen, which are interpolated
+        // linearly in-between.
+        let inv = camera.inverse_transformation();
+        let (near_depth, far_depth) = camera.depth_range();
+
+        if let Some(dirs) = self.directions.data_mut() {
+            for (dir, corner) in dirs.iter_mut().zip(CORNERS.iter()) {
+                let near = inv * Point4::new(corner.0, corner.1, near_depth, 1.0);
+                let far = inv * Point4::new(corner.0, corner.1, far_depth, 1.0);
+                *dir = far.xyz() / far.w - near.xyz() / near.w;
+            }
+        }
+
+        let (cylindrical, tan_half_vfov) = match self.projection {
+            PanoramaProjection::Spherical => (0, 1.0),
+            PanoramaProjection::Cylindrical { vertical_fov } => (1, (vertical_fov / 2.0).tan()),
+        };
+
+        let ctxt = Context::get();
+        self.shader.use_program();
+        self.position.enable();
+        self.direction.enable();
+
+        verify!(ctxt.disable(Context::DEPTH_TEST));
+        verify!(ctxt.depth_mask(false));
+        verify!(ctxt.active_texture(Context::TEXTURE0));
+        verify!(ctxt.bind_texture(Context::TEXTURE_2D, Some(&*self.texture)));
+
+        self.panorama.upload(&0);
+        self.cylindrical.upload(&cylindrical);
+        self.tan_half_vfov.upload(&tan_half_vfov);
+        self.background.upload(background);
+        self.position.bind(&mut self.positions);
+        self.direction.bind(&mut self.directions);
+
+        verify!(ctxt.draw_arrays(Context::TRIANGLE_STRIP, 0, 4));
+
+        verify!(ctxt.depth_mask(true));
+        verify!(ctxt.enable(Context::DEPTH_TEST));
+
+        self.position.disable();
+        self.direction.disable();
+    }
+}
+
+static PANORAMA_VERTEX_SRC: &str = "#version 100
+    attribute vec2 position;
+    attribute vec3 direction;
+    varying vec3 f_direction;
+
+    void main() {
+        gl_Position = vec4(position, 0.0, 1.0);
+        f_direction = direction;
+    }";
+
+static PANORAMA_FRAGMENT_SRC: &str = "#version 100
+#ifdef GL_FRAGMENT_PRECISION_HIGH
+   precision highp float;
+#else
+   precision mediump float;
+#endif
+
+    uniform sampler2D panorama;
+    uniform int cylindrical;
+    uniform float tan_half_vfov;
+    uniform vec3 background;
+    varying vec3 f_direction;
+
+    const float PI = 3.14159265;
+
+    void main() {
+        vec3 dir = normalize(f_direction);
+        float u = 0.5 + atan(dir.x, -dir.z) / (2.0 * PI);
+        float v;
+
+        if (cylindrical == 1) {
+            v = 0.5 - dir.y / max(length(dir.xz), 1.0e-6) / (2.0 * tan_half_vfov);
+
+            if (v < 0.0 || v > 1.0) {
+                gl_FragColor = vec4(background, 1.0);
+                return;
+            }
+        } else {
+            v = 0.5 - asin(clamp(dir.y, -1.0, 1.0)) / PI;
+        }
+
+        gl_FragColor = texture2D(panorama, vec2(u, v));
+    }";
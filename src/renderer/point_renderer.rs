@@ -23,7 +23,7 @@ pub struct PointRenderer {
 impl PointRenderer {
     /// Creates a new points manager.
     pub fn new() -> PointRenderer {
-        let mut shader = Effect::new_from_str(POINTS_VERTEX_SRC, POINTS_FRAGMENT_SRC);
+        let mut shader = Effect::new_from_str(POINTS_VERTEX_SRC, POINTS_FRAGMENT_SRC).unwrap();
 
         shader.use_program();
 
@@ -56,6 +56,16 @@ impl PointRenderer {
             points.push(color);
         }
     }
+
+    // The positions and colors of the points queued for the next frame.
+    pub(crate) fn queued(&self) -> Vec<Point3<f32>> {
+        self.points.data().clone().unwrap_or_default()
+    }
+
+    // Replaces the points queued for the next frame, e.g., after a frame rendered for a snapshot.
+    pub(crate) fn requeue(&mut self, points: Vec<Point3<f32>>) {
+        *self.points.data_mut() = Some(points)
+    }
 }
 
 impl Renderer for PointRenderer {
@@ -0,0 +1,120 @@
+//! Resampling of an off-screen render target to the current framebuffer.
+
+use na::Vector2;
+
+use crate::context::Context;
+use crate::resource::{
+    AllocationType, BufferType, Effect, GPUVec, RenderTarget, ShaderAttribute, ShaderUniform,
+};
+
+#[path = "../error.rs"]
+mod error;
+
+// The maximum number of samples averaged along each axis, i.e., the maximum downsampling factor.
+const MAX_TAPS: u32 = 4;
+
+// Draws the color buffer of a render target over the whole viewport.
+//
+// Each pixel averages a square of `taps` by `taps` texels, `taps` being the downsampling factor
+// rounded up. Upsampling relies on the bilinear filtering of the texture.
+pub(crate) struct Resampler {
+    shader: Effect,
+    v_coord: ShaderAttribute<Vector2<f32>>,
+    fbo_texture: ShaderUniform<i32>,
+    texel: ShaderUniform<Vector2<f32>>,
+    taps: ShaderUniform<f32>,
+    fbo_vertices: GPUVec<Vector2<f32>>,
+}
+
+impl Resampler {
+    pub fn new() -> Resampler {
+        let fbo_vertices: Vec<Vector2<f32>> = vec![
+            Vector2::new(-1.0, -1.0),
+            Vector2::new(1.0, -1.0),
+            Vector2::new(-1.0, 1.0),
+            Vector2::new(1.0, 1.0),
+        ];
+
+        let mut shader = Effect::new_from_str(VERTEX_SHADER, FRAGMENT_SHADER).unwrap();
+
+        shader.use_program();
+
+        Resampler {
+            v_coord: shader.get_attrib("v_coord").unwrap(),
+            fbo_texture: shader.get_uniform("fbo_texture").unwrap(),
+            texel: shader.get_uniform("texel").unwrap(),
+            taps: shader.get_uniform("taps").unwrap(),
+            fbo_vertices: GPUVec::new(fbo_vertices, BufferType::Array, AllocationType::StaticDraw),
+            shader,
+        }
+    }
+
+    // Draws `source`, of the given size in pixels, downsampled by `factor`.
+    pub fn draw(&mut self, source: &RenderTarget, size: (u32, u32), factor: f32) {
+        let ctxt = Context::get();
+        let taps = factor.ceil().max(1.0).min(MAX_TAPS as f32);
+
+        self.shader.use_program();
+        self.v_coord.enable();
+
+        verify!(ctxt.clear_color(0.0, 0.0, 0.0, 1.0));
+        verify!(ctxt.clear(Context::COLOR_BUFFER_BIT | Context::DEPTH_BUFFER_BIT));
+        verify!(ctxt.disable(Context::DEPTH_TEST));
+        verify!(ctxt.active_texture(Context::TEXTURE0));
+        verify!(ctxt.bind_texture(Context::TEXTURE_2D, source.texture_id()));
+
+        self.fbo_texture.upload(&0);
+        self.texel
+            .upload(&Vector2::new(1.0 / size.0 as f32, 1.0 / size.1 as f32));
+        self.taps.upload(&taps);
+        self.v_coord.bind(&mut self.fbo_vertices);
+
+        verify!(ctxt.draw_arrays(Context::TRIANGLE_STRIP, 0, 4));
+
+        verify!(ctxt.enable(Context::DEPTH_TEST));
+        self.v_coord.disable();
+    }
+}
+
+static VERTEX_SHADER: &str = "#version 100
+    attribute vec2 v_coord;
+    varying vec2   f_texcoord;
+
+    void main(void) {
+      gl_Position = vec4(v_coord, 0.0, 1.0);
+      f_texcoord  = (v_coord + 1.0) / 2.0;
+    }";
+
+static FRAGMENT_SHADER: &str = "#version 100
+#ifdef GL_FRAGMENT_PRECISION_HIGH
+   precision highp float;
+#else
+   precision mediump float;
+#endif
+
+    uniform sampler2D fbo_texture;
+    uniform vec2      texel;
+    uniform float     taps;
+    varying vec2      f_texcoord;
+
+    void main(void) {
+      vec4 sum = vec4(0.0);
+
+      // The samples are centered on the texels covered by the pixel.
+      for (int i = 0; i < 4; i++) {
+        if (float(i) >= taps) {
+          break;
+        }
+
+        for (int j = 0; j < 4; j++) {
+          if (float(j) >= taps) {
+            break;
+          }
+
+          vec2 offset = (vec2(float(i), float(j)) + 0.5 - taps / 2.0) * texel;
+          sum += texture2D(fbo_texture, f_texcoord + offset);
+        }
+      }
+
+      gl_FragColor = sum / (taps * taps);
+    }";
@@ -0,0 +1,152 @@
+//! A renderer of the trails of the scene nodes.
+
+use crate::camera::Camera;
+use crate::context::Context;
+use crate::resource::{AllocationType, BufferType, Effect, GPUVec, ShaderAttribute, ShaderUniform};
+use crate::scene::trail::{self, Trail};
+use na::{Matrix4, Point3, Point4};
+
+#[path = "../error.rs"]
+mod error;
+
+// Draws the trails as ribbons facing the camera.
+pub(crate) struct TrailRenderer {
+    shader: Effect,
+    pos: ShaderAttribute<Point3<f32>>,
+    color: ShaderAttribute<Point4<f32>>,
+    proj: ShaderUniform<Matrix4<f32>>,
+    view: ShaderUniform<Matrix4<f32>>,
+    positions: GPUVec<Point3<f32>>,
+    colors: GPUVec<Point4<f32>>,
+}
+
+impl TrailRenderer {
+    pub fn new() -> TrailRenderer {
+        let mut shader = Effect::new_from_str(TRAIL_VERTEX_SRC, TRAIL_FRAGMENT_SRC).unwrap();
+
+        shader.use_program();
+
+        TrailRenderer {
+            positions: GPUVec::new(Vec::new(), BufferType::Array, AllocationType::StreamDraw),
+            colors: GPUVec::new(Vec::new(), BufferType::Array, AllocationType::StreamDraw),
+            pos: shader.get_attrib::<Point3<f32>>("position").unwrap(),
+            color: shader.get_attrib::<Point4<f32>>("color").unwrap(),
+            proj: shader.get_uniform::<Matrix4<f32>>("proj").unwrap(),
+            view: shader.get_uniform::<Matrix4<f32>>("view").unwrap(),
+            shader,
+        }
+    }
+
+    pub fn render(&mut self, pass: usize, camera: &mut dyn Camera) {
+        let eye = camera.eye();
+
+        if let (Some(positions), Some(colors)) = (self.positions.data_mut(), self.colors.data_mut())
+        {
+            positions.clear();
+            colors.clear();
+            trail::for_each(|t| push_ribbon(t, &eye, positions, colors));
+        }
+
+        if self.positions.len() == 0 {
+            return;
+        }
+
+        self.shader.use_program();
+        self.pos.enable();
+        self.color.enable();
+
+        camera.upload(pass, &mut self.proj, &mut self.view);
+
+        self.pos.bind(&mut self.positions);
+        self.color.bind(&mut self.colors);
+
+        let ctxt = Context::get();
+        verify!(ctxt.disable(Context::CULL_FACE));
+        verify!(ctxt.enable(Context::BLEND));
+        verify!(ctxt.blend_func_separate(
+            Context::SRC_ALPHA,
+            Context::ONE_MINUS_SRC_ALPHA,
+            Context::ONE,
+            Context::ONE_MINUS_SRC_ALPHA,
+        ));
+        verify!(ctxt.depth_mask(false));
+
+        verify!(ctxt.draw_arrays(Context::TRIANGLES, 0, self.positions.len() as i32));
+
+        verify!(ctxt.depth_mask(true));
+        verify!(ctxt.disable(Context::BLEND));
+
+        self.pos.disable();
+        self.color.disable();
+    }
+}
+
+// Appends the triangles of a ribbon following the trail, widened orthogonally to the view
+// direction.
+fn push_ribbon(
+    trail: &Trail,
+    eye: &Point3<f32>,
+    positions: &mut Vec<Point3<f32>>,
+    colors: &mut Vec<Point4<f32>>,
+) {
+    let pts = &trail.points;
+    let n = pts.len();
+
+    if n < 2 {
+        return;
+    }
+
+    let color = trail.color.to_render();
+    let edges: Vec<_> = (0..n)
+        .map(|i| {
+            let tangent = pts[(i + 1).min(n - 1)] - pts[i.saturating_sub(1)];
+            let side = tangent
+                .cross(&(eye - pts[i]))
+                .try_normalize(1.0e-12)
+                .unwrap_or_else(na::zero)
+                * (trail.width / 2.0);
+            // The oldest point is transparent when the trail fades.
+            let alpha = if trail.fade {
+                i as f32 / (n - 1) as f32
+            } else {
+                1.0
+            };
+
+            (
+                pts[i] - side,
+                pts[i] + side,
+                Point4::new(color.x, color.y, color.z, alpha),
+            )
+        })
+        .collect();
+
+    for w in edges.windows(2) {
+        let ((a0, a1, ca), (b0, b1, cb)) = (w[0], w[1]);
+
+        positions.extend_from_slice(&[a0, a1, b0, b0, a1, b1]);
+        colors.extend_from_slice(&[ca, ca, cb, cb, ca, cb]);
+    }
+}
+
+static TRAIL_VERTEX_SRC: &str = "#version 100
+    attribute vec3 position;
+    attribute vec4 color;
+    varying   vec4 vColor;
+    uniform   mat4 proj;
+    uniform   mat4 view;
+    void main() {
+        gl_Position = proj * view * vec4(position, 1.0);
+        vColor = color;
+    }";
+
+static TRAIL_FRAGMENT_SRC: &str = "#version 100
+#ifdef GL_FRAGMENT_PRECISION_HIGH
+   precision highp float;
+#else
+   precision mediump float;
+#endif
+
+    varying vec4 vColor;
+    void main() {
+        gl_FragColor = vColor;
+    }";
@@ -0,0 +1,583 @@
+//! A renderer of fields of arrows.
+
+use std::cell::RefCell;
+use std::f32;
+use std::rc::Rc;
+
+use na::{Matrix4, Point3, Vector3};
+
+use crate::camera::Camera;
+use crate::color::Color;
+use crate::colormap::{self, Colormap};
+use crate::context::Context;
+use crate::renderer::instance_culler::InstanceCuller;
+use crate::resource::{AllocationType, BufferType, Effect, GPUVec, ShaderAttribute, ShaderUniform};
+
+#[path = "../error.rs"]
+mod error;
+
+// The number of sides of the arrow geometry.
+const NUM_SIDES: u16 = 12;
+// The dimensions of the arrow geometry, in units of the arrow width.
+const SHAFT_RADIUS: f32 = 0.2;
+const HEAD_RADIUS: f32 = 0.5;
+// Must match the head length of the vertex shader.
+const HEAD_LENGTH: f32 = 1.5;
+
+enum Coloring {
+    Uniform(Color),
+    Magnitude(Colormap, Option<(f32, f32)>),
+}
+
+struct VectorFieldData {
+    origins: GPUVec<Point3<f32>>,
+    vectors: GPUVec<Vector3<f32>>,
+    colors: GPUVec<Point3<f32>>,
+    coloring: Coloring,
+    scale: f32,
+    width: f32,
+    visible: bool,
+    gpu_culling: bool,
+    // The origins, vectors, and colors of the arrows left by the GPU culling, interleaved.
+    culled: GPUVec<Point3<f32>>,
+}
+
+impl VectorFieldData {
+    fn update_colors(&mut self) {
+        let vectors = match self.vectors.data() {
+            Some(vectors) => vectors,
+            None => return,
+        };
+
+        let colors = match &self.coloring {
+            Coloring::Uniform(color) => vec![color.to_render(); vectors.len()],
+            Coloring::Magnitude(colormap, range) => {
+                let norms: Vec<f32> = vectors.iter().map(|v| v.norm()).collect();
+                colormap.map_all(&norms, *range)
+            }
+        };
+
+        *self.colors.data_mut() = Some(colors);
+    }
+}
+
+/// A set of arrows, e.g., the samples of a velocity field or of a gradient.
+///
+/// Each arrow starts at its origin and has the direction of its vector, with a length equal to
+/// the norm of the vector multiplied by the field scale. The arrows are drawn with a single
+/// instanced draw call when the platform supports it, so that large fields can be updated at each
+/// frame with `set_vectors`.
+///
+/// This is a handle to a field drawn by the window that created it with
+/// `Window::add_vector_field`: clones of it refer to the same field.
+#[derive(Clone)]
+pub struct VectorField {
+    data: Rc<RefCell<VectorFieldData>>,
+}
+
+impl VectorField {
+    fn new() -> VectorField {
+        let data = VectorFieldData {
+            origins: GPUVec::new(Vec::new(), BufferType::Array, AllocationType::DynamicDraw),
+            vectors: GPUVec::new(Vec::new(), BufferType::Array, AllocationType::DynamicDraw),
+            colors: GPUVec::new(Vec::new(), BufferType::Array, AllocationType::DynamicDraw),
+            coloring: Coloring::Uniform(Color::new(1.0, 1.0, 1.0)),
+            scale: 1.0,
+            width: 0.05,
+            visible: true,
+            gpu_culling: false,
+            culled: GPUVec::new(
+                Vec::new(),
+                BufferType::TransformFeedback,
+                AllocationType::StreamDraw,
+            ),
+        };
+
+        VectorField {
+            data: Rc::new(RefCell::new(data)),
+        }
+    }
+
+    /// Replaces the arrows of this field.
+    ///
+    /// The `i`-th arrow starts at `origins[i]` and points toward `vectors[i]`. The buffers of the
+    /// previous arrows are reused.
+    pub fn set_vectors(&mut self, origins: &[Point3<f32>], vectors: &[Vector3<f32>]) {
+        assert_eq!(
+            origins.len(),
+            vectors.len(),
+            "There must be one vector per origin."
+        );
+
+        let mut data = self.data.borrow_mut();
+
+        copy_into(data.origins.data_mut(), origins);
+        copy_into(data.vectors.data_mut(), vectors);
+        data.update_colors();
+    }
+
+    /// The number of arrows of this field.
+    pub fn len(&self) -> usize {
+        self.data.borrow().origins.len()
+    }
+
+    /// Whether this field has no arrow.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Sets the factor from the norm of the vectors to the length of the arrows.
+    pub fn set_scale(&mut self, scale: f32) {
+        self.data.borrow_mut().scale = scale;
+    }
+
+    /// The factor from the norm of the vectors to the length of the arrows.
+    pub fn scale(&self) -> f32 {
+        self.data.borrow().scale
+    }
+
+    /// Sets the width of the arrow heads, the shafts being thinner.
+    pub fn set_width(&mut self, width: f32) {
+        self.data.borrow_mut().width = width;
+    }
+
+    /// The width of the arrow heads.
+    pub fn width(&self) -> f32 {
+        self.data.borrow().width
+    }
+
+    /// Gives the same color to all the arrows.
+    pub fn set_color(&mut self, color: impl Into<Color>) {
+        let mut data = self.data.borrow_mut();
+        data.coloring = Coloring::Uniform(color.into());
+        data.update_colors();
+    }
+
+    /// Colors the arrows by the norm of their vector.
+    ///
+    /// The norms are mapped from `range` to the colormap, or from the range of the norms of the
+    /// current vectors if `range` is `None`.
+    pub fn set_colormap(&mut self, colormap: Colormap, range: Option<(f32, f32)>) {
+        let mut data = self.data.borrow_mut();
+        data.coloring = Coloring::Magnitude(colormap, range);
+        data.update_colors();
+    }
+
+    /// The range of the norms of the vectors of this field.
+    pub fn magnitude_range(&self) -> (f32, f32) {
+        let data = self.data.borrow();
+        let norms: Vec<f32> = data
+            .vectors
+            .data()
+            .iter()
+            .flatten()
+            .map(|v| v.norm())
+            .collect();
+
+        colormap::value_range(&norms)
+    }
+
+    /// Sets whether this field is drawn.
+    pub fn set_visible(&mut self, visible: bool) {
+        self.data.borrow_mut().visible = visible;
+    }
+
+    /// Whether this field is drawn.
+    pub fn is_visible(&self) -> bool {
+        self.data.borrow().visible
+    }
+
+    /// Enables the culling of the arrows outside of the view frustum on the GPU.
+    ///
+    /// The visible arrows are selected by a geometry shader and compacted into the buffer read by
+    /// the instanced draw call, which pays off for fields of hundreds of thousands of arrows
+    /// mostly out of view. The number of visible arrows is read back from the GPU, waiting for
+    /// the culling to complete. This requires geometry shaders and instancing, i.e., OpenGL 3.3
+    /// or OpenGL ES 3.2: otherwise, e.g., with WebGL, all the arrows are drawn.
+    pub fn set_gpu_culling(&mut self, enabled: bool) {
+        self.data.borrow_mut().gpu_culling = enabled;
+    }
+
+    /// Whether the arrows outside of the view frustum are culled on the GPU.
+    pub fn gpu_culling(&self) -> bool {
+        self.data.borrow().gpu_culling
+    }
+}
+
+fn copy_into<T: Copy>(buf: &mut Option<Vec<T>>, values: &[T]) {
+    match buf {
+        Some(buf) => {
+            buf.clear();
+            buf.extend_from_slice(values);
+        }
+        None => *buf = Some(values.to_vec()),
+    }
+}
+
+// The inputs of the shader specific to each arrow: vertex attributes read once per instance if
+// instancing is supported, uniforms set before drawing each arrow otherwise.
+enum ArrowInputs {
+    Instanced {
+        origin: ShaderAttribute<Point3<f32>>,
+        vector: ShaderAttribute<Vector3<f32>>,
+        color: ShaderAttribute<Point3<f32>>,
+    },
+    PerArrow {
+        origin: ShaderUniform<Point3<f32>>,
+        vector: ShaderUniform<Vector3<f32>>,
+        color: ShaderUniform<Point3<f32>>,
+    },
+}
+
+// Draws the vector fields of a window.
+pub(crate) struct VectorFieldRenderer {
+    fields: Vec<VectorField>,
+    shader: Effect,
+    shape: ShaderAttribute<Point3<f32>>,
+    normal: ShaderAttribute<Vector3<f32>>,
+    inputs: ArrowInputs,
+    // Created the first time a field enables the GPU culling, if it is supported.
+    culler: Option<InstanceCuller>,
+    proj: ShaderUniform<Matrix4<f32>>,
+    view: ShaderUniform<Matrix4<f32>>,
+    scale: ShaderUniform<f32>,
+    width: ShaderUniform<f32>,
+    shapes: GPUVec<Point3<f32>>,
+    normals: GPUVec<Vector3<f32>>,
+    faces: GPUVec<Point3<u16>>,
+}
+
+impl VectorFieldRenderer {
+    pub fn new() -> VectorFieldRenderer {
+        let instanced = Context::get().supports_instancing();
+        let defines: &[&str] = if instanced { &["INSTANCED"] } else { &[] };
+        let mut shader = Effect::new_from_str_with_defines(
+            VECTOR_FIELD_VERTEX_SRC,
+            VECTOR_FIELD_FRAGMENT_SRC,
+            defines,
+        )
+        .unwrap();
+
+        shader.use_program();
+
+        let inputs = if instanced {
+            ArrowInputs::Instanced {
+                origin: shader.get_attrib("origin").unwrap(),
+                vector: shader.get_attrib("vector").unwrap(),
+                color: shader.get_attrib("color").unwrap(),
+            }
+        } else {
+            ArrowInputs::PerArrow {
+                origin: shader.get_uniform("origin").unwrap(),
+                vector: shader.get_uniform("vector").unwrap(),
+                color: shader.get_uniform("color").unwrap(),
+            }
+        };
+        let (shapes, normals, faces) = arrow_geometry();
+
+        VectorFieldRenderer {
+            fields: Vec::new(),
+            shape: shader.get_attrib("shape").unwrap(),
+            normal: shader.get_attrib("normal").unwrap(),
+            inputs,
+            culler: None,
+            proj: shader.get_uniform("proj").unwrap(),
+            view: shader.get_uniform("view").unwrap(),
+            scale: shader.get_uniform("scale").unwrap(),
+            width: shader.get_uniform("width").unwrap(),
+            shapes: GPUVec::new(shapes, BufferType::Array, AllocationType::StaticDraw),
+            normals: GPUVec::new(normals, BufferType::Array, AllocationType::StaticDraw),
+            faces: GPUVec::new(faces, BufferType::ElementArray, AllocationType::StaticDraw),
+            shader,
+        }
+    }
+
+    pub fn add(&mut self) -> VectorField {
+        let field = VectorField::new();
+        self.fields.push(field.clone());
+        field
+    }
+
+    pub fn remove(&mut self, field: &VectorField) {
+        self.fields.retain(|f| !Rc::ptr_eq(&f.data, &field.data));
+    }
+
+    pub fn render(&mut self, pass: usize, camera: &mut dyn Camera) {
+        let has_arrows = self.fields.iter().any(|f| {
+            let data = f.data.borrow();
+            data.visible && data.origins.len() != 0
+        });
+
+        if !has_arrows {
+            return;
+        }
+
+        // The number of arrows left by the GPU culling of each field, computed before the arrow
+        // shader is bound.
+        let culled_counts = self.cull(pass, camera);
+
+        let ctxt = Context::get();
+        self.shader.use_program();
+        self.shape.enable();
+        self.normal.enable();
+
+        camera.upload(pass, &mut self.proj, &mut self.view);
+
+        self.shape.bind(&mut self.shapes);
+        self.normal.bind(&mut self.normals);
+        self.faces.bind();
+
+        verify!(ctxt.disable(Context::CULL_FACE));
+
+        let num_indices = self.faces.len() as i32 * 3;
+
+        for (field, culled_count) in self.fields.iter().zip(&culled_counts) {
+            let mut data = field.data.borrow_mut();
+            let data = &mut *data;
+
+            if !data.visible || data.origins.len() == 0 || *culled_count == Some(0) {
+                continue;
+            }
+
+            self.scale.upload(&data.scale);
+            self.width.upload(&data.width);
+
+            match &mut self.inputs {
+                ArrowInputs::Instanced {
+                    origin,
+                    vector,
+                    color,
+                } => {
+                    origin.enable();
+                    vector.enable();
+                    color.enable();
+
+                    match culled_count {
+                        Some(_) => unsafe {
+                            origin.bind_sub_buffer_generic(&mut data.culled, 2, 0);
+                            vector.bind_sub_buffer_generic(&mut data.culled, 2, 1);
+                            color.bind_sub_buffer_generic(&mut data.culled, 2, 2);
+                        },
+                        None => {
+                            origin.bind(&mut data.origins);
+                            vector.bind(&mut data.vectors);
+                            color.bind(&mut data.colors);
+                        }
+                    }
+
+                    origin.set_divisor(1);
+                    vector.set_divisor(1);
+                    color.set_divisor(1);
+
+                    // Binding the attributes may have changed the bound element array.
+                    self.faces.bind();
+                    verify!(ctxt.draw_elements_instanced(
+                        Context::TRIANGLES,
+                        num_indices,
+                        Context::UNSIGNED_SHORT,
+                        0,
+                        culled_count.unwrap_or(data.origins.len()) as i32,
+                    ));
+
+                    origin.set_divisor(0);
+                    vector.set_divisor(0);
+                    color.set_divisor(0);
+                    origin.disable();
+                    vector.disable();
+                    color.disable();
+                }
+                ArrowInputs::PerArrow {
+                    origin,
+                    vector,
+                    color,
+                } => {
+                    if let (Some(origins), Some(vectors), Some(colors)) =
+                        (data.origins.data(), data.vectors.data(), data.colors.data())
+                    {
+                        for ((o, v), c) in origins.iter().zip(vectors).zip(colors) {
+                            origin.upload(o);
+                            vector.upload(v);
+                            color.upload(c);
+                            verify!(ctxt.draw_elements(
+                                Context::TRIANGLES,
+                                num_indices,
+                                Context::UNSIGNED_SHORT,
+                                0
+                            ));
+                        }
+                    }
+                }
+            }
+        }
+
+        self.shape.disable();
+        self.normal.disable();
+    }
+
+    // Culls the visible fields enabling the GPU culling, and returns the number of arrows left
+    // for each field, or `None` for the fields drawn without culling.
+    fn cull(&mut self, pass: usize, camera: &dyn Camera) -> Vec<Option<usize>> {
+        let needs_culling = self.fields.iter().any(|f| {
+            let data = f.data.borrow();
+            data.visible && data.gpu_culling
+        });
+
+        if needs_culling
+            && self.culler.is_none()
+            && matches!(self.inputs, ArrowInputs::Instanced { .. })
+            && Context::get().supports_geometry_shaders()
+        {
+            self.culler = InstanceCuller::new();
+        }
+
+        let (proj, view) = camera.pass_matrices(pass);
+        let proj_view = proj * view;
+        let culler = &mut self.culler;
+
+        self.fields
+            .iter()
+            .map(|field| {
+                let mut data = field.data.borrow_mut();
+                let data = &mut *data;
+
+                match culler {
+                    Some(culler) if data.visible && data.gpu_culling => Some(culler.cull(
+                        &proj_view,
+                        data.scale,
+                        data.width,
+                        &mut data.origins,
+                        &mut data.vectors,
+                        &mut data.colors,
+                        &mut data.culled,
+                    )),
+                    _ => None,
+                }
+            })
+            .collect()
+    }
+}
+
+// The vertices, normals and faces of an arrow.
+type ArrowGeometry = (Vec<Point3<f32>>, Vec<Vector3<f32>>, Vec<Point3<u16>>);
+
+// The geometry of an arrow along the `y` axis.
+//
+// The `x` and `z` coordinates are given in units of the arrow width. The `y` coordinate is `0`
+// at the origin of the arrow, `1` at the base of its head, and `2` at its tip: the shader places
+// these according to the length of each arrow.
+fn arrow_geometry() -> ArrowGeometry {
+    let mut shapes = Vec::new();
+    let mut normals = Vec::new();
+    let mut faces = Vec::new();
+    let n = NUM_SIDES;
+    let dirs: Vec<(f32, f32)> = (0..n)
+        .map(|i| {
+            let angle = i as f32 * f32::consts::PI * 2.0 / n as f32;
+            (angle.cos(), angle.sin())
+        })
+        .collect();
+
+    // A ring of vertices, returning the index of the first one.
+    let ring = |shapes: &mut Vec<Point3<f32>>,
+                normals: &mut Vec<Vector3<f32>>,
+                radius: f32,
+                y: f32,
+                normal: &dyn Fn(f32, f32) -> Vector3<f32>| {
+        let first = shapes.len() as u16;
+
+        for &(c, s) in &dirs {
+            shapes.push(Point3::new(c * radius, y, s * radius));
+            normals.push(normal(c, s));
+        }
+
+        first
+    };
+
+    // The shaft.
+    let radial = |c: f32, s: f32| Vector3::new(c, 0.0, s);
+    let bottom = ring(&mut shapes, &mut normals, SHAFT_RADIUS, 0.0, &radial);
+    let top = ring(&mut shapes, &mut normals, SHAFT_RADIUS, 1.0, &radial);
+
+    for i in 0..n {
+        let j = (i + 1) % n;
+        faces.push(Point3::new(bottom + i, top + i, bottom + j));
+        faces.push(Point3::new(bottom + j, top + i, top + j));
+    }
+
+    // The discs closing the shaft and the back of the head.
+    let down = |_: f32, _: f32| -Vector3::y();
+
+    for &(radius, y) in &[(SHAFT_RADIUS, 0.0), (HEAD_RADIUS, 1.0)] {
+        let center = shapes.len() as u16;
+        shapes.push(Point3::new(0.0, y, 0.0));
+        normals.push(-Vector3::y());
+        let rim = ring(&mut shapes, &mut normals, radius, y, &down);
+
+        for i in 0..n {
+            faces.push(Point3::new(center, rim + (i + 1) % n, rim + i));
+        }
+    }
+
+    // The head, with one tip vertex per side for sharp normals.
+    let slope =
+        |c: f32, s: f32| Vector3::new(c * HEAD_LENGTH, HEAD_RADIUS, s * HEAD_LENGTH).normalize();
+    let base = ring(&mut shapes, &mut normals, HEAD_RADIUS, 1.0, &slope);
+    let tip = ring(&mut shapes, &mut normals, 0.0, 2.0, &slope);
+
+    for i in 0..n {
+        faces.push(Point3::new(base + i, tip + i, base + (i + 1) % n));
+    }
+
+    (shapes, normals, faces)
+}
+
+static VECTOR_FIELD_VERTEX_SRC: &str = "#version 100
+    attribute vec3 shape;
+    attribute vec3 normal;
+#ifdef INSTANCED
+    attribute vec3 origin;
+    attribute vec3 vector;
+    attribute vec3 color;
+#else
+    uniform vec3 origin;
+    uniform vec3 vector;
+    uniform vec3 color;
+#endif
+    uniform mat4 proj;
+    uniform mat4 view;
+    uniform float scale;
+    uniform float width;
+    varying vec3 vColor;
+
+    void main() {
+        float len = length(vector) * abs(scale);
+        vec3 dir = len > 0.0 ? normalize(vector) * sign(scale) : vec3(0.0, 1.0, 0.0);
+        vec3 helper = abs(dir.y) < 0.9 ? vec3(0.0, 1.0, 0.0) : vec3(1.0, 0.0, 0.0);
+        vec3 u = normalize(cross(dir, helper));
+        vec3 w = cross(u, dir);
+
+        // The head is `HEAD_LENGTH` widths long, and shortened on arrows too short to hold it.
+        float head = min(1.5 * width, 0.5 * len);
+        float along = shape.y < 0.5 ? 0.0 : (shape.y < 1.5 ? len - head : len);
+        // Arrows of zero length collapse to their origin.
+        float radial = len > 0.0 ? width : 0.0;
+        vec3 world = origin + (u * shape.x + w * shape.z) * radial + dir * along;
+        vec3 world_normal = u * normal.x + dir * normal.y + w * normal.z;
+
+        // Lighting from the camera.
+        vec3 view_normal = normalize((view * vec4(world_normal, 0.0)).xyz);
+        vColor = color * (0.3 + 0.7 * abs(view_normal.z));
+        gl_Position = proj * view * vec4(world, 1.0);
+    }";
+
+static VECTOR_FIELD_FRAGMENT_SRC: &str = "#version 100
+#ifdef GL_FRAGMENT_PRECISION_HIGH
+   precision highp float;
+#else
+   precision mediump float;
+#endif
+
+    varying vec3 vColor;
+    void main() {
+        gl_FragColor = vec4(vColor, 1.0);
+    }";
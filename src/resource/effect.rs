@@ -1,102 +1,309 @@
-use std::fs::File;
-use std::io::Read;
+use std::cell::RefCell;
 use std::marker::PhantomData;
 use std::path::Path;
+use std::rc::{Rc, Weak};
 use std::str;
 
 use crate::context::{Context, GLintptr, Program, Shader, UniformLocation};
-use crate::resource::{GLPrimitive, GPUVec};
+use crate::loader::asset_source;
+use crate::resource::shader_error::map_compilation_log;
+use crate::resource::shader_library::MappedSource;
+use crate::resource::{GLPrimitive, GPUVec, ShaderError, ShaderStage, VertexFormat};
 
 #[path = "../error.rs"]
 mod error;
 
 /// Structure encapsulating a program.
+///
+/// The program is compiled again from its sources if the context is lost and restored.
 pub struct Effect {
+    state: Rc<RefCell<ProgramState>>,
+    vshader_source: MappedSource,
+    fshader_source: MappedSource,
+    feedback: Option<Feedback>,
+    // The uniform blocks bound to a binding point, with that binding point.
+    uniform_blocks: Vec<(String, u32)>,
+}
+
+// The geometry shader of a program whose outputs are captured by transform feedback, with the
+// names of these outputs.
+struct Feedback {
+    gshader_source: MappedSource,
+    varyings: Vec<String>,
+}
+
+struct ProgramState {
     program: Program,
     vshader: Shader,
+    gshader: Option<Shader>,
     fshader: Shader,
+    // The context generation the program has been compiled with.
+    generation: u64,
 }
 
 impl Effect {
     /// Creates a new shader program from two files containing the vertex and fragment shader.
-    pub fn new(vshader_path: &Path, fshader_path: &Path) -> Option<Effect> {
-        let mut vshader = String::new();
-        let mut fshader = String::new();
-
-        if File::open(vshader_path)
-            .map(|mut v| v.read_to_string(&mut vshader))
-            .is_err()
-        {
-            return None;
-        }
+    ///
+    /// Errors are reported with the paths of the files.
+    pub fn new(vshader_path: &Path, fshader_path: &Path) -> Result<Effect, ShaderError> {
+        let read = |path: &Path| {
+            asset_source::read_to_string(path).map_err(|e| ShaderError::Io(path.to_path_buf(), e))
+        };
+
+        let vshader = read(vshader_path)?;
+        let fshader = read(fshader_path)?;
+
+        Effect::build(
+            (&vshader, &vshader_path.display().to_string()),
+            (&fshader, &fshader_path.display().to_string()),
+            None,
+            &[],
+            &[],
+        )
+    }
 
-        if File::open(fshader_path)
-            .map(|mut f| f.read_to_string(&mut fshader))
-            .is_err()
-        {
-            return None;
-        }
+    /// Creates a new shader program from strings of the vertex and fragment shader.
+    ///
+    /// The `#include` directives of the shaders are replaced by the snippets of the shader
+    /// library, see `register_shader_snippet`. Fails if a snippet is not registered, or if the
+    /// shaders do not compile or link, with the driver log mapped back to the lines of the
+    /// original shaders and snippets.
+    pub fn new_from_str(vshader: &str, fshader: &str) -> Result<Effect, ShaderError> {
+        Effect::new_from_str_with_defines(vshader, fshader, &[])
+    }
 
-        Some(Effect::new_from_str(&vshader[..], &fshader[..]))
+    /// Creates a new shader program from strings of the vertex and fragment shader, compiled with
+    /// the given preprocessor definitions.
+    ///
+    /// Each definition, e.g. `"HAS_TEXTURE"` or `"NUM_LIGHTS 4"`, is inserted as a `#define`
+    /// directive right after the `#version` directive of both shaders.
+    pub fn new_from_str_with_defines(
+        vshader: &str,
+        fshader: &str,
+        defines: &[&str],
+    ) -> Result<Effect, ShaderError> {
+        let header: Vec<_> = defines.iter().map(|d| format!("#define {}", d)).collect();
+
+        Effect::build(
+            (vshader, "vertex shader"),
+            (fshader, "fragment shader"),
+            None,
+            &header,
+            &header,
+        )
     }
 
-    /// Creates a new shader program from strings of the vertex and fragment shader.
-    pub fn new_from_str(vshader: &str, fshader: &str) -> Effect {
-        let (program, vshader, fshader) = load_shader_program(vshader, fshader);
+    /// Creates a new shader program from strings of GLSL ES 1.0 vertex and fragment shaders,
+    /// converted to a GLSL version able to declare uniform blocks.
+    ///
+    /// The `UNIFORM_BUFFERS` preprocessor definition is added to the given ones, so the shaders
+    /// can declare uniform blocks when it is defined and plain uniforms otherwise, e.g., by
+    /// including the `kiss3d/frame` snippet. Fails with
+    /// `ShaderError::UniformBuffersUnsupported` if the context does not support uniform buffers.
+    pub fn new_with_uniform_buffers(
+        vshader: &str,
+        fshader: &str,
+        defines: &[&str],
+    ) -> Result<Effect, ShaderError> {
+        let version = Context::get()
+            .uniform_buffer_glsl_version()
+            .ok_or(ShaderError::UniformBuffersUnsupported)?;
+
+        Effect::build(
+            (vshader, "vertex shader"),
+            (fshader, "fragment shader"),
+            Some(version),
+            &glsl3_header(false, defines),
+            &glsl3_header(true, defines),
+        )
+    }
 
-        Effect {
-            program,
-            vshader,
-            fshader,
-        }
+    // Creates a program from the vertex and fragment shaders, given with the names of the files
+    // they come from, and the lines to insert after their `#version` directive.
+    fn build(
+        vshader: (&str, &str),
+        fshader: (&str, &str),
+        version: Option<&str>,
+        vheader: &[String],
+        fheader: &[String],
+    ) -> Result<Effect, ShaderError> {
+        let vshader_source = mapped_source(ShaderStage::Vertex, vshader, version, vheader)?;
+        let fshader_source = mapped_source(ShaderStage::Fragment, fshader, version, fheader)?;
+        let state = ProgramState::new(&vshader_source, &fshader_source, None)?;
+
+        Ok(Effect {
+            state: Rc::new(RefCell::new(state)),
+            vshader_source,
+            fshader_source,
+            feedback: None,
+            uniform_blocks: Vec::new(),
+        })
     }
 
-    /// Gets a uniform variable from the shader program.
-    pub fn get_uniform<T: GLPrimitive>(&self, name: &str) -> Option<ShaderUniform<T>> {
-        let ctxt = Context::get();
-        let location = ctxt.get_uniform_location(&self.program, name);
+    /// Creates a new shader program with a geometry shader, whose outputs `varyings` are captured
+    /// by transform feedback.
+    ///
+    /// The shaders are written in GLSL 1.50 without `#version` directive, the version being
+    /// chosen by the context, and the fragment shader must declare the precision of its floats.
+    /// The varyings are interleaved, in the given order, in the buffer bound to the binding point
+    /// `0` of `Context::TRANSFORM_FEEDBACK_BUFFER`. Fails with
+    /// `ShaderError::GeometryShadersUnsupported` if the context does not support geometry
+    /// shaders.
+    pub fn new_with_feedback(
+        vshader: &str,
+        gshader: &str,
+        fshader: &str,
+        varyings: &[&str],
+    ) -> Result<Effect, ShaderError> {
+        let version = Context::get()
+            .geometry_shader_glsl_version()
+            .ok_or(ShaderError::GeometryShadersUnsupported)?;
+        let vshader_source = mapped_source(
+            ShaderStage::Vertex,
+            (vshader, "vertex shader"),
+            Some(version),
+            &[],
+        )?;
+        let gshader_source = mapped_source(
+            ShaderStage::Geometry,
+            (gshader, "geometry shader"),
+            Some(version),
+            &[],
+        )?;
+        let fshader_source = mapped_source(
+            ShaderStage::Fragment,
+            (fshader, "fragment shader"),
+            Some(version),
+            &[],
+        )?;
+        let feedback = Feedback {
+            gshader_source,
+            varyings: varyings.iter().map(|v| v.to_string()).collect(),
+        };
+        let state = ProgramState::new(&vshader_source, &fshader_source, Some(&feedback))?;
+
+        Ok(Effect {
+            state: Rc::new(RefCell::new(state)),
+            vshader_source,
+            fshader_source,
+            feedback: Some(feedback),
+            uniform_blocks: Vec::new(),
+        })
+    }
 
-        if ctxt.get_error() == 0 {
-            if let Some(id) = location {
-                let data_type = PhantomData;
-                return Some(ShaderUniform { id, data_type });
-            }
+    /// Binds the uniform block `name` of the shader program to the uniform buffer binding point
+    /// `binding`.
+    ///
+    /// The binding is restored if the program is compiled again after a context loss. Returns
+    /// `false` if the program has no such uniform block.
+    pub fn bind_uniform_block(&mut self, name: &str, binding: u32) -> bool {
+        self.restore();
+
+        let bound = bind_uniform_block(&self.state.borrow().program, name, binding);
+
+        if bound {
+            self.uniform_blocks.retain(|(n, _)| n != name);
+            self.uniform_blocks.push((name.to_string(), binding));
         }
 
-        None
+        bound
+    }
+
+    /// Gets a uniform variable from the shader program.
+    pub fn get_uniform<T: GLPrimitive>(&self, name: &str) -> Option<ShaderUniform<T>> {
+        self.restore();
+        let id = uniform_location(&self.state.borrow().program, name)?;
+
+        Some(ShaderUniform {
+            id,
+            name: name.to_string(),
+            program: Rc::downgrade(&self.state),
+            generation: Context::generation(),
+            data_type: PhantomData,
+        })
     }
 
     /// Gets an attribute from the shader program.
     pub fn get_attrib<T: GLPrimitive>(&self, name: &str) -> Option<ShaderAttribute<T>> {
-        let ctxt = Context::get();
-        let location = ctxt.get_attrib_location(&self.program, name);
-
-        if ctxt.get_error() == 0 && location != -1 {
-            let id = location as u32;
-            let data_type = PhantomData;
-            return Some(ShaderAttribute { id, data_type });
-        }
-
-        None
+        self.restore();
+        let id = attrib_location(&self.state.borrow().program, name)?;
+
+        Some(ShaderAttribute {
+            id,
+            name: name.to_string(),
+            program: Rc::downgrade(&self.state),
+            generation: Context::generation(),
+            data_type: PhantomData,
+        })
     }
 
     /// Make this program active.
     pub fn use_program(&mut self) {
-        verify!(Context::get().use_program(Some(&self.program)));
+        self.restore();
+        verify!(Context::get().use_program(Some(&self.state.borrow().program)));
+    }
+
+    // Compiles the program again if it has been lost with the context.
+    fn restore(&self) {
+        let mut state = self.state.borrow_mut();
+
+        if state.generation != Context::generation() {
+            // The same sources compiled with the lost context.
+            *state = ProgramState::new(
+                &self.vshader_source,
+                &self.fshader_source,
+                self.feedback.as_ref(),
+            )
+            .unwrap_or_else(|e| panic!("Shader compilation failed: {}", e));
+
+            for (name, binding) in &self.uniform_blocks {
+                let _ = bind_uniform_block(&state.program, name, *binding);
+            }
+        }
+    }
+}
+
+impl ProgramState {
+    fn new(
+        vshader: &MappedSource,
+        fshader: &MappedSource,
+        feedback: Option<&Feedback>,
+    ) -> Result<ProgramState, ShaderError> {
+        let (program, vshader, gshader, fshader) = load_shader_program(vshader, fshader, feedback)?;
+
+        Ok(ProgramState {
+            program,
+            vshader,
+            gshader,
+            fshader,
+            generation: Context::generation(),
+        })
     }
 }
 
 impl Drop for Effect {
     fn drop(&mut self) {
+        let state = self.state.borrow();
+
+        if state.generation != Context::generation() {
+            // The program no longer exists.
+            return;
+        }
+
         let ctxt = Context::get();
-        if verify!(ctxt.is_program(Some(&self.program))) {
-            verify!(ctxt.delete_program(Some(&self.program)));
+        if verify!(ctxt.is_program(Some(&state.program))) {
+            verify!(ctxt.delete_program(Some(&state.program)));
+        }
+        if verify!(ctxt.is_shader(Some(&state.fshader))) {
+            verify!(ctxt.delete_shader(Some(&state.fshader)));
         }
-        if verify!(ctxt.is_shader(Some(&self.fshader))) {
-            verify!(ctxt.delete_shader(Some(&self.fshader)));
+        if let Some(gshader) = &state.gshader {
+            if verify!(ctxt.is_shader(Some(gshader))) {
+                verify!(ctxt.delete_shader(Some(gshader)));
+            }
         }
-        if verify!(ctxt.is_shader(Some(&self.vshader))) {
-            verify!(ctxt.delete_shader(Some(&self.vshader)));
+        if verify!(ctxt.is_shader(Some(&state.vshader))) {
+            verify!(ctxt.delete_shader(Some(&state.vshader)));
         }
     }
 }
@@ -104,31 +311,83 @@ impl Drop for Effect {
 /// Structure encapsulating an uniform variable.
 pub struct ShaderUniform<T> {
     id: UniformLocation,
+    name: String,
+    program: Weak<RefCell<ProgramState>>,
+    generation: u64,
     data_type: PhantomData<T>,
 }
 
 impl<T: GLPrimitive> ShaderUniform<T> {
     /// Upload a value to this variable.
     pub fn upload(&mut self, value: &T) {
+        if self.generation != Context::generation() {
+            self.relocate();
+        }
+
         value.upload(&self.id)
     }
+
+    // Finds the location of this variable in the program compiled again after a context loss.
+    fn relocate(&mut self) {
+        if let Some(state) = self.program.upgrade() {
+            let state = state.borrow();
+
+            if state.generation == Context::generation() {
+                if let Some(id) = uniform_location(&state.program, &self.name) {
+                    self.id = id;
+                    self.generation = state.generation;
+                }
+            }
+        }
+    }
 }
 
 /// Structure encapsulating an attribute.
 pub struct ShaderAttribute<T> {
     id: u32,
+    name: String,
+    program: Weak<RefCell<ProgramState>>,
+    generation: u64,
     data_type: PhantomData<T>,
 }
 
 impl<T: GLPrimitive> ShaderAttribute<T> {
     /// Disable this attribute.
     pub fn disable(&mut self) {
-        verify!(Context::get().disable_vertex_attrib_array(self.id));
+        verify!(Context::get().disable_vertex_attrib_array(self.id()));
     }
 
     /// Enable this attribute.
     pub fn enable(&mut self) {
-        verify!(Context::get().enable_vertex_attrib_array(self.id));
+        verify!(Context::get().enable_vertex_attrib_array(self.id()));
+    }
+
+    // The location of this attribute, found again if the program has been compiled again after
+    // a context loss.
+    fn id(&mut self) -> u32 {
+        if self.generation != Context::generation() {
+            if let Some(state) = self.program.upgrade() {
+                let state = state.borrow();
+
+                if state.generation == Context::generation() {
+                    if let Some(id) = attrib_location(&state.program, &self.name) {
+                        self.id = id;
+                        self.generation = state.generation;
+                    }
+                }
+            }
+        }
+
+        self.id
+    }
+
+    /// Sets the number of instances drawn with the same value of this attribute, or `0` for an
+    /// attribute read once per vertex.
+    ///
+    /// This requires `Context::supports_instancing`. The divisor is a state of the attribute
+    /// location, so it should be set back to `0` after the instanced draw calls.
+    pub fn set_divisor(&mut self, divisor: u32) {
+        verify!(Context::get().vertex_attrib_divisor(self.id(), divisor));
     }
 
     /// Binds this attribute to a gpu vector.
@@ -136,7 +395,7 @@ impl<T: GLPrimitive> ShaderAttribute<T> {
         vector.bind();
 
         verify!(Context::get().vertex_attrib_pointer(
-            self.id,
+            self.id(),
             T::size() as i32,
             T::GLTYPE,
             false,
@@ -145,6 +404,29 @@ impl<T: GLPrimitive> ShaderAttribute<T> {
         ));
     }
 
+    /// Binds this attribute to a gpu vector of raw bytes, with components in the given format.
+    ///
+    /// The `stride` between two consecutive attributes, and the `offset` of the first one, are
+    /// given in bytes.
+    pub fn bind_packed(
+        &mut self,
+        vector: &mut GPUVec<u8>,
+        format: VertexFormat,
+        stride: usize,
+        offset: usize,
+    ) {
+        vector.bind();
+
+        verify!(Context::get().vertex_attrib_pointer(
+            self.id(),
+            T::size() as i32,
+            format.gl_type(),
+            format.is_normalized(),
+            stride as i32,
+            offset as GLintptr
+        ));
+    }
+
     /// Binds this attribute to non contiguous parts of a gpu vector.
     pub fn bind_sub_buffer(&mut self, vector: &mut GPUVec<T>, strides: usize, start_index: usize) {
         unsafe { self.bind_sub_buffer_generic(vector, strides, start_index) }
@@ -162,7 +444,7 @@ impl<T: GLPrimitive> ShaderAttribute<T> {
         vector.bind();
 
         verify!(Context::get().vertex_attrib_pointer(
-            self.id,
+            self.id(),
             T::size() as i32,
             T::GLTYPE,
             false,
@@ -172,48 +454,195 @@ impl<T: GLPrimitive> ShaderAttribute<T> {
     }
 }
 
-/// Loads a shader program using the given source codes for the vertex and fragment shader.
+// Resolves the includes of a shader given with the name of its file, and inserts the lines of
+// `header` after its `#version` directive.
+fn mapped_source(
+    stage: ShaderStage,
+    (source, origin): (&str, &str),
+    version: Option<&str>,
+    header: &[String],
+) -> Result<MappedSource, ShaderError> {
+    let mut mapped =
+        MappedSource::resolve(source, origin).map_err(|e| ShaderError::UnknownInclude {
+            stage,
+            name: e.name,
+            location: e.at.to_string(),
+        })?;
+
+    mapped.insert_header(version, header);
+    Ok(mapped)
+}
+
+// The lines converting a GLSL ES 1.0 shader to a GLSL version supporting uniform blocks, by
+// redefining the keywords and built-ins that have been renamed.
+fn glsl3_header(fragment: bool, defines: &[&str]) -> Vec<String> {
+    let mut header = vec![
+        "#define UNIFORM_BUFFERS".to_string(),
+        "#define texture2D texture".to_string(),
+        "#define textureCube texture".to_string(),
+    ];
+
+    if fragment {
+        header.push("#define varying in".to_string());
+        header.push("out highp vec4 kiss3d_frag_color;".to_string());
+        header.push("#define gl_FragColor kiss3d_frag_color".to_string());
+    } else {
+        header.push("#define attribute in".to_string());
+        header.push("#define varying out".to_string());
+    }
+
+    header.extend(defines.iter().map(|d| format!("#define {}", d)));
+    header
+}
+
+fn bind_uniform_block(program: &Program, name: &str, binding: u32) -> bool {
+    let ctxt = Context::get();
+
+    match ctxt.get_uniform_block_index(program, name) {
+        Some(index) => {
+            verify!(ctxt.uniform_block_binding(program, index, binding));
+            true
+        }
+        None => false,
+    }
+}
+
+fn uniform_location(program: &Program, name: &str) -> Option<UniformLocation> {
+    let ctxt = Context::get();
+    let location = ctxt.get_uniform_location(program, name);
+
+    if ctxt.get_error() == 0 {
+        location
+    } else {
+        None
+    }
+}
+
+fn attrib_location(program: &Program, name: &str) -> Option<u32> {
+    let ctxt = Context::get();
+    let location = ctxt.get_attrib_location(program, name);
+
+    if ctxt.get_error() == 0 && location != -1 {
+        Some(location as u32)
+    } else {
+        None
+    }
+}
+
+/// Loads a shader program using the given source codes for the vertex and fragment shader, and
+/// the geometry shader and captured outputs of a transform feedback program.
 ///
-/// Fails after displaying opengl compilation errors if the shaders are invalid.
-fn load_shader_program(vertex_shader: &str, fragment_shader: &str) -> (Program, Shader, Shader) {
-    // Create and compile the vertex shader
+/// Fails with the compilation or link log if the shaders are invalid.
+fn load_shader_program(
+    vertex_shader: &MappedSource,
+    fragment_shader: &MappedSource,
+    feedback: Option<&Feedback>,
+) -> Result<(Program, Shader, Option<Shader>, Shader), ShaderError> {
     let ctxt = Context::get();
-    let vshader = verify!(ctxt
-        .create_shader(Context::VERTEX_SHADER)
-        .expect("Could not create vertex shader."));
-
-    verify!(ctxt.shader_source(&vshader, vertex_shader));
-    verify!(ctxt.compile_shader(&vshader));
-    check_shader_error(&vshader);
-
-    // Create and compile the fragment shader
-    let fshader = verify!(ctxt
-        .create_shader(Context::FRAGMENT_SHADER)
-        .expect("Could not create fragment shader."));
-    verify!(ctxt.shader_source(&fshader, fragment_shader));
-    verify!(ctxt.compile_shader(&fshader));
-    check_shader_error(&fshader);
-
-    // Link the vertex and fragment shader into a shader program
+    let vshader = compile_shader(Context::VERTEX_SHADER, ShaderStage::Vertex, vertex_shader)?;
+    let gshader = match feedback {
+        Some(feedback) => {
+            match compile_shader(
+                Context::GEOMETRY_SHADER,
+                ShaderStage::Geometry,
+                &feedback.gshader_source,
+            ) {
+                Ok(gshader) => Some(gshader),
+                Err(e) => {
+                    verify!(ctxt.delete_shader(Some(&vshader)));
+                    return Err(e);
+                }
+            }
+        }
+        None => None,
+    };
+    let delete_shaders = |shaders: &[Option<&Shader>]| {
+        for shader in shaders.iter().flatten() {
+            verify!(ctxt.delete_shader(Some(shader)));
+        }
+    };
+
+    let fshader = match compile_shader(
+        Context::FRAGMENT_SHADER,
+        ShaderStage::Fragment,
+        fragment_shader,
+    ) {
+        Ok(fshader) => fshader,
+        Err(e) => {
+            delete_shaders(&[gshader.as_ref(), Some(&vshader)]);
+            return Err(e);
+        }
+    };
+
+    // Link the shaders into a shader program
     let program = verify!(ctxt.create_program().expect("Could not create program."));
     verify!(ctxt.attach_shader(&program, &vshader));
     verify!(ctxt.attach_shader(&program, &fshader));
+
+    if let (Some(gshader), Some(feedback)) = (&gshader, feedback) {
+        let varyings: Vec<&str> = feedback.varyings.iter().map(|v| v.as_str()).collect();
+        verify!(ctxt.attach_shader(&program, gshader));
+        verify!(ctxt.transform_feedback_varyings(
+            &program,
+            &varyings,
+            Context::INTERLEAVED_ATTRIBS
+        ));
+    }
+
     verify!(ctxt.link_program(&program));
-    (program, vshader, fshader)
+
+    if ctxt.get_program_parameter_int(&program, Context::LINK_STATUS) == Some(0) {
+        let log = ctxt.get_program_info_log(&program).unwrap_or_default();
+
+        verify!(ctxt.delete_program(Some(&program)));
+        delete_shaders(&[Some(&fshader), gshader.as_ref(), Some(&vshader)]);
+        return Err(ShaderError::Link { log });
+    }
+
+    Ok((program, vshader, gshader, fshader))
+}
+
+/// Creates and compiles a shader of the given type.
+fn compile_shader(
+    gl_type: u32,
+    stage: ShaderStage,
+    source: &MappedSource,
+) -> Result<Shader, ShaderError> {
+    let ctxt = Context::get();
+    let shader = verify!(ctxt
+        .create_shader(gl_type)
+        .unwrap_or_else(|| panic!("Could not create {}.", stage)));
+
+    verify!(ctxt.shader_source(&shader, &source.text()));
+    verify!(ctxt.compile_shader(&shader));
+
+    if let Err(e) = check_shader_error(&shader, stage, source) {
+        verify!(ctxt.delete_shader(Some(&shader)));
+        return Err(e);
+    }
+
+    Ok(shader)
 }
 
 /// Checks if a shader handle is valid.
 ///
-/// If it is not valid, it fails with a descriptive error message.
-fn check_shader_error(shader: &Shader) {
+/// If it is not valid, it fails with the compilation log mapped to the original sources.
+fn check_shader_error(
+    shader: &Shader,
+    stage: ShaderStage,
+    source: &MappedSource,
+) -> Result<(), ShaderError> {
     let ctxt = Context::get();
     let compiles = ctxt.get_shader_parameter_int(shader, Context::COMPILE_STATUS);
 
     if compiles == Some(0) {
-        if let Some(log) = ctxt.get_shader_info_log(shader) {
-            panic!("Shader compilation failed: {}", log);
-        } else {
-            println!("Shader compilation failed.");
-        }
+        let log = ctxt.get_shader_info_log(shader).unwrap_or_default();
+
+        Err(ShaderError::Compilation {
+            stage,
+            log: map_compilation_log(&log, source),
+        })
+    } else {
+        Ok(())
     }
 }
@@ -46,6 +46,11 @@ unsafe impl GLPrimitive for f32 {
     }
 }
 
+unsafe impl GLPrimitive for u8 {
+    type Element = u8;
+    const GLTYPE: u32 = Context::UNSIGNED_BYTE;
+}
+
 unsafe impl GLPrimitive for i32 {
     type Element = i32;
     const GLTYPE: u32 = Context::INT;
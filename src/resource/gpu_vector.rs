@@ -13,22 +13,65 @@ pub struct GPUVec<T> {
     len: usize,
     buf_type: BufferType,
     alloc_type: AllocationType,
-    buffer: Option<(usize, Buffer)>,
+    update_strategy: UpdateStrategy,
+    // The gpu buffers, with the number of elements each one can hold. There is more than one
+    // buffer only with the `Ring` update strategy.
+    buffers: Vec<(usize, Buffer)>,
+    current: usize,
     data: Option<Vec<T>>,
+    version: u64,
+    // The context generation the gpu buffers have been created with.
+    generation: u64,
 }
 
 // FIXME: implement Clone
 impl<T: GLPrimitive> GPUVec<T> {
     /// Creates a new `GPUVec` that is not yet uploaded to the GPU.
+    ///
+    /// Vectors allocated with `AllocationType::StreamDraw` use the `UpdateStrategy::Orphan`
+    /// update strategy. Other vectors are updated in place.
     pub fn new(data: Vec<T>, buf_type: BufferType, alloc_type: AllocationType) -> GPUVec<T> {
+        let update_strategy = match alloc_type {
+            AllocationType::StreamDraw => UpdateStrategy::Orphan,
+            _ => UpdateStrategy::InPlace,
+        };
+
         GPUVec {
             trash: true,
             len: data.len(),
             buf_type,
             alloc_type,
-            buffer: None,
+            update_strategy,
+            buffers: Vec::new(),
+            current: 0,
             data: Some(data),
+            version: 0,
+            generation: Context::generation(),
+        }
+    }
+
+    /// The allocation type of the gpu buffer of this vector.
+    #[inline]
+    pub fn allocation_type(&self) -> AllocationType {
+        self.alloc_type
+    }
+
+    /// The strategy used to upload the modified data of this vector to the GPU.
+    #[inline]
+    pub fn update_strategy(&self) -> UpdateStrategy {
+        self.update_strategy
+    }
+
+    /// Sets the strategy used to upload the modified data of this vector to the GPU.
+    ///
+    /// This unloads this vector from the GPU if the number of gpu buffers it needs changes.
+    pub fn set_update_strategy(&mut self, strategy: UpdateStrategy) {
+        if strategy.num_buffers() != self.update_strategy.num_buffers() && self.is_on_gpu() {
+            self.unload_from_gpu();
+            self.trash = true;
         }
+
+        self.update_strategy = strategy;
     }
 
     /// The length of this vector.
@@ -50,6 +93,7 @@ impl<T: GLPrimitive> GPUVec<T> {
     #[inline]
     pub fn data_mut(&mut self) -> &mut Option<Vec<T>> {
         self.trash = true;
+        self.version = self.version.wrapping_add(1);
 
         &mut self.data
     }
@@ -60,10 +104,19 @@ impl<T: GLPrimitive> GPUVec<T> {
         &self.data
     }
 
+    /// A counter incremented each time the data of this vector is mutably accessed.
+    ///
+    /// This can be used to detect that the content of this vector may have changed, e.g., to
+    /// invalidate data derived from it.
+    #[inline]
+    pub fn version(&self) -> u64 {
+        self.version
+    }
+
     /// Returns `true` if this vector is already uploaded to the GPU.
     #[inline]
     pub fn is_on_gpu(&self) -> bool {
-        self.buffer.is_some()
+        !self.buffers.is_empty()
     }
 
     /// Returns `true` if the cpu data and gpu data are out of sync.
@@ -83,24 +136,58 @@ impl<T: GLPrimitive> GPUVec<T> {
     /// Loads the vector from the RAM to the GPU.
     ///
     /// If the vector is not available on RAM or already loaded to the GPU, nothing will happen.
+    /// If the gpu buffers have been lost with the context, they are uploaded again.
     #[inline]
     pub fn load_to_gpu(&mut self) {
+        if self.is_on_gpu() && self.generation != Context::generation() {
+            // The buffers no longer exist: don't try to delete them.
+            for (_, buffer) in self.buffers.drain(..) {
+                std::mem::forget(buffer);
+            }
+        }
+
         if !self.is_on_gpu() {
-            let buf_type = self.buf_type;
-            let alloc_type = self.alloc_type;
-            let len = &mut self.len;
-
-            self.buffer = self.data.as_ref().map(|d| {
-                *len = d.len();
-                (d.len(), upload_array(&d[..], buf_type, alloc_type))
-            });
+            if let Some(ref d) = self.data {
+                self.len = d.len();
+                self.current = 0;
+                self.generation = Context::generation();
+                self.buffers.push((
+                    d.len(),
+                    upload_array(&d[..], self.buf_type, self.alloc_type),
+                ));
+            }
         } else if self.trash() {
-            for d in self.data.iter() {
+            if let Some(ref d) = self.data {
                 self.len = d.len();
 
-                if let Some((ref mut len, ref buffer)) = self.buffer {
-                    *len = update_buffer(&d[..], *len, buffer, self.buf_type, self.alloc_type)
-                }
+                let orphan = match self.update_strategy {
+                    UpdateStrategy::InPlace => false,
+                    UpdateStrategy::Orphan => true,
+                    UpdateStrategy::Ring(n) => {
+                        // Write to the next buffer of the ring, while the GPU may still be
+                        // reading the previous ones.
+                        self.current = (self.current + 1) % n.max(1);
+
+                        if self.current == self.buffers.len() {
+                            let buf = upload_array(&d[..], self.buf_type, self.alloc_type);
+                            self.buffers.push((d.len(), buf));
+                            self.trash = false;
+                            return;
+                        }
+
+                        true
+                    }
+                };
+
+                let (ref mut len, ref buffer) = self.buffers[self.current];
+                *len = update_buffer_with(
+                    &d[..],
+                    *len,
+                    buffer,
+                    self.buf_type,
+                    self.alloc_type,
+                    orphan,
+                );
             }
         }
 
@@ -114,10 +201,80 @@ impl<T: GLPrimitive> GPUVec<T> {
     pub fn bind(&mut self) {
         self.load_to_gpu();
 
-        let buffer = self.buffer.as_ref().map(|e| &e.1);
+        let buffer = self.buffers.get(self.current).map(|e| &e.1);
         verify!(Context::get().bind_buffer(self.buf_type.to_gl(), buffer));
     }
 
+    /// Overwrites the content of the gpu buffer of this vector directly, without going through
+    /// its RAM copy.
+    ///
+    /// The vector is uploaded first if needed, and its gpu buffer is mapped for writing only:
+    /// the content of the slice given to `f` is unspecified, so `f` must write all its elements.
+    /// The RAM copy, if any, is left unchanged and overwrites the gpu buffer again the next time
+    /// it is mutably accessed.
+    ///
+    /// If the context cannot map buffers, e.g. with WebGL, `f` writes to the RAM copy instead,
+    /// which is then uploaded. Returns `false`, without calling `f`, if the vector is neither on
+    /// the GPU nor on RAM, or if its buffer cannot be mapped.
+    pub fn write_on_gpu<F: FnOnce(&mut [T])>(&mut self, f: F) -> bool {
+        let ctxt = Context::get();
+
+        if !ctxt.supports_buffer_mapping() {
+            return match self.data_mut() {
+                Some(data) => {
+                    f(&mut data[..]);
+                    self.load_to_gpu();
+                    true
+                }
+                None => false,
+            };
+        }
+
+        self.load_to_gpu();
+
+        let buffer = match self.buffers.get(self.current) {
+            Some((_, buffer)) => buffer,
+            None => return false,
+        };
+
+        if self.len == 0 {
+            f(&mut []);
+            return true;
+        }
+
+        let target = self.buf_type.to_gl();
+        verify!(ctxt.bind_buffer(target, Some(buffer)));
+
+        unsafe {
+            let ptr = verify!(ctxt.map_buffer_range(
+                target,
+                0,
+                self.len * size_of::<T>(),
+                Context::MAP_WRITE_BIT | Context::MAP_INVALIDATE_BUFFER_BIT
+            ));
+
+            if ptr.is_null() {
+                return false;
+            }
+
+            // Mapped buffers are aligned to at least 64 bytes.
+            f(std::slice::from_raw_parts_mut(ptr as *mut T, self.len));
+            verify!(ctxt.unmap_buffer(target));
+        }
+
+        true
+    }
+
+    /// Binds this vector to the indexed binding point `index` of the appropriate gpu target,
+    /// e.g., to a uniform buffer binding point.
+    #[inline]
+    pub fn bind_base(&mut self, index: u32) {
+        self.load_to_gpu();
+
+        let buffer = self.buffers.get(self.current).map(|e| &e.1);
+        verify!(Context::get().bind_buffer_base(self.buf_type.to_gl(), index, buffer));
+    }
+
     /// Unbind this vector to the corresponding gpu buffer.
     #[inline]
     pub fn unbind(&mut self) {
@@ -146,12 +303,13 @@ impl<T: GLPrimitive> GPUVec<T> {
     /// Unloads this resource from the GPU.
     #[inline]
     pub fn unload_from_gpu(&mut self) {
-        let _ = self
-            .buffer
-            .as_ref()
-            .map(|&(_, ref h)| unsafe { verify!(Context::get().delete_buffer(Some(h))) });
+        for (_, h) in self.buffers.iter() {
+            unsafe { verify!(Context::get().delete_buffer(Some(h))) }
+        }
+
         self.len = self.len();
-        self.buffer = None;
+        self.buffers.clear();
+        self.current = 0;
         self.trash = false;
     }
 
@@ -165,6 +323,7 @@ impl<T: GLPrimitive> GPUVec<T> {
         }
 
         self.data = None;
+        self.version = self.version.wrapping_add(1);
     }
 }
 
@@ -186,6 +345,10 @@ pub enum BufferType {
     Array,
     /// An array buffer bindable to a gl::ELEMENT_ARRAY_BUFFER.
     ElementArray,
+    /// A buffer bindable to a gl::UNIFORM_BUFFER, holding the values of a uniform block.
+    Uniform,
+    /// A buffer bindable to a gl::TRANSFORM_FEEDBACK_BUFFER, recording the outputs of a shader.
+    TransformFeedback,
 }
 
 impl BufferType {
@@ -194,6 +357,8 @@ impl BufferType {
         match *self {
             BufferType::Array => Context::ARRAY_BUFFER,
             BufferType::ElementArray => Context::ELEMENT_ARRAY_BUFFER,
+            BufferType::Uniform => Context::UNIFORM_BUFFER,
+            BufferType::TransformFeedback => Context::TRANSFORM_FEEDBACK_BUFFER,
         }
     }
 }
@@ -220,6 +385,31 @@ impl AllocationType {
     }
 }
 
+/// Strategy used to upload the modified content of a `GPUVec` to the GPU.
+///
+/// Overwriting a buffer still in use by the GPU may stall until the GPU is done reading it. The
+/// `Orphan` and `Ring` strategies avoid this stall for data modified every frame.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum UpdateStrategy {
+    /// The content of the gpu buffer is overwritten in place.
+    InPlace,
+    /// The storage of the gpu buffer is orphaned before each update, so that the driver can
+    /// allocate a new storage while the GPU keeps reading the old one.
+    Orphan,
+    /// The updates are written to a ring of the given number of gpu buffers, each one being
+    /// orphaned before reuse.
+    Ring(usize),
+}
+
+impl UpdateStrategy {
+    fn num_buffers(&self) -> usize {
+        match *self {
+            UpdateStrategy::Ring(n) => n.max(1),
+            _ => 1,
+        }
+    }
+}
+
 /// Allocates and uploads a buffer to the gpu.
 #[inline]
 pub fn upload_array<T: GLPrimitive>(
@@ -244,7 +434,7 @@ pub fn upload_array<T: GLPrimitive>(
 //         verify!(gl::GetBufferSubData(
 //             buf_type.to_gl(),
 //             0,
-//             (out.len() * mem::size_of::<T>()) as GLsizeiptr,
+//             (out.len() * size_of::<T>()) as GLsizeiptr,
 //             mem::transmute(&out[0])
 //         ));
 //     }
@@ -260,17 +450,59 @@ pub fn update_buffer<T: GLPrimitive>(
     gpu_buf: &Buffer,
     gpu_buf_type: BufferType,
     gpu_allocation_type: AllocationType,
+) -> usize {
+    update_buffer_with(
+        arr,
+        gpu_buf_len,
+        gpu_buf,
+        gpu_buf_type,
+        gpu_allocation_type,
+        false,
+    )
+}
+
+// Updates a buffer to the gpu, possibly orphaning its previous storage first.
+//
+// When orphaning, the capacity of the buffer grows to the next power of two when it is too
+// small, so that buffers filled with a slowly increasing amount of data are not reallocated
+// every frame.
+fn update_buffer_with<T: GLPrimitive>(
+    arr: &[T],
+    gpu_buf_len: usize,
+    gpu_buf: &Buffer,
+    gpu_buf_type: BufferType,
+    gpu_allocation_type: AllocationType,
+    orphan: bool,
 ) -> usize {
     unsafe {
         let ctxt = Context::get();
+        let target = gpu_buf_type.to_gl();
 
-        verify!(ctxt.bind_buffer(gpu_buf_type.to_gl(), Some(gpu_buf)));
+        verify!(ctxt.bind_buffer(target, Some(gpu_buf)));
+
+        if orphan {
+            let capacity = if arr.len() <= gpu_buf_len {
+                gpu_buf_len
+            } else {
+                arr.len().next_power_of_two()
+            };
+
+            verify!(ctxt.buffer_data_uninitialized(
+                target,
+                capacity * size_of::<T>(),
+                gpu_allocation_type.to_gl()
+            ));
+
+            if !arr.is_empty() {
+                verify!(ctxt.buffer_sub_data(target, 0, arr));
+            }
 
-        if arr.len() < gpu_buf_len {
-            verify!(ctxt.buffer_sub_data(gpu_buf_type.to_gl(), 0, arr));
+            capacity
+        } else if arr.len() < gpu_buf_len {
+            verify!(ctxt.buffer_sub_data(target, 0, arr));
             gpu_buf_len
         } else {
-            verify!(ctxt.buffer_data(gpu_buf_type.to_gl(), arr, gpu_allocation_type.to_gl()));
+            verify!(ctxt.buffer_data(target, arr, gpu_allocation_type.to_gl()));
             arr.len()
         }
     }
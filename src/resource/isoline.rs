@@ -0,0 +1,134 @@
+//! Extraction of the isolines of a scalar field defined on the vertices of a mesh.
+
+use std::collections::HashMap;
+
+use na::{Point3, Vector3};
+
+// The distance the isolines are moved along the surface normal, relative to the diagonal of the
+// bounding box of the mesh, so that they are not hidden by the surface.
+const SURFACE_OFFSET: f32 = 1.0e-3;
+
+/// A polyline along which a scalar field defined on a surface has a constant value.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Isoline {
+    /// The value of the field along this line.
+    pub value: f32,
+    /// The points of this line. The first point is repeated at the end of closed lines.
+    pub points: Vec<Point3<f32>>,
+}
+
+// Intersects the triangles with the level sets of the per-vertex field, and chains the resulting
+// segments into polylines.
+pub(crate) fn extract(
+    coords: &[Point3<f32>],
+    normals: &[Vector3<f32>],
+    faces: &[Point3<u16>],
+    scalars: &[f32],
+    values: &[f32],
+) -> Vec<Isoline> {
+    assert_eq!(
+        scalars.len(),
+        coords.len(),
+        "There must be exactly one scalar value per vertex."
+    );
+
+    let offset = match coords.split_first() {
+        Some((first, rest)) => {
+            let (mins, maxs) = rest.iter().fold((*first, *first), |(mins, maxs), p| {
+                (mins.inf(p), maxs.sup(p))
+            });
+            na::distance(&mins, &maxs) * SURFACE_OFFSET
+        }
+        None => return Vec::new(),
+    };
+    let mut isolines = Vec::new();
+
+    for &value in values {
+        // The points where the line crosses the edges of the mesh, and their neighbors.
+        let mut crossings: HashMap<(u16, u16), usize> = HashMap::new();
+        let mut points = Vec::new();
+        let mut neighbors: Vec<Vec<usize>> = Vec::new();
+
+        for face in faces {
+            let mut ends = Vec::with_capacity(2);
+
+            for k in 0..3 {
+                let (a, b) = (face[k], face[(k + 1) % 3]);
+                let (sa, sb) = (scalars[a as usize], scalars[b as usize]);
+
+                if (sa >= value) == (sb >= value) {
+                    continue;
+                }
+
+                let key = (a.min(b), a.max(b));
+                let id = *crossings.entry(key).or_insert_with(|| {
+                    let (i, j) = (key.0 as usize, key.1 as usize);
+                    let t = (value - scalars[i]) / (scalars[j] - scalars[i]);
+                    let normal = normals
+                        .get(i)
+                        .zip(normals.get(j))
+                        .and_then(|(ni, nj)| ni.lerp(nj, t).try_normalize(1.0e-12))
+                        .unwrap_or_else(Vector3::zeros);
+
+                    points.push(coords[i] + (coords[j] - coords[i]) * t + normal * offset);
+                    neighbors.push(Vec::new());
+                    points.len() - 1
+                });
+
+                ends.push(id);
+            }
+
+            // A triangle is crossed on either zero or two of its edges.
+            if let [a, b] = ends[..] {
+                neighbors[a].push(b);
+                neighbors[b].push(a);
+            }
+        }
+
+        let mut visited = vec![false; points.len()];
+
+        // The open lines start at a point with a single neighbor, e.g., on the mesh border.
+        let starts = (0..points.len())
+            .filter(|&i| neighbors[i].len() == 1)
+            .chain(0..points.len());
+
+        for start in starts {
+            if visited[start] || neighbors[start].is_empty() {
+                continue;
+            }
+
+            let mut line = vec![points[start]];
+            let mut prev = None;
+            let mut curr = start;
+            visited[start] = true;
+
+            loop {
+                let next = neighbors[curr]
+                    .iter()
+                    .copied()
+                    .find(|&n| Some(n) != prev && (!visited[n] || (n == start && line.len() > 2)));
+
+                match next {
+                    Some(n) if n == start => {
+                        line.push(points[start]);
+                        break;
+                    }
+                    Some(n) => {
+                        visited[n] = true;
+                        line.push(points[n]);
+                        prev = Some(curr);
+                        curr = n;
+                    }
+                    None => break,
+                }
+            }
+
+            isolines.push(Isoline {
+                value,
+                points: line,
+            });
+        }
+    }
+
+    isolines
+}
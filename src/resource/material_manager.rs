@@ -1,6 +1,8 @@
 //! A resource manager to load materials.
 
-use crate::builtin::{NormalsMaterial, ObjectMaterial, UvsMaterial};
+use crate::builtin::{
+    DebugMaterial, NormalsMaterial, ObjectMaterial, UvsMaterial, VertexColorMaterial,
+};
 use crate::resource::Material;
 use std::cell::RefCell;
 use std::collections::HashMap;
@@ -11,6 +13,9 @@ use std::rc::Rc;
 /// Upon construction, it contains:
 /// * the `object` material, used as the default to render objects.
 /// * the `normals` material, used do display an object normals.
+/// * the `uvs` material, used do display an object texture coordinates.
+/// * the `vertex_color` material, used to display an object per-vertex colors.
+/// * the `debug` material, used to display the `DebugVisualization` of an object.
 ///
 /// It keeps a cache of already-loaded materials. Note that this is only a cache, nothing more.
 /// Thus, its usage is not required to load materials.
@@ -40,6 +45,16 @@ impl MaterialManager {
         ));
         let _ = materials.insert("uvs".to_string(), um.clone());
 
+        let vcm = Rc::new(RefCell::new(
+            Box::new(VertexColorMaterial::new()) as Box<dyn Material + 'static>
+        ));
+        let _ = materials.insert("vertex_color".to_string(), vcm.clone());
+
+        let dm = Rc::new(RefCell::new(
+            Box::new(DebugMaterial::new()) as Box<dyn Material + 'static>
+        ));
+        let _ = materials.insert("debug".to_string(), dm.clone());
+
         MaterialManager {
             default_material: om,
             materials,
@@ -3,14 +3,40 @@ use std::iter;
 use std::sync::{Arc, RwLock};
 
 use crate::resource::gpu_vector::{AllocationType, BufferType, GPUVec};
-use crate::resource::ShaderAttribute;
-use na::{self, Point2, Point3, Vector3};
+use crate::resource::isoline::{self, Isoline};
+use crate::resource::{
+    GLPrimitive, MeshBvh, MeshLoadOptions, ShaderAttribute, VertexFormat, MAX_JOINTS,
+};
+use na::{self, Point2, Point3, Vector3, Vector4};
+use ncollide3d::bounding_volume::AABB;
 use ncollide3d::procedural::{IndexBuffer, TriMesh};
 use num::Zero;
 
 #[path = "../error.rs"]
 mod error;
 
+// The hierarchy, with the versions of the vertices and faces it has been built from.
+type BvhCache = Option<((u64, u64), Arc<MeshBvh>)>;
+// The bounding box, with the version of the vertices it has been computed from.
+type AabbCache = Option<(u64, AABB<f32>)>;
+// The indices of the joints influencing each vertex of a skinned mesh, and their weights.
+type SkinBuffers = (
+    Arc<RwLock<GPUVec<Vector4<f32>>>>,
+    Arc<RwLock<GPUVec<Vector4<f32>>>>,
+);
+
+// A gpu buffer storing some vertex attributes of a mesh with a reduced precision, or interleaved.
+struct PackedBuffer {
+    data: GPUVec<u8>,
+    stride: usize,
+    // The format and offset of the vertices, normals, and texture coordinates, if they are stored
+    // by this buffer.
+    attributes: [Option<(VertexFormat, usize)>; 3],
+    // The versions of the vertices, normals, and texture coordinates this buffer has been built
+    // from.
+    versions: Option<[u64; 3]>,
+}
+
 /// Aggregation of vertices, indices, normals and texture coordinates.
 ///
 /// It also contains the GPU location of those buffers.
@@ -19,7 +45,14 @@ pub struct Mesh {
     faces: Arc<RwLock<GPUVec<Point3<u16>>>>,
     normals: Arc<RwLock<GPUVec<Vector3<f32>>>>,
     uvs: Arc<RwLock<GPUVec<Point2<f32>>>>,
+    colors: Option<Arc<RwLock<GPUVec<Point3<f32>>>>>,
+    lightmap_uvs: Option<Arc<RwLock<GPUVec<Point2<f32>>>>>,
+    skin: Option<SkinBuffers>,
     edges: Option<Arc<RwLock<GPUVec<Point2<u16>>>>>,
+    bvh: RwLock<BvhCache>,
+    aabb: RwLock<AabbCache>,
+    load_options: MeshLoadOptions,
+    packed: Vec<Arc<RwLock<PackedBuffer>>>,
 }
 
 impl Mesh {
@@ -96,6 +129,33 @@ impl Mesh {
         )
     }
 
+    /// Creates a new mesh, stored on the GPU as specified by `options`.
+    ///
+    /// If the normals and uvs are not given, they are automatically computed.
+    pub fn new_with_options(
+        coords: Vec<Point3<f32>>,
+        faces: Vec<Point3<u16>>,
+        normals: Option<Vec<Vector3<f32>>>,
+        uvs: Option<Vec<Point2<f32>>>,
+        dynamic_draw: bool,
+        options: &MeshLoadOptions,
+    ) -> Mesh {
+        let mut mesh = Mesh::new(coords, faces, normals, uvs, dynamic_draw);
+        mesh.set_load_options(options);
+        mesh
+    }
+
+    /// Creates a new mesh from a mesh descr, stored on the GPU as specified by `options`.
+    pub fn from_trimesh_with_options(
+        mesh: TriMesh<f32>,
+        dynamic_draw: bool,
+        options: &MeshLoadOptions,
+    ) -> Mesh {
+        let mut mesh = Mesh::from_trimesh(mesh, dynamic_draw);
+        mesh.set_load_options(options);
+        mesh
+    }
+
     // XXX: The `load_to_ram` require WebGL 2.
     /// Creates a triangle mesh from this mesh.
     ///
@@ -186,23 +246,229 @@ impl Mesh {
             faces,
             normals,
             uvs,
+            colors: None,
+            lightmap_uvs: None,
+            skin: None,
             edges: None,
+            bvh: RwLock::new(None),
+            aabb: RwLock::new(None),
+            load_options: MeshLoadOptions::new(),
+            packed: Vec::new(),
+        }
+    }
+
+    /// The options specifying how this mesh is stored on the GPU.
+    pub fn load_options(&self) -> &MeshLoadOptions {
+        &self.load_options
+    }
+
+    /// Changes how this mesh is stored on the GPU.
+    ///
+    /// With reduced-precision formats or interleaving, the vertex attributes are packed into
+    /// separate gpu buffers, rebuilt each time the vertices, normals, or texture coordinates of
+    /// this mesh are modified.
+    pub fn set_load_options(&mut self, options: &MeshLoadOptions) {
+        let alloc_type = self.coords.read().unwrap().allocation_type();
+        let layout = [
+            (VertexFormat::Float32, 3),
+            (options.normals, 3),
+            (options.uvs, 2),
+        ];
+        let new_buffer = |stride, attributes| {
+            Arc::new(RwLock::new(PackedBuffer {
+                data: GPUVec::new(Vec::new(), BufferType::Array, alloc_type),
+                stride,
+                attributes,
+                versions: None,
+            }))
+        };
+
+        self.load_options = *options;
+        self.packed.clear();
+
+        if options.interleaved {
+            let mut attributes = [None; 3];
+            let mut stride = 0;
+
+            for (i, (format, ncomponents)) in layout.iter().enumerate() {
+                attributes[i] = Some((*format, stride));
+                stride += format.attribute_size(*ncomponents);
+            }
+
+            self.packed.push(new_buffer(stride, attributes));
+        } else {
+            for (i, (format, ncomponents)) in layout.iter().enumerate() {
+                if *format != VertexFormat::Float32 {
+                    let mut attributes = [None; 3];
+                    attributes[i] = Some((*format, 0));
+                    self.packed
+                        .push(new_buffer(format.attribute_size(*ncomponents), attributes));
+                }
+            }
+        }
+    }
+
+    // Uses the same load options and packed buffers as `other`, which must share the vertices,
+    // normals, and texture coordinates of this mesh.
+    pub(crate) fn share_packed_buffers(&mut self, other: &Mesh) {
+        self.load_options = other.load_options;
+        self.packed = other.packed.clone();
+    }
+
+    // Rebuilds the packed buffers if the vertices, normals, or texture coordinates changed.
+    fn refresh_packed_buffers(&self) {
+        if self.packed.is_empty() {
+            return;
+        }
+
+        let coords = self.coords.read().unwrap();
+        let normals = self.normals.read().unwrap();
+        let uvs = self.uvs.read().unwrap();
+        let versions = [coords.version(), normals.version(), uvs.version()];
+
+        let (coords, normals, uvs) = match (coords.data(), normals.data(), uvs.data()) {
+            (Some(c), Some(n), Some(u)) => (c, n, u),
+            // The data is not available on RAM anymore: keep the current buffers.
+            _ => return,
+        };
+
+        for buffer in &self.packed {
+            let mut buffer = buffer.write().unwrap();
+
+            if buffer.versions == Some(versions) {
+                continue;
+            }
+
+            let mut bytes = Vec::with_capacity(coords.len() * buffer.stride);
+
+            for (i, coord) in coords.iter().enumerate() {
+                for (a, attribute) in buffer.attributes.iter().enumerate() {
+                    if let Some((format, _)) = *attribute {
+                        let start = bytes.len();
+                        let (components, ncomponents) = match a {
+                            0 => (coord.coords.as_slice(), 3),
+                            1 => (normals.get(i).map(|n| n.as_slice()).unwrap_or(&[]), 3),
+                            _ => (uvs.get(i).map(|u| u.coords.as_slice()).unwrap_or(&[]), 2),
+                        };
+
+                        for c in components {
+                            format.push(*c, &mut bytes);
+                        }
+
+                        bytes.resize(start + format.attribute_size(ncomponents), 0);
+                    }
+                }
+            }
+
+            *buffer.data.data_mut() = Some(bytes);
+            buffer.versions = Some(versions);
+        }
+    }
+
+    // Binds the `attribute`-th vertex attribute from a packed buffer. Returns `false` if it is
+    // not stored by any packed buffer.
+    fn bind_packed<T: GLPrimitive>(
+        &mut self,
+        attribute: usize,
+        shader_attribute: &mut ShaderAttribute<T>,
+    ) -> bool {
+        self.refresh_packed_buffers();
+
+        for buffer in &self.packed {
+            let mut buffer = buffer.write().unwrap();
+
+            if let Some((format, offset)) = buffer.attributes[attribute] {
+                let stride = buffer.stride;
+                shader_attribute.bind_packed(&mut buffer.data, format, stride, offset);
+                return true;
+            }
+        }
+
+        false
+    }
+
+    /// Overwrites the vertex coordinates of this mesh directly in their gpu buffer.
+    ///
+    /// `f` must write every vertex, see `GPUVec::write_on_gpu`. If the vertices are interleaved
+    /// with other attributes, `f` writes to their RAM copy instead. Returns `false`, without
+    /// calling `f`, if the vertices cannot be written.
+    pub fn write_coords_on_gpu<F: FnOnce(&mut [Point3<f32>])>(&mut self, f: F) -> bool {
+        let interleaved = self
+            .packed
+            .iter()
+            .any(|buffer| buffer.read().unwrap().attributes[0].is_some());
+        let mut coords = self.coords.write().unwrap();
+
+        if interleaved {
+            coords.data_mut().as_mut().map(|c| f(&mut c[..])).is_some()
+        } else {
+            coords.write_on_gpu(f)
         }
     }
 
     /// Binds this mesh vertex coordinates buffer to a vertex attribute.
     pub fn bind_coords(&mut self, coords: &mut ShaderAttribute<Point3<f32>>) {
-        coords.bind(&mut *self.coords.write().unwrap());
+        if !self.bind_packed(0, coords) {
+            coords.bind(&mut *self.coords.write().unwrap());
+        }
     }
 
     /// Binds this mesh vertex normals buffer to a vertex attribute.
     pub fn bind_normals(&mut self, normals: &mut ShaderAttribute<Vector3<f32>>) {
-        normals.bind(&mut *self.normals.write().unwrap());
+        if !self.bind_packed(1, normals) {
+            normals.bind(&mut *self.normals.write().unwrap());
+        }
     }
 
     /// Binds this mesh vertex uvs buffer to a vertex attribute.
     pub fn bind_uvs(&mut self, uvs: &mut ShaderAttribute<Point2<f32>>) {
-        uvs.bind(&mut *self.uvs.write().unwrap());
+        if !self.bind_packed(2, uvs) {
+            uvs.bind(&mut *self.uvs.write().unwrap());
+        }
+    }
+
+    /// Binds this mesh vertex colors buffer to a vertex attribute.
+    ///
+    /// Returns `false` if this mesh has no vertex colors.
+    pub fn bind_colors(&mut self, colors: &mut ShaderAttribute<Point3<f32>>) -> bool {
+        match self.colors {
+            Some(ref cs) => {
+                colors.bind(&mut *cs.write().unwrap());
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Binds this mesh lightmap texture coordinates buffer to a vertex attribute.
+    ///
+    /// Returns `false` if this mesh has no lightmap texture coordinates.
+    pub fn bind_lightmap_uvs(&mut self, uvs: &mut ShaderAttribute<Point2<f32>>) -> bool {
+        match self.lightmap_uvs {
+            Some(ref us) => {
+                uvs.bind(&mut *us.write().unwrap());
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Binds this mesh joint indices and weights buffers to vertex attributes.
+    ///
+    /// Returns `false` if this mesh is not skinned.
+    pub fn bind_skin(
+        &mut self,
+        joints: &mut ShaderAttribute<Vector4<f32>>,
+        weights: &mut ShaderAttribute<Vector4<f32>>,
+    ) -> bool {
+        match self.skin {
+            Some((ref js, ref ws)) => {
+                joints.bind(&mut *js.write().unwrap());
+                weights.bind(&mut *ws.write().unwrap());
+                true
+            }
+            None => false,
+        }
     }
 
     /// Binds this mesh index buffer to a vertex attribute.
@@ -246,6 +512,23 @@ impl Mesh {
         self.normals.write().unwrap().unbind();
         self.uvs.write().unwrap().unbind();
         self.faces.write().unwrap().unbind();
+
+        for buffer in &self.packed {
+            buffer.write().unwrap().data.unbind();
+        }
+
+        if let Some(ref colors) = self.colors {
+            colors.write().unwrap().unbind();
+        }
+
+        if let Some(ref uvs) = self.lightmap_uvs {
+            uvs.write().unwrap().unbind();
+        }
+
+        if let Some((ref joints, ref weights)) = self.skin {
+            joints.write().unwrap().unbind();
+            weights.write().unwrap().unbind();
+        }
     }
 
     /// Number of points needed to draw this mesh.
@@ -262,6 +545,49 @@ impl Mesh {
         );
     }
 
+    /// The isolines of a scalar field defined on the vertices of this mesh, at the given values.
+    ///
+    /// There must be one scalar per vertex, e.g., the values given to
+    /// `SceneNode::set_scalar_field`. The lines are expressed in the local frame of the mesh and
+    /// slightly moved along its normals, so that they are not hidden by the surface when drawn
+    /// over it. No line is returned if the vertices or faces are not available on RAM.
+    ///
+    /// ```no_run
+    /// # use kiss3d::window::Window;
+    /// # use kiss3d::colormap::Colormap;
+    /// let mut window = Window::new("Kiss3d: isolines");
+    /// let mut sphere = window.add_sphere(1.0);
+    /// let mesh = sphere.data().object().unwrap().mesh().clone();
+    /// let heights: Vec<f32> = mesh.borrow().coords().read().unwrap().data().as_ref().unwrap()
+    ///     .iter().map(|p| p.y).collect();
+    /// sphere.set_scalar_field(&heights, &Colormap::Viridis, None);
+    /// let isolines = mesh.borrow().extract_isolines(&heights, &[-0.5, 0.0, 0.5]);
+    ///
+    /// while window.render() {
+    ///     for line in &isolines {
+    ///         for w in line.points.windows(2) {
+    ///             window.draw_line(&w[0], &w[1], &[1.0, 1.0, 1.0]);
+    ///         }
+    ///     }
+    /// }
+    /// ```
+    pub fn extract_isolines(&self, scalars: &[f32], values: &[f32]) -> Vec<Isoline> {
+        let coords = self.coords.read().unwrap();
+        let faces = self.faces.read().unwrap();
+        let normals = self.normals.read().unwrap();
+
+        match (coords.data(), faces.data()) {
+            (Some(coords), Some(faces)) => isoline::extract(
+                coords,
+                normals.data().as_deref().unwrap_or(&[]),
+                faces,
+                scalars,
+                values,
+            ),
+            _ => Vec::new(),
+        }
+    }
+
     /// This mesh faces.
     pub fn faces(&self) -> &Arc<RwLock<GPUVec<Point3<u16>>>> {
         &self.faces
@@ -282,6 +608,273 @@ impl Mesh {
         &self.uvs
     }
 
+    /// The bounding volume hierarchy of this mesh triangles.
+    ///
+    /// The hierarchy is built on the first call, and rebuilt only after the vertices or faces of
+    /// this mesh have been modified. Returns `None` if they are not available on RAM.
+    pub fn bvh(&self) -> Option<Arc<MeshBvh>> {
+        let coords = self.coords.read().unwrap();
+        let faces = self.faces.read().unwrap();
+        let version = (coords.version(), faces.version());
+
+        if let Some((v, ref bvh)) = *self.bvh.read().unwrap() {
+            if v == version {
+                return Some(bvh.clone());
+            }
+        }
+
+        let bvh = Arc::new(MeshBvh::new(
+            coords.data().as_ref()?,
+            faces.data().as_ref()?,
+        ));
+        *self.bvh.write().unwrap() = Some((version, bvh.clone()));
+
+        Some(bvh)
+    }
+
+    /// The axis-aligned bounding box of this mesh vertices.
+    ///
+    /// The box is computed on the first call, and again only after the vertices of this mesh have
+    /// been modified. Returns `None` if there is no vertex, or if they are not available on RAM.
+    pub fn aabb(&self) -> Option<AABB<f32>> {
+        let coords = self.coords.read().unwrap();
+        let version = coords.version();
+
+        if let Some((v, aabb)) = *self.aabb.read().unwrap() {
+            if v == version {
+                return Some(aabb);
+            }
+        }
+
+        let pts = coords.data().as_ref()?;
+        let first = *pts.first()?;
+        let (mins, maxs) = pts.iter().fold((first, first), |(mins, maxs), pt| {
+            (mins.inf(pt), maxs.sup(pt))
+        });
+        let aabb = AABB::new(mins, maxs);
+        *self.aabb.write().unwrap() = Some((version, aabb));
+
+        Some(aabb)
+    }
+
+    /// This mesh vertex colors, if any.
+    pub fn colors(&self) -> Option<&Arc<RwLock<GPUVec<Point3<f32>>>>> {
+        self.colors.as_ref()
+    }
+
+    /// Sets this mesh vertex colors.
+    ///
+    /// There must be one color per vertex. Vertex colors are only used by materials that support
+    /// them, e.g., the `vertex_color` material. Use `None` to remove them.
+    pub fn set_colors(&mut self, colors: Option<Vec<Point3<f32>>>) {
+        if let Some(ref colors) = colors {
+            assert_eq!(
+                colors.len(),
+                self.coords.read().unwrap().len(),
+                "There must be exactly one color per vertex."
+            );
+        }
+
+        match (colors, &self.colors) {
+            (Some(colors), Some(curr)) => {
+                let mut curr = curr.write().unwrap();
+                if let Some(data) = curr.data_mut() {
+                    *data = colors;
+                } else {
+                    *curr = GPUVec::new(colors, BufferType::Array, AllocationType::DynamicDraw);
+                }
+            }
+            (Some(colors), None) => {
+                let gpu_colors =
+                    GPUVec::new(colors, BufferType::Array, AllocationType::DynamicDraw);
+                self.colors = Some(Arc::new(RwLock::new(gpu_colors)));
+            }
+            (None, _) => self.colors = None,
+        }
+    }
+
+    /// This mesh second set of texture coordinates, used to sample its lightmaps, if any.
+    pub fn lightmap_uvs(&self) -> Option<&Arc<RwLock<GPUVec<Point2<f32>>>>> {
+        self.lightmap_uvs.as_ref()
+    }
+
+    /// Sets this mesh lightmap texture coordinates.
+    ///
+    /// There must be one pair of coordinates per vertex, and the triangles must not overlap in
+    /// texture space, so that each point of the mesh has its own texels in a lightmap. Use
+    /// `None` to remove them.
+    pub fn set_lightmap_uvs(&mut self, uvs: Option<Vec<Point2<f32>>>) {
+        if let Some(ref uvs) = uvs {
+            assert_eq!(
+                uvs.len(),
+                self.coords.read().unwrap().len(),
+                "There must be exactly one lightmap uv per vertex."
+            );
+        }
+
+        self.lightmap_uvs = uvs.map(|uvs| {
+            let location = self.coords.read().unwrap().allocation_type();
+            Arc::new(RwLock::new(GPUVec::new(uvs, BufferType::Array, location)))
+        });
+    }
+
+    /// Generates lightmap texture coordinates giving each triangle its own area of a lightmap of
+    /// `resolution` × `resolution` texels.
+    ///
+    /// The triangles are packed by pairs in the cells of a grid, with gaps between them so that
+    /// they do not bleed into each other. Their vertices can then not be shared anymore, so each
+    /// triangle gets its own three vertices. Returns `false`, leaving this mesh unchanged, if its
+    /// data is not available on RAM, if it has too many triangles to duplicate its vertices, or
+    /// if it is skinned.
+    pub fn generate_lightmap_uvs(&mut self, resolution: usize) -> bool {
+        if self.is_skinned() {
+            return false;
+        }
+
+        let (coords, normals, uvs, faces) = {
+            let coords = self.coords.read().unwrap();
+            let normals = self.normals.read().unwrap();
+            let uvs = self.uvs.read().unwrap();
+            let faces = self.faces.read().unwrap();
+
+            match (coords.data(), normals.data(), uvs.data(), faces.data()) {
+                (Some(c), Some(n), Some(u), Some(f)) => {
+                    (c.clone(), n.clone(), u.clone(), f.clone())
+                }
+                _ => return false,
+            }
+        };
+
+        if faces.len() * 3 > u16::MAX as usize + 1 {
+            return false;
+        }
+
+        let colors = match self.colors {
+            Some(ref colors) => match colors.read().unwrap().data() {
+                Some(colors) => Some(colors.clone()),
+                None => return false,
+            },
+            None => None,
+        };
+
+        let ncells = (faces.len() as f32 / 2.0).sqrt().ceil().max(1.0);
+        let cell = 1.0 / ncells;
+        // Two texels between the triangles, and one around the cells.
+        let gap = 2.0 / resolution as f32;
+        let margin = gap / 2.0;
+        let mut new_coords = Vec::with_capacity(faces.len() * 3);
+        let mut new_normals = Vec::with_capacity(faces.len() * 3);
+        let mut new_uvs = Vec::with_capacity(faces.len() * 3);
+        let mut new_colors = colors.as_ref().map(|_| Vec::with_capacity(faces.len() * 3));
+        let mut lightmap_uvs = Vec::with_capacity(faces.len() * 3);
+        let mut new_faces = Vec::with_capacity(faces.len());
+
+        for (i, face) in faces.iter().enumerate() {
+            let cell_id = i / 2;
+            let x0 = (cell_id % ncells as usize) as f32 * cell + margin;
+            let y0 = (cell_id / ncells as usize) as f32 * cell + margin;
+            let size = cell - 2.0 * margin - gap;
+            // The first triangle of a cell fills its lower-left half, and the second one its
+            // upper-right half.
+            let corners = if i % 2 == 0 {
+                [
+                    Point2::new(x0, y0),
+                    Point2::new(x0 + size, y0),
+                    Point2::new(x0, y0 + size),
+                ]
+            } else {
+                let (x1, y1) = (x0 + size + gap, y0 + size + gap);
+                [
+                    Point2::new(x1, y1),
+                    Point2::new(x1 - size, y1),
+                    Point2::new(x1, y1 - size),
+                ]
+            };
+
+            for (k, id) in [face.x, face.y, face.z].iter().enumerate() {
+                let id = *id as usize;
+                new_coords.push(coords[id]);
+                new_normals.push(normals[id]);
+                new_uvs.push(uvs.get(id).cloned().unwrap_or_else(Point2::origin));
+                lightmap_uvs.push(corners[k]);
+
+                if let (Some(new_colors), Some(colors)) = (&mut new_colors, &colors) {
+                    new_colors.push(colors[id]);
+                }
+            }
+
+            let first = (i * 3) as u16;
+            new_faces.push(Point3::new(first, first + 1, first + 2));
+        }
+
+        // Modified in place, so that the caches depending on their versions are rebuilt.
+        *self.coords.write().unwrap().data_mut() = Some(new_coords);
+        *self.normals.write().unwrap().data_mut() = Some(new_normals);
+        *self.uvs.write().unwrap().data_mut() = Some(new_uvs);
+        *self.faces.write().unwrap().data_mut() = Some(new_faces);
+        self.edges = None;
+        self.set_colors(new_colors);
+        self.set_lightmap_uvs(Some(lightmap_uvs));
+
+        true
+    }
+
+    /// Whether this mesh has joint indices and weights, to be deformed by a `Skeleton`.
+    pub fn is_skinned(&self) -> bool {
+        self.skin.is_some()
+    }
+
+    /// Sets the joints influencing each vertex of this mesh, and their weights.
+    ///
+    /// There must be four joint indices and four weights per vertex, the weights summing to 1.
+    /// The unused influences have a zero weight. The vertices are then moved by the joints of
+    /// the skeleton of the objects drawing this mesh, see `Object::set_skeleton`, each joint
+    /// index referring to a joint of that skeleton. The joint indices must be smaller than
+    /// `MAX_JOINTS`.
+    pub fn set_skin(&mut self, joints: Vec<[u16; 4]>, weights: Vec<[f32; 4]>) {
+        let len = self.coords.read().unwrap().len();
+        assert_eq!(
+            joints.len(),
+            len,
+            "There must be exactly four joint indices per vertex."
+        );
+        assert_eq!(
+            weights.len(),
+            len,
+            "There must be exactly four joint weights per vertex."
+        );
+        assert!(
+            joints.iter().flatten().all(|j| (*j as usize) < MAX_JOINTS),
+            "The joint indices must be smaller than MAX_JOINTS."
+        );
+
+        // The attributes of the vertex shaders are floating-point.
+        let joints = joints
+            .iter()
+            .map(|j| Vector4::new(j[0] as f32, j[1] as f32, j[2] as f32, j[3] as f32))
+            .collect();
+        let weights = weights.iter().map(|w| Vector4::from(*w)).collect();
+        let location = self.coords.read().unwrap().allocation_type();
+
+        self.skin = Some((
+            Arc::new(RwLock::new(GPUVec::new(
+                joints,
+                BufferType::Array,
+                location,
+            ))),
+            Arc::new(RwLock::new(GPUVec::new(
+                weights,
+                BufferType::Array,
+                location,
+            ))),
+        ));
+    }
+
+    /// Removes the joint indices and weights of this mesh, which is then drawn as it is modeled.
+    pub fn remove_skin(&mut self) {
+        self.skin = None;
+    }
+
     /// Computes normals from a set of faces.
     pub fn compute_normals_array(
         coordinates: &[Point3<f32>],
@@ -332,4 +925,51 @@ impl Mesh {
             *n /= *divisor
         }
     }
+
+    /// Computes unit tangents, orthogonal to the normals and pointing toward increasing `u`
+    /// texture coordinates.
+    ///
+    /// Vertices without any valid texture coordinates have a zero tangent.
+    pub fn compute_tangents_array(
+        coordinates: &[Point3<f32>],
+        normals: &[Vector3<f32>],
+        uvs: &[Point2<f32>],
+        faces: &[Point3<u16>],
+    ) -> Vec<Vector3<f32>> {
+        let mut tangents = vec![Vector3::zero(); coordinates.len()];
+
+        for f in faces.iter() {
+            let (a, b, c) = (f.x as usize, f.y as usize, f.z as usize);
+
+            if a.max(b).max(c) >= uvs.len().min(coordinates.len()) {
+                continue;
+            }
+
+            let edge1 = coordinates[b] - coordinates[a];
+            let edge2 = coordinates[c] - coordinates[a];
+            let duv1 = uvs[b] - uvs[a];
+            let duv2 = uvs[c] - uvs[a];
+            let det = duv1.x * duv2.y - duv2.x * duv1.y;
+
+            if det.abs() <= f32::EPSILON {
+                continue;
+            }
+
+            let tangent = (edge1 * duv2.y - edge2 * duv1.y) / det;
+
+            tangents[a] += tangent;
+            tangents[b] += tangent;
+            tangents[c] += tangent;
+        }
+
+        // Gram-Schmidt orthogonalization with respect to the normals.
+        for (t, n) in tangents.iter_mut().zip(normals.iter()) {
+            let orthogonal = *t - n * n.dot(t);
+            *t = orthogonal
+                .try_normalize(1.0e-12)
+                .unwrap_or_else(Vector3::zero);
+        }
+
+        tangents
+    }
 }
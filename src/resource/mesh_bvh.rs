@@ -0,0 +1,507 @@
+//! Bounding volume hierarchy of the triangles of a mesh.
+
+use na::{Point3, Vector3};
+use ncollide3d::bounding_volume::AABB;
+use ncollide3d::query::Ray;
+use std::f32;
+
+// Number of triangles stored by each leaf, and tested at once.
+const LANES: usize = 4;
+
+/// The intersection of a ray with a triangle of a mesh.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct MeshRayHit {
+    /// The time of impact of the ray: the hit point is `ray.origin + ray.dir * toi`.
+    pub toi: f32,
+    /// The index of the hit face.
+    pub face: usize,
+    /// The barycentric coordinates of the hit point, relative to the three vertices of the face.
+    pub barycentric: Vector3<f32>,
+    /// The unit normal of the hit face, oriented following the winding of its vertices.
+    pub normal: Vector3<f32>,
+}
+
+// A node of the hierarchy. Two nodes fit in a cache line.
+#[derive(Copy, Clone, Debug)]
+#[repr(C)]
+struct BvhNode {
+    mins: [f32; 3],
+    // The index of the second child of an internal node (the first child directly follows its
+    // parent), or the index of the triangle block of a leaf.
+    index: u32,
+    maxs: [f32; 3],
+    // The number of triangles of a leaf, zero for internal nodes.
+    count: u32,
+}
+
+// The triangles of a leaf, stored as a structure of arrays so that they can be processed at once
+// with SIMD instructions. Unused lanes contain degenerate triangles.
+#[derive(Copy, Clone, Debug)]
+#[repr(C, align(16))]
+struct TriangleBlock {
+    v0: [[f32; LANES]; 3],
+    e1: [[f32; LANES]; 3],
+    e2: [[f32; LANES]; 3],
+    faces: [u32; LANES],
+}
+
+struct BuildTriangle {
+    face: u32,
+    centroid: Point3<f32>,
+    mins: Point3<f32>,
+    maxs: Point3<f32>,
+}
+
+/// A bounding volume hierarchy accelerating ray casts and proximity queries on the triangles of a
+/// mesh.
+///
+/// The hierarchy is stored in a flat array, and the triangles of its leaves are grouped by four
+/// with a layout suitable for SIMD processing. Use `Mesh::bvh` to get the hierarchy of a mesh,
+/// built lazily and cached.
+#[derive(Clone, Debug)]
+pub struct MeshBvh {
+    nodes: Vec<BvhNode>,
+    blocks: Vec<TriangleBlock>,
+    num_faces: usize,
+}
+
+impl MeshBvh {
+    /// Builds the hierarchy of the given triangles.
+    ///
+    /// Faces referencing vertices out of `coords` are ignored.
+    pub fn new(coords: &[Point3<f32>], faces: &[Point3<u16>]) -> MeshBvh {
+        let mut tris: Vec<BuildTriangle> = faces
+            .iter()
+            .enumerate()
+            .filter(|(_, f)| f.iter().all(|i| (*i as usize) < coords.len()))
+            .map(|(i, f)| {
+                let (a, b, c) = (
+                    coords[f.x as usize],
+                    coords[f.y as usize],
+                    coords[f.z as usize],
+                );
+
+                BuildTriangle {
+                    face: i as u32,
+                    centroid: Point3::from((a.coords + b.coords + c.coords) / 3.0),
+                    mins: a.inf(&b).inf(&c),
+                    maxs: a.sup(&b).sup(&c),
+                }
+            })
+            .collect();
+
+        let mut res = MeshBvh {
+            nodes: Vec::with_capacity(tris.len() / LANES * 2 + 1),
+            blocks: Vec::with_capacity(tris.len() / LANES + 1),
+            num_faces: faces.len(),
+        };
+
+        if !tris.is_empty() {
+            let _ = res.build(coords, faces, &mut tris);
+        }
+
+        res
+    }
+
+    fn build(
+        &mut self,
+        coords: &[Point3<f32>],
+        faces: &[Point3<u16>],
+        tris: &mut [BuildTriangle],
+    ) -> u32 {
+        let id = self.nodes.len();
+        let mut mins = Point3::from(Vector3::from_element(f32::MAX));
+        let mut maxs = Point3::from(Vector3::from_element(f32::MIN));
+
+        for t in tris.iter() {
+            mins = mins.inf(&t.mins);
+            maxs = maxs.sup(&t.maxs);
+        }
+
+        self.nodes.push(BvhNode {
+            mins: mins.coords.into(),
+            index: 0,
+            maxs: maxs.coords.into(),
+            count: 0,
+        });
+
+        if tris.len() <= LANES {
+            self.nodes[id].index = self.blocks.len() as u32;
+            self.nodes[id].count = tris.len() as u32;
+            self.blocks.push(make_block(coords, faces, tris));
+        } else {
+            // Split at the median of the centroids, along the largest axis of their bounds.
+            let mut cmins = Point3::from(Vector3::from_element(f32::MAX));
+            let mut cmaxs = Point3::from(Vector3::from_element(f32::MIN));
+
+            for t in tris.iter() {
+                cmins = cmins.inf(&t.centroid);
+                cmaxs = cmaxs.sup(&t.centroid);
+            }
+
+            let axis = (cmaxs - cmins).imax();
+            let mid = tris.len() / 2;
+            let _ = tris.select_nth_unstable_by(mid, |a, b| {
+                a.centroid[axis]
+                    .partial_cmp(&b.centroid[axis])
+                    .unwrap_or(std::cmp::Ordering::Equal)
+            });
+
+            let (left, right) = tris.split_at_mut(mid);
+            let _ = self.build(coords, faces, left);
+            self.nodes[id].index = self.build(coords, faces, right);
+        }
+
+        id as u32
+    }
+
+    /// The number of faces of the mesh this hierarchy has been built from.
+    #[inline]
+    pub fn num_faces(&self) -> usize {
+        self.num_faces
+    }
+
+    /// The number of nodes of this hierarchy.
+    #[inline]
+    pub fn num_nodes(&self) -> usize {
+        self.nodes.len()
+    }
+
+    /// The bounding box of all the triangles, or `None` if there is no valid triangle.
+    pub fn aabb(&self) -> Option<AABB<f32>> {
+        self.nodes
+            .first()
+            .map(|n| AABB::new(Point3::from(n.mins), Point3::from(n.maxs)))
+    }
+
+    /// Computes the first intersection of a ray with the triangles, both sides of each triangle
+    /// being considered.
+    ///
+    /// Only intersections with a time of impact smaller than `max_toi` are reported. The ray
+    /// direction does not need to be normalized.
+    pub fn cast_ray(&self, ray: &Ray<f32>, max_toi: f32) -> Option<MeshRayHit> {
+        if self.nodes.is_empty() {
+            return None;
+        }
+
+        let inv_dir = ray.dir.map(|d| 1.0 / d);
+        let mut best: Option<(f32, usize, usize, f32, f32)> = None;
+        let mut max_toi = max_toi;
+        let mut stack = vec![0u32];
+
+        while let Some(id) = stack.pop() {
+            let node = &self.nodes[id as usize];
+
+            if node.count != 0 {
+                let block = &self.blocks[node.index as usize];
+
+                if let Some((lane, t, u, v)) = cast_ray_on_block(block, ray, max_toi) {
+                    max_toi = t;
+                    best = Some((t, node.index as usize, lane, u, v));
+                }
+            } else {
+                let left = id + 1;
+                let right = node.index;
+                let tl = ray_aabb(&self.nodes[left as usize], ray, &inv_dir, max_toi);
+                let tr = ray_aabb(&self.nodes[right as usize], ray, &inv_dir, max_toi);
+
+                // Push the farthest child first, so the closest one is visited first.
+                match (tl, tr) {
+                    (Some(tl), Some(tr)) if tl < tr => stack.extend_from_slice(&[right, left]),
+                    (Some(_), Some(_)) => stack.extend_from_slice(&[left, right]),
+                    (Some(_), None) => stack.push(left),
+                    (None, Some(_)) => stack.push(right),
+                    (None, None) => {}
+                }
+            }
+        }
+
+        best.map(|(toi, block, lane, u, v)| {
+            let block = &self.blocks[block];
+            let e1 = lane_vector(&block.e1, lane);
+            let e2 = lane_vector(&block.e2, lane);
+
+            MeshRayHit {
+                toi,
+                face: block.faces[lane] as usize,
+                barycentric: Vector3::new(1.0 - u - v, u, v),
+                normal: e1.cross(&e2).normalize(),
+            }
+        })
+    }
+
+    /// Appends to `out` the index of each face whose bounding box intersects `aabb`.
+    pub fn faces_intersecting_aabb(&self, aabb: &AABB<f32>, out: &mut Vec<usize>) {
+        let mins = aabb.mins.coords;
+        let maxs = aabb.maxs.coords;
+        let mut stack = Vec::new();
+
+        if !self.nodes.is_empty() {
+            stack.push(0u32);
+        }
+
+        while let Some(id) = stack.pop() {
+            let node = &self.nodes[id as usize];
+
+            if (0..3).any(|i| node.mins[i] > maxs[i] || node.maxs[i] < mins[i]) {
+                continue;
+            }
+
+            if node.count != 0 {
+                let block = &self.blocks[node.index as usize];
+
+                for lane in 0..node.count as usize {
+                    let v0 = lane_vector(&block.v0, lane);
+                    let v1 = v0 + lane_vector(&block.e1, lane);
+                    let v2 = v0 + lane_vector(&block.e2, lane);
+                    let tmins = v0.inf(&v1).inf(&v2);
+                    let tmaxs = v0.sup(&v1).sup(&v2);
+
+                    if (0..3).all(|i| tmins[i] <= maxs[i] && tmaxs[i] >= mins[i]) {
+                        out.push(block.faces[lane] as usize);
+                    }
+                }
+            } else {
+                stack.push(id + 1);
+                stack.push(node.index);
+            }
+        }
+    }
+
+    /// The point of the triangles closest to `point`, with the index of its face.
+    ///
+    /// Returns `None` if there is no valid triangle.
+    pub fn closest_point(&self, point: &Point3<f32>) -> Option<(usize, Point3<f32>)> {
+        let mut best: Option<(f32, usize, Point3<f32>)> = None;
+        let mut stack = Vec::new();
+
+        if !self.nodes.is_empty() {
+            stack.push(0u32);
+        }
+
+        while let Some(id) = stack.pop() {
+            let node = &self.nodes[id as usize];
+            let best_dist = best.map(|b| b.0).unwrap_or(f32::MAX);
+
+            if aabb_distance_squared(node, point) > best_dist {
+                continue;
+            }
+
+            if node.count != 0 {
+                let block = &self.blocks[node.index as usize];
+
+                for lane in 0..node.count as usize {
+                    let v0 = lane_vector(&block.v0, lane);
+                    let e1 = lane_vector(&block.e1, lane);
+                    let e2 = lane_vector(&block.e2, lane);
+                    let proj = closest_point_on_triangle(point, &Point3::from(v0), &e1, &e2);
+                    let dist = na::distance_squared(point, &proj);
+
+                    if best.map(|b| dist < b.0).unwrap_or(true) {
+                        best = Some((dist, block.faces[lane] as usize, proj));
+                    }
+                }
+            } else {
+                let left = id + 1;
+                let right = node.index;
+                let dl = aabb_distance_squared(&self.nodes[left as usize], point);
+                let dr = aabb_distance_squared(&self.nodes[right as usize], point);
+
+                if dl < dr {
+                    stack.extend_from_slice(&[right, left]);
+                } else {
+                    stack.extend_from_slice(&[left, right]);
+                }
+            }
+        }
+
+        best.map(|b| (b.1, b.2))
+    }
+}
+
+fn make_block(
+    coords: &[Point3<f32>],
+    faces: &[Point3<u16>],
+    tris: &[BuildTriangle],
+) -> TriangleBlock {
+    let mut block = TriangleBlock {
+        v0: [[0.0; LANES]; 3],
+        e1: [[0.0; LANES]; 3],
+        e2: [[0.0; LANES]; 3],
+        faces: [u32::MAX; LANES],
+    };
+
+    for (lane, t) in tris.iter().enumerate() {
+        let f = faces[t.face as usize];
+        let a = coords[f.x as usize];
+        let e1 = coords[f.y as usize] - a;
+        let e2 = coords[f.z as usize] - a;
+
+        for i in 0..3 {
+            block.v0[i][lane] = a[i];
+            block.e1[i][lane] = e1[i];
+            block.e2[i][lane] = e2[i];
+        }
+
+        block.faces[lane] = t.face;
+    }
+
+    block
+}
+
+#[inline]
+fn lane_vector(v: &[[f32; LANES]; 3], lane: usize) -> Vector3<f32> {
+    Vector3::new(v[0][lane], v[1][lane], v[2][lane])
+}
+
+// Möller-Trumbore intersection of a ray with the four triangles of a block. Returns the lane,
+// time of impact, and barycentric coordinates of the closest hit.
+//
+// The computations are written lane-wise on fixed-size arrays so they get vectorized.
+fn cast_ray_on_block(
+    block: &TriangleBlock,
+    ray: &Ray<f32>,
+    max_toi: f32,
+) -> Option<(usize, f32, f32, f32)> {
+    let o = ray.origin;
+    let d = ray.dir;
+    let [e1x, e1y, e1z] = block.e1;
+    let [e2x, e2y, e2z] = block.e2;
+    let mut toi = [f32::MAX; LANES];
+    let mut us = [0.0; LANES];
+    let mut vs = [0.0; LANES];
+
+    for l in 0..LANES {
+        // p = d x e2
+        let px = d.y * e2z[l] - d.z * e2y[l];
+        let py = d.z * e2x[l] - d.x * e2z[l];
+        let pz = d.x * e2y[l] - d.y * e2x[l];
+        let det = e1x[l] * px + e1y[l] * py + e1z[l] * pz;
+        let inv_det = 1.0 / det;
+
+        let tx = o.x - block.v0[0][l];
+        let ty = o.y - block.v0[1][l];
+        let tz = o.z - block.v0[2][l];
+        let u = (tx * px + ty * py + tz * pz) * inv_det;
+
+        // q = t x e1
+        let qx = ty * e1z[l] - tz * e1y[l];
+        let qy = tz * e1x[l] - tx * e1z[l];
+        let qz = tx * e1y[l] - ty * e1x[l];
+        let v = (d.x * qx + d.y * qy + d.z * qz) * inv_det;
+        let t = (e2x[l] * qx + e2y[l] * qy + e2z[l] * qz) * inv_det;
+
+        let hit =
+            det.abs() > 1.0e-12 && u >= 0.0 && v >= 0.0 && u + v <= 1.0 && t >= 0.0 && t <= max_toi;
+
+        toi[l] = if hit { t } else { f32::MAX };
+        us[l] = u;
+        vs[l] = v;
+    }
+
+    let mut best = None;
+    let mut best_toi = f32::MAX;
+
+    for l in 0..LANES {
+        if toi[l] < best_toi {
+            best_toi = toi[l];
+            best = Some((l, toi[l], us[l], vs[l]));
+        }
+    }
+
+    best
+}
+
+// The entry time of a ray into the bounding box of a node, if it hits it before `max_toi`.
+#[inline]
+fn ray_aabb(node: &BvhNode, ray: &Ray<f32>, inv_dir: &Vector3<f32>, max_toi: f32) -> Option<f32> {
+    let mut tmin = 0.0f32;
+    let mut tmax = max_toi;
+
+    for i in 0..3 {
+        let t1 = (node.mins[i] - ray.origin[i]) * inv_dir[i];
+        let t2 = (node.maxs[i] - ray.origin[i]) * inv_dir[i];
+
+        // `max` and `min` ignore the NaNs produced by rays parallel to a slab boundary.
+        tmin = tmin.max(t1.min(t2));
+        tmax = tmax.min(t1.max(t2));
+    }
+
+    if tmin <= tmax {
+        Some(tmin)
+    } else {
+        None
+    }
+}
+
+#[inline]
+fn aabb_distance_squared(node: &BvhNode, point: &Point3<f32>) -> f32 {
+    (0..3)
+        .map(|i| {
+            let d = (node.mins[i] - point[i])
+                .max(point[i] - node.maxs[i])
+                .max(0.0);
+            d * d
+        })
+        .sum()
+}
+
+// The point of the triangle `(a, a + ab, a + ac)` closest to `p`.
+fn closest_point_on_triangle(
+    p: &Point3<f32>,
+    a: &Point3<f32>,
+    ab: &Vector3<f32>,
+    ac: &Vector3<f32>,
+) -> Point3<f32> {
+    let ap = p - a;
+    let d1 = ab.dot(&ap);
+    let d2 = ac.dot(&ap);
+
+    if d1 <= 0.0 && d2 <= 0.0 {
+        return *a;
+    }
+
+    let bp = ap - ab;
+    let d3 = ab.dot(&bp);
+    let d4 = ac.dot(&bp);
+
+    if d3 >= 0.0 && d4 <= d3 {
+        return a + ab;
+    }
+
+    let vc = d1 * d4 - d3 * d2;
+
+    if vc <= 0.0 && d1 >= 0.0 && d3 <= 0.0 {
+        return a + ab * (d1 / (d1 - d3));
+    }
+
+    let cp = ap - ac;
+    let d5 = ab.dot(&cp);
+    let d6 = ac.dot(&cp);
+
+    if d6 >= 0.0 && d5 <= d6 {
+        return a + ac;
+    }
+
+    let vb = d5 * d2 - d1 * d6;
+
+    if vb <= 0.0 && d2 >= 0.0 && d6 <= 0.0 {
+        return a + ac * (d2 / (d2 - d6));
+    }
+
+    let va = d3 * d6 - d5 * d4;
+
+    if va <= 0.0 && (d4 - d3) >= 0.0 && (d5 - d6) >= 0.0 {
+        let w = (d4 - d3) / ((d4 - d3) + (d5 - d6));
+        return a + ab + (ac - ab) * w;
+    }
+
+    let denom = va + vb + vc;
+
+    if denom.abs() <= f32::EPSILON {
+        // Degenerate triangle.
+        return *a;
+    }
+
+    a + ab * (vb / denom) + ac * (vc / denom)
+}
@@ -1,8 +1,10 @@
 //! A resource manager to load meshes.
 
+use crate::loader::import::ImportOptions;
 use crate::loader::mtl::MtlMaterial;
 use crate::loader::obj;
-use crate::resource::Mesh;
+use crate::loader::progress::LoadProgress;
+use crate::resource::{Mesh, MeshLoadOptions};
 use ncollide3d::procedural;
 use ncollide3d::procedural::TriMesh;
 use std::cell::RefCell;
@@ -11,6 +13,9 @@ use std::io::Result as IoResult;
 use std::path::Path;
 use std::rc::Rc;
 
+// The meshes of an obj file, with their names and materials.
+type ObjMeshes = Vec<(String, Rc<RefCell<Mesh>>, Option<MtlMaterial>)>;
+
 /// The mesh manager.
 ///
 /// Upon construction, it contains:
@@ -74,12 +79,64 @@ impl MeshManager {
 
     // FIXME: is this the right place to put this?
     /// Loads the meshes described by an obj file.
-    pub fn load_obj(
+    pub fn load_obj(path: &Path, mtl_dir: &Path, geometry_name: &str) -> IoResult<ObjMeshes> {
+        MeshManager::load_obj_with_options(path, mtl_dir, geometry_name, &MeshLoadOptions::new())
+    }
+
+    /// Loads the meshes described by an obj file, stored on the GPU as specified by `options`.
+    pub fn load_obj_with_options(
+        path: &Path,
+        mtl_dir: &Path,
+        geometry_name: &str,
+        options: &MeshLoadOptions,
+    ) -> IoResult<ObjMeshes> {
+        MeshManager::load_obj_with_import_options(
+            path,
+            mtl_dir,
+            geometry_name,
+            options,
+            &ImportOptions::new(),
+        )
+    }
+
+    /// Loads the meshes described by an obj file, converted to the coordinate system of kiss3d
+    /// as specified by `import_options`, and stored on the GPU as specified by `options`.
+    pub fn load_obj_with_import_options(
         path: &Path,
         mtl_dir: &Path,
         geometry_name: &str,
-    ) -> IoResult<Vec<(String, Rc<RefCell<Mesh>>, Option<MtlMaterial>)>> {
-        obj::parse_file(path, mtl_dir, geometry_name).map(|ms| {
+        options: &MeshLoadOptions,
+        import_options: &ImportOptions,
+    ) -> IoResult<ObjMeshes> {
+        MeshManager::load_obj_with_progress(
+            path,
+            mtl_dir,
+            geometry_name,
+            options,
+            import_options,
+            &mut |_| {},
+        )
+    }
+
+    /// Loads the meshes described by an obj file like `load_obj_with_import_options`, reporting
+    /// the bytes read and the meshes built to `progress`.
+    pub fn load_obj_with_progress(
+        path: &Path,
+        mtl_dir: &Path,
+        geometry_name: &str,
+        options: &MeshLoadOptions,
+        import_options: &ImportOptions,
+        progress: &mut dyn FnMut(LoadProgress),
+    ) -> IoResult<ObjMeshes> {
+        obj::parse_file_with_progress(
+            path,
+            mtl_dir,
+            geometry_name,
+            options,
+            import_options,
+            progress,
+        )
+        .map(|ms| {
             let mut res = Vec::new();
 
             for (n, m, mat) in ms.into_iter() {
@@ -91,4 +148,22 @@ impl MeshManager {
             res
         })
     }
+
+    /// Loads the meshes described by the content of an obj file, reading its material libraries
+    /// through `mtl_resolver` instead of the filesystem. See `obj::parse_from_memory`.
+    pub fn load_obj_from_memory(
+        data: &[u8],
+        geometry_name: &str,
+        options: &MeshLoadOptions,
+        import_options: &ImportOptions,
+        mtl_resolver: &mut dyn FnMut(&str) -> Option<Vec<u8>>,
+    ) -> IoResult<ObjMeshes> {
+        obj::parse_from_memory(data, geometry_name, options, import_options, mtl_resolver).map(
+            |ms| {
+                ms.into_iter()
+                    .map(|(n, m, mat)| (n, Rc::new(RefCell::new(m)), mat))
+                    .collect()
+            },
+        )
+    }
 }
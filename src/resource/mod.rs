@@ -6,25 +6,47 @@ pub use crate::resource::framebuffer_manager::{
     FramebufferManager, OffscreenBuffers, RenderTarget,
 };
 pub use crate::resource::gl_primitive::GLPrimitive;
-pub use crate::resource::gpu_vector::{AllocationType, BufferType, GPUVec};
+pub use crate::resource::gpu_vector::{AllocationType, BufferType, GPUVec, UpdateStrategy};
+pub use crate::resource::isoline::Isoline;
 pub use crate::resource::material::{Material, PlanarMaterial};
 pub use crate::resource::material_manager::MaterialManager;
 pub use crate::resource::mesh::Mesh;
+pub use crate::resource::mesh_bvh::{MeshBvh, MeshRayHit};
 pub use crate::resource::mesh_manager::MeshManager;
 pub use crate::resource::planar_material_manager::PlanarMaterialManager;
 pub use crate::resource::planar_mesh::PlanarMesh;
 pub use crate::resource::planar_mesh_manager::PlanarMeshManager;
+pub use crate::resource::shader_error::{ShaderError, ShaderStage};
+pub use crate::resource::shader_library::{
+    register_shader_snippet, resolve_shader_includes, shader_snippet,
+};
+pub use crate::resource::skeleton::{JointTransform, Skeleton, MAX_JOINTS};
+pub use crate::resource::texture_atlas::{AtlasRegion, TextureAtlas, TextureAtlasPacker};
 pub use crate::resource::texture_manager::{TextureManager, TextureWrapping};
+pub use crate::resource::vertex_format::{MeshLoadOptions, VertexFormat};
+#[cfg(all(feature = "video", not(target_arch = "wasm32")))]
+pub use crate::resource::video_texture::VideoTexture;
 
 mod effect;
 mod framebuffer_manager;
 mod gl_primitive;
 mod gpu_vector;
+mod isoline;
 pub mod material;
 mod material_manager;
 mod mesh;
+mod mesh_bvh;
 mod mesh_manager;
 mod planar_material_manager;
 mod planar_mesh;
 mod planar_mesh_manager;
+mod shader_error;
+mod shader_library;
+mod skeleton;
+mod texture_atlas;
 mod texture_manager;
+#[cfg(not(target_arch = "wasm32"))]
+mod texture_streamer;
+mod vertex_format;
+#[cfg(all(feature = "video", not(target_arch = "wasm32")))]
+mod video_texture;
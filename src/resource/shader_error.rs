@@ -0,0 +1,164 @@
+use std::fmt;
+use std::io;
+use std::path::PathBuf;
+
+use crate::resource::shader_library::MappedSource;
+
+/// A stage of a shader program.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum ShaderStage {
+    /// The vertex shader.
+    Vertex,
+    /// The geometry shader.
+    Geometry,
+    /// The fragment shader.
+    Fragment,
+}
+
+impl fmt::Display for ShaderStage {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ShaderStage::Vertex => write!(f, "vertex shader"),
+            ShaderStage::Geometry => write!(f, "geometry shader"),
+            ShaderStage::Fragment => write!(f, "fragment shader"),
+        }
+    }
+}
+
+/// An error raised while creating an `Effect`.
+///
+/// The line numbers reported refer to the original files and snippets the shaders have been
+/// assembled from, not to the sources given to the driver after the `#include` directives have
+/// been replaced and the preprocessor definitions inserted.
+#[derive(Debug)]
+pub enum ShaderError {
+    /// A shader file could not be read.
+    Io(PathBuf, io::Error),
+    /// A shader includes a snippet that is not registered in the shader library.
+    UnknownInclude {
+        /// The shader including the snippet.
+        stage: ShaderStage,
+        /// The name of the missing snippet.
+        name: String,
+        /// The file or snippet containing the `#include` directive, with its line number.
+        location: String,
+    },
+    /// A shader does not compile.
+    Compilation {
+        /// The shader that does not compile.
+        stage: ShaderStage,
+        /// The compilation log of the driver, with its line numbers replaced by the file or
+        /// snippet they come from, each one followed by the offending line.
+        log: String,
+    },
+    /// The shaders do not link into a program.
+    Link {
+        /// The link log of the driver.
+        log: String,
+    },
+    /// The context does not support uniform buffers.
+    UniformBuffersUnsupported,
+    /// The context does not support geometry shaders.
+    GeometryShadersUnsupported,
+}
+
+impl fmt::Display for ShaderError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ShaderError::Io(path, e) => {
+                write!(f, "unable to read the shader {}: {}", path.display(), e)
+            }
+            ShaderError::UnknownInclude {
+                stage,
+                name,
+                location,
+            } => write!(f, "{}: {}: unknown include \"{}\"", stage, location, name),
+            ShaderError::Compilation { stage, log } => {
+                write!(f, "{} compilation failed:\n{}", stage, log)
+            }
+            ShaderError::Link { log } => write!(f, "shader program link failed:\n{}", log),
+            ShaderError::UniformBuffersUnsupported => {
+                write!(f, "uniform buffers are not supported by the context")
+            }
+            ShaderError::GeometryShadersUnsupported => {
+                write!(f, "geometry shaders are not supported by the context")
+            }
+        }
+    }
+}
+
+impl std::error::Error for ShaderError {}
+
+// Replaces the line numbers of a compilation log by the files and lines of the original sources.
+//
+// Drivers report locations as `0:12` (ANGLE, Mesa, most vendors) or `0(12)` (NVIDIA), where `0`
+// is the index of the source string: only the first location of each line is mapped.
+pub(crate) fn map_compilation_log(log: &str, source: &MappedSource) -> String {
+    let mut result = String::new();
+
+    for line in log.lines() {
+        let mapped = log_location(line).and_then(|(start, end, number)| {
+            let origin = source.origin(number)?;
+            let text = source.line(number)?;
+
+            Some(format!(
+                "{}{}{}\n    | {}",
+                &line[..start],
+                origin,
+                &line[end..],
+                text.trim()
+            ))
+        });
+
+        match mapped {
+            Some(mapped) => result.push_str(&mapped),
+            None => result.push_str(line),
+        }
+
+        result.push('\n');
+    }
+
+    result
+}
+
+// The byte range of the first `string:line` or `string(line)` location of a log line, with the
+// line number.
+fn log_location(line: &str) -> Option<(usize, usize, usize)> {
+    let bytes = line.as_bytes();
+
+    for start in 0..bytes.len() {
+        if !bytes[start].is_ascii_digit() || (start > 0 && bytes[start - 1].is_ascii_alphanumeric())
+        {
+            continue;
+        }
+
+        let string_end = start + digits(&bytes[start..]);
+        let (open, close) = match bytes.get(string_end) {
+            Some(b':') => (string_end + 1, None),
+            Some(b'(') => (string_end + 1, Some(b')')),
+            _ => continue,
+        };
+
+        let len = digits(&bytes[open..]);
+
+        if len == 0 {
+            continue;
+        }
+
+        let number = line[open..open + len].parse().ok()?;
+
+        match close {
+            None => return Some((start, open + len, number)),
+            Some(c) if bytes.get(open + len) == Some(&c) => {
+                return Some((start, open + len + 1, number))
+            }
+            Some(_) => continue,
+        }
+    }
+
+    None
+}
+
+fn digits(bytes: &[u8]) -> usize {
+    bytes.iter().take_while(|b| b.is_ascii_digit()).count()
+}
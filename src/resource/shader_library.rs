@@ -0,0 +1,238 @@
+//! A library of GLSL snippets shared by shaders.
+//!
+//! The sources of an `Effect` may contain `#include "name"` directives, each one replaced by the
+//! snippet registered with that name. The library initially contains the building blocks of the
+//! built-in shaders:
+//!
+//! * `kiss3d/lighting`: the Blinn-Phong model and a fresnel factor.
+//! * `kiss3d/fog`: linear and exponential fog.
+//! * `kiss3d/tonemap`: Reinhard and ACES tone mapping, and sRGB conversions.
+//! * `kiss3d/noise`: interleaved gradient noise, hashing, and value noise.
+//! * `kiss3d/frame`: the `proj`, `view`, and `light_position` uniforms, declared in the
+//!   `Kiss3dFrame` uniform block when `UNIFORM_BUFFERS` is defined.
+//! * `kiss3d/shadow`: the packing of depths into colors, and the sampling of a packed shadow map.
+//!
+//! All their functions are prefixed by `kiss3d_`. A snippet is included at most once per shader,
+//! so snippets can include the ones they depend on.
+//!
+//! ```no_run
+//! # use kiss3d::window::Window;
+//! use kiss3d::resource::{register_shader_snippet, Effect};
+//!
+//! # let _window = Window::new("Kiss3d: includes");
+//! register_shader_snippet("my_app/pulse", "float pulse(float t) { return 0.5 + 0.5 * sin(t); }");
+//!
+//! let fragment = "#version 100
+//! precision mediump float;
+//! #include \"kiss3d/tonemap\"
+//! #include \"my_app/pulse\"
+//! uniform float time;
+//!
+//! void main() {
+//!     gl_FragColor = vec4(kiss3d_tonemap_reinhard(vec3(pulse(time) * 4.0)), 1.0);
+//! }
+//! ";
+//! let vertex = "#version 100
+//! attribute vec3 position;
+//! void main() { gl_Position = vec4(position, 1.0); }
+//! ";
+//! match Effect::new_from_str(vertex, fragment) {
+//!     Ok(_effect) => { /* ... */ }
+//!     // The errors refer to the lines of `my_app/pulse` or of the fragment shader.
+//!     Err(e) => eprintln!("{}", e),
+//! }
+//! ```
+
+use std::cell::RefCell;
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+use std::rc::Rc;
+
+thread_local!(static SNIPPETS: RefCell<HashMap<String, String>> = RefCell::new(builtin_snippets()));
+
+fn builtin_snippets() -> HashMap<String, String> {
+    let snippets = [
+        (
+            "kiss3d/lighting",
+            include_str!("../builtin/glsl/lighting.glsl"),
+        ),
+        ("kiss3d/fog", include_str!("../builtin/glsl/fog.glsl")),
+        (
+            "kiss3d/tonemap",
+            include_str!("../builtin/glsl/tonemap.glsl"),
+        ),
+        ("kiss3d/noise", include_str!("../builtin/glsl/noise.glsl")),
+        ("kiss3d/frame", include_str!("../builtin/glsl/frame.glsl")),
+        ("kiss3d/shadow", include_str!("../builtin/glsl/shadow.glsl")),
+    ];
+
+    snippets
+        .iter()
+        .map(|(name, source)| (name.to_string(), source.to_string()))
+        .collect()
+}
+
+/// Registers a snippet that shaders can include with `#include "name"`.
+///
+/// This replaces any snippet previously registered with the same name. Only the shaders created
+/// afterward are affected.
+pub fn register_shader_snippet(name: &str, source: &str) {
+    SNIPPETS.with(|s| {
+        let _ = s.borrow_mut().insert(name.to_string(), source.to_string());
+    })
+}
+
+/// The source of the snippet registered with the given name.
+pub fn shader_snippet(name: &str) -> Option<String> {
+    SNIPPETS.with(|s| s.borrow().get(name).cloned())
+}
+
+/// Replaces the `#include "name"` directives of a shader source by the registered snippets.
+///
+/// The names may also be delimited by angle brackets. Returns the name of the first snippet that
+/// is not registered as an error.
+pub fn resolve_shader_includes(source: &str) -> Result<String, String> {
+    MappedSource::resolve(source, "source")
+        .map(|mapped| mapped.text())
+        .map_err(|e| e.name)
+}
+
+// The place of a line of a shader in the files it has been assembled from.
+#[derive(Clone, Debug)]
+pub(crate) struct SourceLine {
+    // The name of the file or snippet the line comes from.
+    pub file: Rc<str>,
+    // The number of the line in that file, starting at 1, or 0 for generated lines.
+    pub line: usize,
+}
+
+impl fmt::Display for SourceLine {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.line == 0 {
+            write!(f, "{}", self.file)
+        } else {
+            write!(f, "{}:{}", self.file, self.line)
+        }
+    }
+}
+
+// An `#include` directive naming a snippet that is not registered.
+pub(crate) struct UnknownInclude {
+    pub name: String,
+    pub at: SourceLine,
+}
+
+// A shader source with its includes resolved, remembering where each of its lines comes from.
+#[derive(Clone)]
+pub(crate) struct MappedSource {
+    lines: Vec<(String, SourceLine)>,
+}
+
+impl MappedSource {
+    // Resolves the includes of `source`, whose own lines are attributed to the file `origin`.
+    pub fn resolve(source: &str, origin: &str) -> Result<MappedSource, UnknownInclude> {
+        let mut included = HashSet::new();
+        let mut lines = Vec::new();
+
+        resolve(source, Rc::from(origin), &mut included, &mut lines)?;
+
+        Ok(MappedSource { lines })
+    }
+
+    // Inserts some generated lines after the `#version` directive, which must come first,
+    // possibly replacing it by `version`.
+    pub fn insert_header(&mut self, version: Option<&str>, header: &[String]) {
+        if header.is_empty() && version.is_none() {
+            return;
+        }
+
+        let generated = SourceLine {
+            file: Rc::from("<generated>"),
+            line: 0,
+        };
+        let first = self.lines.iter().position(|(l, _)| !l.trim().is_empty());
+        let at = match first {
+            Some(i) if self.lines[i].0.trim_start().starts_with("#version") => {
+                let _ = self.lines.drain(..i);
+
+                if let Some(version) = version {
+                    self.lines[0].0 = version.to_string();
+                }
+
+                1
+            }
+            _ => {
+                if let Some(version) = version {
+                    self.lines
+                        .insert(0, (version.to_string(), generated.clone()));
+                    1
+                } else {
+                    0
+                }
+            }
+        };
+
+        let header = header.iter().map(|l| (l.clone(), generated.clone()));
+        let _ = self.lines.splice(at..at, header);
+    }
+
+    // The source code.
+    pub fn text(&self) -> String {
+        let mut result = String::new();
+
+        for (line, _) in &self.lines {
+            result.push_str(line);
+            result.push('\n');
+        }
+
+        result
+    }
+
+    // The origin of the line of the source code with the given number, starting at 1.
+    pub fn origin(&self, line: usize) -> Option<&SourceLine> {
+        self.lines.get(line.checked_sub(1)?).map(|l| &l.1)
+    }
+
+    // The text of the line of the source code with the given number, starting at 1.
+    pub fn line(&self, line: usize) -> Option<&str> {
+        self.lines.get(line.checked_sub(1)?).map(|l| &l.0[..])
+    }
+}
+
+fn resolve(
+    source: &str,
+    origin: Rc<str>,
+    included: &mut HashSet<String>,
+    out: &mut Vec<(String, SourceLine)>,
+) -> Result<(), UnknownInclude> {
+    for (i, line) in source.lines().enumerate() {
+        let at = SourceLine {
+            file: origin.clone(),
+            line: i + 1,
+        };
+
+        match include_name(line) {
+            Some(name) => {
+                if included.insert(name.to_string()) {
+                    let snippet = shader_snippet(name).ok_or_else(|| UnknownInclude {
+                        name: name.to_string(),
+                        at,
+                    })?;
+                    resolve(&snippet, Rc::from(name), included, out)?;
+                }
+            }
+            None => out.push((line.to_string(), at)),
+        }
+    }
+
+    Ok(())
+}
+// The name of the snippet included by a line, if it is an `#include` directive.
+fn include_name(line: &str) -> Option<&str> {
+    let directive = line.trim().strip_prefix('#')?.trim_start();
+    let name = directive.strip_prefix("include")?.trim();
+
+    name.strip_prefix('"')
+        .and_then(|n| n.strip_suffix('"'))
+        .or_else(|| name.strip_prefix('<').and_then(|n| n.strip_suffix('>')))
+}
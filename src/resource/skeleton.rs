@@ -0,0 +1,241 @@
+//! Hierarchies of joints deforming skinned meshes.
+
+use na::{Isometry3, Matrix4, Translation3, UnitQuaternion, Vector3};
+
+/// The maximum number of joints of a skeleton deforming the meshes drawn by the default
+/// material. The other joints do not move the vertices.
+pub const MAX_JOINTS: usize = 64;
+
+/// The transformation of a joint relative to its parent: a scaling, followed by a rotation and a
+/// translation.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct JointTransform {
+    /// The translation of the joint.
+    pub translation: Vector3<f32>,
+    /// The rotation of the joint.
+    pub rotation: UnitQuaternion<f32>,
+    /// The scale factors of the joint along each axis.
+    pub scale: Vector3<f32>,
+}
+
+impl JointTransform {
+    /// The transformation leaving a joint at the origin of its parent.
+    pub fn identity() -> JointTransform {
+        JointTransform {
+            translation: Vector3::zeros(),
+            rotation: UnitQuaternion::identity(),
+            scale: Vector3::repeat(1.0),
+        }
+    }
+
+    /// A transformation without scaling.
+    pub fn from_isometry(isometry: &Isometry3<f32>) -> JointTransform {
+        JointTransform {
+            translation: isometry.translation.vector,
+            rotation: isometry.rotation,
+            scale: Vector3::repeat(1.0),
+        }
+    }
+
+    /// This transformation as a matrix.
+    pub fn to_homogeneous(&self) -> Matrix4<f32> {
+        Translation3::from(self.translation).to_homogeneous()
+            * self.rotation.to_homogeneous()
+            * Matrix4::new_nonuniform_scaling(&self.scale)
+    }
+
+    /// Interpolates between this transformation, at `t = 0.0`, and `other`, at `t = 1.0`.
+    ///
+    /// The rotations are interpolated spherically, and the translations and scales linearly.
+    pub fn interpolate(&self, other: &JointTransform, t: f32) -> JointTransform {
+        JointTransform {
+            translation: self.translation.lerp(&other.translation, t),
+            rotation: self
+                .rotation
+                .try_slerp(&other.rotation, t, 1.0e-6)
+                .unwrap_or(other.rotation),
+            scale: self.scale.lerp(&other.scale, t),
+        }
+    }
+}
+
+impl Default for JointTransform {
+    fn default() -> JointTransform {
+        JointTransform::identity()
+    }
+}
+
+#[derive(Clone, Debug)]
+struct Joint {
+    name: String,
+    parent: Option<usize>,
+    rest_pose: JointTransform,
+    pose: JointTransform,
+    // Transforms the vertices of the meshes from their space to the space of this joint in its
+    // rest pose.
+    inverse_bind: Matrix4<f32>,
+}
+
+/// A hierarchy of joints moving the vertices of skinned meshes.
+///
+/// Each vertex of a skinned mesh, see `Mesh::set_skin`, follows the joints influencing it, from
+/// where they are in their rest pose to where they are in their current pose. A skeleton is
+/// usually shared, with `Rc<RefCell<Skeleton>>`, by the objects it deforms, see
+/// `Object::set_skeleton`, and by an `AnimationPlayer` changing its pose at each frame.
+///
+/// # Example
+/// ```
+/// # use kiss3d::resource::{JointTransform, Skeleton};
+/// # use kiss3d::nalgebra::{Isometry3, UnitQuaternion, Vector3};
+/// let mut skeleton = Skeleton::new();
+/// let shoulder = skeleton.add_joint("shoulder", None, JointTransform::identity());
+/// let elbow = skeleton.add_joint(
+///     "elbow",
+///     Some(shoulder),
+///     JointTransform::from_isometry(&Isometry3::translation(0.0, 1.0, 0.0)),
+/// );
+///
+/// let mut bent = *skeleton.rest_pose(elbow);
+/// bent.rotation = UnitQuaternion::from_axis_angle(&Vector3::z_axis(), 1.0);
+/// skeleton.set_pose(elbow, bent);
+/// assert_eq!(skeleton.joint_index("elbow"), Some(elbow));
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct Skeleton {
+    joints: Vec<Joint>,
+}
+
+impl Skeleton {
+    /// Creates a skeleton without joints.
+    pub fn new() -> Skeleton {
+        Skeleton { joints: Vec::new() }
+    }
+
+    /// Adds a joint to this skeleton, and returns its index.
+    ///
+    /// The joint is placed relative to its parent by `rest_pose`, which is also its current
+    /// pose. The meshes are expected to be modeled around the skeleton in its rest pose, see
+    /// `set_inverse_bind_matrix` otherwise.
+    ///
+    /// # Panics
+    /// If `parent` is not the index of a joint of this skeleton: the parents must be added
+    /// before their children.
+    pub fn add_joint(
+        &mut self,
+        name: &str,
+        parent: Option<usize>,
+        rest_pose: JointTransform,
+    ) -> usize {
+        if let Some(parent) = parent {
+            assert!(
+                parent < self.joints.len(),
+                "The parent of a joint must be added before it."
+            );
+        }
+
+        let parent_global = parent.map_or_else(Matrix4::identity, |p| self.rest_global(p));
+        let global = parent_global * rest_pose.to_homogeneous();
+
+        self.joints.push(Joint {
+            name: name.to_string(),
+            parent,
+            rest_pose,
+            pose: rest_pose,
+            inverse_bind: global.try_inverse().unwrap_or_else(Matrix4::identity),
+        });
+
+        self.joints.len() - 1
+    }
+
+    /// The number of joints of this skeleton.
+    pub fn len(&self) -> usize {
+        self.joints.len()
+    }
+
+    /// Whether this skeleton has no joint.
+    pub fn is_empty(&self) -> bool {
+        self.joints.is_empty()
+    }
+
+    /// The index of the first joint named `name`, if any.
+    pub fn joint_index(&self, name: &str) -> Option<usize> {
+        self.joints.iter().position(|j| j.name == name)
+    }
+
+    /// The name of the `i`-th joint.
+    pub fn joint_name(&self, i: usize) -> &str {
+        &self.joints[i].name
+    }
+
+    /// The index of the parent of the `i`-th joint, if it is not a root.
+    pub fn parent(&self, i: usize) -> Option<usize> {
+        self.joints[i].parent
+    }
+
+    /// The rest pose of the `i`-th joint, relative to its parent.
+    pub fn rest_pose(&self, i: usize) -> &JointTransform {
+        &self.joints[i].rest_pose
+    }
+
+    /// The current pose of the `i`-th joint, relative to its parent.
+    pub fn pose(&self, i: usize) -> &JointTransform {
+        &self.joints[i].pose
+    }
+
+    /// Sets the current pose of the `i`-th joint, relative to its parent.
+    pub fn set_pose(&mut self, i: usize, pose: JointTransform) {
+        self.joints[i].pose = pose
+    }
+
+    /// Puts every joint back in its rest pose.
+    pub fn reset_pose(&mut self) {
+        for joint in &mut self.joints {
+            joint.pose = joint.rest_pose
+        }
+    }
+
+    /// Sets the matrix transforming the vertices of the meshes to the space of the `i`-th joint
+    /// in the pose the meshes were modeled around, e.g., as given by a glTF skin.
+    ///
+    /// It is the inverse of the rest pose of the joint relative to the meshes by default.
+    pub fn set_inverse_bind_matrix(&mut self, i: usize, matrix: Matrix4<f32>) {
+        self.joints[i].inverse_bind = matrix
+    }
+
+    /// The current pose of each joint, relative to the meshes.
+    pub fn global_poses(&self) -> Vec<Matrix4<f32>> {
+        let mut globals: Vec<Matrix4<f32>> = Vec::with_capacity(self.joints.len());
+
+        for joint in &self.joints {
+            let local = joint.pose.to_homogeneous();
+            let global = match joint.parent {
+                Some(p) => globals[p] * local,
+                None => local,
+            };
+            globals.push(global);
+        }
+
+        globals
+    }
+
+    /// The transformation of the vertices influenced by each joint, from where they are modeled
+    /// to where the current pose of the joint moves them.
+    pub fn joint_matrices(&self) -> Vec<Matrix4<f32>> {
+        self.global_poses()
+            .iter()
+            .zip(&self.joints)
+            .map(|(global, joint)| global * joint.inverse_bind)
+            .collect()
+    }
+
+    // The rest pose of the `i`-th joint, relative to the meshes.
+    fn rest_global(&self, i: usize) -> Matrix4<f32> {
+        let joint = &self.joints[i];
+        let local = joint.rest_pose.to_homogeneous();
+
+        match joint.parent {
+            Some(p) => self.rest_global(p) * local,
+            None => local,
+        }
+    }
+}
@@ -0,0 +1,311 @@
+//! Packing of many small textures into shared atlas textures.
+
+use std::collections::HashMap;
+use std::path::Path;
+use std::rc::Rc;
+
+use image::{DynamicImage, GenericImageView, RgbaImage};
+use na::Vector2;
+
+use crate::context::Texture;
+use crate::loader::asset_source;
+use crate::resource::TextureManager;
+
+/// The location of a packed image inside of a `TextureAtlas`.
+#[derive(Clone)]
+pub struct AtlasRegion {
+    texture: Rc<Texture>,
+    page: usize,
+    uv_offset: Vector2<f32>,
+    uv_scale: Vector2<f32>,
+    size: (u32, u32),
+}
+
+impl AtlasRegion {
+    /// The atlas page containing the image.
+    #[inline]
+    pub fn texture(&self) -> &Rc<Texture> {
+        &self.texture
+    }
+
+    /// The index of the atlas page containing the image.
+    #[inline]
+    pub fn page(&self) -> usize {
+        self.page
+    }
+
+    /// The texture coordinates of the top-left corner of the image on its atlas page.
+    #[inline]
+    pub fn uv_offset(&self) -> Vector2<f32> {
+        self.uv_offset
+    }
+
+    /// The size of the image on its atlas page, in texture coordinates.
+    #[inline]
+    pub fn uv_scale(&self) -> Vector2<f32> {
+        self.uv_scale
+    }
+
+    /// The size of the image, in pixels.
+    #[inline]
+    pub fn size(&self) -> (u32, u32) {
+        self.size
+    }
+}
+
+/// Collects images to be packed together by `TextureAtlasPacker::pack`.
+///
+/// Drawing objects sharing an atlas page does not require any texture switch. An object uses an
+/// image of the atlas with `PlanarSceneNode::set_texture_from_atlas`, which sets its texture to the
+/// atlas page and maps its texture coordinates to the region of the image.
+///
+/// Texture coordinates outside of `[0, 1]` can not repeat a packed image: they sample its
+/// neighbours on the atlas page instead.
+pub struct TextureAtlasPacker {
+    max_size: u32,
+    padding: u32,
+    images: Vec<(String, RgbaImage)>,
+}
+
+impl TextureAtlasPacker {
+    /// Creates a packer of atlas pages at most `max_size` pixels wide and high.
+    pub fn new(max_size: u32) -> TextureAtlasPacker {
+        assert!(max_size > 0, "The size of an atlas page must be positive.");
+
+        TextureAtlasPacker {
+            max_size,
+            padding: 2,
+            images: Vec::new(),
+        }
+    }
+
+    /// Sets the number of pixels separating the packed images.
+    ///
+    /// The border pixels of each image are repeated over its padding, so that filtering does not
+    /// blend the images together. The default value is 2.
+    pub fn set_padding(&mut self, padding: u32) {
+        self.padding = padding
+    }
+
+    /// Adds an image to pack, identified by `name`.
+    ///
+    /// Panics if the image does not fit on an atlas page.
+    pub fn add_image(&mut self, image: DynamicImage, name: &str) {
+        let (width, height) = image.dimensions();
+
+        assert!(width > 0 && height > 0, "The image {} is empty.", name);
+        assert!(
+            width + 2 * self.padding <= self.max_size && height + 2 * self.padding <= self.max_size,
+            "The image {} is too large for an atlas page of size {}.",
+            name,
+            self.max_size
+        );
+
+        self.images.push((name.to_string(), image.into_rgba8()))
+    }
+
+    /// Adds an image read from a file to pack, identified by `name`.
+    pub fn add_file(&mut self, path: &Path, name: &str) {
+        let image = asset_source::read_image(path)
+            .unwrap_or_else(|e| panic!("Unable to load texture from file {:?}: {:?}", path, e));
+
+        self.add_image(image, name)
+    }
+
+    /// The number of images added to this packer.
+    pub fn len(&self) -> usize {
+        self.images.len()
+    }
+
+    /// Whether no image has been added to this packer.
+    pub fn is_empty(&self) -> bool {
+        self.images.is_empty()
+    }
+
+    /// Packs the images into as few atlas pages as possible.
+    ///
+    /// The pages are registered by the global `TextureManager` as `name#0`, `name#1`, etc.
+    pub fn pack(self, name: &str) -> TextureAtlas {
+        let padding = self.padding;
+        let mut shelves = ShelfPacker::new(self.max_size);
+        let mut order: Vec<usize> = (0..self.images.len()).collect();
+
+        // Packing the tallest images first leaves less space unused at the top of the shelves.
+        order.sort_by_key(|i| {
+            let image = &self.images[*i].1;
+            (
+                std::cmp::Reverse(image.height()),
+                std::cmp::Reverse(image.width()),
+            )
+        });
+
+        let placements: HashMap<usize, (usize, u32, u32)> = order
+            .into_iter()
+            .map(|i| {
+                let image = &self.images[i].1;
+                let size = (image.width() + 2 * padding, image.height() + 2 * padding);
+                (i, shelves.insert(size))
+            })
+            .collect();
+
+        let mut pages: Vec<RgbaImage> = shelves
+            .page_sizes()
+            .into_iter()
+            .map(|(width, height)| RgbaImage::new(width, height))
+            .collect();
+
+        for (i, (_, image)) in self.images.iter().enumerate() {
+            let (page, x, y) = placements[&i];
+            blit_extruded(&mut pages[page], image, x, y, padding);
+        }
+
+        let sizes: Vec<(u32, u32)> = pages.iter().map(|page| page.dimensions()).collect();
+        let textures: Vec<Rc<Texture>> = TextureManager::get_global_manager(|tm| {
+            pages
+                .drain(..)
+                .enumerate()
+                .map(|(i, page)| {
+                    tm.add_image(DynamicImage::ImageRgba8(page), &format!("{}#{}", name, i))
+                })
+                .collect()
+        });
+
+        let regions = self
+            .images
+            .into_iter()
+            .enumerate()
+            .map(|(i, (name, image))| {
+                let (page, x, y) = placements[&i];
+                let (page_width, page_height) = sizes[page];
+                let page_size = Vector2::new(page_width as f32, page_height as f32);
+                let origin = Vector2::new((x + padding) as f32, (y + padding) as f32);
+                let size = Vector2::new(image.width() as f32, image.height() as f32);
+                let region = AtlasRegion {
+                    texture: textures[page].clone(),
+                    page,
+                    uv_offset: origin.component_div(&page_size),
+                    uv_scale: size.component_div(&page_size),
+                    size: image.dimensions(),
+                };
+
+                (name, region)
+            })
+            .collect();
+
+        TextureAtlas {
+            pages: textures,
+            regions,
+        }
+    }
+}
+
+/// Images packed into shared textures by a `TextureAtlasPacker`.
+pub struct TextureAtlas {
+    pages: Vec<Rc<Texture>>,
+    regions: HashMap<String, AtlasRegion>,
+}
+
+impl TextureAtlas {
+    /// The textures of the atlas pages.
+    pub fn pages(&self) -> &[Rc<Texture>] {
+        &self.pages
+    }
+
+    /// The location of the image packed as `name`, if any.
+    pub fn region(&self, name: &str) -> Option<&AtlasRegion> {
+        self.regions.get(name)
+    }
+
+    /// The names of the packed images.
+    pub fn names(&self) -> impl Iterator<Item = &str> {
+        self.regions.keys().map(|name| name.as_str())
+    }
+}
+
+// A row of rectangles with the height of the first (and tallest) one.
+struct Shelf {
+    y: u32,
+    height: u32,
+    width: u32,
+}
+
+// Places rectangles on shelves stacked on pages, opening a new page when none has enough room.
+struct ShelfPacker {
+    max_size: u32,
+    pages: Vec<Vec<Shelf>>,
+}
+
+impl ShelfPacker {
+    fn new(max_size: u32) -> ShelfPacker {
+        ShelfPacker {
+            max_size,
+            pages: Vec::new(),
+        }
+    }
+
+    // Returns the page and the position of the top-left corner of the rectangle.
+    fn insert(&mut self, (width, height): (u32, u32)) -> (usize, u32, u32) {
+        let max_size = self.max_size;
+
+        for (i, shelves) in self.pages.iter_mut().enumerate() {
+            for shelf in shelves.iter_mut() {
+                if height <= shelf.height && shelf.width + width <= max_size {
+                    let x = shelf.width;
+                    shelf.width += width;
+                    return (i, x, shelf.y);
+                }
+            }
+
+            let top = shelves.last().map_or(0, |shelf| shelf.y + shelf.height);
+
+            if top + height <= max_size {
+                shelves.push(Shelf {
+                    y: top,
+                    height,
+                    width,
+                });
+                return (i, 0, top);
+            }
+        }
+
+        self.pages.push(vec![Shelf {
+            y: 0,
+            height,
+            width,
+        }]);
+
+        (self.pages.len() - 1, 0, 0)
+    }
+
+    // The size of each page, rounded up to powers of two so that mipmaps can be generated on
+    // every platform.
+    fn page_sizes(&self) -> Vec<(u32, u32)> {
+        self.pages
+            .iter()
+            .map(|shelves| {
+                let width = shelves.iter().map(|shelf| shelf.width).max().unwrap_or(1);
+                let height = shelves.last().map_or(1, |shelf| shelf.y + shelf.height);
+
+                (
+                    width.next_power_of_two().min(self.max_size),
+                    height.next_power_of_two().min(self.max_size),
+                )
+            })
+            .collect()
+    }
+}
+
+// Copies `image` to `page`, with its top-left corner at `(x + padding, y + padding)`, and repeats
+// its border pixels over the padding.
+fn blit_extruded(page: &mut RgbaImage, image: &RgbaImage, x: u32, y: u32, padding: u32) {
+    let (width, height) = image.dimensions();
+
+    for j in 0..height + 2 * padding {
+        let src_y = j.saturating_sub(padding).min(height - 1);
+
+        for i in 0..width + 2 * padding {
+            let src_x = i.saturating_sub(padding).min(width - 1);
+            page.put_pixel(x + i, y + j, *image.get_pixel(src_x, src_y));
+        }
+    }
+}
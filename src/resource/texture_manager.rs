@@ -3,10 +3,15 @@
 use image::{self, imageops::FilterType, DynamicImage, GenericImageView};
 use std::collections::hash_map::Entry;
 use std::collections::HashMap;
-use std::path::Path;
+use std::path::{Path, PathBuf};
 use std::rc::Rc;
 
 use crate::context::{Context, Texture};
+use crate::load_error::Error;
+use crate::loader::asset_source;
+use crate::loader::progress::{self, LoadProgress};
+#[cfg(not(target_arch = "wasm32"))]
+use crate::resource::texture_streamer::TextureStreamer;
 
 #[path = "../error.rs"]
 mod error;
@@ -70,6 +75,16 @@ impl Drop for Texture {
     }
 }
 
+// How to upload a texture again when the context is lost.
+enum TextureSource {
+    File(PathBuf, bool),
+    // Images are only kept on platforms where the context may be restored.
+    #[cfg(target_arch = "wasm32")]
+    Image(DynamicImage, bool),
+    #[cfg(not(target_arch = "wasm32"))]
+    Streamed(PathBuf),
+}
+
 // thread_local!(static KEY_TEXTURE_MANAGER: RefCell<Option<TextureManager>> = RefCell::new(Some(TextureManager::new())));
 
 /// The texture manager.
@@ -78,19 +93,35 @@ impl Drop for Texture {
 pub struct TextureManager {
     default_texture: Rc<Texture>,
     textures: HashMap<String, (Rc<Texture>, (u32, u32))>,
+    sources: HashMap<String, TextureSource>,
     // If generate_mipmaps is true, mipmaps are generated for textures when they
     // are loaded.
     generate_mipmaps: bool,
+    #[cfg(not(target_arch = "wasm32"))]
+    streamer: TextureStreamer,
 }
 
 impl TextureManager {
     /// Creates a new texture manager.
     pub fn new() -> TextureManager {
-        let ctxt = Context::get();
         let default_tex = Texture::new();
+        TextureManager::init_default_texture(&default_tex);
+
+        TextureManager {
+            textures: HashMap::new(),
+            sources: HashMap::new(),
+            default_texture: default_tex,
+            generate_mipmaps: false,
+            #[cfg(not(target_arch = "wasm32"))]
+            streamer: TextureStreamer::new(),
+        }
+    }
+
+    fn init_default_texture(tex: &Texture) {
+        let ctxt = Context::get();
         let default_tex_pixels: [u8; 12] = [255; 12];
         verify!(ctxt.active_texture(Context::TEXTURE0));
-        verify!(ctxt.bind_texture(Context::TEXTURE_2D, Some(&*default_tex)));
+        verify!(ctxt.bind_texture(Context::TEXTURE_2D, Some(tex)));
         // verify!(ctxt.tex_parameteri(Context::TEXTURE_2D, Context::TEXTURE_BASE_LEVEL, 0));
         // verify!(ctxt.tex_parameteri(Context::TEXTURE_2D, Context::TEXTURE_MAX_LEVEL, 0));
         verify!(ctxt.tex_parameteri(
@@ -124,12 +155,6 @@ impl TextureManager {
             Context::RGB,
             Some(&default_tex_pixels)
         ));
-
-        TextureManager {
-            textures: HashMap::new(),
-            default_texture: default_tex,
-            generate_mipmaps: false,
-        }
     }
 
     /// Mutably applies a function to the texture manager.
@@ -155,6 +180,14 @@ impl TextureManager {
             .map(|t| (t.0.clone(), t.1))
     }
 
+    /// Removes the texture with the specified name from this manager, and returns it.
+    ///
+    /// The texture is freed once the objects using it drop it.
+    pub fn remove(&mut self, name: &str) -> Option<Rc<Texture>> {
+        let _ = self.sources.remove(name);
+        self.textures.remove(name).map(|t| t.0)
+    }
+
     /// Allocates a new texture that is not yet configured.
     ///
     /// If a texture with same name exists, nothing is created and the old texture is returned.
@@ -170,6 +203,15 @@ impl TextureManager {
     /// If a texture with same name exists, nothing is created and the old texture is returned.
     pub fn add_image(&mut self, image: DynamicImage, name: &str) -> Rc<Texture> {
         let generate_mipmaps = self.generate_mipmaps;
+
+        #[cfg(target_arch = "wasm32")]
+        {
+            if !self.textures.contains_key(name) {
+                let source = TextureSource::Image(image.clone(), generate_mipmaps);
+                let _ = self.sources.insert(name.to_string(), source);
+            }
+        }
+
         self.textures
             .entry(name.to_string())
             .or_insert_with(|| {
@@ -179,6 +221,16 @@ impl TextureManager {
             .clone()
     }
 
+    /// Allocates a new texture read from a `DynamicImage` object, without adding it to the cache.
+    ///
+    /// The texture is deleted once dropped, and is not uploaded again when the context is
+    /// restored.
+    pub(crate) fn load_image(&self, image: DynamicImage) -> Rc<Texture> {
+        TextureManager::load_texture_into_context(image, self.generate_mipmaps)
+            .unwrap()
+            .0
+    }
+
     /// Allocates a new texture and tries to decode it from bytes array
     /// Panics if unable to do so
     /// If a texture with same name exists, nothing is created and the old texture is returned.
@@ -189,25 +241,71 @@ impl TextureManager {
         )
     }
 
+    /// Allocates a new texture decoded from the content of an image file, e.g., embedded in the
+    /// binary with `include_bytes!`.
+    ///
+    /// Returns an error if the image cannot be decoded or uploaded. If a texture with same name
+    /// exists, nothing is created and the old texture is returned.
+    pub fn add_from_memory(&mut self, data: &[u8], name: &str) -> Result<Rc<Texture>, Error> {
+        if let Some(texture) = self.get(name) {
+            return Ok(texture);
+        }
+
+        let generate_mipmaps = self.generate_mipmaps;
+        let image =
+            image::load_from_memory(data).map_err(|e| Error::Image(PathBuf::from(name), e))?;
+
+        #[cfg(target_arch = "wasm32")]
+        {
+            let source = TextureSource::Image(image.clone(), generate_mipmaps);
+            let _ = self.sources.insert(name.to_string(), source);
+        }
+
+        let entry = TextureManager::load_texture_into_context(image, generate_mipmaps)
+            .map_err(|e| Error::Texture(PathBuf::from(name), e))?;
+
+        Ok(self
+            .textures
+            .entry(name.to_string())
+            .or_insert(entry)
+            .0
+            .clone())
+    }
+
     /// Allocates a new texture read from a file.
-    fn load_texture_from_file(path: &Path, generate_mipmaps: bool) -> (Rc<Texture>, (u32, u32)) {
-        let image = image::open(path)
-            .unwrap_or_else(|e| panic!("Unable to load texture from file {:?}: {:?}", path, e));
+    fn load_texture_from_file(
+        path: &Path,
+        generate_mipmaps: bool,
+    ) -> Result<(Rc<Texture>, (u32, u32)), Error> {
+        let bytes = asset_source::read(path).map_err(|e| Error::Io(path.to_path_buf(), e))?;
+        let image = asset_source::decode_image(&bytes, path)
+            .map_err(|e| Error::Image(path.to_path_buf(), e))?;
         TextureManager::load_texture_into_context(image, generate_mipmaps)
-            .unwrap_or_else(|e| panic!("Unable to upload texture {:?}: {:?}", path, e))
+            .map_err(|e| Error::Texture(path.to_path_buf(), e))
     }
 
     fn load_texture_into_context(
         image: DynamicImage,
         generate_mipmaps: bool,
     ) -> Result<(Rc<Texture>, (u32, u32)), &'static str> {
-        let ctxt = Context::get();
         let tex = Texture::new();
+        let size = image.dimensions();
+        TextureManager::upload_image(&tex, image, generate_mipmaps)?;
+        Ok((tex, size))
+    }
+
+    fn upload_image(
+        tex: &Texture,
+        image: DynamicImage,
+        generate_mipmaps: bool,
+    ) -> Result<(), &'static str> {
+        let ctxt = Context::get();
         let (width, height) = image.dimensions();
+        let image = to_uploadable(image);
 
         unsafe {
             verify!(ctxt.active_texture(Context::TEXTURE0));
-            verify!(ctxt.bind_texture(Context::TEXTURE_2D, Some(&*tex)));
+            verify!(ctxt.bind_texture(Context::TEXTURE_2D, Some(tex)));
             TextureManager::call_tex_image2d(&ctxt, &image, 0)?;
 
             let mut min_filter = Context::LINEAR;
@@ -248,7 +346,7 @@ impl TextureManager {
                 Context::LINEAR as i32
             ));
         }
-        Ok((tex, (width, height)))
+        Ok(())
     }
 
     fn call_tex_image2d(
@@ -280,13 +378,62 @@ impl TextureManager {
 
     /// Allocates a new texture read from a file. If a texture with same name exists, nothing is
     /// created and the old texture is returned.
-    pub fn add(&mut self, path: &Path, name: &str) -> Rc<Texture> {
+    ///
+    /// Returns an error if the file cannot be read or decoded.
+    pub fn add(&mut self, path: &Path, name: &str) -> Result<Rc<Texture>, Error> {
+        if let Some(texture) = self.get(name) {
+            return Ok(texture);
+        }
+
         let generate_mipmaps = self.generate_mipmaps;
-        self.textures
+        let entry = TextureManager::load_texture_from_file(path, generate_mipmaps)?;
+        let source = TextureSource::File(path.to_path_buf(), generate_mipmaps);
+        let _ = self.sources.insert(name.to_string(), source);
+
+        Ok(self
+            .textures
             .entry(name.to_string())
-            .or_insert_with(|| TextureManager::load_texture_from_file(path, generate_mipmaps))
+            .or_insert(entry)
             .0
-            .clone()
+            .clone())
+    }
+
+    /// Allocates a new texture read from a file like `add`, reporting the bytes read and the
+    /// decoding of the texture to `progress`.
+    ///
+    /// Nothing is reported if a texture with same name exists.
+    pub fn add_with_progress(
+        &mut self,
+        path: &Path,
+        name: &str,
+        progress: &mut dyn FnMut(LoadProgress),
+    ) -> Result<Rc<Texture>, Error> {
+        if let Some(texture) = self.get(name) {
+            return Ok(texture);
+        }
+
+        let generate_mipmaps = self.generate_mipmaps;
+        let bytes =
+            progress::read_file(path, progress).map_err(|e| Error::Io(path.to_path_buf(), e))?;
+        let image = asset_source::decode_image(&bytes, path)
+            .map_err(|e| Error::Image(path.to_path_buf(), e))?;
+        let entry = TextureManager::load_texture_into_context(image, generate_mipmaps)
+            .map_err(|e| Error::Texture(path.to_path_buf(), e))?;
+        let source = TextureSource::File(path.to_path_buf(), generate_mipmaps);
+        let _ = self.sources.insert(name.to_string(), source);
+
+        progress(LoadProgress::TextureDecoded {
+            path,
+            decoded: 1,
+            total: 1,
+        });
+
+        Ok(self
+            .textures
+            .entry(name.to_string())
+            .or_insert(entry)
+            .0
+            .clone())
     }
 
     /// Changes whether textures will have mipmaps generated when they are
@@ -295,4 +442,147 @@ impl TextureManager {
     pub fn set_generate_mipmaps(&mut self, enabled: bool) {
         self.generate_mipmaps = enabled;
     }
+
+    /// Allocates a new texture streamed from a file on a background thread. If a texture with
+    /// same name exists, nothing is created and the old texture is returned.
+    ///
+    /// The texture is usable immediately, but remains completely white until its first mipmap
+    /// is loaded. Its mipmaps are then uploaded from the lowest resolution to the highest by
+    /// `update_streaming`, within the budgets set by `set_streaming_budget`. Panics if the
+    /// size of the image cannot be read.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn add_streamed(&mut self, path: &Path, name: &str) -> Rc<Texture> {
+        if let Some(texture) = self.textures.get(name) {
+            return texture.0.clone();
+        }
+
+        let size = image::image_dimensions(path)
+            .unwrap_or_else(|e| panic!("Unable to load texture from file {:?}: {:?}", path, e));
+        let tex = Texture::new();
+        TextureManager::init_streamed_texture(&tex);
+
+        self.streamer.stream(path.to_path_buf(), &tex);
+        let source = TextureSource::Streamed(path.to_path_buf());
+        let _ = self.sources.insert(name.to_string(), source);
+        let _ = self.textures.insert(name.to_string(), (tex.clone(), size));
+        tex
+    }
+
+    // Makes `tex` a white texture until its first mipmap is streamed.
+    #[cfg(not(target_arch = "wasm32"))]
+    fn init_streamed_texture(tex: &Texture) {
+        let ctxt = Context::get();
+        let white: [u8; 3] = [255; 3];
+
+        verify!(ctxt.active_texture(Context::TEXTURE0));
+        verify!(ctxt.bind_texture(Context::TEXTURE_2D, Some(tex)));
+        verify!(ctxt.tex_image2d(
+            Context::TEXTURE_2D,
+            0,
+            Context::RGB as i32,
+            1,
+            1,
+            0,
+            Context::RGB,
+            Some(&white)
+        ));
+        verify!(ctxt.tex_parameteri(
+            Context::TEXTURE_2D,
+            Context::TEXTURE_WRAP_S,
+            Context::CLAMP_TO_EDGE as i32
+        ));
+        verify!(ctxt.tex_parameteri(
+            Context::TEXTURE_2D,
+            Context::TEXTURE_WRAP_T,
+            Context::CLAMP_TO_EDGE as i32
+        ));
+        verify!(ctxt.tex_parameteri(
+            Context::TEXTURE_2D,
+            Context::TEXTURE_MIN_FILTER,
+            Context::LINEAR_MIPMAP_LINEAR as i32
+        ));
+        verify!(ctxt.tex_parameteri(
+            Context::TEXTURE_2D,
+            Context::TEXTURE_MAG_FILTER,
+            Context::LINEAR as i32
+        ));
+    }
+
+    /// Sets the budgets of texture streaming.
+    ///
+    /// At most `upload_bytes_per_frame` bytes of mipmaps are uploaded by each call to
+    /// `update_streaming` (though at least one mipmap chain is). Once the streamed textures use
+    /// `memory_bytes` bytes of GPU memory, they stop gaining resolution. Defaults to 16MiB per
+    /// frame and no memory limit.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn set_streaming_budget(&mut self, upload_bytes_per_frame: usize, memory_bytes: usize) {
+        self.streamer
+            .set_budgets(upload_bytes_per_frame, memory_bytes)
+    }
+
+    /// Uploads the mipmaps of streamed textures loaded since the last call.
+    ///
+    /// This is called by the window at the beginning of each frame. Returns `true` if at least
+    /// one texture gained resolution.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn update_streaming(&mut self) -> bool {
+        self.streamer.update()
+    }
+
+    /// The number of streamed textures that have not reached their full resolution yet.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn num_streaming(&self) -> usize {
+        self.streamer.num_streaming()
+    }
+
+    /// The number of bytes of GPU memory used by the mipmaps of the streamed textures.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn streaming_resident_bytes(&self) -> usize {
+        self.streamer.resident_bytes()
+    }
+
+    /// Uploads every texture again after the context has been restored.
+    ///
+    /// Textures loaded from files are read again. Textures created from images are only restored
+    /// on WebGL, where the images are kept in memory. The content of empty textures is lost.
+    pub(crate) fn restore(&mut self) {
+        let ctxt = Context::get();
+        ctxt.recreate_texture(&self.default_texture);
+        TextureManager::init_default_texture(&self.default_texture);
+
+        for (name, (tex, _)) in self.textures.iter() {
+            ctxt.recreate_texture(tex);
+
+            let result = match self.sources.get(name) {
+                Some(TextureSource::File(path, generate_mipmaps)) => asset_source::read_image(path)
+                    .map_err(|_| "Failed to read texture file.")
+                    .and_then(|image| TextureManager::upload_image(tex, image, *generate_mipmaps)),
+                #[cfg(target_arch = "wasm32")]
+                Some(TextureSource::Image(image, generate_mipmaps)) => {
+                    TextureManager::upload_image(tex, image.clone(), *generate_mipmaps)
+                }
+                #[cfg(not(target_arch = "wasm32"))]
+                Some(TextureSource::Streamed(path)) => {
+                    TextureManager::init_streamed_texture(tex);
+                    self.streamer.stream(path.clone(), tex);
+                    Ok(())
+                }
+                None => Ok(()),
+            };
+
+            if let Err(e) = result {
+                println!("Warning: unable to restore texture {}: {}", name, e);
+            }
+        }
+    }
+}
+
+// Converts an image to 8-bit RGB or RGBA, the formats supported by every OpenGL version. Images
+// with more bits per channel, e.g. 16-bit PNG or EXR files, lose their additional precision.
+pub(crate) fn to_uploadable(image: DynamicImage) -> DynamicImage {
+    match image {
+        DynamicImage::ImageRgb8(_) | DynamicImage::ImageRgba8(_) => image,
+        _ if image.color().has_alpha() => DynamicImage::ImageRgba8(image.to_rgba8()),
+        _ => DynamicImage::ImageRgb8(image.to_rgb8()),
+    }
 }
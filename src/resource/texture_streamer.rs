@@ -0,0 +1,250 @@
+//! Background loading of texture mipmaps, from the lowest resolution to the highest.
+
+use image::{self, imageops::FilterType, DynamicImage, GenericImageView};
+use std::collections::{HashMap, VecDeque};
+use std::path::PathBuf;
+use std::rc::{Rc, Weak};
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::thread;
+
+use crate::context::{Context, Texture};
+use crate::loader::asset_source;
+use crate::resource::texture_manager;
+
+#[path = "../error.rs"]
+mod error;
+
+enum StreamMessage {
+    // A mipmap level of a streamed texture, level 0 being the full-resolution image.
+    Level {
+        id: usize,
+        level: usize,
+        image: DynamicImage,
+    },
+    Failed {
+        id: usize,
+        error: String,
+    },
+}
+
+struct StreamedTexture {
+    texture: Weak<Texture>,
+    // The levels received so far, from the lowest resolution to the highest.
+    levels: Vec<DynamicImage>,
+    // The number of bytes of the mipmap chain currently on the GPU.
+    resident_bytes: usize,
+    // Whether no more levels will be uploaded.
+    done: bool,
+}
+
+/// Loads textures in the background and uploads their mipmaps progressively, within budgets.
+pub(crate) struct TextureStreamer {
+    jobs: Option<Sender<(usize, PathBuf)>>,
+    results: Option<Receiver<StreamMessage>>,
+    pending: VecDeque<StreamMessage>,
+    textures: HashMap<usize, StreamedTexture>,
+    next_id: usize,
+    resident_bytes: usize,
+    upload_budget: usize,
+    memory_budget: usize,
+}
+
+impl TextureStreamer {
+    pub fn new() -> TextureStreamer {
+        TextureStreamer {
+            jobs: None,
+            results: None,
+            pending: VecDeque::new(),
+            textures: HashMap::new(),
+            next_id: 0,
+            resident_bytes: 0,
+            upload_budget: 16 * 1024 * 1024,
+            memory_budget: usize::MAX,
+        }
+    }
+
+    pub fn set_budgets(&mut self, upload_budget: usize, memory_budget: usize) {
+        self.upload_budget = upload_budget;
+        self.memory_budget = memory_budget;
+    }
+
+    pub fn resident_bytes(&self) -> usize {
+        self.resident_bytes
+    }
+
+    pub fn num_streaming(&self) -> usize {
+        self.textures.values().filter(|t| !t.done).count()
+    }
+
+    /// Starts loading the image at `path` into `texture` on the background thread.
+    pub fn stream(&mut self, path: PathBuf, texture: &Rc<Texture>) {
+        if self.jobs.is_none() {
+            let (job_sender, job_receiver) = mpsc::channel();
+            let (result_sender, result_receiver) = mpsc::channel();
+            let _ = thread::spawn(move || load_levels(job_receiver, result_sender));
+            self.jobs = Some(job_sender);
+            self.results = Some(result_receiver);
+        }
+
+        let id = self.next_id;
+        self.next_id += 1;
+
+        let _ = self.textures.insert(
+            id,
+            StreamedTexture {
+                texture: Rc::downgrade(texture),
+                levels: Vec::new(),
+                resident_bytes: 0,
+                done: false,
+            },
+        );
+
+        let _ = self.jobs.as_ref().unwrap().send((id, path));
+    }
+
+    /// Uploads the mipmaps loaded since the last call, until the upload budget is exhausted.
+    ///
+    /// Returns `true` if at least one texture has been updated.
+    pub fn update(&mut self) -> bool {
+        // Forget the textures that have been dropped.
+        self.textures.retain(|_, t| t.texture.strong_count() != 0);
+        self.resident_bytes = self.textures.values().map(|t| t.resident_bytes).sum();
+
+        if let Some(results) = &self.results {
+            self.pending.extend(results.try_iter());
+        }
+
+        let mut uploaded = 0;
+        let mut updated = false;
+
+        while uploaded < self.upload_budget {
+            let message = match self.pending.pop_front() {
+                Some(message) => message,
+                None => break,
+            };
+
+            match message {
+                StreamMessage::Level { id, level, image } => {
+                    let bytes = self.upload_level(id, level, image);
+                    uploaded += bytes;
+                    updated = updated || bytes != 0;
+                }
+                StreamMessage::Failed { id, error } => {
+                    println!("Warning: unable to stream texture: {}", error);
+                    self.forget(id);
+                }
+            }
+        }
+
+        updated
+    }
+
+    // Uploads the mipmap chain starting at `level`, and returns the number of bytes uploaded.
+    fn upload_level(&mut self, id: usize, level: usize, image: DynamicImage) -> usize {
+        let texture = match self.textures.get(&id) {
+            Some(streamed) if !streamed.done => match streamed.texture.upgrade() {
+                Some(texture) => texture,
+                None => return 0,
+            },
+            _ => return 0,
+        };
+
+        let streamed = self.textures.get_mut(&id).unwrap();
+        streamed.levels.push(image);
+
+        let chain_bytes: usize = streamed.levels.iter().map(image_bytes).sum();
+        let resident = self.resident_bytes - streamed.resident_bytes + chain_bytes;
+
+        if resident > self.memory_budget && streamed.resident_bytes != 0 {
+            // Keep the current resolution.
+            self.forget(id);
+            return 0;
+        }
+
+        let ctxt = Context::get();
+        verify!(ctxt.active_texture(Context::TEXTURE0));
+        verify!(ctxt.bind_texture(Context::TEXTURE_2D, Some(&*texture)));
+
+        for (i, image) in streamed.levels.iter().rev().enumerate() {
+            let (format, pixels) = match image {
+                DynamicImage::ImageRgb8(image) => (Context::RGB, &image.as_raw()[..]),
+                DynamicImage::ImageRgba8(image) => (Context::RGBA, &image.as_raw()[..]),
+                _ => unreachable!(),
+            };
+            let (width, height) = image.dimensions();
+
+            verify!(ctxt.tex_image2d(
+                Context::TEXTURE_2D,
+                i as i32,
+                format as i32,
+                width as i32,
+                height as i32,
+                0,
+                format,
+                Some(pixels)
+            ));
+        }
+
+        self.resident_bytes = resident;
+        streamed.resident_bytes = chain_bytes;
+
+        if level == 0 {
+            self.forget(id);
+        }
+
+        chain_bytes
+    }
+
+    // Stops uploading levels to a texture, keeping the levels already on the GPU.
+    fn forget(&mut self, id: usize) {
+        if let Some(streamed) = self.textures.get_mut(&id) {
+            streamed.levels = Vec::new();
+            streamed.done = true;
+        }
+    }
+}
+
+fn image_bytes(image: &DynamicImage) -> usize {
+    let (width, height) = image.dimensions();
+    (width * height) as usize * image.color().bytes_per_pixel() as usize
+}
+
+// Runs on the background thread: decodes the images and sends their mipmaps, smallest first.
+fn load_levels(jobs: Receiver<(usize, PathBuf)>, results: Sender<StreamMessage>) {
+    for (id, path) in jobs.iter() {
+        let image = match asset_source::read_image(&path) {
+            Ok(image) => image,
+            Err(e) => {
+                let error = format!("{:?}: {}", path, e);
+                if results.send(StreamMessage::Failed { id, error }).is_err() {
+                    return;
+                }
+                continue;
+            }
+        };
+
+        let image = texture_manager::to_uploadable(image);
+
+        let (mut w, mut h) = image.dimensions();
+        let mut levels = vec![image];
+
+        while w > 1 || h > 1 {
+            w = (w / 2).max(1);
+            h = (h / 2).max(1);
+            let next = levels
+                .last()
+                .unwrap()
+                .resize_exact(w, h, FilterType::Triangle);
+            levels.push(next);
+        }
+
+        for (level, image) in levels.into_iter().enumerate().rev() {
+            if results
+                .send(StreamMessage::Level { id, level, image })
+                .is_err()
+            {
+                return;
+            }
+        }
+    }
+}
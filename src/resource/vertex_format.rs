@@ -0,0 +1,163 @@
+//! Vertex attribute formats used to upload meshes to the GPU.
+
+use crate::context::Context;
+
+/// The format of the components of a vertex attribute stored on the GPU.
+///
+/// Vertex attributes are always seen as floating-point values by the shaders. Normalized integer
+/// formats map the integer range to `[-1.0, 1.0]` for signed formats, and `[0.0, 1.0]` for
+/// unsigned formats: values out of that range are clamped when the mesh is uploaded.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash, Default)]
+pub enum VertexFormat {
+    /// 32-bits floating-point components.
+    #[default]
+    Float32,
+    /// 16-bits floating-point components.
+    ///
+    /// This requires OpenGL 3.0, OpenGL ES 3.0, or WebGL 2.
+    Float16,
+    /// 16-bits signed integers, normalized to `[-1.0, 1.0]`.
+    Snorm16,
+    /// 16-bits unsigned integers, normalized to `[0.0, 1.0]`.
+    Unorm16,
+    /// 8-bits signed integers, normalized to `[-1.0, 1.0]`.
+    Snorm8,
+    /// 8-bits unsigned integers, normalized to `[0.0, 1.0]`.
+    Unorm8,
+}
+
+impl VertexFormat {
+    /// The size of one component, in bytes.
+    pub fn component_size(self) -> usize {
+        match self {
+            VertexFormat::Float32 => 4,
+            VertexFormat::Float16 | VertexFormat::Snorm16 | VertexFormat::Unorm16 => 2,
+            VertexFormat::Snorm8 | VertexFormat::Unorm8 => 1,
+        }
+    }
+
+    /// The size of an attribute with `ncomponents` components, padded to a multiple of four
+    /// bytes.
+    pub fn attribute_size(self, ncomponents: usize) -> usize {
+        (self.component_size() * ncomponents).div_ceil(4) * 4
+    }
+
+    /// Whether the components are normalized integers.
+    pub fn is_normalized(self) -> bool {
+        !matches!(self, VertexFormat::Float32 | VertexFormat::Float16)
+    }
+
+    pub(crate) fn gl_type(self) -> u32 {
+        match self {
+            VertexFormat::Float32 => Context::FLOAT,
+            VertexFormat::Float16 => Context::HALF_FLOAT,
+            VertexFormat::Snorm16 => Context::SHORT,
+            VertexFormat::Unorm16 => Context::UNSIGNED_SHORT,
+            VertexFormat::Snorm8 => Context::BYTE,
+            VertexFormat::Unorm8 => Context::UNSIGNED_BYTE,
+        }
+    }
+
+    // Appends the bytes of `value`, converted to this format.
+    pub(crate) fn push(self, value: f32, out: &mut Vec<u8>) {
+        match self {
+            VertexFormat::Float32 => out.extend_from_slice(&value.to_ne_bytes()),
+            VertexFormat::Float16 => out.extend_from_slice(&f32_to_f16(value).to_ne_bytes()),
+            VertexFormat::Snorm16 => {
+                let v = (value.clamp(-1.0, 1.0) * 32767.0).round() as i16;
+                out.extend_from_slice(&v.to_ne_bytes())
+            }
+            VertexFormat::Unorm16 => {
+                let v = (value.clamp(0.0, 1.0) * 65535.0).round() as u16;
+                out.extend_from_slice(&v.to_ne_bytes())
+            }
+            VertexFormat::Snorm8 => out.push((value.clamp(-1.0, 1.0) * 127.0).round() as i8 as u8),
+            VertexFormat::Unorm8 => out.push((value.clamp(0.0, 1.0) * 255.0).round() as u8),
+        }
+    }
+}
+
+/// Options controlling how the vertex attributes of a mesh are stored on the GPU.
+///
+/// Vertex positions are always stored as 32-bits floats. Reduced-precision normals and texture
+/// coordinates, as well as interleaved buffers, only affect the GPU copy of the mesh: the
+/// vertices, normals, and texture coordinates accessible on RAM remain 32-bits floats.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub struct MeshLoadOptions {
+    /// The format of the vertex normals. `Snorm16` and `Snorm8` are good candidates.
+    pub normals: VertexFormat,
+    /// The format of the texture coordinates. Normalized formats should only be used if all the
+    /// texture coordinates are in `[0.0, 1.0]`.
+    pub uvs: VertexFormat,
+    /// Whether the positions, normals, and texture coordinates are stored in a single buffer,
+    /// one vertex after the other.
+    pub interleaved: bool,
+}
+
+impl MeshLoadOptions {
+    /// Options storing each attribute as 32-bits floats, in separate buffers.
+    pub fn new() -> MeshLoadOptions {
+        MeshLoadOptions {
+            normals: VertexFormat::Float32,
+            uvs: VertexFormat::Float32,
+            interleaved: false,
+        }
+    }
+
+    /// Options storing normals as 16-bits normalized integers, texture coordinates as 16-bits
+    /// floats, and interleaving all the attributes.
+    ///
+    /// This halves the GPU memory used by the normals and texture coordinates.
+    pub fn compact() -> MeshLoadOptions {
+        MeshLoadOptions {
+            normals: VertexFormat::Snorm16,
+            uvs: VertexFormat::Float16,
+            interleaved: true,
+        }
+    }
+
+    /// Whether these options store each attribute as 32-bits floats, in separate buffers.
+    pub fn is_default(&self) -> bool {
+        *self == MeshLoadOptions::new()
+    }
+}
+
+impl Default for MeshLoadOptions {
+    fn default() -> MeshLoadOptions {
+        MeshLoadOptions::new()
+    }
+}
+
+// Converts a float to the bits of the closest half-precision float.
+fn f32_to_f16(value: f32) -> u16 {
+    let bits = value.to_bits();
+    let sign = ((bits >> 16) & 0x8000) as u16;
+    let exponent = ((bits >> 23) & 0xff) as i32;
+    let mantissa = bits & 0x7f_ffff;
+
+    if exponent == 0xff {
+        // Infinity or NaN.
+        return sign | 0x7c00 | if mantissa != 0 { 0x200 } else { 0 };
+    }
+
+    let half_exponent = exponent - 127 + 15;
+
+    if half_exponent >= 0x1f {
+        // Overflow.
+        sign | 0x7c00
+    } else if half_exponent <= 0 {
+        // Subnormal, or underflow.
+        let shift = 14 - half_exponent;
+
+        if shift > 24 {
+            return sign;
+        }
+
+        let full = mantissa | 0x80_0000;
+        sign | (((full >> (shift - 1)) + 1) >> 1) as u16
+    } else {
+        // Rounding may carry to the exponent, which is the expected result.
+        let half = ((half_exponent as u32) << 10) | (mantissa >> 13);
+        sign | (half + ((mantissa >> 12) & 1)) as u16
+    }
+}
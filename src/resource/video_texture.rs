@@ -0,0 +1,283 @@
+//! Textures updated with the frames of a video, decoded by ffmpeg.
+
+use std::io::{self, Read};
+use std::process::{Child, Command, Stdio};
+use std::rc::Rc;
+use std::sync::mpsc::{self, Receiver, TryRecvError};
+use std::thread;
+
+use crate::context::{Context, Texture};
+
+#[path = "../error.rs"]
+mod error;
+
+/// A texture playing a video file or a camera stream.
+///
+/// The video is decoded by an `ffmpeg` process, so the `ffmpeg` and `ffprobe` executables must
+/// be in the `PATH`. Any input supported by ffmpeg can be played, including network streams.
+///
+/// The texture can be set on any number of scene nodes, and is updated by calling `update` once
+/// per frame:
+///
+/// ```no_run
+/// # use kiss3d::window::Window;
+/// # use kiss3d::resource::VideoTexture;
+/// let mut window = Window::new("Video");
+/// let mut quad = window.add_quad(1.6, 0.9, 1, 1);
+/// let mut video = VideoTexture::open("video.mp4").unwrap();
+/// quad.set_texture(video.texture());
+///
+/// while window.render() {
+///     let _ = video.update();
+/// }
+/// ```
+pub struct VideoTexture {
+    texture: Rc<Texture>,
+    width: usize,
+    height: usize,
+    frame_rate: f32,
+    frames: Receiver<Vec<u8>>,
+    process: Child,
+    finished: bool,
+    // The context generation the texture was created with.
+    generation: u64,
+}
+
+impl VideoTexture {
+    /// Plays a video file at its native frame rate, looping at its end.
+    pub fn open(path: &str) -> io::Result<VideoTexture> {
+        VideoTexture::open_with_args(&["-re", "-stream_loop", "-1"], path)
+    }
+
+    /// Plays a video file once at its native frame rate.
+    pub fn open_once(path: &str) -> io::Result<VideoTexture> {
+        VideoTexture::open_with_args(&["-re"], path)
+    }
+
+    /// Plays the stream of a camera, e.g., `/dev/video0` on Linux.
+    ///
+    /// The camera is read with the `v4l2` input device of ffmpeg on Linux, `avfoundation` on
+    /// macOS, and `dshow` on Windows, where `device` must be of the form `video=<name>`.
+    pub fn open_camera(device: &str) -> io::Result<VideoTexture> {
+        let format = if cfg!(target_os = "macos") {
+            "avfoundation"
+        } else if cfg!(target_os = "windows") {
+            "dshow"
+        } else {
+            "v4l2"
+        };
+
+        VideoTexture::open_with_args(&["-f", format], device)
+    }
+
+    /// Plays an ffmpeg input, given with the ffmpeg options placed before `-i input`.
+    pub fn open_with_args(input_args: &[&str], input: &str) -> io::Result<VideoTexture> {
+        let (width, height, frame_rate) = probe(input_args, input)?;
+
+        let mut process = Command::new("ffmpeg")
+            .args(["-loglevel", "error", "-nostdin"])
+            .args(input_args)
+            .args(["-i", input])
+            .args(["-f", "rawvideo", "-pix_fmt", "rgb24", "-"])
+            .stdin(Stdio::null())
+            .stdout(Stdio::piped())
+            .spawn()?;
+
+        let stdout = process.stdout.take().unwrap();
+        // Keep a couple of frames in advance, so that decoding does not get ahead of rendering.
+        let (sender, frames) = mpsc::sync_channel(2);
+        let frame_len = width * height * 3;
+
+        let _ = thread::spawn(move || {
+            let mut stdout = stdout;
+
+            loop {
+                let mut frame = vec![0; frame_len];
+
+                if stdout.read_exact(&mut frame).is_err() || sender.send(frame).is_err() {
+                    return;
+                }
+            }
+        });
+
+        let texture = Texture::new();
+        init_texture(&texture, width, height);
+
+        Ok(VideoTexture {
+            texture,
+            width,
+            height,
+            frame_rate,
+            frames,
+            process,
+            finished: false,
+            generation: Context::generation(),
+        })
+    }
+
+    /// The texture displaying the video.
+    pub fn texture(&self) -> Rc<Texture> {
+        self.texture.clone()
+    }
+
+    /// The width and height of the video, in pixels.
+    pub fn size(&self) -> (usize, usize) {
+        (self.width, self.height)
+    }
+
+    /// The number of frames per second of the video, as reported by ffprobe.
+    pub fn frame_rate(&self) -> f32 {
+        self.frame_rate
+    }
+
+    /// Whether the end of the video has been reached, or the stream has been interrupted.
+    pub fn is_finished(&self) -> bool {
+        self.finished
+    }
+
+    /// Uploads the latest decoded frame to the texture, if any.
+    ///
+    /// Returns `true` if the texture has been updated. Frames decoded since the last call are
+    /// skipped if the rendering is slower than the video.
+    pub fn update(&mut self) -> bool {
+        if self.generation != Context::generation() {
+            // The texture has been lost with the context.
+            Context::get().recreate_texture(&self.texture);
+            init_texture(&self.texture, self.width, self.height);
+            self.generation = Context::generation();
+        }
+
+        let mut latest = None;
+
+        loop {
+            match self.frames.try_recv() {
+                Ok(frame) => latest = Some(frame),
+                Err(TryRecvError::Empty) => break,
+                Err(TryRecvError::Disconnected) => {
+                    self.finished = true;
+                    break;
+                }
+            }
+        }
+
+        match latest {
+            Some(frame) => {
+                let ctxt = Context::get();
+                verify!(ctxt.active_texture(Context::TEXTURE0));
+                verify!(ctxt.bind_texture(Context::TEXTURE_2D, Some(&*self.texture)));
+                verify!(ctxt.pixel_storei(Context::UNPACK_ALIGNMENT, 1));
+                verify!(ctxt.tex_sub_image2d(
+                    Context::TEXTURE_2D,
+                    0,
+                    0,
+                    0,
+                    self.width as i32,
+                    self.height as i32,
+                    Context::RGB,
+                    Some(&frame)
+                ));
+                true
+            }
+            None => false,
+        }
+    }
+}
+
+impl Drop for VideoTexture {
+    fn drop(&mut self) {
+        let _ = self.process.kill();
+        let _ = self.process.wait();
+    }
+}
+
+// Reads the size and frame rate of the first video stream of an input.
+fn probe(input_args: &[&str], input: &str) -> io::Result<(usize, usize, f32)> {
+    // ffprobe does not know the options controlling the playback.
+    let mut probe_args = Vec::new();
+    let mut args = input_args.iter();
+
+    while let Some(arg) = args.next() {
+        match *arg {
+            "-re" => {}
+            "-stream_loop" => {
+                let _ = args.next();
+            }
+            _ => probe_args.push(*arg),
+        }
+    }
+
+    let output = Command::new("ffprobe")
+        .args(["-v", "error", "-select_streams", "v:0"])
+        .args(["-show_entries", "stream=width,height,r_frame_rate"])
+        .args(["-of", "csv=p=0"])
+        .args(&probe_args)
+        .arg(input)
+        .output()?;
+
+    let invalid = || {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("unable to read the video stream of {}: {}", input, stderr),
+        )
+    };
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let fields: Vec<&str> = stdout.trim().split(',').collect();
+
+    if fields.len() != 3 {
+        return Err(invalid());
+    }
+
+    let width = fields[0].parse().map_err(|_| invalid())?;
+    let height = fields[1].parse().map_err(|_| invalid())?;
+    // The frame rate is a fraction, e.g., `30000/1001`.
+    let frame_rate = match fields[2].split_once('/') {
+        Some((num, den)) => match (num.parse::<f32>(), den.parse::<f32>()) {
+            (Ok(num), Ok(den)) if den != 0.0 => num / den,
+            _ => 0.0,
+        },
+        None => fields[2].parse().unwrap_or(0.0),
+    };
+
+    Ok((width, height, frame_rate))
+}
+
+fn init_texture(texture: &Texture, width: usize, height: usize) {
+    let ctxt = Context::get();
+    let black = vec![0u8; width * height * 3];
+
+    verify!(ctxt.active_texture(Context::TEXTURE0));
+    verify!(ctxt.bind_texture(Context::TEXTURE_2D, Some(texture)));
+    verify!(ctxt.pixel_storei(Context::UNPACK_ALIGNMENT, 1));
+    verify!(ctxt.tex_image2d(
+        Context::TEXTURE_2D,
+        0,
+        Context::RGB as i32,
+        width as i32,
+        height as i32,
+        0,
+        Context::RGB,
+        Some(&black)
+    ));
+    verify!(ctxt.tex_parameteri(
+        Context::TEXTURE_2D,
+        Context::TEXTURE_WRAP_S,
+        Context::CLAMP_TO_EDGE as i32
+    ));
+    verify!(ctxt.tex_parameteri(
+        Context::TEXTURE_2D,
+        Context::TEXTURE_WRAP_T,
+        Context::CLAMP_TO_EDGE as i32
+    ));
+    verify!(ctxt.tex_parameteri(
+        Context::TEXTURE_2D,
+        Context::TEXTURE_MIN_FILTER,
+        Context::LINEAR as i32
+    ));
+    verify!(ctxt.tex_parameteri(
+        Context::TEXTURE_2D,
+        Context::TEXTURE_MAG_FILTER,
+        Context::LINEAR as i32
+    ));
+}
@@ -0,0 +1,53 @@
+//! Behaviors attached to scene nodes, executed once per frame.
+
+use std::cell::{Cell, RefCell};
+
+use instant::Instant;
+
+use crate::scene::{NodeHandle, SceneNode};
+
+// A closure executed once per frame with its node and the time elapsed since the previous
+// frame, in seconds.
+pub(crate) type Behavior = Box<dyn FnMut(&mut SceneNode, f32)>;
+
+// The nodes having behaviors.
+thread_local!(static BEHAVING: RefCell<Vec<NodeHandle>> = const { RefCell::new(Vec::new()) });
+// The time the behaviors were last executed.
+thread_local!(static LAST_RUN: Cell<Option<Instant>> = const { Cell::new(None) });
+
+// Executes the behaviors of all the nodes. Called once per frame.
+pub(crate) fn run() {
+    let now = Instant::now();
+    let dt = LAST_RUN
+        .with(|l| l.replace(Some(now)))
+        .map_or(0.0, |last| (now - last).as_secs_f32());
+    let handles = BEHAVING.with(|b| std::mem::take(&mut *b.borrow_mut()));
+
+    for handle in handles {
+        if let Some(mut node) = SceneNode::from_handle(handle) {
+            // The behaviors are taken out of the node, which they may modify.
+            let mut behaviors = node.data_mut().take_behaviors();
+
+            for behavior in behaviors.iter_mut() {
+                behavior(&mut node, dt);
+            }
+
+            let mut data = node.data_mut();
+            data.restore_behaviors(behaviors);
+
+            if data.has_behaviors() {
+                register(handle);
+            }
+        }
+    }
+}
+
+pub(crate) fn register(handle: NodeHandle) {
+    BEHAVING.with(|b| {
+        let mut behaving = b.borrow_mut();
+
+        if !behaving.contains(&handle) {
+            behaving.push(handle)
+        }
+    })
+}
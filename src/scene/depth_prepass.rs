@@ -0,0 +1,120 @@
+use na::{Isometry3, Matrix3, Matrix4, Point3, Vector3};
+
+use crate::camera::Camera;
+use crate::context::Context;
+use crate::resource::{Effect, ShaderAttribute, ShaderUniform};
+use crate::scene::Object;
+
+#[path = "../error.rs"]
+mod error;
+
+// Fills the depth buffer with the opaque objects before they are shaded, so that the fragments of
+// the hidden surfaces are rejected by the depth test instead of being shaded.
+//
+// The depths are pushed slightly backward, so that the shaded pass is not rejected by the
+// rounding differences between the shaders.
+pub(crate) struct DepthPrepass {
+    effect: Effect,
+    pos: ShaderAttribute<Point3<f32>>,
+    transform: ShaderUniform<Matrix4<f32>>,
+    scale: ShaderUniform<Matrix3<f32>>,
+    view: ShaderUniform<Matrix4<f32>>,
+    proj: ShaderUniform<Matrix4<f32>>,
+}
+
+impl DepthPrepass {
+    pub fn new() -> DepthPrepass {
+        let mut effect = Effect::new_from_str(DEPTH_VERTEX_SRC, DEPTH_FRAGMENT_SRC).unwrap();
+        effect.use_program();
+
+        DepthPrepass {
+            pos: effect.get_attrib("position").unwrap(),
+            transform: effect.get_uniform("transform").unwrap(),
+            scale: effect.get_uniform("scale").unwrap(),
+            view: effect.get_uniform("view").unwrap(),
+            proj: effect.get_uniform("proj").unwrap(),
+            effect,
+        }
+    }
+
+    // Whether an object is drawn by the pre-pass: only the surfaces drawn opaque can hide other
+    // objects. The skinned objects are not, since their vertices are moved by their skeleton.
+    pub fn accepts(object: &Object) -> bool {
+        let data = object.data();
+        data.surface_rendering_active()
+            && data.opacity() >= 1.0
+            && !data.xray_enabled()
+            && data.skeleton().is_none()
+    }
+
+    // Prepares the GL state for drawing depths. Must be followed by a call to `end`.
+    pub fn begin(&mut self, pass: usize, camera: &mut dyn Camera) {
+        let ctxt = Context::get();
+
+        self.effect.use_program();
+        self.pos.enable();
+        camera.upload(pass, &mut self.proj, &mut self.view);
+        verify!(ctxt.color_mask(false, false, false, false));
+        verify!(ctxt.enable(Context::POLYGON_OFFSET_FILL));
+        // Backward is toward the smallest depths if the depths are reversed.
+        let offset = if camera.reversed_depth() { -1.0 } else { 1.0 };
+        verify!(ctxt.polygon_offset(offset, offset));
+        let _ = verify!(ctxt.polygon_mode(Context::FRONT_AND_BACK, Context::FILL));
+    }
+
+    pub fn draw(&mut self, object: &Object, transform: &Isometry3<f32>, scale: &Vector3<f32>) {
+        let ctxt = Context::get();
+        let mut mesh = object.mesh().borrow_mut();
+
+        if object.data().backface_culling_enabled() {
+            verify!(ctxt.enable(Context::CULL_FACE));
+        } else {
+            verify!(ctxt.disable(Context::CULL_FACE));
+        }
+
+        self.transform.upload(&transform.to_homogeneous());
+        self.scale.upload(&Matrix3::from_diagonal(scale));
+        mesh.bind_coords(&mut self.pos);
+        mesh.bind_faces();
+
+        verify!(ctxt.draw_elements(
+            Context::TRIANGLES,
+            mesh.num_pts() as i32,
+            Context::UNSIGNED_SHORT,
+            0
+        ));
+    }
+
+    // Restores the GL state modified by `begin`.
+    pub fn end(&mut self) {
+        let ctxt = Context::get();
+
+        verify!(ctxt.polygon_offset(0.0, 0.0));
+        verify!(ctxt.disable(Context::POLYGON_OFFSET_FILL));
+        verify!(ctxt.color_mask(true, true, true, true));
+        self.pos.disable();
+    }
+}
+
+// The position is computed as in the default vertex shader, for the depths to match.
+static DEPTH_VERTEX_SRC: &str = "#version 100
+attribute vec3 position;
+uniform mat3 scale;
+uniform mat4 proj, view, transform;
+
+void main() {
+    gl_Position = proj * view * transform * vec4(scale * position, 1.0);
+}
+";
+
+static DEPTH_FRAGMENT_SRC: &str = "#version 100
+#ifdef GL_FRAGMENT_PRECISION_HIGH
+   precision highp float;
+#else
+   precision mediump float;
+#endif
+
+void main() {
+    gl_FragColor = vec4(1.0);
+}
+";
@@ -0,0 +1,156 @@
+//! Exploded views of assemblies.
+//!
+//! Exploding a node moves each of its children away from the centroid of the node, along the
+//! direction from that centroid to the centroid of the child. The original translations of the
+//! children are restored once the explosion factor is back to zero.
+
+use std::cell::RefCell;
+
+use instant::Instant;
+use na::{Translation3, Vector3};
+
+use crate::scene::{NodeHandle, SceneNode};
+
+// The nodes whose explosion is being animated.
+thread_local!(static ANIMATED: RefCell<Vec<NodeHandle>> = const { RefCell::new(Vec::new()) });
+
+// The exploded state of a node.
+pub(crate) struct Explosion {
+    // Each child, with its translation before the explosion and its offset for a factor of 1.
+    parts: Vec<(NodeHandle, Translation3<f32>, Vector3<f32>)>,
+    factor: f32,
+    animation: Option<Animation>,
+}
+
+#[derive(Copy, Clone)]
+struct Animation {
+    from: f32,
+    to: f32,
+    start: Instant,
+    duration: f32,
+}
+
+impl Explosion {
+    // Measures the offsets of the children of `node` from its centroid. Returns `None` if the
+    // node has no geometry.
+    fn measure(node: &SceneNode) -> Option<Explosion> {
+        let centroid = node.world_aabb()?.center();
+        let data = node.data();
+        let transform = data.world_transformation();
+        let scale = data.world_scale();
+
+        let parts = data
+            .children()
+            .iter()
+            .map(|child| {
+                // The offsets are applied to the local translations of the children, so they are
+                // expressed in the local frame of the exploded node.
+                let offset = child
+                    .world_aabb()
+                    .map(|aabb| {
+                        transform
+                            .inverse_transform_vector(&(aabb.center() - centroid))
+                            .component_div(&scale)
+                    })
+                    .unwrap_or_else(Vector3::zeros);
+
+                (child.handle(), child.data().local_translation(), offset)
+            })
+            .collect();
+
+        Some(Explosion {
+            parts,
+            factor: 0.0,
+            animation: None,
+        })
+    }
+
+    pub fn factor(&self) -> f32 {
+        self.factor
+    }
+
+    pub fn is_animating(&self) -> bool {
+        self.animation.is_some()
+    }
+
+    fn apply(&self) {
+        for (handle, translation, offset) in self.parts.iter() {
+            if let Some(mut child) = SceneNode::from_handle(*handle) {
+                child.set_local_translation(Translation3::from(
+                    translation.vector + offset * self.factor,
+                ));
+            }
+        }
+    }
+}
+
+// Explodes `node` by `factor`, progressively over `duration` seconds if it is positive.
+pub(crate) fn explode(node: &mut SceneNode, factor: f32, duration: f32) {
+    let explosion = node.data_mut().explosion_mut().take();
+
+    if let Some(mut explosion) = explosion.or_else(|| Explosion::measure(node)) {
+        if duration > 0.0 && explosion.factor != factor {
+            explosion.animation = Some(Animation {
+                from: explosion.factor,
+                to: factor,
+                start: Instant::now(),
+                duration,
+            });
+            register(node.handle());
+        } else {
+            explosion.animation = None;
+            explosion.factor = factor;
+        }
+
+        apply(node, explosion);
+    }
+}
+
+// Advances the animated explosions. Called once per frame.
+pub(crate) fn advance() {
+    let handles = ANIMATED.with(|a| std::mem::take(&mut *a.borrow_mut()));
+
+    for handle in handles {
+        if let Some(mut node) = SceneNode::from_handle(handle) {
+            let explosion = node.data_mut().explosion_mut().take();
+
+            if let Some(mut explosion) = explosion {
+                if let Some(anim) = explosion.animation {
+                    let s = (anim.start.elapsed().as_secs_f32() / anim.duration).min(1.0);
+                    // Ease in and out.
+                    let s = s * s * (3.0 - 2.0 * s);
+                    explosion.factor = anim.from + (anim.to - anim.from) * s;
+
+                    if s >= 1.0 {
+                        explosion.factor = anim.to;
+                        explosion.animation = None;
+                    } else {
+                        register(handle);
+                    }
+                }
+
+                apply(&mut node, explosion);
+            }
+        }
+    }
+}
+
+fn register(handle: NodeHandle) {
+    ANIMATED.with(|a| {
+        let mut animated = a.borrow_mut();
+
+        if !animated.contains(&handle) {
+            animated.push(handle)
+        }
+    })
+}
+
+// Moves the children of `node` according to the explosion, and keeps it unless the node is back
+// to its original state.
+fn apply(node: &mut SceneNode, explosion: Explosion) {
+    explosion.apply();
+
+    if explosion.factor != 0.0 || explosion.is_animating() {
+        *node.data_mut().explosion_mut() = Some(explosion);
+    }
+}
@@ -0,0 +1,153 @@
+//! Interpolation of the node transformations between the steps of a fixed-timestep simulation.
+//!
+//! A simulation advanced at a fixed rate gives the transformations of the nodes at the end of
+//! each step. Instead of showing them as they are, which stutters when the rate differs from the
+//! display rate, the window can show each interpolated node between its last two steps, by the
+//! fraction of a step accumulated since the last one:
+//!
+//! ```no_run
+//! # use kiss3d::window::Window;
+//! # use kiss3d::nalgebra::{Isometry3, Vector3};
+//! # use kiss3d::scene::FixedTimestep;
+//! let mut window = Window::new("Kiss3d: interpolation");
+//! let mut cube = window.add_cube(0.2, 0.2, 0.2);
+//! let mut clock = FixedTimestep::new(1.0 / 10.0);
+//! let mut angle = 0.0f32;
+//!
+//! while window.render() {
+//!     for _ in 0..clock.advance() {
+//!         angle += 0.1;
+//!         let pos = Vector3::new(angle.cos(), 0.0, angle.sin());
+//!         cube.push_interpolated_transformation(Isometry3::translation(pos.x, pos.y, pos.z));
+//!     }
+//!
+//!     window.set_interpolation_alpha(clock.alpha());
+//! }
+//! ```
+
+use std::cell::RefCell;
+
+use instant::Instant;
+use na::Isometry3;
+
+use crate::scene::{NodeHandle, SceneNode};
+
+// The nodes whose local transformation is interpolated.
+thread_local!(static INTERPOLATED: RefCell<Vec<NodeHandle>> = const { RefCell::new(Vec::new()) });
+
+// The local transformations of a node at the last two steps of a simulation.
+#[derive(Copy, Clone, Debug)]
+pub(crate) struct Interpolation {
+    pub previous: Isometry3<f32>,
+    pub current: Isometry3<f32>,
+}
+
+impl Interpolation {
+    fn at(&self, alpha: f32) -> Isometry3<f32> {
+        self.previous.lerp_slerp(&self.current, alpha)
+    }
+}
+
+/// An accumulator splitting the time elapsed between frames into fixed simulation steps.
+#[derive(Clone, Debug)]
+pub struct FixedTimestep {
+    step: f32,
+    max_steps: usize,
+    accumulator: f32,
+    last: Option<Instant>,
+}
+
+impl FixedTimestep {
+    /// Creates an accumulator of steps lasting `step` seconds.
+    pub fn new(step: f32) -> FixedTimestep {
+        assert!(step > 0.0, "The duration of a step must be positive.");
+
+        FixedTimestep {
+            step,
+            max_steps: 8,
+            accumulator: 0.0,
+            last: None,
+        }
+    }
+
+    /// The duration of a step, in seconds.
+    pub fn step(&self) -> f32 {
+        self.step
+    }
+
+    /// Sets the maximum number of steps returned by a single call to `advance`.
+    ///
+    /// The time exceeding this number of steps is dropped, so that a simulation slower than real
+    /// time does not fall further behind at each frame. The default value is 8.
+    pub fn set_max_steps(&mut self, max_steps: usize) {
+        self.max_steps = max_steps.max(1)
+    }
+
+    /// Accumulates the time elapsed since the previous call, and returns the number of whole
+    /// steps to simulate.
+    ///
+    /// The first call only starts the clock and returns 0.
+    pub fn advance(&mut self) -> usize {
+        let now = Instant::now();
+        let elapsed = self
+            .last
+            .replace(now)
+            .map_or(0.0, |last| (now - last).as_secs_f32());
+
+        self.advance_by(elapsed)
+    }
+
+    /// Accumulates `elapsed` seconds, and returns the number of whole steps to simulate.
+    pub fn advance_by(&mut self, elapsed: f32) -> usize {
+        self.accumulator += elapsed.max(0.0);
+
+        let steps = (self.accumulator / self.step).floor() as usize;
+        self.accumulator -= steps as f32 * self.step;
+
+        if steps > self.max_steps {
+            self.accumulator = 0.0;
+            self.max_steps
+        } else {
+            steps
+        }
+    }
+
+    /// The fraction of a step accumulated since the last one, in `[0, 1]`.
+    ///
+    /// This is the interpolation factor to give to `Window::set_interpolation_alpha`.
+    pub fn alpha(&self) -> f32 {
+        (self.accumulator / self.step).clamp(0.0, 1.0)
+    }
+}
+
+// Sets the local transformations of the interpolated nodes, `alpha` being the fraction of the
+// way from their previous transformation to their current one. Called once per frame.
+pub(crate) fn apply(alpha: f32) {
+    INTERPOLATED.with(|i| {
+        i.borrow_mut()
+            .retain(|handle| match SceneNode::from_handle(*handle) {
+                Some(mut node) => {
+                    let mut data = node.data_mut();
+
+                    match data.interpolation() {
+                        Some(interpolation) => {
+                            data.set_local_transformation(interpolation.at(alpha));
+                            true
+                        }
+                        None => false,
+                    }
+                }
+                None => false,
+            })
+    })
+}
+
+pub(crate) fn register(handle: NodeHandle) {
+    INTERPOLATED.with(|i| {
+        let mut interpolated = i.borrow_mut();
+
+        if !interpolated.contains(&handle) {
+            interpolated.push(handle)
+        }
+    })
+}
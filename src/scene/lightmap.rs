@@ -0,0 +1,519 @@
+use crate::color::Color;
+use crate::light::{self, LightKind, LightSource};
+use crate::resource::{MeshBvh, Texture, TextureManager};
+use crate::scene::SceneNode;
+use image::{DynamicImage, Rgba, RgbaImage};
+use na::{Isometry3, Point2, Point3, Vector3};
+use ncollide3d::bounding_volume::AABB;
+use ncollide3d::query::{Ray, RayCast};
+use std::f32::consts::PI;
+use std::rc::Rc;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+
+// The number of baked lightmaps, giving each one its own texture name.
+static NUM_BAKED: AtomicU64 = AtomicU64::new(0);
+
+/// What a lightmap stores, and how the default material uses it.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum LightmapContent {
+    /// The ambient occlusion, darkening the lighting of the object.
+    AmbientOcclusion,
+    /// The direct lighting, replacing the lighting of the object by the main light.
+    DirectLighting,
+}
+
+/// A texture storing the precomputed lighting of an object.
+///
+/// It is sampled with the lightmap texture coordinates of the mesh of the object.
+#[derive(Clone)]
+pub struct Lightmap {
+    texture: Rc<Texture>,
+    content: LightmapContent,
+    // The name of the texture in the texture manager, if it has been baked.
+    name: Option<String>,
+}
+
+impl Lightmap {
+    /// A lightmap storing `content` in `texture`.
+    pub fn new(texture: Rc<Texture>, content: LightmapContent) -> Lightmap {
+        Lightmap {
+            texture,
+            content,
+            name: None,
+        }
+    }
+
+    /// The texture of this lightmap.
+    pub fn texture(&self) -> &Rc<Texture> {
+        &self.texture
+    }
+
+    /// What this lightmap stores.
+    pub fn content(&self) -> LightmapContent {
+        self.content
+    }
+}
+
+/// What is baked into lightmaps.
+#[derive(Clone, Debug, PartialEq)]
+pub enum LightmapBake {
+    /// The fraction of the hemisphere above each point that is not hidden by the geometry
+    /// closer than `max_distance`, estimated with `samples` rays.
+    AmbientOcclusion {
+        /// The number of rays cast from each texel.
+        samples: usize,
+        /// The distance beyond which the geometry does not occlude anymore.
+        max_distance: f32,
+    },
+    /// The light received by each point from `lights`, with their shadows, plus the `ambient`
+    /// light.
+    DirectLighting {
+        /// The lights baked.
+        lights: Vec<LightSource>,
+        /// The light received by every point.
+        ambient: Color,
+    },
+}
+
+/// The options of `SceneNode::bake_lightmaps`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct LightmapOptions {
+    /// The width and height of the lightmap of each object, in texels.
+    pub resolution: usize,
+    /// What is baked.
+    pub bake: LightmapBake,
+}
+
+impl LightmapOptions {
+    /// Options baking 128 × 128 lightmaps of ambient occlusion, with 64 rays per texel, from
+    /// the geometry closer than `max_distance`.
+    pub fn ambient_occlusion(max_distance: f32) -> LightmapOptions {
+        LightmapOptions {
+            resolution: 128,
+            bake: LightmapBake::AmbientOcclusion {
+                samples: 64,
+                max_distance,
+            },
+        }
+    }
+
+    /// Options baking 128 × 128 lightmaps of the direct lighting by `lights`, with the ambient
+    /// light of the default material.
+    pub fn direct_lighting(lights: Vec<LightSource>) -> LightmapOptions {
+        LightmapOptions {
+            resolution: 128,
+            bake: LightmapBake::DirectLighting {
+                lights,
+                ambient: Color::new(1.0 / 3.0, 1.0 / 3.0, 1.0 / 3.0),
+            },
+        }
+    }
+
+    /// Sets the width and height of the lightmap of each object, in texels.
+    pub fn with_resolution(mut self, resolution: usize) -> LightmapOptions {
+        self.resolution = resolution;
+        self
+    }
+
+    fn content(&self) -> LightmapContent {
+        match self.bake {
+            LightmapBake::AmbientOcclusion { .. } => LightmapContent::AmbientOcclusion,
+            LightmapBake::DirectLighting { .. } => LightmapContent::DirectLighting,
+        }
+    }
+}
+
+// A shadow caster, whose triangles are hit by the rays in its local space.
+struct Occluder {
+    bvh: Arc<MeshBvh>,
+    transform: Isometry3<f32>,
+    scale: Vector3<f32>,
+    aabb: AABB<f32>,
+}
+
+impl Occluder {
+    fn new(node: &SceneNode) -> Option<Occluder> {
+        let data = node.data();
+        let bvh = data.object()?.mesh().borrow().bvh()?;
+        let transform = data.world_transformation();
+        let scale = data.world_scale();
+
+        if scale.iter().any(|s| *s == 0.0) {
+            return None;
+        }
+
+        let local_aabb = bvh.aabb()?;
+        let mut mins = Point3::new(f32::MAX, f32::MAX, f32::MAX);
+        let mut maxs = Point3::new(f32::MIN, f32::MIN, f32::MIN);
+
+        for i in 0..8 {
+            let corner = Vector3::new(
+                if i & 1 == 0 {
+                    local_aabb.mins.x
+                } else {
+                    local_aabb.maxs.x
+                },
+                if i & 2 == 0 {
+                    local_aabb.mins.y
+                } else {
+                    local_aabb.maxs.y
+                },
+                if i & 4 == 0 {
+                    local_aabb.mins.z
+                } else {
+                    local_aabb.maxs.z
+                },
+            );
+            let pt = transform * Point3::from(corner.component_mul(&scale));
+            mins = mins.inf(&pt);
+            maxs = maxs.sup(&pt);
+        }
+
+        Some(Occluder {
+            bvh,
+            transform,
+            scale,
+            aabb: AABB::new(mins, maxs),
+        })
+    }
+
+    fn hits(&self, ray: &Ray<f32>, max_toi: f32) -> bool {
+        if !self
+            .aabb
+            .intersects_ray(&Isometry3::identity(), ray, max_toi)
+        {
+            return false;
+        }
+
+        // The direction is scaled like the origin, so the times of impact are unchanged.
+        let origin = self.transform.inverse_transform_point(&ray.origin);
+        let dir = self.transform.inverse_transform_vector(&ray.dir);
+        let local = Ray::new(
+            Point3::from(origin.coords.component_div(&self.scale)),
+            dir.component_div(&self.scale),
+        );
+
+        self.bvh.cast_ray(&local, max_toi).is_some()
+    }
+}
+
+// Bakes the lightmaps of the visible objects of `scene`, occluded by its shadow casters.
+pub(crate) fn bake(scene: &SceneNode, options: &LightmapOptions) -> usize {
+    let occluders: Vec<_> = scene
+        .shadow_casters()
+        .iter()
+        .filter_map(Occluder::new)
+        .collect();
+
+    scene
+        .visible_objects()
+        .into_iter()
+        .map(|mut node| bake_object(&mut node, &occluders, options))
+        .filter(|baked| *baked)
+        .count()
+}
+
+// Bakes the lightmap of the object of `node`. Returns `false` if its mesh is not available on
+// RAM, or has no lightmap texture coordinates and too many triangles to generate them.
+fn bake_object(node: &mut SceneNode, occluders: &[Occluder], options: &LightmapOptions) -> bool {
+    let res = options.resolution.max(1);
+    let mut data = node.data_mut();
+    let transform = data.world_transformation();
+    let scale = data.world_scale();
+    let object = match data.object_mut() {
+        Some(object) => object,
+        None => return false,
+    };
+    let mesh = object.mesh().clone();
+    let mut mesh = mesh.borrow_mut();
+
+    if mesh.lightmap_uvs().is_none() && !mesh.generate_lightmap_uvs(res) {
+        return false;
+    }
+
+    let coords = mesh.coords().read().unwrap();
+    let normals = mesh.normals().read().unwrap();
+    let faces = mesh.faces().read().unwrap();
+    let uvs = mesh.lightmap_uvs().unwrap().read().unwrap();
+    let (coords, normals, faces, uvs) =
+        match (coords.data(), normals.data(), faces.data(), uvs.data()) {
+            (Some(c), Some(n), Some(f), Some(u)) => (c, n, f, u),
+            _ => return false,
+        };
+
+    // The world position and normal of the center of each covered texel.
+    let mut points = Vec::new();
+
+    for face in faces {
+        let ids = [face.x as usize, face.y as usize, face.z as usize];
+        let uv = ids.map(|i| uvs[i] * res as f32);
+        let pos = ids.map(|i| transform * Point3::from(coords[i].coords.component_mul(&scale)));
+        let nrm = ids.map(|i| transform.rotation * normals[i].component_div(&scale));
+        let area = edge(&uv[0], &uv[1], &uv[2]);
+
+        if area == 0.0 {
+            continue;
+        }
+
+        let mins = uv[0].inf(&uv[1]).inf(&uv[2]);
+        let maxs = uv[0].sup(&uv[1]).sup(&uv[2]);
+        let (x0, y0) = (
+            (mins.x - 0.5).floor().max(0.0),
+            (mins.y - 0.5).floor().max(0.0),
+        );
+        let (x1, y1) = (
+            (maxs.x - 0.5).ceil().min(res as f32 - 1.0),
+            (maxs.y - 0.5).ceil().min(res as f32 - 1.0),
+        );
+
+        for y in y0 as usize..=y1.max(y0) as usize {
+            for x in x0 as usize..=x1.max(x0) as usize {
+                let center = Point2::new(x as f32 + 0.5, y as f32 + 0.5);
+                let b = Vector3::new(
+                    edge(&uv[1], &uv[2], &center),
+                    edge(&uv[2], &uv[0], &center),
+                    edge(&uv[0], &uv[1], &center),
+                ) / area;
+
+                if b.min() < -1.0e-4 {
+                    continue;
+                }
+
+                let p =
+                    Point3::from(pos[0].coords * b.x + pos[1].coords * b.y + pos[2].coords * b.z);
+                let n = (nrm[0] * b.x + nrm[1] * b.y + nrm[2] * b.z)
+                    .try_normalize(1.0e-8)
+                    .unwrap_or_else(|| {
+                        (pos[1] - pos[0])
+                            .cross(&(pos[2] - pos[0]))
+                            .try_normalize(1.0e-12)
+                            .unwrap_or_else(Vector3::z)
+                    });
+                points.push((y * res + x, p, n));
+            }
+        }
+    }
+
+    #[cfg(feature = "parallel")]
+    let values: Vec<_> = {
+        use rayon::prelude::*;
+        points
+            .par_iter()
+            .map(|(i, p, n)| (*i, texel_value(*i, p, n, occluders, &options.bake)))
+            .collect()
+    };
+
+    #[cfg(not(feature = "parallel"))]
+    let values: Vec<_> = points
+        .iter()
+        .map(|(i, p, n)| (*i, texel_value(*i, p, n, occluders, &options.bake)))
+        .collect();
+
+    let mut texels = vec![None; res * res];
+
+    for (i, value) in values {
+        texels[i] = Some(value);
+    }
+
+    // Fills the texels around the triangles, sampled by the bilinear filtering of their edges.
+    dilate(&mut texels, res, 2);
+
+    let mut image = RgbaImage::new(res as u32, res as u32);
+
+    for (i, texel) in texels.iter().enumerate() {
+        let v = texel.unwrap_or_else(|| Vector3::repeat(1.0)) * 255.0;
+        let pixel = Rgba([v.x as u8, v.y as u8, v.z as u8, 255]);
+        image.put_pixel((i % res) as u32, (i / res) as u32, pixel);
+    }
+
+    let name = format!(
+        "kiss3d/lightmap/{}",
+        NUM_BAKED.fetch_add(1, Ordering::Relaxed)
+    );
+    let previous = object.lightmap().and_then(|l| l.name.clone());
+    let mut image = Some(DynamicImage::ImageRgba8(image));
+    let texture = TextureManager::get_global_manager(|tm| {
+        if let Some(ref previous) = previous {
+            let _ = tm.remove(previous);
+        }
+
+        tm.add_image(image.take().unwrap(), &name)
+    });
+
+    object.set_lightmap(Some(Lightmap {
+        texture,
+        content: options.content(),
+        name: Some(name),
+    }));
+
+    true
+}
+
+// The lighting of a texel at the world position `p` with the normal `n`, in `[0, 1]`.
+fn texel_value(
+    texel: usize,
+    p: &Point3<f32>,
+    n: &Vector3<f32>,
+    occluders: &[Occluder],
+    bake: &LightmapBake,
+) -> Vector3<f32> {
+    let occluded = |origin: &Point3<f32>, dir: &Vector3<f32>, max_toi: f32| {
+        let ray = Ray::new(*origin, *dir);
+        occluders.iter().any(|o| o.hits(&ray, max_toi))
+    };
+    // The rays start slightly above the surface, so that they do not hit it.
+    let bias = 1.0e-4 * (1.0 + p.coords.amax());
+    let origin = p + n * bias;
+
+    match bake {
+        LightmapBake::AmbientOcclusion {
+            samples,
+            max_distance,
+        } => {
+            let samples = (*samples).max(1);
+            let (t, b) = tangent_basis(n);
+            // The same directions rotated differently at each texel, so that the error is noise
+            // instead of banding.
+            let (r1, r2) = (hash(texel as u32), hash(texel as u32 ^ 0x9e37_79b9));
+            let mut hits = 0;
+
+            for i in 0..samples {
+                let (u1, u2) = hammersley(i, samples);
+                let (u1, u2) = ((u1 + r1).fract(), (u2 + r2).fract());
+                // Cosine-weighted directions over the hemisphere.
+                let (r, phi) = (u1.sqrt(), 2.0 * PI * u2);
+                let dir = t * (r * phi.cos()) + b * (r * phi.sin()) + n * (1.0 - u1).sqrt();
+
+                if occluded(&origin, &dir, *max_distance) {
+                    hits += 1;
+                }
+            }
+
+            Vector3::repeat(1.0 - hits as f32 / samples as f32)
+        }
+        LightmapBake::DirectLighting { lights, ambient } => {
+            let mut sum = ambient.to_render().coords;
+
+            for light in lights {
+                let a = light.attenuation;
+                // The direction and distance to the light, with the cosine-weighted light
+                // reaching the point.
+                let (dir, dist, cosine) = match light.kind {
+                    LightKind::Point(pos) => {
+                        let dir = (pos - p).normalize();
+                        (dir, (pos - p).norm(), n.dot(&dir))
+                    }
+                    LightKind::Directional(d) => {
+                        let dir = -d.normalize();
+                        (dir, f32::MAX, n.dot(&dir))
+                    }
+                    LightKind::Spot {
+                        position,
+                        direction,
+                        inner_angle,
+                        outer_angle,
+                    } => {
+                        let dir = (position - p).normalize();
+                        let cos = (-dir).dot(&direction.normalize());
+                        let (cos_inner, cos_outer) = (inner_angle.cos(), outer_angle.cos());
+                        let cone = smoothstep(cos_outer, cos_inner.max(cos_outer + 1.0e-4), cos);
+                        (dir, (position - p).norm(), n.dot(&dir) * cone)
+                    }
+                    // The rectangle is occluded as a whole, from its center.
+                    LightKind::Rect {
+                        position,
+                        right,
+                        up,
+                    } => (
+                        (position - p).normalize(),
+                        (position - p).norm(),
+                        light::rect_form_factor(p, n, &position, &right, &up),
+                    ),
+                };
+                let attenuation = if dist == f32::MAX {
+                    1.0
+                } else {
+                    1.0 / (a.x + a.y * dist + a.z * dist * dist)
+                };
+                let lambertian = cosine.max(0.0) * attenuation;
+
+                if lambertian > 0.0 && !occluded(&origin, &dir, dist - bias) {
+                    let color = light.color.to_render().coords * light.intensity;
+                    // Scaled like the lights of the default material.
+                    sum += color * (lambertian / 3.0);
+                }
+            }
+
+            sum.map(|c| c.clamp(0.0, 1.0))
+        }
+    }
+}
+
+// Twice the signed area of the triangle `abc`.
+fn edge(a: &Point2<f32>, b: &Point2<f32>, c: &Point2<f32>) -> f32 {
+    (b.x - a.x) * (c.y - a.y) - (b.y - a.y) * (c.x - a.x)
+}
+
+fn tangent_basis(n: &Vector3<f32>) -> (Vector3<f32>, Vector3<f32>) {
+    let t = if n.x.abs() < 0.9 {
+        n.cross(&Vector3::x()).normalize()
+    } else {
+        n.cross(&Vector3::y()).normalize()
+    };
+
+    (t, n.cross(&t))
+}
+
+// The `i`-th point of the Hammersley sequence of `n` points in the unit square.
+fn hammersley(i: usize, n: usize) -> (f32, f32) {
+    let radical_inverse = (i as u32).reverse_bits() as f32 / 4_294_967_296.0;
+    (i as f32 / n as f32, radical_inverse)
+}
+
+// A pseudo-random number in `[0, 1)`.
+fn hash(x: u32) -> f32 {
+    let mut x = x.wrapping_mul(0x85eb_ca6b);
+    x ^= x >> 13;
+    x = x.wrapping_mul(0xc2b2_ae35);
+    x ^= x >> 16;
+    (x >> 8) as f32 / 16_777_216.0
+}
+
+fn smoothstep(edge0: f32, edge1: f32, x: f32) -> f32 {
+    let t = ((x - edge0) / (edge1 - edge0)).clamp(0.0, 1.0);
+    t * t * (3.0 - 2.0 * t)
+}
+
+// Gives the empty texels the mean of their filled neighbors, `iterations` times.
+fn dilate(texels: &mut [Option<Vector3<f32>>], res: usize, iterations: usize) {
+    for _ in 0..iterations {
+        let prev = texels.to_vec();
+
+        for y in 0..res {
+            for x in 0..res {
+                if prev[y * res + x].is_some() {
+                    continue;
+                }
+
+                let mut sum = Vector3::zeros();
+                let mut count = 0;
+
+                for (dx, dy) in [(-1, 0), (1, 0), (0, -1), (0, 1)] {
+                    let (nx, ny) = (x as isize + dx, y as isize + dy);
+
+                    if nx >= 0 && ny >= 0 && (nx as usize) < res && (ny as usize) < res {
+                        if let Some(v) = prev[ny as usize * res + nx as usize] {
+                            sum += v;
+                            count += 1;
+                        }
+                    }
+                }
+
+                if count != 0 {
+                    texels[y * res + x] = Some(sum / count as f32);
+                }
+            }
+        }
+    }
+}
@@ -1,11 +1,51 @@
 //! Everything related to the scene graph.
 
-pub use self::object::{Object, ObjectData};
+pub(crate) use self::depth_prepass::DepthPrepass;
+pub use self::interpolation::FixedTimestep;
+pub use self::lightmap::{Lightmap, LightmapBake, LightmapContent, LightmapOptions};
+pub use self::nine_slice::NineSlice;
+pub use self::node_arena::NodeHandle;
+pub use self::object::{DebugVisualization, Object, ObjectData, TransparencyMode};
+pub(crate) use self::occlusion_culler::OcclusionCuller;
 pub use self::planar_object::{PlanarObject, PlanarObjectData};
+pub use self::planar_path::{LineJoin, PlanarPath};
 pub use self::planar_scene_node::{PlanarSceneNode, PlanarSceneNodeData};
-pub use self::scene_node::{SceneNode, SceneNodeData};
+pub(crate) use self::scene_command_queue::SceneCommandApplier;
+pub use self::scene_command_queue::{QueuedNode, SceneCommandBuffer, SceneCommandQueue};
+pub use self::scene_node::{PickHit, SceneNode, SceneNodeData};
+pub use self::scene_snapshot::{
+    snapshot_channel, NodeSnapshot, NodeUpdate, SceneDiff, SceneSnapshot, SnapshotReader,
+    SnapshotScene, SnapshotWriter,
+};
+pub use self::shadow_map::{
+    CascadeSplit, ContactShadows, ShadowCascades, ShadowFilter, MAX_SHADOW_CASCADES,
+};
+pub(crate) use self::shadow_map::{ShadowMap, ShadowSettings};
+pub use self::skeletal_animation::{AnimationClip, AnimationPlayer};
+pub use self::soft_body::{ClothNode, RopeNode, SoftBodyParams};
+pub use self::spatial_index::SpatialIndex;
+pub use self::validation::{ValidationError, ValidationIssue};
+pub use self::world_origin::{set_world_origin, to_render_coords, to_world_coords, world_origin};
 
+pub(crate) mod behavior;
+mod depth_prepass;
+pub(crate) mod explode;
+pub mod interpolation;
+mod lightmap;
+mod nine_slice;
+mod node_arena;
 mod object;
+mod occlusion_culler;
 mod planar_object;
-mod planar_scene_node;
+mod planar_path;
+pub(crate) mod planar_scene_node;
+mod scene_command_queue;
 mod scene_node;
+mod scene_snapshot;
+pub(crate) mod shadow_map;
+mod skeletal_animation;
+mod soft_body;
+mod spatial_index;
+pub(crate) mod trail;
+pub(crate) mod validation;
+pub(crate) mod world_origin;
@@ -0,0 +1,106 @@
+use na::{Point2, Point3};
+
+use crate::resource::PlanarMesh;
+
+/// Description of a nine-slice (a.k.a. nine-patch) rectangle.
+///
+/// The rectangle is split into a 3x3 grid. The four corners keep their size, the edges are
+/// stretched along one axis, and the center is stretched along both axes. This is useful to
+/// build resizable panels, buttons, or speech bubbles from a single texture.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct NineSlice {
+    /// The total width of the rectangle.
+    pub width: f32,
+    /// The total height of the rectangle.
+    pub height: f32,
+    /// The size of the left, right, bottom, and top borders, in world units.
+    pub borders: [f32; 4],
+    /// The size of the left, right, bottom, and top borders, as fractions of the texture size.
+    pub uv_borders: [f32; 4],
+}
+
+impl NineSlice {
+    /// Creates a nine-slice rectangle with the same border on all sides.
+    ///
+    /// # Arguments
+    /// * `width` - the rectangle extent along the x axis
+    /// * `height` - the rectangle extent along the y axis
+    /// * `border` - the size of the corners, in world units
+    /// * `uv_border` - the size of the corners, as a fraction of the texture size
+    pub fn new(width: f32, height: f32, border: f32, uv_border: f32) -> NineSlice {
+        NineSlice {
+            width,
+            height,
+            borders: [border; 4],
+            uv_borders: [uv_border; 4],
+        }
+    }
+
+    /// The same nine-slice rectangle with a different size.
+    pub fn with_size(mut self, width: f32, height: f32) -> NineSlice {
+        self.width = width;
+        self.height = height;
+        self
+    }
+
+    /// The 16 vertices of the grid, centered at (0, 0).
+    ///
+    /// The borders are shrunk if they do not fit inside of the rectangle.
+    pub fn coords(&self) -> Vec<Point2<f32>> {
+        let [left, right, bottom, top] = self.borders;
+        let hw = self.width / 2.0;
+        let hh = self.height / 2.0;
+        let sx = (self.width / (left + right).max(f32::EPSILON)).min(1.0);
+        let sy = (self.height / (bottom + top).max(f32::EPSILON)).min(1.0);
+
+        let xs = [-hw, -hw + left * sx, hw - right * sx, hw];
+        let ys = [hh, hh - top * sy, -hh + bottom * sy, -hh];
+
+        grid(&xs, &ys)
+    }
+
+    /// The 16 texture coordinates of the grid.
+    pub fn uvs(&self) -> Vec<Point2<f32>> {
+        let [left, right, bottom, top] = self.uv_borders;
+        let us = [0.0, left, 1.0 - right, 1.0];
+        let vs = [0.0, top, 1.0 - bottom, 1.0];
+
+        grid(&us, &vs)
+    }
+
+    /// The 18 triangles of the grid.
+    pub fn faces() -> Vec<Point3<u16>> {
+        let mut faces = Vec::with_capacity(18);
+
+        for j in 0..3u16 {
+            for i in 0..3u16 {
+                let tl = j * 4 + i;
+                let tr = tl + 1;
+                let bl = tl + 4;
+                let br = bl + 1;
+
+                faces.push(Point3::new(tr, bl, tl));
+                faces.push(Point3::new(bl, tr, br));
+            }
+        }
+
+        faces
+    }
+
+    /// Builds the mesh of this nine-slice rectangle.
+    pub fn to_mesh(&self) -> PlanarMesh {
+        PlanarMesh::new(self.coords(), NineSlice::faces(), Some(self.uvs()), true)
+    }
+}
+
+fn grid(xs: &[f32; 4], ys: &[f32; 4]) -> Vec<Point2<f32>> {
+    let mut res = Vec::with_capacity(16);
+
+    for y in ys.iter() {
+        for x in xs.iter() {
+            res.push(Point2::new(*x, *y));
+        }
+    }
+
+    res
+}
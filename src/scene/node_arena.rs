@@ -0,0 +1,151 @@
+use std::cell::{Cell, RefCell};
+use std::sync::atomic::{AtomicU32, Ordering};
+
+use crate::scene::SceneNodeData;
+
+// Number of nodes allocated at once by the arena.
+const CHUNK_SIZE: usize = 256;
+
+// The identifier of the next arena created, one per thread.
+static NEXT_ARENA_ID: AtomicU32 = AtomicU32::new(0);
+
+/// A lightweight identifier of a scene node.
+///
+/// Unlike a `SceneNode`, a handle does not keep its node alive. Handles are invalidated when
+/// their node is destroyed, and are never reused for another node. A handle only identifies a
+/// node on the thread that created it.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+pub struct NodeHandle {
+    arena: u32,
+    index: u32,
+    generation: u32,
+}
+
+impl NodeHandle {
+    /// The index of the node storage slot.
+    #[inline]
+    pub fn index(&self) -> u32 {
+        self.index
+    }
+
+    /// The number of times the node storage slot had been reused when this handle was created.
+    #[inline]
+    pub fn generation(&self) -> u32 {
+        self.generation
+    }
+}
+
+pub(crate) struct Slot {
+    pub data: RefCell<Option<SceneNodeData>>,
+    // The number of `SceneNode` referencing this slot.
+    pub ref_count: Cell<usize>,
+    pub generation: Cell<u32>,
+}
+
+impl Slot {
+    pub fn retain(&self) {
+        self.ref_count.set(self.ref_count.get() + 1);
+    }
+}
+
+// Pool of scene node storage, shared by all the scene nodes of a thread.
+//
+// Slots are allocated by chunks that are never freed nor moved, so a slot reference stays valid
+// for the lifetime of the thread.
+pub(crate) struct NodeArena {
+    // Distinguishes the handles of this arena from those of the other threads.
+    id: u32,
+    chunks: RefCell<Vec<Box<[Slot]>>>,
+    free: RefCell<Vec<u32>>,
+}
+
+thread_local!(static NODE_ARENA: &'static NodeArena = Box::leak(Box::new(NodeArena {
+    id: NEXT_ARENA_ID.fetch_add(1, Ordering::Relaxed),
+    chunks: RefCell::new(Vec::new()),
+    free: RefCell::new(Vec::new()),
+})));
+
+impl NodeArena {
+    pub fn get() -> &'static NodeArena {
+        NODE_ARENA.with(|arena| *arena)
+    }
+
+    // Stores the data of a new node, with a reference count of one.
+    pub fn alloc(&'static self, data: SceneNodeData) -> (NodeHandle, &'static Slot) {
+        let free = self.free.borrow_mut().pop();
+        let index = match free {
+            Some(index) => index,
+            None => {
+                let mut chunks = self.chunks.borrow_mut();
+                let first = (chunks.len() * CHUNK_SIZE) as u32;
+                let chunk = (0..CHUNK_SIZE)
+                    .map(|_| Slot {
+                        data: RefCell::new(None),
+                        ref_count: Cell::new(0),
+                        generation: Cell::new(0),
+                    })
+                    .collect();
+                chunks.push(chunk);
+
+                // Keep the first slot of the new chunk, in reverse order so that slots are
+                // reused in increasing order.
+                self.free
+                    .borrow_mut()
+                    .extend((first + 1..first + CHUNK_SIZE as u32).rev());
+                first
+            }
+        };
+
+        let slot = self.slot(index);
+        *slot.data.borrow_mut() = Some(data);
+        slot.ref_count.set(1);
+
+        let handle = NodeHandle {
+            arena: self.id,
+            index,
+            generation: slot.generation.get(),
+        };
+
+        (handle, slot)
+    }
+
+    fn slot(&'static self, index: u32) -> &'static Slot {
+        let chunks = self.chunks.borrow();
+        let slot: *const Slot = &chunks[index as usize / CHUNK_SIZE][index as usize % CHUNK_SIZE];
+        // The chunks are never freed, and moving the vector does not move their content.
+        unsafe { &*slot }
+    }
+
+    // The slot of a live node of this arena.
+    pub fn get_slot(&'static self, handle: NodeHandle) -> Option<&'static Slot> {
+        if handle.arena != self.id
+            || handle.index as usize >= self.chunks.borrow().len() * CHUNK_SIZE
+        {
+            return None;
+        }
+
+        let slot = self.slot(handle.index);
+
+        if slot.generation.get() == handle.generation && slot.ref_count.get() > 0 {
+            Some(slot)
+        } else {
+            None
+        }
+    }
+
+    // Decrements the reference count of the node stored in `slot`, destroying it if it reaches
+    // zero.
+    pub fn release(&'static self, handle: NodeHandle, slot: &Slot) {
+        let count = slot.ref_count.get() - 1;
+        slot.ref_count.set(count);
+
+        if count == 0 {
+            let data = slot.data.borrow_mut().take();
+            slot.generation.set(slot.generation.get().wrapping_add(1));
+            self.free.borrow_mut().push(handle.index);
+
+            // Dropping the data releases the children, so no borrow must be active here.
+            drop(data);
+        }
+    }
+}
@@ -1,8 +1,12 @@
 //! Data structure of a scene node.
 
 use crate::camera::Camera;
+use crate::color::Color;
 use crate::light::Light;
-use crate::resource::{Material, Mesh, Texture, TextureManager};
+use crate::load_error::Error;
+use crate::resource::{Material, MaterialManager, Mesh, Skeleton, Texture, TextureManager};
+use crate::scene::occlusion_culler::OcclusionState;
+use crate::scene::Lightmap;
 use na::{Isometry3, Point2, Point3, Vector3};
 use std::any::Any;
 use std::cell::RefCell;
@@ -12,6 +16,41 @@ use std::rc::Rc;
 #[path = "../error.rs"]
 mod error;
 
+/// How the objects with an opacity smaller than `1.0` are rendered.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum TransparencyMode {
+    /// The object is blended with what is behind it.
+    ///
+    /// Blended objects are drawn after the opaque ones, from the farthest to the closest to the
+    /// camera. The sorting is done per object, so intersecting or concave transparent objects may
+    /// still be drawn incorrectly.
+    Blend,
+    /// A fraction of the pixels of the object, equal to its opacity, is drawn opaque with a
+    /// screen-door pattern.
+    ///
+    /// This does not require any sorting, so it is always correct, at the cost of a noisy look.
+    Dither,
+}
+
+/// A debug rendering of the geometry of an object, replacing or completing its material.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum DebugVisualization {
+    /// The object is rendered by its material.
+    None,
+    /// The object is colored by its world-space normals, mapped from `[-1, 1]` to `[0, 1]`.
+    Normals,
+    /// The object is rendered by its material, with a line of the given length drawn along the
+    /// normal of each vertex, and colored by this normal.
+    NormalGlyphs(f32),
+    /// The object is drawn with a checker pattern of its texture coordinates, tinted by their
+    /// values, to reveal stretching, seams, and flipped coordinates.
+    UvChecker,
+    /// The object is colored by its world-space tangents, mapped from `[-1, 1]` to `[0, 1]`.
+    ///
+    /// The tangents are derived from the texture coordinates each frame.
+    Tangents,
+}
+
 /// Set of data identifying a scene node.
 pub struct ObjectData {
     material: Rc<RefCell<Box<dyn Material + 'static>>>,
@@ -22,6 +61,14 @@ pub struct ObjectData {
     wpoints: f32,
     draw_surface: bool,
     cull: bool,
+    opacity: f32,
+    transparency: TransparencyMode,
+    xray: bool,
+    flat_shading: bool,
+    skeleton: Option<Rc<RefCell<Skeleton>>>,
+    receive_shadows: bool,
+    lightmap: Option<Lightmap>,
+    debug: DebugVisualization,
     user_data: Box<dyn Any + 'static>,
 }
 
@@ -68,6 +115,60 @@ impl ObjectData {
         self.cull
     }
 
+    /// The opacity of this object, from `0.0` (invisible) to `1.0` (opaque).
+    #[inline]
+    pub fn opacity(&self) -> f32 {
+        self.opacity
+    }
+
+    /// How this object is rendered when its opacity is smaller than `1.0`.
+    #[inline]
+    pub fn transparency_mode(&self) -> TransparencyMode {
+        self.transparency
+    }
+
+    /// Whether this object must be blended with what is behind it.
+    #[inline]
+    pub fn is_blended(&self) -> bool {
+        self.opacity < 1.0 && self.transparency == TransparencyMode::Blend
+    }
+
+    /// Whether this object is rendered as a ghost visible through other objects.
+    #[inline]
+    pub fn xray_enabled(&self) -> bool {
+        self.xray
+    }
+
+    /// Whether this object is lit with the normals of its faces instead of its vertex normals.
+    #[inline]
+    pub fn flat_shading_enabled(&self) -> bool {
+        self.flat_shading
+    }
+
+    /// The skeleton deforming the mesh of this object, if any.
+    #[inline]
+    pub fn skeleton(&self) -> Option<&Rc<RefCell<Skeleton>>> {
+        self.skeleton.as_ref()
+    }
+
+    /// Whether the shadows of the other objects are drawn on this object.
+    #[inline]
+    pub fn receives_shadows(&self) -> bool {
+        self.receive_shadows
+    }
+
+    /// The lightmap of this object, if any.
+    #[inline]
+    pub fn lightmap(&self) -> Option<&Lightmap> {
+        self.lightmap.as_ref()
+    }
+
+    /// The debug rendering of this object.
+    #[inline]
+    pub fn debug_visualization(&self) -> DebugVisualization {
+        self.debug
+    }
+
     /// An user-defined data.
     ///
     /// Use dynamic typing capabilities of the `Any` type to recover the actual data.
@@ -85,6 +186,7 @@ pub struct Object {
     // (thus removing the need of ObjectData at all.)
     data: ObjectData,
     mesh: Rc<RefCell<Mesh>>,
+    occlusion: Option<RefCell<OcclusionState>>,
 }
 
 impl Object {
@@ -106,11 +208,23 @@ impl Object {
             wpoints: 0.0,
             draw_surface: true,
             cull: true,
+            opacity: 1.0,
+            transparency: TransparencyMode::Blend,
+            xray: false,
+            flat_shading: false,
+            skeleton: None,
+            receive_shadows: true,
+            lightmap: None,
+            debug: DebugVisualization::None,
             material,
             user_data: Box::new(user_data),
         };
 
-        Object { data, mesh }
+        Object {
+            data,
+            mesh,
+            occlusion: None,
+        }
     }
 
     #[doc(hidden)]
@@ -122,15 +236,38 @@ impl Object {
         camera: &mut dyn Camera,
         light: &Light,
     ) {
-        self.data.material.borrow_mut().render(
-            pass,
-            transform,
-            scale,
-            camera,
-            light,
-            &self.data,
-            &mut *self.mesh.borrow_mut(),
-        );
+        let debug = match self.data.debug {
+            DebugVisualization::None => None,
+            _ => MaterialManager::get_global_manager(|mm| mm.get("debug")),
+        };
+        // Except for the normal glyphs, drawn on top of the object rendered by its own material,
+        // the debug material replaces the material of the object.
+        let (material, overlay) = match (self.data.debug, debug) {
+            (DebugVisualization::NormalGlyphs(_), debug) => (self.data.material.clone(), debug),
+            (_, Some(debug)) => (debug, None),
+            (_, None) => (self.data.material.clone(), None),
+        };
+
+        for material in std::iter::once(material).chain(overlay) {
+            material.borrow_mut().render(
+                pass,
+                transform,
+                scale,
+                camera,
+                light,
+                &self.data,
+                &mut self.mesh.borrow_mut(),
+            );
+        }
+    }
+
+    // The key used to sort objects so that those sharing the same material and texture are
+    // drawn consecutively.
+    pub(crate) fn draw_key(&self) -> (usize, usize) {
+        (
+            Rc::as_ptr(&self.data.material) as usize,
+            Rc::as_ptr(&self.data.texture) as usize,
+        )
     }
 
     /// Gets the data of this object.
@@ -139,6 +276,46 @@ impl Object {
         &self.data
     }
 
+    /// Enables or disables occlusion culling for this object.
+    ///
+    /// When enabled, the bounding box of this object is tested against the depth buffer at the
+    /// end of each frame, and the object is not drawn while its bounding box is entirely hidden
+    /// by other objects. Because the result of the test is used during the next frames, an object
+    /// becoming visible may appear one frame late. This is only worth it for objects that are
+    /// expensive to draw and often hidden, and has no effect if the GPU does not support
+    /// occlusion queries.
+    ///
+    /// The bounding box is computed when occlusion culling is enabled, and updated by
+    /// `modify_vertices`.
+    pub fn set_occlusion_culling(&mut self, enabled: bool) {
+        self.occlusion = if enabled {
+            Some(RefCell::new(OcclusionState::new(&self.mesh.borrow())))
+        } else {
+            None
+        };
+    }
+
+    /// Whether occlusion culling is enabled for this object.
+    #[inline]
+    pub fn occlusion_culling_enabled(&self) -> bool {
+        self.occlusion.is_some()
+    }
+
+    /// Whether this object has been found entirely hidden by the last occlusion test.
+    ///
+    /// Always `false` if occlusion culling is disabled for this object.
+    #[inline]
+    pub fn is_occluded(&self) -> bool {
+        self.occlusion
+            .as_ref()
+            .map(|s| s.borrow().is_occluded())
+            .unwrap_or(false)
+    }
+
+    pub(crate) fn occlusion_state(&self) -> Option<&RefCell<OcclusionState>> {
+        self.occlusion.as_ref()
+    }
+
     /// Gets the data of this object.
     #[inline]
     pub fn data_mut(&mut self) -> &mut ObjectData {
@@ -234,6 +411,21 @@ impl Object {
             .data_mut()
             .as_mut()
             .map(|coords| f(coords));
+
+        if let Some(ref state) = self.occlusion {
+            state.borrow_mut().update_aabb(&bmesh);
+        }
+    }
+
+    /// Overwrites the object's vertices directly in their gpu buffer.
+    ///
+    /// The closure must write every vertex: see `Mesh::write_coords_on_gpu`. The bounding box of
+    /// this object is not updated. Returns `false` if the vertices could not be written.
+    #[inline(always)]
+    pub fn write_vertices_gpu<F: FnMut(&mut [Point3<f32>])>(&mut self, f: &mut F) -> bool {
+        self.mesh
+            .borrow_mut()
+            .write_coords_on_gpu(|coords| f(coords))
     }
 
     /// Access the object's vertices.
@@ -333,27 +525,141 @@ impl Object {
             .map(|uvs| f(&uvs[..]));
     }
 
+    /// Sets the opacity of the object, from `0.0` (invisible) to `1.0` (opaque).
+    ///
+    /// The opacity is applied by the default material, according to the transparency mode of
+    /// the object. Custom materials can read it with `ObjectData::opacity`.
+    #[inline]
+    pub fn set_opacity(&mut self, opacity: f32) {
+        self.data.opacity = opacity.clamp(0.0, 1.0)
+    }
+
+    /// The opacity of the object.
+    #[inline]
+    pub fn opacity(&self) -> f32 {
+        self.data.opacity
+    }
+
+    /// Sets how the object is rendered when its opacity is smaller than `1.0`.
+    #[inline]
+    pub fn set_transparency_mode(&mut self, mode: TransparencyMode) {
+        self.data.transparency = mode
+    }
+
+    /// How the object is rendered when its opacity is smaller than `1.0`.
+    #[inline]
+    pub fn transparency_mode(&self) -> TransparencyMode {
+        self.data.transparency
+    }
+
+    /// Enables or disables the x-ray rendering of the object.
+    ///
+    /// An x-rayed object is drawn after all the others, as a transparent ghost whose silhouette
+    /// is highlighted, and without being hidden by the objects in front of it. It does not hide
+    /// the objects behind it either.
+    #[inline]
+    pub fn set_xray(&mut self, enabled: bool) {
+        self.data.xray = enabled
+    }
+
+    /// Whether the object is rendered as an x-ray ghost.
+    #[inline]
+    pub fn xray_enabled(&self) -> bool {
+        self.data.xray
+    }
+
+    /// Enables or disables the flat shading of the object.
+    ///
+    /// A flat shaded object is lit with the normals of its faces, computed by the fragment
+    /// shader, instead of the normals of its vertices. This does not require the vertices shared
+    /// by several faces to be duplicated.
+    #[inline]
+    pub fn set_flat_shading(&mut self, enabled: bool) {
+        self.data.flat_shading = enabled
+    }
+
+    /// Whether the object is lit with the normals of its faces.
+    #[inline]
+    pub fn flat_shading_enabled(&self) -> bool {
+        self.data.flat_shading
+    }
+
+    /// Sets the skeleton deforming the mesh of the object, if it is skinned, see
+    /// `Mesh::set_skin`. Use `None` to draw the mesh as it is modeled.
+    ///
+    /// Only the default material deforms the meshes: the shadows, bounding volumes, and picking
+    /// of the object still use its mesh as it is modeled.
+    #[inline]
+    pub fn set_skeleton(&mut self, skeleton: Option<Rc<RefCell<Skeleton>>>) {
+        self.data.skeleton = skeleton
+    }
+
+    /// The skeleton deforming the mesh of the object, if any.
+    #[inline]
+    pub fn skeleton(&self) -> Option<&Rc<RefCell<Skeleton>>> {
+        self.data.skeleton.as_ref()
+    }
+
+    /// Sets whether the shadows of the other objects are drawn on this object, when shadow
+    /// mapping is enabled. Enabled by default.
+    #[inline]
+    pub fn set_receive_shadows(&mut self, enabled: bool) {
+        self.data.receive_shadows = enabled
+    }
+
+    /// Whether the shadows of the other objects are drawn on this object.
+    #[inline]
+    pub fn receives_shadows(&self) -> bool {
+        self.data.receive_shadows
+    }
+
+    /// Sets the lightmap of the object, sampled with the lightmap texture coordinates of its
+    /// mesh by the default material. Use `None` to remove it.
+    ///
+    /// Lightmaps are usually baked with `SceneNode::bake_lightmaps`.
+    #[inline]
+    pub fn set_lightmap(&mut self, lightmap: Option<Lightmap>) {
+        self.data.lightmap = lightmap
+    }
+
+    /// The lightmap of the object, if any.
+    #[inline]
+    pub fn lightmap(&self) -> Option<&Lightmap> {
+        self.data.lightmap.as_ref()
+    }
+
+    /// Sets the debug rendering of the object.
+    #[inline]
+    pub fn set_debug_visualization(&mut self, mode: DebugVisualization) {
+        self.data.debug = mode
+    }
+
+    /// The debug rendering of the object.
+    #[inline]
+    pub fn debug_visualization(&self) -> DebugVisualization {
+        self.data.debug
+    }
+
     /// Sets the color of the object.
     ///
     /// Colors components must be on the range `[0.0, 1.0]`.
     #[inline]
-    pub fn set_color(&mut self, r: f32, g: f32, b: f32) {
-        self.data.color.x = r;
-        self.data.color.y = g;
-        self.data.color.z = b;
+    pub fn set_color(&mut self, color: impl Into<Color>) {
+        self.data.color = color.into().to_render();
     }
 
     /// Sets the texture of the object.
     ///
-    /// The texture is loaded from a file and registered by the global `TextureManager`.
+    /// The texture is loaded from a file and registered by the global `TextureManager`. Returns
+    /// an error if the file cannot be read or decoded.
     ///
     /// # Arguments
     ///   * `path` - relative path of the texture on the disk
     #[inline]
-    pub fn set_texture_from_file(&mut self, path: &Path, name: &str) {
-        let texture = TextureManager::get_global_manager(|tm| tm.add(path, name));
-
-        self.set_texture(texture)
+    pub fn set_texture_from_file(&mut self, path: &Path, name: &str) -> Result<(), Error> {
+        let texture = TextureManager::get_global_manager(|tm| tm.add(path, name))?;
+        self.set_texture(texture);
+        Ok(())
     }
 
     /// Sets the texture of the object.
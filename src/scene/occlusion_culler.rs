@@ -0,0 +1,241 @@
+use na::{Isometry3, Matrix4, Point3, Translation3, Vector3};
+
+use crate::camera::Camera;
+use crate::context::{Context, Query};
+use crate::resource::{
+    AllocationType, BufferType, Effect, GPUVec, Mesh, ShaderAttribute, ShaderUniform,
+};
+
+#[path = "../error.rs"]
+mod error;
+
+// The occlusion culling state of an object.
+pub(crate) struct OcclusionState {
+    // The bounding box of the object mesh, in local space. Objects without bounding box are never
+    // culled.
+    aabb: Option<(Point3<f32>, Point3<f32>)>,
+    query: Option<Query>,
+    // The context generation the query has been created with.
+    generation: u64,
+    pending: bool,
+    occluded: bool,
+}
+
+impl OcclusionState {
+    pub fn new(mesh: &Mesh) -> OcclusionState {
+        let mut res = OcclusionState {
+            aabb: None,
+            query: None,
+            generation: Context::generation(),
+            pending: false,
+            occluded: false,
+        };
+
+        res.update_aabb(mesh);
+        res
+    }
+
+    pub fn update_aabb(&mut self, mesh: &Mesh) {
+        let coords = mesh.coords().read().unwrap();
+
+        self.aabb = coords.data().as_ref().and_then(|pts| {
+            let first = *pts.first()?;
+
+            Some(pts.iter().fold((first, first), |(mins, maxs), pt| {
+                (mins.inf(pt), maxs.sup(pt))
+            }))
+        });
+    }
+
+    // Whether the object was hidden during the last completed query.
+    pub fn is_occluded(&self) -> bool {
+        self.occluded
+    }
+
+    // Reads the result of the last query, if it is available.
+    pub fn poll(&mut self) {
+        if self.generation != Context::generation() {
+            // The query has been lost with the context.
+            if let Some(query) = self.query.take() {
+                std::mem::forget(query);
+            }
+
+            self.generation = Context::generation();
+            self.pending = false;
+            self.occluded = false;
+        }
+
+        if let (true, Some(query)) = (self.pending, self.query.as_ref()) {
+            let ctxt = Context::get();
+
+            if verify!(ctxt.get_query_parameter_u32(query, Context::QUERY_RESULT_AVAILABLE)) != 0 {
+                self.occluded =
+                    verify!(ctxt.get_query_parameter_u32(query, Context::QUERY_RESULT)) == 0;
+                self.pending = false;
+            }
+        }
+    }
+}
+
+// Issues occlusion queries by drawing the bounding boxes of the objects against the depth buffer.
+//
+// The results are used during the next frames, so objects becoming visible may appear one frame
+// late.
+pub(crate) struct OcclusionCuller {
+    effect: Effect,
+    pos: ShaderAttribute<Point3<f32>>,
+    model: ShaderUniform<Matrix4<f32>>,
+    view: ShaderUniform<Matrix4<f32>>,
+    proj: ShaderUniform<Matrix4<f32>>,
+    coords: GPUVec<Point3<f32>>,
+    faces: GPUVec<Point3<u16>>,
+}
+
+impl OcclusionCuller {
+    // Returns `None` if occlusion queries are not supported.
+    pub fn new() -> Option<OcclusionCuller> {
+        if !Context::get().supports_occlusion_queries() {
+            return None;
+        }
+
+        let mut effect = Effect::new_from_str(BOX_VERTEX_SRC, BOX_FRAGMENT_SRC).unwrap();
+        effect.use_program();
+
+        let coords = (0..8)
+            .map(|i| {
+                Point3::new(
+                    (i & 1) as f32 - 0.5,
+                    ((i >> 1) & 1) as f32 - 0.5,
+                    ((i >> 2) & 1) as f32 - 0.5,
+                )
+            })
+            .collect();
+        let faces = vec![
+            Point3::new(0, 2, 1),
+            Point3::new(1, 2, 3),
+            Point3::new(4, 5, 6),
+            Point3::new(5, 7, 6),
+            Point3::new(0, 1, 4),
+            Point3::new(1, 5, 4),
+            Point3::new(2, 6, 3),
+            Point3::new(3, 6, 7),
+            Point3::new(0, 4, 2),
+            Point3::new(2, 4, 6),
+            Point3::new(1, 3, 5),
+            Point3::new(3, 7, 5),
+        ];
+
+        Some(OcclusionCuller {
+            pos: effect.get_attrib("position").unwrap(),
+            model: effect.get_uniform("model").unwrap(),
+            view: effect.get_uniform("view").unwrap(),
+            proj: effect.get_uniform("proj").unwrap(),
+            effect,
+            coords: GPUVec::new(coords, BufferType::Array, AllocationType::StaticDraw),
+            faces: GPUVec::new(faces, BufferType::ElementArray, AllocationType::StaticDraw),
+        })
+    }
+
+    // Prepares the GL state for issuing queries. Must be followed by a call to `end`.
+    pub fn begin(&mut self, pass: usize, camera: &mut dyn Camera) {
+        let ctxt = Context::get();
+
+        self.effect.use_program();
+        self.pos.enable();
+        camera.upload(pass, &mut self.proj, &mut self.view);
+        self.pos.bind(&mut self.coords);
+        self.faces.bind();
+
+        verify!(ctxt.color_mask(false, false, false, false));
+        verify!(ctxt.depth_mask(false));
+        verify!(ctxt.disable(Context::CULL_FACE));
+        let _ = verify!(ctxt.polygon_mode(Context::FRONT_AND_BACK, Context::FILL));
+    }
+
+    // Issues a query for an object, unless the result of its previous query is still pending.
+    pub fn query(
+        &mut self,
+        state: &mut OcclusionState,
+        transform: &Isometry3<f32>,
+        scale: &Vector3<f32>,
+        eye: &Point3<f32>,
+    ) {
+        if state.pending {
+            return;
+        }
+
+        let (mins, maxs) = match state.aabb {
+            Some(aabb) => aabb,
+            None => {
+                state.occluded = false;
+                return;
+            }
+        };
+
+        // The box would be clipped by the near plane if the camera is inside of it.
+        let local_eye = transform.inverse_transform_point(eye);
+        let inside = (0..3).all(|i| {
+            let margin = (maxs[i] - mins[i]) * 0.05 + 1.0e-3;
+            let s = scale[i].abs().max(1.0e-6);
+            local_eye[i] / s >= mins[i] - margin && local_eye[i] / s <= maxs[i] + margin
+        });
+
+        if inside {
+            state.occluded = false;
+            return;
+        }
+
+        let ctxt = Context::get();
+
+        if state.query.is_none() {
+            state.query = verify!(ctxt.create_query());
+        }
+
+        if let Some(ref query) = state.query {
+            let center = na::center(&mins, &maxs);
+            let extents = (maxs - mins).map(|e| e.max(1.0e-4));
+            let model = transform.to_homogeneous()
+                * Matrix4::new_nonuniform_scaling(scale)
+                * Translation3::from(center.coords).to_homogeneous()
+                * Matrix4::new_nonuniform_scaling(&extents);
+
+            self.model.upload(&model);
+
+            verify!(ctxt.begin_query(Context::ANY_SAMPLES_PASSED, query));
+            verify!(ctxt.draw_elements(Context::TRIANGLES, 36, Context::UNSIGNED_SHORT, 0));
+            verify!(ctxt.end_query(Context::ANY_SAMPLES_PASSED));
+
+            state.pending = true;
+        }
+    }
+
+    // Restores the GL state modified by `begin`.
+    pub fn end(&mut self) {
+        let ctxt = Context::get();
+
+        verify!(ctxt.color_mask(true, true, true, true));
+        verify!(ctxt.depth_mask(true));
+        self.pos.disable();
+    }
+}
+
+static BOX_VERTEX_SRC: &str = "#version 100
+attribute vec3 position;
+uniform mat4 proj, view, model;
+
+void main() {
+    gl_Position = proj * view * model * vec4(position, 1.0);
+}
+";
+
+static BOX_FRAGMENT_SRC: &str = "#version 100
+#ifdef GL_FRAGMENT_PRECISION_HIGH
+   precision highp float;
+#else
+   precision mediump float;
+#endif
+
+void main() {
+    gl_FragColor = vec4(1.0);
+}
+";
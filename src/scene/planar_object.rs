@@ -1,7 +1,9 @@
 //! Data structure of a scene node.
 
+use crate::color::Color;
+use crate::load_error::Error;
 use crate::planar_camera::PlanarCamera;
-use crate::resource::{PlanarMaterial, PlanarMesh, Texture, TextureManager};
+use crate::resource::{PlanarMaterial, PlanarMesh, Texture, TextureAtlas, TextureManager};
 use na::{Isometry2, Point2, Point3, Vector2};
 use std::any::Any;
 use std::cell::RefCell;
@@ -15,6 +17,8 @@ mod error;
 pub struct PlanarObjectData {
     material: Rc<RefCell<Box<dyn PlanarMaterial + 'static>>>,
     texture: Rc<Texture>,
+    uv_offset: Vector2<f32>,
+    uv_scale: Vector2<f32>,
     color: Point3<f32>,
     lines_color: Option<Point3<f32>>,
     wlines: f32,
@@ -31,6 +35,12 @@ impl PlanarObjectData {
         &self.texture
     }
 
+    /// The offset and the scale applied to the texture coordinates of this object.
+    #[inline]
+    pub fn uv_transform(&self) -> (&Vector2<f32>, &Vector2<f32>) {
+        (&self.uv_offset, &self.uv_scale)
+    }
+
     /// The color of this object.
     #[inline]
     pub fn color(&self) -> &Point3<f32> {
@@ -101,6 +111,8 @@ impl PlanarObject {
             color: Point3::new(r, g, b),
             lines_color: None,
             texture,
+            uv_offset: Vector2::zeros(),
+            uv_scale: Vector2::repeat(1.0),
             wlines: 0.0,
             wpoints: 0.0,
             draw_surface: true,
@@ -300,23 +312,22 @@ impl PlanarObject {
     ///
     /// Colors components must be on the range `[0.0, 1.0]`.
     #[inline]
-    pub fn set_color(&mut self, r: f32, g: f32, b: f32) {
-        self.data.color.x = r;
-        self.data.color.y = g;
-        self.data.color.z = b;
+    pub fn set_color(&mut self, color: impl Into<Color>) {
+        self.data.color = color.into().to_render();
     }
 
     /// Sets the texture of the object.
     ///
-    /// The texture is loaded from a file and registered by the global `TextureManager`.
+    /// The texture is loaded from a file and registered by the global `TextureManager`. Returns
+    /// an error if the file cannot be read or decoded.
     ///
     /// # Arguments
     ///   * `path` - relative path of the texture on the disk
     #[inline]
-    pub fn set_texture_from_file(&mut self, path: &Path, name: &str) {
-        let texture = TextureManager::get_global_manager(|tm| tm.add(path, name));
-
-        self.set_texture(texture)
+    pub fn set_texture_from_file(&mut self, path: &Path, name: &str) -> Result<(), Error> {
+        let texture = TextureManager::get_global_manager(|tm| tm.add(path, name))?;
+        self.set_texture(texture);
+        Ok(())
     }
 
     /// Sets the texture of the object.
@@ -338,4 +349,25 @@ impl PlanarObject {
     pub fn set_texture(&mut self, texture: Rc<Texture>) {
         self.data.texture = texture
     }
+
+    /// Sets the texture of the object to the atlas page containing the image packed as `name`,
+    /// and maps its texture coordinates to the region of this image.
+    #[inline]
+    pub fn set_texture_from_atlas(&mut self, atlas: &TextureAtlas, name: &str) {
+        let region = atlas
+            .region(name)
+            .unwrap_or_else(|| panic!("Invalid attempt to use the unpacked texture: {}", name));
+
+        self.set_texture(region.texture().clone());
+        self.set_uv_transform(region.uv_offset(), region.uv_scale())
+    }
+
+    /// Sets the offset and the scale applied to the texture coordinates of the object.
+    ///
+    /// The texture is sampled at `offset + uv * scale` instead of `uv`.
+    #[inline]
+    pub fn set_uv_transform(&mut self, offset: Vector2<f32>, scale: Vector2<f32>) {
+        self.data.uv_offset = offset;
+        self.data.uv_scale = scale;
+    }
 }
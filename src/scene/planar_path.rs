@@ -0,0 +1,368 @@
+use na::{Point2, Point3, Vector2};
+use std::f32;
+
+use crate::resource::PlanarMesh;
+
+// The maximum number of vertices of a mesh indexed with `u16`.
+const MAX_VERTICES: usize = u16::MAX as usize + 1;
+
+/// The shape used to join two consecutive segments of a stroked path.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum LineJoin {
+    /// The outer corner is cut by a straight line.
+    Bevel,
+    /// The outer edges are extended until they meet. Falls back to `Bevel` if the miter is too
+    /// long.
+    Miter,
+    /// The outer corner is rounded.
+    Round,
+}
+
+#[derive(Clone, Debug)]
+struct SubPath {
+    points: Vec<Point2<f32>>,
+    closed: bool,
+}
+
+/// A 2D path made of lines and Bézier curves.
+///
+/// A path is a sequence of sub-paths, each started by `move_to`. Curves are flattened into line
+/// segments when the path is built. The path can then be turned into a filled mesh (using
+/// ear-clipping triangulation, so each closed sub-path must be a simple polygon without holes)
+/// or into a stroked outline mesh.
+#[derive(Clone, Debug)]
+pub struct PlanarPath {
+    subpaths: Vec<SubPath>,
+    curve_segments: usize,
+}
+
+impl PlanarPath {
+    /// Creates a new empty path.
+    pub fn new() -> PlanarPath {
+        PlanarPath {
+            subpaths: Vec::new(),
+            curve_segments: 16,
+        }
+    }
+
+    /// The number of line segments each curve is flattened into. The default value is 16.
+    pub fn curve_segments(&self) -> usize {
+        self.curve_segments
+    }
+
+    /// Sets the number of line segments each subsequently added curve is flattened into.
+    pub fn set_curve_segments(&mut self, segments: usize) {
+        self.curve_segments = segments.max(1);
+    }
+
+    /// Starts a new sub-path at `pt`.
+    pub fn move_to(&mut self, pt: Point2<f32>) -> &mut PlanarPath {
+        self.subpaths.push(SubPath {
+            points: vec![pt],
+            closed: false,
+        });
+        self
+    }
+
+    /// Adds a straight line from the current point to `pt`.
+    ///
+    /// Starts a new sub-path at `pt` if there is no current point.
+    pub fn line_to(&mut self, pt: Point2<f32>) -> &mut PlanarPath {
+        match self.current_subpath() {
+            Some(sub) => sub.points.push(pt),
+            None => {
+                let _ = self.move_to(pt);
+            }
+        }
+        self
+    }
+
+    /// Adds a quadratic Bézier curve from the current point to `to`.
+    pub fn quadratic_to(&mut self, ctrl: Point2<f32>, to: Point2<f32>) -> &mut PlanarPath {
+        let from = self.current_point().unwrap_or(ctrl);
+        let n = self.curve_segments;
+
+        for i in 1..=n {
+            let t = i as f32 / n as f32;
+            let it = 1.0 - t;
+            let pt = from.coords * (it * it) + ctrl.coords * (2.0 * it * t) + to.coords * (t * t);
+            let _ = self.line_to(Point2::from(pt));
+        }
+
+        self
+    }
+
+    /// Adds a cubic Bézier curve from the current point to `to`.
+    pub fn bezier_to(
+        &mut self,
+        ctrl1: Point2<f32>,
+        ctrl2: Point2<f32>,
+        to: Point2<f32>,
+    ) -> &mut PlanarPath {
+        let from = self.current_point().unwrap_or(ctrl1);
+        let n = self.curve_segments;
+
+        for i in 1..=n {
+            let t = i as f32 / n as f32;
+            let it = 1.0 - t;
+            let pt = from.coords * (it * it * it)
+                + ctrl1.coords * (3.0 * it * it * t)
+                + ctrl2.coords * (3.0 * it * t * t)
+                + to.coords * (t * t * t);
+            let _ = self.line_to(Point2::from(pt));
+        }
+
+        self
+    }
+
+    /// Closes the current sub-path by joining its last point to its first point.
+    pub fn close(&mut self) -> &mut PlanarPath {
+        if let Some(sub) = self.current_subpath() {
+            sub.closed = true;
+        }
+        self
+    }
+
+    /// The current point of this path, if any.
+    pub fn current_point(&self) -> Option<Point2<f32>> {
+        self.subpaths.last().and_then(|s| s.points.last().cloned())
+    }
+
+    /// Whether this path contains no point.
+    pub fn is_empty(&self) -> bool {
+        self.subpaths.iter().all(|s| s.points.is_empty())
+    }
+
+    fn current_subpath(&mut self) -> Option<&mut SubPath> {
+        match self.subpaths.last_mut() {
+            Some(sub) if !sub.closed => Some(sub),
+            _ => None,
+        }
+    }
+
+    /// Triangulates the interior of each sub-path, all sub-paths being implicitly closed.
+    ///
+    /// Returns `None` if the path does not enclose any area, or if the mesh would have more
+    /// vertices than can be indexed with `u16`.
+    pub fn fill_mesh(&self) -> Option<PlanarMesh> {
+        let mut coords = Vec::new();
+        let mut faces = Vec::new();
+
+        for sub in &self.subpaths {
+            let mut poly = sub.points.clone();
+            poly.dedup_by(|a, b| na::distance_squared(a, b) < 1.0e-12);
+
+            if poly.len() > 1 && na::distance_squared(&poly[0], poly.last().unwrap()) < 1.0e-12 {
+                let _ = poly.pop();
+            }
+
+            if poly.len() < 3 {
+                continue;
+            }
+
+            if coords.len() + poly.len() > MAX_VERTICES {
+                return None;
+            }
+
+            let base = coords.len() as u16;
+
+            for tri in triangulate(&poly) {
+                faces.push(Point3::new(base + tri.x, base + tri.y, base + tri.z));
+            }
+
+            coords.extend(poly);
+        }
+
+        if faces.is_empty() {
+            None
+        } else {
+            Some(PlanarMesh::new(coords, faces, None, false))
+        }
+    }
+
+    /// Builds a mesh covering the outline of each sub-path, with the given width and joins.
+    ///
+    /// Returns `None` if the path contains no segment, or if the mesh would have more vertices
+    /// than can be indexed with `u16`.
+    pub fn stroke_mesh(&self, width: f32, join: LineJoin) -> Option<PlanarMesh> {
+        let hw = width / 2.0;
+        let mut coords = Vec::new();
+
+        for sub in &self.subpaths {
+            let mut pts = sub.points.clone();
+            pts.dedup_by(|a, b| na::distance_squared(a, b) < 1.0e-12);
+
+            if sub.closed && pts.len() > 2 {
+                if na::distance_squared(&pts[0], pts.last().unwrap()) < 1.0e-12 {
+                    let _ = pts.pop();
+                }
+
+                let first = pts[0];
+                pts.push(first);
+            }
+
+            if pts.len() < 2 {
+                continue;
+            }
+
+            let nsegs = pts.len() - 1;
+
+            for i in 0..nsegs {
+                let (a, b) = (pts[i], pts[i + 1]);
+                let n = normal(&a, &b) * hw;
+                push_quad(&mut coords, a + n, a - n, b + n, b - n);
+            }
+
+            let njoins = if sub.closed && pts.len() > 3 {
+                nsegs
+            } else {
+                nsegs - 1
+            };
+
+            for i in 0..njoins {
+                let a = pts[i];
+                let p = pts[i + 1];
+                let b = if i + 2 < pts.len() {
+                    pts[i + 2]
+                } else {
+                    pts[1]
+                };
+                push_join(&mut coords, &a, &p, &b, hw, join);
+            }
+        }
+
+        if coords.is_empty() || coords.len() > MAX_VERTICES {
+            return None;
+        }
+
+        let faces = (0..(coords.len() / 3) as u16)
+            .map(|i| Point3::new(i * 3, i * 3 + 1, i * 3 + 2))
+            .collect();
+
+        Some(PlanarMesh::new(coords, faces, None, false))
+    }
+}
+
+impl Default for PlanarPath {
+    fn default() -> PlanarPath {
+        PlanarPath::new()
+    }
+}
+
+fn normal(a: &Point2<f32>, b: &Point2<f32>) -> Vector2<f32> {
+    let dir = (b - a).normalize();
+    Vector2::new(-dir.y, dir.x)
+}
+
+fn push_quad(
+    coords: &mut Vec<Point2<f32>>,
+    a: Point2<f32>,
+    b: Point2<f32>,
+    c: Point2<f32>,
+    d: Point2<f32>,
+) {
+    coords.extend_from_slice(&[a, b, c, c, b, d]);
+}
+
+fn push_join(
+    coords: &mut Vec<Point2<f32>>,
+    a: &Point2<f32>,
+    p: &Point2<f32>,
+    b: &Point2<f32>,
+    hw: f32,
+    join: LineJoin,
+) {
+    let n0 = normal(a, p);
+    let n1 = normal(p, b);
+    let turn = (p - a).perp(&(b - p));
+
+    if turn.abs() < 1.0e-7 {
+        return;
+    }
+
+    // The join is only needed on the outer side of the turn.
+    let side = if turn > 0.0 { -1.0 } else { 1.0 };
+    let o0 = p + n0 * (hw * side);
+    let o1 = p + n1 * (hw * side);
+
+    match join {
+        LineJoin::Bevel => coords.extend_from_slice(&[*p, o0, o1]),
+        LineJoin::Miter => {
+            let mid = (n0 + n1).normalize();
+            let cos = mid.dot(&n0);
+            let len = hw / cos.max(1.0e-6);
+
+            if len > hw * 4.0 {
+                coords.extend_from_slice(&[*p, o0, o1]);
+            } else {
+                let m = p + mid * (len * side);
+                coords.extend_from_slice(&[*p, o0, m, *p, m, o1]);
+            }
+        }
+        LineJoin::Round => {
+            let d0 = o0 - p;
+            let d1 = o1 - p;
+            let angle = d0.perp(&d1).atan2(d0.dot(&d1));
+            let nsubdivs = ((angle.abs() / (f32::consts::PI / 16.0)).ceil() as usize).max(1);
+            let mut prev = o0;
+
+            for i in 1..=nsubdivs {
+                let (s, c) = (angle * i as f32 / nsubdivs as f32).sin_cos();
+                let curr = p + Vector2::new(d0.x * c - d0.y * s, d0.x * s + d0.y * c);
+                coords.extend_from_slice(&[*p, prev, curr]);
+                prev = curr;
+            }
+        }
+    }
+}
+
+/// Ear-clipping triangulation of a simple polygon.
+fn triangulate(poly: &[Point2<f32>]) -> Vec<Point3<u16>> {
+    let area: f32 = (0..poly.len())
+        .map(|i| poly[i].coords.perp(&poly[(i + 1) % poly.len()].coords))
+        .sum();
+    let mut ids: Vec<u16> = (0..poly.len()).map(|i| i as u16).collect();
+    let mut res = Vec::with_capacity(poly.len() - 2);
+
+    if area < 0.0 {
+        ids.reverse();
+    }
+
+    let mut i = 0;
+    let mut nfails = 0;
+
+    while ids.len() > 3 {
+        let n = ids.len();
+        let (ia, ib, ic) = (ids[(i + n - 1) % n], ids[i % n], ids[(i + 1) % n]);
+        let (a, b, c) = (poly[ia as usize], poly[ib as usize], poly[ic as usize]);
+        let convex = (b - a).perp(&(c - b)) > 0.0;
+        let is_ear = convex
+            && ids
+                .iter()
+                .filter(|j| **j != ia && **j != ib && **j != ic)
+                .all(|j| !point_in_triangle(&poly[*j as usize], &a, &b, &c));
+
+        if is_ear || nfails > n {
+            // If no ear is found after a full loop, the polygon is degenerate: clip anyway.
+            res.push(Point3::new(ia, ib, ic));
+            let _ = ids.remove(i % n);
+            nfails = 0;
+        } else {
+            i += 1;
+            nfails += 1;
+        }
+
+        i %= ids.len();
+    }
+
+    res.push(Point3::new(ids[0], ids[1], ids[2]));
+    res
+}
+
+fn point_in_triangle(p: &Point2<f32>, a: &Point2<f32>, b: &Point2<f32>, c: &Point2<f32>) -> bool {
+    let d1 = (b - a).perp(&(p - a));
+    let d2 = (c - b).perp(&(p - b));
+    let d3 = (a - c).perp(&(p - c));
+
+    d1 >= 0.0 && d2 >= 0.0 && d3 >= 0.0
+}
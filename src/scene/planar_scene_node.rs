@@ -1,16 +1,29 @@
 use na::{self, Isometry2, Point2, Point3, Translation2, UnitComplex, Vector2};
 
+use crate::color::Color;
+use crate::load_error::Error;
 use crate::planar_camera::PlanarCamera;
 use crate::resource::{
-    PlanarMaterial, PlanarMaterialManager, PlanarMesh, PlanarMeshManager, Texture, TextureManager,
+    PlanarMaterial, PlanarMaterialManager, PlanarMesh, PlanarMeshManager, Texture, TextureAtlas,
+    TextureManager,
 };
-use crate::scene::PlanarObject;
-use std::cell::{Ref, RefCell, RefMut};
+use crate::scene::{LineJoin, NineSlice, PlanarObject, PlanarPath};
+use std::cell::{Cell, Ref, RefCell, RefMut};
 use std::f32;
 use std::mem;
 use std::path::Path;
 use std::rc::Rc;
 
+// Incremented each time a planar scene node is mutably accessed, i.e., each time the planar scene
+// may have changed.
+thread_local!(static GENERATION: Cell<u64> = const { Cell::new(0) });
+
+// The number of mutable accesses to the planar scene nodes so far, used to detect that the
+// planar scene needs to be rendered again.
+pub(crate) fn generation() -> u64 {
+    GENERATION.with(|g| g.get())
+}
+
 // XXX: once something like `fn foo(self: Rc<RefCell<PlanarSceneNode>>)` is allowed, this extra struct
 // will not be needed any more.
 /// The datas contained by a `PlanarSceneNode`.
@@ -102,8 +115,9 @@ impl PlanarSceneNodeData {
             o.render(&self.world_transform, &self.world_scale, camera)
         }
 
+        // Rendering does not change the scene, so it does not count as a mutable access.
         for c in self.children.iter_mut() {
-            let mut bc = c.data_mut();
+            let mut bc = c.data.borrow_mut();
             if bc.visible {
                 bc.do_render(&self.world_transform, &self.world_scale, camera)
             }
@@ -254,6 +268,19 @@ impl PlanarSceneNodeData {
         self.apply_to_objects(&mut |o| o.read_uvs(f))
     }
 
+    /// Replaces the geometry of the nine-slice rectangles contained by this node and its
+    /// children.
+    ///
+    /// This is typically used to resize a nine-slice rectangle without stretching its corners.
+    #[inline]
+    pub fn set_nine_slice(&mut self, slice: &NineSlice) {
+        let coords = slice.coords();
+        let uvs = slice.uvs();
+
+        self.modify_vertices(&mut |vs| vs.clone_from(&coords));
+        self.modify_uvs(&mut |us| us.clone_from(&uvs));
+    }
+
     /// Get the visibility status of node.
     #[inline]
     pub fn is_visible(&self) -> bool {
@@ -272,22 +299,24 @@ impl PlanarSceneNodeData {
     ///
     /// Colors components must be on the range `[0.0, 1.0]`.
     #[inline]
-    pub fn set_color(&mut self, r: f32, g: f32, b: f32) {
-        self.apply_to_objects_mut(&mut |o| o.set_color(r, g, b))
+    pub fn set_color(&mut self, color: impl Into<Color>) {
+        let color = color.into();
+        self.apply_to_objects_mut(&mut |o| o.set_color(color))
     }
 
     /// Sets the texture of the objects contained by this node and its children.
     ///
-    /// The texture is loaded from a file and registered by the global `TextureManager`.
+    /// The texture is loaded from a file and registered by the global `TextureManager`. Returns
+    /// an error if the file cannot be read or decoded.
     ///
     /// # Arguments
     ///   * `path` - relative path of the texture on the disk
     ///   * `name` - &str identifier to store this texture under
     #[inline]
-    pub fn set_texture_from_file(&mut self, path: &Path, name: &str) {
-        let texture = TextureManager::get_global_manager(|tm| tm.add(path, name));
-
-        self.set_texture(texture)
+    pub fn set_texture_from_file(&mut self, path: &Path, name: &str) -> Result<(), Error> {
+        let texture = TextureManager::get_global_manager(|tm| tm.add(path, name))?;
+        self.set_texture(texture);
+        Ok(())
     }
 
     /// Sets the texture of the objects contained by this node and its children.
@@ -324,6 +353,18 @@ impl PlanarSceneNodeData {
         self.apply_to_objects_mut(&mut |o| o.set_texture(texture.clone()))
     }
 
+    /// Sets the texture of the objects contained by this node and its children to the atlas page
+    /// containing the image packed as `name`, and maps their texture coordinates to its region.
+    pub fn set_texture_from_atlas(&mut self, atlas: &TextureAtlas, name: &str) {
+        self.apply_to_objects_mut(&mut |o| o.set_texture_from_atlas(atlas, name))
+    }
+
+    /// Sets the offset and the scale applied to the texture coordinates of the objects contained
+    /// by this node and its children.
+    pub fn set_uv_transform(&mut self, offset: Vector2<f32>, scale: Vector2<f32>) {
+        self.apply_to_objects_mut(&mut |o| o.set_uv_transform(offset, scale))
+    }
+
     /// Applies a closure to each object contained by this node and its children.
     #[inline]
     pub fn apply_to_objects_mut<F: FnMut(&mut PlanarObject)>(&mut self, f: &mut F) {
@@ -579,6 +620,7 @@ impl PlanarSceneNode {
 
     /// The data of this scene node.
     pub fn data_mut(&mut self) -> RefMut<PlanarSceneNodeData> {
+        GENERATION.with(|g| g.set(g.get().wrapping_add(1)));
         self.data.borrow_mut()
     }
 
@@ -738,6 +780,44 @@ impl PlanarSceneNode {
         self.add_object(scale, na::one(), object)
     }
 
+    /// Adds the filled interior of a path as a children of this node.
+    ///
+    /// Each sub-path is implicitly closed and must be a simple polygon. Returns `None` if the path
+    /// does not enclose any area.
+    pub fn add_filled_path(
+        &mut self,
+        path: &PlanarPath,
+        scale: Vector2<f32>,
+    ) -> Option<PlanarSceneNode> {
+        path.fill_mesh()
+            .map(|mesh| self.add_mesh(Rc::new(RefCell::new(mesh)), scale))
+    }
+
+    /// Adds the outline of a path as a children of this node.
+    ///
+    /// # Arguments
+    /// * `width` - the width of the outline
+    /// * `join` - the shape of the junctions between consecutive segments
+    pub fn add_stroked_path(
+        &mut self,
+        path: &PlanarPath,
+        width: f32,
+        join: LineJoin,
+    ) -> Option<PlanarSceneNode> {
+        path.stroke_mesh(width, join)
+            .map(|mesh| self.add_mesh(Rc::new(RefCell::new(mesh)), Vector2::repeat(1.0)))
+    }
+
+    /// Adds a nine-slice rectangle as a children of this node. The rectangle is initially
+    /// axis-aligned and centered at (0, 0).
+    ///
+    /// Use `set_nine_slice` on the returned node to resize it without stretching its corners.
+    pub fn add_nine_slice(&mut self, slice: &NineSlice) -> PlanarSceneNode {
+        let mesh = Rc::new(RefCell::new(slice.to_mesh()));
+
+        self.add_mesh(mesh, Vector2::repeat(1.0))
+    }
+
     /// Applies a closure to each object contained by this node and its children.
     #[inline]
     pub fn apply_to_scene_nodes_mut<F: FnMut(&mut PlanarSceneNode)>(&mut self, f: &mut F) {
@@ -766,7 +846,7 @@ impl PlanarSceneNode {
 
     /// Render the scene graph rooted by this node.
     pub fn render(&mut self, camera: &mut dyn PlanarCamera) {
-        self.data_mut().render(camera)
+        self.data.borrow_mut().render(camera)
     }
 
     /// Sets the material of the objects contained by this node and its children.
@@ -862,6 +942,15 @@ impl PlanarSceneNode {
         self.data().read_uvs(f)
     }
 
+    /// Replaces the geometry of the nine-slice rectangles contained by this node and its
+    /// children.
+    ///
+    /// This is typically used to resize a nine-slice rectangle without stretching its corners.
+    #[inline]
+    pub fn set_nine_slice(&mut self, slice: &NineSlice) {
+        self.data_mut().set_nine_slice(slice)
+    }
+
     /// Get the visibility status of node.
     #[inline]
     pub fn is_visible(&self) -> bool {
@@ -880,19 +969,20 @@ impl PlanarSceneNode {
     ///
     /// Colors components must be on the range `[0.0, 1.0]`.
     #[inline]
-    pub fn set_color(&mut self, r: f32, g: f32, b: f32) {
-        self.data_mut().set_color(r, g, b)
+    pub fn set_color(&mut self, color: impl Into<Color>) {
+        self.data_mut().set_color(color)
     }
 
     /// Sets the texture of the objects contained by this node and its children.
     ///
-    /// The texture is loaded from a file and registered by the global `TextureManager`.
+    /// The texture is loaded from a file and registered by the global `TextureManager`. Returns
+    /// an error if the file cannot be read or decoded.
     ///
     /// # Arguments
     ///   * `path` - relative path of the texture on the disk
     ///   * `name` - &str to identify this texture in `TextureManager`
     #[inline]
-    pub fn set_texture_from_file(&mut self, path: &Path, name: &str) {
+    pub fn set_texture_from_file(&mut self, path: &Path, name: &str) -> Result<(), Error> {
         self.data_mut().set_texture_from_file(path, name)
     }
 
@@ -918,6 +1008,18 @@ impl PlanarSceneNode {
         self.data_mut().set_texture(texture)
     }
 
+    /// Sets the texture of the objects contained by this node and its children to the atlas page
+    /// containing the image packed as `name`, and maps their texture coordinates to its region.
+    pub fn set_texture_from_atlas(&mut self, atlas: &TextureAtlas, name: &str) {
+        self.data_mut().set_texture_from_atlas(atlas, name)
+    }
+
+    /// Sets the offset and the scale applied to the texture coordinates of the objects contained
+    /// by this node and its children.
+    pub fn set_uv_transform(&mut self, offset: Vector2<f32>, scale: Vector2<f32>) {
+        self.data_mut().set_uv_transform(offset, scale)
+    }
+
     /// Sets the local scaling factors of the object.
     #[inline]
     pub fn set_local_scale(&mut self, sx: f32, sy: f32) {
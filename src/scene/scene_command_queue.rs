@@ -0,0 +1,404 @@
+use na::{Isometry3, Point3, Translation3, UnitQuaternion, Vector3};
+use ncollide3d::procedural::TriMesh;
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+
+use crate::color::Color;
+use crate::scene::SceneNode;
+
+/// A node created by a `SceneCommandBuffer`.
+///
+/// The identifier is available as soon as the creation is recorded, so that subsequent commands
+/// can refer to the node before it actually exists. Once the commands have been applied, the
+/// scene node itself is given by `Window::queued_node`.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct QueuedNode(u64);
+
+enum QueuedShape {
+    Group,
+    Cube(Vector3<f32>),
+    Sphere(f32),
+    Cone(f32, f32),
+    Cylinder(f32, f32),
+    Capsule(f32, f32),
+    Quad(f32, f32, usize, usize),
+    Geometry(String, Vector3<f32>),
+    TriMesh(TriMesh<f32>, Vector3<f32>),
+}
+
+type Modification = Box<dyn FnOnce(&mut SceneNode) + Send>;
+
+enum SceneCommand {
+    Add {
+        node: QueuedNode,
+        parent: Option<QueuedNode>,
+        shape: QueuedShape,
+    },
+    Modify {
+        node: QueuedNode,
+        f: Modification,
+    },
+    Remove {
+        node: QueuedNode,
+    },
+}
+
+struct Shared {
+    next_id: AtomicU64,
+    pending: Mutex<Vec<SceneCommand>>,
+}
+
+/// A handle to enqueue modifications of the scene of a window from any thread.
+///
+/// Modifications are recorded into a `SceneCommandBuffer`, then submitted to the queue. The
+/// window applies all the submitted buffers at the beginning of the next frame, on the
+/// rendering thread, so a frame never shows the effect of a buffer partially applied.
+///
+/// ```no_run
+/// # use kiss3d::window::Window;
+/// # use kiss3d::nalgebra::Translation3;
+/// # use std::thread;
+/// let mut window = Window::new("Command queue");
+/// let queue = window.scene_command_queue();
+///
+/// thread::spawn(move || {
+///     let mut commands = queue.buffer();
+///     let cube = commands.add_cube(None, 0.2, 0.2, 0.2);
+///     commands.set_color(cube, (1.0, 0.0, 0.0));
+///     commands.set_local_translation(cube, Translation3::new(0.0, 0.5, 0.0));
+///     queue.submit(commands);
+/// });
+///
+/// while window.render() {}
+/// ```
+#[derive(Clone)]
+pub struct SceneCommandQueue {
+    shared: Arc<Shared>,
+}
+
+impl SceneCommandQueue {
+    pub(crate) fn new() -> SceneCommandQueue {
+        SceneCommandQueue {
+            shared: Arc::new(Shared {
+                next_id: AtomicU64::new(0),
+                pending: Mutex::new(Vec::new()),
+            }),
+        }
+    }
+
+    /// Creates an empty buffer recording commands for this queue.
+    pub fn buffer(&self) -> SceneCommandBuffer {
+        SceneCommandBuffer {
+            shared: self.shared.clone(),
+            commands: Vec::new(),
+        }
+    }
+
+    /// Submits the commands of `buffer`, to be applied all together at the next frame.
+    pub fn submit(&self, buffer: SceneCommandBuffer) {
+        if !buffer.commands.is_empty() {
+            self.shared.pending.lock().unwrap().extend(buffer.commands)
+        }
+    }
+
+    fn take_pending(&self) -> Vec<SceneCommand> {
+        std::mem::take(&mut *self.shared.pending.lock().unwrap())
+    }
+}
+
+/// A list of scene modifications, recorded on any thread and submitted to a `SceneCommandQueue`.
+///
+/// Nodes are designated by the `QueuedNode` identifiers returned by the `add_*` methods, and
+/// parents set to `None` designate the root of the scene.
+pub struct SceneCommandBuffer {
+    shared: Arc<Shared>,
+    commands: Vec<SceneCommand>,
+}
+
+impl SceneCommandBuffer {
+    /// The number of commands recorded in this buffer.
+    pub fn len(&self) -> usize {
+        self.commands.len()
+    }
+
+    /// Whether no command has been recorded in this buffer.
+    pub fn is_empty(&self) -> bool {
+        self.commands.is_empty()
+    }
+
+    fn add(&mut self, parent: Option<QueuedNode>, shape: QueuedShape) -> QueuedNode {
+        let node = QueuedNode(self.shared.next_id.fetch_add(1, Ordering::Relaxed));
+        self.commands.push(SceneCommand::Add {
+            node,
+            parent,
+            shape,
+        });
+        node
+    }
+
+    /// Adds an empty group node.
+    pub fn add_group(&mut self, parent: Option<QueuedNode>) -> QueuedNode {
+        self.add(parent, QueuedShape::Group)
+    }
+
+    /// Adds a cube, see `SceneNode::add_cube`.
+    pub fn add_cube(
+        &mut self,
+        parent: Option<QueuedNode>,
+        wx: f32,
+        wy: f32,
+        wz: f32,
+    ) -> QueuedNode {
+        self.add(parent, QueuedShape::Cube(Vector3::new(wx, wy, wz)))
+    }
+
+    /// Adds a sphere, see `SceneNode::add_sphere`.
+    pub fn add_sphere(&mut self, parent: Option<QueuedNode>, r: f32) -> QueuedNode {
+        self.add(parent, QueuedShape::Sphere(r))
+    }
+
+    /// Adds a cone, see `SceneNode::add_cone`.
+    pub fn add_cone(&mut self, parent: Option<QueuedNode>, r: f32, h: f32) -> QueuedNode {
+        self.add(parent, QueuedShape::Cone(r, h))
+    }
+
+    /// Adds a cylinder, see `SceneNode::add_cylinder`.
+    pub fn add_cylinder(&mut self, parent: Option<QueuedNode>, r: f32, h: f32) -> QueuedNode {
+        self.add(parent, QueuedShape::Cylinder(r, h))
+    }
+
+    /// Adds a capsule, see `SceneNode::add_capsule`.
+    pub fn add_capsule(&mut self, parent: Option<QueuedNode>, r: f32, h: f32) -> QueuedNode {
+        self.add(parent, QueuedShape::Capsule(r, h))
+    }
+
+    /// Adds a quad, see `SceneNode::add_quad`.
+    pub fn add_quad(
+        &mut self,
+        parent: Option<QueuedNode>,
+        w: f32,
+        h: f32,
+        usubdivs: usize,
+        vsubdivs: usize,
+    ) -> QueuedNode {
+        self.add(parent, QueuedShape::Quad(w, h, usubdivs, vsubdivs))
+    }
+
+    /// Adds an object with a geometry registered in the `MeshManager`, see
+    /// `SceneNode::add_geom_with_name`.
+    ///
+    /// If the geometry is not registered when the commands are applied, the node is not created,
+    /// and the commands referring to it are ignored.
+    pub fn add_geom_with_name(
+        &mut self,
+        parent: Option<QueuedNode>,
+        geometry_name: &str,
+        scale: Vector3<f32>,
+    ) -> QueuedNode {
+        self.add(
+            parent,
+            QueuedShape::Geometry(geometry_name.to_string(), scale),
+        )
+    }
+
+    /// Adds a triangle mesh, see `SceneNode::add_trimesh`.
+    ///
+    /// The GPU buffers of the mesh are created when the commands are applied, so the mesh itself
+    /// can be generated on the thread recording the commands.
+    pub fn add_trimesh(
+        &mut self,
+        parent: Option<QueuedNode>,
+        mesh: TriMesh<f32>,
+        scale: Vector3<f32>,
+    ) -> QueuedNode {
+        self.add(parent, QueuedShape::TriMesh(mesh, scale))
+    }
+
+    /// Modifies a node with an arbitrary closure, run on the rendering thread.
+    pub fn modify<F: FnOnce(&mut SceneNode) + Send + 'static>(&mut self, node: QueuedNode, f: F) {
+        self.commands.push(SceneCommand::Modify {
+            node,
+            f: Box::new(f),
+        })
+    }
+
+    /// Sets the local transformation of a node.
+    pub fn set_local_transformation(&mut self, node: QueuedNode, t: Isometry3<f32>) {
+        self.modify(node, move |n| n.set_local_transformation(t))
+    }
+
+    /// Sets the local translation of a node.
+    pub fn set_local_translation(&mut self, node: QueuedNode, t: Translation3<f32>) {
+        self.modify(node, move |n| n.set_local_translation(t))
+    }
+
+    /// Sets the local rotation of a node.
+    pub fn set_local_rotation(&mut self, node: QueuedNode, r: UnitQuaternion<f32>) {
+        self.modify(node, move |n| n.set_local_rotation(r))
+    }
+
+    /// Sets the local scaling factors of a node.
+    pub fn set_local_scale(&mut self, node: QueuedNode, sx: f32, sy: f32, sz: f32) {
+        self.modify(node, move |n| n.set_local_scale(sx, sy, sz))
+    }
+
+    /// Sets the color of the objects of a node and its children.
+    pub fn set_color(&mut self, node: QueuedNode, color: impl Into<Color>) {
+        let color = color.into();
+        self.modify(node, move |n| n.set_color(color))
+    }
+
+    /// Sets the color of the lines of the objects of a node and its children.
+    pub fn set_lines_color(&mut self, node: QueuedNode, color: Option<Point3<f32>>) {
+        self.modify(node, move |n| n.set_lines_color(color))
+    }
+
+    /// Sets the visibility of a node and its children.
+    pub fn set_visible(&mut self, node: QueuedNode, visible: bool) {
+        self.modify(node, move |n| n.set_visible(visible))
+    }
+
+    /// Sets whether a node and its children can be picked.
+    pub fn set_pickable(&mut self, node: QueuedNode, pickable: bool) {
+        self.modify(node, move |n| n.set_pickable(pickable))
+    }
+
+    /// Sets whether the object of a node casts shadows.
+    pub fn set_shadow_caster(&mut self, node: QueuedNode, shadow_caster: bool) {
+        self.modify(node, move |n| n.set_shadow_caster(shadow_caster))
+    }
+
+    /// Sets whether the shadows are drawn on the objects of a node and its children.
+    pub fn set_receive_shadows(&mut self, node: QueuedNode, enabled: bool) {
+        self.modify(node, move |n| n.set_receive_shadows(enabled))
+    }
+
+    /// Sets the texture of the objects of a node and its children, by its name in the
+    /// `TextureManager`.
+    pub fn set_texture_with_name(&mut self, node: QueuedNode, name: &str) {
+        let name = name.to_string();
+        self.modify(node, move |n| n.set_texture_with_name(&name))
+    }
+
+    /// Replaces the vertices of the mesh of a node, e.g., computed by a simulation.
+    ///
+    /// Nothing happens if the node has no object.
+    pub fn set_vertices(&mut self, node: QueuedNode, vertices: Vec<Point3<f32>>) {
+        self.modify(node, move |n| {
+            if n.data().has_object() {
+                let mut vertices = Some(vertices);
+                n.modify_vertices(&mut |v| {
+                    if let Some(vertices) = vertices.take() {
+                        *v = vertices
+                    }
+                })
+            }
+        })
+    }
+
+    /// Removes a node and its children from the scene.
+    pub fn remove(&mut self, node: QueuedNode) {
+        self.commands.push(SceneCommand::Remove { node })
+    }
+}
+
+// Applies the commands submitted to a queue to the scene graph of a window.
+pub(crate) struct SceneCommandApplier {
+    queue: SceneCommandQueue,
+    // The created nodes, with their queued parent.
+    nodes: HashMap<QueuedNode, (SceneNode, Option<QueuedNode>)>,
+}
+
+impl SceneCommandApplier {
+    pub fn new() -> SceneCommandApplier {
+        SceneCommandApplier {
+            queue: SceneCommandQueue::new(),
+            nodes: HashMap::new(),
+        }
+    }
+
+    pub fn queue(&self) -> SceneCommandQueue {
+        self.queue.clone()
+    }
+
+    pub fn node(&self, node: QueuedNode) -> Option<&SceneNode> {
+        self.nodes.get(&node).map(|n| &n.0)
+    }
+
+    pub fn apply(&mut self, root: &mut SceneNode) {
+        for command in self.queue.take_pending() {
+            match command {
+                SceneCommand::Add {
+                    node,
+                    parent,
+                    shape,
+                } => {
+                    let parent_node = match parent {
+                        Some(parent) => match self.nodes.get_mut(&parent) {
+                            Some(parent) => &mut parent.0,
+                            None => continue,
+                        },
+                        None => &mut *root,
+                    };
+
+                    let new_node = match shape {
+                        QueuedShape::Group => Some(parent_node.add_group()),
+                        QueuedShape::Cube(s) => Some(parent_node.add_cube(s.x, s.y, s.z)),
+                        QueuedShape::Sphere(r) => Some(parent_node.add_sphere(r)),
+                        QueuedShape::Cone(r, h) => Some(parent_node.add_cone(r, h)),
+                        QueuedShape::Cylinder(r, h) => Some(parent_node.add_cylinder(r, h)),
+                        QueuedShape::Capsule(r, h) => Some(parent_node.add_capsule(r, h)),
+                        QueuedShape::Quad(w, h, us, vs) => Some(parent_node.add_quad(w, h, us, vs)),
+                        QueuedShape::Geometry(name, scale) => {
+                            let node = parent_node.add_geom_with_name(&name, scale);
+
+                            if node.is_none() {
+                                println!("Warning: unknown geometry `{}` in queued command.", name);
+                            }
+
+                            node
+                        }
+                        QueuedShape::TriMesh(mesh, scale) => {
+                            Some(parent_node.add_trimesh(mesh, scale))
+                        }
+                    };
+
+                    if let Some(new_node) = new_node {
+                        let _ = self.nodes.insert(node, (new_node, parent));
+                    }
+                }
+                SceneCommand::Modify { node, f } => {
+                    if let Some((node, _)) = self.nodes.get_mut(&node) {
+                        f(node)
+                    }
+                }
+                SceneCommand::Remove { node } => {
+                    if let Some((mut scene_node, _)) = self.nodes.remove(&node) {
+                        scene_node.unlink();
+                        self.forget_descendants(node);
+                    }
+                }
+            }
+        }
+    }
+
+    // Stops tracking the descendants of a removed node.
+    fn forget_descendants(&mut self, node: QueuedNode) {
+        let mut removed = vec![node];
+
+        while let Some(parent) = removed.pop() {
+            let children: Vec<_> = self
+                .nodes
+                .iter()
+                .filter(|(_, (_, p))| *p == Some(parent))
+                .map(|(id, _)| *id)
+                .collect();
+
+            for child in children {
+                let _ = self.nodes.remove(&child);
+                removed.push(child);
+            }
+        }
+    }
+}
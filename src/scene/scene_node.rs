@@ -1,68 +1,193 @@
 use crate::camera::Camera;
+use crate::color::Color;
+use crate::colormap::Colormap;
+use crate::convert::{IntoTranslation3, IntoUnitQuaternion};
 use crate::light::Light;
-use crate::resource::{Material, MaterialManager, Mesh, MeshManager, Texture, TextureManager};
-use crate::scene::Object;
+use crate::load_error::Error;
+use crate::loader::import::ImportOptions;
+use crate::loader::mtl::MtlMaterial;
+use crate::loader::progress::LoadProgress;
+use crate::resource::{
+    Material, MaterialManager, Mesh, MeshLoadOptions, MeshManager, MeshRayHit, Skeleton, Texture,
+    TextureManager,
+};
+use crate::scene::behavior::{self, Behavior};
+use crate::scene::explode::{self, Explosion};
+use crate::scene::interpolation::{self, Interpolation};
+use crate::scene::lightmap;
+use crate::scene::node_arena::{NodeArena, Slot};
+use crate::scene::spatial_index;
+use crate::scene::trail::{self, Trail};
+use crate::scene::validation;
+use crate::scene::world_origin;
+use crate::scene::{
+    AnimationPlayer, ClothNode, DebugVisualization, DepthPrepass, LightmapOptions, NodeHandle,
+    Object, OcclusionCuller, RopeNode, TransparencyMode,
+};
 use na;
-use na::{Isometry3, Point2, Point3, Translation3, UnitQuaternion, Vector3};
+use na::{
+    Isometry3, Matrix3, Matrix4, Point2, Point3, Rotation3, Translation3, UnitQuaternion, Vector3,
+};
+use ncollide3d::bounding_volume::{BoundingSphere, AABB};
 use ncollide3d::procedural;
 use ncollide3d::procedural::TriMesh;
-use std::cell::{Ref, RefCell, RefMut};
-use std::mem;
+use ncollide3d::query::Ray;
+use std::cell::{Cell, Ref, RefCell, RefMut};
+use std::cmp::Ordering;
+use std::marker::PhantomData;
 use std::path::{Path, PathBuf};
 use std::rc::Rc;
 
+/// The object of a scene graph hit by a ray, e.g., the object under the mouse cursor.
+#[derive(Clone)]
+pub struct PickHit {
+    /// The node containing the hit object.
+    pub node: SceneNode,
+    /// The world-space hit point.
+    pub point: Point3<f32>,
+    /// The index of the hit triangle of the object mesh.
+    pub face: usize,
+    /// The distance from the origin of the ray to the hit point, in units of the ray direction.
+    pub toi: f32,
+    /// The world-space unit normal of the hit triangle.
+    pub normal: Vector3<f32>,
+}
+
 // XXX: once something like `fn foo(self: Rc<RefCell<SceneNode>>)` is allowed, this extra struct
 // will not be needed any more.
 /// The datas contained by a `SceneNode`.
 pub struct SceneNodeData {
     local_scale: Vector3<f32>,
     local_transform: Isometry3<f32>,
-    world_scale: Vector3<f32>,
-    world_transform: Isometry3<f32>,
+    // The double-precision translation, in world coordinates, set by `set_local_translation_f64`.
+    // The translation of `local_transform` is then derived from it and the world origin.
+    translation_f64: Option<Vector3<f64>>,
+    // The generation of the world origin when the world transformation was computed.
+    origin_generation: Cell<u64>,
+    // The world transformation is recomputed lazily, and only for the nodes whose local
+    // transformation, or one of its ancestors' transformation, changed.
+    world_scale: Cell<Vector3<f32>>,
+    world_transform: Cell<Isometry3<f32>>,
+    // Whether the local transformation changed since the world transformation was computed.
+    local_dirty: Cell<bool>,
+    // Incremented each time the world transformation is recomputed.
+    world_generation: Cell<u64>,
+    // The `world_generation` of the parent when the world transformation was computed.
+    parent_generation: Cell<u64>,
     visible: bool,
-    up_to_date: bool,
+    // Whether this node can be picked, or `None` to follow its visibility.
+    pickable: Option<bool>,
+    // Whether this node casts shadows, or `None` to follow its visibility.
+    shadow_caster: Option<bool>,
+    name: Option<String>,
     children: Vec<SceneNode>,
     object: Option<Object>,
-    // FIXME: use Weak pointers instead of the raw pointer.
-    parent: Option<*const RefCell<SceneNodeData>>,
+    // A handle does not keep the parent alive: a destroyed parent is treated as no parent.
+    parent: Option<NodeHandle>,
+    // The offsets of the children, while this node is exploded.
+    explosion: Option<Explosion>,
+    behaviors: Vec<Behavior>,
+    // Whether the behaviors have been removed since they were taken out to be executed.
+    behaviors_cleared: bool,
+    trail: Option<Trail>,
+    // The transformations at the last two simulation steps, while this node is interpolated.
+    interpolation: Option<Interpolation>,
+    // The conversion applied to the asset this node was imported from.
+    import_options: Option<ImportOptions>,
+    // A light following this node, its position being relative to the node.
+    light: Option<Light>,
+}
+
+// A visible node, copied out of the scene graph so that it can be processed on other threads.
+#[cfg(feature = "parallel")]
+struct FlatNode {
+    parent: Option<usize>,
+    depth: usize,
+    local_transform: Isometry3<f32>,
+    local_scale: Vector3<f32>,
+    world_transform: Isometry3<f32>,
+    world_scale: Vector3<f32>,
+    dirty: bool,
+    draw_key: Option<(usize, usize)>,
+}
+
+// Removes the objects whose bounding box is entirely outside of the view frustum of the pass.
+//
+// Skinned objects are kept, their vertices being moved on the GPU. The boxes are tested on
+// multiple threads with the `parallel` feature.
+fn cull_outside_frustum(
+    queue: &mut Vec<((usize, usize), SceneNode)>,
+    camera: &dyn Camera,
+    pass: usize,
+) {
+    let (proj, view) = camera.pass_matrices(pass);
+    let proj_view = proj * view;
+    let boxes: Vec<_> = queue
+        .iter()
+        .map(|(_, node)| {
+            let data = node.data();
+            let object = data.object.as_ref()?;
+
+            if object.data().skeleton().is_some() {
+                return None;
+            }
+
+            let aabb = object.mesh().borrow().aabb()?;
+            let model = data.world_transform.get().to_homogeneous()
+                * Matrix4::new_nonuniform_scaling(&data.world_scale.get());
+
+            Some((aabb, proj_view * model))
+        })
+        .collect();
+    let in_frustum = |b: &Option<(AABB<f32>, Matrix4<f32>)>| match b {
+        Some((aabb, transform)) => spatial_index::aabb_in_frustum(aabb, transform),
+        None => true,
+    };
+
+    #[cfg(not(feature = "parallel"))]
+    let visible: Vec<bool> = boxes.iter().map(in_frustum).collect();
+
+    #[cfg(feature = "parallel")]
+    let visible: Vec<bool> = {
+        use rayon::prelude::*;
+        boxes.par_iter().map(in_frustum).collect()
+    };
+
+    let mut visible = visible.into_iter();
+    queue.retain(|_| visible.next().unwrap());
 }
 
 /// A node of the scene graph.
 ///
 /// This may represent a group of other nodes, and/or contain an object that can be rendered.
-#[derive(Clone)]
+///
+/// The data of all the scene nodes of a thread are stored in a shared pool. A `SceneNode` is a
+/// reference-counted handle to its data: the node is destroyed when its last `SceneNode` is
+/// dropped.
 pub struct SceneNode {
-    data: Rc<RefCell<SceneNodeData>>,
+    handle: NodeHandle,
+    // The storage of the node data, cached to avoid looking it up in the arena.
+    slot: &'static Slot,
+    // Scene nodes are bound to the thread of their pool.
+    _marker: PhantomData<Rc<()>>,
 }
 
 impl SceneNodeData {
-    // XXX: Because `node.borrow_mut().parent = Some(self.data.downgrade())`
-    // causes a weird compiler error:
-    //
-    // ```
-    // error: mismatched types: expected `&std::cell::RefCell<scene::scene_node::SceneNodeData>`
-    // but found
-    // `std::option::Option<std::rc::Weak<std::cell::RefCell<scene::scene_node::SceneNodeData>>>`
-    // (expe cted &-ptr but found enum std::option::Option)
-    // ```
-    fn set_parent(&mut self, parent: *const RefCell<SceneNodeData>) {
+    fn set_parent(&mut self, parent: NodeHandle) {
         self.parent = Some(parent);
+        self.invalidate();
     }
 
-    // XXX: this exists because of a similar bug as `set_parent`.
     fn remove_from_parent(&mut self, to_remove: &SceneNode) {
-        let _ = self.parent.as_ref().map(|p| unsafe {
-            let mut bp = (**p).borrow_mut();
-            bp.remove(to_remove)
-        });
+        if let Some(slot) = self.parent.and_then(|p| NodeArena::get().get_slot(p)) {
+            if let Some(parent) = slot.data.borrow_mut().as_mut() {
+                parent.remove(to_remove)
+            }
+        }
     }
 
     fn remove(&mut self, o: &SceneNode) {
-        if let Some(i) = self
-            .children
-            .iter()
-            .rposition(|e| std::ptr::eq(&*o.data, &*e.data))
-        {
+        if let Some(i) = self.children.iter().rposition(|e| e.handle == o.handle) {
             let _ = self.children.swap_remove(i);
         }
     }
@@ -76,46 +201,238 @@ impl SceneNodeData {
     /// Whether this node has no parent.
     #[inline]
     pub fn is_root(&self) -> bool {
-        self.parent.is_none()
+        self.parent().is_none()
+    }
+
+    /// The handle of the parent of this node, if it has one.
+    #[inline]
+    pub fn parent(&self) -> Option<NodeHandle> {
+        self.parent
+            .filter(|p| NodeArena::get().get_slot(*p).is_some())
     }
 
     /// Render the scene graph rooted by this node.
+    ///
+    /// Only the world transformations of the nodes that changed since the last render are
+    /// recomputed. The visible objects are sorted by material and texture before being drawn, to
+    /// minimize the number of state changes. Objects sharing the same material and texture are
+    /// drawn in the scene graph order.
+    ///
+    /// With the `parallel` feature, the world transformations, the frustum culling enabled by
+    /// `Window::set_frustum_culling`, and the sorting of the objects, including the blended ones
+    /// by distance, are computed on multiple threads. Only the draw calls are issued from the
+    /// current thread.
     pub fn render(&mut self, pass: usize, camera: &mut dyn Camera, light: &Light) {
-        if self.visible {
-            self.do_render(&na::one(), &Vector3::from_element(1.0), pass, camera, light)
-        }
+        self.render_with_occlusion_culler(pass, camera, light, None, None, false)
     }
 
-    fn do_render(
+    // Renders the scene graph rooted by this node, skipping the objects outside of the view
+    // frustum if `frustum_culling` is set, and those found occluded by the culler, if any, after
+    // filling the depth buffer with the opaque objects if a depth pre-pass is given.
+    pub(crate) fn render_with_occlusion_culler(
         &mut self,
-        transform: &Isometry3<f32>,
-        scale: &Vector3<f32>,
         pass: usize,
         camera: &mut dyn Camera,
         light: &Light,
+        culler: Option<&mut OcclusionCuller>,
+        prepass: Option<&mut DepthPrepass>,
+        frustum_culling: bool,
     ) {
-        if !self.up_to_date {
-            self.up_to_date = true;
-            self.world_transform = *transform * self.local_transform;
-            self.world_scale = scale.component_mul(&self.local_scale);
+        if !self.visible {
+            return;
+        }
+
+        self.update();
+
+        let mut queue = Vec::new();
+
+        #[cfg(not(feature = "parallel"))]
+        {
+            self.collect_visible_objects(&mut queue);
+            queue.sort_by_key(|e| e.0);
+        }
+
+        #[cfg(feature = "parallel")]
+        self.collect_visible_objects_par(&mut queue);
+
+        if frustum_culling {
+            cull_outside_frustum(&mut queue, &*camera, pass);
+        }
+
+        // Only one light is supported, so a light attached to a node replaces the given one.
+        let attached_light = self.find_attached_light();
+        let light = attached_light.as_ref().unwrap_or(light);
+
+        if let Some(prepass) = prepass {
+            prepass.begin(pass, camera);
+
+            if let Some(ref o) = self.object {
+                if DepthPrepass::accepts(o) {
+                    prepass.draw(o, &self.world_transform.get(), &self.world_scale.get());
+                }
+            }
+
+            for (_, node) in queue.iter() {
+                let bn = node.data();
+
+                if let Some(ref o) = bn.object {
+                    if DepthPrepass::accepts(o) && !(culler.is_some() && is_occluded(o, pass)) {
+                        prepass.draw(o, &bn.world_transform.get(), &bn.world_scale.get());
+                    }
+                }
+            }
+
+            prepass.end();
         }
 
+        // Blended and x-rayed objects are drawn last, once everything behind them is in the color
+        // buffer.
+        let mut blended = Vec::new();
+
         if let Some(ref o) = self.object {
-            o.render(
-                &self.world_transform,
-                &self.world_scale,
-                pass,
-                camera,
-                light,
-            )
+            if o.data().is_blended() || o.data().xray_enabled() {
+                blended.push(None);
+            } else {
+                o.render(
+                    &self.world_transform.get(),
+                    &self.world_scale.get(),
+                    pass,
+                    camera,
+                    light,
+                )
+            }
         }
 
-        for c in self.children.iter_mut() {
-            let mut bc = c.data_mut();
-            if bc.visible {
-                bc.do_render(
-                    &self.world_transform,
-                    &self.world_scale,
+        let validate = validation::is_enabled();
+
+        for (_, node) in queue.iter() {
+            let bn = node.data();
+
+            if let Some(ref o) = bn.object {
+                if culler.is_some() && is_occluded(o, pass) {
+                    continue;
+                }
+
+                if o.data().is_blended() || o.data().xray_enabled() {
+                    blended.push(Some(node));
+                    continue;
+                }
+
+                if validate && pass == 0 {
+                    validation::validate(
+                        node.handle,
+                        bn.name(),
+                        o,
+                        &bn.world_transform.get(),
+                        &bn.world_scale.get(),
+                    );
+                }
+
+                o.render(
+                    &bn.world_transform.get(),
+                    &bn.world_scale.get(),
+                    pass,
+                    camera,
+                    light,
+                )
+            }
+        }
+
+        if !blended.is_empty() {
+            self.render_blended(&blended, pass, camera, light);
+        }
+
+        // Test the bounding boxes against the depth buffer of the first pass, for the next frame.
+        if let (Some(culler), 0) = (culler, pass) {
+            let eye = camera.eye();
+            let mut started = false;
+
+            for (_, node) in queue.iter() {
+                let bn = node.data();
+
+                if let Some(state) = bn.object.as_ref().and_then(|o| o.occlusion_state()) {
+                    if !started {
+                        culler.begin(pass, camera);
+                        started = true;
+                    }
+
+                    culler.query(
+                        &mut state.borrow_mut(),
+                        &bn.world_transform.get(),
+                        &bn.world_scale.get(),
+                        &eye,
+                    );
+                }
+            }
+
+            if started {
+                culler.end();
+            }
+        }
+    }
+
+    // Renders the blended objects of the given nodes, `None` being this node, from the farthest
+    // to the closest to the camera, followed by the x-rayed objects in the same order.
+    fn render_blended(
+        &self,
+        nodes: &[Option<&SceneNode>],
+        pass: usize,
+        camera: &mut dyn Camera,
+        light: &Light,
+    ) {
+        let eye = camera.eye();
+        let mut sorted: Vec<_> = nodes
+            .iter()
+            .enumerate()
+            .map(|(i, node)| {
+                let t = match node {
+                    Some(node) => node.data().world_transform.get(),
+                    None => self.world_transform.get(),
+                };
+                let dist = na::distance_squared(&eye, &Point3::from(t.translation.vector));
+                let xray = match node {
+                    Some(node) => node
+                        .data()
+                        .object
+                        .as_ref()
+                        .is_some_and(|o| o.data().xray_enabled()),
+                    None => self
+                        .object
+                        .as_ref()
+                        .is_some_and(|o| o.data().xray_enabled()),
+                };
+                (xray, dist, i)
+            })
+            .collect();
+        let order = |a: &(bool, f32, usize), b: &(bool, f32, usize)| {
+            a.0.cmp(&b.0)
+                .then(b.1.partial_cmp(&a.1).unwrap_or(Ordering::Equal))
+        };
+
+        #[cfg(not(feature = "parallel"))]
+        sorted.sort_by(order);
+
+        #[cfg(feature = "parallel")]
+        {
+            use rayon::prelude::*;
+            sorted.par_sort_by(order);
+        }
+
+        for (_, _, i) in sorted {
+            let node = nodes[i];
+            let guard;
+            let bn = match node {
+                Some(node) => {
+                    guard = node.data();
+                    &*guard
+                }
+                None => self,
+            };
+
+            if let Some(ref o) = bn.object {
+                o.render(
+                    &bn.world_transform.get(),
+                    &bn.world_scale.get(),
                     pass,
                     camera,
                     light,
@@ -124,6 +441,143 @@ impl SceneNodeData {
         }
     }
 
+    // Collects the visible descendants of this node that contain an object, along with their
+    // draw key, assuming the world transformation of this node is up to date.
+    #[cfg(not(feature = "parallel"))]
+    fn collect_visible_objects(&self, out: &mut Vec<((usize, usize), SceneNode)>) {
+        let world_transform = self.world_transform.get();
+        let world_scale = self.world_scale.get();
+        let world_generation = self.world_generation.get();
+
+        for c in self.children.iter() {
+            let bc = c.data();
+
+            if bc.visible {
+                bc.refresh_world_transform(&world_transform, &world_scale, world_generation);
+
+                if let Some(ref o) = bc.object {
+                    out.push((o.draw_key(), c.clone()));
+                }
+
+                bc.collect_visible_objects(out);
+            }
+        }
+    }
+
+    // Same as `collect_visible_objects` followed by a sort of the result, except that the world
+    // transformations are computed and the objects are sorted on multiple threads.
+    #[cfg(feature = "parallel")]
+    fn collect_visible_objects_par(&self, out: &mut Vec<((usize, usize), SceneNode)>) {
+        use rayon::prelude::*;
+
+        let mut nodes = Vec::new();
+        let mut flat = Vec::new();
+        self.flatten_visible(None, 0, &mut nodes, &mut flat);
+
+        // Nodes of the same depth do not depend on each other.
+        let ndepths = flat.iter().map(|n| n.depth + 1).max().unwrap_or(0);
+        let mut levels = vec![Vec::new(); ndepths];
+
+        for (i, n) in flat.iter().enumerate() {
+            levels[n.depth].push(i);
+        }
+
+        let root_transform = self.world_transform.get();
+        let root_scale = self.world_scale.get();
+        let mut recomputed = vec![false; flat.len()];
+
+        for level in levels {
+            let updates: Vec<_> = level
+                .par_iter()
+                .filter_map(|&i| {
+                    let n = &flat[i];
+                    let (transform, scale, parent_recomputed) = match n.parent {
+                        Some(p) => (flat[p].world_transform, flat[p].world_scale, recomputed[p]),
+                        None => (root_transform, root_scale, false),
+                    };
+
+                    if n.dirty || parent_recomputed {
+                        Some((
+                            i,
+                            transform * n.local_transform,
+                            scale.component_mul(&n.local_scale),
+                        ))
+                    } else {
+                        None
+                    }
+                })
+                .collect();
+
+            for (i, transform, scale) in updates {
+                flat[i].world_transform = transform;
+                flat[i].world_scale = scale;
+                recomputed[i] = true;
+            }
+        }
+
+        // Parents are stored before their children so their generation is already updated.
+        for (i, node) in nodes.iter().enumerate() {
+            if recomputed[i] {
+                let parent_generation = match flat[i].parent {
+                    Some(p) => nodes[p].data().world_generation.get(),
+                    None => self.world_generation.get(),
+                };
+
+                node.data().set_world_transform(
+                    &flat[i].world_transform,
+                    &flat[i].world_scale,
+                    parent_generation,
+                );
+            }
+        }
+
+        let mut keys: Vec<_> = flat
+            .iter()
+            .enumerate()
+            .filter_map(|(i, n)| n.draw_key.map(|k| (k, i)))
+            .collect();
+        keys.par_sort_by_key(|e| e.0);
+
+        out.extend(keys.into_iter().map(|(k, i)| (k, nodes[i].clone())));
+    }
+
+    // Flattens the visible descendants of this node, each parent being stored before its
+    // children.
+    #[cfg(feature = "parallel")]
+    fn flatten_visible(
+        &self,
+        parent: Option<usize>,
+        depth: usize,
+        nodes: &mut Vec<SceneNode>,
+        flat: &mut Vec<FlatNode>,
+    ) {
+        let world_generation = self.world_generation.get();
+
+        for c in self.children.iter() {
+            let bc = c.data();
+
+            if bc.visible {
+                let id = nodes.len();
+
+                flat.push(FlatNode {
+                    parent,
+                    depth,
+                    local_transform: bc.effective_local_transform(),
+                    local_scale: bc.local_scale,
+                    world_transform: bc.world_transform.get(),
+                    world_scale: bc.world_scale.get(),
+                    dirty: bc.local_dirty.get()
+                        || bc.parent_generation.get() != world_generation
+                        || bc.origin_moved(),
+                    draw_key: bc.object.as_ref().map(|o| o.draw_key()),
+                });
+                nodes.push(c.clone());
+
+                bc.flatten_visible(Some(id), depth + 1, nodes, flat);
+            }
+        }
+    }
+
     /// A reference to the object possibly contained by this node.
     #[inline]
     pub fn object(&self) -> Option<&Object> {
@@ -193,6 +647,15 @@ impl SceneNodeData {
         self.apply_to_objects_mut(&mut |o| o.set_lines_width(width))
     }
 
+    /// Enables or disables occlusion culling for the objects contained by this node and its
+    /// children.
+    ///
+    /// See `Object::set_occlusion_culling` for details.
+    #[inline]
+    pub fn set_occlusion_culling(&mut self, enabled: bool) {
+        self.apply_to_objects_mut(&mut |o| o.set_occlusion_culling(enabled))
+    }
+
     /// Sets the color of the lines drawn for the objects contained by this node and its children.
     #[inline]
     pub fn set_lines_color(&mut self, color: Option<Point3<f32>>) {
@@ -227,6 +690,18 @@ impl SceneNodeData {
         self.apply_to_objects_mut(&mut |o| o.modify_vertices(f))
     }
 
+    /// Overwrites the vertices of the objects contained by this node and its children directly
+    /// in their gpu buffers.
+    ///
+    /// The provided closure is called once per object. Returns `false` if the vertices of an
+    /// object could not be written.
+    #[inline(always)]
+    pub fn write_vertices_gpu<F: FnMut(&mut [Point3<f32>])>(&mut self, f: &mut F) -> bool {
+        let mut written = true;
+        self.apply_to_objects_mut(&mut |o| written &= o.write_vertices_gpu(f));
+        written
+    }
+
     /// Accesses the vertices of the objects contained by this node and its children.
     ///
     /// The provided closure is called once per object.
@@ -235,6 +710,24 @@ impl SceneNodeData {
         self.apply_to_objects(&mut |o| o.read_vertices(f))
     }
 
+    /// Colors the vertices of the objects contained by this node and its children according to
+    /// one scalar value per vertex.
+    ///
+    /// Each object mesh must have exactly one value per vertex. If `range` is `None`, the minimum
+    /// and maximum of `values` are mapped to the two ends of the colormap. The objects are
+    /// switched to the `vertex_color` material.
+    pub fn set_scalar_field(
+        &mut self,
+        values: &[f32],
+        colormap: &Colormap,
+        range: Option<(f32, f32)>,
+    ) {
+        let colors = colormap.map_all(values, range);
+
+        self.apply_to_objects_mut(&mut |o| o.mesh().borrow_mut().set_colors(Some(colors.clone())));
+        self.set_material_with_name("vertex_color")
+    }
+
     /// Recomputes the normals of the meshes of the objects contained by this node and its
     /// children.
     #[inline]
@@ -305,26 +798,237 @@ impl SceneNodeData {
         self.visible = visible;
     }
 
+    /// Whether this node can be hit by the ray casts and closest point queries of the scene
+    /// graph, i.e., by `SceneNode::cast_ray` and `SceneNode::closest_point`.
+    #[inline]
+    pub fn is_pickable(&self) -> bool {
+        self.pickable.unwrap_or(self.visible)
+    }
+
+    /// Sets whether this node and its children can be picked, independently of their
+    /// visibility.
+    ///
+    /// By default, a node is pickable if and only if it is visible. An invisible but pickable
+    /// node may serve as a collision proxy, and a visible but non-pickable node as a gizmo that
+    /// must not get in the way of picking.
+    #[inline]
+    pub fn set_pickable(&mut self, pickable: bool) {
+        self.pickable = Some(pickable);
+    }
+
+    /// Whether the object of this node casts shadows.
+    ///
+    /// Unless set with `set_shadow_caster`, this is whether this node and all its ancestors are
+    /// visible.
+    #[inline]
+    pub fn is_shadow_caster(&self) -> bool {
+        self.shadow_caster
+            .unwrap_or_else(|| self.is_visible_with_ancestors())
+    }
+
+    // Whether this node and all its ancestors are visible.
+    fn is_visible_with_ancestors(&self) -> bool {
+        if !self.visible {
+            return false;
+        }
+
+        match self.parent.and_then(|p| NodeArena::get().get_slot(p)) {
+            // The parent cannot be borrowed while it is being traversed, e.g., during rendering,
+            // where only the visible nodes are traversed.
+            Some(slot) => match slot.data.try_borrow().as_deref() {
+                Ok(Some(dp)) => dp.is_visible_with_ancestors(),
+                _ => true,
+            },
+            None => true,
+        }
+    }
+
+    /// Sets whether the object of this node casts shadows, independently of its visibility.
+    ///
+    /// By default, a node casts shadows if and only if it and its ancestors are visible. An
+    /// invisible shadow caster is an occluder that only appears through its shadow. The shadows
+    /// are rendered when shadow mapping is enabled with `Window::set_shadow_mapping`.
+    #[inline]
+    pub fn set_shadow_caster(&mut self, shadow_caster: bool) {
+        self.shadow_caster = Some(shadow_caster);
+    }
+
+    /// The name of this node, used to identify it in diagnostics.
+    #[inline]
+    pub fn name(&self) -> Option<&str> {
+        self.name.as_deref()
+    }
+
+    /// Sets the name of this node, used to identify it in diagnostics.
+    #[inline]
+    pub fn set_name(&mut self, name: &str) {
+        self.name = Some(name.to_string());
+    }
+
+    /// The conversion applied to the coordinates of the asset this node was imported from, if
+    /// this node is the root of an imported asset.
+    #[inline]
+    pub fn import_options(&self) -> Option<&ImportOptions> {
+        self.import_options.as_ref()
+    }
+
+    #[cfg(feature = "gltf")]
+    pub(crate) fn set_import_options(&mut self, import_options: ImportOptions) {
+        self.import_options = Some(import_options);
+    }
+
+    /// The light attached to this node, if any.
+    #[inline]
+    pub fn light(&self) -> Option<&Light> {
+        self.light.as_ref()
+    }
+
+    /// Attaches a light to this node, replacing the one already attached, so that it follows the
+    /// node when it moves.
+    ///
+    /// The position of a `Light::Absolute` is relative to this node. While this node and its
+    /// ancestors are visible, the light replaces the one of the window it is rendered by. Only
+    /// one light is supported: if several nodes have one, the first one found in the scene graph
+    /// order is used.
+    #[inline]
+    pub fn attach_light(&mut self, light: Light) {
+        self.light = Some(light);
+    }
+
+    /// Detaches the light attached to this node, and returns it.
+    #[inline]
+    pub fn detach_light(&mut self) -> Option<Light> {
+        self.light.take()
+    }
+
+    /// The light attached to this node, with the position of a `Light::Absolute` converted to
+    /// world coordinates.
+    pub fn world_light(&self) -> Option<Light> {
+        match self.light.as_ref()? {
+            Light::Absolute(p) => {
+                let scaled = Point3::from(p.coords.component_mul(&self.world_scale()));
+                Some(Light::Absolute(self.world_transformation() * scaled))
+            }
+            Light::StickToCamera => Some(Light::StickToCamera),
+        }
+    }
+
+    // The world light attached to this node or to its first visible descendant with one.
+    pub(crate) fn find_attached_light(&self) -> Option<Light> {
+        if self.light.is_some() {
+            return self.world_light();
+        }
+
+        self.children.iter().find_map(|c| {
+            let bc = c.data();
+
+            if bc.visible {
+                bc.find_attached_light()
+            } else {
+                None
+            }
+        })
+    }
+
+    // The world lights attached to this node and its descendants, each one with whether it is
+    // the light returned by `find_attached_light`.
+    pub(crate) fn attached_lights(&self) -> Vec<(Light, bool)> {
+        let mut lights = Vec::new();
+        let mut found = false;
+        self.collect_attached_lights(true, &mut found, &mut lights);
+        lights
+    }
+
+    fn collect_attached_lights(
+        &self,
+        reachable: bool,
+        found: &mut bool,
+        out: &mut Vec<(Light, bool)>,
+    ) {
+        if let Some(light) = self.world_light() {
+            let active = reachable && !*found;
+            *found |= active;
+            out.push((light, active));
+        }
+
+        for c in self.children.iter() {
+            let bc = c.data();
+            bc.collect_attached_lights(reachable && bc.visible, found, out);
+        }
+    }
+
     /// Sets the color of the objects contained by this node and its children.
     ///
     /// Colors components must be on the range `[0.0, 1.0]`.
     #[inline]
-    pub fn set_color(&mut self, r: f32, g: f32, b: f32) {
-        self.apply_to_objects_mut(&mut |o| o.set_color(r, g, b))
+    pub fn set_color(&mut self, color: impl Into<Color>) {
+        let color = color.into();
+        self.apply_to_objects_mut(&mut |o| o.set_color(color))
+    }
+
+    /// Sets the opacity of the objects contained by this node and its children, from `0.0`
+    /// (invisible) to `1.0` (opaque).
+    ///
+    /// Only the default material supports transparency.
+    #[inline]
+    pub fn set_opacity(&mut self, opacity: f32) {
+        self.apply_to_objects_mut(&mut |o| o.set_opacity(opacity))
+    }
+
+    /// Sets how the objects contained by this node and its children are rendered when their
+    /// opacity is smaller than `1.0`.
+    #[inline]
+    pub fn set_transparency_mode(&mut self, mode: TransparencyMode) {
+        self.apply_to_objects_mut(&mut |o| o.set_transparency_mode(mode))
+    }
+
+    /// Enables or disables the x-ray rendering of the objects contained by this node and its
+    /// children.
+    #[inline]
+    pub fn set_xray(&mut self, enabled: bool) {
+        self.apply_to_objects_mut(&mut |o| o.set_xray(enabled))
+    }
+
+    /// Enables or disables the flat shading of the objects contained by this node and its
+    /// children.
+    #[inline]
+    pub fn set_flat_shading(&mut self, enabled: bool) {
+        self.apply_to_objects_mut(&mut |o| o.set_flat_shading(enabled))
+    }
+
+    /// Sets the skeleton deforming the skinned meshes of the objects contained by this node and
+    /// its children.
+    #[inline]
+    pub fn set_skeleton(&mut self, skeleton: Option<Rc<RefCell<Skeleton>>>) {
+        self.apply_to_objects_mut(&mut |o| o.set_skeleton(skeleton.clone()))
+    }
+
+    /// Sets whether the shadows are drawn on the objects contained by this node and its
+    /// children.
+    #[inline]
+    pub fn set_receive_shadows(&mut self, enabled: bool) {
+        self.apply_to_objects_mut(&mut |o| o.set_receive_shadows(enabled))
+    }
+
+    /// Sets the debug rendering of the objects contained by this node and its children.
+    #[inline]
+    pub fn set_debug_visualization(&mut self, mode: DebugVisualization) {
+        self.apply_to_objects_mut(&mut |o| o.set_debug_visualization(mode))
     }
 
     /// Sets the texture of the objects contained by this node and its children.
     ///
-    /// The texture is loaded from a file and registered by the global `TextureManager`.
+    /// The texture is loaded from a file and registered by the global `TextureManager`. Returns
+    /// an error if the file cannot be read or decoded.
     ///
     /// # Arguments
     ///   * `path` - relative path of the texture on the disk
     ///   * `name` - &str identifier to store this texture under
     #[inline]
-    pub fn set_texture_from_file(&mut self, path: &Path, name: &str) {
-        let texture = TextureManager::get_global_manager(|tm| tm.add(path, name));
-
-        self.set_texture(texture)
+    pub fn set_texture_from_file(&mut self, path: &Path, name: &str) -> Result<(), Error> {
+        let texture = TextureManager::get_global_manager(|tm| tm.add(path, name))?;
+        self.set_texture(texture);
+        Ok(())
     }
 
     /// Sets the texture of the objects contained by this node and its children.
@@ -405,6 +1109,7 @@ impl SceneNodeData {
     #[inline]
     pub fn reorient(&mut self, eye: &Point3<f32>, at: &Point3<f32>, up: &Vector3<f32>) {
         self.invalidate();
+        self.translation_f64 = None;
         // FIXME: multiply by the parent's world transform?
         self.local_transform = Isometry3::face_towards(eye, at, up)
     }
@@ -412,28 +1117,24 @@ impl SceneNodeData {
     /// This node local transformation.
     #[inline]
     pub fn local_transformation(&self) -> Isometry3<f32> {
-        self.local_transform
+        self.effective_local_transform()
     }
 
     /// Inverse of this node local transformation.
     #[inline]
     pub fn inverse_local_transformation(&self) -> Isometry3<f32> {
-        self.local_transform.inverse()
+        self.effective_local_transform().inverse()
     }
 
     /// This node world transformation.
     ///
     /// This will force an update of the world transformation of its parents if they have been
-    /// invalidated.
+    /// invalidated. If nothing changed since the last computation, the cached value is returned
+    /// after a walk to the root of the scene graph.
     #[inline]
-    #[allow(mutable_transmutes)]
     pub fn world_transformation(&self) -> Isometry3<f32> {
-        // NOTE: this is to have some kind of laziness without a `&mut self`.
-        unsafe {
-            let mself: &mut SceneNodeData = mem::transmute(self);
-            mself.update();
-        }
-        self.world_transform
+        self.update();
+        self.world_transform.get()
     }
 
     /// The inverse of this node world transformation.
@@ -441,53 +1142,106 @@ impl SceneNodeData {
     /// This will force an update of the world transformation of its parents if they have been
     /// invalidated.
     #[inline]
-    #[allow(mutable_transmutes)]
     pub fn inverse_world_transformation(&self) -> Isometry3<f32> {
-        // NOTE: this is to have some kind of laziness without a `&mut self`.
-        unsafe {
-            let mself: &mut SceneNodeData = mem::transmute(self);
-            mself.update();
-        }
-        self.local_transform.inverse()
+        self.world_transformation().inverse()
     }
 
-    /// Appends a transformation to this node local transformation.
-    #[inline]
-    pub fn append_transformation(&mut self, t: &Isometry3<f32>) {
-        self.invalidate();
-        self.local_transform = t * self.local_transform
-    }
+    /// Computes the first intersection of a world-space ray with the object of this node.
+    ///
+    /// The intersection is computed on the bounding volume hierarchy of the object mesh. The
+    /// returned time of impact and normal are expressed in world-space.
+    pub fn cast_ray_on_object(&self, ray: &Ray<f32>, max_toi: f32) -> Option<MeshRayHit> {
+        let object = self.object.as_ref()?;
+        let bvh = object.mesh().borrow().bvh()?;
+        let transform = self.world_transformation();
+        let scale = self.world_scale();
+
+        // The direction is not normalized, so the time of impact is preserved.
+        let origin = transform.inverse_transform_point(&ray.origin);
+        let dir = transform.inverse_transform_vector(&ray.dir);
+        let local_ray = Ray::new(
+            Point3::from(origin.coords.component_div(&scale)),
+            dir.component_div(&scale),
+        );
 
-    /// Prepends a transformation to this node local transformation.
-    #[inline]
-    pub fn prepend_to_local_transformation(&mut self, t: &Isometry3<f32>) {
-        self.invalidate();
-        self.local_transform *= t;
+        let mut hit = bvh.cast_ray(&local_ray, max_toi)?;
+        hit.normal = (transform.rotation * hit.normal.component_div(&scale)).normalize();
+
+        Some(hit)
+    }
+
+    /// The world-space point of the object of this node closest to `point`.
+    ///
+    /// The point is computed on the bounding volume hierarchy of the object mesh, and is exact
+    /// unless the node has a non-uniform world scale.
+    pub fn closest_point_on_object(&self, point: &Point3<f32>) -> Option<Point3<f32>> {
+        let object = self.object.as_ref()?;
+        let bvh = object.mesh().borrow().bvh()?;
+        let transform = self.world_transformation();
+        let scale = self.world_scale();
+
+        let local = transform.inverse_transform_point(point);
+        let (_, closest) = bvh.closest_point(&Point3::from(local.coords.component_div(&scale)))?;
+
+        Some(transform * Point3::from(closest.coords.component_mul(&scale)))
+    }
+
+    /// This node world scaling factors, i.e., the product of its local scale and the local scales
+    /// of its parents.
+    ///
+    /// This will force an update of the world transformation of its parents if they have been
+    /// invalidated.
+    #[inline]
+    pub fn world_scale(&self) -> Vector3<f32> {
+        self.update();
+        self.world_scale.get()
+    }
+
+    /// Appends a transformation to this node local transformation.
+    #[inline]
+    pub fn append_transformation(&mut self, t: &Isometry3<f32>) {
+        self.invalidate();
+        self.discard_translation_f64();
+        self.local_transform = t * self.local_transform
+    }
+
+    /// Prepends a transformation to this node local transformation.
+    #[inline]
+    pub fn prepend_to_local_transformation(&mut self, t: &Isometry3<f32>) {
+        self.invalidate();
+        self.discard_translation_f64();
+        self.local_transform *= t;
     }
 
     /// Set this node local transformation.
     #[inline]
     pub fn set_local_transformation(&mut self, t: Isometry3<f32>) {
         self.invalidate();
+        self.translation_f64 = None;
         self.local_transform = t
     }
 
     /// This node local translation.
     #[inline]
     pub fn local_translation(&self) -> Translation3<f32> {
-        self.local_transform.translation
+        self.effective_local_transform().translation
     }
 
     /// The inverse of this node local translation.
     #[inline]
     pub fn inverse_local_translation(&self) -> Translation3<f32> {
-        self.local_transform.translation.inverse()
+        self.local_translation().inverse()
     }
 
     /// Appends a translation to this node local transformation.
     #[inline]
     pub fn append_translation(&mut self, t: &Translation3<f32>) {
         self.invalidate();
+
+        if let Some(ref mut t64) = self.translation_f64 {
+            *t64 += t.vector.cast::<f64>();
+        }
+
         self.local_transform = t * self.local_transform
     }
 
@@ -495,6 +1249,11 @@ impl SceneNodeData {
     #[inline]
     pub fn prepend_to_local_translation(&mut self, t: &Translation3<f32>) {
         self.invalidate();
+
+        if let Some(ref mut t64) = self.translation_f64 {
+            *t64 += (self.local_transform.rotation * t.vector).cast::<f64>();
+        }
+
         self.local_transform *= t
     }
 
@@ -502,9 +1261,35 @@ impl SceneNodeData {
     #[inline]
     pub fn set_local_translation(&mut self, t: Translation3<f32>) {
         self.invalidate();
+        self.translation_f64 = None;
         self.local_transform.translation = t
     }
 
+    /// Sets the local translation of this node with double precision, in world coordinates.
+    ///
+    /// The world origin, see `scene::set_world_origin`, is subtracted from `t` with double
+    /// precision before the result is converted to the single-precision translation used for
+    /// rendering. This keeps objects far from the origin of the world from jittering, as long
+    /// as the world origin is kept close to the camera.
+    ///
+    /// Since the world origin is subtracted from the local translation, this is intended for the
+    /// nodes whose parents are not transformed, typically the children of the scene root. The
+    /// children of such a node can use single-precision translations relative to it.
+    #[inline]
+    pub fn set_local_translation_f64(&mut self, t: Vector3<f64>) {
+        self.invalidate();
+        self.translation_f64 = Some(t);
+        self.local_transform.translation = Translation3::from(world_origin::to_render_vector(&t));
+    }
+
+    /// The double-precision translation set by `set_local_translation_f64`, if any.
+    ///
+    /// This returns `None` once the translation has been replaced by a single-precision one.
+    #[inline]
+    pub fn local_translation_f64(&self) -> Option<Vector3<f64>> {
+        self.translation_f64
+    }
+
     /// This node local rotation.
     #[inline]
     pub fn local_rotation(&self) -> UnitQuaternion<f32> {
@@ -521,6 +1306,7 @@ impl SceneNodeData {
     #[inline]
     pub fn append_rotation(&mut self, r: &UnitQuaternion<f32>) {
         self.invalidate();
+        self.discard_translation_f64();
         self.local_transform = r * self.local_transform
     }
 
@@ -545,41 +1331,131 @@ impl SceneNodeData {
         self.local_transform.rotation = r
     }
 
+    // Marks the world transformation of this node as outdated. The children are not traversed:
+    // they notice the change through the `world_generation` of this node.
     fn invalidate(&mut self) {
-        self.up_to_date = false;
-
-        for c in self.children.iter_mut() {
-            let mut dm = c.data_mut();
+        self.local_dirty.set(true);
+    }
+
+    // The local transformation, with the translation derived from the double-precision one and
+    // the current world origin if it has been set.
+    fn effective_local_transform(&self) -> Isometry3<f32> {
+        match self.translation_f64 {
+            Some(t) => Isometry3::from_parts(
+                Translation3::from(world_origin::to_render_vector(&t)),
+                self.local_transform.rotation,
+            ),
+            None => self.local_transform,
+        }
+    }
 
-            if dm.up_to_date {
-                dm.invalidate()
-            }
+    // Replaces the double-precision translation by its single-precision equivalent, before a
+    // modification of the translation that cannot be tracked with double precision.
+    fn discard_translation_f64(&mut self) {
+        if self.translation_f64.is_some() {
+            self.local_transform = self.effective_local_transform();
+            self.translation_f64 = None;
         }
     }
 
-    // FIXME: make this public?
-    fn update(&mut self) {
-        // NOTE: makin this test
-        if !self.up_to_date {
-            match self.parent {
-                Some(ref mut p) => unsafe {
-                    let mut dp = (**p).borrow_mut();
+    // Whether the world origin moved since the world transformation of this node, which has a
+    // double-precision translation, was computed.
+    fn origin_moved(&self) -> bool {
+        self.translation_f64.is_some() && self.origin_generation.get() != world_origin::generation()
+    }
+
+    pub(crate) fn children(&self) -> &[SceneNode] {
+        &self.children
+    }
+
+    pub(crate) fn explosion_mut(&mut self) -> &mut Option<Explosion> {
+        &mut self.explosion
+    }
 
+    pub(crate) fn trail(&self) -> Option<&Trail> {
+        self.trail.as_ref()
+    }
+
+    pub(crate) fn trail_mut(&mut self) -> Option<&mut Trail> {
+        self.trail.as_mut()
+    }
+
+    pub(crate) fn interpolation(&self) -> Option<Interpolation> {
+        self.interpolation
+    }
+
+    pub(crate) fn has_behaviors(&self) -> bool {
+        !self.behaviors.is_empty()
+    }
+
+    // Takes the behaviors out of this node, to execute them.
+    pub(crate) fn take_behaviors(&mut self) -> Vec<Behavior> {
+        self.behaviors_cleared = false;
+        std::mem::take(&mut self.behaviors)
+    }
+
+    // Puts back the executed behaviors before the ones added meanwhile, unless they have been
+    // removed.
+    pub(crate) fn restore_behaviors(&mut self, behaviors: Vec<Behavior>) {
+        if !self.behaviors_cleared {
+            let added = std::mem::replace(&mut self.behaviors, behaviors);
+            self.behaviors.extend(added);
+        }
+    }
+
+    // Brings the world transformation of this node and of its parents up to date.
+    fn update(&self) {
+        match self.parent.and_then(|p| NodeArena::get().get_slot(p)) {
+            Some(slot) => {
+                // The parent cannot be borrowed while it is being traversed, e.g., during
+                // rendering. Its children have then already been refreshed.
+                if let Ok(Some(dp)) = slot.data.try_borrow().as_deref() {
                     dp.update();
-                    self.world_transform = self.local_transform * dp.world_transform;
-                    self.world_scale = self.local_scale.component_mul(&dp.local_scale);
-                    self.up_to_date = true;
-                    return;
-                },
-                None => {}
+                    self.refresh_world_transform(
+                        &dp.world_transform.get(),
+                        &dp.world_scale.get(),
+                        dp.world_generation.get(),
+                    );
+                }
             }
+            None => self.refresh_world_transform(&na::one(), &Vector3::from_element(1.0), 0),
+        }
+    }
 
-            // no parent
-            self.world_transform = self.local_transform;
-            self.world_scale = self.local_scale;
-            self.up_to_date = true;
+    // Recomputes the world transformation of this node if either its local transformation or
+    // the world transformation of its parent changed.
+    fn refresh_world_transform(
+        &self,
+        parent_transform: &Isometry3<f32>,
+        parent_scale: &Vector3<f32>,
+        parent_generation: u64,
+    ) {
+        if self.local_dirty.get()
+            || self.parent_generation.get() != parent_generation
+            || self.origin_moved()
+        {
+            self.set_world_transform(
+                &(parent_transform * self.effective_local_transform()),
+                &parent_scale.component_mul(&self.local_scale),
+                parent_generation,
+            );
         }
     }
+
+    fn set_world_transform(
+        &self,
+        world_transform: &Isometry3<f32>,
+        world_scale: &Vector3<f32>,
+        parent_generation: u64,
+    ) {
+        self.world_transform.set(*world_transform);
+        self.world_scale.set(*world_scale);
+        self.parent_generation.set(parent_generation);
+        self.world_generation
+            .set(self.world_generation.get().wrapping_add(1));
+        self.local_dirty.set(false);
+        self.origin_generation.set(world_origin::generation());
+    }
 }
 
 impl SceneNode {
@@ -592,20 +1468,58 @@ impl SceneNode {
         let data = SceneNodeData {
             local_scale,
             local_transform,
-            world_transform: local_transform,
-            world_scale: local_scale,
+            translation_f64: None,
+            origin_generation: Cell::new(0),
+            world_transform: Cell::new(local_transform),
+            world_scale: Cell::new(local_scale),
+            local_dirty: Cell::new(true),
+            world_generation: Cell::new(0),
+            parent_generation: Cell::new(0),
             visible: true,
-            up_to_date: false,
+            pickable: None,
+            shadow_caster: None,
+            name: None,
             children: Vec::new(),
             object,
             parent: None,
+            explosion: None,
+            behaviors: Vec::new(),
+            behaviors_cleared: false,
+            trail: None,
+            interpolation: None,
+            import_options: None,
+            light: None,
         };
 
+        let (handle, slot) = NodeArena::get().alloc(data);
+
         SceneNode {
-            data: Rc::new(RefCell::new(data)),
+            handle,
+            slot,
+            _marker: PhantomData,
         }
     }
 
+    /// The handle of this node.
+    #[inline]
+    pub fn handle(&self) -> NodeHandle {
+        self.handle
+    }
+
+    /// Retrieves the scene node identified by `handle`.
+    ///
+    /// Returns `None` if the node has been destroyed, or if it belongs to another thread.
+    pub fn from_handle(handle: NodeHandle) -> Option<SceneNode> {
+        NodeArena::get().get_slot(handle).map(|slot| {
+            slot.retain();
+            SceneNode {
+                handle,
+                slot,
+                _marker: PhantomData,
+            }
+        })
+    }
+
     /// Creates a new empty, not rooted, node with identity transformations.
     pub fn new_empty() -> SceneNode {
         SceneNode::new(Vector3::from_element(1.0), na::one(), None)
@@ -615,17 +1529,18 @@ impl SceneNode {
     pub fn unlink(&mut self) {
         let self_self = self.clone();
         self.data_mut().remove_from_parent(&self_self);
-        self.data_mut().parent = None
+        self.data_mut().parent = None;
+        self.data_mut().invalidate()
     }
 
     /// The data of this scene node.
     pub fn data(&self) -> Ref<SceneNodeData> {
-        self.data.borrow()
+        Ref::map(self.slot.data.borrow(), |d| d.as_ref().unwrap())
     }
 
     /// The data of this scene node.
     pub fn data_mut(&mut self) -> RefMut<SceneNodeData> {
-        self.data.borrow_mut()
+        RefMut::map(self.slot.data.borrow_mut(), |d| d.as_mut().unwrap())
     }
 
     /*
@@ -653,7 +1568,7 @@ impl SceneNode {
         );
 
         let mut node = node;
-        node.data_mut().set_parent(&*self.data);
+        node.data_mut().set_parent(self.handle);
         self.data_mut().children.push(node)
     }
 
@@ -694,6 +1609,57 @@ impl SceneNode {
         res.expect("Unable to load the default sphere geometry.")
     }
 
+    /// Adds an ellipsoid showing a 3D gaussian distribution as a children of this node.
+    ///
+    /// See `set_covariance_ellipsoid` for the meaning of the arguments.
+    pub fn add_covariance_ellipsoid(
+        &mut self,
+        mean: &Point3<f32>,
+        covariance: &Matrix3<f32>,
+        sigma: f32,
+    ) -> SceneNode {
+        let mut res = self.add_sphere(0.5);
+        res.set_covariance_ellipsoid(mean, covariance, sigma);
+
+        res
+    }
+
+    /// Sets the local transformation and scale of this node so that a sphere of diameter `1.0`
+    /// centered at its origin, like the `sphere` geometry, becomes the ellipsoid of a 3D gaussian
+    /// distribution.
+    ///
+    /// The ellipsoid is centered at `mean`, its axes are the eigenvectors of the `covariance`
+    /// matrix, and its radii are `sigma` times the standard deviations along them: with `sigma`
+    /// set to `1.0`, `2.0`, or `3.0`, it bounds the 1σ, 2σ, or 3σ confidence region. Only the
+    /// symmetric part of the covariance is considered, and its negative eigenvalues are treated
+    /// as zero. This is meant to be called at each update of an estimate, on a node created by
+    /// `add_covariance_ellipsoid`.
+    pub fn set_covariance_ellipsoid(
+        &mut self,
+        mean: &Point3<f32>,
+        covariance: &Matrix3<f32>,
+        sigma: f32,
+    ) {
+        let eigen = ((covariance + covariance.transpose()) * 0.5).symmetric_eigen();
+        let mut axes = eigen.eigenvectors;
+
+        // The axes must form a rotation, not a reflection.
+        if axes.determinant() < 0.0 {
+            axes.set_column(2, &-axes.column(2));
+        }
+
+        let rotation =
+            UnitQuaternion::from_rotation_matrix(&Rotation3::from_matrix_unchecked(axes));
+        let radii = eigen.eigenvalues.map(|l| l.max(0.0).sqrt() * sigma);
+
+        let mut data = self.data_mut();
+        data.set_local_transformation(Isometry3::from_parts(
+            Translation3::from(mean.coords),
+            rotation,
+        ));
+        data.set_local_scale(radii.x * 2.0, radii.y * 2.0, radii.z * 2.0);
+    }
+
     /// Adds a cone to the scene. The cone is initially centered at (0, 0, 0) and points toward the
     /// positive `y` axis.
     ///
@@ -753,6 +1719,28 @@ impl SceneNode {
         node
     }
 
+    /// Adds a cloth as a children of this node, simulated once per frame. See `ClothNode`.
+    ///
+    /// # Arguments
+    /// * `w` - the cloth width.
+    /// * `h` - the cloth height.
+    /// * `wsubdivs` - number of horizontal subdivisions. Must not be `0`.
+    /// * `hsubdivs` - number of vertical subdivisions. Must not be `0`.
+    pub fn add_cloth(&mut self, w: f32, h: f32, wsubdivs: usize, hsubdivs: usize) -> ClothNode {
+        ClothNode::new(self, w, h, wsubdivs, hsubdivs)
+    }
+
+    /// Adds a rope as a children of this node, simulated once per frame. See `RopeNode`.
+    ///
+    /// # Arguments
+    /// * `length` - the rope length.
+    /// * `radius` - the radius of the tube rendering the rope.
+    /// * `segments` - the number of segments between the particles of the rope. Must not be
+    ///   `0`.
+    pub fn add_rope(&mut self, length: f32, radius: f32, segments: usize) -> RopeNode {
+        RopeNode::new(self, length, radius, segments)
+    }
+
     /// Adds a double-sided quad with the specified vertices.
     pub fn add_quad_with_vertices(
         &mut self,
@@ -772,22 +1760,30 @@ impl SceneNode {
     pub fn add_geom_with_name(
         &mut self,
         geometry_name: &str,
-        scale: Vector3<f32>,
+        scale: impl Into<Vector3<f32>>,
     ) -> Option<SceneNode> {
         MeshManager::get_global_manager(|mm| mm.get(geometry_name)).map(|g| self.add_mesh(g, scale))
     }
 
     /// Creates and adds a new object to this node children using a mesh.
-    pub fn add_mesh(&mut self, mesh: Rc<RefCell<Mesh>>, scale: Vector3<f32>) -> SceneNode {
+    pub fn add_mesh(
+        &mut self,
+        mesh: Rc<RefCell<Mesh>>,
+        scale: impl Into<Vector3<f32>>,
+    ) -> SceneNode {
         let tex = TextureManager::get_global_manager(|tm| tm.get_default());
         let mat = MaterialManager::get_global_manager(|mm| mm.get_default());
         let object = Object::new(mesh, 1.0, 1.0, 1.0, tex, mat);
 
-        self.add_object(scale, na::one(), object)
+        self.add_object(scale.into(), na::one(), object)
     }
 
     /// Creates and adds a new object using a mesh descriptor.
-    pub fn add_trimesh(&mut self, descr: TriMesh<f32>, scale: Vector3<f32>) -> SceneNode {
+    pub fn add_trimesh(
+        &mut self,
+        descr: TriMesh<f32>,
+        scale: impl Into<Vector3<f32>>,
+    ) -> SceneNode {
         self.add_mesh(
             Rc::new(RefCell::new(Mesh::from_trimesh(descr, false))),
             scale,
@@ -798,65 +1794,232 @@ impl SceneNode {
     ///
     /// This will create a new node serving as a root of the scene described by the obj file. This
     /// newly created node is added to this node's children.
-    pub fn add_obj(&mut self, path: &Path, mtl_dir: &Path, scale: Vector3<f32>) -> SceneNode {
+    ///
+    /// Returns an error, without adding any node, if the obj file or one of its textures cannot be
+    /// read or is malformed.
+    pub fn add_obj(
+        &mut self,
+        path: &Path,
+        mtl_dir: &Path,
+        scale: impl Into<Vector3<f32>>,
+    ) -> Result<SceneNode, Error> {
+        self.add_obj_with_options(path, mtl_dir, scale, &MeshLoadOptions::new())
+    }
+
+    /// Creates and adds multiple nodes created from an obj file, the meshes being stored on the
+    /// GPU as specified by `options`.
+    ///
+    /// See `add_obj` for details.
+    pub fn add_obj_with_options(
+        &mut self,
+        path: &Path,
+        mtl_dir: &Path,
+        scale: impl Into<Vector3<f32>>,
+        options: &MeshLoadOptions,
+    ) -> Result<SceneNode, Error> {
+        self.add_obj_with_import_options(path, mtl_dir, scale, options, &ImportOptions::new())
+    }
+
+    /// Creates and adds multiple nodes created from an obj file, its coordinates being converted
+    /// to the coordinate system of kiss3d as specified by `import_options`.
+    ///
+    /// The import options are stored on the returned node. See `add_obj` for details.
+    pub fn add_obj_with_import_options(
+        &mut self,
+        path: &Path,
+        mtl_dir: &Path,
+        scale: impl Into<Vector3<f32>>,
+        options: &MeshLoadOptions,
+        import_options: &ImportOptions,
+    ) -> Result<SceneNode, Error> {
+        self.add_obj_with_progress(path, mtl_dir, scale, options, import_options, &mut |_| {})
+    }
+
+    /// Creates and adds multiple nodes created from an obj file like
+    /// `add_obj_with_import_options`, reporting the bytes read, the meshes built, and the
+    /// textures decoded to `progress`.
+    pub fn add_obj_with_progress(
+        &mut self,
+        path: &Path,
+        mtl_dir: &Path,
+        scale: impl Into<Vector3<f32>>,
+        options: &MeshLoadOptions,
+        import_options: &ImportOptions,
+        progress: &mut dyn FnMut(LoadProgress),
+    ) -> Result<SceneNode, Error> {
+        let objs = MeshManager::load_obj_with_progress(
+            path,
+            mtl_dir,
+            &path.to_string_lossy(),
+            options,
+            import_options,
+            progress,
+        )
+        .map_err(|e| Error::Io(path.to_path_buf(), e))?;
+
+        let num_textures = objs
+            .iter()
+            .filter_map(|(_, _, mtl)| mtl.as_ref())
+            .map(|mtl| {
+                mtl.diffuse_texture
+                    .iter()
+                    .chain(mtl.ambiant_texture.iter())
+                    .count()
+            })
+            .sum();
+        let mut num_decoded = 0;
+
+        self.add_obj_meshes(objs, scale.into(), import_options, &mut |t| {
+            let mut tpath = PathBuf::new();
+            tpath.push(mtl_dir);
+            tpath.push(t);
+
+            // The decoding is reported with the count of the whole obj file.
+            let texture = TextureManager::get_global_manager(|tm| {
+                tm.add_with_progress(&tpath, &tpath.to_string_lossy(), &mut |p| {
+                    if let LoadProgress::BytesRead { .. } = p {
+                        progress(p)
+                    }
+                })
+            })?;
+
+            num_decoded += 1;
+            progress(LoadProgress::TextureDecoded {
+                path: &tpath,
+                decoded: num_decoded,
+                total: num_textures,
+            });
+
+            Ok(texture)
+        })
+    }
+
+    /// Creates and adds multiple nodes created from the content of an obj file, without touching
+    /// the filesystem, e.g., for a file embedded in the binary with `include_bytes!`.
+    ///
+    /// `resolver` is called with the name of each material library and texture referenced by the
+    /// file, and returns its content, or `None` if it is not available. A missing material
+    /// library is ignored with a warning, like with `add_obj`, while a missing texture is an
+    /// error. The textures are named `name` followed by `#` and their file name in the texture
+    /// manager.
+    ///
+    /// Returns an error, without adding any node, if the content or one of the textures is
+    /// malformed. See `add_obj` for details.
+    pub fn add_obj_from_memory(
+        &mut self,
+        data: &[u8],
+        name: &str,
+        scale: impl Into<Vector3<f32>>,
+        resolver: &mut dyn FnMut(&str) -> Option<Vec<u8>>,
+    ) -> Result<SceneNode, Error> {
+        let import_options = ImportOptions::new();
+        let objs = MeshManager::load_obj_from_memory(
+            data,
+            name,
+            &MeshLoadOptions::new(),
+            &import_options,
+            resolver,
+        )
+        .map_err(|e| Error::Io(PathBuf::from(name), e))?;
+
+        self.add_obj_meshes(objs, scale.into(), &import_options, &mut |t| {
+            let tname = format!("{}#{}", name, t);
+            let bytes = resolver(t).ok_or_else(|| {
+                Error::Io(
+                    PathBuf::from(&tname),
+                    std::io::Error::from(std::io::ErrorKind::NotFound),
+                )
+            })?;
+
+            TextureManager::get_global_manager(|tm| tm.add_from_memory(&bytes, &tname))
+        })
+    }
+
+    // Adds the meshes of an obj file, their textures being loaded by `load_texture` given their
+    // file names.
+    fn add_obj_meshes(
+        &mut self,
+        objs: Vec<(String, Rc<RefCell<Mesh>>, Option<MtlMaterial>)>,
+        scale: Vector3<f32>,
+        import_options: &ImportOptions,
+        load_texture: &mut dyn FnMut(&str) -> Result<Rc<Texture>, Error>,
+    ) -> Result<SceneNode, Error> {
         let tex = TextureManager::get_global_manager(|tm| tm.get_default());
         let mat = MaterialManager::get_global_manager(|mm| mm.get_default());
+        let mut objects = Vec::new();
 
-        // FIXME: is there some error-handling stuff to do here instead of the `let _`.
-        let result = MeshManager::load_obj(path, mtl_dir, path.to_str().unwrap()).map(|objs| {
-            let mut root;
-
-            let self_root = objs.len() == 1;
-            let child_scale;
+        // The textures are loaded before any node is added, so that no node is left behind if
+        // one of them fails to load.
+        for (_, mesh, mtl) in objs.into_iter() {
+            let mut object = Object::new(mesh, 1.0, 1.0, 1.0, tex.clone(), mat.clone());
 
-            if self_root {
-                root = self.clone();
-                child_scale = scale;
-            } else {
-                root = SceneNode::new(scale, na::one(), None);
-                self.add_child(root.clone());
-                child_scale = Vector3::from_element(1.0);
-            }
+            match mtl {
+                None => {}
+                Some(mtl) => {
+                    object.set_color(mtl.diffuse);
 
-            for (_, mesh, mtl) in objs.into_iter() {
-                let mut object = Object::new(mesh, 1.0, 1.0, 1.0, tex.clone(), mat.clone());
-
-                match mtl {
-                    None => {}
-                    Some(mtl) => {
-                        object.set_color(mtl.diffuse.x, mtl.diffuse.y, mtl.diffuse.z);
-
-                        for t in mtl.diffuse_texture.iter() {
-                            let mut tpath = PathBuf::new();
-                            tpath.push(mtl_dir);
-                            tpath.push(&t[..]);
-                            object.set_texture_from_file(&tpath, tpath.to_str().unwrap())
-                        }
-
-                        for t in mtl.ambiant_texture.iter() {
-                            let mut tpath = PathBuf::new();
-                            tpath.push(mtl_dir);
-                            tpath.push(&t[..]);
-                            object.set_texture_from_file(&tpath, tpath.to_str().unwrap())
-                        }
+                    for t in mtl.diffuse_texture.iter().chain(mtl.ambiant_texture.iter()) {
+                        object.set_texture(load_texture(&t[..])?)
                     }
                 }
-
-                let _ = root.add_object(child_scale, na::one(), object);
             }
 
-            if self_root {
-                root.data()
-                    .children
-                    .last()
-                    .expect("There was nothing on this obj file.")
-                    .clone()
-            } else {
-                root
-            }
-        });
+            objects.push(object);
+        }
+
+        let mut root;
+        let self_root = objects.len() == 1;
+        let child_scale;
+
+        if self_root {
+            root = self.clone();
+            child_scale = scale;
+        } else {
+            root = SceneNode::new(scale, na::one(), None);
+            self.add_child(root.clone());
+            child_scale = Vector3::from_element(1.0);
+        }
+
+        let mut node = root.clone();
 
-        result.unwrap()
+        for object in objects {
+            node = root.add_object(child_scale, na::one(), object);
+        }
+
+        if !self_root {
+            node = root;
+        }
+
+        if !import_options.is_identity() {
+            node.data_mut().import_options = Some(*import_options);
+        }
+
+        Ok(node)
+    }
+
+    /// Creates and adds the nodes of the default scene of a glTF or GLB file.
+    ///
+    /// This will create a new node serving as a root of the scene, with the node hierarchy of the
+    /// file below it. This newly created node is added to this node's children.
+    ///
+    /// Returns an error, without adding any node, if the file, one of its buffers, or one of its
+    /// images cannot be read or is malformed. See `loader::gltf` for what is imported.
+    #[cfg(feature = "gltf")]
+    pub fn add_gltf(&mut self, path: &Path) -> Result<SceneNode, Error> {
+        self.add_gltf_with_import_options(path, &ImportOptions::new())
+    }
+
+    /// Creates and adds the nodes of the default scene of a glTF or GLB file, its coordinates
+    /// being converted to the coordinate system of kiss3d as specified by `import_options`.
+    ///
+    /// The import options are stored on the returned node. See `add_gltf` for details.
+    #[cfg(feature = "gltf")]
+    pub fn add_gltf_with_import_options(
+        &mut self,
+        path: &Path,
+        import_options: &ImportOptions,
+    ) -> Result<SceneNode, Error> {
+        crate::loader::gltf::add_to(self, path, import_options)
     }
 
     /// Applies a closure to each object contained by this node and its children.
@@ -879,6 +2042,149 @@ impl SceneNode {
         }
     }
 
+    /// Visits this node and its descendants in depth-first order, with their world
+    /// transformations.
+    ///
+    /// Every node is visited, including the invisible ones and the groups without object. The
+    /// closure may read the node with `SceneNode::data`, e.g., to get its object or its world
+    /// scale, but must not modify the scene graph.
+    pub fn visit(&self, f: &mut dyn FnMut(&SceneNode, &Isometry3<f32>)) {
+        let transform = self.data().world_transformation();
+
+        f(self, &transform);
+
+        for c in self.data().children.iter() {
+            c.visit(f)
+        }
+    }
+
+    /// The world-space axis-aligned bounding box of the objects of this node and its children.
+    ///
+    /// This transforms every vertex of the objects, so it should not be called at each frame for
+    /// large scene graphs. Returns `None` if there is no object with vertices.
+    pub fn world_aabb(&self) -> Option<AABB<f32>> {
+        let mut bounds: Option<(Point3<f32>, Point3<f32>)> = None;
+
+        self.apply_to_scene_nodes(&mut |node| {
+            let data = node.data();
+
+            if let Some(object) = data.object() {
+                let transform = data.world_transformation();
+                let scale = data.world_scale();
+                let mesh = object.mesh().borrow();
+                let coords = mesh.coords().read().unwrap();
+
+                for pt in coords.data().iter().flat_map(|pts| pts.iter()) {
+                    let pt = transform * Point3::from(pt.coords.component_mul(&scale));
+                    bounds = Some(match bounds {
+                        Some((mins, maxs)) => (mins.inf(&pt), maxs.sup(&pt)),
+                        None => (pt, pt),
+                    });
+                }
+            }
+        });
+
+        bounds.map(|(mins, maxs)| AABB::new(mins, maxs))
+    }
+
+    /// The world-space bounding sphere of the objects of this node and its children.
+    ///
+    /// This is the sphere enclosing `world_aabb`.
+    pub fn world_bounding_sphere(&self) -> Option<BoundingSphere<f32>> {
+        self.world_aabb()
+            .map(|aabb| BoundingSphere::new(aabb.center(), aabb.half_extents().norm()))
+    }
+
+    /// Computes the first intersection of a world-space ray with the pickable objects of the
+    /// scene graph rooted by this node.
+    ///
+    /// Returns the node containing the intersected object, and the intersection expressed in
+    /// world-space.
+    pub fn cast_ray(&self, ray: &Ray<f32>, max_toi: f32) -> Option<(SceneNode, MeshRayHit)> {
+        let mut best = None;
+        self.cast_ray_with_best(ray, max_toi, &mut best);
+        best
+    }
+
+    /// Computes the first pickable object of the scene graph rooted by this node hit by a
+    /// world-space ray.
+    ///
+    /// See `Window::pick` to pick the object under a point of the screen.
+    pub fn pick(&self, ray: &Ray<f32>) -> Option<PickHit> {
+        self.cast_ray(ray, f32::MAX).map(|(node, hit)| PickHit {
+            node,
+            point: ray.point_at(hit.toi),
+            face: hit.face,
+            toi: hit.toi,
+            normal: hit.normal,
+        })
+    }
+
+    /// Computes the point of the pickable objects of the scene graph rooted by this node closest
+    /// to a world-space point, if it is closer than `max_dist`.
+    ///
+    /// Returns the node containing the closest object, and the closest point expressed in
+    /// world-space.
+    pub fn closest_point(
+        &self,
+        point: &Point3<f32>,
+        max_dist: f32,
+    ) -> Option<(SceneNode, Point3<f32>)> {
+        let mut best = None;
+        self.closest_point_with_best(point, max_dist, &mut best);
+        best.map(|(node, pt, _)| (node, pt))
+    }
+
+    fn closest_point_with_best(
+        &self,
+        point: &Point3<f32>,
+        max_dist: f32,
+        best: &mut Option<(SceneNode, Point3<f32>, f32)>,
+    ) {
+        let data = self.data();
+
+        if !data.is_pickable() {
+            return;
+        }
+
+        if let Some(closest) = data.closest_point_on_object(point) {
+            let dist = na::distance(point, &closest);
+            let max_dist = best.as_ref().map(|b| b.2).unwrap_or(max_dist);
+
+            if dist < max_dist {
+                *best = Some((self.clone(), closest, dist));
+            }
+        }
+
+        for c in data.children.iter() {
+            c.closest_point_with_best(point, max_dist, best);
+        }
+    }
+
+    fn cast_ray_with_best(
+        &self,
+        ray: &Ray<f32>,
+        max_toi: f32,
+        best: &mut Option<(SceneNode, MeshRayHit)>,
+    ) {
+        let data = self.data();
+
+        if !data.is_pickable() {
+            return;
+        }
+
+        let max_toi = best.as_ref().map(|b| b.1.toi).unwrap_or(max_toi);
+
+        if let Some(hit) = data.cast_ray_on_object(ray, max_toi) {
+            *best = Some((self.clone(), hit));
+        }
+
+        for c in data.children.iter() {
+            let max_toi = best.as_ref().map(|b| b.1.toi).unwrap_or(max_toi);
+            c.cast_ray_with_best(ray, max_toi, best);
+        }
+    }
+
     //
     //
     // fwd
@@ -908,6 +2214,15 @@ impl SceneNode {
         self.data_mut().set_lines_width(width)
     }
 
+    /// Enables or disables occlusion culling for the objects contained by this node and its
+    /// children.
+    ///
+    /// See `Object::set_occlusion_culling` for details.
+    #[inline]
+    pub fn set_occlusion_culling(&mut self, enabled: bool) {
+        self.data_mut().set_occlusion_culling(enabled)
+    }
+
     /// Sets the color of the lines drawn for the objects contained by this node and its children.
     #[inline]
     pub fn set_lines_color(&mut self, color: Option<Point3<f32>>) {
@@ -942,6 +2257,41 @@ impl SceneNode {
         self.data_mut().modify_vertices(f)
     }
 
+    /// Overwrites the vertices of the objects contained by this node and its children directly
+    /// in their gpu buffers, e.g., to deform them every frame.
+    ///
+    /// This avoids copying the vertices from the meshes to the GPU, but the content of the slice
+    /// given to the closure is unspecified: it must write every vertex, e.g., from a copy of the
+    /// rest positions, as below. The meshes keep their previous vertices on RAM, so the bounding
+    /// boxes, picking, ray casts, and frustum culling ignore the written vertices, and the next
+    /// `modify_vertices` uploads the vertices on RAM again. Without support for buffer mapping,
+    /// e.g. with WebGL, the vertices on RAM are written and uploaded instead. The provided closure
+    /// is called once per object. Returns `false` if the vertices of an object could not be
+    /// written.
+    ///
+    /// ```no_run
+    /// # use kiss3d::window::Window;
+    /// let mut window = Window::new("Kiss3d: write_vertices_gpu");
+    /// let mut quad = window.add_quad(4.0, 4.0, 32, 32);
+    /// let mut rest = Vec::new();
+    /// quad.read_vertices(&mut |coords| rest = coords.to_vec());
+    /// let mut t = 0.0f32;
+    ///
+    /// while window.render() {
+    ///     t += 0.02;
+    ///     let _ = quad.write_vertices_gpu(&mut |coords| {
+    ///         for (c, r) in coords.iter_mut().zip(rest.iter()) {
+    ///             *c = *r;
+    ///             c.z = (r.x * 2.0 + t).sin() * 0.2;
+    ///         }
+    ///     });
+    /// }
+    /// ```
+    #[inline(always)]
+    pub fn write_vertices_gpu<F: FnMut(&mut [Point3<f32>])>(&mut self, f: &mut F) -> bool {
+        self.data_mut().write_vertices_gpu(f)
+    }
+
     /// Accesses the vertices of the objects contained by this node and its children.
     ///
     /// The provided closure is called once per object.
@@ -950,6 +2300,22 @@ impl SceneNode {
         self.data().read_vertices(f)
     }
 
+    /// Colors the vertices of the objects contained by this node and its children according to
+    /// one scalar value per vertex.
+    ///
+    /// Each object mesh must have exactly one value per vertex. If `range` is `None`, the minimum
+    /// and maximum of `values` are mapped to the two ends of the colormap. The objects are
+    /// switched to the `vertex_color` material.
+    #[inline]
+    pub fn set_scalar_field(
+        &mut self,
+        values: &[f32],
+        colormap: &Colormap,
+        range: Option<(f32, f32)>,
+    ) {
+        self.data_mut().set_scalar_field(values, colormap, range)
+    }
+
     /// Recomputes the normals of the meshes of the objects contained by this node and its
     /// children.
     #[inline]
@@ -1020,22 +2386,433 @@ impl SceneNode {
         self.data_mut().set_visible(visible)
     }
 
+    /// Whether this node can be hit by the ray casts and closest point queries of the scene
+    /// graph.
+    #[inline]
+    pub fn is_pickable(&self) -> bool {
+        self.data().is_pickable()
+    }
+
+    /// Sets whether this node and its children can be picked, independently of their
+    /// visibility.
+    ///
+    /// By default, a node is pickable if and only if it is visible.
+    #[inline]
+    pub fn set_pickable(&mut self, pickable: bool) {
+        self.data_mut().set_pickable(pickable)
+    }
+
+    /// Whether the object of this node casts shadows.
+    ///
+    /// Unless set with `set_shadow_caster`, this is whether this node and all its ancestors are
+    /// visible.
+    #[inline]
+    pub fn is_shadow_caster(&self) -> bool {
+        self.data().is_shadow_caster()
+    }
+
+    /// Sets whether the object of this node casts shadows, independently of its visibility.
+    ///
+    /// By default, a node casts shadows if and only if it and its ancestors are visible.
+    #[inline]
+    pub fn set_shadow_caster(&mut self, shadow_caster: bool) {
+        self.data_mut().set_shadow_caster(shadow_caster)
+    }
+
+    /// Bakes the lighting of the visible objects of this node and its children into lightmaps,
+    /// with the shadows of the shadow casters of the same graph.
+    ///
+    /// This is meant for static geometry: the lightmaps are computed on the CPU, and are not
+    /// updated when the objects move. The meshes without lightmap texture coordinates get some
+    /// with `Mesh::generate_lightmap_uvs`, which duplicates their vertices. Returns the number of
+    /// objects baked, the ones whose mesh is not available on RAM being skipped.
+    pub fn bake_lightmaps(&mut self, options: &LightmapOptions) -> usize {
+        lightmap::bake(self, options)
+    }
+
+    // The nodes of the graph rooted by this node with an object, and visible along with their
+    // ancestors.
+    pub(crate) fn visible_objects(&self) -> Vec<SceneNode> {
+        let mut nodes = Vec::new();
+        self.apply_to_visible_nodes(true, &mut |node| {
+            if node.data().object.is_some() {
+                nodes.push(node.clone())
+            }
+        });
+        nodes
+    }
+
+    fn apply_to_visible_nodes(&self, ancestors_visible: bool, f: &mut dyn FnMut(&SceneNode)) {
+        let visible = ancestors_visible && self.data().visible;
+
+        if visible {
+            f(self);
+
+            for c in self.data().children.iter() {
+                c.apply_to_visible_nodes(visible, f);
+            }
+        }
+    }
+
+    // The nodes of the graph rooted by this node whose object casts shadows.
+    pub(crate) fn shadow_casters(&self) -> Vec<SceneNode> {
+        let mut casters = Vec::new();
+        self.collect_shadow_casters(true, &mut casters);
+        casters
+    }
+
+    fn collect_shadow_casters(&self, ancestors_visible: bool, out: &mut Vec<SceneNode>) {
+        let data = self.data();
+        let visible = ancestors_visible && data.visible;
+
+        if data.object.is_some() && data.shadow_caster.unwrap_or(visible) {
+            out.push(self.clone());
+        }
+
+        for c in data.children.iter() {
+            c.collect_shadow_casters(visible, out);
+        }
+    }
+
+    /// Moves the children of this node away from each other, e.g., to show the parts of an
+    /// assembly.
+    ///
+    /// Each child is offset along the vector from the centroid of this node to its own centroid,
+    /// scaled by `factor`: a factor of `1.0` doubles the distance of every part to the centroid.
+    /// The directions are measured when the node is first exploded, and the original
+    /// translations of the children are restored when the factor is set back to `0.0`. The
+    /// children should not be moved by other means in-between.
+    ///
+    /// ```no_run
+    /// # use kiss3d::window::Window;
+    /// # use kiss3d::nalgebra::Translation3;
+    /// let mut window = Window::new("Kiss3d: explode");
+    /// let mut assembly = window.add_group();
+    /// let mut part = assembly.add_cube(1.0, 1.0, 1.0);
+    /// part.set_local_translation(Translation3::new(1.0, 0.0, 0.0));
+    /// let _ = assembly.add_sphere(0.5);
+    ///
+    /// assembly.explode_animated(1.5, 0.5);
+    ///
+    /// while window.render() {}
+    /// ```
+    pub fn explode(&mut self, factor: f32) {
+        explode::explode(self, factor, 0.0)
+    }
+
+    /// Same as `explode`, except that the children move progressively from their current
+    /// offsets during `duration` seconds, at each frame rendered by the window.
+    pub fn explode_animated(&mut self, factor: f32, duration: f32) {
+        explode::explode(self, factor, duration)
+    }
+
+    /// Attaches a behavior to this node, executed once per frame before rendering.
+    ///
+    /// The closure is given the node and the time elapsed since the previous frame, in seconds,
+    /// so that simple per-object animation logic can live with its node. The behaviors of a node
+    /// are executed in the order they were added, as long as the node exists, even if it is not
+    /// part of the scene of the window. A behavior should not capture its own node, which would
+    /// then never be destroyed.
+    ///
+    /// ```no_run
+    /// # use kiss3d::window::Window;
+    /// # use kiss3d::nalgebra::{UnitQuaternion, Vector3};
+    /// let mut window = Window::new("Kiss3d: behaviors");
+    /// let mut cube = window.add_cube(1.0, 1.0, 1.0);
+    ///
+    /// cube.add_behavior(|node, dt| {
+    ///     node.prepend_to_local_rotation(&UnitQuaternion::from_axis_angle(&Vector3::y_axis(), dt))
+    /// });
+    ///
+    /// while window.render() {}
+    /// ```
+    pub fn add_behavior(&mut self, behavior: impl FnMut(&mut SceneNode, f32) + 'static) {
+        self.data_mut().behaviors.push(Box::new(behavior));
+        behavior::register(self.handle);
+    }
+
+    /// Records the world positions of this node at each frame, and renders them as a ribbon.
+    ///
+    /// The trail keeps the last `max_points` positions of the node, is `width` wide, and faces
+    /// the camera. If `fade` is `true`, it is increasingly transparent toward its oldest
+    /// positions. The trail has the color of the object of this node, or is white, until it is
+    /// changed with `set_trail_color`. It is rendered as long as the node is visible. Enabling
+    /// the trail again clears it.
+    ///
+    /// ```no_run
+    /// # use kiss3d::window::Window;
+    /// # use kiss3d::nalgebra::{Translation3, UnitQuaternion, Vector3};
+    /// let mut window = Window::new("Kiss3d: trail");
+    /// let mut orbit = window.add_group();
+    /// let mut planet = orbit.add_sphere(0.1);
+    /// planet.set_local_translation(Translation3::new(1.0, 0.0, 0.0));
+    /// planet.enable_trail(200, 0.05, true);
+    ///
+    /// while window.render() {
+    ///     let rot = UnitQuaternion::from_axis_angle(&Vector3::y_axis(), 0.02);
+    ///     orbit.prepend_to_local_rotation(&rot);
+    /// }
+    /// ```
+    pub fn enable_trail(&mut self, max_points: usize, width: f32, fade: bool) {
+        trail::register(self.handle);
+
+        let mut data = self.data_mut();
+        let color = data
+            .object()
+            .map_or(Color::WHITE, |o| Color::from(o.data().color()));
+        data.trail = Some(Trail::new(max_points, width, fade, color));
+    }
+
+    /// Stops recording and rendering the trail of this node.
+    pub fn disable_trail(&mut self) {
+        self.data_mut().trail = None
+    }
+
+    /// Sets the color of the trail of this node, if it has one.
+    pub fn set_trail_color(&mut self, color: impl Into<Color>) {
+        if let Some(trail) = self.data_mut().trail.as_mut() {
+            trail.color = color.into();
+        }
+    }
+
+    /// Removes the positions recorded by the trail of this node, if it has one.
+    pub fn clear_trail(&mut self) {
+        if let Some(trail) = self.data_mut().trail.as_mut() {
+            trail.points.clear();
+        }
+    }
+
+    /// Sets the local transformations of this node at the last two steps of a simulation.
+    ///
+    /// Until `stop_interpolation` is called, the window sets the local transformation of this
+    /// node at each frame, between `previous` and `current` by the factor given to
+    /// `Window::set_interpolation_alpha`. See the `scene::interpolation` module for an example.
+    pub fn set_interpolated_transformations(
+        &mut self,
+        previous: Isometry3<f32>,
+        current: Isometry3<f32>,
+    ) {
+        interpolation::register(self.handle);
+        self.data_mut().interpolation = Some(Interpolation { previous, current });
+    }
+
+    /// Sets the local transformation of this node at a new simulation step, the current one
+    /// becoming the previous one.
+    ///
+    /// If this node is not interpolated yet, both are set to `current`.
+    pub fn push_interpolated_transformation(&mut self, current: Isometry3<f32>) {
+        let previous = self
+            .data()
+            .interpolation
+            .map_or(current, |interpolation| interpolation.current);
+        self.set_interpolated_transformations(previous, current)
+    }
+
+    /// Stops interpolating this node, and gives it its current transformation.
+    pub fn stop_interpolation(&mut self) {
+        let mut data = self.data_mut();
+
+        if let Some(interpolation) = data.interpolation.take() {
+            data.set_local_transformation(interpolation.current);
+        }
+    }
+
+    /// Whether the local transformation of this node is interpolated.
+    pub fn is_interpolated(&self) -> bool {
+        self.data().interpolation.is_some()
+    }
+
+    /// Removes all the behaviors of this node.
+    pub fn clear_behaviors(&mut self) {
+        let mut data = self.data_mut();
+        data.behaviors.clear();
+        data.behaviors_cleared = true;
+    }
+
+    /// The current explosion factor of this node, `0.0` if it is not exploded.
+    pub fn explosion_factor(&self) -> f32 {
+        self.data().explosion.as_ref().map_or(0.0, |e| e.factor())
+    }
+
+    /// Whether an explosion started by `explode_animated` is in progress.
+    pub fn is_explosion_animating(&self) -> bool {
+        self.data()
+            .explosion
+            .as_ref()
+            .is_some_and(|e| e.is_animating())
+    }
+
+    /// The name of this node, used to identify it in diagnostics.
+    #[inline]
+    pub fn name(&self) -> Option<String> {
+        self.data().name().map(|n| n.to_string())
+    }
+
+    /// The conversion applied to the coordinates of the asset this node was imported from, if
+    /// this node is the root of an imported asset.
+    #[inline]
+    pub fn import_options(&self) -> Option<ImportOptions> {
+        self.data().import_options().copied()
+    }
+
+    /// Sets the name of this node, used to identify it in diagnostics.
+    #[inline]
+    pub fn set_name(&mut self, name: &str) {
+        self.data_mut().set_name(name)
+    }
+
+    /// The light attached to this node, if any.
+    #[inline]
+    pub fn light(&self) -> Option<Light> {
+        self.data().light().cloned()
+    }
+
+    /// Attaches a light to this node, so that it follows the node when it moves.
+    ///
+    /// For example, the headlight of a car is attached with
+    /// `car.attach_light(Light::Absolute(Point3::new(0.0, 0.5, 2.0)))`. See
+    /// `SceneNodeData::attach_light` for details.
+    #[inline]
+    pub fn attach_light(&mut self, light: Light) {
+        self.data_mut().attach_light(light)
+    }
+
+    /// Detaches the light attached to this node, and returns it.
+    #[inline]
+    pub fn detach_light(&mut self) -> Option<Light> {
+        self.data_mut().detach_light()
+    }
+
     /// Sets the color of the objects contained by this node and its children.
     ///
     /// Colors components must be on the range `[0.0, 1.0]`.
     #[inline]
-    pub fn set_color(&mut self, r: f32, g: f32, b: f32) {
-        self.data_mut().set_color(r, g, b)
+    pub fn set_color(&mut self, color: impl Into<Color>) {
+        self.data_mut().set_color(color)
+    }
+
+    /// Sets the opacity of the objects contained by this node and its children, from `0.0`
+    /// (invisible) to `1.0` (opaque).
+    ///
+    /// Objects are blended with what is behind them by default, which can be changed with
+    /// `set_transparency_mode`, e.g., to fade objects in or out without sorting artifacts:
+    ///
+    /// ```no_run
+    /// # use kiss3d::window::Window;
+    /// # use kiss3d::scene::TransparencyMode;
+    /// let mut window = Window::new("Kiss3d: opacity");
+    /// let mut cube = window.add_cube(1.0, 1.0, 1.0);
+    /// cube.set_transparency_mode(TransparencyMode::Dither);
+    /// let mut opacity = 1.0;
+    ///
+    /// while window.render() {
+    ///     opacity = (opacity - 0.005f32).max(0.0);
+    ///     cube.set_opacity(opacity);
+    /// }
+    /// ```
+    #[inline]
+    pub fn set_opacity(&mut self, opacity: f32) {
+        self.data_mut().set_opacity(opacity)
+    }
+
+    /// Sets how the objects contained by this node and its children are rendered when their
+    /// opacity is smaller than `1.0`.
+    #[inline]
+    pub fn set_transparency_mode(&mut self, mode: TransparencyMode) {
+        self.data_mut().set_transparency_mode(mode)
+    }
+
+    /// Enables or disables the x-ray rendering of the objects contained by this node and its
+    /// children.
+    ///
+    /// X-rayed objects are drawn as transparent ghosts with highlighted silhouettes, on top of
+    /// the rest of the scene. This lets the parts hidden inside an enclosure remain visible when
+    /// the enclosure is x-rayed, or an occluded part be located when the part itself is x-rayed.
+    /// Only the default material supports x-ray rendering.
+    #[inline]
+    pub fn set_xray(&mut self, enabled: bool) {
+        self.data_mut().set_xray(enabled)
+    }
+
+    /// Enables or disables the flat shading of the objects contained by this node and its
+    /// children.
+    ///
+    /// Flat shaded objects are lit with the normals of their faces, computed from the
+    /// screen-space derivatives of their positions, instead of their vertex normals. This gives
+    /// a faceted look to smooth meshes without duplicating their vertices, and helps spotting
+    /// wrong vertex normals. Only the default material supports flat shading.
+    #[inline]
+    pub fn set_flat_shading(&mut self, enabled: bool) {
+        self.data_mut().set_flat_shading(enabled)
+    }
+
+    /// Sets the skeleton deforming the skinned meshes of the objects contained by this node and
+    /// its children, see `Mesh::set_skin`. Use `None` to draw the meshes as they are modeled.
+    ///
+    /// Only the default material deforms the meshes. The skeleton is usually posed by an
+    /// `AnimationPlayer`, see `add_animation_player`.
+    #[inline]
+    pub fn set_skeleton(&mut self, skeleton: Option<Rc<RefCell<Skeleton>>>) {
+        self.data_mut().set_skeleton(skeleton)
+    }
+
+    /// Deforms the skinned meshes of the objects contained by this node and its children with
+    /// the skeleton of `player`, and advances the player at each frame.
+    ///
+    /// Returns the player, shared with this node, to change its clip later on. It is advanced as
+    /// long as the node exists, like the behaviors of `add_behavior`.
+    pub fn add_animation_player(
+        &mut self,
+        player: AnimationPlayer,
+    ) -> Rc<RefCell<AnimationPlayer>> {
+        self.set_skeleton(Some(player.skeleton().clone()));
+
+        let player = Rc::new(RefCell::new(player));
+        let updated = player.clone();
+        self.add_behavior(move |_, dt| updated.borrow_mut().update(dt));
+
+        player
+    }
+
+    /// Sets whether the shadows are drawn on the objects contained by this node and its
+    /// children, when shadow mapping is enabled with `Window::set_shadow_mapping`.
+    ///
+    /// Only the default material draws shadows. Enabled by default.
+    #[inline]
+    pub fn set_receive_shadows(&mut self, enabled: bool) {
+        self.data_mut().set_receive_shadows(enabled)
+    }
+
+    /// Sets the debug rendering of the objects contained by this node and its children.
+    ///
+    /// This displays the normals, texture coordinates, or tangents of the meshes to debug them,
+    /// whatever their material is. Use `DebugVisualization::None` to render the objects with
+    /// their material again.
+    ///
+    /// ```no_run
+    /// # use kiss3d::window::Window;
+    /// use kiss3d::scene::DebugVisualization;
+    ///
+    /// let mut window = Window::new("Kiss3d: debug visualization");
+    /// let mut sphere = window.add_sphere(1.0);
+    /// sphere.set_debug_visualization(DebugVisualization::NormalGlyphs(0.1));
+    ///
+    /// while window.render() {}
+    /// ```
+    #[inline]
+    pub fn set_debug_visualization(&mut self, mode: DebugVisualization) {
+        self.data_mut().set_debug_visualization(mode)
     }
 
     /// Sets the texture of the objects contained by this node and its children.
     ///
-    /// The texture is loaded from a file and registered by the global `TextureManager`.
+    /// The texture is loaded from a file and registered by the global `TextureManager`. Returns
+    /// an error if the file cannot be read or decoded.
     ///
     /// # Arguments
     ///   * `path` - relative path of the texture on the disk
     #[inline]
-    pub fn set_texture_from_file(&mut self, path: &Path, name: &str) {
+    pub fn set_texture_from_file(&mut self, path: &Path, name: &str) -> Result<(), Error> {
         self.data_mut().set_texture_from_file(path, name)
     }
 
@@ -1072,8 +2849,29 @@ impl SceneNode {
     /// Move and orient the object such that it is placed at the point `eye` and have its `x` axis
     /// oriented toward `at`.
     #[inline]
-    pub fn reorient(&mut self, eye: &Point3<f32>, at: &Point3<f32>, up: &Vector3<f32>) {
-        self.data_mut().reorient(eye, at, up)
+    pub fn reorient(
+        &mut self,
+        eye: &(impl Into<Point3<f32>> + Copy),
+        at: &(impl Into<Point3<f32>> + Copy),
+        up: &(impl Into<Vector3<f32>> + Copy),
+    ) {
+        self.data_mut()
+            .reorient(&(*eye).into(), &(*at).into(), &(*up).into())
+    }
+
+    /// This node world transformation.
+    ///
+    /// Only the world transformations of the parents that changed since the last computation
+    /// are updated.
+    #[inline]
+    pub fn world_transformation(&self) -> Isometry3<f32> {
+        self.data().world_transformation()
+    }
+
+    /// This node world scaling factors.
+    #[inline]
+    pub fn world_scale(&self) -> Vector3<f32> {
+        self.data().world_scale()
     }
 
     /// Appends a transformation to this node local transformation.
@@ -1096,43 +2894,94 @@ impl SceneNode {
 
     /// Appends a translation to this node local transformation.
     #[inline]
-    pub fn append_translation(&mut self, t: &Translation3<f32>) {
-        self.data_mut().append_translation(t)
+    pub fn append_translation(&mut self, t: &impl IntoTranslation3) {
+        self.data_mut().append_translation(&t.into_translation3())
     }
 
     /// Prepends a translation to this node local transformation.
     #[inline]
-    pub fn prepend_to_local_translation(&mut self, t: &Translation3<f32>) {
-        self.data_mut().prepend_to_local_translation(t)
+    pub fn prepend_to_local_translation(&mut self, t: &impl IntoTranslation3) {
+        self.data_mut()
+            .prepend_to_local_translation(&t.into_translation3())
     }
 
     /// Sets the local translation of this node.
     #[inline]
-    pub fn set_local_translation(&mut self, t: Translation3<f32>) {
-        self.data_mut().set_local_translation(t)
+    pub fn set_local_translation(&mut self, t: impl IntoTranslation3) {
+        self.data_mut().set_local_translation(t.into_translation3())
+    }
+
+    /// Sets the local translation of this node with double precision, in world coordinates.
+    ///
+    /// See `SceneNodeData::set_local_translation_f64` for details.
+    #[inline]
+    pub fn set_local_translation_f64(&mut self, t: Vector3<f64>) {
+        self.data_mut().set_local_translation_f64(t)
+    }
+
+    /// The double-precision translation of this node, if it has been set.
+    #[inline]
+    pub fn local_translation_f64(&self) -> Option<Vector3<f64>> {
+        self.data().local_translation_f64()
     }
 
     /// Appends a rotation to this node local transformation.
     #[inline]
-    pub fn append_rotation(&mut self, r: &UnitQuaternion<f32>) {
-        self.data_mut().append_rotation(r)
+    pub fn append_rotation(&mut self, r: &impl IntoUnitQuaternion) {
+        self.data_mut().append_rotation(&r.into_unit_quaternion())
     }
 
     /// Appends a rotation to this node local transformation.
     #[inline]
-    pub fn append_rotation_wrt_center(&mut self, r: &UnitQuaternion<f32>) {
-        (*self.data_mut()).append_rotation_wrt_center(r)
+    pub fn append_rotation_wrt_center(&mut self, r: &impl IntoUnitQuaternion) {
+        (*self.data_mut()).append_rotation_wrt_center(&r.into_unit_quaternion())
     }
 
     /// Prepends a rotation to this node local transformation.
     #[inline]
-    pub fn prepend_to_local_rotation(&mut self, r: &UnitQuaternion<f32>) {
-        self.data_mut().prepend_to_local_rotation(r)
+    pub fn prepend_to_local_rotation(&mut self, r: &impl IntoUnitQuaternion) {
+        self.data_mut()
+            .prepend_to_local_rotation(&r.into_unit_quaternion())
     }
 
     /// Sets the local rotation of this node.
     #[inline]
-    pub fn set_local_rotation(&mut self, r: UnitQuaternion<f32>) {
-        self.data_mut().set_local_rotation(r)
+    pub fn set_local_rotation(&mut self, r: impl IntoUnitQuaternion) {
+        self.data_mut().set_local_rotation(r.into_unit_quaternion())
+    }
+}
+
+// Whether an object has been found occluded, reading the result of its last occlusion query
+// during the first pass.
+fn is_occluded(object: &Object, pass: usize) -> bool {
+    match object.occlusion_state() {
+        Some(state) => {
+            let mut state = state.borrow_mut();
+
+            if pass == 0 {
+                state.poll();
+            }
+
+            state.is_occluded()
+        }
+        None => false,
+    }
+}
+
+impl Clone for SceneNode {
+    fn clone(&self) -> SceneNode {
+        self.slot.retain();
+
+        SceneNode {
+            handle: self.handle,
+            slot: self.slot,
+            _marker: PhantomData,
+        }
+    }
+}
+
+impl Drop for SceneNode {
+    fn drop(&mut self) {
+        NodeArena::get().release(self.handle, self.slot)
     }
 }
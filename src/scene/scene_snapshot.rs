@@ -0,0 +1,467 @@
+use na::{Isometry3, Point3, Vector3};
+use std::collections::{HashMap, HashSet};
+use std::sync::{Arc, Mutex};
+
+use crate::resource::TextureManager;
+use crate::scene::SceneNode;
+
+/// The state of an object of a `SceneSnapshot`.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct NodeSnapshot {
+    /// The identifier of the object, unique among the objects of a snapshot.
+    pub id: u64,
+    /// The name of the object geometry, as registered in the global `MeshManager`.
+    pub geometry: String,
+    /// The name of the object texture, as registered in the global `TextureManager`. The
+    /// default texture is used if `None`.
+    pub texture: Option<String>,
+    /// The transformation of the object, relative to the root of the `SnapshotScene`.
+    pub transform: Isometry3<f32>,
+    /// The scaling factors of the object.
+    pub scale: Vector3<f32>,
+    /// The color of the object.
+    pub color: Point3<f32>,
+    /// Whether the object is rendered.
+    pub visible: bool,
+}
+
+impl NodeSnapshot {
+    /// A visible, white, untextured object with the given geometry at the origin.
+    pub fn new(id: u64, geometry: &str) -> NodeSnapshot {
+        NodeSnapshot {
+            id,
+            geometry: geometry.to_string(),
+            texture: None,
+            transform: Isometry3::identity(),
+            scale: Vector3::from_element(1.0),
+            color: Point3::new(1.0, 1.0, 1.0),
+            visible: true,
+        }
+    }
+}
+
+/// A self-contained description of the objects of a scene, at a given time.
+///
+/// Unlike scene nodes, snapshots do not reference any GPU resource, so they can be built on
+/// another thread than the one rendering the scene, and sent to it through a
+/// `snapshot_channel`. A `SnapshotScene` then mirrors the latest snapshot into the scene graph.
+///
+/// Snapshots can also be sent to other processes, either entirely or as the `SceneDiff` between
+/// two successive snapshots, computed by `diff`.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct SceneSnapshot {
+    /// The objects of the scene.
+    pub nodes: Vec<NodeSnapshot>,
+}
+
+impl SceneSnapshot {
+    /// Creates an empty snapshot.
+    pub fn new() -> SceneSnapshot {
+        SceneSnapshot { nodes: Vec::new() }
+    }
+
+    /// Adds an object to this snapshot.
+    pub fn push(&mut self, node: NodeSnapshot) {
+        self.nodes.push(node)
+    }
+
+    /// Removes all the objects of this snapshot, keeping its allocated memory.
+    pub fn clear(&mut self) {
+        self.nodes.clear()
+    }
+
+    /// The changes turning this snapshot into `target`.
+    ///
+    /// Objects are matched by identifier, and only the properties that changed are recorded.
+    pub fn diff(&self, target: &SceneSnapshot) -> SceneDiff {
+        let old: HashMap<u64, &NodeSnapshot> = self.nodes.iter().map(|n| (n.id, n)).collect();
+        let mut diff = SceneDiff::new();
+
+        for node in &target.nodes {
+            match old.get(&node.id) {
+                Some(old_node) => {
+                    if let Some(update) = NodeUpdate::between(old_node, node) {
+                        diff.updated.push(update)
+                    }
+                }
+                None => diff.added.push(node.clone()),
+            }
+        }
+
+        let ids: HashSet<u64> = target.nodes.iter().map(|n| n.id).collect();
+        diff.removed = self
+            .nodes
+            .iter()
+            .map(|n| n.id)
+            .filter(|id| !ids.contains(id))
+            .collect();
+
+        diff
+    }
+
+    /// Applies the changes of `diff` to this snapshot.
+    ///
+    /// If `diff` was computed from a snapshot equal to `self`, the result is equal to the target
+    /// of the diff, up to the order of the objects: added objects are appended at the end.
+    pub fn apply_diff(&mut self, diff: &SceneDiff) {
+        if !diff.removed.is_empty() {
+            let removed: HashSet<u64> = diff.removed.iter().cloned().collect();
+            self.nodes.retain(|n| !removed.contains(&n.id));
+        }
+
+        if !diff.updated.is_empty() {
+            let updates: HashMap<u64, &NodeUpdate> =
+                diff.updated.iter().map(|u| (u.id, u)).collect();
+
+            for node in &mut self.nodes {
+                if let Some(update) = updates.get(&node.id) {
+                    update.apply(node)
+                }
+            }
+        }
+
+        for added in &diff.added {
+            match self.nodes.iter_mut().find(|n| n.id == added.id) {
+                Some(node) => node.clone_from(added),
+                None => self.nodes.push(added.clone()),
+            }
+        }
+    }
+}
+
+/// The properties of an object that changed between two snapshots.
+///
+/// Unchanged properties are `None`.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct NodeUpdate {
+    /// The identifier of the object.
+    pub id: u64,
+    /// The new geometry name.
+    pub geometry: Option<String>,
+    /// The new texture name, `Some(None)` meaning the default texture.
+    pub texture: Option<Option<String>>,
+    /// The new transformation.
+    pub transform: Option<Isometry3<f32>>,
+    /// The new scaling factors.
+    pub scale: Option<Vector3<f32>>,
+    /// The new color.
+    pub color: Option<Point3<f32>>,
+    /// The new visibility.
+    pub visible: Option<bool>,
+}
+
+impl NodeUpdate {
+    /// The changes from `old` to `new`, or `None` if both are equal.
+    pub fn between(old: &NodeSnapshot, new: &NodeSnapshot) -> Option<NodeUpdate> {
+        fn changed<T: PartialEq + Clone>(old: &T, new: &T) -> Option<T> {
+            if old != new {
+                Some(new.clone())
+            } else {
+                None
+            }
+        }
+
+        let update = NodeUpdate {
+            id: new.id,
+            geometry: changed(&old.geometry, &new.geometry),
+            texture: changed(&old.texture, &new.texture),
+            transform: changed(&old.transform, &new.transform),
+            scale: changed(&old.scale, &new.scale),
+            color: changed(&old.color, &new.color),
+            visible: changed(&old.visible, &new.visible),
+        };
+
+        if update.is_empty() {
+            None
+        } else {
+            Some(update)
+        }
+    }
+
+    /// Whether this update does not change anything.
+    pub fn is_empty(&self) -> bool {
+        self.geometry.is_none()
+            && self.texture.is_none()
+            && self.transform.is_none()
+            && self.scale.is_none()
+            && self.color.is_none()
+            && self.visible.is_none()
+    }
+
+    /// Sets the changed properties on `node`.
+    pub fn apply(&self, node: &mut NodeSnapshot) {
+        if let Some(ref geometry) = self.geometry {
+            node.geometry.clone_from(geometry);
+        }
+
+        if let Some(ref texture) = self.texture {
+            node.texture.clone_from(texture);
+        }
+
+        if let Some(transform) = self.transform {
+            node.transform = transform;
+        }
+
+        if let Some(scale) = self.scale {
+            node.scale = scale;
+        }
+
+        if let Some(color) = self.color {
+            node.color = color;
+        }
+
+        if let Some(visible) = self.visible {
+            node.visible = visible;
+        }
+    }
+}
+
+/// The changes between two scene snapshots, computed by `SceneSnapshot::diff`.
+///
+/// A diff is much smaller than a full snapshot when only a few objects move at each frame, so
+/// it is well suited to synchronize the scene of remote viewers: the process owning the scene
+/// computes the diff between its last two snapshots, serializes it with `serde`, and the viewers
+/// apply it to their own snapshot with `SceneSnapshot::apply_diff`, or directly to their scene
+/// graph with `SnapshotScene::apply_diff`.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct SceneDiff {
+    /// The objects that did not exist in the old snapshot.
+    pub added: Vec<NodeSnapshot>,
+    /// The objects whose properties changed.
+    pub updated: Vec<NodeUpdate>,
+    /// The identifiers of the objects that no longer exist in the new snapshot.
+    pub removed: Vec<u64>,
+}
+
+impl SceneDiff {
+    /// Creates an empty diff.
+    pub fn new() -> SceneDiff {
+        SceneDiff::default()
+    }
+
+    /// Whether this diff does not change anything.
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.updated.is_empty() && self.removed.is_empty()
+    }
+}
+
+#[derive(Default)]
+struct SnapshotSlots {
+    // The latest published snapshot, not yet taken by the reader.
+    latest: Option<SceneSnapshot>,
+    // Snapshots already consumed, kept to reuse their memory.
+    free: Vec<SceneSnapshot>,
+    frame: u64,
+}
+
+/// The writing end of a `snapshot_channel`, usually owned by a simulation thread.
+#[derive(Clone)]
+pub struct SnapshotWriter {
+    slots: Arc<Mutex<SnapshotSlots>>,
+}
+
+/// The reading end of a `snapshot_channel`, owned by the thread rendering the scene.
+#[derive(Clone)]
+pub struct SnapshotReader {
+    slots: Arc<Mutex<SnapshotSlots>>,
+}
+
+/// Creates a channel carrying scene snapshots from a thread updating the scene to the thread
+/// rendering it.
+///
+/// The channel is double-buffered: the writer never waits for the renderer, and the renderer
+/// only sees the latest published snapshot, older ones being dropped. Consumed snapshots are
+/// recycled by `SnapshotWriter::begin_frame` to avoid allocations.
+pub fn snapshot_channel() -> (SnapshotWriter, SnapshotReader) {
+    let slots = Arc::new(Mutex::new(SnapshotSlots::default()));
+
+    (
+        SnapshotWriter {
+            slots: slots.clone(),
+        },
+        SnapshotReader { slots },
+    )
+}
+
+impl SnapshotWriter {
+    /// An empty snapshot to fill and publish, possibly reusing the memory of a consumed one.
+    pub fn begin_frame(&self) -> SceneSnapshot {
+        let mut snapshot = self.slots.lock().unwrap().free.pop().unwrap_or_default();
+        snapshot.clear();
+        snapshot
+    }
+
+    /// Makes `snapshot` the latest snapshot of the scene, replacing the previous one if it has
+    /// not been read yet.
+    pub fn publish(&self, snapshot: SceneSnapshot) {
+        let mut slots = self.slots.lock().unwrap();
+        slots.frame += 1;
+
+        if let Some(old) = slots.latest.replace(snapshot) {
+            slots.free.push(old);
+        }
+    }
+}
+
+impl SnapshotReader {
+    /// Takes the latest published snapshot, if a new one has been published since the last
+    /// call.
+    ///
+    /// Give it back with `recycle` once it is no longer needed.
+    pub fn take_latest(&self) -> Option<SceneSnapshot> {
+        self.slots.lock().unwrap().latest.take()
+    }
+
+    /// Gives back a snapshot so that its memory can be reused by the writer.
+    pub fn recycle(&self, snapshot: SceneSnapshot) {
+        self.slots.lock().unwrap().free.push(snapshot)
+    }
+
+    /// The number of snapshots published so far.
+    pub fn published_frames(&self) -> u64 {
+        self.slots.lock().unwrap().frame
+    }
+}
+
+struct SyncedNode {
+    // The last state applied to the node.
+    state: NodeSnapshot,
+    node: SceneNode,
+}
+
+/// Mirrors scene snapshots into the scene graph.
+///
+/// Each object of a snapshot is mapped to a child of the root node given at construction,
+/// created when its identifier first appears, updated while it remains, and removed when it
+/// disappears from the snapshots.
+///
+/// The scene can be updated either with full snapshots, or with the `SceneDiff`s between
+/// successive snapshots, e.g., received from another process owning the scene.
+pub struct SnapshotScene {
+    root: SceneNode,
+    nodes: HashMap<u64, SyncedNode>,
+}
+
+impl SnapshotScene {
+    /// Creates a mirror of the snapshots, as children of `root`.
+    pub fn new(root: SceneNode) -> SnapshotScene {
+        SnapshotScene {
+            root,
+            nodes: HashMap::new(),
+        }
+    }
+
+    /// The node mirroring the object `id` of the last applied snapshot.
+    pub fn node(&self, id: u64) -> Option<&SceneNode> {
+        self.nodes.get(&id).map(|n| &n.node)
+    }
+
+    /// Applies the latest snapshot of `reader`, if a new one has been published.
+    ///
+    /// Returns `true` if the scene has been updated. Call this once per frame, before rendering.
+    pub fn sync(&mut self, reader: &SnapshotReader) -> bool {
+        match reader.take_latest() {
+            Some(snapshot) => {
+                self.apply(&snapshot);
+                reader.recycle(snapshot);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Updates the scene graph so that it matches `snapshot`.
+    ///
+    /// Objects whose geometry is not registered in the global `MeshManager` are ignored, and
+    /// the textures not registered in the global `TextureManager` are not set.
+    pub fn apply(&mut self, snapshot: &SceneSnapshot) {
+        let mut alive = HashMap::with_capacity(snapshot.nodes.len());
+
+        for ns in &snapshot.nodes {
+            let previous = self.nodes.remove(&ns.id);
+
+            if let Some(synced) = sync_node(&mut self.root, ns, previous) {
+                let _ = alive.insert(ns.id, synced);
+            }
+        }
+
+        for (_, mut synced) in self.nodes.drain() {
+            synced.node.unlink();
+        }
+
+        self.nodes = alive;
+    }
+
+    /// Updates the scene graph with the changes of `diff`.
+    ///
+    /// The diff must have been computed from the last snapshot applied to this scene. Updates of
+    /// objects that are not in the scene are ignored.
+    pub fn apply_diff(&mut self, diff: &SceneDiff) {
+        for id in &diff.removed {
+            if let Some(mut synced) = self.nodes.remove(id) {
+                synced.node.unlink();
+            }
+        }
+
+        for update in &diff.updated {
+            if let Some(previous) = self.nodes.remove(&update.id) {
+                let mut state = previous.state.clone();
+                update.apply(&mut state);
+
+                if let Some(synced) = sync_node(&mut self.root, &state, Some(previous)) {
+                    let _ = self.nodes.insert(update.id, synced);
+                }
+            }
+        }
+
+        for ns in &diff.added {
+            let previous = self.nodes.remove(&ns.id);
+
+            if let Some(synced) = sync_node(&mut self.root, ns, previous) {
+                let _ = self.nodes.insert(ns.id, synced);
+            }
+        }
+    }
+}
+
+// Updates the node mirroring an object, given the node mirroring its previous state. The node is
+// recreated if the geometry changed. Returns `None` if the geometry is not registered.
+fn sync_node(
+    root: &mut SceneNode,
+    ns: &NodeSnapshot,
+    previous: Option<SyncedNode>,
+) -> Option<SyncedNode> {
+    let mut synced = match previous {
+        Some(synced) if synced.state.geometry == ns.geometry => synced,
+        previous => {
+            if let Some(mut previous) = previous {
+                previous.node.unlink();
+            }
+
+            let node = root.add_geom_with_name(&ns.geometry, ns.scale)?;
+            // A new node has the default texture.
+            let state = NodeSnapshot::new(ns.id, &ns.geometry);
+
+            SyncedNode { state, node }
+        }
+    };
+
+    let node = &mut synced.node;
+    node.set_local_transformation(ns.transform);
+    node.set_local_scale(ns.scale.x, ns.scale.y, ns.scale.z);
+    node.set_color(ns.color);
+    node.set_visible(ns.visible);
+
+    if synced.state.texture != ns.texture {
+        match ns.texture {
+            Some(ref name) => match TextureManager::get_global_manager(|tm| tm.get(name)) {
+                Some(texture) => node.set_texture(texture),
+                None => println!("Warning: unknown texture `{}` in scene snapshot.", name),
+            },
+            None => node.set_texture(TextureManager::get_global_manager(|tm| tm.get_default())),
+        }
+    }
+
+    synced.state.clone_from(ns);
+    Some(synced)
+}
@@ -0,0 +1,752 @@
+use na::{
+    Isometry3, Matrix3, Matrix4, Orthographic3, Perspective3, Point3, Point4, Translation3,
+    UnitQuaternion, Vector3, Vector4,
+};
+use ncollide3d::bounding_volume::{BoundingVolume, AABB};
+use std::cell::RefCell;
+use std::f32;
+use std::rc::Rc;
+
+use crate::camera::Camera;
+use crate::context::Context;
+use crate::resource::{Effect, FramebufferManager, RenderTarget, ShaderAttribute, ShaderUniform};
+use crate::scene::SceneNode;
+use crate::window::set_depth_convention;
+
+#[path = "../error.rs"]
+mod error;
+
+// The width and height of the shadow map, in texels.
+pub(crate) const SHADOW_MAP_SIZE: usize = 2048;
+
+/// The maximum number of cascades of the shadow map, see `Window::set_shadow_cascades`.
+pub const MAX_SHADOW_CASCADES: usize = 4;
+
+// The shadow map sampled by the default material.
+thread_local!(static ACTIVE: RefCell<Option<ActiveShadowMap>> = const { RefCell::new(None) });
+
+/// How the depth range covered by the cascades of the shadow map is split between them.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum CascadeSplit {
+    /// The cascades cover equal depth ranges.
+    Uniform,
+    /// Each cascade covers a depth range a constant factor longer than the previous one, which
+    /// keeps the size of the shadow texels on the screen constant.
+    Logarithmic,
+    /// The blend of the logarithmic splits, with the given weight in `[0, 1]`, and the uniform
+    /// ones, which keeps the first cascades from being too short.
+    Practical(f32),
+}
+
+impl CascadeSplit {
+    // The view-space distance where the fraction `t` of the cascades between `near` and `far`
+    // ends.
+    fn distance(&self, near: f32, far: f32, t: f32) -> f32 {
+        let uniform = near + (far - near) * t;
+        let logarithmic = near * (far / near).powf(t);
+
+        match *self {
+            CascadeSplit::Uniform => uniform,
+            CascadeSplit::Logarithmic => logarithmic,
+            CascadeSplit::Practical(weight) => {
+                let weight = weight.clamp(0.0, 1.0);
+                logarithmic * weight + uniform * (1.0 - weight)
+            }
+        }
+    }
+}
+
+/// The cascades of the shadow map, see `Window::set_shadow_cascades`.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct ShadowCascades {
+    /// The number of cascades, between 1 and `MAX_SHADOW_CASCADES`.
+    pub count: usize,
+    /// How the depth range covered by the cascades is split between them.
+    pub split: CascadeSplit,
+    /// The distance from the camera beyond which no shadow is drawn, if it is closer than the
+    /// far clipping plane.
+    pub max_distance: f32,
+}
+
+impl ShadowCascades {
+    /// `count` cascades with practical splits, drawing the shadows up to 100 units from the
+    /// camera.
+    pub fn new(count: usize) -> ShadowCascades {
+        assert!(
+            (1..=MAX_SHADOW_CASCADES).contains(&count),
+            "The number of shadow cascades must be between 1 and {}.",
+            MAX_SHADOW_CASCADES
+        );
+
+        ShadowCascades {
+            count,
+            split: CascadeSplit::Practical(0.75),
+            max_distance: 100.0,
+        }
+    }
+
+    /// Sets how the depth range covered by the cascades is split between them.
+    pub fn with_split(mut self, split: CascadeSplit) -> ShadowCascades {
+        self.split = split;
+        self
+    }
+
+    /// Sets the distance from the camera beyond which no shadow is drawn.
+    pub fn with_max_distance(mut self, max_distance: f32) -> ShadowCascades {
+        self.max_distance = max_distance;
+        self
+    }
+}
+
+/// How the shadows of the shadow map are filtered, see `Window::set_shadow_filter`.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum ShadowFilter {
+    /// Shadows with a narrow penumbra of constant width, from the 3x3 texels of the shadow map
+    /// around each point.
+    Pcf,
+    /// Contact-hardening shadows, aka. percentage-closer soft shadows, whose penumbra widens
+    /// with the distance between the shadow casters and the shadowed points, as cast by a light
+    /// with the given diameter.
+    ///
+    /// The penumbrae are limited to 32 texels of the shadow map.
+    Pcss {
+        /// The diameter of the light.
+        light_size: f32,
+    },
+}
+
+/// The screen-space shadows completing the shadow map where objects touch each other, see
+/// `Window::set_contact_shadows`.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct ContactShadows {
+    /// The length of the ray marched from each point toward the light, in world units.
+    pub length: f32,
+    /// The thickness given to the surfaces seen by the camera, in world units: a ray passing
+    /// farther behind a surface is not hidden by it.
+    pub thickness: f32,
+}
+
+impl ContactShadows {
+    /// Contact shadows marched over 0.2 units, behind surfaces 0.1 units thick.
+    pub fn new() -> ContactShadows {
+        ContactShadows {
+            length: 0.2,
+            thickness: 0.1,
+        }
+    }
+
+    /// Sets the length of the ray marched from each point toward the light.
+    pub fn with_length(mut self, length: f32) -> ContactShadows {
+        self.length = length;
+        self
+    }
+
+    /// Sets the thickness given to the surfaces seen by the camera.
+    pub fn with_thickness(mut self, thickness: f32) -> ContactShadows {
+        self.thickness = thickness;
+        self
+    }
+}
+
+impl Default for ContactShadows {
+    fn default() -> ContactShadows {
+        ContactShadows::new()
+    }
+}
+
+// The settings of the shadow map of a window.
+pub(crate) struct ShadowSettings {
+    pub cascades: Option<ShadowCascades>,
+    pub cascade_debug: bool,
+    pub filter: ShadowFilter,
+    pub contact_shadows: Option<ContactShadows>,
+}
+
+// The shadow map sampled by the default material.
+pub(crate) struct ActiveShadowMap {
+    pub target: Rc<RenderTarget>,
+    // The view-projection of the light or, with cascades, of each cascade. The cascades are
+    // rendered in the tiles of a 2x2 atlas, row by row from the bottom-left one.
+    pub view_projs: Vec<Matrix4<f32>>,
+    // The view-space distances where each cascade ends, empty without cascades.
+    pub splits: Vec<f32>,
+    // Whether the objects are tinted with the color of the cascade they are shadowed with.
+    pub debug: bool,
+    // The parameters of the contact-hardening shadows, if enabled: the near and far distances
+    // of the projection of the light, and the diameter of the light in texture coordinates at a
+    // unit distance or, with cascades, the width of the penumbra of each cascade per unit of
+    // depth between the casters and the shadowed points, in texture coordinates of its tile.
+    pub pcss: Option<Vector4<f32>>,
+    // The distances of the shadow casters to the camera for the contact shadows, if enabled.
+    pub contact: Option<ActiveContactShadows>,
+}
+
+// The distances of the shadow casters to the camera, sampled by the default material for the
+// contact shadows.
+pub(crate) struct ActiveContactShadows {
+    pub target: Rc<RenderTarget>,
+    // The projection of the camera the distances are seen with.
+    pub proj: Matrix4<f32>,
+    // The length of the rays, the thickness of the surfaces, and the far distance the distances
+    // are divided by.
+    pub params: Vector3<f32>,
+}
+
+impl ActiveShadowMap {
+    // Whether the shadow map is split into cascades.
+    pub fn has_cascades(&self) -> bool {
+        !self.splits.is_empty()
+    }
+}
+
+// The view of a cascade, an orthographic projection of the sphere of the given radius
+// centered at the origin of the view space.
+struct Cascade {
+    view: Isometry3<f32>,
+    view_proj: Matrix4<f32>,
+    radius: f32,
+    // The distance between the near and far planes of the projection.
+    depth: f32,
+}
+
+// Renders the depths of the shadow casters seen from the light, for the default material to
+// darken the fragments they hide from it.
+//
+// The light is a point light, so the depths are rendered with a perspective projection from the
+// light to the bounding sphere of the scene, unless the map is split into cascades. The depths
+// are packed into the channels of a color texture, since depth textures are not available
+// everywhere.
+//
+// With contact shadows, the distances of the casters to the camera are rendered as well, for the
+// default material to march the short rays between the fragments and the light through them.
+pub(crate) struct ShadowMap {
+    target: Rc<RenderTarget>,
+    program: DepthProgram,
+    // The target of the contact shadows and its size, created when they are first rendered.
+    contact_target: Option<(Rc<RenderTarget>, (u32, u32))>,
+    contact_program: Option<DepthProgram>,
+}
+
+// The program rendering the packed depths of the shadow casters.
+struct DepthProgram {
+    effect: Effect,
+    pos: ShaderAttribute<Point3<f32>>,
+    transform: ShaderUniform<Matrix4<f32>>,
+    scale: ShaderUniform<Matrix3<f32>>,
+    light_view_proj: ShaderUniform<Matrix4<f32>>,
+    // With `LINEAR_DEPTH`, the plane whose signed distance to each point is stored instead of
+    // its depth.
+    depth_plane: Option<ShaderUniform<Vector4<f32>>>,
+}
+
+// Creates a render target of the given size whose packed depths are not interpolated.
+fn new_depth_target(width: usize, height: usize) -> RenderTarget {
+    let ctxt = Context::get();
+    let target = FramebufferManager::new_render_target(width, height, false);
+
+    // Interpolating packed depths would mix their channels.
+    verify!(ctxt.bind_texture(Context::TEXTURE_2D, target.texture_id()));
+    verify!(ctxt.tex_parameteri(
+        Context::TEXTURE_2D,
+        Context::TEXTURE_MAG_FILTER,
+        Context::NEAREST as i32
+    ));
+    verify!(ctxt.tex_parameteri(
+        Context::TEXTURE_2D,
+        Context::TEXTURE_MIN_FILTER,
+        Context::NEAREST as i32
+    ));
+    verify!(ctxt.bind_texture(Context::TEXTURE_2D, None));
+
+    target
+}
+
+impl DepthProgram {
+    fn new(defines: &[&str]) -> DepthProgram {
+        let mut effect =
+            Effect::new_from_str_with_defines(SHADOW_VERTEX_SRC, SHADOW_FRAGMENT_SRC, defines)
+                .unwrap();
+        effect.use_program();
+
+        DepthProgram {
+            pos: effect.get_attrib("position").unwrap(),
+            transform: effect.get_uniform("transform").unwrap(),
+            scale: effect.get_uniform("scale").unwrap(),
+            light_view_proj: effect.get_uniform("light_view_proj").unwrap(),
+            depth_plane: effect.get_uniform("depth_plane"),
+            effect,
+        }
+    }
+
+    // Prepares the GL state for drawing depths. Must be followed by a call to `end`.
+    fn begin(&mut self) {
+        let ctxt = Context::get();
+
+        self.effect.use_program();
+        self.pos.enable();
+        // Pushes the depths backward, so that the lit surfaces do not shadow themselves.
+        verify!(ctxt.enable(Context::POLYGON_OFFSET_FILL));
+        verify!(ctxt.polygon_offset(2.0, 4.0));
+        let _ = verify!(ctxt.polygon_mode(Context::FRONT_AND_BACK, Context::FILL));
+    }
+
+    // Restores the GL state modified by `begin`.
+    fn end(&mut self) {
+        let ctxt = Context::get();
+
+        verify!(ctxt.polygon_offset(0.0, 0.0));
+        verify!(ctxt.disable(Context::POLYGON_OFFSET_FILL));
+        self.pos.disable();
+    }
+
+    // Draws the depths of the casters seen with `light_view_proj`.
+    fn draw_casters<'a>(
+        &mut self,
+        casters: impl Iterator<Item = &'a (SceneNode, Option<AABB<f32>>)>,
+        light_view_proj: &Matrix4<f32>,
+    ) {
+        let ctxt = Context::get();
+        self.light_view_proj.upload(light_view_proj);
+
+        for (node, _) in casters {
+            let data = node.data();
+            let object = match data.object() {
+                Some(object) if object.data().surface_rendering_active() => object,
+                _ => continue,
+            };
+            let mut mesh = object.mesh().borrow_mut();
+
+            if object.data().backface_culling_enabled() {
+                verify!(ctxt.enable(Context::CULL_FACE));
+            } else {
+                verify!(ctxt.disable(Context::CULL_FACE));
+            }
+
+            self.transform
+                .upload(&data.world_transformation().to_homogeneous());
+            self.scale
+                .upload(&Matrix3::from_diagonal(&data.world_scale()));
+            mesh.bind_coords(&mut self.pos);
+            mesh.bind_faces();
+
+            verify!(ctxt.draw_elements(
+                Context::TRIANGLES,
+                mesh.num_pts() as i32,
+                Context::UNSIGNED_SHORT,
+                0
+            ));
+        }
+    }
+}
+
+impl ShadowMap {
+    pub fn new() -> ShadowMap {
+        ShadowMap {
+            target: Rc::new(new_depth_target(SHADOW_MAP_SIZE, SHADOW_MAP_SIZE)),
+            program: DepthProgram::new(&[]),
+            contact_target: None,
+            contact_program: None,
+        }
+    }
+
+    // Renders the depths of the shadow casters of `scene` seen from `light`, and makes the
+    // default material sample them until `deactivate` is called.
+    //
+    // With cascades, the depths are rendered for each cascade fit to a slice of the view frustum
+    // of `camera`, with the light infinitely far away. With contact shadows, the distances of the
+    // casters to `camera` are rendered at `size_px`, the size of the image rendered by `camera`.
+    // The framebuffer, viewport, and scissor box must be set again afterward.
+    pub fn render(
+        &mut self,
+        scene: &SceneNode,
+        light: &Point3<f32>,
+        camera: &dyn Camera,
+        settings: &ShadowSettings,
+        size_px: (u32, u32),
+        framebuffers: &mut FramebufferManager,
+    ) {
+        // The casters whose mesh has no bounding box are drawn, but not fit into the map.
+        let casters: Vec<_> = scene
+            .shadow_casters()
+            .into_iter()
+            .map(|node| {
+                let aabb = world_aabb(&node);
+                (node, aabb)
+            })
+            .collect();
+        let bounds = match casters
+            .iter()
+            .filter_map(|c| c.1)
+            .reduce(|a, b| a.merged(&b))
+        {
+            Some(bounds) => bounds,
+            None => return deactivate(),
+        };
+        let ctxt = Context::get();
+        let size = SHADOW_MAP_SIZE as i32;
+
+        framebuffers.select(&self.target);
+        verify!(ctxt.viewport(0, 0, size, size));
+        verify!(ctxt.scissor(0, 0, size, size));
+        set_depth_convention(false);
+        verify!(ctxt.clear_color(1.0, 1.0, 1.0, 1.0));
+        verify!(ctxt.clear(Context::COLOR_BUFFER_BIT | Context::DEPTH_BUFFER_BIT));
+
+        self.program.begin();
+
+        let light_size = match settings.filter {
+            ShadowFilter::Pcf => None,
+            ShadowFilter::Pcss { light_size } => Some(light_size),
+        };
+        let fitted = settings
+            .cascades
+            .as_ref()
+            .and_then(|c| fit_cascades(&bounds, light, camera, c));
+        let mut active = match fitted {
+            Some((cascades, splits)) => {
+                let tile = size / 2;
+
+                for (i, cascade) in cascades.iter().enumerate() {
+                    let (x, y) = ((i as i32 % 2) * tile, (i as i32 / 2) * tile);
+                    verify!(ctxt.viewport(x, y, tile, tile));
+                    verify!(ctxt.scissor(x, y, tile, tile));
+
+                    // Only the casters intersecting the cascade are drawn.
+                    self.program.draw_casters(
+                        casters
+                            .iter()
+                            .filter(|c| c.1.is_none_or(|aabb| cascade.intersects(&aabb))),
+                        &cascade.view_proj,
+                    );
+                }
+
+                // The light is seen from the casters with the angle `light_size / dist`.
+                let dist = na::distance(light, &bounds.center()).max(1.0e-6);
+                let pcss = light_size.map(|size| {
+                    let mut penumbrae = Vector4::zeros();
+
+                    for (p, c) in penumbrae.iter_mut().zip(&cascades) {
+                        *p = c.depth * size / dist / (c.radius * 2.0);
+                    }
+
+                    penumbrae
+                });
+
+                ActiveShadowMap {
+                    target: self.target.clone(),
+                    view_projs: cascades.iter().map(|c| c.view_proj).collect(),
+                    splits,
+                    debug: settings.cascade_debug,
+                    pcss,
+                    contact: None,
+                }
+            }
+            None => {
+                let (light_view_proj, znear, zfar, tan_half_angle) =
+                    light_view_proj(&bounds, light);
+                self.program.draw_casters(casters.iter(), &light_view_proj);
+
+                ActiveShadowMap {
+                    target: self.target.clone(),
+                    view_projs: vec![light_view_proj],
+                    splits: Vec::new(),
+                    debug: false,
+                    pcss: light_size
+                        .map(|size| Vector4::new(znear, zfar, size / (tan_half_angle * 2.0), 0.0)),
+                    contact: None,
+                }
+            }
+        };
+
+        self.program.end();
+
+        if let Some(ref contact) = settings.contact_shadows {
+            active.contact =
+                Some(self.render_contact(&casters, camera, contact, size_px, framebuffers));
+        }
+
+        ACTIVE.with(|a| *a.borrow_mut() = Some(active));
+    }
+
+    // Renders the distances of the casters to `camera`, seen from its first pass, into the
+    // target of the contact shadows, of the given size.
+    fn render_contact(
+        &mut self,
+        casters: &[(SceneNode, Option<AABB<f32>>)],
+        camera: &dyn Camera,
+        contact: &ContactShadows,
+        size: (u32, u32),
+        framebuffers: &mut FramebufferManager,
+    ) -> ActiveContactShadows {
+        let (w, h) = (size.0.max(1), size.1.max(1));
+        let target = match self.contact_target {
+            Some((ref target, curr)) if curr == (w, h) => target.clone(),
+            _ => {
+                let target = Rc::new(new_depth_target(w as usize, h as usize));
+                self.contact_target = Some((target.clone(), (w, h)));
+                target
+            }
+        };
+        let program = self
+            .contact_program
+            .get_or_insert_with(|| DepthProgram::new(&["LINEAR_DEPTH"]));
+        let ctxt = Context::get();
+
+        framebuffers.select(&target);
+        verify!(ctxt.viewport(0, 0, w as i32, h as i32));
+        verify!(ctxt.scissor(0, 0, w as i32, h as i32));
+        set_depth_convention(camera.reversed_depth());
+        verify!(ctxt.clear_color(1.0, 1.0, 1.0, 1.0));
+        verify!(ctxt.clear(Context::COLOR_BUFFER_BIT | Context::DEPTH_BUFFER_BIT));
+
+        // The distances along the view direction are divided by the far distance to fit in the
+        // packed depths.
+        let (proj, view) = camera.pass_matrices(0);
+        let zfar = camera.clip_planes().1;
+        let depth_plane = -view.row(2).transpose() / zfar;
+
+        program.begin();
+
+        if let Some(ref mut uniform) = program.depth_plane {
+            uniform.upload(&depth_plane);
+        }
+
+        program.draw_casters(casters.iter(), &(proj * view));
+        program.end();
+        set_depth_convention(false);
+
+        ActiveContactShadows {
+            target,
+            proj,
+            params: Vector3::new(contact.length, contact.thickness, zfar),
+        }
+    }
+}
+
+impl Cascade {
+    // Whether a world-space bounding box may cast shadows in this cascade.
+    fn intersects(&self, aabb: &AABB<f32>) -> bool {
+        let center = self.view * aabb.center();
+        let reach = self.radius + aabb.half_extents().norm();
+
+        // The casters between the light and the cascade are kept.
+        center.x.abs() <= reach && center.y.abs() <= reach && center.z >= -reach
+    }
+}
+
+// Stops the sampling of the shadow map by the default material.
+pub(crate) fn deactivate() {
+    ACTIVE.with(|a| *a.borrow_mut() = None)
+}
+
+// Calls `f` with the shadow map sampled by the default material, if any.
+pub(crate) fn with_active<T>(f: impl FnOnce(&ActiveShadowMap) -> T) -> Option<T> {
+    ACTIVE.with(|a| a.borrow().as_ref().map(f))
+}
+
+// The world-space bounding box of the object of `node`.
+//
+// The corners of the cached bounding box of the mesh are cheaper to transform than its
+// vertices.
+fn world_aabb(node: &SceneNode) -> Option<AABB<f32>> {
+    let data = node.data();
+    let aabb = data
+        .object()
+        .and_then(|o| o.mesh().borrow().bvh())
+        .and_then(|bvh| bvh.aabb())?;
+    let transform = data.world_transformation();
+    let scale = data.world_scale();
+    let mut mins = Point3::new(f32::MAX, f32::MAX, f32::MAX);
+    let mut maxs = Point3::new(f32::MIN, f32::MIN, f32::MIN);
+
+    for i in 0..8 {
+        let pt = transform * Point3::from(corner(&aabb, i).coords.component_mul(&scale));
+        mins = mins.inf(&pt);
+        maxs = maxs.sup(&pt);
+    }
+
+    Some(AABB::new(mins, maxs))
+}
+
+// The `i`-th of the eight corners of a bounding box.
+fn corner(aabb: &AABB<f32>, i: usize) -> Point3<f32> {
+    Point3::new(
+        if i & 1 == 0 { aabb.mins.x } else { aabb.maxs.x },
+        if i & 2 == 0 { aabb.mins.y } else { aabb.maxs.y },
+        if i & 4 == 0 { aabb.mins.z } else { aabb.maxs.z },
+    )
+}
+
+// The view-projection looking from `light` to the bounding sphere of the shadow casters, with
+// its near and far distances, and the tangent of half its field of view.
+//
+// A light inside the sphere only casts the shadows within a cone of 160°.
+fn light_view_proj(bounds: &AABB<f32>, light: &Point3<f32>) -> (Matrix4<f32>, f32, f32, f32) {
+    let center = bounds.center();
+    let radius = bounds.half_extents().norm();
+    let dir = center - light;
+    let dist = dir.norm();
+    let max_half_angle = 80.0f32.to_radians();
+    let half_angle = if dist > radius {
+        (radius / dist).asin().min(max_half_angle)
+    } else {
+        max_half_angle
+    };
+    let up = if dir.cross(&Vector3::y()).norm_squared() > 1.0e-6 * dist * dist {
+        Vector3::y()
+    } else {
+        Vector3::x()
+    };
+    let target = if dist > 1.0e-6 {
+        center
+    } else {
+        light + Vector3::z()
+    };
+    let zfar = dist + radius;
+    let znear = (dist - radius).max(zfar * 1.0e-3);
+    let view = Isometry3::look_at_rh(light, &target, &up);
+    let proj = Perspective3::new(1.0, half_angle * 2.0, znear, zfar);
+
+    (
+        proj.as_matrix() * view.to_homogeneous(),
+        znear,
+        zfar,
+        half_angle.tan(),
+    )
+}
+
+// The cascades fit to the slices of the view frustum of `camera`, with the view-space distances
+// where the slices end.
+//
+// The light is infinitely far away, in the direction of `light` from the center of the shadow
+// casters. Each cascade is an orthographic projection of the bounding sphere of its slice, whose
+// size does not change when the camera rotates, and whose center is snapped to the texels of the
+// shadow map, so that the shadows do not flicker when the camera moves.
+fn fit_cascades(
+    bounds: &AABB<f32>,
+    light: &Point3<f32>,
+    camera: &dyn Camera,
+    cascades: &ShadowCascades,
+) -> Option<(Vec<Cascade>, Vec<f32>)> {
+    let (znear, zfar) = camera.clip_planes();
+    let far = zfar.min(cascades.max_distance);
+
+    if far <= znear {
+        return None;
+    }
+
+    let dir = bounds.center() - light;
+    let dir = if dir.norm_squared() > 1.0e-12 {
+        dir.normalize()
+    } else {
+        -Vector3::y()
+    };
+    let up = if dir.cross(&Vector3::y()).norm_squared() > 1.0e-6 {
+        Vector3::y()
+    } else {
+        Vector3::x()
+    };
+    let rotation = UnitQuaternion::look_at_rh(&dir, &up);
+
+    // The edges of the view frustum, from the near to the far plane, with the view-space
+    // distances of their ends.
+    let view = camera.view_transform();
+    let inv = camera.inverse_transformation();
+    let (near_depth, far_depth) = camera.depth_range();
+    let mut edges = Vec::with_capacity(4);
+
+    for (x, y) in [(-1.0, -1.0), (1.0, -1.0), (-1.0, 1.0), (1.0, 1.0)] {
+        let a = Point3::from_homogeneous(inv * Point4::new(x, y, near_depth, 1.0).coords)?;
+        let b = Point3::from_homogeneous(inv * Point4::new(x, y, far_depth, 1.0).coords)?;
+        edges.push((a, b, -(view * a).z, -(view * b).z));
+    }
+
+    let point_at = |edge: &(Point3<f32>, Point3<f32>, f32, f32), dist: f32| {
+        let (a, b, da, db) = *edge;
+        a + (b - a) * ((dist - da) / (db - da))
+    };
+    let count = cascades.count.clamp(1, MAX_SHADOW_CASCADES);
+    let splits: Vec<f32> = (1..=count)
+        .map(|i| cascades.split.distance(znear, far, i as f32 / count as f32))
+        .collect();
+    let texels = (SHADOW_MAP_SIZE / 2) as f32;
+    let mut result = Vec::with_capacity(count);
+
+    for (i, end) in splits.iter().enumerate() {
+        let start = if i == 0 { znear } else { splits[i - 1] };
+        let corners: Vec<_> = edges
+            .iter()
+            .flat_map(|edge| [point_at(edge, start), point_at(edge, *end)])
+            .collect();
+        let center = corners
+            .iter()
+            .fold(Point3::origin(), |c, p| c + p.coords / corners.len() as f32);
+        let radius = corners
+            .iter()
+            .map(|p| na::distance(p, &center))
+            .fold(0.0, f32::max);
+        // Rounded up, for the rounding errors not to change the size of the texels.
+        let radius = (radius * 16.0).ceil() / 16.0;
+        let texel = radius * 2.0 / texels;
+        let mut center = rotation * center.coords;
+        center.x = (center.x / texel).floor() * texel;
+        center.y = (center.y / texel).floor() * texel;
+
+        // The casters closer to the light than the sphere are kept.
+        let nearest = (0..8)
+            .map(|i| (rotation * corner(bounds, i).coords).z - center.z)
+            .fold(radius, f32::max);
+        let view = Isometry3::from_parts(Translation3::from(-center), rotation);
+        let proj = Orthographic3::new(-radius, radius, -radius, radius, -nearest, radius);
+
+        result.push(Cascade {
+            view,
+            view_proj: proj.as_matrix() * view.to_homogeneous(),
+            radius,
+            depth: radius + nearest,
+        });
+    }
+
+    Some((result, splits))
+}
+
+// The position is computed as in the default vertex shader, for the depths to match.
+static SHADOW_VERTEX_SRC: &str = "#version 100
+attribute vec3 position;
+uniform mat3 scale;
+uniform mat4 light_view_proj, transform;
+#ifdef LINEAR_DEPTH
+uniform vec4 depth_plane;
+varying float depth;
+#endif
+
+void main() {
+    vec4 world = transform * vec4(scale * position, 1.0);
+    gl_Position = light_view_proj * world;
+#ifdef LINEAR_DEPTH
+    depth = dot(depth_plane, world);
+#endif
+}
+";
+
+static SHADOW_FRAGMENT_SRC: &str = "#version 100
+#ifdef GL_FRAGMENT_PRECISION_HIGH
+   precision highp float;
+#else
+   precision mediump float;
+#endif
+
+#include \"kiss3d/shadow\"
+
+#ifdef LINEAR_DEPTH
+varying float depth;
+#endif
+
+void main() {
+#ifdef LINEAR_DEPTH
+    gl_FragColor = kiss3d_pack_depth(clamp(depth, 0.0, 0.9999));
+#else
+    gl_FragColor = kiss3d_pack_depth(gl_FragCoord.z);
+#endif
+}
+";
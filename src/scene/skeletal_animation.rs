@@ -0,0 +1,261 @@
+//! Animation of the joints of a skeleton along keyframes.
+
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use crate::resource::{JointTransform, Skeleton};
+
+// The keyframes of one joint, sorted by time.
+#[derive(Clone, Debug)]
+struct JointTrack {
+    joint: usize,
+    keyframes: Vec<(f32, JointTransform)>,
+}
+
+impl JointTrack {
+    // The pose at `time`, interpolated between the keyframes around it.
+    fn sample(&self, time: f32) -> JointTransform {
+        let next = self.keyframes.partition_point(|(t, _)| *t <= time);
+
+        if next == 0 {
+            return self.keyframes[0].1;
+        }
+
+        if next == self.keyframes.len() {
+            return self.keyframes[next - 1].1;
+        }
+
+        let (t0, ref pose0) = self.keyframes[next - 1];
+        let (t1, ref pose1) = self.keyframes[next];
+        pose0.interpolate(pose1, (time - t0) / (t1 - t0))
+    }
+}
+
+/// The keyframes of the poses of some joints of a skeleton, e.g., a walk cycle.
+///
+/// The poses are interpolated between the keyframes of each joint, and are held before the first
+/// and after the last one. The joints without keyframes keep their pose when the clip is applied.
+#[derive(Clone, Debug, Default)]
+pub struct AnimationClip {
+    tracks: Vec<JointTrack>,
+    duration: f32,
+}
+
+impl AnimationClip {
+    /// Creates a clip without keyframes.
+    pub fn new() -> AnimationClip {
+        AnimationClip {
+            tracks: Vec::new(),
+            duration: 0.0,
+        }
+    }
+
+    /// Adds a keyframe setting the pose of the `joint`-th joint at `time`, in seconds.
+    ///
+    /// It replaces the keyframe of that joint at the same time, if any. The keyframe is ignored
+    /// if `time` is not finite.
+    pub fn add_keyframe(&mut self, joint: usize, time: f32, pose: JointTransform) {
+        if !time.is_finite() {
+            return;
+        }
+
+        let track = match self.tracks.iter().position(|t| t.joint == joint) {
+            Some(i) => &mut self.tracks[i],
+            None => {
+                self.tracks.push(JointTrack {
+                    joint,
+                    keyframes: Vec::new(),
+                });
+                self.tracks.last_mut().unwrap()
+            }
+        };
+
+        match track
+            .keyframes
+            .binary_search_by(|(t, _)| t.partial_cmp(&time).unwrap())
+        {
+            Ok(i) => track.keyframes[i].1 = pose,
+            Err(i) => track.keyframes.insert(i, (time, pose)),
+        }
+
+        self.duration = self.duration.max(time);
+    }
+
+    /// The time of the last keyframe of this clip, in seconds.
+    pub fn duration(&self) -> f32 {
+        self.duration
+    }
+
+    /// Sets the poses of the joints of `skeleton` animated by this clip to their poses at `time`,
+    /// in seconds.
+    ///
+    /// The keyframes of joints `skeleton` does not have are ignored.
+    pub fn apply(&self, time: f32, skeleton: &mut Skeleton) {
+        for track in &self.tracks {
+            if track.joint < skeleton.len() {
+                skeleton.set_pose(track.joint, track.sample(time));
+            }
+        }
+    }
+}
+
+/// Plays animation clips on a skeleton.
+///
+/// Once attached to a scene node with `SceneNode::add_animation_player`, the player advances at
+/// each frame, and poses the skeleton deforming the objects of the node.
+///
+/// # Example
+/// ```no_run
+/// # use std::cell::RefCell;
+/// # use std::rc::Rc;
+/// # use kiss3d::resource::Skeleton;
+/// # use kiss3d::scene::{AnimationClip, AnimationPlayer};
+/// # use kiss3d::window::Window;
+/// # use kiss3d::nalgebra::{UnitQuaternion, Vector3};
+/// # fn skinned_arm(_: &mut Window) -> (kiss3d::scene::SceneNode, Skeleton) { unimplemented!() }
+/// let mut window = Window::new("Kiss3d: skeletal animation");
+/// let (mut arm, skeleton) = skinned_arm(&mut window);
+/// let elbow = skeleton.joint_index("elbow").unwrap();
+///
+/// let mut wave = AnimationClip::new();
+/// let mut bent = *skeleton.rest_pose(elbow);
+/// wave.add_keyframe(elbow, 0.0, bent);
+/// bent.rotation = UnitQuaternion::from_axis_angle(&Vector3::z_axis(), 1.5);
+/// wave.add_keyframe(elbow, 1.0, bent);
+///
+/// let mut player = AnimationPlayer::new(Rc::new(RefCell::new(skeleton)));
+/// player.set_looping(true);
+/// player.play(Rc::new(wave));
+/// arm.add_animation_player(player);
+///
+/// while window.render() {}
+/// ```
+#[derive(Clone, Debug)]
+pub struct AnimationPlayer {
+    skeleton: Rc<RefCell<Skeleton>>,
+    clip: Option<Rc<AnimationClip>>,
+    time: f32,
+    speed: f32,
+    looping: bool,
+    playing: bool,
+}
+
+impl AnimationPlayer {
+    /// Creates a player posing `skeleton`, without clip.
+    pub fn new(skeleton: Rc<RefCell<Skeleton>>) -> AnimationPlayer {
+        AnimationPlayer {
+            skeleton,
+            clip: None,
+            time: 0.0,
+            speed: 1.0,
+            looping: false,
+            playing: false,
+        }
+    }
+
+    /// The skeleton posed by this player.
+    pub fn skeleton(&self) -> &Rc<RefCell<Skeleton>> {
+        &self.skeleton
+    }
+
+    /// Plays `clip` from its beginning.
+    pub fn play(&mut self, clip: Rc<AnimationClip>) {
+        clip.apply(0.0, &mut self.skeleton.borrow_mut());
+        self.clip = Some(clip);
+        self.time = 0.0;
+        self.playing = true;
+    }
+
+    /// The clip played, or paused, by this player.
+    pub fn clip(&self) -> Option<&Rc<AnimationClip>> {
+        self.clip.as_ref()
+    }
+
+    /// Stops playing the clip, and puts the skeleton back in its rest pose.
+    pub fn stop(&mut self) {
+        self.clip = None;
+        self.time = 0.0;
+        self.playing = false;
+        self.skeleton.borrow_mut().reset_pose();
+    }
+
+    /// Stops advancing the clip, keeping the skeleton in its current pose.
+    pub fn pause(&mut self) {
+        self.playing = false;
+    }
+
+    /// Advances the clip again after `pause`.
+    pub fn resume(&mut self) {
+        self.playing = self.clip.is_some();
+    }
+
+    /// Whether a clip is being played.
+    pub fn is_playing(&self) -> bool {
+        self.playing
+    }
+
+    /// The time elapsed in the clip, in seconds.
+    pub fn time(&self) -> f32 {
+        self.time
+    }
+
+    /// Moves to `time`, in seconds, in the clip, and poses the skeleton accordingly.
+    pub fn seek(&mut self, time: f32) {
+        self.time = time;
+        self.update(0.0);
+    }
+
+    /// The factor multiplying the time elapsed in the clip.
+    pub fn speed(&self) -> f32 {
+        self.speed
+    }
+
+    /// Sets the factor multiplying the time elapsed in the clip (default at 1.0).
+    ///
+    /// A negative speed plays the clip backward.
+    pub fn set_speed(&mut self, speed: f32) {
+        self.speed = speed;
+    }
+
+    /// Whether the clip restarts once finished.
+    pub fn is_looping(&self) -> bool {
+        self.looping
+    }
+
+    /// Sets whether the clip restarts once finished (default at `false`).
+    ///
+    /// Otherwise, the player stops advancing at the end of the clip, keeping its last pose.
+    pub fn set_looping(&mut self, looping: bool) {
+        self.looping = looping;
+    }
+
+    /// Advances the clip by `dt` seconds, and poses the skeleton accordingly.
+    ///
+    /// This is called at each frame for the players attached to scene nodes.
+    pub fn update(&mut self, dt: f32) {
+        let clip = match self.clip {
+            Some(ref clip) => clip,
+            None => return,
+        };
+
+        if self.playing {
+            self.time += dt * self.speed;
+        }
+
+        let duration = clip.duration();
+
+        if self.looping && duration > 0.0 {
+            self.time = self.time.rem_euclid(duration);
+        } else {
+            let time = self.time.clamp(0.0, duration);
+
+            // The clip ends past its last keyframe, or before its first one if played backward.
+            if time != self.time {
+                self.time = time;
+                self.playing = false;
+            }
+        }
+
+        clip.apply(self.time, &mut self.skeleton.borrow_mut());
+    }
+}
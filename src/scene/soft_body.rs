@@ -0,0 +1,555 @@
+//! Ropes and cloths simulated with position-based dynamics.
+//!
+//! A soft body is a set of particles linked by distance constraints. At each step, the particles
+//! move under the gravity and the wind, then their positions are corrected to satisfy the
+//! constraints. The mesh of the scene node of the body is updated after each step, from a
+//! behavior of the node: the simulation advances automatically once per frame.
+
+use std::cell::RefCell;
+use std::f32;
+use std::rc::Rc;
+
+use na::{Point2, Point3, Vector3};
+
+use crate::procedural::tube_vertices;
+use crate::resource::Mesh;
+use crate::scene::SceneNode;
+
+/// The parameters of the simulation of a soft body.
+///
+/// The vectors are expressed in the local frame of the scene node of the body.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct SoftBodyParams {
+    /// The acceleration of gravity.
+    pub gravity: Vector3<f32>,
+    /// The velocity of the wind.
+    pub wind: Vector3<f32>,
+    /// How fast the particles are dragged to the velocity of the wind, in `1 / s`.
+    pub drag: f32,
+    /// The fraction of their velocity the particles lose per second.
+    pub damping: f32,
+    /// The stiffness of the constraints keeping the distances between neighboring particles,
+    /// from `0.0` to `1.0`.
+    pub stiffness: f32,
+    /// The stiffness of the constraints resisting the bending of the body, from `0.0` to `1.0`.
+    pub bending_stiffness: f32,
+    /// The number of times the constraints are solved at each step. More iterations make the
+    /// body less stretchy.
+    pub iterations: usize,
+    /// The maximum duration of a step, in seconds. Each frame is simulated with as many steps as
+    /// needed.
+    pub max_time_step: f32,
+}
+
+impl Default for SoftBodyParams {
+    fn default() -> SoftBodyParams {
+        SoftBodyParams {
+            gravity: Vector3::new(0.0, -9.81, 0.0),
+            wind: Vector3::zeros(),
+            drag: 1.0,
+            damping: 0.5,
+            stiffness: 1.0,
+            bending_stiffness: 0.1,
+            iterations: 10,
+            max_time_step: 1.0 / 120.0,
+        }
+    }
+}
+
+// The longest duration simulated at once, so that a stalled frame does not make the body jump.
+const MAX_FRAME_TIME: f32 = 0.1;
+
+struct Constraint {
+    a: usize,
+    b: usize,
+    rest: f32,
+    bending: bool,
+}
+
+// The geometry rendered for the particles.
+enum Shape {
+    // A grid of particles, rendered as is.
+    Cloth { faces: Vec<Point3<u16>> },
+    // A line of particles, rendered as a tube.
+    Rope { radius: f32, sides: usize },
+}
+
+struct Simulation {
+    params: SoftBodyParams,
+    positions: Vec<Point3<f32>>,
+    previous: Vec<Point3<f32>>,
+    velocities: Vec<Vector3<f32>>,
+    // Zero for the pinned particles.
+    inv_masses: Vec<f32>,
+    constraints: Vec<Constraint>,
+    shape: Shape,
+}
+
+impl Simulation {
+    fn new(positions: Vec<Point3<f32>>, constraints: Vec<Constraint>, shape: Shape) -> Simulation {
+        let n = positions.len();
+
+        Simulation {
+            params: SoftBodyParams::default(),
+            previous: positions.clone(),
+            positions,
+            velocities: vec![Vector3::zeros(); n],
+            inv_masses: vec![1.0; n],
+            constraints,
+            shape,
+        }
+    }
+
+    fn step(&mut self, dt: f32) {
+        let dt = dt.min(MAX_FRAME_TIME);
+
+        if dt <= 0.0 {
+            return;
+        }
+
+        let substeps = (dt / self.params.max_time_step.max(1.0e-4)).ceil().max(1.0);
+
+        for _ in 0..substeps as usize {
+            self.substep(dt / substeps);
+        }
+    }
+
+    fn substep(&mut self, h: f32) {
+        let accelerations = self.wind_accelerations();
+        let damping = (1.0 - self.params.damping * h).max(0.0);
+        let gravity = self.params.gravity;
+
+        self.previous.copy_from_slice(&self.positions);
+
+        for (i, (pos, vel)) in self
+            .positions
+            .iter_mut()
+            .zip(self.velocities.iter_mut())
+            .enumerate()
+        {
+            if self.inv_masses[i] != 0.0 {
+                *vel = *vel * damping + (gravity + accelerations[i]) * h;
+                *pos += *vel * h;
+            }
+        }
+
+        for _ in 0..self.params.iterations {
+            for c in &self.constraints {
+                let stiffness = if c.bending {
+                    self.params.bending_stiffness
+                } else {
+                    self.params.stiffness
+                };
+                let (wa, wb) = (self.inv_masses[c.a], self.inv_masses[c.b]);
+                let delta = self.positions[c.b] - self.positions[c.a];
+                let len = delta.norm();
+
+                if wa + wb == 0.0 || len < 1.0e-6 {
+                    continue;
+                }
+
+                let correction = delta * ((len - c.rest) / (len * (wa + wb)) * stiffness);
+                self.positions[c.a] += correction * wa;
+                self.positions[c.b] -= correction * wb;
+            }
+        }
+
+        for ((vel, pos), prev) in self
+            .velocities
+            .iter_mut()
+            .zip(&self.positions)
+            .zip(&self.previous)
+        {
+            *vel = (pos - prev) / h;
+        }
+    }
+
+    // The accelerations of the particles due to the wind.
+    //
+    // The air drags the particles of a rope. For a cloth, only the component of the relative
+    // velocity of the air orthogonal to the surface pushes it.
+    fn wind_accelerations(&self) -> Vec<Vector3<f32>> {
+        let drag = self.params.drag;
+        let wind = self.params.wind;
+
+        match &self.shape {
+            Shape::Rope { .. } => self.velocities.iter().map(|v| (wind - v) * drag).collect(),
+            Shape::Cloth { faces } => {
+                let mut result = vec![Vector3::zeros(); self.positions.len()];
+                let mut counts = vec![0.0f32; self.positions.len()];
+
+                for f in faces {
+                    let (a, b, c) = (f.x as usize, f.y as usize, f.z as usize);
+                    let normal = (self.positions[b] - self.positions[a])
+                        .cross(&(self.positions[c] - self.positions[a]));
+
+                    if let Some(normal) = normal.try_normalize(1.0e-12) {
+                        let velocity =
+                            (self.velocities[a] + self.velocities[b] + self.velocities[c]) / 3.0;
+                        let push = normal * (normal.dot(&(wind - velocity)) * drag);
+
+                        for i in [a, b, c] {
+                            result[i] += push;
+                            counts[i] += 1.0;
+                        }
+                    }
+                }
+
+                for (acc, count) in result.iter_mut().zip(counts) {
+                    if count > 0.0 {
+                        *acc /= count;
+                    }
+                }
+
+                result
+            }
+        }
+    }
+
+    fn pin(&mut self, i: usize, position: Option<Point3<f32>>) {
+        if let Some(position) = position {
+            self.positions[i] = position;
+        }
+
+        self.inv_masses[i] = 0.0;
+        self.velocities[i] = Vector3::zeros();
+    }
+
+    fn unpin(&mut self, i: usize) {
+        self.inv_masses[i] = 1.0;
+    }
+
+    // Writes the vertices of the mesh of the body.
+    fn write_vertices(&self, coords: &mut Vec<Point3<f32>>) {
+        match self.shape {
+            Shape::Cloth { .. } => {
+                coords.clear();
+                coords.extend_from_slice(&self.positions);
+            }
+            Shape::Rope { radius, sides } => {
+                coords.clear();
+                coords.extend(tube_vertices(&self.positions, radius, sides));
+            }
+        }
+    }
+}
+
+// Creates the node of a soft body, updated once per frame by a behavior.
+fn add_body(
+    parent: &mut SceneNode,
+    sim: Simulation,
+    faces: Vec<Point3<u16>>,
+    uvs: Vec<Point2<f32>>,
+) -> (SceneNode, Rc<RefCell<Simulation>>) {
+    let mut coords = Vec::new();
+    sim.write_vertices(&mut coords);
+    assert!(
+        coords.len() <= u16::MAX as usize + 1,
+        "A soft body cannot have more than 65536 vertices."
+    );
+
+    let mesh = Mesh::new(coords, faces, None, Some(uvs), true);
+    let mut node = parent.add_mesh(Rc::new(RefCell::new(mesh)), Vector3::from_element(1.0));
+    node.enable_backface_culling(false);
+
+    let sim = Rc::new(RefCell::new(sim));
+    let shared = sim.clone();
+
+    node.add_behavior(move |node, dt| {
+        let mut sim = shared.borrow_mut();
+        sim.step(dt);
+        node.modify_vertices(&mut |coords| sim.write_vertices(coords));
+        node.recompute_normals();
+    });
+
+    (node, sim)
+}
+
+/// A rectangular cloth, simulated with position-based dynamics.
+///
+/// The cloth is a grid of particles, initially hanging in the `xy` plane of its node below its
+/// top edge, which is centered at the origin. The particles are numbered row by row, from the top
+/// left corner. Nothing is pinned initially, so the cloth falls unless some particles are pinned.
+///
+/// ```no_run
+/// # use kiss3d::window::Window;
+/// # use kiss3d::nalgebra::Vector3;
+/// let mut window = Window::new("Kiss3d: cloth");
+/// let mut cloth = window.add_cloth(2.0, 2.0, 20, 20);
+///
+/// cloth.pin(0, 0);
+/// cloth.pin(20, 0);
+/// cloth.set_wind(Vector3::new(0.0, 0.0, 2.0));
+///
+/// while window.render() {}
+/// ```
+pub struct ClothNode {
+    node: SceneNode,
+    sim: Rc<RefCell<Simulation>>,
+    columns: usize,
+}
+
+impl ClothNode {
+    pub(crate) fn new(
+        parent: &mut SceneNode,
+        width: f32,
+        height: f32,
+        wsubdivs: usize,
+        hsubdivs: usize,
+    ) -> ClothNode {
+        assert!(
+            wsubdivs > 0 && hsubdivs > 0,
+            "The cloth must have at least one subdivision along each side."
+        );
+
+        let (columns, rows) = (wsubdivs + 1, hsubdivs + 1);
+        let id = |i: usize, j: usize| j * columns + i;
+        let mut positions = Vec::with_capacity(columns * rows);
+        let mut uvs = Vec::with_capacity(columns * rows);
+        let mut faces = Vec::new();
+        let mut constraints = Vec::new();
+
+        for j in 0..rows {
+            for i in 0..columns {
+                let (u, v) = (i as f32 / wsubdivs as f32, j as f32 / hsubdivs as f32);
+                positions.push(Point3::new((u - 0.5) * width, -v * height, 0.0));
+                uvs.push(Point2::new(u, v));
+            }
+        }
+
+        let mut link = |a: usize, b: usize, bending: bool| {
+            constraints.push(Constraint {
+                a,
+                b,
+                rest: na::distance(&positions[a], &positions[b]),
+                bending,
+            })
+        };
+
+        for j in 0..rows {
+            for i in 0..columns {
+                if i + 1 < columns {
+                    link(id(i, j), id(i + 1, j), false);
+                }
+                if j + 1 < rows {
+                    link(id(i, j), id(i, j + 1), false);
+                }
+                if i + 1 < columns && j + 1 < rows {
+                    link(id(i, j), id(i + 1, j + 1), false);
+                    link(id(i + 1, j), id(i, j + 1), false);
+                }
+                if i + 2 < columns {
+                    link(id(i, j), id(i + 2, j), true);
+                }
+                if j + 2 < rows {
+                    link(id(i, j), id(i, j + 2), true);
+                }
+            }
+        }
+
+        for j in 0..hsubdivs {
+            for i in 0..wsubdivs {
+                let (a, b, c, d) = (id(i, j), id(i, j + 1), id(i + 1, j), id(i + 1, j + 1));
+                faces.push(Point3::new(a as u16, b as u16, c as u16));
+                faces.push(Point3::new(c as u16, b as u16, d as u16));
+            }
+        }
+
+        let shape = Shape::Cloth {
+            faces: faces.clone(),
+        };
+        let (node, sim) = add_body(
+            parent,
+            Simulation::new(positions, constraints, shape),
+            faces,
+            uvs,
+        );
+
+        ClothNode { node, sim, columns }
+    }
+
+    /// The scene node rendering this cloth.
+    pub fn node(&self) -> &SceneNode {
+        &self.node
+    }
+
+    /// The scene node rendering this cloth.
+    pub fn node_mut(&mut self) -> &mut SceneNode {
+        &mut self.node
+    }
+
+    /// The parameters of the simulation.
+    pub fn params(&self) -> SoftBodyParams {
+        self.sim.borrow().params
+    }
+
+    /// Sets the parameters of the simulation.
+    pub fn set_params(&mut self, params: SoftBodyParams) {
+        self.sim.borrow_mut().params = params
+    }
+
+    /// Sets the velocity of the wind, in the local frame of the node.
+    pub fn set_wind(&mut self, wind: Vector3<f32>) {
+        self.sim.borrow_mut().params.wind = wind
+    }
+
+    /// Sets the acceleration of gravity, in the local frame of the node.
+    pub fn set_gravity(&mut self, gravity: Vector3<f32>) {
+        self.sim.borrow_mut().params.gravity = gravity
+    }
+
+    /// The position of the particle at the given column and row, in the local frame of the node.
+    pub fn particle(&self, column: usize, row: usize) -> Point3<f32> {
+        self.sim.borrow().positions[self.index(column, row)]
+    }
+
+    /// Pins the particle at the given column and row where it is.
+    pub fn pin(&mut self, column: usize, row: usize) {
+        let i = self.index(column, row);
+        self.sim.borrow_mut().pin(i, None)
+    }
+
+    /// Pins the particle at the given column and row at a position of the local frame of the
+    /// node, e.g., to move the cloth by one of its pinned particles.
+    pub fn pin_at(&mut self, column: usize, row: usize, position: Point3<f32>) {
+        let i = self.index(column, row);
+        self.sim.borrow_mut().pin(i, Some(position))
+    }
+
+    /// Releases the particle at the given column and row.
+    pub fn unpin(&mut self, column: usize, row: usize) {
+        let i = self.index(column, row);
+        self.sim.borrow_mut().unpin(i)
+    }
+
+    fn index(&self, column: usize, row: usize) -> usize {
+        assert!(column < self.columns, "Cloth column out of bounds.");
+        row * self.columns + column
+    }
+}
+
+/// A rope, simulated with position-based dynamics.
+///
+/// The rope is a line of particles, initially hanging along the `-y` axis of its node from the
+/// origin, and rendered as a tube. The particles are numbered from the top end. Nothing is pinned
+/// initially, so the rope falls unless some particles are pinned.
+pub struct RopeNode {
+    node: SceneNode,
+    sim: Rc<RefCell<Simulation>>,
+}
+
+impl RopeNode {
+    pub(crate) fn new(
+        parent: &mut SceneNode,
+        length: f32,
+        radius: f32,
+        segments: usize,
+    ) -> RopeNode {
+        assert!(segments > 0, "The rope must have at least one segment.");
+
+        // The number of vertices of each ring of the tube.
+        let sides = 8;
+        let positions: Vec<_> = (0..=segments)
+            .map(|k| Point3::new(0.0, -(k as f32) / segments as f32 * length, 0.0))
+            .collect();
+        let mut constraints = Vec::new();
+        let mut faces = Vec::new();
+        let mut uvs = Vec::new();
+
+        for k in 0..=segments {
+            for (offset, bending) in [(1, false), (2, true)] {
+                if k + offset <= segments {
+                    constraints.push(Constraint {
+                        a: k,
+                        b: k + offset,
+                        rest: length * offset as f32 / segments as f32,
+                        bending,
+                    });
+                }
+            }
+
+            for s in 0..sides {
+                uvs.push(Point2::new(
+                    s as f32 / sides as f32,
+                    k as f32 / segments as f32,
+                ));
+            }
+        }
+
+        for k in 0..segments {
+            for s in 0..sides {
+                let a = k * sides + s;
+                let b = k * sides + (s + 1) % sides;
+                let (c, d) = (a + sides, b + sides);
+                faces.push(Point3::new(a as u16, b as u16, c as u16));
+                faces.push(Point3::new(b as u16, d as u16, c as u16));
+            }
+        }
+
+        let shape = Shape::Rope { radius, sides };
+        let (node, sim) = add_body(
+            parent,
+            Simulation::new(positions, constraints, shape),
+            faces,
+            uvs,
+        );
+
+        RopeNode { node, sim }
+    }
+
+    /// The scene node rendering this rope.
+    pub fn node(&self) -> &SceneNode {
+        &self.node
+    }
+
+    /// The scene node rendering this rope.
+    pub fn node_mut(&mut self) -> &mut SceneNode {
+        &mut self.node
+    }
+
+    /// The parameters of the simulation.
+    pub fn params(&self) -> SoftBodyParams {
+        self.sim.borrow().params
+    }
+
+    /// Sets the parameters of the simulation.
+    pub fn set_params(&mut self, params: SoftBodyParams) {
+        self.sim.borrow_mut().params = params
+    }
+
+    /// Sets the velocity of the wind, in the local frame of the node.
+    pub fn set_wind(&mut self, wind: Vector3<f32>) {
+        self.sim.borrow_mut().params.wind = wind
+    }
+
+    /// Sets the acceleration of gravity, in the local frame of the node.
+    pub fn set_gravity(&mut self, gravity: Vector3<f32>) {
+        self.sim.borrow_mut().params.gravity = gravity
+    }
+
+    /// The number of particles of the rope.
+    pub fn num_particles(&self) -> usize {
+        self.sim.borrow().positions.len()
+    }
+
+    /// The position of a particle, in the local frame of the node.
+    pub fn particle(&self, i: usize) -> Point3<f32> {
+        self.sim.borrow().positions[i]
+    }
+
+    /// Pins a particle where it is.
+    pub fn pin(&mut self, i: usize) {
+        self.sim.borrow_mut().pin(i, None)
+    }
+
+    /// Pins a particle at a position of the local frame of the node, e.g., to drag the rope by
+    /// one of its ends.
+    pub fn pin_at(&mut self, i: usize, position: Point3<f32>) {
+        self.sim.borrow_mut().pin(i, Some(position))
+    }
+
+    /// Releases a particle.
+    pub fn unpin(&mut self, i: usize) {
+        self.sim.borrow_mut().unpin(i)
+    }
+}
@@ -0,0 +1,391 @@
+//! A spatial hash of the world bounding boxes of the objects of a scene graph.
+
+use std::collections::{HashMap, HashSet};
+
+use na::{Isometry3, Matrix4, Point3, Vector3};
+use ncollide3d::bounding_volume::{BoundingVolume, AABB};
+
+use crate::camera::Camera;
+use crate::scene::{NodeHandle, SceneNode, SceneNodeData};
+
+// The maximum number of cells a bounding box is inserted into. Larger boxes are stored apart,
+// and tested by every query.
+const MAX_CELLS_PER_NODE: f32 = 64.0;
+
+type Cell = (i32, i32, i32);
+type LocalAabb = (AABB<f32>, Isometry3<f32>, Vector3<f32>);
+
+struct Entry {
+    aabb: AABB<f32>,
+    // The range of cells containing the node, or `None` if it is stored apart.
+    cells: Option<(Cell, Cell)>,
+    pickable: bool,
+    // The update during which the node was last seen in the scene graph.
+    stamp: u64,
+}
+
+/// A spatial hash of the world-space bounding boxes of the objects of a scene graph.
+///
+/// The space is divided into cubic cells, each one listing the nodes whose bounding box overlaps
+/// it, so that a query only considers the nodes close to its region. The bounding boxes are those
+/// of the triangles of the meshes: the objects without triangles, e.g., point clouds, are not
+/// indexed.
+///
+/// The index is refreshed with `update`, which walks the scene graph but only moves the nodes
+/// whose bounding box changed. `Window::nodes_in_sphere` and `Window::nodes_in_frustum` rely on
+/// an index of the window scene maintained this way.
+pub struct SpatialIndex {
+    cell_size: f32,
+    entries: HashMap<NodeHandle, Entry>,
+    cells: HashMap<Cell, Vec<NodeHandle>>,
+    oversized: HashSet<NodeHandle>,
+    stamp: u64,
+}
+
+impl SpatialIndex {
+    /// Creates an empty index with cells of the given size.
+    ///
+    /// The cells should be about the size of the typical object of the scene.
+    pub fn new(cell_size: f32) -> SpatialIndex {
+        assert!(cell_size > 0.0, "The cell size must be positive.");
+
+        SpatialIndex {
+            cell_size,
+            entries: HashMap::new(),
+            cells: HashMap::new(),
+            oversized: HashSet::new(),
+            stamp: 0,
+        }
+    }
+
+    /// The size of the cells of this index.
+    #[inline]
+    pub fn cell_size(&self) -> f32 {
+        self.cell_size
+    }
+
+    /// Sets the size of the cells of this index, emptying it until the next `update`.
+    pub fn set_cell_size(&mut self, cell_size: f32) {
+        assert!(cell_size > 0.0, "The cell size must be positive.");
+
+        self.cell_size = cell_size;
+        self.clear();
+    }
+
+    /// The number of nodes indexed.
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Whether no node is indexed.
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Removes all the nodes from this index.
+    pub fn clear(&mut self) {
+        self.entries.clear();
+        self.cells.clear();
+        self.oversized.clear();
+    }
+
+    /// Indexes the objects of the scene graph rooted by `root`, removing the nodes that are no
+    /// longer part of it.
+    ///
+    /// With the `parallel` feature, the bounding boxes are computed on multiple threads.
+    pub fn update(&mut self, root: &SceneNode) {
+        self.stamp += 1;
+
+        #[cfg(not(feature = "parallel"))]
+        self.update_subtree(root, true);
+
+        #[cfg(feature = "parallel")]
+        self.update_subtree_par(root);
+
+        let stamp = self.stamp;
+        let removed: Vec<NodeHandle> = self
+            .entries
+            .iter()
+            .filter(|(_, e)| e.stamp != stamp)
+            .map(|(h, _)| *h)
+            .collect();
+
+        for handle in removed {
+            self.remove(handle);
+        }
+    }
+
+    /// The world-space bounding box of an indexed node.
+    pub fn aabb(&self, node: NodeHandle) -> Option<&AABB<f32>> {
+        self.entries.get(&node).map(|e| &e.aabb)
+    }
+
+    /// The nodes whose bounding box intersects the given world-space box.
+    pub fn nodes_in_aabb(&self, aabb: &AABB<f32>) -> Vec<SceneNode> {
+        self.query(aabb, |e| e.aabb.intersects(aabb))
+    }
+
+    /// The nodes whose bounding box intersects the given world-space sphere.
+    pub fn nodes_in_sphere(&self, center: &Point3<f32>, radius: f32) -> Vec<SceneNode> {
+        let half_extents = Vector3::from_element(radius);
+        let aabb = AABB::new(center - half_extents, center + half_extents);
+
+        self.query(&aabb, |e| {
+            let closest = center.sup(&e.aabb.mins).inf(&e.aabb.maxs);
+            na::distance_squared(center, &closest) <= radius * radius
+        })
+    }
+
+    /// The nodes whose bounding box intersects the view frustum of the camera.
+    ///
+    /// The test is conservative: a box close to a corner of the frustum may be reported even if
+    /// it is entirely outside. If the far plane of the camera is at infinity, the frustum is cut
+    /// at the depth given by `Camera::depth_range`.
+    pub fn nodes_in_frustum(&self, camera: &dyn Camera) -> Vec<SceneNode> {
+        let transform = camera.transformation();
+        let inv = camera.inverse_transformation();
+        let (near, far) = camera.depth_range();
+        let mut mins = Point3::from(Vector3::from_element(f32::MAX));
+        let mut maxs = Point3::from(Vector3::from_element(f32::MIN));
+        let ndc_mins = Point3::new(-1.0, -1.0, near.min(far));
+        let ndc_maxs = Point3::new(1.0, 1.0, near.max(far));
+
+        for corner in &box_corners(&ndc_mins, &ndc_maxs) {
+            let pt = inv * corner.to_homogeneous();
+            let pt = Point3::from(pt.xyz() / pt.w);
+            mins = mins.inf(&pt);
+            maxs = maxs.sup(&pt);
+        }
+
+        self.query(&AABB::new(mins, maxs), |e| {
+            aabb_in_frustum(&e.aabb, &transform)
+        })
+    }
+
+    // The pickable nodes whose bounding box intersects the given world-space box.
+    pub(crate) fn pickable_nodes_in_aabb(&self, aabb: &AABB<f32>) -> Vec<SceneNode> {
+        self.query(aabb, |e| e.pickable && e.aabb.intersects(aabb))
+    }
+
+    #[cfg(not(feature = "parallel"))]
+    fn update_subtree(&mut self, node: &SceneNode, pickable: bool) {
+        let data = node.data();
+        let pickable = pickable && data.is_pickable();
+        let aabb = local_aabb(&data).map(|local| world_aabb(&local));
+
+        match aabb {
+            Some(aabb) => self.insert(node.handle(), aabb, pickable),
+            None => self.remove(node.handle()),
+        }
+
+        for c in data.children() {
+            self.update_subtree(c, pickable);
+        }
+    }
+
+    // Same as `update_subtree` on the whole scene graph, except that the world bounding boxes
+    // are computed on multiple threads.
+    #[cfg(feature = "parallel")]
+    fn update_subtree_par(&mut self, root: &SceneNode) {
+        use rayon::prelude::*;
+
+        let mut nodes = Vec::new();
+        flatten(root, true, &mut nodes);
+
+        let aabbs: Vec<_> = nodes
+            .par_iter()
+            .map(|(_, local, _)| local.as_ref().map(world_aabb))
+            .collect();
+
+        for ((handle, _, pickable), aabb) in nodes.into_iter().zip(aabbs) {
+            match aabb {
+                Some(aabb) => self.insert(handle, aabb, pickable),
+                None => self.remove(handle),
+            }
+        }
+    }
+
+    fn insert(&mut self, handle: NodeHandle, aabb: AABB<f32>, pickable: bool) {
+        let stamp = self.stamp;
+
+        if let Some(entry) = self.entries.get_mut(&handle) {
+            entry.pickable = pickable;
+            entry.stamp = stamp;
+
+            if entry.aabb == aabb {
+                return;
+            }
+        }
+
+        self.remove(handle);
+
+        let cells = self.cell_range(&aabb);
+
+        match cells {
+            Some((mins, maxs)) => {
+                for cell in cell_iter(mins, maxs) {
+                    self.cells.entry(cell).or_default().push(handle);
+                }
+            }
+            None => {
+                let _ = self.oversized.insert(handle);
+            }
+        }
+
+        let entry = Entry {
+            aabb,
+            cells,
+            pickable,
+            stamp,
+        };
+        let _ = self.entries.insert(handle, entry);
+    }
+
+    fn remove(&mut self, handle: NodeHandle) {
+        match self.entries.remove(&handle).map(|e| e.cells) {
+            Some(Some((mins, maxs))) => {
+                for cell in cell_iter(mins, maxs) {
+                    if let Some(nodes) = self.cells.get_mut(&cell) {
+                        nodes.retain(|h| *h != handle);
+
+                        if nodes.is_empty() {
+                            let _ = self.cells.remove(&cell);
+                        }
+                    }
+                }
+            }
+            Some(None) => {
+                let _ = self.oversized.remove(&handle);
+            }
+            None => {}
+        }
+    }
+
+    // The range of cells overlapped by a box, or `None` if there are too many of them.
+    fn cell_range(&self, aabb: &AABB<f32>) -> Option<(Cell, Cell)> {
+        let cell = |x: f32| {
+            (x / self.cell_size)
+                .floor()
+                .max(i32::MIN as f32)
+                .min(i32::MAX as f32)
+        };
+        let mins = aabb.mins.map(cell);
+        let maxs = aabb.maxs.map(cell);
+        let count = (maxs - mins).iter().fold(1.0, |n, d| n * (d + 1.0));
+
+        if count > MAX_CELLS_PER_NODE {
+            return None;
+        }
+
+        Some((
+            (mins.x as i32, mins.y as i32, mins.z as i32),
+            (maxs.x as i32, maxs.y as i32, maxs.z as i32),
+        ))
+    }
+
+    // The nodes overlapping the cells of `aabb` that pass the given test.
+    fn query(&self, aabb: &AABB<f32>, mut test: impl FnMut(&Entry) -> bool) -> Vec<SceneNode> {
+        let cell = |x: f32| (x / self.cell_size).floor();
+        let mins = aabb.mins.map(cell);
+        let maxs = aabb.maxs.map(cell);
+        let count = (maxs - mins).iter().fold(1.0, |n, d| n * (d + 1.0));
+        let mut seen = HashSet::new();
+        let mut result = Vec::new();
+
+        let mut visit = |handle: &NodeHandle| {
+            if seen.insert(*handle) && test(&self.entries[handle]) {
+                result.extend(SceneNode::from_handle(*handle));
+            }
+        };
+
+        // Large regions are tested against the occupied cells instead of being enumerated.
+        if count > self.cells.len() as f32 {
+            let contains = |c: &Cell| {
+                let c = Point3::new(c.0 as f32, c.1 as f32, c.2 as f32);
+                (0..3).all(|i| c[i] >= mins[i] && c[i] <= maxs[i])
+            };
+
+            for (_, nodes) in self.cells.iter().filter(|(c, _)| contains(c)) {
+                nodes.iter().for_each(&mut visit);
+            }
+        } else {
+            let to_cell = |p: Point3<f32>| (p.x as i32, p.y as i32, p.z as i32);
+
+            for cell in cell_iter(to_cell(mins), to_cell(maxs)) {
+                if let Some(nodes) = self.cells.get(&cell) {
+                    nodes.iter().for_each(&mut visit);
+                }
+            }
+        }
+
+        self.oversized.iter().for_each(&mut visit);
+        result
+    }
+}
+
+fn cell_iter(mins: Cell, maxs: Cell) -> impl Iterator<Item = Cell> {
+    (mins.0..=maxs.0).flat_map(move |x| {
+        (mins.1..=maxs.1).flat_map(move |y| (mins.2..=maxs.2).map(move |z| (x, y, z)))
+    })
+}
+
+fn box_corners(mins: &Point3<f32>, maxs: &Point3<f32>) -> [Point3<f32>; 8] {
+    let mut corners = [*mins; 8];
+
+    for (i, corner) in corners.iter_mut().enumerate() {
+        for k in 0..3 {
+            if i & (1 << k) != 0 {
+                corner[k] = maxs[k];
+            }
+        }
+    }
+
+    corners
+}
+
+// Whether a box may intersect the view frustum, `transform` mapping it to clip-space.
+//
+// The test is conservative: a box close to a corner of the frustum may be reported even if it is
+// entirely outside.
+pub(crate) fn aabb_in_frustum(aabb: &AABB<f32>, transform: &Matrix4<f32>) -> bool {
+    let clip = box_corners(&aabb.mins, &aabb.maxs).map(|c| transform * c.to_homogeneous());
+
+    // The box is outside if all its corners are outside of the same clipping plane.
+    (0..3).all(|i| !clip.iter().all(|c| c[i] < -c.w) && !clip.iter().all(|c| c[i] > c.w))
+}
+
+// The bounding box of the triangles of the object of a node, with the world transformation and
+// scale of the node.
+fn local_aabb(data: &SceneNodeData) -> Option<LocalAabb> {
+    let local = data.object()?.mesh().borrow().bvh()?.aabb()?;
+    Some((local, data.world_transformation(), data.world_scale()))
+}
+
+// Lists the nodes of the scene graph rooted by `node`, with their local bounding box and whether
+// they are pickable.
+#[cfg(feature = "parallel")]
+fn flatten(node: &SceneNode, pickable: bool, out: &mut Vec<(NodeHandle, Option<LocalAabb>, bool)>) {
+    let data = node.data();
+    let pickable = pickable && data.is_pickable();
+    out.push((node.handle(), local_aabb(&data), pickable));
+
+    for c in data.children() {
+        flatten(c, pickable, out);
+    }
+}
+
+// The world-space bounding box of a local bounding box.
+fn world_aabb((local, transform, scale): &LocalAabb) -> AABB<f32> {
+    let mut mins = Point3::from(Vector3::from_element(f32::MAX));
+    let mut maxs = Point3::from(Vector3::from_element(f32::MIN));
+
+    for corner in &box_corners(&local.mins, &local.maxs) {
+        let pt = transform * Point3::from(corner.coords.component_mul(scale));
+        mins = mins.inf(&pt);
+        maxs = maxs.sup(&pt);
+    }
+
+    AABB::new(mins, maxs)
+}
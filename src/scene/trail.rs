@@ -0,0 +1,93 @@
+//! Trails following the motion of scene nodes.
+
+use std::cell::RefCell;
+use std::collections::VecDeque;
+
+use na::Point3;
+
+use crate::color::Color;
+use crate::scene::{NodeHandle, SceneNode};
+
+// The nodes having a trail.
+thread_local!(static TRAILED: RefCell<Vec<NodeHandle>> = const { RefCell::new(Vec::new()) });
+
+// The last world positions of a node.
+pub(crate) struct Trail {
+    // The recorded positions, from the oldest to the most recent.
+    pub points: VecDeque<Point3<f32>>,
+    pub max_points: usize,
+    pub width: f32,
+    pub fade: bool,
+    pub color: Color,
+}
+
+impl Trail {
+    pub fn new(max_points: usize, width: f32, fade: bool, color: Color) -> Trail {
+        Trail {
+            points: VecDeque::with_capacity(max_points),
+            max_points,
+            width,
+            fade,
+            color,
+        }
+    }
+
+    fn record(&mut self, pt: Point3<f32>) {
+        if self.points.back() == Some(&pt) {
+            return;
+        }
+
+        while self.points.len() >= self.max_points.max(1) {
+            let _ = self.points.pop_front();
+        }
+
+        self.points.push_back(pt);
+    }
+}
+
+// Records the world positions of the nodes having a trail. Called once per frame.
+pub(crate) fn record() {
+    TRAILED.with(|t| {
+        t.borrow_mut()
+            .retain(|handle| match SceneNode::from_handle(*handle) {
+                Some(mut node) => {
+                    let mut data = node.data_mut();
+                    let pt = Point3::from(data.world_transformation().translation.vector);
+
+                    match data.trail_mut() {
+                        Some(trail) => {
+                            trail.record(pt);
+                            true
+                        }
+                        None => false,
+                    }
+                }
+                None => false,
+            })
+    })
+}
+
+// Applies a closure to the trails of the visible nodes.
+pub(crate) fn for_each(mut f: impl FnMut(&Trail)) {
+    TRAILED.with(|t| {
+        for handle in t.borrow().iter() {
+            if let Some(node) = SceneNode::from_handle(*handle) {
+                let data = node.data();
+
+                if let (true, Some(trail)) = (data.is_visible(), data.trail()) {
+                    f(trail)
+                }
+            }
+        }
+    })
+}
+
+pub(crate) fn register(handle: NodeHandle) {
+    TRAILED.with(|t| {
+        let mut trailed = t.borrow_mut();
+
+        if !trailed.contains(&handle) {
+            trailed.push(handle)
+        }
+    })
+}
@@ -0,0 +1,292 @@
+//! Validation of the draw state of the objects, for debugging.
+
+use na::{Isometry3, Vector3};
+use std::cell::RefCell;
+use std::collections::HashSet;
+use std::fmt;
+
+use crate::context::Context;
+use crate::scene::{NodeHandle, Object};
+
+/// A problem preventing an object from being rendered correctly.
+#[derive(Clone, Debug, PartialEq)]
+pub enum ValidationIssue {
+    /// The world transformation of the object contains NaN or infinite values.
+    NonFiniteTransform,
+    /// The world scale of the object contains NaN or infinite values.
+    NonFiniteScale,
+    /// A component of the world scale of the object is zero.
+    ZeroScale,
+    /// The mesh of the object has no vertex or no face.
+    EmptyMesh,
+    /// Some vertices of the mesh contain NaN or infinite values.
+    NonFiniteVertices(usize),
+    /// A face of the mesh references a vertex that does not exist.
+    FaceIndexOutOfBounds {
+        /// The index of the face.
+        face: usize,
+        /// The invalid vertex index.
+        index: usize,
+        /// The number of vertices of the mesh.
+        num_vertices: usize,
+    },
+    /// All the triangles of the mesh have a zero area.
+    DegenerateTriangles(usize),
+    /// A vertex attribute of the mesh does not have one value per vertex.
+    AttributeLength {
+        /// The name of the attribute.
+        attribute: &'static str,
+        /// The number of values of the attribute.
+        len: usize,
+        /// The number of vertices of the mesh.
+        num_vertices: usize,
+    },
+    /// The texture of the object has been deleted, or never been configured.
+    InvalidTexture,
+}
+
+impl fmt::Display for ValidationIssue {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            ValidationIssue::NonFiniteTransform => write!(
+                f,
+                "the world transformation contains NaN or infinite values; check the \
+                 transformations of this node and its parents"
+            ),
+            ValidationIssue::NonFiniteScale => write!(
+                f,
+                "the world scale contains NaN or infinite values; check the scales of this node \
+                 and its parents"
+            ),
+            ValidationIssue::ZeroScale => write!(
+                f,
+                "a component of the world scale is zero, so the object is flattened; hide it \
+                 with `set_visible(false)` instead"
+            ),
+            ValidationIssue::EmptyMesh => write!(f, "the mesh has no vertex or no face"),
+            ValidationIssue::NonFiniteVertices(n) => {
+                write!(f, "{} vertices contain NaN or infinite values", n)
+            }
+            ValidationIssue::FaceIndexOutOfBounds {
+                face,
+                index,
+                num_vertices,
+            } => write!(
+                f,
+                "face {} references the vertex {}, but the mesh has only {} vertices",
+                face, index, num_vertices
+            ),
+            ValidationIssue::DegenerateTriangles(n) => write!(
+                f,
+                "all the {} triangles of the mesh have a zero area, so nothing is rendered",
+                n
+            ),
+            ValidationIssue::AttributeLength {
+                attribute,
+                len,
+                num_vertices,
+            } => write!(
+                f,
+                "the mesh has {} {} for {} vertices; provide one per vertex or none",
+                len, attribute, num_vertices
+            ),
+            ValidationIssue::InvalidTexture => write!(
+                f,
+                "the texture is not a valid GPU texture; textures created with \
+                 `TextureManager::add_empty` must be configured before use"
+            ),
+        }
+    }
+}
+
+/// A problem found on an object by the debug validation layer.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ValidationError {
+    /// The handle of the node containing the object.
+    pub node: NodeHandle,
+    /// The name of the node containing the object, if it has one.
+    pub name: Option<String>,
+    /// The problem found.
+    pub issue: ValidationIssue,
+}
+
+impl fmt::Display for ValidationError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.name {
+            Some(ref name) => write!(f, "node `{}`: {}", name, self.issue),
+            None => write!(
+                f,
+                "unnamed node #{} (name it with `SceneNode::set_name`): {}",
+                self.node.index(),
+                self.issue
+            ),
+        }
+    }
+}
+
+#[derive(Default)]
+struct ValidationLayer {
+    enabled: bool,
+    // The errors already reported, so that each one is reported once.
+    reported: HashSet<(NodeHandle, String)>,
+    errors: Vec<ValidationError>,
+}
+
+thread_local!(static VALIDATION_LAYER: RefCell<ValidationLayer> = RefCell::new(ValidationLayer::default()));
+
+pub(crate) fn is_enabled() -> bool {
+    VALIDATION_LAYER.with(|layer| layer.borrow().enabled)
+}
+
+pub(crate) fn set_enabled(enabled: bool) {
+    VALIDATION_LAYER.with(|layer| {
+        let mut layer = layer.borrow_mut();
+        layer.enabled = enabled;
+        layer.reported.clear();
+    })
+}
+
+pub(crate) fn take_errors() -> Vec<ValidationError> {
+    VALIDATION_LAYER.with(|layer| std::mem::take(&mut layer.borrow_mut().errors))
+}
+
+// Validates an object about to be drawn, printing the problems found for the first time.
+pub(crate) fn validate(
+    node: NodeHandle,
+    name: Option<&str>,
+    object: &Object,
+    transform: &Isometry3<f32>,
+    scale: &Vector3<f32>,
+) {
+    let issues = object_issues(object, transform, scale);
+
+    if issues.is_empty() {
+        return;
+    }
+
+    VALIDATION_LAYER.with(|layer| {
+        let mut layer = layer.borrow_mut();
+
+        for issue in issues {
+            let error = ValidationError {
+                node,
+                name: name.map(|n| n.to_string()),
+                issue,
+            };
+
+            if layer.reported.insert((node, error.issue.to_string())) {
+                println!("Warning: {}", error);
+                layer.errors.push(error);
+            }
+        }
+    })
+}
+
+fn object_issues(
+    object: &Object,
+    transform: &Isometry3<f32>,
+    scale: &Vector3<f32>,
+) -> Vec<ValidationIssue> {
+    let mut issues = Vec::new();
+
+    let finite_transform = transform.translation.vector.iter().all(|e| e.is_finite())
+        && transform.rotation.coords.iter().all(|e| e.is_finite());
+
+    if !finite_transform {
+        issues.push(ValidationIssue::NonFiniteTransform);
+    }
+
+    if !scale.iter().all(|e| e.is_finite()) {
+        issues.push(ValidationIssue::NonFiniteScale);
+    } else if scale.iter().any(|e| *e == 0.0) {
+        issues.push(ValidationIssue::ZeroScale);
+    }
+
+    if !Context::get().is_texture(Some(&**object.data().texture())) {
+        issues.push(ValidationIssue::InvalidTexture);
+    }
+
+    let mesh = object.mesh().borrow();
+    let coords = mesh.coords().read().unwrap();
+    let faces = mesh.faces().read().unwrap();
+
+    // Meshes unloaded from the RAM cannot be validated.
+    let (coords, faces) = match (coords.data(), faces.data()) {
+        (Some(coords), Some(faces)) => (coords, faces),
+        _ => return issues,
+    };
+
+    if coords.is_empty() || faces.is_empty() {
+        issues.push(ValidationIssue::EmptyMesh);
+        return issues;
+    }
+
+    let non_finite = coords
+        .iter()
+        .filter(|pt| !pt.iter().all(|e| e.is_finite()))
+        .count();
+
+    if non_finite != 0 {
+        issues.push(ValidationIssue::NonFiniteVertices(non_finite));
+    }
+
+    let num_vertices = coords.len();
+    let mut degenerate = 0;
+
+    for (i, face) in faces.iter().enumerate() {
+        if let Some(index) = face
+            .iter()
+            .map(|e| *e as usize)
+            .find(|e| *e >= num_vertices)
+        {
+            issues.push(ValidationIssue::FaceIndexOutOfBounds {
+                face: i,
+                index,
+                num_vertices,
+            });
+            return issues;
+        }
+
+        let a = coords[face.x as usize];
+        let e1 = coords[face.y as usize] - a;
+        let e2 = coords[face.z as usize] - a;
+
+        if e1.cross(&e2).norm_squared() <= 1.0e-12 * e1.norm_squared() * e2.norm_squared() {
+            degenerate += 1;
+        }
+    }
+
+    if degenerate == faces.len() {
+        issues.push(ValidationIssue::DegenerateTriangles(degenerate));
+    }
+
+    let attributes = [
+        (
+            "normals",
+            mesh.normals()
+                .read()
+                .unwrap()
+                .data()
+                .as_ref()
+                .map(|e| e.len()),
+        ),
+        (
+            "texture coordinates",
+            mesh.uvs().read().unwrap().data().as_ref().map(|e| e.len()),
+        ),
+    ];
+
+    for (attribute, len) in attributes.iter() {
+        if let Some(len) = *len {
+            if len != 0 && len != num_vertices {
+                issues.push(ValidationIssue::AttributeLength {
+                    attribute,
+                    len,
+                    num_vertices,
+                });
+            }
+        }
+    }
+
+    issues
+}
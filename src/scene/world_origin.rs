@@ -0,0 +1,56 @@
+//! The origin of the world coordinates used for rendering.
+//!
+//! Positions are stored on the GPU with single precision, which is not enough for large scenes:
+//! an `f32` has a precision of about one meter at a distance of 10 000 km from the origin. Scene
+//! nodes and cameras can instead be positioned with double precision, relative to a world origin
+//! that is kept close to the camera. Everything is then rendered relative to that origin, where
+//! single precision is sufficient.
+
+use na::{Point3, Vector3};
+use std::cell::Cell;
+
+thread_local!(static WORLD_ORIGIN: Cell<(Point3<f64>, u64)> = Cell::new((Point3::origin(), 0)));
+
+/// The current world origin, in double-precision world coordinates.
+pub fn world_origin() -> Point3<f64> {
+    WORLD_ORIGIN.with(|o| o.get().0)
+}
+
+/// Moves the world origin.
+///
+/// Everything is rendered relative to the world origin with single precision, so it should be
+/// kept close to the camera when the scene spans large distances.
+///
+/// The nodes positioned with `SceneNode::set_local_translation_f64` are moved accordingly, but
+/// the cameras are not: use `Window::set_world_origin` to move the default camera as well, or
+/// `Camera::shift_origin` for other cameras.
+pub fn set_world_origin(origin: Point3<f64>) {
+    WORLD_ORIGIN.with(|o| {
+        let (current, generation) = o.get();
+
+        if current != origin {
+            o.set((origin, generation + 1))
+        }
+    })
+}
+
+/// Converts a point from double-precision world coordinates to the single-precision coordinates
+/// used for rendering.
+pub fn to_render_coords(pt: &Point3<f64>) -> Point3<f32> {
+    Point3::from(to_render_vector(&pt.coords))
+}
+
+/// Converts a point from the single-precision coordinates used for rendering to double-precision
+/// world coordinates.
+pub fn to_world_coords(pt: &Point3<f32>) -> Point3<f64> {
+    world_origin() + pt.coords.cast::<f64>()
+}
+
+pub(crate) fn to_render_vector(v: &Vector3<f64>) -> Vector3<f32> {
+    (v - world_origin().coords).cast::<f32>()
+}
+
+// Incremented each time the world origin moves.
+pub(crate) fn generation() -> u64 {
+    WORLD_ORIGIN.with(|o| o.get().1)
+}
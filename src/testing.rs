@@ -0,0 +1,283 @@
+//! Utilities for regression tests of the rendering output.
+//!
+//! A scene is rendered in a hidden window with `render_once`, and the resulting image is compared
+//! to a reference image, the golden image, stored alongside the tests:
+//!
+//! ```no_run
+//! use kiss3d::testing::{self, Tolerance};
+//!
+//! let image = testing::render_once(|window| {
+//!     let mut cube = window.add_cube(0.2, 0.2, 0.2);
+//!     cube.set_color((1.0, 0.0, 0.0));
+//! });
+//!
+//! testing::assert_matches_golden(&image, "tests/golden/red_cube.png", &Tolerance::default());
+//! ```
+//!
+//! Missing golden images are created by `assert_matches_golden`, and existing ones are replaced
+//! when the `KISS3D_UPDATE_GOLDEN` environment variable is set.
+//!
+//! The images are compared with a perceptual metric, so that the small color differences
+//! between GPUs and drivers can be tolerated without missing visible changes. Since all windows
+//! share the same OpenGL context, tests rendering images must not run in parallel, e.g., by
+//! running them with `cargo test -- --test-threads=1`.
+
+use image::{ImageBuffer, Rgb, RgbImage};
+use na::Point3;
+use std::env;
+use std::fmt;
+use std::path::{Path, PathBuf};
+
+use crate::camera::{ArcBall, Camera};
+use crate::window::Window;
+
+/// The width of the images rendered by `render_once`.
+pub const DEFAULT_WIDTH: u32 = 320;
+/// The height of the images rendered by `render_once`.
+pub const DEFAULT_HEIGHT: u32 = 240;
+
+/// The environment variable forcing `assert_matches_golden` to overwrite the golden images.
+pub const UPDATE_GOLDEN_VAR: &str = "KISS3D_UPDATE_GOLDEN";
+
+/// Renders a single frame of a scene in a hidden window, and returns the rendered image.
+///
+/// The scene is set up by `setup`, and seen by an `ArcBall` camera looking at the origin from
+/// the point `(0.0, 0.0, -1.0)`. The image has a size of `DEFAULT_WIDTH` by `DEFAULT_HEIGHT`
+/// pixels.
+pub fn render_once<F: FnOnce(&mut Window)>(setup: F) -> RgbImage {
+    let mut camera = ArcBall::new(Point3::new(0.0, 0.0, -1.0), Point3::origin());
+    render_once_with_camera(DEFAULT_WIDTH, DEFAULT_HEIGHT, &mut camera, setup)
+}
+
+/// Renders a single frame of a scene in a hidden window with a custom size and camera.
+pub fn render_once_with_camera<F: FnOnce(&mut Window)>(
+    width: u32,
+    height: u32,
+    camera: &mut dyn Camera,
+    setup: F,
+) -> RgbImage {
+    let mut window = Window::new_hidden_with_size("kiss3d test", width, height);
+    setup(&mut window);
+    let _ = window.render_with_camera(camera);
+    let image = window.snap_image();
+    window.close();
+    image
+}
+
+/// The thresholds under which two images are considered identical.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Tolerance {
+    /// The perceptual difference above which two pixels are considered different, between
+    /// `0.0` (identical colors) and `1.0` (black and white).
+    pub pixel_threshold: f32,
+    /// The fraction of pixels allowed to be different, between `0.0` and `1.0`.
+    pub max_different_pixels: f32,
+}
+
+impl Default for Tolerance {
+    fn default() -> Self {
+        Tolerance {
+            pixel_threshold: 0.02,
+            max_different_pixels: 0.001,
+        }
+    }
+}
+
+impl Tolerance {
+    /// A tolerance requiring the two images to be exactly identical.
+    pub fn exact() -> Self {
+        Tolerance {
+            pixel_threshold: 0.0,
+            max_different_pixels: 0.0,
+        }
+    }
+}
+
+/// The differences between two images of the same size.
+#[derive(Clone, Debug)]
+pub struct ImageDiff {
+    /// The number of pixels with a perceptual difference above the threshold.
+    pub num_different_pixels: usize,
+    /// The total number of pixels of the images.
+    pub num_pixels: usize,
+    /// The largest perceptual difference between two pixels, between `0.0` and `1.0`.
+    pub max_difference: f32,
+    /// The average perceptual difference between the pixels of both images.
+    pub mean_difference: f32,
+    /// A faded grayscale version of the first image, with the different pixels in red.
+    pub diff_image: RgbImage,
+}
+
+impl ImageDiff {
+    /// The fraction of pixels that are different, between `0.0` and `1.0`.
+    pub fn different_fraction(&self) -> f32 {
+        if self.num_pixels == 0 {
+            0.0
+        } else {
+            self.num_different_pixels as f32 / self.num_pixels as f32
+        }
+    }
+
+    /// Whether the images are considered identical with the given tolerance.
+    pub fn is_within(&self, tolerance: &Tolerance) -> bool {
+        self.different_fraction() <= tolerance.max_different_pixels
+    }
+}
+
+impl fmt::Display for ImageDiff {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{} of {} pixels differ ({:.3}%), max difference: {:.4}, mean difference: {:.4}",
+            self.num_different_pixels,
+            self.num_pixels,
+            self.different_fraction() * 100.0,
+            self.max_difference,
+            self.mean_difference
+        )
+    }
+}
+
+/// The perceptual difference between two colors, between `0.0` (identical) and `1.0` (black
+/// and white).
+///
+/// The difference is the distance between the colors in the YIQ color space, weighted to
+/// account for the higher sensitivity of the eye to brightness than to hue.
+pub fn color_difference(a: &Rgb<u8>, b: &Rgb<u8>) -> f32 {
+    // Weights and maximum distance from "Measuring perceived color difference using YIQ NTSC
+    // transmission color space in mobile applications", Kotsarenko & Ramos, 2010.
+    const MAX_DELTA: f32 = 35215.0;
+
+    let (ya, ia, qa) = rgb_to_yiq(a);
+    let (yb, ib, qb) = rgb_to_yiq(b);
+    let (dy, di, dq) = (ya - yb, ia - ib, qa - qb);
+    let delta = 0.5053 * dy * dy + 0.299 * di * di + 0.1957 * dq * dq;
+
+    (delta / MAX_DELTA).sqrt().min(1.0)
+}
+
+fn rgb_to_yiq(c: &Rgb<u8>) -> (f32, f32, f32) {
+    let (r, g, b) = (c[0] as f32, c[1] as f32, c[2] as f32);
+    let y = 0.298_895_3 * r + 0.586_622_5 * g + 0.114_482_23 * b;
+    let i = 0.595_978 * r - 0.274_176_1 * g - 0.321_801_9 * b;
+    let q = 0.211_470_17 * r - 0.522_617_1 * g + 0.311_146_94 * b;
+    (y, i, q)
+}
+
+/// Compares two images pixel by pixel.
+///
+/// Returns `None` if the images do not have the same size.
+pub fn diff_images(a: &RgbImage, b: &RgbImage, pixel_threshold: f32) -> Option<ImageDiff> {
+    if a.dimensions() != b.dimensions() {
+        return None;
+    }
+
+    let mut diff_image = ImageBuffer::new(a.width(), a.height());
+    let mut num_different_pixels = 0;
+    let mut max_difference = 0.0f32;
+    let mut total_difference = 0.0f64;
+
+    for (x, y, pa) in a.enumerate_pixels() {
+        let pb = b.get_pixel(x, y);
+        let difference = color_difference(pa, pb);
+
+        max_difference = max_difference.max(difference);
+        total_difference += difference as f64;
+
+        let pixel = if difference > pixel_threshold {
+            num_different_pixels += 1;
+            Rgb([255, 0, 0])
+        } else {
+            let (luma, _, _) = rgb_to_yiq(pa);
+            let faded = (255.0 - (255.0 - luma) * 0.1) as u8;
+            Rgb([faded, faded, faded])
+        };
+
+        diff_image.put_pixel(x, y, pixel);
+    }
+
+    let num_pixels = (a.width() * a.height()) as usize;
+    let mean_difference = if num_pixels == 0 {
+        0.0
+    } else {
+        (total_difference / num_pixels as f64) as f32
+    };
+
+    Some(ImageDiff {
+        num_different_pixels,
+        num_pixels,
+        max_difference,
+        mean_difference,
+        diff_image,
+    })
+}
+
+/// Checks that an image matches the golden image stored at `path`.
+///
+/// If the golden image does not exist, or if the `KISS3D_UPDATE_GOLDEN` environment variable is
+/// set, the image is saved as the new golden image instead. Otherwise, on mismatch, the image and
+/// the differences are saved next to the golden image, with the `.actual.png` and `.diff.png`
+/// extensions, and an error describing the differences is returned.
+pub fn check_golden<P: AsRef<Path>>(
+    image: &RgbImage,
+    path: P,
+    tolerance: &Tolerance,
+) -> Result<(), String> {
+    let path = path.as_ref();
+
+    if env::var_os(UPDATE_GOLDEN_VAR).is_some() || !path.exists() {
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir)
+                .map_err(|e| format!("unable to create {}: {}", dir.display(), e))?;
+        }
+
+        return image
+            .save(path)
+            .map_err(|e| format!("unable to save {}: {}", path.display(), e));
+    }
+
+    let golden = image::open(path)
+        .map_err(|e| format!("unable to open {}: {}", path.display(), e))?
+        .into_rgb8();
+    let actual_path = with_suffix(path, "actual");
+    let diff_path = with_suffix(path, "diff");
+
+    let failure = match diff_images(&golden, image, tolerance.pixel_threshold) {
+        Some(diff) if diff.is_within(tolerance) => return Ok(()),
+        Some(diff) => {
+            let _ = diff.diff_image.save(&diff_path);
+            format!(
+                "{} does not match: {}. See {} and {}.",
+                path.display(),
+                diff,
+                actual_path.display(),
+                diff_path.display()
+            )
+        }
+        None => format!(
+            "{} has a size of {:?}, but the image has a size of {:?}. See {}.",
+            path.display(),
+            golden.dimensions(),
+            image.dimensions(),
+            actual_path.display()
+        ),
+    };
+
+    let _ = image.save(&actual_path);
+    Err(failure)
+}
+
+/// Panics if an image does not match the golden image stored at `path`.
+///
+/// See `check_golden` for details.
+pub fn assert_matches_golden<P: AsRef<Path>>(image: &RgbImage, path: P, tolerance: &Tolerance) {
+    if let Err(e) = check_golden(image, path, tolerance) {
+        panic!("{}", e)
+    }
+}
+
+// `dir/name.png` becomes `dir/name.<suffix>.png`.
+fn with_suffix(path: &Path, suffix: &str) -> PathBuf {
+    let stem = path.file_stem().unwrap_or_default().to_string_lossy();
+    path.with_file_name(format!("{}.{}.png", stem, suffix))
+}
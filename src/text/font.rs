@@ -1,12 +1,16 @@
 use std::borrow::Borrow;
-use std::fs::File;
-use std::io::Read;
 use std::path::Path;
 use std::rc::Rc;
 use std::sync::Once;
 
+use image::{DynamicImage, Rgba, RgbaImage};
 use rusttype;
 
+use crate::context::Texture;
+use crate::load_error::Error;
+use crate::loader::asset_source;
+use crate::resource::TextureManager;
+
 #[path = "../error.rs"]
 mod error;
 
@@ -17,17 +21,19 @@ pub struct Font {
 
 impl Font {
     /// Loads a new ttf font from a file.
-    pub fn new(path: &Path) -> Option<Rc<Font>> {
-        let mut memory = Vec::new();
-        let mut file = File::open(path).unwrap();
-        let _ = file.read_to_end(&mut memory).unwrap();
+    ///
+    /// Returns an error if the file cannot be read or is not a valid font.
+    pub fn new(path: &Path) -> Result<Rc<Font>, Error> {
+        let memory = asset_source::read(path).map_err(|e| Error::Io(path.to_path_buf(), e))?;
         Font::from_bytes(&memory)
     }
 
     /// Loads a new ttf font from the memory.
-    pub fn from_bytes(memory: &[u8]) -> Option<Rc<Font>> {
-        let font = rusttype::Font::from_bytes(memory.to_vec()).unwrap();
-        Some(Rc::new(Font { font }))
+    ///
+    /// Returns an error if the bytes are not a valid font.
+    pub fn from_bytes(memory: &[u8]) -> Result<Rc<Font>, Error> {
+        let font = rusttype::Font::from_bytes(memory.to_vec())?;
+        Ok(Rc::new(Font { font }))
     }
 
     /// Instanciate a default font.
@@ -52,6 +58,63 @@ impl Font {
         &self.font
     }
 
+    /// Rasterizes a text into an image, on the CPU.
+    ///
+    /// The text is drawn in white on a transparent black background. Each line break starts a
+    /// new line. The image is just large enough to contain the whole text.
+    pub fn render_to_image(&self, text: &str, size: f32) -> DynamicImage {
+        let scale = rusttype::Scale::uniform(size);
+        let vmetrics = self.font.v_metrics(scale);
+        let line_height = vmetrics.ascent - vmetrics.descent + vmetrics.line_gap;
+        let mut glyphs = Vec::new();
+        let mut width = 1.0f32;
+        let mut nlines = 0;
+
+        for (i, line) in text.lines().enumerate() {
+            let orig = rusttype::point(0.0, vmetrics.ascent + line_height * i as f32);
+
+            for glyph in self.font.layout(line, scale, orig) {
+                let right = glyph.position().x + glyph.unpositioned().h_metrics().advance_width;
+                width = width.max(right);
+                glyphs.push(glyph);
+            }
+
+            nlines = i + 1;
+        }
+
+        let height = (line_height * nlines.max(1) as f32).ceil().max(1.0) as u32;
+        let width = width.ceil() as u32;
+        let mut image = RgbaImage::from_pixel(width, height, Rgba([0, 0, 0, 0]));
+
+        for glyph in glyphs {
+            if let Some(bb) = glyph.pixel_bounding_box() {
+                glyph.draw(|x, y, coverage| {
+                    let x = x as i32 + bb.min.x;
+                    let y = y as i32 + bb.min.y;
+
+                    if x >= 0 && y >= 0 && (x as u32) < width && (y as u32) < height {
+                        let pixel = image.get_pixel_mut(x as u32, y as u32);
+                        let v = (coverage * 255.0) as u8;
+                        let v = v.max(pixel[3]);
+                        *pixel = Rgba([v, v, v, v]);
+                    }
+                });
+            }
+        }
+
+        DynamicImage::ImageRgba8(image)
+    }
+
+    /// Rasterizes a text into a texture that can be applied to any object.
+    ///
+    /// See `render_to_image` for details about the content of the texture. The texture is not
+    /// cached: it is owned by the caller and deleted once every reference to it is dropped. It is
+    /// not uploaded again when the context is restored after having been lost.
+    pub fn render_to_texture(&self, text: &str, size: f32) -> Rc<Texture> {
+        let mut image = Some(self.render_to_image(text, size));
+        TextureManager::get_global_manager(|tm| tm.load_image(image.take().unwrap()))
+    }
+
     /// The unique identifier of the specified font instance.
     #[inline]
     pub fn uid(font: &Rc<Font>) -> usize {
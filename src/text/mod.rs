@@ -3,7 +3,9 @@
 pub use crate::text::font::Font;
 pub use crate::text::glyph::Glyph;
 pub use crate::text::renderer::TextRenderer;
+pub use crate::text::span::{FontFamily, TextSpan};
 
 mod font;
 mod glyph;
 mod renderer;
+mod span;
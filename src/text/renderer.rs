@@ -2,39 +2,68 @@
 // available under the BSD-3 licence.
 // It has been modified to work with gl-rs, nalgebra, and rust-freetype
 
-use na::{Point2, Point3, Vector2};
+use na::{Point2, Point3, Point4, Vector2};
 use rusttype;
 use rusttype::gpu_cache::Cache;
+use rusttype::PositionedGlyph;
+use std::hash::Hasher;
 use std::rc::Rc;
 
 use crate::context::{Context, Texture};
 use crate::resource::{AllocationType, BufferType, Effect, GPUVec, ShaderAttribute, ShaderUniform};
-use crate::text::Font;
+use crate::text::{Font, TextSpan};
 
 #[path = "../error.rs"]
 mod error;
 
+// The initial and maximal sizes of the glyph atlas.
+const INITIAL_ATLAS_SIZE: u32 = 1024;
+const MAX_ATLAS_SIZE: u32 = 4096;
+
+#[derive(Clone)]
 struct TextRenderContext {
     len: usize,
     scale: f32,
     color: Point3<f32>,
     pos: Point2<f32>,
     font: Rc<Font>,
+    underline: Option<f32>,
+}
+
+// The texts queued for the next frame.
+#[derive(Default)]
+pub(crate) struct QueuedText {
+    text: String,
+    contexts: Vec<TextRenderContext>,
+}
+
+struct QueuedGlyph {
+    font_uid: usize,
+    glyph: PositionedGlyph<'static>,
+    ascent: f32,
+    color: Point3<f32>,
 }
 
 /// A ttf text renderer.
+///
+/// Glyphs are rasterized once into a glyph atlas kept from one frame to the next, and only the
+/// glyphs missing from the atlas are uploaded to the GPU. All the texts of a frame are then drawn
+/// with a single draw call.
 pub struct TextRenderer {
     text: String,
     texture: Texture,
+    atlas_size: u32,
     shader: Effect,
     cache: Cache<'static>,
     invsz: ShaderUniform<Vector2<f32>>,
     tex: ShaderUniform<i32>,
-    color: ShaderUniform<Point3<f32>>,
-    pos: ShaderAttribute<Point2<f32>>,
-    uvs: ShaderAttribute<Point2<f32>>,
+    pos_uvs: ShaderAttribute<Point4<f32>>,
+    color: ShaderAttribute<Point4<f32>>,
     contexts: Vec<TextRenderContext>,
-    coords: GPUVec<Point2<f32>>,
+    glyphs: Vec<QueuedGlyph>,
+    // Two elements per vertex: the position and texture coordinates, then the color and whether
+    // the texture is ignored.
+    vertices: GPUVec<Point4<f32>>,
 }
 
 impl TextRenderer {
@@ -43,10 +72,8 @@ impl TextRenderer {
         //
         // Create cache.
         //
-        let atlas_width = 1024;
-        let atlas_height = 1024;
         let cache = Cache::builder()
-            .dimensions(atlas_width, atlas_height)
+            .dimensions(INITIAL_ATLAS_SIZE, INITIAL_ATLAS_SIZE)
             .build();
 
         //
@@ -60,60 +87,29 @@ impl TextRenderer {
         let texture = verify!(ctxt
             .create_texture()
             .expect("Font texture creation failed."));
-        verify!(ctxt.bind_texture(Context::TEXTURE_2D, Some(&texture)));
-        verify!(ctxt.tex_image2d(
-            Context::TEXTURE_2D,
-            0,
-            Context::RED as i32,
-            atlas_width as i32,
-            atlas_height as i32,
-            0,
-            Context::RED,
-            None
-        ));
-
-        /* Clamp to the edge to avoid artifacts when scaling. */
-        verify!(ctxt.tex_parameteri(
-            Context::TEXTURE_2D,
-            Context::TEXTURE_WRAP_S,
-            Context::CLAMP_TO_EDGE as i32
-        ));
-        verify!(ctxt.tex_parameteri(
-            Context::TEXTURE_2D,
-            Context::TEXTURE_WRAP_T,
-            Context::CLAMP_TO_EDGE as i32
-        ));
-
-        /* Linear filtering usually looks best for text. */
-        verify!(ctxt.tex_parameteri(
-            Context::TEXTURE_2D,
-            Context::TEXTURE_MIN_FILTER,
-            Context::LINEAR as i32
-        ));
-        verify!(ctxt.tex_parameteri(
-            Context::TEXTURE_2D,
-            Context::TEXTURE_MAG_FILTER,
-            Context::LINEAR as i32
-        ));
+        allocate_atlas(&texture, INITIAL_ATLAS_SIZE);
 
         //
         // Create shader.
         //
-        let mut shader = Effect::new_from_str(TEXT_VERTEX_SRC, TEXT_FRAGMENT_SRC);
+        let mut shader = Effect::new_from_str(TEXT_VERTEX_SRC, TEXT_FRAGMENT_SRC).unwrap();
         shader.use_program();
 
         TextRenderer {
             text: String::new(),
             cache,
             texture,
+            atlas_size: INITIAL_ATLAS_SIZE,
             invsz: shader.get_uniform("invsz").expect("Could not find invsz"),
             tex: shader.get_uniform("tex0").expect("Could not find tex0"),
-            color: shader.get_uniform("color").expect("Could not find color"),
-            pos: shader.get_attrib("pos").expect("Could not find pos"),
-            uvs: shader.get_attrib("uvs").expect("Could not find uvs"),
+            pos_uvs: shader
+                .get_attrib("pos_uvs")
+                .expect("Could not find pos_uvs"),
+            color: shader.get_attrib("color").expect("Could not find color"),
             shader,
             contexts: Vec::new(),
-            coords: GPUVec::new(Vec::new(), BufferType::Array, AllocationType::StreamDraw),
+            glyphs: Vec::new(),
+            vertices: GPUVec::new(Vec::new(), BufferType::Array, AllocationType::StreamDraw),
         }
     }
 
@@ -135,15 +131,147 @@ impl TextRenderer {
             color: *color,
             pos: *pos,
             font: font.clone(),
+            underline: None,
         })
     }
 
+    /// Adds a sequence of styled spans to be drawn during the next frame.
+    ///
+    /// Each span starts where the previous one ended. Line breaks inside of a span move the
+    /// cursor back to `pos.x`, on the next line. Like `draw_text`, this must be called at each
+    /// update loop iteration.
+    pub fn draw_text_spans(&mut self, spans: &[TextSpan], pos: &Point2<f32>, scale: f32) {
+        let rscale = rusttype::Scale::uniform(scale);
+        let mut cursor = *pos;
+        let mut line_height = 0.0f32;
+
+        for span in spans {
+            let vmetrics = span.font.font().v_metrics(rscale);
+
+            for (i, line) in span.text.split('\n').enumerate() {
+                if i != 0 {
+                    cursor.x = pos.x;
+                    cursor.y += line_height;
+                    line_height = 0.0;
+                }
+
+                line_height = line_height.max(vmetrics.ascent - vmetrics.descent);
+
+                if line.is_empty() {
+                    continue;
+                }
+
+                let width = span
+                    .font
+                    .font()
+                    .layout(line, rscale, rusttype::point(0.0, 0.0))
+                    .last()
+                    .map(|g| g.position().x + g.unpositioned().h_metrics().advance_width)
+                    .unwrap_or(0.0);
+
+                self.text.push_str(line);
+                self.contexts.push(TextRenderContext {
+                    len: line.len(),
+                    scale,
+                    color: span.color,
+                    pos: cursor,
+                    font: span.font.clone(),
+                    underline: if span.underline { Some(width) } else { None },
+                });
+
+                cursor.x += width;
+            }
+        }
+    }
+
+    // The texts queued for the next frame.
+    pub(crate) fn queued(&self) -> QueuedText {
+        QueuedText {
+            text: self.text.clone(),
+            contexts: self.contexts.clone(),
+        }
+    }
+
+    // Feeds the texts queued for the next frame to `state`, to detect that they changed.
+    pub(crate) fn hash_queued<H: Hasher>(&self, state: &mut H) {
+        state.write(self.text.as_bytes());
+
+        for context in &self.contexts {
+            state.write_usize(context.len);
+            state.write_usize(Rc::as_ptr(&context.font) as usize);
+
+            let values = [context.scale, context.pos.x, context.pos.y];
+            let colors = [context.color.x, context.color.y, context.color.z];
+
+            for v in values.iter().chain(&colors).chain(&context.underline) {
+                state.write_u32(v.to_bits());
+            }
+        }
+    }
+
+    // Replaces the texts queued for the next frame, e.g., after a frame rendered for a snapshot.
+    pub(crate) fn requeue(&mut self, queued: QueuedText) {
+        self.text = queued.text;
+        self.contexts = queued.contexts;
+    }
+
+    /// The size, in pixels, of the square glyph atlas.
+    ///
+    /// The atlas grows when the glyphs of a single frame do not fit in it.
+    pub fn atlas_size(&self) -> u32 {
+        self.atlas_size
+    }
+
     /// Actually draws the text.
     pub fn render(&mut self, width: f32, height: f32) {
         if self.contexts.is_empty() {
             return;
         }
 
+        self.layout_glyphs();
+        self.cache_glyphs();
+
+        //
+        // Build the vertices of all the glyphs and underlines.
+        //
+        {
+            let vertices = self.vertices.data_mut().as_mut().unwrap();
+            vertices.clear();
+
+            for queued in &self.glyphs {
+                if let Ok(Some((tex, rect))) = self.cache.rect_for(queued.font_uid, &queued.glyph) {
+                    let min = Point2::new(rect.min.x as f32, rect.min.y as f32 + queued.ascent);
+                    let max = Point2::new(rect.max.x as f32, rect.max.y as f32 + queued.ascent);
+                    let uvs_min = Point2::new(tex.min.x, tex.min.y);
+                    let uvs_max = Point2::new(tex.max.x, tex.max.y);
+                    push_quad(vertices, &min, &max, &uvs_min, &uvs_max, &queued.color, 0.0);
+                }
+            }
+
+            for context in &self.contexts {
+                if let Some(width) = context.underline {
+                    let scale = rusttype::Scale::uniform(context.scale);
+                    let ascent = context.font.font().v_metrics(scale).ascent;
+                    let thickness = (context.scale * 0.06).max(1.0);
+                    let min = Point2::new(context.pos.x, context.pos.y + ascent + thickness);
+                    let max = Point2::new(context.pos.x + width, min.y + thickness);
+                    let uvs = Point2::origin();
+                    push_quad(vertices, &min, &max, &uvs, &uvs, &context.color, 1.0);
+                }
+            }
+        }
+
+        self.contexts.clear();
+        self.glyphs.clear();
+        self.text.clear();
+
+        if self.vertices.len() == 0 {
+            return;
+        }
+
+        //
+        // Draw everything at once.
+        //
         let ctxt = Context::get();
         self.shader.use_program();
 
@@ -157,23 +285,28 @@ impl TextRenderer {
         ));
         verify!(ctxt.disable(Context::DEPTH_TEST));
 
-        self.pos.enable();
-        self.uvs.enable();
+        self.pos_uvs.enable();
+        self.color.enable();
         self.tex.upload(&0);
         self.invsz.upload(&Vector2::new(1.0 / width, -1.0 / height));
 
+        verify!(ctxt.active_texture(Context::TEXTURE0));
         verify!(ctxt.bind_texture(Context::TEXTURE_2D, Some(&self.texture)));
-        verify!(ctxt.tex_parameteri(
-            Context::TEXTURE_2D,
-            Context::TEXTURE_WRAP_S,
-            Context::CLAMP_TO_EDGE as i32
-        ));
-        verify!(ctxt.tex_parameteri(
-            Context::TEXTURE_2D,
-            Context::TEXTURE_WRAP_T,
-            Context::CLAMP_TO_EDGE as i32
-        ));
 
+        self.pos_uvs.bind_sub_buffer(&mut self.vertices, 1, 0);
+        self.color.bind_sub_buffer(&mut self.vertices, 1, 1);
+
+        verify!(ctxt.draw_arrays(Context::TRIANGLES, 0, (self.vertices.len() / 2) as i32));
+
+        self.pos_uvs.disable();
+        self.color.disable();
+
+        verify!(ctxt.enable(Context::DEPTH_TEST));
+        verify!(ctxt.disable(Context::BLEND));
+    }
+
+    // Lays out the texts of this frame, and queues their glyphs for caching.
+    fn layout_glyphs(&mut self) {
         let mut pos = 0;
 
         for context in self.contexts.iter() {
@@ -190,83 +323,127 @@ impl TextRenderer {
                     y: context.pos.y + vshift,
                 };
 
-                vshift += line_height as f32;
-                let layout = context.font.font().layout(line, scale, orig);
-
-                for glyph in layout {
-                    let gly: rusttype::PositionedGlyph<'static> = context
-                        .font
-                        .font()
-                        .glyph(glyph.id())
-                        .scaled(glyph.scale())
-                        .positioned(glyph.position());
-                    self.cache.queue_glyph(font_uid, gly); // FIXME: is the call to `.standalone()` costly?
-                }
+                vshift += line_height;
 
-                let _ = self.cache.cache_queued(|rect, data| {
-                    verify!(ctxt.tex_sub_image2d(
-                        Context::TEXTURE_2D,
-                        0,
-                        rect.min.x as i32,
-                        rect.min.y as i32,
-                        rect.width() as i32,
-                        rect.height() as i32,
-                        Context::RED,
-                        Some(data)
-                    ));
-                });
-
-                let layout = context.font.font().layout(line, scale, orig);
-
-                {
-                    let coords = self.coords.data_mut().as_mut().unwrap();
-                    for glyph in layout {
-                        if let Ok(Some((tex, rect))) = self.cache.rect_for(font_uid, &glyph) {
-                            let min_px = rect.min.x as f32;
-                            let min_py = rect.min.y as f32 + vmetrics.ascent;
-                            let max_px = rect.max.x as f32;
-                            let max_py = rect.max.y as f32 + vmetrics.ascent;
-
-                            coords.push(Point2::new(min_px, min_py));
-                            coords.push(Point2::new(tex.min.x, tex.min.y));
-
-                            coords.push(Point2::new(min_px, max_py));
-                            coords.push(Point2::new(tex.min.x, tex.max.y));
-
-                            coords.push(Point2::new(max_px, min_py));
-                            coords.push(Point2::new(tex.max.x, tex.min.y));
-
-                            coords.push(Point2::new(max_px, min_py));
-                            coords.push(Point2::new(tex.max.x, tex.min.y));
-
-                            coords.push(Point2::new(min_px, max_py));
-                            coords.push(Point2::new(tex.min.x, tex.max.y));
-
-                            coords.push(Point2::new(max_px, max_py));
-                            coords.push(Point2::new(tex.max.x, tex.max.y));
-                        }
-                    }
+                for glyph in context.font.font().layout(line, scale, orig) {
+                    self.cache.queue_glyph(font_uid, glyph.clone());
+                    self.glyphs.push(QueuedGlyph {
+                        font_uid,
+                        glyph,
+                        ascent: vmetrics.ascent,
+                        color: context.color,
+                    });
                 }
+            }
 
-                self.pos.bind_sub_buffer(&mut self.coords, 1, 0);
-                self.uvs.bind_sub_buffer(&mut self.coords, 1, 1);
-                self.color.upload(&context.color);
+            pos += context.len;
+        }
+    }
 
-                verify!(ctxt.draw_arrays(Context::TRIANGLES, 0, (self.coords.len() / 2) as i32));
+    // Uploads the queued glyphs missing from the atlas, growing the atlas if they do not fit.
+    fn cache_glyphs(&mut self) {
+        loop {
+            let ctxt = Context::get();
+            verify!(ctxt.pixel_storei(Context::UNPACK_ALIGNMENT, 1));
+            verify!(ctxt.bind_texture(Context::TEXTURE_2D, Some(&self.texture)));
+
+            let res = self.cache.cache_queued(|rect, data| {
+                verify!(ctxt.tex_sub_image2d(
+                    Context::TEXTURE_2D,
+                    0,
+                    rect.min.x as i32,
+                    rect.min.y as i32,
+                    rect.width() as i32,
+                    rect.height() as i32,
+                    Context::RED,
+                    Some(data)
+                ));
+            });
+
+            if res.is_ok() || self.atlas_size >= MAX_ATLAS_SIZE {
+                // If the atlas cannot grow anymore, the glyphs that do not fit are not drawn.
+                return;
             }
-            pos += context.len;
 
-            self.coords.data_mut().as_mut().unwrap().clear();
+            // Rebuilding the cache clears it, so all the glyphs are queued again.
+            self.atlas_size *= 2;
+            self.cache
+                .to_builder()
+                .dimensions(self.atlas_size, self.atlas_size)
+                .rebuild(&mut self.cache);
+            allocate_atlas(&self.texture, self.atlas_size);
+
+            for queued in &self.glyphs {
+                self.cache
+                    .queue_glyph(queued.font_uid, queued.glyph.clone());
+            }
         }
+    }
+}
 
-        self.pos.disable();
-        self.uvs.enable();
-
-        verify!(ctxt.enable(Context::DEPTH_TEST));
-        verify!(ctxt.disable(Context::BLEND));
+// (Re)allocates the storage of the atlas texture, and sets its parameters.
+fn allocate_atlas(texture: &Texture, size: u32) {
+    let ctxt = Context::get();
+
+    verify!(ctxt.bind_texture(Context::TEXTURE_2D, Some(texture)));
+    verify!(ctxt.tex_image2d(
+        Context::TEXTURE_2D,
+        0,
+        Context::RED as i32,
+        size as i32,
+        size as i32,
+        0,
+        Context::RED,
+        None
+    ));
+
+    /* Clamp to the edge to avoid artifacts when scaling. */
+    verify!(ctxt.tex_parameteri(
+        Context::TEXTURE_2D,
+        Context::TEXTURE_WRAP_S,
+        Context::CLAMP_TO_EDGE as i32
+    ));
+    verify!(ctxt.tex_parameteri(
+        Context::TEXTURE_2D,
+        Context::TEXTURE_WRAP_T,
+        Context::CLAMP_TO_EDGE as i32
+    ));
+
+    /* Linear filtering usually looks best for text. */
+    verify!(ctxt.tex_parameteri(
+        Context::TEXTURE_2D,
+        Context::TEXTURE_MIN_FILTER,
+        Context::LINEAR as i32
+    ));
+    verify!(ctxt.tex_parameteri(
+        Context::TEXTURE_2D,
+        Context::TEXTURE_MAG_FILTER,
+        Context::LINEAR as i32
+    ));
+}
 
-        self.contexts.clear();
-        self.text.clear();
+fn push_quad(
+    vertices: &mut Vec<Point4<f32>>,
+    min: &Point2<f32>,
+    max: &Point2<f32>,
+    uvs_min: &Point2<f32>,
+    uvs_max: &Point2<f32>,
+    color: &Point3<f32>,
+    solid: f32,
+) {
+    let color = Point4::new(color.x, color.y, color.z, solid);
+    let corners = [
+        (min.x, min.y, uvs_min.x, uvs_min.y),
+        (min.x, max.y, uvs_min.x, uvs_max.y),
+        (max.x, min.y, uvs_max.x, uvs_min.y),
+        (max.x, min.y, uvs_max.x, uvs_min.y),
+        (min.x, max.y, uvs_min.x, uvs_max.y),
+        (max.x, max.y, uvs_max.x, uvs_max.y),
+    ];
+
+    for (x, y, u, v) in corners.iter() {
+        vertices.push(Point4::new(*x, *y, *u, *v));
+        vertices.push(color);
     }
 }
 
@@ -279,17 +456,16 @@ const A_VERY_LONG_STRING: &str = "
 #version 100
 
 uniform vec2 invsz;
-uniform vec3 color;
 
-attribute vec2 pos;
-attribute vec2 uvs;
+attribute vec4 pos_uvs;
+attribute vec4 color;
 
 varying vec2 tex;
-varying vec3 Color;
+varying vec4 Color;
 
 void main() {
-    gl_Position = vec4(pos.x * invsz.x - 1.0, pos.y * invsz.y + 1.0, -1.0, 1.0);
-    tex         = uvs;
+    gl_Position = vec4(pos_uvs.x * invsz.x - 1.0, pos_uvs.y * invsz.y + 1.0, -1.0, 1.0);
+    tex         = pos_uvs.zw;
     Color       = color;
 }
 ";
@@ -306,9 +482,10 @@ const ANOTHER_VERY_LONG_STRING: &str = "
 uniform sampler2D tex0;
 
 varying vec2 tex;
-varying vec3 Color;
+varying vec4 Color;
 
 void main() {
-    gl_FragColor = vec4(Color, texture2D(tex0, tex).r);
+    // The alpha channel of the color is 1.0 for solid quads, e.g., underlines.
+    gl_FragColor = vec4(Color.rgb, max(texture2D(tex0, tex).r, Color.a));
 }
 ";
@@ -0,0 +1,183 @@
+use na::Point3;
+use std::rc::Rc;
+
+use crate::text::Font;
+
+/// The set of font faces used to render styled text.
+///
+/// Missing faces fall back to the regular one.
+#[derive(Clone)]
+pub struct FontFamily {
+    /// The regular face.
+    pub regular: Rc<Font>,
+    /// The bold face.
+    pub bold: Option<Rc<Font>>,
+    /// The italic face.
+    pub italic: Option<Rc<Font>>,
+    /// The bold and italic face.
+    pub bold_italic: Option<Rc<Font>>,
+}
+
+impl FontFamily {
+    /// Creates a font family containing only a regular face.
+    pub fn new(regular: Rc<Font>) -> FontFamily {
+        FontFamily {
+            regular,
+            bold: None,
+            italic: None,
+            bold_italic: None,
+        }
+    }
+
+    /// Selects the face matching the given style.
+    pub fn face(&self, bold: bool, italic: bool) -> &Rc<Font> {
+        let face = match (bold, italic) {
+            (true, true) => self
+                .bold_italic
+                .as_ref()
+                .or(self.bold.as_ref())
+                .or(self.italic.as_ref()),
+            (true, false) => self.bold.as_ref(),
+            (false, true) => self.italic.as_ref(),
+            (false, false) => None,
+        };
+
+        face.unwrap_or(&self.regular)
+    }
+}
+
+impl Default for FontFamily {
+    fn default() -> FontFamily {
+        FontFamily::new(Font::default())
+    }
+}
+
+/// A piece of text sharing the same style.
+#[derive(Clone)]
+pub struct TextSpan {
+    /// The text of this span. It may contain line breaks.
+    pub text: String,
+    /// The font used to render this span.
+    pub font: Rc<Font>,
+    /// The color of this span.
+    pub color: Point3<f32>,
+    /// Whether this span is underlined.
+    pub underline: bool,
+}
+
+impl TextSpan {
+    /// Creates a new, non-underlined, span.
+    pub fn new(text: &str, font: &Rc<Font>, color: &Point3<f32>) -> TextSpan {
+        TextSpan {
+            text: text.to_string(),
+            font: font.clone(),
+            color: *color,
+            underline: false,
+        }
+    }
+
+    /// Sets whether this span is underlined.
+    pub fn underlined(mut self, underline: bool) -> TextSpan {
+        self.underline = underline;
+        self
+    }
+
+    /// Splits a markup string into styled spans.
+    ///
+    /// The supported tags are `<b>`, `<i>`, `<u>`, and `<color=#rrggbb>`, each closed by its
+    /// `</...>` counterpart. Tags may be nested. Unknown tags are kept as plain text, and `<<`
+    /// produces a literal `<`.
+    pub fn parse_markup(markup: &str, family: &FontFamily, color: &Point3<f32>) -> Vec<TextSpan> {
+        let mut spans = Vec::new();
+        let mut curr = String::new();
+        let mut bold = 0usize;
+        let mut italic = 0usize;
+        let mut underline = 0usize;
+        let mut colors = vec![*color];
+        let mut rest = markup;
+
+        while let Some(start) = rest.find('<') {
+            curr.push_str(&rest[..start]);
+            rest = &rest[start..];
+
+            if rest.starts_with("<<") {
+                curr.push('<');
+                rest = &rest[2..];
+                continue;
+            }
+
+            let end = match rest.find('>') {
+                Some(end) => end,
+                None => break,
+            };
+
+            let tag = &rest[1..end];
+            let mut new_bold = bold;
+            let mut new_italic = italic;
+            let mut new_underline = underline;
+            let mut new_color = None;
+            let mut pop_color = false;
+
+            match tag {
+                "b" => new_bold += 1,
+                "/b" => new_bold = bold.saturating_sub(1),
+                "i" => new_italic += 1,
+                "/i" => new_italic = italic.saturating_sub(1),
+                "u" => new_underline += 1,
+                "/u" => new_underline = underline.saturating_sub(1),
+                "/color" => pop_color = true,
+                _ => match parse_color_tag(tag) {
+                    Some(c) => new_color = Some(c),
+                    None => {
+                        curr.push_str(&rest[..=end]);
+                        rest = &rest[end + 1..];
+                        continue;
+                    }
+                },
+            }
+
+            if !curr.is_empty() {
+                let font = family.face(bold > 0, italic > 0);
+                let color = colors.last().unwrap();
+                spans.push(TextSpan::new(&curr, font, color).underlined(underline > 0));
+                curr.clear();
+            }
+
+            bold = new_bold;
+            italic = new_italic;
+            underline = new_underline;
+
+            if let Some(c) = new_color {
+                colors.push(c);
+            } else if pop_color && colors.len() > 1 {
+                let _ = colors.pop();
+            }
+
+            rest = &rest[end + 1..];
+        }
+
+        curr.push_str(rest);
+
+        if !curr.is_empty() {
+            let font = family.face(bold > 0, italic > 0);
+            let color = colors.last().unwrap();
+            spans.push(TextSpan::new(&curr, font, color).underlined(underline > 0));
+        }
+
+        spans
+    }
+}
+
+fn parse_color_tag(tag: &str) -> Option<Point3<f32>> {
+    let hex = tag.strip_prefix("color=#")?;
+
+    if hex.len() != 6 {
+        return None;
+    }
+
+    let r = u8::from_str_radix(hex.get(0..2)?, 16).ok()?;
+    let g = u8::from_str_radix(hex.get(2..4)?, 16).ok()?;
+    let b = u8::from_str_radix(hex.get(4..6)?, 16).ok()?;
+
+    Some(Point3::new(r as f32, g as f32, b as f32) / 255.0)
+}
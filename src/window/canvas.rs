@@ -1,6 +1,6 @@
 use std::sync::mpsc::Sender;
 
-use crate::event::{Action, Key, MouseButton, WindowEvent};
+use crate::event::{Action, GamepadState, Key, MouseButton, WindowEvent};
 #[cfg(not(target_arch = "wasm32"))]
 use crate::window::GLCanvas as CanvasImpl;
 #[cfg(target_arch = "wasm32")]
@@ -52,6 +52,9 @@ pub struct CanvasSetup {
 /// An abstract structure representing a window for native applications, and a canvas for web applications.
 pub struct Canvas {
     canvas: CanvasImpl,
+    gamepad: GamepadState,
+    #[cfg(feature = "gamepad")]
+    gilrs: Option<gilrs::Gilrs>,
 }
 
 impl Canvas {
@@ -66,6 +69,15 @@ impl Canvas {
     ) -> Self {
         Canvas {
             canvas: CanvasImpl::open(title, hide, width, height, canvas_setup, out_events),
+            gamepad: GamepadState::new(),
+            #[cfg(feature = "gamepad")]
+            gilrs: match gilrs::Gilrs::new() {
+                Ok(gilrs) => Some(gilrs),
+                Err(e) => {
+                    println!("Warning: unable to read gamepads: {}", e);
+                    None
+                }
+            },
         }
     }
 
@@ -76,7 +88,39 @@ impl Canvas {
 
     /// Poll all events tha occurred since the last call to this method.
     pub fn poll_events(&mut self) {
-        self.canvas.poll_events()
+        self.canvas.poll_events();
+
+        #[cfg(feature = "gamepad")]
+        self.poll_gamepad();
+    }
+
+    #[cfg(feature = "gamepad")]
+    fn poll_gamepad(&mut self) {
+        use crate::event::GamepadAxis;
+        use gilrs::{Axis, Button};
+
+        let gilrs = match self.gilrs {
+            Some(ref mut gilrs) => gilrs,
+            None => return,
+        };
+
+        // Update the state of the gamepads.
+        while gilrs.next_event().is_some() {}
+
+        let mut state = GamepadState::new();
+
+        if let Some((_, pad)) = gilrs.gamepads().next() {
+            let trigger = |button| pad.button_data(button).map_or(0.0, |data| data.value());
+            state.set_connected(true);
+            state.set_axis(GamepadAxis::LeftStickX, pad.value(Axis::LeftStickX));
+            state.set_axis(GamepadAxis::LeftStickY, pad.value(Axis::LeftStickY));
+            state.set_axis(GamepadAxis::RightStickX, pad.value(Axis::RightStickX));
+            state.set_axis(GamepadAxis::RightStickY, pad.value(Axis::RightStickY));
+            state.set_axis(GamepadAxis::LeftTrigger, trigger(Button::LeftTrigger2));
+            state.set_axis(GamepadAxis::RightTrigger, trigger(Button::RightTrigger2));
+        }
+
+        self.gamepad = state;
     }
 
     /// If double-buffering is supported, swap the buffers.
@@ -146,6 +190,19 @@ impl Canvas {
     pub fn get_key(&self, key: Key) -> Action {
         self.canvas.get_key(key)
     }
+
+    /// The state of the gamepad.
+    pub fn gamepad(&self) -> &GamepadState {
+        &self.gamepad
+    }
+
+    /// Sets the state of the gamepad.
+    ///
+    /// With the `gamepad` feature, this state is replaced at each frame by the state read from
+    /// the gamepads, unless they cannot be accessed.
+    pub fn set_gamepad_state(&mut self, state: GamepadState) {
+        self.gamepad = state
+    }
 }
 
 /// Note: the closure must have static lifetime because of the constraints imposed by wasm-bindgen:
@@ -0,0 +1,64 @@
+use crate::context::Context;
+
+#[path = "../error.rs"]
+mod error;
+
+bitflags! {
+    /// The buffers cleared by the window at the beginning of each frame.
+    ///
+    /// The background gradient, panorama, and ground disc are part of the color clear: they are
+    /// only drawn if `COLOR` is set.
+    pub struct ClearFlags: u32 {
+        /// Fills the color buffer with the background.
+        ///
+        /// Without it, the previous frames remain visible below the new one, e.g., for trail
+        /// effects. This is only reliable with a post-processing effect, or on platforms
+        /// preserving the content of the screen buffer after it is presented.
+        const COLOR   = 0b001;
+        /// Clears the depth buffer.
+        const DEPTH   = 0b010;
+        /// Clears the stencil buffer.
+        const STENCIL = 0b100;
+    }
+}
+
+impl Default for ClearFlags {
+    fn default() -> ClearFlags {
+        ClearFlags::COLOR | ClearFlags::DEPTH
+    }
+}
+
+impl ClearFlags {
+    // The mask given to the GL `clear` function.
+    pub(crate) fn gl_mask(&self) -> u32 {
+        let mut mask = 0;
+
+        if self.contains(ClearFlags::COLOR) {
+            mask |= Context::COLOR_BUFFER_BIT;
+        }
+
+        if self.contains(ClearFlags::DEPTH) {
+            mask |= Context::DEPTH_BUFFER_BIT;
+        }
+
+        if self.contains(ClearFlags::STENCIL) {
+            mask |= Context::STENCIL_BUFFER_BIT;
+        }
+
+        mask
+    }
+}
+
+// Sets the value the depth buffer is cleared to, and the depth comparison, for a projection
+// mapping the near plane either to the smallest depth or, if reversed, to the greatest.
+pub(crate) fn set_depth_convention(reversed_depth: bool) {
+    let ctxt = Context::get();
+
+    if reversed_depth {
+        verify!(ctxt.clear_depth(0.0));
+        verify!(ctxt.depth_func(Context::GEQUAL));
+    } else {
+        verify!(ctxt.clear_depth(1.0));
+        verify!(ctxt.depth_func(Context::LEQUAL));
+    }
+}
@@ -1,6 +1,6 @@
 use std::sync::mpsc::Sender;
 
-use crate::context::Context;
+use crate::context::{Context, GLContext};
 use crate::event::{Action, Key, Modifiers, MouseButton, TouchAction, WindowEvent};
 use crate::window::canvas::{CanvasSetup, NumSamples, RenderLoopClosure};
 use crate::window::AbstractCanvas;
@@ -79,8 +79,12 @@ impl AbstractCanvas for GLCanvas {
             .build_windowed(window, &events)
             .unwrap();
         let window = unsafe { window.make_current().unwrap() };
-        Context::init(|| unsafe {
-            glow::Context::from_loader_function(|name| window.get_proc_address(name) as *const _)
+        Context::init(|| {
+            GLContext::new(unsafe {
+                glow::Context::from_loader_function(|name| {
+                    window.get_proc_address(name) as *const _
+                })
+            })
         });
 
         let ctxt = Context::get();
@@ -0,0 +1,180 @@
+use na::{Point2, Point3};
+use std::collections::VecDeque;
+use std::time::Duration;
+
+use crate::context::{Context, Query};
+use crate::overlay::OverlayContext;
+
+#[path = "../error.rs"]
+mod error;
+
+// Frames whose results are still unavailable after this many frames are dropped.
+const MAX_PENDING_FRAMES: usize = 4;
+
+/// The GPU time spent in one rendering pass.
+#[derive(Clone, Debug)]
+pub struct GpuPassTiming {
+    /// The name of the pass.
+    pub name: &'static str,
+    /// The time spent by the GPU executing the pass.
+    pub duration: Duration,
+}
+
+/// The GPU time spent in each rendering pass of a frame.
+#[derive(Clone, Debug, Default)]
+pub struct GpuProfile {
+    /// The timing of each pass, in submission order.
+    pub passes: Vec<GpuPassTiming>,
+}
+
+impl GpuProfile {
+    /// The GPU time spent in all the passes of the frame.
+    pub fn total(&self) -> Duration {
+        self.passes.iter().map(|p| p.duration).sum()
+    }
+
+    /// The GPU time spent in the passes with the given name, if any.
+    pub fn pass(&self, name: &str) -> Option<Duration> {
+        let mut res = None;
+
+        for p in self.passes.iter().filter(|p| p.name == name) {
+            *res.get_or_insert_with(Duration::default) += p.duration;
+        }
+
+        res
+    }
+}
+
+type FrameQueries = Vec<(&'static str, Query)>;
+
+// Measures the GPU time spent in each pass with timer queries.
+//
+// Results are read a few frames later, once available, so the CPU never waits for the GPU.
+pub(crate) struct GpuProfiler {
+    free_queries: Vec<Query>,
+    current: FrameQueries,
+    pending: VecDeque<FrameQueries>,
+    in_pass: bool,
+    profile: Option<GpuProfile>,
+    overlay: bool,
+}
+
+impl GpuProfiler {
+    // Returns `None` if timer queries are not supported.
+    pub fn new() -> Option<GpuProfiler> {
+        if !Context::get().supports_timer_queries() {
+            return None;
+        }
+
+        Some(GpuProfiler {
+            free_queries: Vec::new(),
+            current: Vec::new(),
+            pending: VecDeque::new(),
+            in_pass: false,
+            profile: None,
+            overlay: false,
+        })
+    }
+
+    pub fn profile(&self) -> Option<&GpuProfile> {
+        self.profile.as_ref()
+    }
+
+    pub fn set_overlay(&mut self, visible: bool) {
+        self.overlay = visible;
+    }
+
+    // Starts measuring a pass, ending the previous one if needed.
+    pub fn begin_pass(&mut self, name: &'static str) {
+        self.end_pass();
+
+        let ctxt = Context::get();
+        let query = match self.free_queries.pop() {
+            Some(q) => q,
+            None => match verify!(ctxt.create_query()) {
+                Some(q) => q,
+                None => return,
+            },
+        };
+
+        verify!(ctxt.begin_query(Context::TIME_ELAPSED, &query));
+        self.current.push((name, query));
+        self.in_pass = true;
+    }
+
+    pub fn end_pass(&mut self) {
+        if self.in_pass {
+            verify!(Context::get().end_query(Context::TIME_ELAPSED));
+            self.in_pass = false;
+        }
+    }
+
+    // Ends the current frame and collects the results of the previous frames that are available.
+    pub fn end_frame(&mut self) {
+        self.end_pass();
+
+        if !self.current.is_empty() {
+            let frame = std::mem::take(&mut self.current);
+            self.pending.push_back(frame);
+        }
+
+        let ctxt = Context::get();
+
+        while let Some(frame) = self.pending.front() {
+            let available = match frame.last() {
+                Some((_, q)) => {
+                    verify!(ctxt.get_query_parameter_u32(q, Context::QUERY_RESULT_AVAILABLE)) != 0
+                }
+                None => true,
+            };
+
+            if !available && self.pending.len() <= MAX_PENDING_FRAMES {
+                break;
+            }
+
+            let frame = self.pending.pop_front().unwrap();
+
+            if available {
+                let passes = frame
+                    .iter()
+                    .map(|(name, q)| {
+                        let ns = verify!(ctxt.get_query_parameter_u32(q, Context::QUERY_RESULT));
+                        GpuPassTiming {
+                            name,
+                            duration: Duration::from_nanos(ns as u64),
+                        }
+                    })
+                    .collect();
+
+                self.profile = Some(GpuProfile { passes });
+            }
+
+            self.free_queries.extend(frame.into_iter().map(|e| e.1));
+        }
+    }
+
+    pub fn draw(&self, ctxt: &mut OverlayContext) {
+        let profile = match self.profile {
+            Some(ref p) if self.overlay => p,
+            _ => return,
+        };
+
+        let scale = ctxt.scale();
+        let line_height = 18.0 * scale;
+        let mut pos = Point2::new(ctxt.size().x - 200.0 * scale, 8.0 * scale);
+        let color = Point3::new(1.0, 1.0, 0.6);
+
+        for pass in profile.passes.iter() {
+            let text = format!("{:<16}{:>7.3} ms", pass.name, as_millis(pass.duration));
+            ctxt.draw_text(&text, &pos, 14.0, &color);
+            pos.y += line_height;
+        }
+
+        let text = format!("{:<16}{:>7.3} ms", "total", as_millis(profile.total()));
+        ctxt.draw_text(&text, &pos, 14.0, &color);
+    }
+}
+
+fn as_millis(duration: Duration) -> f64 {
+    duration.as_secs_f64() * 1000.0
+}
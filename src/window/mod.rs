@@ -1,9 +1,14 @@
 //! The window, and things to handle the rendering loop and events.
 
 mod canvas;
+mod clear;
 #[cfg(not(target_arch = "wasm32"))]
 mod gl_canvas;
+mod gpu_profiler;
+mod panorama;
+mod snapshot;
 mod state;
+mod sub_view;
 #[cfg(target_arch = "wasm32")]
 mod webgl_canvas;
 mod window;
@@ -11,9 +16,15 @@ mod window_cache;
 
 pub(crate) use canvas::AbstractCanvas;
 pub use canvas::{Canvas, CanvasSetup, NumSamples, RenderLoopClosure};
+pub(crate) use clear::set_depth_convention;
+pub use clear::ClearFlags;
 #[cfg(not(target_arch = "wasm32"))]
 pub use gl_canvas::GLCanvas;
+pub(crate) use gpu_profiler::GpuProfiler;
+pub use gpu_profiler::{GpuPassTiming, GpuProfile};
+pub use snapshot::{SnapColorSpace, SnapFormat, SnapOptions};
 pub use state::State;
+pub use sub_view::{SubView, SubViewId};
 #[cfg(target_arch = "wasm32")]
 pub use webgl_canvas::WebGLCanvas;
 pub use window::Window;
@@ -0,0 +1,149 @@
+use std::f32;
+
+use image::{ImageBuffer, Rgb, RgbImage};
+use na::{self, Isometry3, Matrix4, Perspective3, Point3, Vector3};
+
+use crate::camera::Camera;
+use crate::event::WindowEvent;
+use crate::resource::ShaderUniform;
+use crate::window::Canvas;
+
+// The viewing directions and up vectors of the six faces of a cube map.
+const FACES: [([f32; 3], [f32; 3]); 6] = [
+    ([1.0, 0.0, 0.0], [0.0, 1.0, 0.0]),
+    ([-1.0, 0.0, 0.0], [0.0, 1.0, 0.0]),
+    ([0.0, 1.0, 0.0], [0.0, 0.0, 1.0]),
+    ([0.0, -1.0, 0.0], [0.0, 0.0, -1.0]),
+    ([0.0, 0.0, 1.0], [0.0, 1.0, 0.0]),
+    ([0.0, 0.0, -1.0], [0.0, 1.0, 0.0]),
+];
+
+// A camera with a square 90° field of view, looking along one axis of the world.
+pub(crate) struct CubeFaceCamera {
+    eye: Point3<f32>,
+    view_transform: Isometry3<f32>,
+    projection: Perspective3<f32>,
+    proj: Matrix4<f32>,
+    view: Matrix4<f32>,
+    proj_view: Matrix4<f32>,
+    inverse_proj_view: Matrix4<f32>,
+}
+
+impl CubeFaceCamera {
+    // The cameras for each face of a cube map centered at `eye`.
+    pub fn faces(eye: Point3<f32>, znear: f32, zfar: f32) -> Vec<CubeFaceCamera> {
+        FACES
+            .iter()
+            .map(|(dir, up)| {
+                let projection = Perspective3::new(1.0, f32::consts::FRAC_PI_2, znear, zfar);
+                let at = eye + Vector3::from(*dir);
+                let view_transform = Isometry3::look_at_rh(&eye, &at, &Vector3::from(*up));
+                let proj = *projection.as_matrix();
+                let view = view_transform.to_homogeneous();
+                let proj_view = proj * view;
+
+                CubeFaceCamera {
+                    eye,
+                    view_transform,
+                    projection,
+                    proj,
+                    view,
+                    proj_view,
+                    inverse_proj_view: proj_view.try_inverse().unwrap_or_else(na::one),
+                }
+            })
+            .collect()
+    }
+}
+
+impl Camera for CubeFaceCamera {
+    fn handle_event(&mut self, _: &Canvas, _: &WindowEvent) {}
+
+    fn eye(&self) -> Point3<f32> {
+        self.eye
+    }
+
+    fn view_transform(&self) -> Isometry3<f32> {
+        self.view_transform
+    }
+
+    fn transformation(&self) -> Matrix4<f32> {
+        self.proj_view
+    }
+
+    fn inverse_transformation(&self) -> Matrix4<f32> {
+        self.inverse_proj_view
+    }
+
+    fn clip_planes(&self) -> (f32, f32) {
+        (self.projection.znear(), self.projection.zfar())
+    }
+
+    fn update(&mut self, _: &Canvas) {}
+
+    fn upload(
+        &self,
+        _: usize,
+        proj: &mut ShaderUniform<Matrix4<f32>>,
+        view: &mut ShaderUniform<Matrix4<f32>>,
+    ) {
+        proj.upload(&self.proj);
+        view.upload(&self.view);
+    }
+
+    fn pass_matrices(&self, _: usize) -> (Matrix4<f32>, Matrix4<f32>) {
+        (self.proj, self.view)
+    }
+}
+
+// Resamples the faces of a cube map into an equirectangular image of `width` by `width / 2`
+// pixels, centered on the `-z` direction with `y` pointing up.
+//
+// The faces are the images rendered by the cameras of the same index, with their first row at
+// the bottom.
+pub(crate) fn equirectangular(
+    cameras: &[CubeFaceCamera],
+    faces: &[RgbImage],
+    width: u32,
+) -> RgbImage {
+    let height = (width / 2).max(1);
+
+    ImageBuffer::from_fn(width, height, |x, y| {
+        let lon = ((x as f32 + 0.5) / width as f32 - 0.5) * f32::consts::PI * 2.0;
+        let lat = (0.5 - (y as f32 + 0.5) / height as f32) * f32::consts::PI;
+        let dir = Vector3::new(lat.cos() * lon.sin(), lat.sin(), -lat.cos() * lon.cos());
+
+        // Find the face the direction goes through, i.e., the one whose camera sees it the most
+        // straight ahead.
+        let (i, local) = cameras
+            .iter()
+            .map(|c| c.view_transform.rotation * dir)
+            .enumerate()
+            .min_by(|a, b| a.1.z.partial_cmp(&b.1.z).unwrap())
+            .unwrap();
+
+        let u = (local.x / -local.z + 1.0) * 0.5;
+        let v = (local.y / -local.z + 1.0) * 0.5;
+        sample_bilinear(&faces[i], u, v)
+    })
+}
+
+fn sample_bilinear(image: &RgbImage, u: f32, v: f32) -> Rgb<u8> {
+    let (w, h) = image.dimensions();
+    let fx = (u * w as f32 - 0.5).clamp(0.0, (w - 1) as f32);
+    let fy = (v * h as f32 - 0.5).clamp(0.0, (h - 1) as f32);
+    let (x0, y0) = (fx as u32, fy as u32);
+    let (x1, y1) = ((x0 + 1).min(w - 1), (y0 + 1).min(h - 1));
+    let (tx, ty) = (fx - x0 as f32, fy - y0 as f32);
+
+    let mut res = [0u8; 3];
+
+    for (c, out) in res.iter_mut().enumerate() {
+        let p = |x, y| image.get_pixel(x, y)[c] as f32;
+        let top = p(x0, y0) * (1.0 - tx) + p(x1, y0) * tx;
+        let bottom = p(x0, y1) * (1.0 - tx) + p(x1, y1) * tx;
+        *out = (top * (1.0 - ty) + bottom * ty).round() as u8;
+    }
+
+    Rgb(res)
+}
@@ -0,0 +1,228 @@
+use image::{DynamicImage, ImageBuffer};
+
+use crate::color::srgb_to_linear;
+use crate::context::Context;
+
+/// The pixel format of a snapshot of the window.
+///
+/// Snapshots are converted to `DynamicImage`s, so they can be saved to any file format supported
+/// by the `image` crate, as long as the format supports their pixel type: 16-bit formats to PNG
+/// or TIFF files, and 32-bit float formats to EXR files.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum SnapFormat {
+    /// RGB colors with 8 bits per channel.
+    Rgb8,
+    /// RGB colors with 16 bits per channel.
+    Rgb16,
+    /// RGB colors with a 32-bit float per channel.
+    Rgb32F,
+    /// The depth buffer, as grayscale with 16 bits per pixel.
+    Depth16,
+    /// The depth buffer, with a 32-bit float per pixel replicated in the RGB channels.
+    Depth32F,
+}
+
+impl SnapFormat {
+    /// Whether this format reads the depth buffer instead of the colors.
+    pub fn is_depth(self) -> bool {
+        matches!(self, SnapFormat::Depth16 | SnapFormat::Depth32F)
+    }
+}
+
+/// The color space of the colors of a snapshot.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum SnapColorSpace {
+    /// The sRGB-encoded colors, as displayed on the screen.
+    Srgb,
+    /// The linear intensities of the colors, e.g., for compositing or for EXR files.
+    ///
+    /// Linear colors should be saved with 16 bits per channel or more, to avoid banding in dark
+    /// areas.
+    Linear,
+}
+
+/// The options of a snapshot taken by `Window::snap_with`.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct SnapOptions {
+    /// The pixel format of the snapshot.
+    pub format: SnapFormat,
+    /// The color space of the colors of the snapshot. Ignored by depth formats.
+    pub color_space: SnapColorSpace,
+    /// Whether the snapshot has an alpha channel, in which the background is transparent.
+    ///
+    /// The background gradient and panorama are opaque. Ignored by depth formats.
+    pub alpha: bool,
+    /// Whether the overlays, texts, and user interface are drawn over the scene.
+    pub overlays: bool,
+    /// The width and height of the snapshot, in pixels, or `None` for the size of the window.
+    pub resolution: Option<(u32, u32)>,
+}
+
+impl Default for SnapOptions {
+    fn default() -> SnapOptions {
+        SnapOptions {
+            format: SnapFormat::Rgb8,
+            color_space: SnapColorSpace::Srgb,
+            alpha: false,
+            overlays: true,
+            resolution: None,
+        }
+    }
+}
+
+// Reads a section of the current framebuffer into an image with the given pixel format. The
+// rows of the image are not flipped, so the first row is at the bottom of the screen.
+pub(crate) fn read_framebuffer(
+    x: usize,
+    y: usize,
+    width: usize,
+    height: usize,
+    format: SnapFormat,
+) -> DynamicImage {
+    let options = SnapOptions {
+        format,
+        ..SnapOptions::default()
+    };
+
+    read_framebuffer_with(x, y, width, height, &options)
+}
+
+// Reads a section of the current framebuffer into an image with the pixel format, color space,
+// and alpha channel given by `options`. The rows of the image are not flipped.
+pub(crate) fn read_framebuffer_with(
+    x: usize,
+    y: usize,
+    width: usize,
+    height: usize,
+    options: &SnapOptions,
+) -> DynamicImage {
+    let ctxt = Context::get();
+    let (w, h) = (width as u32, height as u32);
+    let format = options.format;
+    let alpha = options.alpha && !format.is_depth();
+    let linear = options.color_space == SnapColorSpace::Linear && !format.is_depth();
+
+    if format == SnapFormat::Rgb8 && !linear {
+        let (gl_format, channels) = if alpha {
+            (Context::RGBA, 4)
+        } else {
+            (Context::RGB, 3)
+        };
+
+        let mut pixels = vec![0u8; width * height * channels];
+        ctxt.pixel_storei(Context::PACK_ALIGNMENT, 1);
+        ctxt.read_pixels(
+            x as i32,
+            y as i32,
+            width as i32,
+            height as i32,
+            gl_format,
+            Some(&mut pixels),
+        );
+
+        return if alpha {
+            DynamicImage::ImageRgba8(ImageBuffer::from_raw(w, h, pixels).unwrap())
+        } else {
+            DynamicImage::ImageRgb8(ImageBuffer::from_raw(w, h, pixels).unwrap())
+        };
+    }
+
+    let (gl_format, channels) = if format.is_depth() {
+        (Context::DEPTH_COMPONENT, 1)
+    } else if alpha {
+        (Context::RGBA, 4)
+    } else {
+        (Context::RGB, 3)
+    };
+
+    let mut pixels = vec![0.0f32; width * height * channels];
+    ctxt.pixel_storei(Context::PACK_ALIGNMENT, 4);
+    ctxt.read_pixels_f32(
+        x as i32,
+        y as i32,
+        width as i32,
+        height as i32,
+        gl_format,
+        &mut pixels,
+    );
+
+    if linear {
+        // The alpha channel is not encoded.
+        for (i, c) in pixels.iter_mut().enumerate() {
+            if !alpha || i % 4 != 3 {
+                *c = srgb_to_linear(*c);
+            }
+        }
+    }
+
+    let to_u8 = |v: &f32| (v.clamp(0.0, 1.0) * 255.0).round() as u8;
+    let to_u16 = |v: &f32| (v.clamp(0.0, 1.0) * 65535.0).round() as u16;
+
+    match (format, alpha) {
+        (SnapFormat::Rgb8, false) => {
+            let pixels = pixels.iter().map(to_u8).collect();
+            DynamicImage::ImageRgb8(ImageBuffer::from_raw(w, h, pixels).unwrap())
+        }
+        (SnapFormat::Rgb8, true) => {
+            let pixels = pixels.iter().map(to_u8).collect();
+            DynamicImage::ImageRgba8(ImageBuffer::from_raw(w, h, pixels).unwrap())
+        }
+        (SnapFormat::Rgb16, false) => {
+            let pixels = pixels.iter().map(to_u16).collect();
+            DynamicImage::ImageRgb16(ImageBuffer::from_raw(w, h, pixels).unwrap())
+        }
+        (SnapFormat::Rgb16, true) => {
+            let pixels = pixels.iter().map(to_u16).collect();
+            DynamicImage::ImageRgba16(ImageBuffer::from_raw(w, h, pixels).unwrap())
+        }
+        (SnapFormat::Rgb32F, false) => {
+            DynamicImage::ImageRgb32F(ImageBuffer::from_raw(w, h, pixels).unwrap())
+        }
+        (SnapFormat::Rgb32F, true) => {
+            DynamicImage::ImageRgba32F(ImageBuffer::from_raw(w, h, pixels).unwrap())
+        }
+        (SnapFormat::Depth16, _) => {
+            let pixels = pixels.iter().map(to_u16).collect();
+            DynamicImage::ImageLuma16(ImageBuffer::from_raw(w, h, pixels).unwrap())
+        }
+        (SnapFormat::Depth32F, _) => {
+            let pixels = pixels.iter().flat_map(|d| [*d, *d, *d]).collect();
+            DynamicImage::ImageRgb32F(ImageBuffer::from_raw(w, h, pixels).unwrap())
+        }
+    }
+}
+
+// Reads the depth buffer of the current framebuffer, with its depths rescaled so that the
+// nearest and farthest depths not at the clear values 0.0 or 1.0 span the whole grayscale range.
+// The rows of the image are not flipped.
+pub(crate) fn read_normalized_depth(width: usize, height: usize) -> DynamicImage {
+    let ctxt = Context::get();
+    let mut depths = vec![0.0f32; width * height];
+    ctxt.pixel_storei(Context::PACK_ALIGNMENT, 4);
+    ctxt.read_pixels_f32(
+        0,
+        0,
+        width as i32,
+        height as i32,
+        Context::DEPTH_COMPONENT,
+        &mut depths,
+    );
+
+    let (min, max) = depths
+        .iter()
+        .filter(|d| **d > 0.0 && **d < 1.0)
+        .fold((f32::MAX, f32::MIN), |(min, max), d| {
+            (min.min(*d), max.max(*d))
+        });
+    let (min, range) = if max > min {
+        (min, max - min)
+    } else {
+        (0.0, 1.0)
+    };
+    let pixels = depths
+        .iter()
+        .map(|d| (((d - min) / range).clamp(0.0, 1.0) * 65535.0).round() as u16)
+        .collect();
+
+    DynamicImage::ImageLuma16(ImageBuffer::from_raw(width as u32, height as u32, pixels).unwrap())
+}
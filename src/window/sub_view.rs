@@ -0,0 +1,159 @@
+use na::Vector3;
+
+use crate::camera::Camera;
+use crate::color::Color;
+use crate::context::Context;
+use crate::event::WindowEvent;
+use crate::light::Light;
+use crate::scene::SceneNode;
+use crate::window::clear::set_depth_convention;
+use crate::window::Canvas;
+
+#[path = "../error.rs"]
+mod error;
+
+/// A scene rendered with its own camera into a rectangle of the window.
+///
+/// Sub-views are rendered on top of the main scene, e.g., to display thumbnails of objects,
+/// orientation widgets, or side-by-side comparisons. They are added to a window with
+/// `Window::add_sub_view`:
+///
+/// ```no_run
+/// # use kiss3d::window::{SubView, Window};
+/// # use kiss3d::camera::ArcBall;
+/// # use kiss3d::scene::SceneNode;
+/// # use kiss3d::nalgebra::Point3;
+/// let mut window = Window::new("Kiss3d: sub-view");
+/// let camera = ArcBall::new(Point3::new(0.0, 0.0, -2.0), Point3::origin());
+/// let mut view = SubView::new(SceneNode::new_empty(), Box::new(camera), 10, 10, 200, 150);
+/// view.set_background_color((0.2, 0.2, 0.2));
+/// let _ = view.scene_mut().add_cube(0.5, 0.5, 0.5);
+/// let _ = window.add_sub_view(view);
+/// ```
+pub struct SubView {
+    scene: SceneNode,
+    camera: Box<dyn Camera>,
+    rect: (u32, u32, u32, u32),
+    background: Option<Vector3<f32>>,
+    light: Light,
+    visible: bool,
+}
+
+/// The identifier of a sub-view added to a window.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct SubViewId(pub(crate) u64);
+
+impl SubView {
+    /// Creates a sub-view rendering `scene` with `camera`.
+    ///
+    /// The scene can be a node of the main scene, which is then rendered both in the main scene
+    /// and in the sub-view, or a separate scene graph. The rectangle is given in pixels, with
+    /// `(x, y)` its top-left corner relative to the top-left corner of the window.
+    pub fn new(
+        scene: SceneNode,
+        camera: Box<dyn Camera>,
+        x: u32,
+        y: u32,
+        width: u32,
+        height: u32,
+    ) -> SubView {
+        SubView {
+            scene,
+            camera,
+            rect: (x, y, width, height),
+            background: None,
+            light: Light::StickToCamera,
+            visible: true,
+        }
+    }
+
+    /// The scene rendered by this sub-view.
+    pub fn scene(&self) -> &SceneNode {
+        &self.scene
+    }
+
+    /// A mutable reference to the scene rendered by this sub-view.
+    pub fn scene_mut(&mut self) -> &mut SceneNode {
+        &mut self.scene
+    }
+
+    /// The camera of this sub-view.
+    pub fn camera(&self) -> &dyn Camera {
+        &*self.camera
+    }
+
+    /// A mutable reference to the camera of this sub-view.
+    pub fn camera_mut(&mut self) -> &mut dyn Camera {
+        &mut *self.camera
+    }
+
+    /// The rectangle `(x, y, width, height)` covered by this sub-view, in pixels from the
+    /// top-left corner of the window.
+    pub fn rect(&self) -> (u32, u32, u32, u32) {
+        self.rect
+    }
+
+    /// Moves and resizes the rectangle covered by this sub-view.
+    pub fn set_rect(&mut self, x: u32, y: u32, width: u32, height: u32) {
+        self.rect = (x, y, width, height)
+    }
+
+    /// Fills the rectangle of this sub-view with a color before rendering its scene.
+    pub fn set_background_color(&mut self, color: impl Into<Color>) {
+        self.background = Some(color.into().to_render().coords)
+    }
+
+    /// Renders the scene of this sub-view directly on top of the main scene, without filling its
+    /// rectangle first. This is the default.
+    pub fn set_transparent_background(&mut self) {
+        self.background = None
+    }
+
+    /// The background color of this sub-view, if any.
+    pub fn background_color(&self) -> Option<Vector3<f32>> {
+        self.background
+    }
+
+    /// Sets the light used to render the scene of this sub-view. Defaults to
+    /// `Light::StickToCamera`.
+    pub fn set_light(&mut self, light: Light) {
+        self.light = light
+    }
+
+    /// Shows or hides this sub-view.
+    pub fn set_visible(&mut self, visible: bool) {
+        self.visible = visible
+    }
+
+    /// Whether this sub-view is rendered.
+    pub fn is_visible(&self) -> bool {
+        self.visible
+    }
+
+    // Renders the scene into the rectangle of this sub-view, in a window of size `(w, h)`.
+    pub(crate) fn render(&mut self, canvas: &Canvas, w: u32, h: u32) {
+        let (x, y, width, height) = self.rect;
+
+        if !self.visible || width == 0 || height == 0 || x >= w || y >= h {
+            return;
+        }
+
+        let ctxt = Context::get();
+        let gl_y = h as i32 - y as i32 - height as i32;
+        verify!(ctxt.viewport(x as i32, gl_y, width as i32, height as i32));
+        verify!(ctxt.scissor(x as i32, gl_y, width as i32, height as i32));
+
+        if let Some(bg) = self.background {
+            verify!(ctxt.clear_color(bg.x, bg.y, bg.z, 1.0));
+            verify!(ctxt.clear(Context::COLOR_BUFFER_BIT));
+        }
+
+        set_depth_convention(self.camera.reversed_depth());
+        verify!(ctxt.clear(Context::DEPTH_BUFFER_BIT));
+
+        self.camera
+            .handle_event(canvas, &WindowEvent::FramebufferSize(width, height));
+        self.scene.render(0, &mut *self.camera, &self.light);
+        set_depth_convention(false);
+    }
+}
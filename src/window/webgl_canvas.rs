@@ -5,14 +5,15 @@ use std::ops::DerefMut;
 use std::rc::Rc;
 use std::sync::mpsc::Sender;
 
-use crate::context::Context;
+use crate::context::{Context, GLContext};
 use crate::event::{Action, Key, Modifiers, MouseButton, TouchAction, WindowEvent};
 use crate::window::{AbstractCanvas, CanvasSetup, RenderLoopClosure};
 use image::{GenericImage, Pixel};
 use wasm_bindgen::closure::Closure;
 use wasm_bindgen::{JsCast, JsValue};
 use web_sys::{
-    EventTarget, HtmlCanvasElement, KeyboardEvent, MouseEvent, TouchEvent, UiEvent, WheelEvent,
+    Event, EventTarget, HtmlCanvasElement, KeyboardEvent, MouseEvent, TouchEvent, UiEvent,
+    WheelEvent,
 };
 
 struct WebGLCanvasData {
@@ -85,6 +86,9 @@ pub struct WebGLCanvas {
     data: Rc<RefCell<WebGLCanvasData>>,
     #[allow(dead_code)]
     event_listeners: Vec<EventListener>,
+    // The listeners of the losses and restorations of the WebGL context.
+    #[allow(dead_code)]
+    context_listeners: [EventListenerHandle<dyn FnMut(Event)>; 2],
 }
 
 impl Drop for WebGLCanvas {
@@ -129,7 +133,7 @@ impl AbstractCanvas for WebGLCanvas {
                 .unwrap()
                 .dyn_into::<web_sys::WebGlRenderingContext>()
                 .unwrap();
-            glow::Context::from_webgl1_context(webgl_context)
+            GLContext::new(glow::Context::from_webgl1_context(webgl_context))
         });
 
         let w = (canvas.offset_width() as f64 * initial_scale_factor) as u32;
@@ -418,9 +422,32 @@ impl AbstractCanvas for WebGLCanvas {
         let listener = EventListenerHandle::new(&data.borrow().canvas, "keyup", callback);
         event_listeners.push(EventListener::Keyboard(listener));
 
+        let edata = data.clone();
+        let callback = Closure::wrap(Box::new(move |e: Event| {
+            // Without this, the browser never restores the context.
+            e.prevent_default();
+            let _ = edata
+                .borrow_mut()
+                .pending_events
+                .push(WindowEvent::ContextLost);
+        }) as Box<dyn FnMut(_)>);
+        let lost_listener =
+            EventListenerHandle::new(&data.borrow().canvas, "webglcontextlost", callback);
+
+        let edata = data.clone();
+        let callback = Closure::wrap(Box::new(move |_: Event| {
+            let _ = edata
+                .borrow_mut()
+                .pending_events
+                .push(WindowEvent::ContextRestored);
+        }) as Box<dyn FnMut(_)>);
+        let restored_listener =
+            EventListenerHandle::new(&data.borrow().canvas, "webglcontextrestored", callback);
+
         WebGLCanvas {
             data,
             event_listeners,
+            context_listeners: [lost_listener, restored_listener],
         }
     }
 
@@ -2,42 +2,77 @@
 /*
  * FIXME: this file is too big. Some heavy refactoring need to be done here.
  */
-use std::cell::RefCell;
+use std::cell::{Ref, RefCell};
+use std::collections::hash_map::DefaultHasher;
+use std::hash::Hasher;
 use std::iter::repeat;
-use std::path::Path;
+use std::path::{Path, PathBuf};
 use std::rc::Rc;
 use std::sync::mpsc::{self, Receiver};
 use std::time::Duration;
 
-use na::{Point2, Point3, Vector2, Vector3};
+use na::{Matrix3, Point2, Point3, Vector2, Vector3};
 
+use crate::annotations::Annotations;
 use crate::camera::{ArcBall, Camera};
+use crate::color::Color;
 use crate::context::Context;
-use crate::event::{Action, EventManager, Key, WindowEvent};
-use crate::light::Light;
+use crate::event::{Action, EventManager, GamepadState, Key, MouseButton, WindowEvent};
+use crate::light::{self, Light, LightId, LightKind, LightSource};
+use crate::load_error::Error;
+use crate::loader::import::ImportOptions;
+use crate::loader::progress::LoadProgress;
+use crate::measure::{MeasureRenderer, Measurements};
+use crate::overlay::{AxisGizmo, ColormapLegend, Console, OverlayContext, ScaleBar};
 use crate::planar_camera::{FixedView, PlanarCamera};
 use crate::planar_line_renderer::PlanarLineRenderer;
 use crate::post_processing::PostProcessingEffect;
 #[cfg(feature = "conrod")]
 use crate::renderer::ConrodRenderer;
-use crate::renderer::{LineRenderer, PointRenderer, Renderer};
+use crate::renderer::{
+    Backdrop, DebugRenderBackend, GroundFade, Instance, InstancedMesh, InstancedMeshRenderer,
+    LineRenderer, OverlayLayer, PanoramaBackground, PanoramaProjection, PointRenderer, Renderer,
+    Resampler, TrailRenderer, VectorField, VectorFieldRenderer,
+};
 use crate::resource::{
-    FramebufferManager, Mesh, PlanarMesh, RenderTarget, Texture, TextureManager,
+    FramebufferManager, Mesh, MeshLoadOptions, PlanarMesh, RenderTarget, Texture, TextureManager,
+};
+use crate::scene::{self, shadow_map, validation};
+use crate::scene::{
+    ClothNode, ContactShadows, DepthPrepass, LineJoin, NineSlice, OcclusionCuller, PickHit,
+    PlanarPath, PlanarSceneNode, QueuedNode, RopeNode, SceneCommandApplier, SceneCommandQueue,
+    SceneNode, ShadowCascades, ShadowFilter, ShadowMap, ShadowSettings, SpatialIndex,
+    ValidationError,
 };
-use crate::scene::{PlanarSceneNode, SceneNode};
-use crate::text::{Font, TextRenderer};
+use crate::text::{Font, TextRenderer, TextSpan};
 use crate::window::canvas::CanvasSetup;
-use crate::window::{Canvas, RenderLoopClosure, State};
+use crate::window::clear;
+use crate::window::panorama::{self, CubeFaceCamera};
+use crate::window::snapshot;
+use crate::window::{
+    Canvas, ClearFlags, GpuProfile, GpuProfiler, RenderLoopClosure, SnapFormat, SnapOptions, State,
+    SubView, SubViewId,
+};
 use image::imageops;
+use image::{DynamicImage, ImageBuffer, ImageError, ImageResult, Rgb};
 use image::{GenericImage, Pixel};
-use image::{ImageBuffer, Rgb};
+use ncollide3d::bounding_volume::AABB;
 use ncollide3d::procedural::TriMesh;
+use ncollide3d::query::Ray;
 
 #[cfg(feature = "conrod")]
 use std::collections::HashMap;
 
 use super::window_cache::WindowCache;
 
+// The distance to the endpoints of a line of sight below which the obstacles are ignored.
+const LINE_OF_SIGHT_MARGIN: f32 = 1.0e-4;
+// The maximum render scale, the resampling averaging at most 4x4 texels per pixel.
+const MAX_RENDER_SCALE: f32 = 4.0;
+
+// A closure called with the camera and the index of the pass around the rendering of the scene.
+type RenderHook = Box<dyn FnMut(&mut dyn Camera, usize)>;
+
 static DEFAULT_WIDTH: u32 = 800u32;
 static DEFAULT_HEIGHT: u32 = 600u32;
 
@@ -88,19 +123,63 @@ pub struct Window {
     min_dur_per_frame: Option<Duration>,
     scene: SceneNode,
     scene2: PlanarSceneNode,
+    scene_commands: SceneCommandApplier,
+    spatial_index: RefCell<SpatialIndex>,
+    floating_origin: Option<f32>,
+    interpolation_alpha: f32,
     light_mode: Light, // FIXME: move that to the scene graph
+    light_debug: bool,
+    lights: Vec<(LightId, LightSource)>,
+    next_light_id: u64,
     background: Vector3<f32>,
+    // The alpha the background is cleared with, 0.0 only for snapshots with an alpha channel.
+    background_alpha: f32,
+    background_panorama: Option<PanoramaBackground>,
+    backdrop: Option<Backdrop>,
+    clear_flags: ClearFlags,
+    // The rectangle cleared at each frame, in window coordinates, if not the whole window.
+    clear_region: Option<(u32, u32, u32, u32)>,
+    pre_render_hook: Option<RenderHook>,
+    post_render_hook: Option<RenderHook>,
     line_renderer: LineRenderer,
+    trail_renderer: TrailRenderer,
+    vector_field_renderer: VectorFieldRenderer,
+    instanced_mesh_renderer: InstancedMeshRenderer,
     planar_line_renderer: PlanarLineRenderer,
     point_renderer: PointRenderer,
     text_renderer: TextRenderer,
+    overlay_line_renderer: PlanarLineRenderer,
+    overlay_camera: FixedView,
+    axis_gizmo: Option<AxisGizmo>,
+    scale_bar: Option<ScaleBar>,
+    colormap_legend: Option<ColormapLegend>,
+    measurements: Measurements,
+    annotations: Annotations,
+    sub_views: Vec<(SubViewId, SubView)>,
+    next_sub_view_id: u64,
+    measure_renderer: MeasureRenderer,
+    console: Option<Console>,
+    gpu_profiler: Option<GpuProfiler>,
+    occlusion_culler: Option<OcclusionCuller>,
+    depth_prepass: Option<DepthPrepass>,
+    frustum_culling: bool,
+    shadow_map: Option<ShadowMap>,
+    shadow_settings: ShadowSettings,
+    overlay_layer: Option<OverlayLayer>,
     framebuffer_manager: FramebufferManager,
     post_process_render_target: RenderTarget,
+    // Whether the last frame was rendered into `post_process_render_target`.
+    post_processed: bool,
+    render_scale: f32,
+    // The target the scene is rendered into when the render scale is not 1, with its size.
+    scaled_render_target: Option<(RenderTarget, (u32, u32))>,
+    resampler: Option<Resampler>,
     #[cfg(not(target_arch = "wasm32"))]
     curr_time: std::time::Instant,
     planar_camera: Rc<RefCell<FixedView>>,
     camera: Rc<RefCell<ArcBall>>,
     should_close: bool,
+    context_lost: bool,
     #[cfg(feature = "conrod")]
     conrod_context: ConrodContext,
     canvas: Canvas,
@@ -209,10 +288,330 @@ impl Window {
 
     /// Sets the background color.
     #[inline]
-    pub fn set_background_color(&mut self, r: f32, g: f32, b: f32) {
-        self.background.x = r;
-        self.background.y = g;
-        self.background.z = b;
+    pub fn set_background_color(&mut self, color: impl Into<Color>) {
+        self.background = color.into().to_render().coords;
+    }
+
+    /// Sets the buffers cleared at the beginning of each frame.
+    ///
+    /// By default, the color and depth buffers are cleared.
+    #[inline]
+    pub fn set_clear_flags(&mut self, flags: ClearFlags) {
+        self.clear_flags = flags;
+    }
+
+    /// The buffers cleared at the beginning of each frame.
+    #[inline]
+    pub fn clear_flags(&self) -> ClearFlags {
+        self.clear_flags
+    }
+
+    /// Restricts the clear at the beginning of each frame to a rectangle of the window.
+    ///
+    /// The rectangle is given by the coordinates of its top-left corner, its width, and its
+    /// height, in physical pixels. The background gradient, panorama, and ground disc are only
+    /// drawn in this rectangle. Use `None` to clear the whole window, which is the default.
+    #[inline]
+    pub fn set_clear_region(&mut self, region: Option<(u32, u32, u32, u32)>) {
+        self.clear_region = region;
+    }
+
+    /// The rectangle of the window cleared at the beginning of each frame, if not the whole
+    /// window.
+    #[inline]
+    pub fn clear_region(&self) -> Option<(u32, u32, u32, u32)> {
+        self.clear_region
+    }
+
+    /// Sets the resolution the scene is rendered at, relative to the resolution of the window.
+    ///
+    /// With a factor larger than 1, the scene is rendered into a larger off-screen image that is
+    /// then averaged down to the window, i.e., supersampled, for high quality screenshots. With a
+    /// factor smaller than 1, it is rendered at a lower resolution and upscaled, to reduce the
+    /// load of weak GPUs. The planar scene, the sub-views, and the overlays are always rendered
+    /// at the resolution of the window. The factor must be in `(0, 4]`, and defaults to 1.
+    ///
+    /// The clear region and the stereo cameras are not supported when the factor is not 1.
+    pub fn set_render_scale(&mut self, factor: f32) {
+        assert!(
+            factor > 0.0 && factor <= MAX_RENDER_SCALE,
+            "The render scale must be in (0, 4]."
+        );
+
+        self.render_scale = factor;
+
+        if factor == 1.0 {
+            self.scaled_render_target = None;
+        }
+    }
+
+    /// The resolution the scene is rendered at, relative to the resolution of the window.
+    #[inline]
+    pub fn render_scale(&self) -> f32 {
+        self.render_scale
+    }
+
+    /// Enables or disables a depth-only rendering of the opaque objects before they are shaded.
+    ///
+    /// The depth buffer is then filled before the objects are shaded, so that only their visible
+    /// fragments are shaded. This speeds up the rendering of scenes where many objects hide each
+    /// other, e.g., vegetation or dense assemblies, at the cost of drawing the opaque objects
+    /// twice. Objects whose material moves their vertices away from their mesh may be hidden by
+    /// the pre-pass. Disabled by default.
+    pub fn set_depth_prepass(&mut self, enabled: bool) {
+        if enabled != self.depth_prepass.is_some() {
+            self.depth_prepass = if enabled {
+                Some(DepthPrepass::new())
+            } else {
+                None
+            };
+        }
+    }
+
+    /// Whether the opaque objects are rendered in a depth-only pass before being shaded.
+    #[inline]
+    pub fn depth_prepass_enabled(&self) -> bool {
+        self.depth_prepass.is_some()
+    }
+
+    /// Enables or disables the culling of the objects outside of the view frustum.
+    ///
+    /// The bounding box of the mesh of each object is tested against the view frustum at each
+    /// pass, and the object is not drawn if the box is entirely outside. The boxes of the meshes
+    /// are cached, and computed again after their vertices have been modified on RAM. Skinned
+    /// objects are never culled, but objects whose vertices are modified on the GPU, e.g., with
+    /// `SceneNode::write_vertices_gpu` or by a custom material, may be culled while visible.
+    /// With the `parallel` feature, the boxes are tested on multiple threads. Disabled by default.
+    pub fn set_frustum_culling(&mut self, enabled: bool) {
+        self.frustum_culling = enabled
+    }
+
+    /// Whether the objects outside of the view frustum are culled.
+    #[inline]
+    pub fn frustum_culling_enabled(&self) -> bool {
+        self.frustum_culling
+    }
+
+    /// Enables or disables the shadows cast by the light on the objects drawn with the default
+    /// material.
+    ///
+    /// The depths of the shadow casters seen from the light are rendered into a shadow map at
+    /// each frame, before the scene. The objects casting and receiving shadows are chosen with
+    /// `SceneNode::set_shadow_caster` and `SceneNode::set_receive_shadows`. The light being a
+    /// point light, the shadows are only cast within a cone of 160° when it is among the shadow
+    /// casters. Disabled by default.
+    pub fn set_shadow_mapping(&mut self, enabled: bool) {
+        if enabled != self.shadow_map.is_some() {
+            self.shadow_map = if enabled {
+                Some(ShadowMap::new())
+            } else {
+                None
+            };
+        }
+    }
+
+    /// Whether the light casts shadows on the objects drawn with the default material.
+    #[inline]
+    pub fn shadow_mapping_enabled(&self) -> bool {
+        self.shadow_map.is_some()
+    }
+
+    /// Splits the shadow map into cascades fit to slices of the view frustum of the camera, or
+    /// uses a single shadow map fit to the shadow casters if `None`.
+    ///
+    /// The cascades close to the camera cover less space than the far ones, so large scenes
+    /// get detailed shadows near the camera and coarse ones far from it. The light is then
+    /// considered infinitely far away, in its direction from the center of the shadow casters.
+    /// Each cascade has a quarter of the texels of the shadow map. Only used if shadow mapping
+    /// is enabled, see `set_shadow_mapping`.
+    pub fn set_shadow_cascades(&mut self, cascades: Option<ShadowCascades>) {
+        self.shadow_settings.cascades = cascades;
+    }
+
+    /// The cascades of the shadow map, if any.
+    #[inline]
+    pub fn shadow_cascades(&self) -> Option<&ShadowCascades> {
+        self.shadow_settings.cascades.as_ref()
+    }
+
+    /// Enables or disables the tinting of the objects receiving shadows with the color of the
+    /// cascade of the shadow map they are shadowed with: red, green, blue, then yellow from the
+    /// nearest to the farthest.
+    pub fn set_shadow_cascade_debug(&mut self, enabled: bool) {
+        self.shadow_settings.cascade_debug = enabled;
+    }
+
+    /// Whether the objects receiving shadows are tinted with the color of their cascade.
+    #[inline]
+    pub fn shadow_cascade_debug_enabled(&self) -> bool {
+        self.shadow_settings.cascade_debug
+    }
+
+    /// Sets how the shadows cast by the light are filtered.
+    ///
+    /// `ShadowFilter::Pcss` gives the shadows soft penumbrae, sharp where the shadow casters
+    /// touch the shadowed objects and wider away from them, as cast by a light of the given
+    /// size, which is slower to render but suits still renders. Defaults to `ShadowFilter::Pcf`.
+    /// Only used if shadow mapping is enabled, see `set_shadow_mapping`.
+    pub fn set_shadow_filter(&mut self, filter: ShadowFilter) {
+        self.shadow_settings.filter = filter;
+    }
+
+    /// How the shadows cast by the light are filtered.
+    #[inline]
+    pub fn shadow_filter(&self) -> ShadowFilter {
+        self.shadow_settings.filter
+    }
+
+    /// Enables or disables the contact shadows, cast by the light where objects touch each other.
+    ///
+    /// The shadow map is too coarse to capture the shadows of the small gaps between objects, so
+    /// that objects resting on each other seem to float above them. The contact shadows are
+    /// found by marching a short ray from each point toward the light, through the distances
+    /// of the shadow casters to the camera, which only sees the casters facing it. Only used if
+    /// shadow mapping is enabled, see `set_shadow_mapping`. Disabled by default.
+    pub fn set_contact_shadows(&mut self, contact_shadows: Option<ContactShadows>) {
+        self.shadow_settings.contact_shadows = contact_shadows;
+    }
+
+    /// The contact shadows cast by the light, if enabled.
+    #[inline]
+    pub fn contact_shadows(&self) -> Option<&ContactShadows> {
+        self.shadow_settings.contact_shadows.as_ref()
+    }
+
+    /// Enables or disables the caching of the 2D content drawn over the 3D scene.
+    ///
+    /// The planar scene, the overlays, and the texts are then rendered into a layer composited
+    /// over each frame, and rendered again only when they change, which saves time for mostly
+    /// static heads-up displays. The layer is composited after the post-processing effects, so
+    /// they do not apply to the planar scene anymore. Disabled by default.
+    ///
+    /// Changes to the planar scene are detected through `PlanarSceneNode::data_mut`. Changes made
+    /// otherwise, e.g., to a shared mesh or to the content of a texture, require a call to
+    /// `invalidate_overlay_layer`.
+    pub fn set_overlay_layer_caching(&mut self, enabled: bool) {
+        if enabled != self.overlay_layer.is_some() {
+            self.overlay_layer = if enabled {
+                Some(OverlayLayer::new())
+            } else {
+                None
+            };
+        }
+    }
+
+    /// Whether the 2D content drawn over the 3D scene is cached into a layer.
+    #[inline]
+    pub fn overlay_layer_caching_enabled(&self) -> bool {
+        self.overlay_layer.is_some()
+    }
+
+    /// Forces the cached overlay layer, if enabled, to be rendered again at the next frame.
+    pub fn invalidate_overlay_layer(&mut self) {
+        if let Some(ref mut layer) = self.overlay_layer {
+            layer.invalidate()
+        }
+    }
+
+    /// Sets a closure called at each pass of the camera, after the background has been drawn and
+    /// before the scene is rendered.
+    ///
+    /// The closure is given the camera and the index of the pass. It may use the GL context
+    /// directly, e.g., to render with another library: the GL state the renderer relies on is
+    /// saved before it is called, and restored afterward.
+    pub fn set_pre_render_hook(&mut self, hook: impl FnMut(&mut dyn Camera, usize) + 'static) {
+        self.pre_render_hook = Some(Box::new(hook));
+    }
+
+    /// Removes the closure set by `set_pre_render_hook`, if any.
+    pub fn remove_pre_render_hook(&mut self) {
+        self.pre_render_hook = None;
+    }
+
+    /// Sets a closure called at each pass of the camera, after the scene has been rendered.
+    ///
+    /// The closure is given the camera and the index of the pass. As with
+    /// `set_pre_render_hook`, the GL state is saved before it is called, and restored afterward.
+    pub fn set_post_render_hook(&mut self, hook: impl FnMut(&mut dyn Camera, usize) + 'static) {
+        self.post_render_hook = Some(Box::new(hook));
+    }
+
+    /// Removes the closure set by `set_post_render_hook`, if any.
+    pub fn remove_post_render_hook(&mut self) {
+        self.post_render_hook = None;
+    }
+
+    /// Displays an equirectangular image around the camera, behind the scene.
+    ///
+    /// The image follows the rotation of the camera, but not its translation, as if it was
+    /// infinitely far away. See `PanoramaProjection::Spherical` for the orientation of the image.
+    pub fn set_background_panorama(&mut self, texture: Rc<Texture>) {
+        self.set_background_panorama_with_projection(texture, PanoramaProjection::Spherical)
+    }
+
+    /// Displays a panoramic image with the given projection around the camera, behind the scene.
+    pub fn set_background_panorama_with_projection(
+        &mut self,
+        texture: Rc<Texture>,
+        projection: PanoramaProjection,
+    ) {
+        self.background_panorama = Some(PanoramaBackground::new(texture, projection));
+    }
+
+    /// Removes the panoramic image displayed behind the scene, if any.
+    pub fn remove_background_panorama(&mut self) {
+        self.background_panorama = None;
+    }
+
+    /// Fills the background with a vertical gradient, from `top` at the top of the window to
+    /// `bottom` at its bottom, instead of the background color.
+    ///
+    /// A background panorama is displayed over the gradient.
+    pub fn set_background_gradient(&mut self, top: impl Into<Color>, bottom: impl Into<Color>) {
+        let gradient = Some((top.into(), bottom.into()));
+        self.backdrop
+            .get_or_insert_with(Backdrop::new)
+            .set_gradient(gradient);
+    }
+
+    /// Removes the background gradient, if any, to fill the background with its color again.
+    pub fn remove_background_gradient(&mut self) {
+        if let Some(ref mut backdrop) = self.backdrop {
+            backdrop.set_gradient(None);
+        }
+
+        self.drop_empty_backdrop();
+    }
+
+    /// The top and bottom colors of the background gradient, if any.
+    pub fn background_gradient(&self) -> Option<(Color, Color)> {
+        self.backdrop.as_ref().and_then(|b| b.gradient())
+    }
+
+    /// Sets the ground disc drawn behind the scene, fading into the background.
+    ///
+    /// Use `None` to remove it.
+    pub fn set_ground_fade(&mut self, ground: Option<GroundFade>) {
+        if ground.is_some() {
+            self.backdrop
+                .get_or_insert_with(Backdrop::new)
+                .set_ground(ground);
+        } else if let Some(ref mut backdrop) = self.backdrop {
+            backdrop.set_ground(None);
+        }
+
+        self.drop_empty_backdrop();
+    }
+
+    /// The ground disc drawn behind the scene, if any.
+    pub fn ground_fade(&self) -> Option<GroundFade> {
+        self.backdrop.as_ref().and_then(|b| b.ground())
+    }
+
+    fn drop_empty_backdrop(&mut self) {
+        if self.backdrop.as_ref().is_some_and(|b| b.is_empty()) {
+            self.backdrop = None;
+        }
     }
 
     /// Set the size of all points that will be rendered.
@@ -235,8 +634,14 @@ impl Window {
     /// The line is being drawn only during the next frame after this call.
     /// Therefore, this call must be executed at as many frames as you want it to remain visible.
     #[inline]
-    pub fn draw_line(&mut self, a: &Point3<f32>, b: &Point3<f32>, color: &Point3<f32>) {
-        self.line_renderer.draw_line(*a, *b, *color);
+    pub fn draw_line(
+        &mut self,
+        a: &(impl Into<Point3<f32>> + Copy),
+        b: &(impl Into<Point3<f32>> + Copy),
+        color: &(impl Into<Color> + Copy),
+    ) {
+        self.line_renderer
+            .draw_line((*a).into(), (*b).into(), (*color).into().to_render());
     }
 
     /// Draws a 2D line to be drawn during the next render.
@@ -244,14 +649,167 @@ impl Window {
     /// The line is being drawn only during the next frame after this call.
     /// Therefore, this call must be executed at as many frames as you want it to remain visible.
     #[inline]
-    pub fn draw_planar_line(&mut self, a: &Point2<f32>, b: &Point2<f32>, color: &Point3<f32>) {
-        self.planar_line_renderer.draw_line(*a, *b, *color);
+    pub fn draw_planar_line(
+        &mut self,
+        a: &(impl Into<Point2<f32>> + Copy),
+        b: &(impl Into<Point2<f32>> + Copy),
+        color: &(impl Into<Color> + Copy),
+    ) {
+        self.planar_line_renderer
+            .draw_line((*a).into(), (*b).into(), (*color).into().to_render());
     }
 
     /// Adds a point to be drawn during the next frame.
     #[inline]
-    pub fn draw_point(&mut self, pt: &Point3<f32>, color: &Point3<f32>) {
-        self.point_renderer.draw_point(*pt, *color);
+    pub fn draw_point(
+        &mut self,
+        pt: &(impl Into<Point3<f32>> + Copy),
+        color: &(impl Into<Color> + Copy),
+    ) {
+        self.point_renderer
+            .draw_point((*pt).into(), (*color).into().to_render());
+    }
+
+    /// Sets the orientation gizmo drawn in the bottom-left corner of the window.
+    ///
+    /// Use `None` to hide it. If the gizmo is clickable, clicking one of its axes snaps the
+    /// camera to that axis.
+    #[inline]
+    pub fn set_axis_gizmo(&mut self, gizmo: Option<AxisGizmo>) {
+        self.axis_gizmo = gizmo;
+    }
+
+    /// Sets the scale bar drawn in the bottom-right corner of the window.
+    ///
+    /// Use `None` to hide it.
+    #[inline]
+    pub fn set_scale_bar(&mut self, scale_bar: Option<ScaleBar>) {
+        self.scale_bar = scale_bar;
+    }
+
+    /// Sets the colormap legend drawn along the right edge of the window.
+    ///
+    /// Use `None` to hide it.
+    #[inline]
+    pub fn set_colormap_legend(&mut self, legend: Option<ColormapLegend>) {
+        self.colormap_legend = legend;
+    }
+
+    /// The distance, angle and radius measurements drawn by this window.
+    #[inline]
+    pub fn measurements(&self) -> &Measurements {
+        &self.measurements
+    }
+
+    /// A mutable reference to the measurements drawn by this window, to add or remove some.
+    #[inline]
+    pub fn measurements_mut(&mut self) -> &mut Measurements {
+        &mut self.measurements
+    }
+
+    /// The callouts drawn by this window.
+    #[inline]
+    pub fn annotations(&self) -> &Annotations {
+        &self.annotations
+    }
+
+    /// A mutable reference to the callouts drawn by this window, to add or remove some, or to
+    /// hide some of their layers.
+    #[inline]
+    pub fn annotations_mut(&mut self) -> &mut Annotations {
+        &mut self.annotations
+    }
+
+    /// Adds a sub-view rendered on top of the main scene.
+    ///
+    /// Sub-views are rendered in the order they were added.
+    pub fn add_sub_view(&mut self, view: SubView) -> SubViewId {
+        let id = SubViewId(self.next_sub_view_id);
+        self.next_sub_view_id += 1;
+        self.sub_views.push((id, view));
+        id
+    }
+
+    /// Removes a sub-view. Returns it if it existed.
+    pub fn remove_sub_view(&mut self, id: SubViewId) -> Option<SubView> {
+        let i = self.sub_views.iter().position(|v| v.0 == id)?;
+        Some(self.sub_views.remove(i).1)
+    }
+
+    /// A reference to a sub-view, if it exists.
+    pub fn sub_view(&self, id: SubViewId) -> Option<&SubView> {
+        self.sub_views.iter().find(|v| v.0 == id).map(|v| &v.1)
+    }
+
+    /// A mutable reference to a sub-view, if it exists.
+    pub fn sub_view_mut(&mut self, id: SubViewId) -> Option<&mut SubView> {
+        self.sub_views
+            .iter_mut()
+            .find(|v| v.0 == id)
+            .map(|v| &mut v.1)
+    }
+
+    /// Enables or disables the measurement of the GPU time spent in each rendering pass.
+    ///
+    /// Returns `false` if GPU timer queries are not supported by the current context, in which
+    /// case profiling remains disabled. The results are available through `gpu_profile` a few
+    /// frames later.
+    pub fn set_gpu_profiling(&mut self, enabled: bool) -> bool {
+        if !enabled {
+            self.gpu_profiler = None;
+        } else if self.gpu_profiler.is_none() {
+            self.gpu_profiler = GpuProfiler::new();
+        }
+
+        self.gpu_profiler.is_some() == enabled
+    }
+
+    /// Enables or disables the debug validation layer.
+    ///
+    /// When enabled, the objects are checked before being drawn: non-finite transformations,
+    /// meshes with invalid indices, only degenerate triangles, or missing attributes, and invalid
+    /// textures are reported once each, as warnings naming the node at fault. This is slow, and
+    /// only meant for debugging scenes rendering nothing, or not what is expected.
+    pub fn set_debug_validation(&mut self, enabled: bool) {
+        validation::set_enabled(enabled)
+    }
+
+    /// The problems found by the debug validation layer since the last call to this method.
+    pub fn validation_errors(&mut self) -> Vec<ValidationError> {
+        validation::take_errors()
+    }
+
+    /// The GPU time spent in each rendering pass of the most recent frame for which the
+    /// measurements are available.
+    ///
+    /// Returns `None` if GPU profiling is disabled, or if no result is available yet.
+    pub fn gpu_profile(&self) -> Option<&GpuProfile> {
+        self.gpu_profiler.as_ref().and_then(|p| p.profile())
+    }
+
+    /// Shows or hides an overlay displaying the result of `gpu_profile` in the top-right corner
+    /// of the window.
+    ///
+    /// This has no effect while GPU profiling is disabled.
+    pub fn set_gpu_profile_overlay(&mut self, visible: bool) {
+        if let Some(ref mut profiler) = self.gpu_profiler {
+            profiler.set_overlay(visible)
+        }
+    }
+
+    /// Sets the on-screen console.
+    ///
+    /// Use `None` to remove it. While the console is open, it consumes the keyboard events it
+    /// handles.
+    #[inline]
+    pub fn set_console(&mut self, console: Option<Console>) {
+        self.console = console;
+    }
+
+    /// A mutable reference to the on-screen console, if any.
+    #[inline]
+    pub fn console_mut(&mut self) -> Option<&mut Console> {
+        self.console.as_mut()
     }
 
     /// Adds a string to be drawn during the next frame.
@@ -262,9 +820,18 @@ impl Window {
         pos: &Point2<f32>,
         scale: f32,
         font: &Rc<Font>,
-        color: &Point3<f32>,
+        color: &(impl Into<Color> + Copy),
     ) {
-        self.text_renderer.draw_text(text, pos, scale, font, color);
+        let color = (*color).into().to_render();
+        self.text_renderer.draw_text(text, pos, scale, font, &color);
+    }
+
+    /// Adds a sequence of styled text spans to be drawn during the next frame.
+    ///
+    /// Spans can be built manually or with `TextSpan::parse_markup`.
+    #[inline]
+    pub fn draw_text_spans(&mut self, spans: &[TextSpan], pos: &Point2<f32>, scale: f32) {
+        self.text_renderer.draw_text_spans(spans, pos, scale);
     }
 
     /// Removes an object from the scene.
@@ -302,12 +869,119 @@ impl Window {
     /// # Arguments
     /// * `path`  - relative path to the obj file.
     /// * `scale` - scale to apply to the model.
-    pub fn add_obj(&mut self, path: &Path, mtl_dir: &Path, scale: Vector3<f32>) -> SceneNode {
+    ///
+    /// Returns an error if the obj file or one of its textures cannot be read or is malformed.
+    pub fn add_obj(
+        &mut self,
+        path: &Path,
+        mtl_dir: &Path,
+        scale: impl Into<Vector3<f32>>,
+    ) -> Result<SceneNode, Error> {
         self.scene.add_obj(path, mtl_dir, scale)
     }
 
+    /// Adds an obj model to the scene, its meshes being stored on the GPU as specified by
+    /// `options`.
+    ///
+    /// Use, e.g., `MeshLoadOptions::compact()` to reduce the GPU memory used by large meshes.
+    pub fn add_obj_with_options(
+        &mut self,
+        path: &Path,
+        mtl_dir: &Path,
+        scale: impl Into<Vector3<f32>>,
+        options: &MeshLoadOptions,
+    ) -> Result<SceneNode, Error> {
+        self.scene
+            .add_obj_with_options(path, mtl_dir, scale, options)
+    }
+
+    /// Adds an obj model to the scene, its coordinates being converted to the coordinate system
+    /// of kiss3d as specified by `import_options`.
+    ///
+    /// Use, e.g., `ImportOptions::z_up()` for a model exported from Blender with the `z` axis up.
+    pub fn add_obj_with_import_options(
+        &mut self,
+        path: &Path,
+        mtl_dir: &Path,
+        scale: impl Into<Vector3<f32>>,
+        options: &MeshLoadOptions,
+        import_options: &ImportOptions,
+    ) -> Result<SceneNode, Error> {
+        self.scene
+            .add_obj_with_import_options(path, mtl_dir, scale, options, import_options)
+    }
+
+    /// Adds an obj model to the scene like `add_obj_with_import_options`, reporting the bytes
+    /// read, the meshes built, and the textures decoded to `progress`.
+    ///
+    /// See the `loader::progress` module for an example.
+    pub fn add_obj_with_progress(
+        &mut self,
+        path: &Path,
+        mtl_dir: &Path,
+        scale: impl Into<Vector3<f32>>,
+        options: &MeshLoadOptions,
+        import_options: &ImportOptions,
+        progress: &mut dyn FnMut(LoadProgress),
+    ) -> Result<SceneNode, Error> {
+        self.scene
+            .add_obj_with_progress(path, mtl_dir, scale, options, import_options, progress)
+    }
+
+    /// Adds an obj model to the scene from the content of its file, e.g., embedded in the binary
+    /// with `include_bytes!`, its material libraries and textures being given by `resolver`.
+    ///
+    /// ```no_run
+    /// # use kiss3d::window::Window;
+    /// let mut window = Window::new("Kiss3d: embedded obj");
+    /// let obj = include_bytes!("../../examples/media/rust_logo/rust_logo.obj");
+    /// let mtl = include_bytes!("../../examples/media/rust_logo/rust_logo.mtl");
+    ///
+    /// window
+    ///     .add_obj_from_memory(obj, "rust_logo", [0.1, 0.1, 0.1], &mut |name| {
+    ///         (name == "rust_logo.mtl").then(|| mtl.to_vec())
+    ///     })
+    ///     .unwrap();
+    /// ```
+    ///
+    /// See `SceneNode::add_obj_from_memory` for details.
+    pub fn add_obj_from_memory(
+        &mut self,
+        data: &[u8],
+        name: &str,
+        scale: impl Into<Vector3<f32>>,
+        resolver: &mut dyn FnMut(&str) -> Option<Vec<u8>>,
+    ) -> Result<SceneNode, Error> {
+        self.scene.add_obj_from_memory(data, name, scale, resolver)
+    }
+
+    /// Adds the default scene of a glTF or GLB file to the scene.
+    ///
+    /// Returns an error if the file, one of its buffers, or one of its images cannot be read or
+    /// is malformed.
+    #[cfg(feature = "gltf")]
+    pub fn add_gltf(&mut self, path: &Path) -> Result<SceneNode, Error> {
+        self.scene.add_gltf(path)
+    }
+
+    /// Adds the default scene of a glTF or GLB file to the scene, its coordinates being converted
+    /// to the coordinate system of kiss3d as specified by `import_options`.
+    #[cfg(feature = "gltf")]
+    pub fn add_gltf_with_import_options(
+        &mut self,
+        path: &Path,
+        import_options: &ImportOptions,
+    ) -> Result<SceneNode, Error> {
+        self.scene
+            .add_gltf_with_import_options(path, import_options)
+    }
+
     /// Adds an unnamed mesh to the scene.
-    pub fn add_mesh(&mut self, mesh: Rc<RefCell<Mesh>>, scale: Vector3<f32>) -> SceneNode {
+    pub fn add_mesh(
+        &mut self,
+        mesh: Rc<RefCell<Mesh>>,
+        scale: impl Into<Vector3<f32>>,
+    ) -> SceneNode {
         self.scene.add_mesh(mesh, scale)
     }
 
@@ -322,7 +996,11 @@ impl Window {
 
     /// Creates and adds a new object using the geometry generated by a given procedural generator.
     /// Creates and adds a new object using a mesh descriptor.
-    pub fn add_trimesh(&mut self, descr: TriMesh<f32>, scale: Vector3<f32>) -> SceneNode {
+    pub fn add_trimesh(
+        &mut self,
+        descr: TriMesh<f32>,
+        scale: impl Into<Vector3<f32>>,
+    ) -> SceneNode {
         self.scene.add_trimesh(descr, scale)
     }
 
@@ -330,7 +1008,7 @@ impl Window {
     pub fn add_geom_with_name(
         &mut self,
         geometry_name: &str,
-        scale: Vector3<f32>,
+        scale: impl Into<Vector3<f32>>,
     ) -> Option<SceneNode> {
         self.scene.add_geom_with_name(geometry_name, scale)
     }
@@ -353,6 +1031,86 @@ impl Window {
         self.scene.add_sphere(r)
     }
 
+    /// Adds an ellipsoid showing a 3D gaussian distribution to the scene, e.g., the uncertainty of
+    /// an estimated position.
+    ///
+    /// The ellipsoid is centered at `mean`, its axes are the eigenvectors of the `covariance`
+    /// matrix, and its radii are `sigma` times the standard deviations along them. Use
+    /// `SceneNode::set_covariance_ellipsoid` on the returned node to update it.
+    ///
+    /// ```no_run
+    /// # use kiss3d::window::Window;
+    /// # use kiss3d::nalgebra::{Matrix3, Point3};
+    /// let mut window = Window::new("Kiss3d: covariance");
+    /// let covariance = Matrix3::new(0.5, 0.2, 0.0, 0.2, 0.3, 0.0, 0.0, 0.0, 0.1);
+    /// let mut ellipsoid = window.add_covariance_ellipsoid(&Point3::origin(), &covariance, 2.0);
+    /// ellipsoid.set_opacity(0.5);
+    ///
+    /// while window.render() {}
+    /// ```
+    pub fn add_covariance_ellipsoid(
+        &mut self,
+        mean: &Point3<f32>,
+        covariance: &Matrix3<f32>,
+        sigma: f32,
+    ) -> SceneNode {
+        self.scene.add_covariance_ellipsoid(mean, covariance, sigma)
+    }
+
+    /// Adds an empty field of arrows drawn by this window, e.g., to show a velocity field.
+    ///
+    /// ```no_run
+    /// # use kiss3d::window::Window;
+    /// # use kiss3d::colormap::Colormap;
+    /// # use kiss3d::nalgebra::{Point3, Vector3};
+    /// let mut window = Window::new("Kiss3d: vector field");
+    /// let origins: Vec<_> = (0..100).map(|i| Point3::new(i as f32 * 0.1, 0.0, 0.0)).collect();
+    /// let vectors: Vec<_> = origins.iter().map(|p| Vector3::new(0.0, p.x.sin(), 0.0)).collect();
+    /// let mut field = window.add_vector_field();
+    /// field.set_vectors(&origins, &vectors);
+    /// field.set_colormap(Colormap::Viridis, None);
+    ///
+    /// while window.render() {}
+    /// ```
+    pub fn add_vector_field(&mut self) -> VectorField {
+        self.vector_field_renderer.add()
+    }
+
+    /// Stops drawing a vector field added with `add_vector_field`.
+    pub fn remove_vector_field(&mut self, field: &VectorField) {
+        self.vector_field_renderer.remove(field)
+    }
+
+    /// Adds copies of a mesh drawn by this window with a single draw call, e.g., thousands of
+    /// identical cubes.
+    ///
+    /// ```no_run
+    /// # use kiss3d::window::Window;
+    /// # use kiss3d::renderer::Instance;
+    /// # use kiss3d::resource::MeshManager;
+    /// # use kiss3d::nalgebra::Isometry3;
+    /// let mut window = Window::new("Kiss3d: instancing");
+    /// let cube = MeshManager::get_global_manager(|mm| mm.get("cube")).unwrap();
+    /// let instances: Vec<_> = (0..1000)
+    ///     .map(|i| Instance::new(Isometry3::translation(i as f32 * 2.0, 0.0, 0.0)))
+    ///     .collect();
+    /// let cubes = window.add_instanced_mesh(cube, &instances);
+    ///
+    /// while window.render() {}
+    /// ```
+    pub fn add_instanced_mesh(
+        &mut self,
+        mesh: Rc<RefCell<Mesh>>,
+        instances: &[Instance],
+    ) -> InstancedMesh {
+        self.instanced_mesh_renderer.add(mesh, instances)
+    }
+
+    /// Stops drawing the copies of a mesh added with `add_instanced_mesh`.
+    pub fn remove_instanced_mesh(&mut self, mesh: &InstancedMesh) {
+        self.instanced_mesh_renderer.remove(mesh)
+    }
+
     /// Adds a cone to the scene. The cone is initially centered at (0, 0, 0) and points toward the
     /// positive `y` axis.
     ///
@@ -409,6 +1167,28 @@ impl Window {
         self.scene.add_quad(w, h, usubdivs, vsubdivs)
     }
 
+    /// Adds a cloth to the scene, simulated once per frame. See `ClothNode`.
+    ///
+    /// # Arguments
+    /// * `w` - the cloth width.
+    /// * `h` - the cloth height.
+    /// * `wsubdivs` - number of horizontal subdivisions. Must not be `0`.
+    /// * `hsubdivs` - number of vertical subdivisions. Must not be `0`.
+    pub fn add_cloth(&mut self, w: f32, h: f32, wsubdivs: usize, hsubdivs: usize) -> ClothNode {
+        self.scene.add_cloth(w, h, wsubdivs, hsubdivs)
+    }
+
+    /// Adds a rope to the scene, simulated once per frame. See `RopeNode`.
+    ///
+    /// # Arguments
+    /// * `length` - the rope length.
+    /// * `radius` - the radius of the tube rendering the rope.
+    /// * `segments` - the number of segments between the particles of the rope. Must not be
+    ///   `0`.
+    pub fn add_rope(&mut self, length: f32, radius: f32, segments: usize) -> RopeNode {
+        self.scene.add_rope(length, radius, segments)
+    }
+
     /// Adds a double-sided quad with the specified vertices.
     pub fn add_quad_with_vertices(
         &mut self,
@@ -421,10 +1201,21 @@ impl Window {
     }
 
     /// Load a texture from a file and return a reference to it.
-    pub fn add_texture(&mut self, path: &Path, name: &str) -> Rc<Texture> {
+    ///
+    /// Returns an error if the file cannot be read or decoded.
+    pub fn add_texture(&mut self, path: &Path, name: &str) -> Result<Rc<Texture>, Error> {
         TextureManager::get_global_manager(|tm| tm.add(path, name))
     }
 
+    /// Load a texture from a file in the background, from its lowest resolution mipmap to its
+    /// full resolution, and return a reference to it.
+    ///
+    /// See `TextureManager::add_streamed`.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn add_texture_streamed(&mut self, path: &Path, name: &str) -> Rc<Texture> {
+        TextureManager::get_global_manager(|tm| tm.add_streamed(path, name))
+    }
+
     /// Adds a rectangle to the scene. The rectangle is initially axis-aligned and centered at (0, 0, 0).
     ///
     /// # Arguments
@@ -454,6 +1245,38 @@ impl Window {
         self.scene2.add_convex_polygon(polygon, scale)
     }
 
+    /// Adds the filled interior of a path to the 2D scene.
+    ///
+    /// Returns `None` if the path does not enclose any area.
+    pub fn add_filled_path(
+        &mut self,
+        path: &PlanarPath,
+        scale: Vector2<f32>,
+    ) -> Option<PlanarSceneNode> {
+        self.scene2.add_filled_path(path, scale)
+    }
+
+    /// Adds the outline of a path to the 2D scene.
+    ///
+    /// Returns `None` if the path contains no segment.
+    pub fn add_stroked_path(
+        &mut self,
+        path: &PlanarPath,
+        width: f32,
+        join: LineJoin,
+    ) -> Option<PlanarSceneNode> {
+        self.scene2.add_stroked_path(path, width, join)
+    }
+
+    /// Adds a nine-slice rectangle to the 2D scene. The rectangle is initially axis-aligned and
+    /// centered at (0, 0).
+    ///
+    /// # Arguments
+    /// * `slice` - the size and borders of the rectangle
+    pub fn add_nine_slice(&mut self, slice: &NineSlice) -> PlanarSceneNode {
+        self.scene2.add_nine_slice(slice)
+    }
+
     /// Returns whether this window is closed or not.
     pub fn is_closed(&self) -> bool {
         false // FIXME
@@ -464,11 +1287,117 @@ impl Window {
         self.canvas.scale_factor()
     }
 
-    /// Sets the light mode. Only one light is supported.
+    /// Sets the main light, which is the one casting shadows and lighting every material.
+    ///
+    /// A light attached to a node of the scene with `SceneNode::attach_light` takes precedence.
+    /// More lights can be added with `add_light`.
     pub fn set_light(&mut self, pos: Light) {
         self.light_mode = pos;
     }
 
+    /// Adds a light to the scene, in addition to the main light.
+    ///
+    /// The added lights only light the objects drawn with the default material, and do not cast
+    /// shadows. Only the first `light::MAX_LIGHTS` lights are taken into account.
+    pub fn add_light(&mut self, light: LightSource) -> LightId {
+        let id = LightId(self.next_light_id);
+        self.next_light_id += 1;
+        self.lights.push((id, light));
+        id
+    }
+
+    /// Removes a light added with `add_light`. Returns it if it existed.
+    pub fn remove_light(&mut self, id: LightId) -> Option<LightSource> {
+        let i = self.lights.iter().position(|l| l.0 == id)?;
+        Some(self.lights.remove(i).1)
+    }
+
+    /// A reference to a light added with `add_light`, if it exists.
+    pub fn light(&self, id: LightId) -> Option<&LightSource> {
+        self.lights.iter().find(|l| l.0 == id).map(|l| &l.1)
+    }
+
+    /// A mutable reference to a light added with `add_light`, if it exists, e.g., to move it.
+    pub fn light_mut(&mut self, id: LightId) -> Option<&mut LightSource> {
+        self.lights.iter_mut().find(|l| l.0 == id).map(|l| &mut l.1)
+    }
+
+    /// Shows or hides a marker at the position of each light.
+    ///
+    /// The main light is drawn in yellow, and the lights it overrides, attached to other scene
+    /// nodes or set with `set_light`, are drawn in gray. The lights added with `add_light` are
+    /// drawn with their color, with a line along the axis of the spot lights and the outline of
+    /// the rectangular lights. The markers keep the same size on screen. A `Light::StickToCamera`
+    /// and the directional lights are not drawn, since they have no position. Disabled by
+    /// default.
+    pub fn set_light_debug(&mut self, enabled: bool) {
+        self.light_debug = enabled;
+    }
+
+    /// Whether a marker is drawn at the position of each light.
+    #[inline]
+    pub fn light_debug_enabled(&self) -> bool {
+        self.light_debug
+    }
+
+    // Draws the markers of the lights enabled by `set_light_debug`.
+    fn draw_light_markers(&mut self, camera: &dyn Camera) {
+        let eye = camera.eye();
+        let mut lights = self.scene.data().attached_lights();
+        let overridden = lights.iter().any(|(_, active)| *active);
+        lights.push((self.light_mode.clone(), !overridden));
+
+        for (light, is_active) in lights {
+            let pos = match light {
+                Light::Absolute(p) => p,
+                Light::StickToCamera => continue,
+            };
+            let color = if is_active {
+                Point3::new(1.0, 1.0, 0.0)
+            } else {
+                Point3::new(0.5, 0.5, 0.5)
+            };
+            let size = (pos - eye).norm() * 0.02;
+
+            self.draw_light(&pos, size, &color);
+        }
+
+        for i in 0..self.lights.len() {
+            let light = &self.lights[i].1;
+            let pos = match light.position() {
+                Some(pos) => pos,
+                None => continue,
+            };
+            let color = light.color.to_render();
+            let size = (pos - eye).norm() * 0.02;
+            let axis = match light.kind {
+                LightKind::Spot { direction, .. } => Some(direction.normalize()),
+                _ => None,
+            };
+            let outline = match light.kind {
+                LightKind::Rect { right, up, .. } => Some([
+                    pos - right - up,
+                    pos + right - up,
+                    pos + right + up,
+                    pos - right + up,
+                ]),
+                _ => None,
+            };
+
+            self.draw_light(&pos, size, &color);
+
+            if let Some(axis) = axis {
+                self.draw_line(&pos, &(pos + axis * size * 6.0), &color);
+            }
+
+            if let Some(corners) = outline {
+                for j in 0..4 {
+                    self.draw_line(&corners[j], &corners[(j + 1) % 4], &color);
+                }
+            }
+        }
+    }
+
     /// Retrieve a mutable reference to the UI based on Conrod.
     #[cfg(feature = "conrod")]
     pub fn conrod_ui_mut(&mut self) -> &mut conrod::Ui {
@@ -523,6 +1452,16 @@ impl Window {
         Window::do_new(title, true, DEFAULT_WIDTH, DEFAULT_HEIGHT, None)
     }
 
+    /// Opens a hidden window with a custom size.
+    ///
+    /// # Arguments
+    /// * `title` - the window title.
+    /// * `width` - the window width.
+    /// * `height` - the window height.
+    pub fn new_hidden_with_size(title: &str, width: u32, height: u32) -> Window {
+        Window::do_new(title, true, width, height, None)
+    }
+
     /// Opens a window then calls a user-defined procedure.
     ///
     /// # Arguments
@@ -562,18 +1501,59 @@ impl Window {
 
         let mut usr_window = Window {
             should_close: false,
+            context_lost: false,
             min_dur_per_frame: None,
             canvas,
             events: Rc::new(event_receive),
             unhandled_events: Rc::new(RefCell::new(Vec::new())),
             scene: SceneNode::new_empty(),
             scene2: PlanarSceneNode::new_empty(),
+            scene_commands: SceneCommandApplier::new(),
+            spatial_index: RefCell::new(SpatialIndex::new(1.0)),
+            floating_origin: None,
+            interpolation_alpha: 1.0,
             light_mode: Light::Absolute(Point3::new(0.0, 10.0, 0.0)),
+            light_debug: false,
+            lights: Vec::new(),
+            next_light_id: 0,
             background: Vector3::new(0.0, 0.0, 0.0),
+            background_alpha: 1.0,
+            background_panorama: None,
+            backdrop: None,
+            clear_flags: ClearFlags::default(),
+            clear_region: None,
+            pre_render_hook: None,
+            post_render_hook: None,
             line_renderer: LineRenderer::new(),
+            trail_renderer: TrailRenderer::new(),
+            vector_field_renderer: VectorFieldRenderer::new(),
+            instanced_mesh_renderer: InstancedMeshRenderer::new(),
             planar_line_renderer: PlanarLineRenderer::new(),
             point_renderer: PointRenderer::new(),
             text_renderer: TextRenderer::new(),
+            overlay_line_renderer: PlanarLineRenderer::new(),
+            overlay_camera: FixedView::new(),
+            axis_gizmo: None,
+            scale_bar: None,
+            colormap_legend: None,
+            measurements: Measurements::new(),
+            annotations: Annotations::new(),
+            sub_views: Vec::new(),
+            next_sub_view_id: 0,
+            measure_renderer: MeasureRenderer::new(),
+            console: None,
+            gpu_profiler: None,
+            occlusion_culler: OcclusionCuller::new(),
+            depth_prepass: None,
+            frustum_culling: false,
+            shadow_map: None,
+            shadow_settings: ShadowSettings {
+                cascades: None,
+                cascade_debug: false,
+                filter: ShadowFilter::Pcf,
+                contact_shadows: None,
+            },
+            overlay_layer: None,
             #[cfg(feature = "conrod")]
             conrod_context: ConrodContext::new(width as f64, height as f64),
             post_process_render_target: FramebufferManager::new_render_target(
@@ -581,7 +1561,11 @@ impl Window {
                 height as usize,
                 true,
             ),
+            post_processed: false,
             framebuffer_manager: FramebufferManager::new(),
+            render_scale: 1.0,
+            scaled_render_target: None,
+            resampler: None,
             #[cfg(not(target_arch = "wasm32"))]
             curr_time: std::time::Instant::now(),
             planar_camera: Rc::new(RefCell::new(FixedView::new())),
@@ -599,24 +1583,157 @@ impl Window {
         let light = usr_window.light_mode.clone();
         usr_window.set_light(light);
 
-        usr_window
+        usr_window
+    }
+
+    /// Reference to the scene associated with this window.
+    #[inline]
+    pub fn scene(&self) -> &SceneNode {
+        &self.scene
+    }
+
+    /// Mutable reference to the scene associated with this window.
+    #[inline]
+    pub fn scene_mut(&mut self) -> &mut SceneNode {
+        &mut self.scene
+    }
+
+    /// The pickable object of the scene under a point of the window, as seen by the default
+    /// camera of the window.
+    ///
+    /// The point is expressed in the same coordinates as `cursor_pos`, so the object under the
+    /// mouse is picked with `window.cursor_pos().and_then(|(x, y)| window.pick(x, y))`. Use
+    /// `pick_with_camera` when rendering with another camera.
+    pub fn pick(&self, x: f64, y: f64) -> Option<PickHit> {
+        self.pick_with_camera(&*self.camera.borrow(), x, y)
+    }
+
+    /// The pickable object of the scene under a point of the window, as seen by `camera`.
+    ///
+    /// See `pick` for the coordinates of the point.
+    pub fn pick_with_camera(&self, camera: &dyn Camera, x: f64, y: f64) -> Option<PickHit> {
+        let size = self.size();
+        let size = Vector2::new(size.x as f32, size.y as f32);
+        let (origin, dir) = camera.unproject(&Point2::new(x as f32, y as f32), &size);
+
+        self.scene.pick(&Ray::new(origin, dir))
+    }
+
+    /// Whether the segment between two world-space points is not obstructed by the objects of
+    /// the scene.
+    ///
+    /// The segment is tested against the bounding volume hierarchies of the meshes of the
+    /// pickable objects, see `SceneNode::set_pickable`, so invisible collision proxies can block
+    /// the line of sight. The obstacles closer than `1.0e-4` to either point are ignored, so the
+    /// points may lie on the surface of an object, e.g., a sensor and its target.
+    pub fn is_visible_from(&self, point_a: &Point3<f32>, point_b: &Point3<f32>) -> bool {
+        let dir = point_b - point_a;
+        let dist = dir.norm();
+
+        if dist <= 2.0 * LINE_OF_SIGHT_MARGIN {
+            return true;
+        }
+
+        let dir = dir / dist;
+        let ray = Ray::new(point_a + dir * LINE_OF_SIGHT_MARGIN, dir);
+        let max_toi = dist - 2.0 * LINE_OF_SIGHT_MARGIN;
+        let segment = AABB::new(point_a.inf(point_b), point_a.sup(point_b));
+
+        self.updated_spatial_index()
+            .pickable_nodes_in_aabb(&segment)
+            .iter()
+            .all(|node| node.data().cast_ray_on_object(&ray, max_toi).is_none())
+    }
+
+    /// The nodes of the scene whose world-space bounding box intersects a sphere.
+    ///
+    /// The nodes are found with a spatial index of the scene, refreshed by this call for the
+    /// nodes that moved since the last query. Only the objects with triangles are indexed, and
+    /// the invisible ones are reported too.
+    pub fn nodes_in_sphere(&self, center: &Point3<f32>, radius: f32) -> Vec<SceneNode> {
+        self.updated_spatial_index().nodes_in_sphere(center, radius)
+    }
+
+    /// The nodes of the scene whose world-space bounding box intersects the view frustum of a
+    /// camera, e.g., to cull the objects it does not see.
+    ///
+    /// See `nodes_in_sphere` for the nodes considered.
+    pub fn nodes_in_frustum(&self, camera: &dyn Camera) -> Vec<SceneNode> {
+        self.updated_spatial_index().nodes_in_frustum(camera)
+    }
+
+    /// Sets the size of the cells of the spatial index used by the scene queries, e.g.,
+    /// `nodes_in_sphere`.
+    ///
+    /// The cells should be about the size of the typical object of the scene. Defaults to `1.0`.
+    pub fn set_spatial_index_cell_size(&mut self, cell_size: f32) {
+        self.spatial_index.get_mut().set_cell_size(cell_size)
+    }
+
+    fn updated_spatial_index(&self) -> Ref<'_, SpatialIndex> {
+        self.spatial_index.borrow_mut().update(&self.scene);
+        self.spatial_index.borrow()
+    }
+
+    /// A queue to modify the scene from other threads.
+    ///
+    /// The commands submitted to the queue are applied at the beginning of the next frame.
+    pub fn scene_command_queue(&self) -> SceneCommandQueue {
+        self.scene_commands.queue()
+    }
+
+    /// The scene node created by a command of the `scene_command_queue`.
+    ///
+    /// Returns `None` if the command has not been applied yet, or if the node has been removed.
+    pub fn queued_node(&self, node: QueuedNode) -> Option<SceneNode> {
+        self.scene_commands.node(node).cloned()
+    }
+
+    /// The current world origin, in double-precision world coordinates.
+    pub fn world_origin(&self) -> Point3<f64> {
+        scene::world_origin()
+    }
+
+    /// Moves the world origin, and the default camera so that it keeps its position in
+    /// double-precision world coordinates.
+    ///
+    /// The scene nodes positioned with `SceneNode::set_local_translation_f64` are moved
+    /// accordingly, so that the objects far from the origin of the world can be rendered without
+    /// jittering by keeping the world origin close to the camera.
+    pub fn set_world_origin(&mut self, origin: Point3<f64>) {
+        let offset = origin - scene::world_origin();
+        scene::set_world_origin(origin);
+        self.camera.borrow_mut().shift_origin(&offset);
+    }
+
+    /// Moves the world origin to the camera automatically whenever the camera gets farther than
+    /// `threshold` from it.
+    ///
+    /// The origin is moved at the beginning of each frame, and the camera is moved with
+    /// `Camera::shift_origin`, so this has no effect with cameras that do not implement it. Set to
+    /// `None` (the default) to disable it.
+    pub fn set_floating_origin(&mut self, threshold: Option<f32>) {
+        self.floating_origin = threshold;
     }
 
-    /// Reference to the scene associated with this window.
-    #[inline]
-    pub fn scene(&self) -> &SceneNode {
-        &self.scene
+    /// Sets the fraction of the way from their previous transformation to their current one at
+    /// which the interpolated nodes are rendered, usually `FixedTimestep::alpha`.
+    ///
+    /// See `SceneNode::set_interpolated_transformations`. The default value is 1.0, showing the
+    /// current transformations.
+    pub fn set_interpolation_alpha(&mut self, alpha: f32) {
+        self.interpolation_alpha = alpha.clamp(0.0, 1.0)
     }
 
-    /// Mutable reference to the scene associated with this window.
-    #[inline]
-    pub fn scene_mut(&mut self) -> &mut SceneNode {
-        &mut self.scene
+    /// The interpolation factor of the interpolated nodes.
+    pub fn interpolation_alpha(&self) -> f32 {
+        self.interpolation_alpha
     }
 
-    // FIXME: give more options for the snap size and offset.
     /// Read the pixels currently displayed to the screen.
     ///
+    /// See `snap_with` to exclude the overlays, or to choose the resolution of the snapshot.
+    ///
     /// # Arguments:
     /// * `out` - the output buffer. It is automatically resized.
     pub fn snap(&self, out: &mut Vec<u8>) {
@@ -662,6 +1779,302 @@ impl Window {
         imageops::flip_vertical(&img)
     }
 
+    /// Gets the current screen as an image with the given pixel format.
+    ///
+    /// The precision of the colors is limited by the one of the window framebuffer: 16-bit and
+    /// float formats avoid an additional quantization, and allow saving to 16-bit PNG or EXR
+    /// files. Depth formats read the depth buffer, which is not supported by WebGL.
+    pub fn snap_image_with_format(&self, format: SnapFormat) -> DynamicImage {
+        let (width, height) = self.canvas.size();
+        snapshot::read_framebuffer(0, 0, width as usize, height as usize, format).flipv()
+    }
+
+    /// Renders a 360° panorama of the scene seen from the position of the default camera.
+    ///
+    /// The scene is rendered into the six faces of a cube map, which are then resampled into an
+    /// equirectangular image of `resolution` by `resolution / 2` pixels. The center of the image
+    /// looks along the `-z` axis, with the `y` axis pointing up. The planar scene, overlays, and
+    /// post-processing effects are not rendered.
+    pub fn snap_panorama(&mut self, resolution: u32) -> ImageBuffer<Rgb<u8>, Vec<u8>> {
+        let face_size = resolution.div_ceil(4).max(1);
+        let (eye, (znear, zfar)) = {
+            let camera = self.camera.borrow();
+            (camera.eye(), camera.clip_planes())
+        };
+
+        let ctxt = Context::get();
+        let target =
+            FramebufferManager::new_render_target(face_size as usize, face_size as usize, false);
+        self.framebuffer_manager.select(&target);
+        verify!(ctxt.viewport(0, 0, face_size as i32, face_size as i32));
+        verify!(ctxt.scissor(0, 0, face_size as i32, face_size as i32));
+
+        let mut cameras = CubeFaceCamera::faces(eye, znear, zfar);
+        let faces: Vec<_> = cameras
+            .iter_mut()
+            .map(|camera| {
+                self.render_scene(camera, 0);
+                let size = face_size as usize;
+                snapshot::read_framebuffer(0, 0, size, size, SnapFormat::Rgb8).into_rgb8()
+            })
+            .collect();
+
+        self.framebuffer_manager
+            .select(&FramebufferManager::screen());
+        let (w, h) = (self.width(), self.height());
+        verify!(ctxt.viewport(0, 0, w as i32, h as i32));
+        verify!(ctxt.scissor(0, 0, w as i32, h as i32));
+
+        panorama::equirectangular(&cameras, &faces, resolution.max(1))
+    }
+
+    /// Renders the scene seen by `camera` into the color texture of `target`.
+    ///
+    /// The target is created with `FramebufferManager::new_render_target`, and its texture,
+    /// given by `target.texture()`, can then be applied to other objects with
+    /// `SceneNode::set_texture`, e.g., for mirrors, security camera screens, or minimaps. The
+    /// texture keeps the last image rendered into it, and is updated by each call to this method,
+    /// typically once per frame before `render`. Its first row is the bottom of the image, so it
+    /// is flipped vertically compared to the textures loaded from image files. An object textured
+    /// with `target` should not be seen by `camera`, since a texture cannot be read while it is
+    /// rendered into.
+    ///
+    /// The camera is resized to the size of the target but is not otherwise updated, so it is
+    /// moved by the application rather than by the events of the window. The scene, the lines
+    /// and points drawn since the last frame, and the shadows are rendered; the planar scene,
+    /// overlays, sub-views, and post-processing effects are not. The lines and points remain
+    /// queued for the next frame. Nothing is rendered if `target` is the screen.
+    pub fn render_to_texture(&mut self, camera: &mut dyn Camera, target: &RenderTarget) {
+        let (tw, th) = match target.size() {
+            Some((tw, th)) => (tw as u32, th as u32),
+            None => return,
+        };
+        let (w, h) = (self.width(), self.height());
+
+        let lines = self.line_renderer.queued();
+        let points = self.point_renderer.queued();
+        // The clear region is given in window coordinates.
+        let clear_region = self.clear_region.take();
+
+        camera.handle_event(&self.canvas, &WindowEvent::FramebufferSize(tw, th));
+        self.render_shadow_map(camera, (tw, th));
+
+        let ctxt = Context::get();
+        self.framebuffer_manager.select(target);
+        verify!(ctxt.viewport(0, 0, tw as i32, th as i32));
+        verify!(ctxt.scissor(0, 0, tw as i32, th as i32));
+
+        for pass in 0usize..camera.num_passes() {
+            camera.start_pass(pass, &self.canvas);
+            self.render_scene(camera, pass);
+        }
+
+        camera.render_complete(&self.canvas);
+        shadow_map::deactivate();
+
+        self.framebuffer_manager
+            .select(&FramebufferManager::screen());
+        verify!(ctxt.viewport(0, 0, w as i32, h as i32));
+        verify!(ctxt.scissor(0, 0, w as i32, h as i32));
+
+        self.clear_region = clear_region;
+        self.line_renderer.requeue(lines);
+        self.point_renderer.requeue(points);
+    }
+
+    /// Renders the current frame again into an offscreen image, as configured by `options`.
+    ///
+    /// The frame is rendered with the cameras of the window, at the resolution of the snapshot,
+    /// which may differ from the size of the window. The post-processing effect, if any, is
+    /// applied to the scene before the overlays are drawn. Sub-views are only drawn if the
+    /// snapshot has the size of the window.
+    ///
+    /// The lines, points, and texts drawn since the last frame remain queued for the next one, so
+    /// this can be called anywhere between two calls to `render`.
+    pub fn snap_with(
+        &mut self,
+        options: &SnapOptions,
+        mut post_processing: Option<&mut dyn PostProcessingEffect>,
+    ) -> DynamicImage {
+        let (w, h) = (self.width(), self.height());
+        let (sw, sh) = options
+            .resolution
+            .map_or((w, h), |(sw, sh)| (sw.max(1), sh.max(1)));
+
+        let lines = self.line_renderer.queued();
+        let points = self.point_renderer.queued();
+        let planar_lines = self.planar_line_renderer.queued();
+        let texts = self.text_renderer.queued();
+
+        let self_cam = self.camera.clone();
+        let mut camera = self_cam.borrow_mut();
+        let self_cam2 = self.planar_camera.clone();
+        let mut planar_camera = self_cam2.borrow_mut();
+        camera.handle_event(&self.canvas, &WindowEvent::FramebufferSize(sw, sh));
+        planar_camera.handle_event(&self.canvas, &WindowEvent::FramebufferSize(sw, sh));
+
+        let ctxt = Context::get();
+        let target = FramebufferManager::new_render_target(sw as usize, sh as usize, true);
+        let post_process_target = post_processing
+            .as_ref()
+            .map(|_| FramebufferManager::new_render_target(sw as usize, sh as usize, true));
+
+        self.framebuffer_manager
+            .select(post_process_target.as_ref().unwrap_or(&target));
+        verify!(ctxt.viewport(0, 0, sw as i32, sh as i32));
+        verify!(ctxt.scissor(0, 0, sw as i32, sh as i32));
+
+        if options.alpha {
+            self.background_alpha = 0.0;
+        }
+
+        for pass in 0usize..camera.num_passes() {
+            camera.start_pass(pass, &self.canvas);
+            self.render_scene(&mut *camera, pass);
+            self.measure_renderer.render(
+                pass,
+                &mut *camera,
+                &self.measurements,
+                Vector2::new(sw as f32, sh as f32),
+                self.canvas.scale_factor() as f32,
+            );
+
+            call_render_hook(&mut self.post_render_hook, &mut *camera, pass);
+        }
+
+        camera.render_complete(&self.canvas);
+        self.background_alpha = 1.0;
+
+        if (sw, sh) == (w, h) {
+            for (_, view) in &mut self.sub_views {
+                view.render(&self.canvas, w, h);
+            }
+
+            verify!(ctxt.viewport(0, 0, sw as i32, sh as i32));
+            verify!(ctxt.scissor(0, 0, sw as i32, sh as i32));
+        }
+
+        self.render_planar_scene(&mut *planar_camera);
+
+        if let (Some(p), Some(post_process_target)) = (&mut post_processing, &post_process_target) {
+            let (znear, zfar) = camera.clip_planes();
+            self.framebuffer_manager.select(&target);
+            p.update(0.0, sw as f32, sh as f32, znear, zfar);
+            p.draw(post_process_target);
+        }
+
+        if options.overlays {
+            self.render_overlays(&mut *camera, sw, sh);
+            self.text_renderer.render(sw as f32, sh as f32);
+            #[cfg(feature = "conrod")]
+            self.conrod_context.renderer.render(
+                sw as f32,
+                sh as f32,
+                self.canvas.scale_factor() as f32 * sw as f32 / w as f32,
+                &self.conrod_context.textures,
+            );
+        }
+
+        let image =
+            snapshot::read_framebuffer_with(0, 0, sw as usize, sh as usize, options).flipv();
+
+        self.framebuffer_manager
+            .select(&FramebufferManager::screen());
+        verify!(ctxt.viewport(0, 0, w as i32, h as i32));
+        verify!(ctxt.scissor(0, 0, w as i32, h as i32));
+        camera.handle_event(&self.canvas, &WindowEvent::FramebufferSize(w, h));
+        planar_camera.handle_event(&self.canvas, &WindowEvent::FramebufferSize(w, h));
+
+        self.line_renderer.requeue(lines);
+        self.point_renderer.requeue(points);
+        self.planar_line_renderer.requeue(planar_lines);
+        self.text_renderer.requeue(texts);
+
+        image
+    }
+
+    /// Saves the current screen to a file with the given pixel format.
+    ///
+    /// The file format is deduced from the extension of `path`, and must support the pixel
+    /// format, e.g., `.png` for `SnapFormat::Rgb16` or `.exr` for `SnapFormat::Rgb32F`.
+    pub fn save_snap<P: AsRef<Path>>(&self, path: P, format: SnapFormat) -> ImageResult<()> {
+        self.snap_image_with_format(format).save(path)
+    }
+
+    /// Writes the color and depth buffers of the screen and of the offscreen render targets used
+    /// by the last frame to PNG images in `dir`, and returns the paths of the images written.
+    ///
+    /// The images are named after the buffers: `screen_color.png`, `screen_depth.png`, and, if
+    /// used, `post_processing_input_*.png` for the scene before post-processing, and
+    /// `scaled_scene_*.png` for the scene before its resampling to the render scale. The depths
+    /// are rescaled to span the whole grayscale range. Depths can not be read with WebGL, so only
+    /// colors are written on this platform.
+    pub fn dump_render_targets<P: AsRef<Path>>(&mut self, dir: P) -> ImageResult<Vec<PathBuf>> {
+        let dir = dir.as_ref();
+        std::fs::create_dir_all(dir).map_err(ImageError::IoError)?;
+
+        let mut written = Vec::new();
+        let mut dump = |framebuffer_manager: &mut FramebufferManager,
+                        target: &RenderTarget,
+                        name: &str,
+                        (width, height): (usize, usize)|
+         -> ImageResult<()> {
+            framebuffer_manager.select(target);
+
+            let color = snapshot::read_framebuffer(0, 0, width, height, SnapFormat::Rgb8);
+            let path = dir.join(format!("{}_color.png", name));
+            color.flipv().save(&path)?;
+            written.push(path);
+
+            if cfg!(not(target_arch = "wasm32")) {
+                let depth = snapshot::read_normalized_depth(width, height);
+                let path = dir.join(format!("{}_depth.png", name));
+                depth.flipv().save(&path)?;
+                written.push(path);
+            }
+
+            Ok(())
+        };
+
+        let size = (self.width() as usize, self.height() as usize);
+        let mut result = dump(
+            &mut self.framebuffer_manager,
+            &FramebufferManager::screen(),
+            "screen",
+            size,
+        );
+
+        if result.is_ok() && self.post_processed {
+            result = dump(
+                &mut self.framebuffer_manager,
+                &self.post_process_render_target,
+                "post_processing_input",
+                size,
+            );
+        }
+
+        if let (Ok(_), Some(target)) = (
+            &result,
+            self.overlay_layer.as_ref().and_then(|l| l.target()),
+        ) {
+            result = dump(&mut self.framebuffer_manager, target, "overlay_layer", size);
+        }
+
+        if let (Ok(_), Some((target, (width, height)))) = (&result, &self.scaled_render_target) {
+            result = dump(
+                &mut self.framebuffer_manager,
+                target,
+                "scaled_scene",
+                (*width as usize, *height as usize),
+            );
+        }
+
+        self.framebuffer_manager
+            .select(&FramebufferManager::screen());
+
+        result.map(|_| written)
+    }
+
     /// Gets the events manager that gives access to an event iterator.
     pub fn events(&self) -> EventManager {
         EventManager::new(self.events.clone(), self.unhandled_events.clone())
@@ -672,6 +2085,18 @@ impl Window {
         self.canvas.get_key(key)
     }
 
+    /// Gets the state of the gamepad.
+    pub fn gamepad(&self) -> &GamepadState {
+        self.canvas.gamepad()
+    }
+
+    /// Sets the state of the gamepad, e.g., from a gamepad library or the browser Gamepad API.
+    ///
+    /// See `Canvas::set_gamepad_state` for details.
+    pub fn set_gamepad_state(&mut self, state: GamepadState) {
+        self.canvas.set_gamepad_state(state)
+    }
+
     /// Gets the last known position of the mouse.
     ///
     /// The position of the mouse is automatically updated when the mouse moves over the canvas.
@@ -679,6 +2104,66 @@ impl Window {
         self.canvas.cursor_pos()
     }
 
+    /// Whether the rendering context is currently lost.
+    ///
+    /// Context losses are reported by WebGL canvases only, e.g., when the browser reclaims the
+    /// GPU of a background tab. The desktop OpenGL contexts are created without robustness, so
+    /// a GPU reset, e.g., after a driver update or a timeout, is not detected and the window is
+    /// not restored: it must be created again. Nothing is rendered until a
+    /// `WindowEvent::ContextRestored` event is received: meshes, textures, shaders, and
+    /// framebuffers managed by kiss3d are then uploaded again automatically. Render targets
+    /// created with `FramebufferManager::new_render_target` must be created again by the user.
+    pub fn is_context_lost(&self) -> bool {
+        self.context_lost
+    }
+
+    // Re-creates the GPU resources after the context has been restored.
+    fn restore_context(&mut self) {
+        // Meshes and shaders are uploaded again lazily.
+        Context::invalidate_resources();
+        // The state cached for the lost context does not match the new one.
+        Context::get().reset_state_cache();
+        init_gl();
+        TextureManager::get_global_manager(|tm| tm.restore());
+
+        self.framebuffer_manager = FramebufferManager::new();
+        self.post_process_render_target = FramebufferManager::new_render_target(
+            self.width() as usize,
+            self.height() as usize,
+            true,
+        );
+        self.text_renderer = TextRenderer::new();
+
+        if self.occlusion_culler.is_some() {
+            self.occlusion_culler = OcclusionCuller::new();
+        }
+
+        if self.depth_prepass.is_some() {
+            self.depth_prepass = Some(DepthPrepass::new());
+        }
+
+        if self.shadow_map.is_some() {
+            self.shadow_map = Some(ShadowMap::new());
+        }
+
+        if self.overlay_layer.is_some() {
+            self.overlay_layer = Some(OverlayLayer::new());
+        }
+
+        if self.gpu_profiler.is_some() {
+            self.gpu_profiler = GpuProfiler::new();
+        }
+
+        // The scaled render target and its resampler are created again by the next frame.
+        self.scaled_render_target = None;
+        self.resampler = None;
+
+        #[cfg(feature = "conrod")]
+        self.conrod_context.renderer.restore();
+
+        self.context_lost = false;
+    }
+
     #[inline]
     fn handle_events(
         &mut self,
@@ -713,6 +2198,12 @@ impl Window {
             WindowEvent::FramebufferSize(w, h) => {
                 self.update_viewport(w as f32, h as f32);
             }
+            WindowEvent::ContextLost => {
+                self.context_lost = true;
+            }
+            WindowEvent::ContextRestored => {
+                self.restore_context();
+            }
             _ => {}
         }
 
@@ -930,6 +2421,18 @@ impl Window {
             }
         }
 
+        if let Some(ref mut console) = self.console {
+            if console.handle_event(event) {
+                return;
+            }
+        }
+
+        if let WindowEvent::MouseButton(MouseButton::Button1, Action::Press, _) = *event {
+            if self.handle_axis_gizmo_click(camera) {
+                return;
+            }
+        }
+
         match *planar_camera {
             Some(ref mut cam) => cam.handle_event(&self.canvas, event),
             None => self.camera.borrow_mut().handle_event(&self.canvas, event),
@@ -941,6 +2444,36 @@ impl Window {
         }
     }
 
+    // Snaps the camera to the axis of the gizmo under the cursor, if any.
+    fn handle_axis_gizmo_click(&mut self, camera: &mut Option<&mut dyn Camera>) -> bool {
+        let gizmo = match self.axis_gizmo {
+            Some(ref gizmo) if gizmo.clickable => gizmo,
+            _ => return false,
+        };
+        let cursor = match self.canvas.cursor_pos() {
+            Some((x, y)) => Point2::new(x as f32, y as f32),
+            None => return false,
+        };
+        let (w, h) = self.canvas.size();
+        let size = Vector2::new(w as f32, h as f32);
+        let scale = self.canvas.scale_factor() as f32;
+
+        let self_cam = self.camera.clone(); // FIXME: this is ugly.
+        let mut bself_cam = self_cam.borrow_mut();
+        let camera: &mut dyn Camera = match *camera {
+            Some(ref mut cam) => &mut **cam,
+            None => &mut *bself_cam,
+        };
+
+        match gizmo.axis_at(camera, &cursor, &size, scale) {
+            Some(axis) => {
+                camera.look_along(&-axis);
+                true
+            }
+            None => false,
+        }
+    }
+
     /// Runs the render and event loop until the window is closed.
     pub fn render_loop<S: State>(self, state: S) {
         Canvas::render_loop(RenderLoopClosureImpl {
@@ -1053,8 +2586,13 @@ impl Window {
     ) -> bool {
         let mut camera = camera;
         let mut planar_camera = planar_camera;
+        self.scene_commands.apply(&mut self.scene);
         self.handle_events(&mut camera, &mut planar_camera);
 
+        if self.context_lost {
+            return !self.should_close;
+        }
+
         let self_cam2 = self.planar_camera.clone(); // FIXME: this is ugly.
         let mut bself_cam2 = self_cam2.borrow_mut();
 
@@ -1091,16 +2629,85 @@ impl Window {
         let w = self.width();
         let h = self.height();
 
+        scene::explode::advance();
+        scene::behavior::run();
+        scene::interpolation::apply(self.interpolation_alpha);
+        scene::trail::record();
+
         planar_camera.handle_event(&self.canvas, &WindowEvent::FramebufferSize(w, h));
         camera.handle_event(&self.canvas, &WindowEvent::FramebufferSize(w, h));
         planar_camera.update(&self.canvas);
         camera.update(&self.canvas);
 
+        if let Some(threshold) = self.floating_origin {
+            let eye = camera.eye();
+
+            if eye.coords.norm() > threshold {
+                let offset = eye.coords.cast::<f64>();
+                camera.shift_origin(&offset);
+
+                if camera.eye() != eye {
+                    scene::set_world_origin(scene::world_origin() + offset);
+                }
+            }
+        }
+
         if let Light::StickToCamera = self.light_mode {
             self.set_light(Light::StickToCamera)
         }
 
-        if post_processing.is_some() {
+        light::set_light_sources(self.lights.iter().map(|l| &l.1));
+
+        if self.light_debug {
+            self.draw_light_markers(camera);
+        }
+
+        #[cfg(not(target_arch = "wasm32"))]
+        let _ = TextureManager::get_global_manager(|tm| tm.update_streaming());
+
+        // The size of the image the scene is rendered into.
+        let (sw, sh) = if self.render_scale != 1.0 {
+            let size = (
+                ((w as f32 * self.render_scale).round() as u32).max(1),
+                ((h as f32 * self.render_scale).round() as u32).max(1),
+            );
+
+            match self.scaled_render_target {
+                Some((ref mut target, ref mut curr)) if *curr != size => {
+                    target.resize(size.0 as f32, size.1 as f32);
+                    *curr = size;
+                }
+                Some(_) => {}
+                None => {
+                    let target = FramebufferManager::new_render_target(
+                        size.0 as usize,
+                        size.1 as usize,
+                        false,
+                    );
+                    self.scaled_render_target = Some((target, size));
+                }
+            }
+
+            size
+        } else {
+            (w, h)
+        };
+
+        if self.shadow_map.is_some() {
+            self.begin_gpu_pass("shadow map");
+            self.render_shadow_map(camera, (sw, sh));
+
+            let ctxt = Context::get();
+            verify!(ctxt.viewport(0, 0, w as i32, h as i32));
+            verify!(ctxt.scissor(0, 0, w as i32, h as i32));
+        }
+
+        if let Some((ref target, _)) = self.scaled_render_target {
+            let ctxt = Context::get();
+            self.framebuffer_manager.select(target);
+            verify!(ctxt.viewport(0, 0, sw as i32, sh as i32));
+            verify!(ctxt.scissor(0, 0, sw as i32, sh as i32));
+        } else if post_processing.is_some() {
             // if we need post-processing, render to our own frame buffer
             self.framebuffer_manager
                 .select(&self.post_process_render_target);
@@ -1109,18 +2716,69 @@ impl Window {
                 .select(&FramebufferManager::screen());
         }
 
+        self.begin_gpu_pass("scene");
+
         for pass in 0usize..camera.num_passes() {
             camera.start_pass(pass, &self.canvas);
             self.render_scene(camera, pass);
+            self.measure_renderer.render(
+                pass,
+                camera,
+                &self.measurements,
+                Vector2::new(sw as f32, sh as f32),
+                self.canvas.scale_factor() as f32 * self.render_scale,
+            );
 
             if let Some(ref mut renderer) = renderer {
                 renderer.render(pass, camera)
             }
+
+            call_render_hook(&mut self.post_render_hook, camera, pass);
         }
 
         camera.render_complete(&self.canvas);
 
-        self.render_planar_scene(planar_camera);
+        if self.scaled_render_target.is_some() {
+            self.begin_gpu_pass("resampling");
+        }
+
+        if let Some((ref target, size)) = self.scaled_render_target {
+            if post_processing.is_some() {
+                self.framebuffer_manager
+                    .select(&self.post_process_render_target);
+            } else {
+                self.framebuffer_manager
+                    .select(&FramebufferManager::screen());
+            }
+
+            let ctxt = Context::get();
+            verify!(ctxt.viewport(0, 0, w as i32, h as i32));
+            verify!(ctxt.scissor(0, 0, w as i32, h as i32));
+            self.resampler
+                .get_or_insert_with(Resampler::new)
+                .draw(target, size, self.render_scale);
+        }
+
+        self.post_processed = post_processing.is_some();
+
+        if !self.sub_views.is_empty() {
+            self.begin_gpu_pass("sub-views");
+
+            for (_, view) in &mut self.sub_views {
+                view.render(&self.canvas, w, h);
+            }
+
+            let ctxt = Context::get();
+            verify!(ctxt.viewport(0, 0, w as i32, h as i32));
+            verify!(ctxt.scissor(0, 0, w as i32, h as i32));
+        }
+
+        shadow_map::deactivate();
+
+        if self.overlay_layer.is_none() {
+            self.begin_gpu_pass("planar scene");
+            self.render_planar_scene(planar_camera);
+        }
 
         let (znear, zfar) = camera.clip_planes();
 
@@ -1131,6 +2789,7 @@ impl Window {
         // }
 
         if let Some(ref mut p) = post_processing {
+            self.begin_gpu_pass("post-processing");
             // switch back to the screen framebuffer …
             self.framebuffer_manager
                 .select(&FramebufferManager::screen());
@@ -1140,14 +2799,29 @@ impl Window {
             p.draw(&self.post_process_render_target);
         }
 
-        self.text_renderer.render(w as f32, h as f32);
+        if self.overlay_layer.is_some() {
+            self.begin_gpu_pass("overlay layer");
+            self.render_overlay_layer(camera, planar_camera, w, h);
+        } else {
+            self.begin_gpu_pass("overlays");
+            self.render_overlays(camera, w, h);
+            self.begin_gpu_pass("text");
+            self.text_renderer.render(w as f32, h as f32);
+        }
         #[cfg(feature = "conrod")]
-        self.conrod_context.renderer.render(
-            w as f32,
-            h as f32,
-            self.canvas.scale_factor() as f32,
-            &self.conrod_context.textures,
-        );
+        {
+            self.begin_gpu_pass("ui");
+            self.conrod_context.renderer.render(
+                w as f32,
+                h as f32,
+                self.canvas.scale_factor() as f32,
+                &self.conrod_context.textures,
+            );
+        }
+
+        if let Some(ref mut profiler) = self.gpu_profiler {
+            profiler.end_frame();
+        }
 
         // We are done: swap buffers
         self.canvas.swap_buffers();
@@ -1171,18 +2845,114 @@ impl Window {
         !self.should_close()
     }
 
+    // Renders the shadow map of the scene seen by `camera` in an image of the given size, if
+    // shadow mapping is enabled. The viewport and scissor box must be set again afterward.
+    fn render_shadow_map(&mut self, camera: &dyn Camera, size: (u32, u32)) {
+        if let Some(ref mut shadow_map) = self.shadow_map {
+            let light_mode = &self.light_mode;
+            let light = self
+                .scene
+                .data()
+                .find_attached_light()
+                .unwrap_or_else(|| light_mode.clone());
+            let pos = match light {
+                Light::Absolute(p) => p,
+                Light::StickToCamera => camera.eye(),
+            };
+
+            shadow_map.render(
+                &self.scene,
+                &pos,
+                camera,
+                &self.shadow_settings,
+                size,
+                &mut self.framebuffer_manager,
+            );
+        }
+    }
+
+    fn begin_gpu_pass(&mut self, name: &'static str) {
+        if let Some(ref mut profiler) = self.gpu_profiler {
+            profiler.begin_pass(name);
+        }
+    }
+
     fn render_scene(&mut self, camera: &mut dyn Camera, pass: usize) {
         let ctxt = Context::get();
         // Activate the default texture
         verify!(ctxt.active_texture(Context::TEXTURE0));
-        // Clear the screen to black
-        verify!(ctxt.clear_color(self.background.x, self.background.y, self.background.z, 1.0));
-        verify!(ctxt.clear(Context::COLOR_BUFFER_BIT));
-        verify!(ctxt.clear(Context::DEPTH_BUFFER_BIT));
+        // Restrict the clear to the clear region, within the rectangle of the current pass.
+        let pass_scissor = ctxt.scissor_box();
+
+        if let Some((x, y, width, height)) = self.clear_region {
+            let [px, py, pw, ph] = pass_scissor;
+            let gl_y = self.height() as i32 - y as i32 - height as i32;
+            let x0 = (x as i32).max(px);
+            let y0 = gl_y.max(py);
+            let x1 = (x as i32 + width as i32).min(px + pw);
+            let y1 = (gl_y + height as i32).min(py + ph);
+            verify!(ctxt.scissor(x0, y0, (x1 - x0).max(0), (y1 - y0).max(0)));
+        }
+
+        // Clear the screen to the background color
+        verify!(ctxt.clear_color(
+            self.background.x,
+            self.background.y,
+            self.background.z,
+            self.background_alpha
+        ));
+
+        let mask = self.clear_flags.gl_mask();
+        clear::set_depth_convention(camera.reversed_depth());
+
+        if mask != 0 {
+            verify!(ctxt.clear(mask));
+        }
+
+        if self.clear_flags.contains(ClearFlags::COLOR) {
+            if let Some(ref mut backdrop) = self.backdrop {
+                backdrop.render_gradient(camera);
+            }
+
+            if let Some(ref mut panorama) = self.background_panorama {
+                panorama.render(camera, &self.background);
+            }
+
+            if let Some(ref mut backdrop) = self.backdrop {
+                backdrop.render_ground(camera);
+            }
+        }
+
+        if self.clear_region.is_some() {
+            let [px, py, pw, ph] = pass_scissor;
+            verify!(ctxt.scissor(px, py, pw, ph));
+        }
+
+        call_render_hook(&mut self.pre_render_hook, camera, pass);
 
         self.line_renderer.render(pass, camera);
         self.point_renderer.render(pass, camera);
-        self.scene.data_mut().render(pass, camera, &self.light_mode);
+        self.scene.data_mut().render_with_occlusion_culler(
+            pass,
+            camera,
+            &self.light_mode,
+            self.occlusion_culler.as_mut(),
+            self.depth_prepass.as_mut(),
+            self.frustum_culling,
+        );
+        self.trail_renderer.render(pass, camera);
+        self.vector_field_renderer.render(pass, camera);
+
+        // Only one light is supported, so a light attached to a node replaces the given one.
+        let light = self
+            .scene
+            .data()
+            .find_attached_light()
+            .unwrap_or_else(|| self.light_mode.clone());
+        self.instanced_mesh_renderer.render(pass, camera, &light);
+
+        // The planar scene and the post-processing effects assume the standard depths.
+        clear::set_depth_convention(false);
     }
 
     fn render_planar_scene(&mut self, camera: &mut dyn PlanarCamera) {
@@ -1199,7 +2969,119 @@ impl Window {
         //     self.point_renderer2.render(camera);
         // }
 
-        self.scene2.data_mut().render(camera);
+        self.scene2.render(camera);
+    }
+
+    fn render_overlays(&mut self, camera: &mut dyn Camera, w: u32, h: u32) {
+        self.queue_overlays(camera, w, h);
+        self.render_overlay_lines(w, h);
+    }
+
+    // Queues the lines and texts of the overlays, without drawing them.
+    fn queue_overlays(&mut self, camera: &mut dyn Camera, w: u32, h: u32) {
+        let size = Vector2::new(w as f32, h as f32);
+        let scale = self.canvas.scale_factor() as f32;
+        let mut overlay = OverlayContext::new(
+            &mut self.overlay_line_renderer,
+            &mut self.text_renderer,
+            size,
+            scale,
+        );
+
+        if let Some(ref gizmo) = self.axis_gizmo {
+            gizmo.draw(camera, &mut overlay);
+        }
+
+        if let Some(ref scale_bar) = self.scale_bar {
+            scale_bar.draw(camera, &mut overlay);
+        }
+
+        self.measurements.draw_labels(camera, &mut overlay);
+        self.annotations.draw(camera, &mut overlay);
+
+        if let Some(ref legend) = self.colormap_legend {
+            legend.draw(&mut overlay);
+        }
+
+        if let Some(ref console) = self.console {
+            console.draw(&mut overlay);
+        }
+
+        if let Some(ref profiler) = self.gpu_profiler {
+            profiler.draw(&mut overlay);
+        }
+    }
+
+    // Draws the planar scene, the overlays, and the texts through the cached overlay layer, which
+    // is rendered again only if they changed since it was last rendered.
+    fn render_overlay_layer(
+        &mut self,
+        camera: &mut dyn Camera,
+        planar_camera: &mut dyn PlanarCamera,
+        w: u32,
+        h: u32,
+    ) {
+        self.queue_overlays(camera, w, h);
+
+        let mut hasher = DefaultHasher::new();
+        hasher.write_u64(scene::planar_scene_node::generation());
+
+        let size = Vector2::new(w as f32, h as f32);
+
+        for pt in [
+            Point2::origin(),
+            Point2::new(1.0, 0.0),
+            Point2::new(0.0, 1.0),
+        ] {
+            let projected = planar_camera.project(&pt, &size);
+            hasher.write_u32(projected.x.to_bits());
+            hasher.write_u32(projected.y.to_bits());
+        }
+
+        self.planar_line_renderer.hash_queued(&mut hasher);
+        self.overlay_line_renderer.hash_queued(&mut hasher);
+        self.text_renderer.hash_queued(&mut hasher);
+
+        let mut layer = match self.overlay_layer.take() {
+            Some(layer) => layer,
+            None => return,
+        };
+
+        if layer.update(hasher.finish(), (w, h)) {
+            let ctxt = Context::get();
+
+            if let Some(target) = layer.target() {
+                self.framebuffer_manager.select(target);
+            }
+
+            verify!(ctxt.clear_color(0.0, 0.0, 0.0, 0.0));
+            verify!(ctxt.clear(Context::COLOR_BUFFER_BIT | Context::DEPTH_BUFFER_BIT));
+            self.render_planar_scene(planar_camera);
+            self.render_overlay_lines(w, h);
+            self.text_renderer.render(w as f32, h as f32);
+            self.framebuffer_manager
+                .select(&FramebufferManager::screen());
+        } else {
+            // The layer already shows the queued lines and texts.
+            self.planar_line_renderer.requeue(Default::default());
+            self.overlay_line_renderer.requeue(Default::default());
+            self.text_renderer.requeue(Default::default());
+        }
+
+        layer.composite();
+        self.overlay_layer = Some(layer);
+    }
+
+    fn render_overlay_lines(&mut self, w: u32, h: u32) {
+        if self.overlay_line_renderer.needs_rendering() {
+            let ctxt = Context::get();
+            self.overlay_camera
+                .handle_event(&self.canvas, &WindowEvent::FramebufferSize(w, h));
+
+            verify!(ctxt.disable(Context::DEPTH_TEST));
+            self.overlay_line_renderer.render(&mut self.overlay_camera);
+            verify!(ctxt.enable(Context::DEPTH_TEST));
+        }
     }
 
     fn update_viewport(&mut self, w: f32, h: f32) {
@@ -1210,6 +3092,17 @@ impl Window {
     }
 }
 
+// Calls a render hook, restoring the GL state it may have modified.
+fn call_render_hook(hook: &mut Option<RenderHook>, camera: &mut dyn Camera, pass: usize) {
+    if let Some(ref mut hook) = *hook {
+        let ctxt = Context::get();
+        let state = ctxt.save_state();
+
+        hook(camera, pass);
+        ctxt.restore_state(&state);
+    }
+}
+
 fn init_gl() {
     /*
      * Misc configurations